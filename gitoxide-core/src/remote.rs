@@ -112,7 +112,7 @@ fn write_refs(
 
     let start = Instant::now();
     let precompose_unicode = gix::fs::Capabilities::probe(&directory).precompose_unicode;
-    let store = gix::RefStore::at(
+    let store = gix::refs::file::Store::at(
         directory,
         gix::refs::store::init::Options {
             write_reflog: if write_reflog {
@@ -123,6 +123,7 @@ fn write_refs(
             object_hash,
             precompose_unicode,
             prohibit_windows_device_names: cfg!(windows),
+            ..Default::default()
         },
     );
     let edits = refs
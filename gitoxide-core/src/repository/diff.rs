@@ -128,8 +128,8 @@ fn resolve_revspec(
             // We extract the error from the tree to learn the name, and treat it as file.
             let not_found = err
                 .sources()
-                .find_map(|err| err.downcast_ref::<gix::refs::file::find::existing::Error>());
-            if let Some(gix::refs::file::find::existing::Error::NotFound { name }) = not_found {
+                .find_map(|err| err.downcast_ref::<gix::refs::store::find::existing::Error>());
+            if let Some(gix::refs::store::find::existing::Error::NotFound { name }) = not_found {
                 let root = repo.workdir().map(ToOwned::to_owned);
                 let name = gix::path::os_string_into_bstring(name.into())?;
 
@@ -12,6 +12,8 @@ pub struct Options {
     pub handshake_info: bool,
     pub negotiation_info: bool,
     pub open_negotiation_graph: Option<std::path::PathBuf>,
+    /// If `true`, append to `FETCH_HEAD` instead of overwriting it.
+    pub append: bool,
 }
 
 pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=3;
@@ -21,7 +23,7 @@ pub(crate) mod function {
     use gix::{
         prelude::ObjectIdExt,
         refspec::match_group::validate::Fix,
-        remote::fetch::{Status, refs::update::TypeChange},
+        remote::fetch::{FetchHeadMerge, Status, WriteFetchHead, refs::update::TypeChange},
     };
     use layout::{
         backends::svg::SVGWriter,
@@ -44,6 +46,7 @@ pub(crate) mod function {
             handshake_info,
             negotiation_info,
             open_negotiation_graph,
+            append,
             shallow,
             ref_specs,
         }: Options,
@@ -61,12 +64,18 @@ pub(crate) mod function {
             remote.replace_refspecs(ref_specs.iter(), gix::remote::Direction::Fetch)?;
             remote = remote.with_fetch_tags(gix::remote::fetch::Tags::None);
         }
-        let res: gix::remote::fetch::Outcome = remote
+        let mut prepare = remote
             .connect(gix::remote::Direction::Fetch)?
             .prepare_fetch(&mut progress, Default::default())?
             .with_dry_run(dry_run)
-            .with_shallow(shallow)
-            .receive(&mut progress, &gix::interrupt::IS_INTERRUPTED)?;
+            .with_shallow(shallow);
+        if !ref_specs.is_empty() {
+            prepare = prepare.with_fetch_head_merge(FetchHeadMerge::AllMatches);
+        }
+        if append {
+            prepare = prepare.with_write_fetch_head(WriteFetchHead::Append);
+        }
+        let res: gix::remote::fetch::Outcome = prepare.receive(&mut progress, &gix::interrupt::IS_INTERRUPTED)?;
 
         if handshake_info {
             writeln!(out, "Handshake Information")?;
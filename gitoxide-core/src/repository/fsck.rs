@@ -23,7 +23,13 @@ pub fn function(mut repo: gix::Repository, spec: Option<String>, mut out: impl s
         writeln!(out, "{oid}: {kind}").expect("failed to write output");
     };
 
-    let mut check = gix_fsck::Connectivity::new(&repo.objects, on_missing);
+    // Objects promised by promisor remotes in partial clones are expected to be missing.
+    let promised = repo
+        .objects
+        .store_ref()
+        .promised_objects(&repo.objects)
+        .context("Could not determine the objects promised by promisor remotes")?;
+    let mut check = gix_fsck::Connectivity::new(&repo.objects, on_missing).with_promised_objects(promised);
     // Walk all commits, checking each one for connectivity
     for commit in commits {
         let commit = commit?;
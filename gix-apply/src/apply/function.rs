@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+
+use bstr::{BStr, BString, ByteSlice};
+use gix_hash::ObjectId;
+use gix_object::tree::EntryKind;
+
+use super::{Error, File, Options, Outcome, Status, Whitespace};
+use crate::{
+    Patch, Target, blob,
+    patch::{self, BinaryHunk, Content, Hunk, Operation},
+};
+
+/// The content and kind of a file, or `None` if it is removed.
+type State = Option<(Vec<u8>, EntryKind)>;
+
+/// Apply all files of `patch` to `target`, using `objects` to find the blobs a patch was created for if
+/// [3-way merges](Options::three_way) or binary patches without data need them, and configured by `options`.
+///
+/// The files in `target` are only changed if all files of the patch could be applied, and not at all if
+/// [`Options::check`] is set.
+/// Files may be changed by more than one file of `patch`, with each change seeing the result of the previous one.
+pub fn apply(
+    patch: &Patch,
+    target: &mut impl Target,
+    objects: &impl gix_object::Find,
+    options: Options,
+) -> Result<Outcome, Error> {
+    let reversed;
+    let patch = if options.reverse {
+        reversed = patch.reversed();
+        &reversed
+    } else {
+        patch
+    };
+    let text_options = blob::text::Options {
+        min_context: options.min_context,
+        ignore_whitespace: options.ignore_whitespace,
+        unidiff_zero: options.unidiff_zero,
+        fix_whitespace: options.whitespace == Whitespace::Fix,
+    };
+
+    let mut changes = BTreeMap::<BString, State>::new();
+    let mut outcome = Outcome::default();
+    let mut current = |changes: &BTreeMap<BString, State>, path: &BStr| -> Result<State, Error> {
+        match changes.get(path) {
+            Some(state) => Ok(state.clone()),
+            None => target.read(path).map_err(Error::Target),
+        }
+    };
+    for file in &patch.files {
+        validate(file)?;
+        let path = file.path().to_owned();
+        let preimage = match file.operation {
+            Operation::Add => None,
+            _ => {
+                let old_path = file.old_path.as_ref().expect("set unless added").as_bstr();
+                Some(current(&changes, old_path)?.ok_or_else(|| Error::Missing { path: old_path.into() })?)
+            }
+        };
+        if matches!(
+            file.operation,
+            Operation::Add | Operation::Rename { .. } | Operation::Copy { .. }
+        ) && current(&changes, path.as_ref())?.is_some()
+        {
+            return Err(Error::AlreadyExists { path });
+        }
+        let (old_data, old_kind) = preimage.unwrap_or((Vec::new(), EntryKind::Blob));
+
+        let mut status = Status::Applied;
+        let data = match &file.content {
+            Content::Text(hunks) => {
+                let mut out = Vec::new();
+                match blob::apply_hunks(&old_data, hunks, &mut out, text_options) {
+                    Ok(hunks_outcome) => outcome.whitespace_errors += hunks_outcome.whitespace_errors,
+                    Err(err) => {
+                        let merged = if options.three_way {
+                            three_way(file, &old_data, hunks, objects, text_options, &mut out)?
+                        } else {
+                            None
+                        };
+                        status = merged.ok_or_else(|| Error::HunkMismatch {
+                            path: path.clone(),
+                            source: err,
+                        })?;
+                    }
+                }
+                out
+            }
+            Content::Binary { forward, .. } => {
+                if !matches_id(file.old_id.as_ref(), &old_data)? {
+                    return Err(Error::BinaryPreimageMismatch { path });
+                }
+                let data = match forward {
+                    BinaryHunk::Literal(data) => data.clone(),
+                    BinaryHunk::Delta(delta) => {
+                        let mut out = Vec::new();
+                        blob::apply_delta(&old_data, delta, &mut out).map_err(|source| Error::Delta {
+                            path: path.clone(),
+                            source,
+                        })?;
+                        out
+                    }
+                };
+                if !matches_id(file.new_id.as_ref(), &data)? {
+                    return Err(Error::BinaryPostimageMismatch { path });
+                }
+                data
+            }
+            Content::BinaryWithoutData => {
+                let mut buf = Vec::new();
+                let blob = full_id(file.new_id.as_ref())
+                    .filter(|_| matches_id(file.old_id.as_ref(), &old_data).unwrap_or(false))
+                    .map(|id| objects.try_find(&id, &mut buf))
+                    .transpose()?
+                    .flatten()
+                    .filter(|data| data.kind == gix_object::Kind::Blob);
+                match blob {
+                    Some(blob) => blob.data.to_vec(),
+                    None => return Err(Error::BinaryWithoutData { path }),
+                }
+            }
+        };
+
+        match file.operation {
+            Operation::Delete => {
+                if !data.is_empty() {
+                    return Err(Error::DeletionLeavesContent { path });
+                }
+                changes.insert(path.clone(), None);
+            }
+            _ => {
+                if let (Operation::Rename { .. }, Some(old_path)) = (file.operation, file.old_path.as_ref()) {
+                    changes.insert(old_path.clone(), None);
+                }
+                let kind = file.new_mode.unwrap_or(old_kind);
+                changes.insert(path.clone(), Some((data, kind)));
+            }
+        }
+        outcome.files.push(File { path, status });
+    }
+
+    if options.whitespace == Whitespace::Error && outcome.whitespace_errors != 0 {
+        return Err(Error::WhitespaceErrors {
+            count: outcome.whitespace_errors,
+        });
+    }
+    if options.check {
+        return Ok(outcome);
+    }
+    for (path, _) in changes.iter().filter(|(_, state)| state.is_none()) {
+        target.remove(path.as_ref()).map_err(Error::Target)?;
+    }
+    for (path, (data, kind)) in changes
+        .iter()
+        .filter_map(|(path, state)| state.as_ref().map(|state| (path, state)))
+    {
+        target.write(path.as_ref(), data, *kind).map_err(Error::Target)?;
+    }
+    Ok(outcome)
+}
+
+/// Assure the paths of `file` can't escape the target or write into the `.git` directory, and that its modes can be applied.
+fn validate(file: &patch::File) -> Result<(), Error> {
+    for path in [&file.old_path, &file.new_path].into_iter().flatten() {
+        for component in path.split_str("/") {
+            gix_validate::path::component(
+                component.as_bstr(),
+                None,
+                gix_validate::path::component::Options {
+                    protect_windows: cfg!(windows),
+                    ..Default::default()
+                },
+            )
+            .map_err(|source| Error::InvalidPath {
+                path: path.clone(),
+                source,
+            })?;
+        }
+    }
+    for kind in [file.old_mode, file.new_mode].into_iter().flatten() {
+        if matches!(kind, EntryKind::Tree | EntryKind::Commit) {
+            return Err(Error::Unsupported {
+                path: file.path().to_owned(),
+                kind,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Apply `hunks` to the blob `file` was created for and merge the result into `ours`, writing it into `out`.
+/// Return `None` if the blob isn't known or the hunks don't apply to it either.
+fn three_way(
+    file: &patch::File,
+    ours: &[u8],
+    hunks: &[Hunk],
+    objects: &impl gix_object::Find,
+    options: blob::text::Options,
+    out: &mut Vec<u8>,
+) -> Result<Option<Status>, Error> {
+    let Some(base_id) = full_id(file.old_id.as_ref()) else {
+        return Ok(None);
+    };
+    let mut buf = Vec::new();
+    let Some(base) = objects
+        .try_find(&base_id, &mut buf)?
+        .filter(|data| data.kind == gix_object::Kind::Blob)
+    else {
+        return Ok(None);
+    };
+    let mut theirs = Vec::new();
+    if blob::apply_hunks(base.data, hunks, &mut theirs, options).is_err() {
+        return Ok(None);
+    }
+
+    let mut input = imara_diff::InternedInput::default();
+    let resolution = gix_merge::blob::builtin_driver::text(
+        out,
+        &mut input,
+        gix_merge::blob::builtin_driver::text::Labels {
+            ancestor: None,
+            current: Some("ours".into()),
+            other: Some("theirs".into()),
+        },
+        ours,
+        base.data,
+        &theirs,
+        Default::default(),
+    );
+    Ok(Some(match resolution {
+        gix_merge::blob::Resolution::Conflict => Status::Conflict,
+        gix_merge::blob::Resolution::Complete | gix_merge::blob::Resolution::CompleteWithAutoResolvedConflict => {
+            Status::Merged
+        }
+    }))
+}
+
+/// Parse `id` as full object id, or return `None` if it's abbreviated or missing.
+fn full_id(id: Option<&BString>) -> Option<ObjectId> {
+    ObjectId::from_hex(id?).ok()
+}
+
+/// Return `true` if `data` hashes to `id`, or if `id` isn't a full object id and can't be checked.
+fn matches_id(id: Option<&BString>, data: &[u8]) -> Result<bool, Error> {
+    let Some(id) = full_id(id) else {
+        return Ok(true);
+    };
+    Ok(gix_object::compute_hash(id.kind(), gix_object::Kind::Blob, data)? == id)
+}
@@ -0,0 +1,123 @@
+use bstr::BString;
+use gix_object::tree::EntryKind;
+
+pub(crate) mod function;
+
+/// The error returned by [`apply()`](crate::apply()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The path '{path}' in the patch is invalid")]
+    InvalidPath {
+        path: BString,
+        source: gix_validate::path::component::Error,
+    },
+    #[error("'{path}' has mode {kind:?}, which can't be patched")]
+    Unsupported { path: BString, kind: EntryKind },
+    #[error("'{path}' does not exist")]
+    Missing { path: BString },
+    #[error("'{path}' already exists")]
+    AlreadyExists { path: BString },
+    #[error("The patch does not apply to '{path}'")]
+    HunkMismatch {
+        path: BString,
+        source: crate::blob::text::Error,
+    },
+    #[error("The removal patch leaves content in '{path}'")]
+    DeletionLeavesContent { path: BString },
+    #[error("'{path}' doesn't match the blob the binary patch was created for")]
+    BinaryPreimageMismatch { path: BString },
+    #[error("The binary patch applied to '{path}' doesn't produce the expected blob")]
+    BinaryPostimageMismatch { path: BString },
+    #[error("The binary patch for '{path}' has no data, and its new blob isn't available")]
+    BinaryWithoutData { path: BString },
+    #[error("Could not apply the binary delta to '{path}'")]
+    Delta {
+        path: BString,
+        source: crate::blob::delta::Error,
+    },
+    #[error("{count} added lines have whitespace errors")]
+    WhitespaceErrors { count: usize },
+    #[error("Could not read or change files")]
+    Target(#[source] crate::target::Error),
+    #[error(transparent)]
+    FindObject(#[from] gix_object::find::Error),
+    #[error(transparent)]
+    Hash(#[from] gix_hash::hasher::Error),
+}
+
+/// What to do with whitespace errors in added lines, like `git apply --whitespace=<action>`.
+///
+/// Whitespace errors are always [counted](Outcome::whitespace_errors).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Whitespace {
+    /// Apply the lines as they are.
+    #[default]
+    Warn,
+    /// Remove trailing whitespace from added lines.
+    Fix,
+    /// Don't apply the patch if it has whitespace errors.
+    Error,
+}
+
+/// Options for use in [`apply()`](crate::apply()).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// If `true`, apply the patch in reverse, like `git apply --reverse`.
+    pub reverse: bool,
+    /// If `true`, only check if the patch applies, but don't change the target, like `git apply --check`.
+    pub check: bool,
+    /// If `true` and the hunks of a file don't apply, apply them to the blob the patch was created for instead and merge
+    /// the result into the file, like `git apply --3way`.
+    ///
+    /// This requires the full id of the blob in the patch, as produced by `git diff --full-index`, and the blob to be
+    /// available in the object database.
+    /// Conflicts are written into the file using conflict markers.
+    pub three_way: bool,
+    /// If `Some(n)`, allow to ignore context lines if a hunk doesn't apply as is, but require that at least `n` of them
+    /// match before and after the change, like `-C<n>` in `git apply`.
+    pub min_context: Option<usize>,
+    /// If `true`, context and removed lines match even if their whitespace differs, like `git apply --ignore-whitespace`.
+    pub ignore_whitespace: bool,
+    /// If `true`, hunks without context don't have to apply at the beginning or end of the file, like
+    /// `git apply --unidiff-zero`.
+    pub unidiff_zero: bool,
+    /// What to do with whitespace errors.
+    pub whitespace: Whitespace,
+}
+
+/// The way the changes to a file were applied.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// All hunks applied.
+    Applied,
+    /// The hunks didn't apply, but were merged into the file with a 3-way merge.
+    Merged,
+    /// The hunks didn't apply, and merging them into the file led to conflicts, which are marked in the file.
+    Conflict,
+}
+
+/// A file changed by [`apply()`](crate::apply()).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct File {
+    /// The path of the file after the change, or before it if it was deleted.
+    pub path: BString,
+    /// How the changes were applied.
+    pub status: Status,
+}
+
+/// The outcome of [`apply()`](crate::apply()).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// All changed files in the order of the patch.
+    pub files: Vec<File>,
+    /// The amount of added lines with trailing whitespace, which were fixed if [`Whitespace::Fix`] was set.
+    pub whitespace_errors: usize,
+}
+
+impl Outcome {
+    /// Return `true` if 3-way merges led to conflicts in any file.
+    pub fn has_conflicts(&self) -> bool {
+        self.files.iter().any(|file| file.status == Status::Conflict)
+    }
+}
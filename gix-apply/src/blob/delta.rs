@@ -0,0 +1,99 @@
+/// The error returned by [`apply_delta()`](crate::blob::apply_delta()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Corrupt delta data: {message}")]
+    Corrupt { message: &'static str },
+    #[error("The delta expects a base of {expected} bytes, but it has {actual} bytes")]
+    BaseSizeMismatch { expected: u64, actual: u64 },
+    #[error("The delta produced {actual} bytes, but should have produced {expected} bytes")]
+    ResultSizeMismatch { expected: u64, actual: u64 },
+}
+
+pub(super) mod function {
+    use super::Error;
+
+    /// Apply `delta` in the format used in packs, which is what binary patches use as well, to `base`
+    /// and write the result into `out`, which is cleared first.
+    pub fn apply(base: &[u8], delta: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+        let (base_size, consumed) = decode_size(delta)?;
+        let delta = &delta[consumed..];
+        let (result_size, consumed) = decode_size(delta)?;
+        let mut delta = &delta[consumed..];
+        if base_size != base.len() as u64 {
+            return Err(Error::BaseSizeMismatch {
+                expected: base_size,
+                actual: base.len() as u64,
+            });
+        }
+
+        out.clear();
+        while let Some((&cmd, rest)) = delta.split_first() {
+            delta = rest;
+            if cmd & 0x80 != 0 {
+                let mut next_byte = |bit: u8| -> Result<u32, Error> {
+                    if cmd & bit == 0 {
+                        return Ok(0);
+                    }
+                    let (&byte, rest) = delta.split_first().ok_or(Error::Corrupt {
+                        message: "copy instruction is truncated",
+                    })?;
+                    delta = rest;
+                    Ok(u32::from(byte))
+                };
+                let ofs = next_byte(0x01)? | next_byte(0x02)? << 8 | next_byte(0x04)? << 16 | next_byte(0x08)? << 24;
+                let mut size = next_byte(0x10)? | next_byte(0x20)? << 8 | next_byte(0x40)? << 16;
+                if size == 0 {
+                    size = 0x10000;
+                }
+                let (ofs, size) = (ofs as usize, size as usize);
+                let data = ofs
+                    .checked_add(size)
+                    .and_then(|end| base.get(ofs..end))
+                    .ok_or(Error::Corrupt {
+                        message: "copy instruction is out of bounds of the base",
+                    })?;
+                out.extend_from_slice(data);
+            } else if cmd != 0 {
+                let size = usize::from(cmd);
+                let data = delta.get(..size).ok_or(Error::Corrupt {
+                    message: "insert instruction is truncated",
+                })?;
+                out.extend_from_slice(data);
+                delta = &delta[size..];
+            } else {
+                return Err(Error::Corrupt {
+                    message: "encountered unsupported command code 0",
+                });
+            }
+        }
+
+        if result_size != out.len() as u64 {
+            return Err(Error::ResultSizeMismatch {
+                expected: result_size,
+                actual: out.len() as u64,
+            });
+        }
+        Ok(())
+    }
+
+    /// Decode a size from the header of `delta` and return it along with the amount of bytes it occupied.
+    fn decode_size(delta: &[u8]) -> Result<(u64, usize), Error> {
+        let mut size = 0u64;
+        for (idx, byte) in delta.iter().enumerate() {
+            let shift = idx * 7;
+            if shift >= u64::BITS as usize {
+                return Err(Error::Corrupt {
+                    message: "header size uses more bits than fit into u64",
+                });
+            }
+            size |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok((size, idx + 1));
+            }
+        }
+        Err(Error::Corrupt {
+            message: "header size is truncated",
+        })
+    }
+}
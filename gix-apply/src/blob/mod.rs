@@ -0,0 +1,7 @@
+///
+pub mod text;
+pub use text::function::apply as apply_hunks;
+
+///
+pub mod delta;
+pub use delta::function::apply as apply_delta;
@@ -0,0 +1,174 @@
+/// The error returned by [`apply_hunks()`](crate::blob::apply_hunks()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Hunk #{hunk} does not apply")]
+    HunkMismatch { hunk: usize },
+}
+
+/// Options for use in [`apply_hunks()`](crate::blob::apply_hunks()).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// If `Some(n)`, allow to ignore context lines if a hunk doesn't apply as is, but require that at least `n` of them
+    /// match before and after the change, like `-C<n>` in `git apply`.
+    ///
+    /// If `None`, all context lines must match.
+    pub min_context: Option<usize>,
+    /// If `true`, context and removed lines match even if their whitespace differs, like `git apply --ignore-whitespace`.
+    pub ignore_whitespace: bool,
+    /// If `true`, hunks without context, like those produced by `diff -U0`, don't have to apply at the beginning or end
+    /// of the file, like `git apply --unidiff-zero`.
+    pub unidiff_zero: bool,
+    /// If `true`, trailing whitespace of added lines is removed.
+    pub fix_whitespace: bool,
+}
+
+/// The outcome of [`apply_hunks()`](crate::blob::apply_hunks()).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of added lines with trailing whitespace, which were fixed if [`Options::fix_whitespace`] was set.
+    pub whitespace_errors: usize,
+}
+
+pub(super) mod function {
+    use std::borrow::Cow;
+
+    use super::{Error, Options, Outcome};
+    use crate::patch::{Hunk, Line};
+
+    /// Apply `hunks` to the lines of `data` and write the result into `out`, which is cleared first, using `options` to
+    /// decide how to find the position of each hunk.
+    ///
+    /// Each hunk is searched for around the line it's supposed to apply to, and it applies at the position closest to
+    /// that line, just like it's the case with `git apply`.
+    pub fn apply(data: &[u8], hunks: &[Hunk], out: &mut Vec<u8>, options: Options) -> Result<Outcome, Error> {
+        let mut image: Vec<Cow<'_, [u8]>> = data.split_inclusive(|b| *b == b'\n').map(Cow::Borrowed).collect();
+        let mut outcome = Outcome::default();
+        for (idx, hunk) in hunks.iter().enumerate() {
+            let mut lines = hunk.lines.as_slice();
+            let mut leading = hunk.leading_context();
+            let has_changes = leading != lines.len();
+            let mut trailing = if has_changes { hunk.trailing_context() } else { 0 };
+            let mut match_beginning = hunk.old_start == 0 || (hunk.old_start == 1 && !options.unidiff_zero);
+            let mut match_end = !options.unidiff_zero && has_changes && trailing == 0;
+            let mut expected = hunk.new_start.saturating_sub(1) as usize;
+            let min_context = options.min_context.unwrap_or(usize::MAX);
+
+            let pos = loop {
+                let preimage: Vec<&[u8]> = lines
+                    .iter()
+                    .filter_map(|line| match line {
+                        Line::Context(line) | Line::Remove(line) => Some(line.as_slice()),
+                        Line::Add(_) => None,
+                    })
+                    .collect();
+                if let Some(pos) = find_pos(&image, &preimage, expected, match_beginning, match_end, options) {
+                    break pos;
+                }
+                if leading <= min_context && trailing <= min_context {
+                    return Err(Error::HunkMismatch { hunk: idx + 1 });
+                }
+                if match_beginning || match_end {
+                    match_beginning = false;
+                    match_end = false;
+                    continue;
+                }
+                if leading >= trailing {
+                    lines = &lines[1..];
+                    leading -= 1;
+                    expected += 1;
+                }
+                if trailing > leading {
+                    lines = &lines[..lines.len() - 1];
+                    trailing -= 1;
+                }
+            };
+
+            let mut postimage = Vec::with_capacity(lines.len());
+            let mut image_pos = pos;
+            for line in lines {
+                match line {
+                    Line::Context(_) => {
+                        postimage.push(image[image_pos].clone());
+                        image_pos += 1;
+                    }
+                    Line::Remove(_) => image_pos += 1,
+                    Line::Add(line) => {
+                        let trimmed = trim_trailing_whitespace(line);
+                        if trimmed.len() != line.len() {
+                            outcome.whitespace_errors += 1;
+                        }
+                        postimage.push(if options.fix_whitespace && trimmed.len() != line.len() {
+                            Cow::Owned(trimmed)
+                        } else {
+                            Cow::Borrowed(line.as_slice())
+                        });
+                    }
+                }
+            }
+            image.splice(pos..image_pos, postimage);
+        }
+
+        out.clear();
+        for line in image {
+            out.extend_from_slice(&line);
+        }
+        Ok(outcome)
+    }
+
+    /// Find the position in `image` at which `preimage` matches, starting at `expected` and searching outward.
+    fn find_pos(
+        image: &[Cow<'_, [u8]>],
+        preimage: &[&[u8]],
+        expected: usize,
+        match_beginning: bool,
+        match_end: bool,
+        options: Options,
+    ) -> Option<usize> {
+        let last = image.len().checked_sub(preimage.len())?;
+        let matches_at = |pos: usize| {
+            image[pos..][..preimage.len()]
+                .iter()
+                .zip(preimage)
+                .all(|(actual, expected)| lines_match(actual, expected, options.ignore_whitespace))
+        };
+        if match_end {
+            return (!match_beginning || last == 0)
+                .then_some(last)
+                .filter(|pos| matches_at(*pos));
+        }
+        if match_beginning {
+            return matches_at(0).then_some(0);
+        }
+
+        let expected = expected.min(last);
+        (0..=last).find_map(|distance| {
+            let before = expected.checked_sub(distance).filter(|pos| matches_at(*pos));
+            let after = expected
+                .checked_add(distance)
+                .filter(|pos| distance != 0 && *pos <= last && matches_at(*pos));
+            before.or(after)
+        })
+    }
+
+    fn lines_match(actual: &[u8], expected: &[u8], ignore_whitespace: bool) -> bool {
+        fn words(line: &[u8]) -> impl Iterator<Item = &[u8]> {
+            line.split(u8::is_ascii_whitespace).filter(|word| !word.is_empty())
+        }
+        if ignore_whitespace {
+            words(actual).eq(words(expected))
+        } else {
+            actual == expected
+        }
+    }
+
+    /// Return `line` without spaces and tabs before its line ending.
+    fn trim_trailing_whitespace(line: &[u8]) -> Vec<u8> {
+        let content_end = line.len() - line.iter().rev().take_while(|b| matches!(b, b'\n' | b'\r')).count();
+        let (content, line_ending) = line.split_at(content_end);
+        let trimmed_end = content.len() - content.iter().rev().take_while(|b| matches!(b, b' ' | b'\t')).count();
+        let mut out = content[..trimmed_end].to_vec();
+        out.extend_from_slice(line_ending);
+        out
+    }
+}
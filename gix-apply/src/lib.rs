@@ -0,0 +1,40 @@
+//! Parse patches in the unified diff format and apply them, akin to `git apply`.
+//!
+//! The workflow is as follows:
+//!
+//! * [Parse](Patch::from_bytes()) a patch as produced by `git diff`, `git format-patch` or the traditional `diff -u`,
+//!   which understands the extended headers of `git` for renames, copies and mode changes as well as binary patches.
+//! * [`apply()`] all files of the patch to a [`Target`], which is either a [tree](target::Tree) in the object database,
+//!   the [index](target::Index) or the [worktree](target::Worktree).
+//!
+//! Hunks are placed using the same algorithm as `git apply`, so they are found even if the lines they change moved,
+//! and the context they need to match can be [reduced](apply::Options::min_context) to allow for some fuzz.
+//! If a patch doesn't apply, it may still be merged into the file with a [3-way merge](apply::Options::three_way)
+//! if the blob it was created from is available.
+//!
+//! Nothing is changed unless all files of the patch could be applied.
+//!
+//! ### Deviation
+//!
+//! * When applying to the index, `git apply --3way` records conflicts as unmerged entries, but here the file with the
+//!   conflict markers is written as is. Applying to both the index and the worktree, like `git apply --index`, is done by
+//!   applying the patch to each [`Target`] in turn.
+//! * Of the whitespace errors, only trailing whitespace in added lines is detected and fixed.
+//! * Line endings aren't converted, and neither filters nor attributes are applied when reading or writing files.
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+
+///
+pub mod patch;
+pub use patch::Patch;
+
+///
+pub mod blob;
+
+///
+pub mod target;
+pub use target::Target;
+
+///
+pub mod apply;
+pub use apply::function::apply;
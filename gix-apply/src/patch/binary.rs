@@ -0,0 +1,44 @@
+//! Decoding of the data in `GIT binary patch` sections, which is zlib-compressed and encoded with base85.
+
+const ALPHABET: &[u8; 85] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+fn digit(byte: u8) -> Option<u32> {
+    ALPHABET.iter().position(|b| *b == byte).map(|pos| pos as u32)
+}
+
+/// Decode a single data `line` without its line ending, whose first character encodes the amount of bytes it holds,
+/// and append them to `out`. Return `None` if the line is malformed.
+pub(super) fn decode_line(line: &[u8], out: &mut Vec<u8>) -> Option<()> {
+    let (len, encoded) = line.split_first()?;
+    let len = match len {
+        b'A'..=b'Z' => len - b'A' + 1,
+        b'a'..=b'z' => len - b'a' + 27,
+        _ => return None,
+    } as usize;
+    if encoded.len() != len.div_ceil(4) * 5 {
+        return None;
+    }
+    let start = out.len();
+    for group in encoded.chunks(5) {
+        let mut acc = 0u32;
+        for byte in group {
+            acc = acc.checked_mul(85)?.checked_add(digit(*byte)?)?;
+        }
+        out.extend_from_slice(&acc.to_be_bytes());
+    }
+    out.truncate(start + len);
+    Some(())
+}
+
+/// Decompress `data` which must inflate to exactly `size` bytes.
+pub(super) fn inflate(data: &[u8], size: usize) -> Result<Vec<u8>, gix_features::zlib::inflate::Error> {
+    // One more byte than needed, as zlib may not be able to finish the stream into a buffer without space left.
+    let mut out = vec![0; size + 1];
+    let mut inflate = gix_features::zlib::Inflate::default();
+    let (status, consumed, written) = inflate.once(data, &mut out)?;
+    if status != gix_features::zlib::Status::StreamEnd || consumed != data.len() || written != size {
+        return Err(gix_features::zlib::inflate::Error::Status(status));
+    }
+    out.truncate(size);
+    Ok(out)
+}
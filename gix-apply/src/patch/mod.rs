@@ -0,0 +1,212 @@
+use bstr::{BStr, BString, ByteSlice};
+use gix_object::tree::EntryKind;
+
+mod binary;
+
+///
+pub mod parse;
+
+/// A patch with changes to any amount of files, as parsed from the output of `git diff` or `diff -u`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    /// The changes to each file, in the order they appear in the patch.
+    pub files: Vec<File>,
+}
+
+/// The changes of a single file in a [`Patch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct File {
+    /// The path of the file before the change, or `None` if it is [added](Operation::Add).
+    pub old_path: Option<BString>,
+    /// The path of the file after the change, or `None` if it is [deleted](Operation::Delete).
+    pub new_path: Option<BString>,
+    /// The kind of the file before the change, if it was mentioned in the patch.
+    pub old_mode: Option<EntryKind>,
+    /// The kind of the file after the change, if it was mentioned in the patch.
+    pub new_mode: Option<EntryKind>,
+    /// The possibly abbreviated hexadecimal id of the blob before the change, as seen in the `index` header line.
+    pub old_id: Option<BString>,
+    /// The possibly abbreviated hexadecimal id of the blob after the change, as seen in the `index` header line.
+    pub new_id: Option<BString>,
+    /// What happens to the file.
+    pub operation: Operation,
+    /// How the content of the file changes.
+    pub content: Content,
+}
+
+/// What happens to a [`File`] in a patch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// The file is modified in place, which may also be just a change of its mode.
+    Modify,
+    /// The file is created.
+    Add,
+    /// The file is deleted.
+    Delete,
+    /// The file is moved from its old path to its new path, and possibly modified.
+    Rename {
+        /// The similarity of both files in percent, if known.
+        similarity: Option<u8>,
+    },
+    /// The file at the old path is copied to the new path, and possibly modified.
+    Copy {
+        /// The similarity of both files in percent, if known.
+        similarity: Option<u8>,
+    },
+}
+
+/// The way the content of a [`File`] changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Content {
+    /// The content changes as described by the hunks, which may be empty if only the path or mode changes.
+    Text(Vec<Hunk>),
+    /// The content is binary and changes as described by the `forward` hunk.
+    Binary {
+        /// The hunk turning the old content into the new one.
+        forward: BinaryHunk,
+        /// The hunk turning the new content back into the old one, which `git` always provides.
+        reverse: Option<BinaryHunk>,
+    },
+    /// The content is binary, but the patch was created without `--binary` and only says that the files differ.
+    ///
+    /// It can only be applied if the new blob is available in the object database.
+    BinaryWithoutData,
+}
+
+/// A hunk of a textual change, starting with `@@ -old_start,old_len +new_start,new_len @@`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The one-based line number at which the hunk starts in the old file, or `0` if `old_len` is `0`.
+    pub old_start: u32,
+    /// The amount of lines the hunk spans in the old file.
+    pub old_len: u32,
+    /// The one-based line number at which the hunk starts in the new file, or `0` if `new_len` is `0`.
+    pub new_start: u32,
+    /// The amount of lines the hunk spans in the new file.
+    pub new_len: u32,
+    /// The lines of the hunk, each with its line ending, unless it's the last line of a file without newline at the end.
+    pub lines: Vec<Line>,
+}
+
+/// A line in a [`Hunk`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    /// A line that is present before and after the change.
+    Context(BString),
+    /// A line that is removed.
+    Remove(BString),
+    /// A line that is added.
+    Add(BString),
+}
+
+/// The decoded data of a binary patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinaryHunk {
+    /// The complete new content.
+    Literal(Vec<u8>),
+    /// A delta in the format used in packs, to be applied to the old content.
+    Delta(Vec<u8>),
+}
+
+/// Lifecycle
+impl Patch {
+    /// Parse `data` as patch, which may contain arbitrary text before and between the changes of each file, configured
+    /// by `options`.
+    pub fn from_bytes(data: &[u8], options: parse::Options) -> Result<Self, parse::Error> {
+        parse::patch(data, options)
+    }
+}
+
+/// Transformation
+impl Patch {
+    /// Return the inverse of this patch, which turns the new version of each file back into its old version,
+    /// like `git apply --reverse`.
+    ///
+    /// Binary files without reverse hunk will have [`Content::BinaryWithoutData`] as they can't be reversed.
+    pub fn reversed(&self) -> Patch {
+        Patch {
+            files: self.files.iter().map(File::reversed).collect(),
+        }
+    }
+}
+
+impl File {
+    /// Return the path this file change is about, which is the new path unless it's deleted.
+    pub fn path(&self) -> &BStr {
+        self.new_path
+            .as_ref()
+            .or(self.old_path.as_ref())
+            .expect("the parser assures at least one path is set")
+            .as_bstr()
+    }
+
+    /// Return the inverse of this file change.
+    pub fn reversed(&self) -> File {
+        let (old_path, new_path) = match self.operation {
+            Operation::Copy { .. } => (self.new_path.clone(), None),
+            _ => (self.new_path.clone(), self.old_path.clone()),
+        };
+        File {
+            old_path,
+            new_path,
+            old_mode: self.new_mode,
+            new_mode: self.old_mode,
+            old_id: self.new_id.clone(),
+            new_id: self.old_id.clone(),
+            operation: match self.operation {
+                Operation::Add | Operation::Copy { .. } => Operation::Delete,
+                Operation::Delete => Operation::Add,
+                other => other,
+            },
+            content: match &self.content {
+                Content::Text(hunks) => Content::Text(hunks.iter().map(Hunk::reversed).collect()),
+                Content::Binary {
+                    forward,
+                    reverse: Some(reverse),
+                } => Content::Binary {
+                    forward: reverse.clone(),
+                    reverse: Some(forward.clone()),
+                },
+                Content::Binary { reverse: None, .. } | Content::BinaryWithoutData => Content::BinaryWithoutData,
+            },
+        }
+    }
+}
+
+impl Hunk {
+    /// Return the inverse of this hunk, with additions and removals swapped.
+    pub fn reversed(&self) -> Hunk {
+        Hunk {
+            old_start: self.new_start,
+            old_len: self.new_len,
+            new_start: self.old_start,
+            new_len: self.old_len,
+            lines: self
+                .lines
+                .iter()
+                .map(|line| match line {
+                    Line::Context(line) => Line::Context(line.clone()),
+                    Line::Remove(line) => Line::Add(line.clone()),
+                    Line::Add(line) => Line::Remove(line.clone()),
+                })
+                .collect(),
+        }
+    }
+
+    /// Return the amount of context lines before the first change.
+    pub fn leading_context(&self) -> usize {
+        self.lines
+            .iter()
+            .take_while(|line| matches!(line, Line::Context(_)))
+            .count()
+    }
+
+    /// Return the amount of context lines after the last change.
+    pub fn trailing_context(&self) -> usize {
+        self.lines
+            .iter()
+            .rev()
+            .take_while(|line| matches!(line, Line::Context(_)))
+            .count()
+    }
+}
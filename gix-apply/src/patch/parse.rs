@@ -0,0 +1,437 @@
+use bstr::{BStr, BString, ByteSlice};
+use gix_object::tree::EntryKind;
+
+use super::binary;
+use crate::{
+    Patch,
+    patch::{BinaryHunk, Content, File, Hunk, Line, Operation},
+};
+
+/// The error returned by [`Patch::from_bytes()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("No valid patches found in input")]
+    NoPatch,
+    #[error("Corrupt patch at line {line}: {message}")]
+    Corrupt { line: usize, message: &'static str },
+    #[error("The git diff header at line {line} lacks filename information")]
+    MissingFilename { line: usize },
+    #[error("The path at line {line} has less than {strip} leading components to strip")]
+    Strip { line: usize, strip: usize },
+    #[error("Could not unquote the path at line {line}")]
+    Unquote {
+        line: usize,
+        source: gix_quote::ansi_c::undo::Error,
+    },
+    #[error("Corrupt binary patch at line {line}")]
+    CorruptBinary { line: usize },
+    #[error("Could not decompress the binary patch at line {line}")]
+    Inflate {
+        line: usize,
+        source: gix_features::zlib::inflate::Error,
+    },
+}
+
+/// Options for use in [`Patch::from_bytes()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// The amount of leading path components to remove from the paths in the patch, like `-p<n>` in `git apply`.
+    ///
+    /// Defaults to `1`, which removes the `a/` and `b/` prefixes `git` uses. Paths in `rename` and `copy` headers
+    /// are never stripped.
+    pub strip: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { strip: 1 }
+    }
+}
+
+pub(crate) fn patch(data: &[u8], options: Options) -> Result<Patch, Error> {
+    let lines: Vec<&[u8]> = data.split_inclusive(|b| *b == b'\n').collect();
+    let mut files = Vec::new();
+    let mut pos = 0;
+    while let Some(line) = lines.get(pos) {
+        if let Some(header) = line.strip_prefix(b"diff --git ") {
+            let (file, next) = git_file(&lines, pos, header, options)?;
+            files.push(file);
+            pos = next;
+        } else if line.starts_with(b"--- ")
+            && lines.get(pos + 1).is_some_and(|line| line.starts_with(b"+++ "))
+            && lines.get(pos + 2).is_some_and(|line| line.starts_with(b"@@ -"))
+        {
+            let (file, next) = traditional_file(&lines, pos, options)?;
+            files.push(file);
+            pos = next;
+        } else {
+            pos += 1;
+        }
+    }
+    if files.is_empty() {
+        return Err(Error::NoPatch);
+    }
+    Ok(Patch { files })
+}
+
+/// Parse the file whose `diff --git` line is at `pos` and whose remainder is `header`, and return it along with the
+/// position of the first line after it.
+fn git_file(lines: &[&[u8]], mut pos: usize, header: &[u8], options: Options) -> Result<(File, usize), Error> {
+    let header_line = pos + 1;
+    let default_name = git_header_name(trim_newline(header), options.strip);
+    let mut file = File {
+        old_path: None,
+        new_path: None,
+        old_mode: None,
+        new_mode: None,
+        old_id: None,
+        new_id: None,
+        operation: Operation::Modify,
+        content: Content::Text(Vec::new()),
+    };
+    let (mut minus_name, mut plus_name) = (None, None);
+    let (mut from, mut to) = (None, None);
+    let mut similarity = None;
+
+    pos += 1;
+    while let Some(line) = lines.get(pos) {
+        let line_no = pos + 1;
+        let line = trim_newline(line);
+        if let Some(mode) = line.strip_prefix(b"old mode ") {
+            file.old_mode = parse_mode(mode, line_no)?.into();
+        } else if let Some(mode) = line.strip_prefix(b"new mode ") {
+            file.new_mode = parse_mode(mode, line_no)?.into();
+        } else if let Some(mode) = line.strip_prefix(b"deleted file mode ") {
+            file.old_mode = parse_mode(mode, line_no)?.into();
+            file.operation = Operation::Delete;
+        } else if let Some(mode) = line.strip_prefix(b"new file mode ") {
+            file.new_mode = parse_mode(mode, line_no)?.into();
+            file.operation = Operation::Add;
+        } else if let Some(path) = line.strip_prefix(b"rename from ") {
+            from = unquote(path, line_no)?.into();
+            file.operation = Operation::Rename { similarity: None };
+        } else if let Some(path) = line.strip_prefix(b"rename to ") {
+            to = unquote(path, line_no)?.into();
+        } else if let Some(path) = line.strip_prefix(b"copy from ") {
+            from = unquote(path, line_no)?.into();
+            file.operation = Operation::Copy { similarity: None };
+        } else if let Some(path) = line.strip_prefix(b"copy to ") {
+            to = unquote(path, line_no)?.into();
+        } else if let Some(percent) = line.strip_prefix(b"similarity index ") {
+            similarity = parse_percent(percent);
+        } else if line.starts_with(b"dissimilarity index ") {
+        } else if let Some(ids) = line.strip_prefix(b"index ") {
+            let (ids, mode) = match ids.find_byte(b' ') {
+                Some(space) => (&ids[..space], Some(&ids[space + 1..])),
+                None => (ids, None),
+            };
+            let (old, new) = ids.split_once_str("..").ok_or(Error::Corrupt {
+                line: line_no,
+                message: "the index line lacks '..' between the object ids",
+            })?;
+            file.old_id = Some(old.into());
+            file.new_id = Some(new.into());
+            if let Some(mode) = mode {
+                let mode = parse_mode(mode, line_no)?;
+                file.old_mode = Some(mode);
+                file.new_mode = Some(mode);
+            }
+        } else if line.starts_with(b"--- ") && lines.get(pos + 1).is_some_and(|line| line.starts_with(b"+++ ")) {
+            minus_name = Some(name_from_marker_line(line, pos, options.strip)?);
+            plus_name = Some(name_from_marker_line(
+                trim_newline(lines[pos + 1]),
+                pos + 1,
+                options.strip,
+            )?);
+            pos += 2;
+            break;
+        } else if line.starts_with(b"Binary files ") && line.ends_with(b" differ") {
+            file.content = Content::BinaryWithoutData;
+            pos += 1;
+            break;
+        } else if line == b"GIT binary patch" {
+            pos += 1;
+            let forward = binary_hunk(lines, &mut pos)?.ok_or(Error::CorruptBinary { line: pos + 1 })?;
+            let reverse = binary_hunk(lines, &mut pos)?;
+            file.content = Content::Binary { forward, reverse };
+            break;
+        } else {
+            break;
+        }
+        pos += 1;
+    }
+
+    match &mut file.operation {
+        Operation::Rename { similarity: s } | Operation::Copy { similarity: s } => *s = similarity,
+        Operation::Modify | Operation::Add | Operation::Delete => {}
+    }
+    let old = minus_name.flatten().or_else(|| default_name.clone());
+    let new = plus_name.flatten().or(default_name);
+    match file.operation {
+        Operation::Add => file.new_path = new,
+        Operation::Delete => file.old_path = old,
+        Operation::Modify => {
+            file.old_path = old;
+            file.new_path = new;
+        }
+        Operation::Rename { .. } | Operation::Copy { .. } => {
+            file.old_path = from;
+            file.new_path = to;
+        }
+    }
+    let has_paths = match file.operation {
+        Operation::Add => file.new_path.is_some(),
+        Operation::Delete => file.old_path.is_some(),
+        _ => file.old_path.is_some() && file.new_path.is_some(),
+    };
+    if !has_paths {
+        return Err(Error::MissingFilename { line: header_line });
+    }
+
+    if matches!(file.content, Content::Text(_)) {
+        let (hunks, next) = hunks(lines, pos)?;
+        file.content = Content::Text(hunks);
+        pos = next;
+    }
+    Ok((file, pos))
+}
+
+/// Parse a file of a patch without `diff --git` header, whose `---` line is at `pos`.
+fn traditional_file(lines: &[&[u8]], pos: usize, options: Options) -> Result<(File, usize), Error> {
+    let old = name_from_marker_line(trim_newline(lines[pos]), pos, options.strip)?;
+    let new = name_from_marker_line(trim_newline(lines[pos + 1]), pos + 1, options.strip)?;
+    let (hunks, next) = hunks(lines, pos + 2)?;
+    let (operation, old_path, new_path) = match (old, new) {
+        (None, None) => return Err(Error::MissingFilename { line: pos + 1 }),
+        (None, Some(new)) => (Operation::Add, None, Some(new)),
+        (Some(old), None) => (Operation::Delete, Some(old), None),
+        // Like `file.orig` and `file`, where the new name is the one to apply to.
+        (Some(_old), Some(new)) => (Operation::Modify, Some(new.clone()), Some(new)),
+    };
+    Ok((
+        File {
+            old_path,
+            new_path,
+            old_mode: None,
+            new_mode: None,
+            old_id: None,
+            new_id: None,
+            operation,
+            content: Content::Text(hunks),
+        },
+        next,
+    ))
+}
+
+/// Parse all hunks starting at `pos`.
+fn hunks(lines: &[&[u8]], mut pos: usize) -> Result<(Vec<Hunk>, usize), Error> {
+    let mut hunks = Vec::new();
+    while let Some(header) = lines.get(pos).and_then(|line| line.strip_prefix(b"@@ -")) {
+        let corrupt = move |message| Error::Corrupt { line: pos + 1, message };
+        let (old, rest) = header
+            .split_once_str(" +")
+            .ok_or(corrupt("the hunk header lacks the new range"))?;
+        let end = rest
+            .find(" @@")
+            .ok_or(corrupt("the hunk header isn't terminated with '@@'"))?;
+        let (old_start, old_len) = parse_range(old).ok_or(corrupt("the old range of the hunk header is invalid"))?;
+        let (new_start, new_len) =
+            parse_range(&rest[..end]).ok_or(corrupt("the new range of the hunk header is invalid"))?;
+        pos += 1;
+
+        let mut hunk = Hunk {
+            old_start,
+            old_len,
+            new_start,
+            new_len,
+            lines: Vec::new(),
+        };
+        let (mut old, mut new) = (old_len, new_len);
+        while old > 0 || new > 0 {
+            let corrupt = move |message| Error::Corrupt { line: pos + 1, message };
+            let line = lines
+                .get(pos)
+                .ok_or(corrupt("the patch ended in the middle of a hunk"))?;
+            let (counts, line) = match line[0] {
+                b' ' => ((1, 1), Line::Context(line[1..].into())),
+                // Some tools strip the space of empty context lines.
+                b'\n' => ((1, 1), Line::Context(line[..].into())),
+                b'\r' if line == b"\r\n" => ((1, 1), Line::Context(line[..].into())),
+                b'-' => ((1, 0), Line::Remove(line[1..].into())),
+                b'+' => ((0, 1), Line::Add(line[1..].into())),
+                b'\\' => {
+                    strip_last_newline(&mut hunk.lines);
+                    pos += 1;
+                    continue;
+                }
+                _ => return Err(corrupt("the hunk contains fewer lines than its header says")),
+            };
+            old = old
+                .checked_sub(counts.0)
+                .ok_or(corrupt("the hunk contains more old lines than its header says"))?;
+            new = new
+                .checked_sub(counts.1)
+                .ok_or(corrupt("the hunk contains more new lines than its header says"))?;
+            hunk.lines.push(line);
+            pos += 1;
+        }
+        if lines.get(pos).is_some_and(|line| line.starts_with(b"\\")) {
+            strip_last_newline(&mut hunk.lines);
+            pos += 1;
+        }
+        hunks.push(hunk);
+    }
+    Ok((hunks, pos))
+}
+
+/// Parse a `literal` or `delta` section of a binary patch at `pos`, or return `None` if there is none.
+fn binary_hunk(lines: &[&[u8]], pos: &mut usize) -> Result<Option<BinaryHunk>, Error> {
+    let Some(header) = lines.get(*pos).map(|line| trim_newline(line)) else {
+        return Ok(None);
+    };
+    let (is_literal, size) = if let Some(size) = header.strip_prefix(b"literal ") {
+        (true, size)
+    } else if let Some(size) = header.strip_prefix(b"delta ") {
+        (false, size)
+    } else {
+        return Ok(None);
+    };
+    let header_line = *pos + 1;
+    let size: usize = size
+        .to_str()
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .ok_or(Error::CorruptBinary { line: header_line })?;
+    *pos += 1;
+
+    let mut compressed = Vec::new();
+    loop {
+        let line = lines
+            .get(*pos)
+            .map(|line| trim_newline(line))
+            .ok_or(Error::CorruptBinary { line: *pos + 1 })?;
+        *pos += 1;
+        if line.is_empty() {
+            break;
+        }
+        binary::decode_line(line, &mut compressed).ok_or(Error::CorruptBinary { line: *pos })?;
+    }
+    let data = binary::inflate(&compressed, size).map_err(|source| Error::Inflate {
+        line: header_line,
+        source,
+    })?;
+    Ok(Some(if is_literal {
+        BinaryHunk::Literal(data)
+    } else {
+        BinaryHunk::Delta(data)
+    }))
+}
+
+/// Parse a range like `12,3` or `12`, which implies a length of `1`.
+fn parse_range(range: &[u8]) -> Option<(u32, u32)> {
+    let range = range.to_str().ok()?;
+    Some(match range.split_once(',') {
+        Some((start, len)) => (start.parse().ok()?, len.parse().ok()?),
+        None => (range.parse().ok()?, 1),
+    })
+}
+
+fn strip_last_newline(lines: &mut [Line]) {
+    if let Some(Line::Context(line) | Line::Remove(line) | Line::Add(line)) = lines.last_mut() {
+        if line.ends_with(b"\n") {
+            line.pop();
+        }
+    }
+}
+
+fn parse_mode(mode: &[u8], line: usize) -> Result<EntryKind, Error> {
+    let corrupt = Error::Corrupt {
+        line,
+        message: "invalid file mode",
+    };
+    let mode = mode
+        .to_str()
+        .ok()
+        .and_then(|mode| u32::from_str_radix(mode.trim(), 8).ok())
+        .ok_or(corrupt)?;
+    Ok(match mode & 0o170000 {
+        0o100000 if mode & 0o111 != 0 => EntryKind::BlobExecutable,
+        0o100000 => EntryKind::Blob,
+        0o120000 => EntryKind::Link,
+        0o160000 => EntryKind::Commit,
+        0o040000 => EntryKind::Tree,
+        _ => {
+            return Err(Error::Corrupt {
+                line,
+                message: "invalid file mode",
+            });
+        }
+    })
+}
+
+fn parse_percent(percent: &[u8]) -> Option<u8> {
+    percent.strip_suffix(b"%")?.to_str().ok()?.parse().ok()
+}
+
+fn trim_newline(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn unquote(path: &[u8], line: usize) -> Result<BString, Error> {
+    gix_quote::ansi_c::undo(path.as_bstr())
+        .map(|(path, _consumed)| path.into_owned())
+        .map_err(|source| Error::Unquote { line, source })
+}
+
+/// Remove `strip` leading components from `path`, or return `None` if it doesn't have enough of them.
+fn strip_components(path: &BStr, strip: usize) -> Option<BString> {
+    let mut path = path;
+    for _ in 0..strip {
+        let slash = path.find_byte(b'/')?;
+        path = path[slash + 1..].as_bstr();
+    }
+    Some(path.into())
+}
+
+/// Extract the name of a `--- name` or `+++ name` `line` at `pos`, which is `None` for `/dev/null`.
+fn name_from_marker_line(line: &[u8], pos: usize, strip: usize) -> Result<Option<BString>, Error> {
+    let name = &line[4..];
+    let name: BString = if name.starts_with(b"\"") {
+        unquote(name, pos + 1)?
+    } else {
+        // Traditional diffs may have a timestamp after a tab.
+        name.split_str("\t").next().unwrap_or_default().trim_end().into()
+    };
+    if name == "/dev/null" {
+        return Ok(None);
+    }
+    strip_components(name.as_bstr(), strip)
+        .map(Some)
+        .ok_or(Error::Strip { line: pos + 1, strip })
+}
+
+/// Find the name in a `diff --git` `header` like `a/name b/name`, which may be quoted, or return `None` if the names
+/// on both sides differ and thus the header alone is ambiguous.
+fn git_header_name(header: &[u8], strip: usize) -> Option<BString> {
+    let same = |left: &BStr, right: &BStr| {
+        let right: BString = if right.starts_with(b"\"") {
+            gix_quote::ansi_c::undo(right).ok()?.0.into_owned()
+        } else {
+            right.into()
+        };
+        let left = strip_components(left, strip)?;
+        (left == strip_components(right.as_bstr(), strip)?).then_some(left)
+    };
+    if header.starts_with(b"\"") {
+        let (left, consumed) = gix_quote::ansi_c::undo(header.as_bstr()).ok()?;
+        let right = header.get(consumed..)?.strip_prefix(b" ")?;
+        return same(left.as_ref(), right.as_bstr());
+    }
+    header
+        .iter()
+        .enumerate()
+        .filter(|(_, byte)| **byte == b' ')
+        .find_map(|(space, _)| same(header[..space].as_bstr(), header[space + 1..].as_bstr()))
+}
@@ -0,0 +1,235 @@
+use std::path::{Path, PathBuf};
+
+use bstr::{BStr, ByteSlice};
+use gix_hash::ObjectId;
+use gix_object::{FindExt, tree::EntryKind};
+
+/// The error returned by all methods of [`Target`].
+pub type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// A place with files that patches can be [applied](crate::apply()) to.
+///
+/// All files are read before the first one is written or removed, so implementations can read from a snapshot.
+pub trait Target {
+    /// Return the content and kind of the file at the slash-separated, relative `path`, or `None` if there is no such file.
+    fn read(&mut self, path: &BStr) -> Result<Option<(Vec<u8>, EntryKind)>, Error>;
+    /// Write `data` as the content of the file at `path` with the given `kind`, creating it if it doesn't exist yet.
+    ///
+    /// `kind` is never [`EntryKind::Tree`] or [`EntryKind::Commit`].
+    fn write(&mut self, path: &BStr, data: &[u8], kind: EntryKind) -> Result<(), Error>;
+    /// Remove the file at `path`, which may not exist if it was only added by the patch that also removes it.
+    fn remove(&mut self, path: &BStr) -> Result<(), Error>;
+}
+
+/// A tree in the object database whose edited version can be [written](Tree::write()) once the patch was applied,
+/// similar to applying a patch to the index and calling `git write-tree`.
+pub struct Tree<'a, Find> {
+    objects: &'a Find,
+    root: Vec<u8>,
+    object_hash: gix_hash::Kind,
+    editor: gix_object::tree::Editor<'a>,
+}
+
+impl<'a, Find> Tree<'a, Find>
+where
+    Find: gix_object::Find + gix_object::Write,
+{
+    /// Create a new instance to apply patches to the tree with `id`, with `objects` to read and write blobs and trees.
+    pub fn new(id: &gix_hash::oid, objects: &'a Find) -> Result<Self, gix_object::find::existing_object::Error> {
+        let mut root = Vec::new();
+        let tree = objects.find_tree(id, &mut root)?.to_owned();
+        Ok(Tree {
+            objects,
+            root,
+            object_hash: id.kind(),
+            editor: gix_object::tree::Editor::new(tree, objects, id.kind()),
+        })
+    }
+
+    /// Write all edited trees and return the id of the new root tree.
+    pub fn write(&mut self) -> Result<ObjectId, gix_object::write::Error> {
+        self.editor.write(|tree| self.objects.write(tree))
+    }
+}
+
+impl<Find> Target for Tree<'_, Find>
+where
+    Find: gix_object::Find + gix_object::Write,
+{
+    fn read(&mut self, path: &BStr) -> Result<Option<(Vec<u8>, EntryKind)>, Error> {
+        let mut buf = Vec::new();
+        let Some(entry) = gix_object::TreeRefIter::from_bytes(&self.root, self.object_hash).lookup_entry(
+            self.objects,
+            &mut buf,
+            path.split_str("/"),
+        )?
+        else {
+            return Ok(None);
+        };
+        let kind = entry.mode.kind();
+        if !entry.mode.is_blob_or_symlink() {
+            return Err(format!("'{path}' is a {kind:?} and can't be patched").into());
+        }
+        let data = self.objects.find_blob(&entry.oid, &mut buf)?.data.to_vec();
+        Ok(Some((data, kind)))
+    }
+
+    fn write(&mut self, path: &BStr, data: &[u8], kind: EntryKind) -> Result<(), Error> {
+        let id = self.objects.write_buf(gix_object::Kind::Blob, data)?;
+        self.editor.upsert(path.split_str("/"), kind, id)?;
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &BStr) -> Result<(), Error> {
+        self.editor.remove(path.split_str("/"))?;
+        Ok(())
+    }
+}
+
+/// The index, whose entries are changed in memory and which needs to be written by the caller, similar to
+/// `git apply --cached`.
+///
+/// Blobs are read from and written to the object database as needed, and the stat information of all changed entries is
+/// cleared to let them appear modified to the worktree.
+pub struct Index<'a, Find> {
+    state: &'a mut gix_index::State,
+    objects: &'a Find,
+}
+
+impl<'a, Find> Index<'a, Find>
+where
+    Find: gix_object::Find + gix_object::Write,
+{
+    /// Create a new instance to apply patches to `state`, using `objects` to read and write blobs.
+    pub fn new(state: &'a mut gix_index::State, objects: &'a Find) -> Self {
+        Index { state, objects }
+    }
+}
+
+impl<Find> Target for Index<'_, Find>
+where
+    Find: gix_object::Find + gix_object::Write,
+{
+    fn read(&mut self, path: &BStr) -> Result<Option<(Vec<u8>, EntryKind)>, Error> {
+        let Some(entry) = self.state.entry_by_path(path) else {
+            return Ok(None);
+        };
+        let Some(mode) = entry
+            .mode
+            .to_tree_entry_mode()
+            .filter(gix_object::tree::EntryMode::is_blob_or_symlink)
+        else {
+            return Err(format!("'{path}' has mode {:?} and can't be patched", entry.mode).into());
+        };
+        let mut buf = Vec::new();
+        let data = self.objects.find_blob(&entry.id, &mut buf)?.data.to_vec();
+        Ok(Some((data, mode.kind())))
+    }
+
+    fn write(&mut self, path: &BStr, data: &[u8], kind: EntryKind) -> Result<(), Error> {
+        let id = self.objects.write_buf(gix_object::Kind::Blob, data)?;
+        let mode = gix_object::tree::EntryMode::from(kind).into();
+        match self.state.entry_index_by_path(path) {
+            Ok(idx) => {
+                let entry = &mut self.state.entries_mut()[idx];
+                entry.id = id;
+                entry.mode = mode;
+                entry.stat = Default::default();
+            }
+            Err(_) => {
+                self.state
+                    .dangerously_push_entry(Default::default(), id, gix_index::entry::Flags::empty(), mode, path);
+                self.state.sort_entries();
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &BStr) -> Result<(), Error> {
+        self.state.remove_entries(|_idx, entry_path, _entry| entry_path == path);
+        Ok(())
+    }
+}
+
+/// A directory with files on disk, similar to what `git apply` does by default.
+///
+/// Files are read and written as they are, without applying filters or converting line endings.
+pub struct Worktree {
+    root: PathBuf,
+}
+
+impl Worktree {
+    /// Create a new instance to apply patches to the files in the `root` directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Worktree { root: root.into() }
+    }
+
+    fn path(&self, path: &BStr) -> PathBuf {
+        self.root.join(gix_path::from_bstr(path))
+    }
+}
+
+impl Target for Worktree {
+    fn read(&mut self, path: &BStr) -> Result<Option<(Vec<u8>, EntryKind)>, Error> {
+        let path = self.path(path);
+        let metadata = match std::fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Some(if metadata.is_symlink() {
+            let target = std::fs::read_link(&path)?;
+            (gix_path::into_bstr(target).into_owned().into(), EntryKind::Link)
+        } else if metadata.is_file() {
+            let kind = if gix_fs::is_executable(&metadata) {
+                EntryKind::BlobExecutable
+            } else {
+                EntryKind::Blob
+            };
+            (std::fs::read(&path)?, kind)
+        } else {
+            return Err(format!("'{}' is not a file and can't be patched", path.display()).into());
+        }))
+    }
+
+    fn write(&mut self, path: &BStr, data: &[u8], kind: EntryKind) -> Result<(), Error> {
+        let path = self.path(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if std::fs::symlink_metadata(&path).is_ok() {
+            std::fs::remove_file(&path)?;
+        }
+        if kind == EntryKind::Link {
+            gix_fs::symlink::create(gix_path::try_from_byte_slice(data)?, &path)?;
+            return Ok(());
+        }
+        std::fs::write(&path, data)?;
+        #[cfg(unix)]
+        if kind == EntryKind::BlobExecutable {
+            use std::os::unix::fs::PermissionsExt;
+            let mut permissions = std::fs::metadata(&path)?.permissions();
+            permissions.set_mode(permissions.mode() | ((permissions.mode() & 0o444) >> 2));
+            std::fs::set_permissions(&path, permissions)?;
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &BStr) -> Result<(), Error> {
+        let path = self.path(path);
+        match std::fs::remove_file(&path) {
+            Ok(()) => remove_empty_parents(&path, &self.root),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        Ok(())
+    }
+}
+
+fn remove_empty_parents(path: &Path, root: &Path) {
+    for dir in path.ancestors().skip(1).take_while(|dir| *dir != root) {
+        if std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use crate::Fixture;
+use gix_apply::{
+    Patch, apply,
+    apply::{Error, Options, Status, Whitespace},
+    target,
+};
+
+/// Apply `patch` to the tree of `rev` and return the id of the new tree.
+fn apply_to_tree(
+    fixture: &Fixture,
+    rev: &str,
+    patch: &Patch,
+    options: Options,
+) -> Result<(gix_hash::ObjectId, apply::Outcome), Error> {
+    let mut tree = target::Tree::new(&fixture.tree(rev).expect("valid rev"), &fixture.odb).expect("tree exists");
+    let outcome = apply(patch, &mut tree, &fixture.odb, options)?;
+    Ok((tree.write().expect("writable"), outcome))
+}
+
+fn blob_at(fixture: &Fixture, tree: gix_hash::ObjectId, path: &str) -> crate::Result<String> {
+    let out = fixture.git(&["cat-file", "-p", &format!("{tree}:{path}")])?;
+    Ok(out)
+}
+
+#[test]
+fn tree_is_changed_like_git_does() -> crate::Result {
+    let fixture = Fixture::new()?;
+    for patch in ["all.patch", "mail.patch"] {
+        let (tree, outcome) = apply_to_tree(&fixture, "base", &fixture.patch(patch)?, Default::default())?;
+        assert_eq!(tree, fixture.tree("changed")?, "{patch}: the same tree is produced");
+        assert_eq!(outcome.files.len(), 7);
+        assert!(outcome.files.iter().all(|file| file.status == Status::Applied));
+        assert_eq!(outcome.whitespace_errors, 0);
+    }
+
+    let (tree, _) = apply_to_tree(
+        &fixture,
+        "changed",
+        &fixture.patch("all.patch")?,
+        Options {
+            reverse: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(tree, fixture.tree("base")?, "reversing goes back to where it started");
+    Ok(())
+}
+
+#[test]
+fn check_and_failures_change_nothing() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let patch = fixture.patch("all.patch")?;
+    let (tree, outcome) = apply_to_tree(
+        &fixture,
+        "base",
+        &patch,
+        Options {
+            check: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(tree, fixture.tree("base")?);
+    assert_eq!(outcome.files.len(), 7, "the outcome is still reported");
+
+    assert!(
+        matches!(
+            apply_to_tree(&fixture, "changed", &patch, Default::default()),
+            Err(Error::BinaryPreimageMismatch { path }) if path == "binary"
+        ),
+        "the patch was applied already"
+    );
+    let mut worktree = target::Worktree::new(fixture.workdir());
+    std::fs::write(fixture.workdir().join("text"), "local changes\n")?;
+    assert!(matches!(
+        apply(&patch, &mut worktree, &fixture.odb, Default::default()),
+        Err(Error::HunkMismatch { path, .. }) if path == "text"
+    ));
+    assert_eq!(
+        fixture.git(&["status", "--porcelain"])?,
+        " M text\n",
+        "the files before it weren't touched either"
+    );
+    Ok(())
+}
+
+#[test]
+fn worktree() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let mut worktree = target::Worktree::new(fixture.workdir());
+    apply(
+        &fixture.patch("all.patch")?,
+        &mut worktree,
+        &fixture.odb,
+        Default::default(),
+    )?;
+    assert!(!fixture.workdir().join("to-rename").exists());
+    assert!(!fixture.workdir().join("deleted").exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(fixture.workdir().join("script"))?
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o111, 0o111, "the executable bit is set");
+    }
+    fixture.git(&["add", "-A"])?;
+    assert_eq!(
+        fixture.git(&["diff", "--cached", "--name-only", "changed"])?,
+        "",
+        "the worktree matches the changed commit"
+    );
+    Ok(())
+}
+
+#[test]
+fn index() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let path = fixture.workdir().join(".git/index");
+    let mut index = gix_index::File::at(&path, gix_testtools::object_hash(), false, Default::default())?;
+    apply(
+        &fixture.patch("all.patch")?,
+        &mut target::Index::new(&mut index, &fixture.odb),
+        &fixture.odb,
+        Default::default(),
+    )?;
+    index.write(Default::default())?;
+    assert_eq!(
+        fixture.git(&["diff", "--cached", "--name-only", "changed"])?,
+        "",
+        "the index matches the changed commit"
+    );
+    assert_eq!(
+        fixture.git(&["diff", "--name-only"])?,
+        "binary\ndir/added\nlarge-binary\nrenamed\nscript\ntext\n",
+        "the worktree is unchanged"
+    );
+    Ok(())
+}
+
+#[test]
+fn fuzz_and_three_way_merges() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let patch = fixture.patch("all.patch")?;
+    assert!(
+        matches!(
+            apply_to_tree(&fixture, "diverged", &patch, Default::default()),
+            Err(Error::HunkMismatch { path, .. }) if path == "text"
+        ),
+        "the context of both hunks changed"
+    );
+
+    let (tree, _) = apply_to_tree(
+        &fixture,
+        "diverged",
+        &patch,
+        Options {
+            min_context: Some(1),
+            ..Default::default()
+        },
+    )?;
+    let expected = "1\n2\nthree\n4\nfive\n6\n7\n8\n9\n10\n11\n12\n13\nfourteen\n15\n16\nseventeen\n18\n19\n20\n";
+    assert_eq!(blob_at(&fixture, tree, "text")?, expected);
+
+    let (tree, outcome) = apply_to_tree(
+        &fixture,
+        "diverged",
+        &patch,
+        Options {
+            three_way: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(blob_at(&fixture, tree, "text")?, expected);
+    assert_eq!(outcome.files.last().map(|file| file.status), Some(Status::Merged));
+    assert!(!outcome.has_conflicts());
+
+    let (tree, outcome) = apply_to_tree(
+        &fixture,
+        "conflicting",
+        &patch,
+        Options {
+            three_way: true,
+            ..Default::default()
+        },
+    )?;
+    assert!(outcome.has_conflicts());
+    let text = blob_at(&fixture, tree, "text")?;
+    assert!(
+        text.contains("<<<<<<< ours\ndrei\n=======\nthree\n>>>>>>> theirs\n"),
+        "conflicts are marked: {text}"
+    );
+    assert!(text.contains("seventeen\n"), "the other hunk still applies");
+    Ok(())
+}
+
+#[test]
+fn three_way_merges_need_the_full_blob_id() -> crate::Result {
+    let fixture = Fixture::new()?;
+    assert!(matches!(
+        apply_to_tree(
+            &fixture,
+            "diverged",
+            &fixture.patch("text.patch")?,
+            Options {
+                three_way: true,
+                ..Default::default()
+            }
+        ),
+        Err(Error::HunkMismatch { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn binaries_without_data_need_the_new_blob() -> crate::Result {
+    let fixture = Fixture::new()?;
+    assert!(matches!(
+        apply_to_tree(
+            &fixture,
+            "base",
+            &fixture.patch("binary-without-data.patch")?,
+            Default::default()
+        ),
+        Err(Error::BinaryWithoutData { path }) if path == "binary"
+    ));
+
+    let base_id = fixture.git(&["rev-parse", "base:binary"])?;
+    let id = fixture.git(&["rev-parse", "changed:binary"])?;
+    let (base_id, id) = (base_id.trim(), id.trim());
+    let patch = Patch::from_bytes(
+        format!(
+            "diff --git a/binary b/binary\nindex {base_id}..{id} 100644\nBinary files a/binary and b/binary differ\n"
+        )
+        .as_bytes(),
+        Default::default(),
+    )?;
+    let (tree, _) = apply_to_tree(&fixture, "base", &patch, Default::default())?;
+    assert_eq!(
+        fixture.git(&["rev-parse", &format!("{tree}:binary")])?.trim(),
+        id,
+        "with full ids, the blob is taken from the object database"
+    );
+    Ok(())
+}
+
+#[test]
+fn whitespace_errors() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let patch = Patch::from_bytes(
+        b"--- /dev/null\n+++ b/new\n@@ -0,0 +1,2 @@\n+trailing \n+fine\n",
+        Default::default(),
+    )?;
+    let (tree, outcome) = apply_to_tree(&fixture, "base", &patch, Default::default())?;
+    assert_eq!(outcome.whitespace_errors, 1);
+    assert_eq!(blob_at(&fixture, tree, "new")?, "trailing \nfine\n");
+
+    let (tree, _) = apply_to_tree(
+        &fixture,
+        "base",
+        &patch,
+        Options {
+            whitespace: Whitespace::Fix,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(blob_at(&fixture, tree, "new")?, "trailing\nfine\n");
+
+    assert!(matches!(
+        apply_to_tree(
+            &fixture,
+            "base",
+            &patch,
+            Options {
+                whitespace: Whitespace::Error,
+                ..Default::default()
+            }
+        ),
+        Err(Error::WhitespaceErrors { count: 1 })
+    ));
+    Ok(())
+}
+
+#[test]
+fn invalid_paths_and_missing_files() -> crate::Result {
+    let fixture = Fixture::new()?;
+    for path in ["../escape", ".git/config", "a//b"] {
+        let patch = Patch::from_bytes(
+            format!("--- /dev/null\n+++ b/{path}\n@@ -0,0 +1 @@\n+x\n").as_bytes(),
+            Default::default(),
+        )?;
+        assert!(
+            matches!(
+                apply_to_tree(&fixture, "base", &patch, Default::default()),
+                Err(Error::InvalidPath { .. })
+            ),
+            "{path}"
+        );
+    }
+
+    let patch = Patch::from_bytes(b"--- a/missing\n+++ /dev/null\n@@ -1 +0,0 @@\n-x\n", Default::default())?;
+    assert!(matches!(
+        apply_to_tree(&fixture, "base", &patch, Default::default()),
+        Err(Error::Missing { path }) if path == "missing"
+    ));
+
+    let patch = Patch::from_bytes(b"--- a/text\n+++ /dev/null\n@@ -1 +0,0 @@\n-1\n", Default::default())?;
+    assert!(matches!(
+        apply_to_tree(
+            &fixture,
+            "base",
+            &patch,
+            Options {
+                unidiff_zero: true,
+                ..Default::default()
+            }
+        ),
+        Err(Error::DeletionLeavesContent { path }) if path == "text"
+    ));
+    Ok(())
+}
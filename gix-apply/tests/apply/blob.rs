@@ -0,0 +1,190 @@
+use gix_apply::{
+    Patch,
+    blob::{apply_hunks, text},
+    patch::Content,
+};
+
+/// Parse a patch for a single file with the given `hunks` and apply it to `data`.
+fn apply(data: &str, hunks: &str, options: text::Options) -> Result<(String, text::Outcome), text::Error> {
+    let patch =
+        Patch::from_bytes(format!("--- a/f\n+++ b/f\n{hunks}").as_bytes(), Default::default()).expect("valid patch");
+    let Content::Text(hunks) = &patch.files[0].content else {
+        unreachable!("always text")
+    };
+    let mut out = Vec::new();
+    let outcome = apply_hunks(data.as_bytes(), hunks, &mut out, options)?;
+    Ok((String::from_utf8(out).expect("valid UTF-8"), outcome))
+}
+
+fn lines(range: std::ops::RangeInclusive<usize>) -> String {
+    range.fold(String::new(), |mut out, n| {
+        out.push_str(&n.to_string());
+        out.push('\n');
+        out
+    })
+}
+
+#[test]
+fn hunks_apply_at_their_position() -> crate::Result {
+    let (out, outcome) = apply(
+        &lines(1..=10),
+        "@@ -1,3 +1,3 @@\n 1\n-2\n+two\n 3\n@@ -8,3 +8,3 @@\n 8\n-9\n+nine\n 10\n",
+        Default::default(),
+    )?;
+    assert_eq!(out, "1\ntwo\n3\n4\n5\n6\n7\n8\nnine\n10\n");
+    assert_eq!(outcome.whitespace_errors, 0);
+    Ok(())
+}
+
+#[test]
+fn hunks_are_found_if_lines_moved() -> crate::Result {
+    let data = format!("new\nlines\n{}", lines(1..=10));
+    let (out, _) = apply(&data, "@@ -4,3 +4,3 @@\n 4\n-5\n+five\n 6\n", Default::default())?;
+    assert_eq!(out, format!("new\nlines\n1\n2\n3\n4\nfive\n6\n{}", lines(7..=10)));
+
+    let (out, _) = apply(
+        &lines(3..=10),
+        "@@ -4,3 +4,3 @@\n 4\n-5\n+five\n 6\n",
+        Default::default(),
+    )?;
+    assert_eq!(out, format!("3\n4\nfive\n6\n{}", lines(7..=10)), "or removed");
+    Ok(())
+}
+
+#[test]
+fn hunks_at_the_beginning_or_end_must_stay_there() {
+    let data = format!("new\n{}", lines(1..=3));
+    assert!(
+        matches!(
+            apply(&data, "@@ -1,2 +1,2 @@\n-1\n+one\n 2\n", Default::default()),
+            Err(text::Error::HunkMismatch { hunk: 1 })
+        ),
+        "the lack of leading context means the hunk must apply at the first line"
+    );
+    let data = format!("{}new\n", lines(1..=3));
+    assert!(
+        matches!(
+            apply(&data, "@@ -2,2 +2,2 @@\n 2\n-3\n+three\n", Default::default()),
+            Err(text::Error::HunkMismatch { hunk: 1 })
+        ),
+        "the lack of trailing context means the hunk must apply at the last line"
+    );
+}
+
+#[test]
+fn context_can_be_reduced() -> crate::Result {
+    let data = lines(1..=10).replace("2\n", "changed\n");
+    let hunk = "@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n";
+    assert!(matches!(
+        apply(&data, hunk, Default::default()),
+        Err(text::Error::HunkMismatch { hunk: 1 })
+    ));
+    assert!(
+        matches!(
+            apply(
+                &data,
+                hunk,
+                text::Options {
+                    min_context: Some(3),
+                    ..Default::default()
+                }
+            ),
+            Err(text::Error::HunkMismatch { hunk: 1 })
+        ),
+        "all context lines are required"
+    );
+    let (out, _) = apply(
+        &data,
+        hunk,
+        text::Options {
+            min_context: Some(2),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(
+        out,
+        lines(1..=10).replace("2\n", "changed\n").replace("5\n", "five\n"),
+        "the mismatching context is kept"
+    );
+    Ok(())
+}
+
+#[test]
+fn whitespace_can_be_ignored_in_context() -> crate::Result {
+    let data = "a  b\n\tc\nd\n";
+    let hunk = "@@ -1,3 +1,3 @@\n a b\n c\n-d\n+e\n";
+    assert!(apply(data, hunk, Default::default()).is_err());
+    let (out, _) = apply(
+        data,
+        hunk,
+        text::Options {
+            ignore_whitespace: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(out, "a  b\n\tc\ne\n", "context lines are taken from the file");
+    Ok(())
+}
+
+#[test]
+fn trailing_whitespace_is_counted_and_fixed() -> crate::Result {
+    let hunk = "@@ -1 +1,3 @@\n a\n+b \n+c\t\r\n";
+    let (out, outcome) = apply("a\n", hunk, Default::default())?;
+    assert_eq!(out, "a\nb \nc\t\r\n");
+    assert_eq!(outcome.whitespace_errors, 2);
+
+    let (out, outcome) = apply(
+        "a\n",
+        hunk,
+        text::Options {
+            fix_whitespace: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(out, "a\nb\nc\r\n", "line endings are kept");
+    assert_eq!(outcome.whitespace_errors, 2);
+    Ok(())
+}
+
+#[test]
+fn missing_newlines_at_the_end() -> crate::Result {
+    let (out, _) = apply(
+        "a\nb",
+        "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n",
+        Default::default(),
+    )?;
+    assert_eq!(out, "a\nb\n");
+
+    assert!(
+        apply(
+            "a\nb\n",
+            "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+c\n",
+            Default::default()
+        )
+        .is_err(),
+        "the missing newline is part of the line"
+    );
+    Ok(())
+}
+
+#[test]
+fn hunks_without_context() -> crate::Result {
+    let hunk = "@@ -5 +5 @@\n-5\n+five\n";
+    assert!(
+        apply(&lines(1..=10), hunk, Default::default()).is_err(),
+        "such hunks are ambiguous"
+    );
+    let (out, _) = apply(
+        &lines(1..=10),
+        hunk,
+        text::Options {
+            unidiff_zero: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(out, lines(1..=10).replace("5\n", "five\n"));
+
+    let (out, _) = apply("", "@@ -0,0 +1,2 @@\n+a\n+b\n", Default::default())?;
+    assert_eq!(out, "a\nb\n", "new files have no context");
+    Ok(())
+}
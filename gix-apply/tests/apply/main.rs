@@ -0,0 +1,67 @@
+use std::path::{Path, PathBuf};
+
+use gix_apply::Patch;
+use gix_hash::ObjectId;
+
+pub use gix_testtools::Result;
+
+mod apply;
+mod blob;
+mod parse;
+
+/// Read the patch file `name` from the directory of the fixture script.
+fn patch(root: &Path, name: &str) -> Result<Patch> {
+    Ok(Patch::from_bytes(&std::fs::read(root.join(name))?, Default::default())?)
+}
+
+fn read_only_root() -> Result<PathBuf> {
+    gix_testtools::scripted_fixture_read_only("make_apply_repo.sh")
+}
+
+struct Fixture {
+    tmp: gix_testtools::tempfile::TempDir,
+    odb: gix_odb::Handle,
+}
+
+impl Fixture {
+    fn new() -> Result<Self> {
+        let tmp = gix_testtools::scripted_fixture_writable("make_apply_repo.sh")?;
+        let odb = gix_odb::at_opts(
+            tmp.path().join("repo/.git/objects"),
+            None,
+            gix_odb::store::init::Options {
+                object_hash: gix_testtools::object_hash(),
+                ..Default::default()
+            },
+        )?;
+        Ok(Fixture { tmp, odb })
+    }
+
+    fn workdir(&self) -> PathBuf {
+        self.tmp.path().join("repo")
+    }
+
+    fn patch(&self, name: &str) -> Result<Patch> {
+        patch(self.tmp.path(), name)
+    }
+
+    /// Run `git` with `args` in the repository and return its output, failing if it doesn't succeed.
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let out = std::process::Command::new(gix_path::env::exe_invocation())
+            .args(args)
+            .current_dir(self.workdir())
+            .output()?;
+        assert!(
+            out.status.success(),
+            "{args:?} failed: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        Ok(String::from_utf8(out.stdout)?)
+    }
+
+    fn tree(&self, rev: &str) -> Result<ObjectId> {
+        Ok(ObjectId::from_hex(
+            self.git(&["rev-parse", &format!("{rev}^{{tree}}")])?.trim().as_bytes(),
+        )?)
+    }
+}
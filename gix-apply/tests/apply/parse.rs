@@ -0,0 +1,214 @@
+use gix_apply::{
+    Patch,
+    patch::{BinaryHunk, Content, Hunk, Line, Operation, parse},
+};
+use gix_object::tree::EntryKind;
+
+use crate::{patch, read_only_root};
+
+fn parse(input: &str) -> Result<Patch, parse::Error> {
+    Patch::from_bytes(input.as_bytes(), Default::default())
+}
+
+#[test]
+fn all_extended_headers_of_git_diff() -> crate::Result {
+    let patch = patch(&read_only_root()?, "all.patch")?;
+    let summary: Vec<_> = patch
+        .files
+        .iter()
+        .map(|file| {
+            (
+                file.old_path.as_ref().map(ToString::to_string),
+                file.new_path.as_ref().map(ToString::to_string),
+                file.operation,
+            )
+        })
+        .collect();
+    let path = |p: &str| Some(p.to_owned());
+    assert_eq!(
+        summary,
+        [
+            (path("binary"), path("binary"), Operation::Modify),
+            (path("deleted"), None, Operation::Delete),
+            (None, path("dir/added"), Operation::Add),
+            (path("large-binary"), path("large-binary"), Operation::Modify),
+            (
+                path("to-rename"),
+                path("renamed"),
+                Operation::Rename { similarity: Some(90) }
+            ),
+            (path("script"), path("script"), Operation::Modify),
+            (path("text"), path("text"), Operation::Modify),
+        ]
+    );
+
+    let [binary, deleted, added, large_binary, _renamed, script, text] = &patch.files[..] else {
+        unreachable!("checked above")
+    };
+    assert!(
+        matches!(&binary.content, Content::Binary { forward: BinaryHunk::Literal(data), reverse: Some(BinaryHunk::Literal(_)) } if data == b"binary\0data\x01\x02\x03 and more \0\x01\x02"),
+        "small binaries are stored as literal"
+    );
+    assert!(
+        matches!(
+            &large_binary.content,
+            Content::Binary {
+                forward: BinaryHunk::Delta(_),
+                reverse: Some(BinaryHunk::Delta(_))
+            }
+        ),
+        "larger ones as delta"
+    );
+    assert_eq!(deleted.old_mode, Some(EntryKind::Blob));
+    assert_eq!(added.new_mode, Some(EntryKind::Blob));
+    assert_eq!(
+        added.old_id.as_ref().map(|id| id.len()),
+        Some(gix_testtools::object_hash().len_in_hex()),
+        "ids are recorded as is"
+    );
+    assert_eq!(
+        (script.old_mode, script.new_mode),
+        (Some(EntryKind::Blob), Some(EntryKind::BlobExecutable))
+    );
+    assert_eq!(script.content, Content::Text(Vec::new()), "only the mode changes");
+    let Content::Text(hunks) = &text.content else {
+        panic!("text is text")
+    };
+    assert_eq!(hunks.len(), 2);
+    assert_eq!(
+        (
+            hunks[1].old_start,
+            hunks[1].old_len,
+            hunks[1].new_start,
+            hunks[1].new_len
+        ),
+        (14, 7, 14, 7)
+    );
+    assert_eq!(hunks[1].lines[3], Line::Remove("17\n".into()));
+    assert_eq!(hunks[1].lines[4], Line::Add("seventeen\n".into()));
+    Ok(())
+}
+
+#[test]
+fn mails_are_parsed_like_plain_diffs() -> crate::Result {
+    let root = read_only_root()?;
+    let mail = patch(&root, "mail.patch")?;
+    let diff = patch(&root, "all.patch")?;
+    assert_eq!(mail.files.len(), diff.files.len(), "the signature is ignored");
+    for (mail, diff) in mail.files.iter().zip(&diff.files) {
+        assert_eq!(mail.old_path, diff.old_path);
+        assert_eq!(mail.new_path, diff.new_path);
+        assert_eq!(mail.content, diff.content);
+    }
+    Ok(())
+}
+
+#[test]
+fn binary_without_data() -> crate::Result {
+    let patch = patch(&read_only_root()?, "binary-without-data.patch")?;
+    assert_eq!(patch.files.len(), 1);
+    assert_eq!(patch.files[0].content, Content::BinaryWithoutData);
+    assert_eq!(patch.files[0].path(), "binary");
+    Ok(())
+}
+
+#[test]
+fn quoted_paths() -> crate::Result {
+    let patch = parse(
+        r#"diff --git "a/with space\ttab" "b/with space\ttab"
+new file mode 100755
+index 0000000..257cc56
+--- /dev/null
++++ "b/with space\ttab"
+@@ -0,0 +1 @@
++foo
+diff --git a/same b/same b/same b/same
+old mode 100644
+new mode 120000
+diff --git "a/from" b/to
+similarity index 100%
+rename from "from"
+rename to to
+"#,
+    )?;
+    assert_eq!(patch.files[0].new_path.as_ref().expect("added"), "with space\ttab");
+    assert_eq!(patch.files[0].new_mode, Some(EntryKind::BlobExecutable));
+    assert_eq!(
+        patch.files[1].new_path.as_ref().expect("changed"),
+        "same b/same",
+        "the names are found even if they contain the separator"
+    );
+    assert_eq!(patch.files[1].new_mode, Some(EntryKind::Link));
+    assert_eq!(patch.files[2].old_path.as_ref().expect("renamed"), "from");
+    assert_eq!(patch.files[2].new_path.as_ref().expect("renamed"), "to");
+    assert_eq!(patch.files[2].operation, Operation::Rename { similarity: Some(100) });
+    Ok(())
+}
+
+#[test]
+fn traditional_diffs_and_missing_newlines() -> crate::Result {
+    let patch = parse(
+        "Some text before the patch
+--- a/file.orig\t2024-01-01 00:00:00
++++ b/file\t2024-01-01 00:00:01
+@@ -1,2 +1,2 @@
+ a
+-b
+\\ No newline at end of file
++c
+\\ No newline at end of file
+--- /dev/null
++++ dir/new
+@@ -0,0 +1 @@
++new
+",
+    )?;
+    assert_eq!(patch.files.len(), 2);
+    assert_eq!(patch.files[0].operation, Operation::Modify);
+    assert_eq!(patch.files[0].old_path.as_ref().expect("modified"), "file");
+    assert_eq!(
+        patch.files[0].content,
+        Content::Text(vec![Hunk {
+            old_start: 1,
+            old_len: 2,
+            new_start: 1,
+            new_len: 2,
+            lines: vec![
+                Line::Context("a\n".into()),
+                Line::Remove("b".into()),
+                Line::Add("c".into())
+            ],
+        }])
+    );
+    assert_eq!(patch.files[1].operation, Operation::Add);
+    assert_eq!(
+        patch.files[1].new_path.as_ref().expect("added"),
+        "new",
+        "the first component is stripped"
+    );
+
+    let patch = Patch::from_bytes(b"--- a\n+++ b\n@@ -1 +1 @@\n-a\n+b\n", parse::Options { strip: 0 })?;
+    assert_eq!(patch.files[0].new_path.as_ref().expect("modified"), "b");
+    Ok(())
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(parse("just text\n"), Err(parse::Error::NoPatch)));
+    assert!(matches!(
+        parse("--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n-a\n+b\n"),
+        Err(parse::Error::Corrupt { line: 6, .. })
+    ));
+    assert!(matches!(
+        parse("--- a/f\n+++ b/f\n@@ -1 +1 @@\n-a\n-b\n+b\n"),
+        Err(parse::Error::Corrupt { line: 5, .. })
+    ));
+    assert!(matches!(
+        parse("diff --git a/f b/g\nold mode 100644\nnew mode 100755\n"),
+        Err(parse::Error::MissingFilename { line: 1 })
+    ));
+    assert!(matches!(
+        parse("diff --git a/f b/f\nGIT binary patch\nliteral 5\nnot-base85\n\n"),
+        Err(parse::Error::CorruptBinary { line: 4 })
+    ));
+}
@@ -0,0 +1,44 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q repo && cd repo
+
+seq 1 20 > text
+printf '#!/bin/sh\necho hi\n' > script
+printf 'binary\0data\1\2\3' > binary
+{ seq 1 1000; printf '\0'; } > large-binary
+seq 100 130 > to-rename
+echo "gone soon" > deleted
+git add .
+git commit -q -m base
+git tag base
+
+seq 1 20 | sed -e 's/^3$/three/' -e 's/^17$/seventeen/' > text
+chmod +x script
+printf 'binary\0data\1\2\3 and more \0\1\2' > binary
+{ seq 1 1000 | sed 's/^500$/five hundred/'; printf '\0'; } > large-binary
+seq 100 130 | sed 's/^115$/one-fifteen/' > renamed
+rm to-rename deleted
+mkdir dir
+echo "new" > dir/added
+git add -A
+git commit -q -m changed
+git tag changed
+
+git diff --full-index --binary -M base changed > ../all.patch
+git diff base changed -- text > ../text.patch
+git diff base changed -- binary > ../binary-without-data.patch
+git format-patch -q -1 --stdout changed > ../mail.patch
+
+git checkout -q -b diverged base
+seq 1 20 | sed -e 's/^5$/five/' -e 's/^14$/fourteen/' > text
+git commit -q -am "overlapping change"
+git tag diverged
+
+git checkout -q -b conflicting base
+seq 1 20 | sed 's/^3$/drei/' > text
+git commit -q -am "conflicting change"
+git tag conflicting
+
+git checkout -q main
+git reset -q --hard base
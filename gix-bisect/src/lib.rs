@@ -0,0 +1,47 @@
+//! Find the commit that introduced a change by binary search through the commit history, akin to `git bisect`.
+//!
+//! The workflow is as follows:
+//!
+//! * [`session::start()`] a bisection, optionally with a known *bad* and any amount of *good* commits, which persists
+//!   the [state](State) in `.git/BISECT_*` files and references in `refs/bisect/`.
+//! * [`session::mark()`] commits as *bad*, *good* or to be *skipped*, with *bad* and *good* possibly named differently
+//!   by means of [terms](state::Terms).
+//! * Ask for the [`session::next()`] commit to test, which is the [midpoint](midpoint()) between the commits known
+//!   to be *good* and *bad*, until the first *bad* commit was found.
+//! * Or let [`session::run()`] do all of the above by running a command on each commit to test.
+//! * [`session::reset()`] to conclude the bisection.
+//!
+//! Each step is recorded in the [bisect log](log), which can be [replayed](session::replay()) to restore a bisection.
+//!
+//! As the persisted state is compatible with what `git` writes, `git bisect` can pick up where this crate left off,
+//! and vice versa.
+//!
+//! ### Deviation
+//!
+//! * Neither `HEAD`, nor the index or the worktree are touched, the caller is expected to check out the commits to test.
+//! * Pathspecs to limit the commits to test to those changing certain paths aren't supported.
+//! * Good commits that aren't ancestors of the bad commit are accepted as is, whereas `git` would ask to test their
+//!   merge-bases first.
+//! * If there are multiple commits which halve the remaining commits equally well, the one that is chosen may differ from
+//!   the one `git` would choose.
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+
+/// The name of the file in the `.git` directory that holds the commands and decisions of the bisection in progress.
+pub const BISECT_LOG: &str = "BISECT_LOG";
+/// The name of the reference that points to the commit that was chosen to be tested next.
+pub const BISECT_EXPECTED_REV: &str = "BISECT_EXPECTED_REV";
+
+///
+pub mod state;
+pub use state::State;
+
+///
+pub mod log;
+
+///
+pub mod midpoint;
+pub use midpoint::function::midpoint;
+
+///
+pub mod session;
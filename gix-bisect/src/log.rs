@@ -0,0 +1,155 @@
+//! Read and write the bisect log at `.git/BISECT_LOG`, which records all commands that changed the bisection
+//! along with comments about the commits involved.
+//!
+//! The format is the one used by `git`, so its log can be [replayed](crate::session::replay()) by us, and vice versa.
+use std::path::Path;
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+
+/// A command recorded in the bisect log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// The bisection was started, with `args` being the options and revisions passed to `git bisect start`.
+    Start {
+        /// The arguments in the order they were given, which are options like `--term-new=<term>` or `--first-parent`,
+        /// followed by the bad revision and all good revisions.
+        args: Vec<BString>,
+    },
+    /// One or more revisions were marked with `term`, which is one of the [terms](crate::state::Terms) or `skip`.
+    Mark {
+        /// The term the revisions were marked with.
+        term: BString,
+        /// The revisions that were marked, usually hexadecimal object ids.
+        revs: Vec<BString>,
+    },
+}
+
+impl Command {
+    /// Serialize this command into a line of the bisect log, terminated by a newline.
+    pub fn to_bstring(&self) -> BString {
+        let mut out = BString::from("git bisect");
+        match self {
+            Command::Start { args } => {
+                out.push_str(" start");
+                for arg in args {
+                    out.push(b' ');
+                    out.extend_from_slice(&gix_quote::single(arg.as_ref()));
+                }
+            }
+            Command::Mark { term, revs } => {
+                out.push(b' ');
+                out.push_str(term);
+                for rev in revs {
+                    out.push(b' ');
+                    out.push_str(rev);
+                }
+            }
+        }
+        out.push(b'\n');
+        out
+    }
+}
+
+///
+pub mod parse {
+    use bstr::BString;
+
+    /// The error returned by [`parse()`](super::parse()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Line {line_number} of the bisect log is not a bisect command: {line:?}")]
+        Line { line_number: usize, line: BString },
+        #[error("Line {line_number} of the bisect log has an unterminated quote: {line:?}")]
+        Quote { line_number: usize, line: BString },
+    }
+}
+
+/// Parse all commands from the bisect `log`, skipping comments and empty lines.
+pub fn parse(log: &[u8]) -> Result<Vec<Command>, parse::Error> {
+    let mut out = Vec::new();
+    for (line_number, line) in log.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(b"#") {
+            continue;
+        }
+        let line_number = line_number + 1;
+        let invalid = || parse::Error::Line {
+            line_number,
+            line: line.into(),
+        };
+        let rest = line
+            .strip_prefix(b"git bisect ")
+            .or_else(|| line.strip_prefix(b"git-bisect "))
+            .ok_or_else(invalid)?;
+        let mut words = split_words(rest.as_bstr()).ok_or_else(|| parse::Error::Quote {
+            line_number,
+            line: line.into(),
+        })?;
+        if words.is_empty() {
+            return Err(invalid());
+        }
+        let command = words.remove(0);
+        out.push(if command == "start" {
+            Command::Start { args: words }
+        } else {
+            Command::Mark {
+                term: command,
+                revs: words,
+            }
+        });
+    }
+    Ok(out)
+}
+
+/// Split `line` into whitespace-separated words, undoing the quoting of [`gix_quote::single()`],
+/// or return `None` if a quote isn't terminated.
+fn split_words(mut line: &BStr) -> Option<Vec<BString>> {
+    let mut out = Vec::new();
+    loop {
+        line = line.trim_start().as_bstr();
+        if line.is_empty() {
+            return Some(out);
+        }
+        let mut word = BString::default();
+        while let Some(&first) = line.first() {
+            match first {
+                b'\'' => {
+                    let end = line[1..].find_byte(b'\'')? + 1;
+                    word.extend_from_slice(&line[1..end]);
+                    line = line[end + 1..].as_bstr();
+                }
+                b'\\' if line.len() > 1 => {
+                    word.push(line[1]);
+                    line = line[2..].as_bstr();
+                }
+                b' ' | b'\t' => break,
+                _ => {
+                    word.push(first);
+                    line = line[1..].as_bstr();
+                }
+            }
+        }
+        out.push(word);
+    }
+}
+
+/// Append `text` to the bisect log in `git_dir`.
+pub(crate) fn append(git_dir: &Path, text: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(git_dir.join(crate::BISECT_LOG))?
+        .write_all(text)
+}
+
+/// Return a comment describing `id` with its `summary`, as in `# <what>: [<id>] <summary>`.
+pub(crate) fn commit_comment(what: &BStr, id: &gix_hash::oid, summary: &BStr) -> BString {
+    let mut out = BString::from("# ");
+    out.push_str(what);
+    out.push_str(format!(": [{id}] "));
+    out.push_str(summary);
+    out.push(b'\n');
+    out
+}
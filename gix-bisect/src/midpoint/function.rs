@@ -0,0 +1,203 @@
+use std::cmp::Ordering;
+
+use gix_hash::ObjectId;
+use gix_revwalk::{Graph, PriorityQueue, graph};
+
+use super::{Error, Flags, Outcome, estimate_steps};
+
+/// Given the `bad` commit and all `good` ones, traverse the commit `graph` to find the commit that is best to test next,
+/// which is the one that splits the commits which may be the first bad one into halves of equal size.
+///
+/// These *candidates* are all commits reachable from `bad`, but not from any of the `good` commits.
+/// `skipped` commits are never chosen, but they are candidates nonetheless.
+/// If `first_parent` is `true`, only the first parent of merge commits is followed.
+///
+/// Note that commits are traversed by generation number if `graph` is backed by a commit-graph, and by commit time otherwise.
+///
+/// # Performance
+///
+/// For repeated calls, be sure to re-use `graph` as its content will be kept and reused for a great speed-up. The contained flags
+/// will automatically be cleared.
+pub fn midpoint(
+    bad: ObjectId,
+    good: &[ObjectId],
+    skipped: &[ObjectId],
+    first_parent: bool,
+    graph: &mut Graph<'_, '_, graph::Commit<Flags>>,
+) -> Result<Outcome, Error> {
+    graph.clear_commit_data(|f| *f = Flags::empty());
+    let candidates = paint(bad, good, first_parent, graph)?;
+    if !candidates.contains(&bad) {
+        return Err(Error::BadIsGood { bad });
+    }
+    if candidates.len() == 1 {
+        return Ok(Outcome::FirstBad { id: bad });
+    }
+
+    let weights = weights(&candidates, first_parent, graph);
+    let total = candidates.len();
+    let distance = |weight: usize| weight.min(total - weight);
+    let best = candidates
+        .iter()
+        .zip(&weights)
+        .enumerate()
+        .filter(|(_, (id, _))| **id != bad && !skipped.contains(id))
+        // Among equally good commits, prefer the one that comes last in the traversal, i.e. the oldest one.
+        .max_by_key(|(position, (_, weight))| (distance(**weight), *position))
+        .map(|(_, best)| best);
+    Ok(match best {
+        Some((id, weight)) => Outcome::Next {
+            id: *id,
+            remaining: total - weight - 1,
+            steps: estimate_steps(total),
+        },
+        None => Outcome::OnlySkipped {
+            candidates: std::iter::once(bad)
+                .chain(candidates.into_iter().filter(|id| *id != bad))
+                .collect(),
+        },
+    })
+}
+
+/// Mark everything reachable from `bad` and `good` and return all commits only reachable from `bad`, in traversal order.
+fn paint(
+    bad: ObjectId,
+    good: &[ObjectId],
+    first_parent: bool,
+    graph: &mut Graph<'_, '_, graph::Commit<Flags>>,
+) -> Result<Vec<ObjectId>, Error> {
+    let mut queue = PriorityQueue::<GenThenTime, ObjectId>::new();
+    for (id, flags) in std::iter::once((bad, Flags::BAD)).chain(good.iter().map(|id| (*id, Flags::GOOD))) {
+        if graph
+            .get_or_insert_full_commit(id, |commit| {
+                commit.data |= flags;
+                queue.insert(GenThenTime::from(&*commit), id);
+            })?
+            .is_none()
+        {
+            return Err(Error::MissingCommit { id });
+        }
+    }
+
+    let mut out = Vec::new();
+    while queue
+        .iter_unordered()
+        .any(|id| graph.get(id).is_some_and(|commit| !commit.data.contains(Flags::GOOD)))
+    {
+        let (_info, commit_id) = queue.pop().expect("we have non-good");
+        let commit = graph.get(&commit_id).expect("everything queued is in graph");
+        let flags = commit.data;
+        if flags == Flags::BAD {
+            out.push(commit_id);
+        }
+        let parents = commit.parents.clone();
+        for parent_id in parents.into_iter().take(if first_parent { 1 } else { usize::MAX }) {
+            // Parents that can't be found are treated as boundary, as in shallow clones.
+            graph.get_or_insert_full_commit(parent_id, |parent| {
+                if (parent.data & flags) != flags {
+                    parent.data |= flags;
+                    queue.insert(GenThenTime::from(&*parent), parent_id);
+                }
+            })?;
+        }
+    }
+
+    out.retain(|id| graph.get(id).is_some_and(|commit| commit.data == Flags::BAD));
+    Ok(out)
+}
+
+/// Return the amount of candidates reachable from each of the `candidates`, including itself.
+fn weights(candidates: &[ObjectId], first_parent: bool, graph: &Graph<'_, '_, graph::Commit<Flags>>) -> Vec<usize> {
+    let index: gix_hashtable::HashMap<_, _> = candidates.iter().enumerate().map(|(idx, id)| (*id, idx)).collect();
+    let parents: Vec<Vec<usize>> = candidates
+        .iter()
+        .map(|id| {
+            let commit = graph.get(id).expect("candidates are in graph");
+            commit
+                .parents
+                .iter()
+                .take(if first_parent { 1 } else { usize::MAX })
+                .filter_map(|parent| index.get(parent).copied())
+                .collect()
+        })
+        .collect();
+
+    const UNSET: usize = 0;
+    let mut weights = vec![UNSET; candidates.len()];
+    let mut seen = vec![false; candidates.len()];
+    let mut stack = Vec::new();
+    for root in 0..candidates.len() {
+        // Visit parents before their children, so single-parent chains can reuse the weight of their parent.
+        stack.push((root, false));
+        while let Some((idx, parents_done)) = stack.pop() {
+            if weights[idx] != UNSET {
+                continue;
+            }
+            if !parents_done {
+                stack.push((idx, true));
+                stack.extend(
+                    parents[idx]
+                        .iter()
+                        .filter(|p| weights[**p] == UNSET)
+                        .map(|p| (*p, false)),
+                );
+                continue;
+            }
+            weights[idx] = match parents[idx].as_slice() {
+                [] => 1,
+                [parent] => weights[*parent] + 1,
+                _ => {
+                    seen.iter_mut().for_each(|seen| *seen = false);
+                    let mut count = 0;
+                    let mut to_visit = vec![idx];
+                    while let Some(idx) = to_visit.pop() {
+                        if std::mem::replace(&mut seen[idx], true) {
+                            continue;
+                        }
+                        count += 1;
+                        to_visit.extend(parents[idx].iter().copied());
+                    }
+                    count
+                }
+            };
+        }
+    }
+    weights
+}
+
+#[derive(Debug, Clone, Copy)]
+struct GenThenTime {
+    /// Note that the special [`GENERATION_NUMBER_INFINITY`](gix_commitgraph::GENERATION_NUMBER_INFINITY) is used to indicate
+    /// that no commitgraph is available.
+    generation: gix_revwalk::graph::Generation,
+    time: gix_date::SecondsSinceUnixEpoch,
+}
+
+impl From<&graph::Commit<Flags>> for GenThenTime {
+    fn from(commit: &graph::Commit<Flags>) -> Self {
+        GenThenTime {
+            generation: commit.generation.unwrap_or(gix_commitgraph::GENERATION_NUMBER_INFINITY),
+            time: commit.commit_time,
+        }
+    }
+}
+
+impl Eq for GenThenTime {}
+
+impl PartialEq<Self> for GenThenTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl PartialOrd<Self> for GenThenTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GenThenTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.generation.cmp(&other.generation).then(self.time.cmp(&other.time))
+    }
+}
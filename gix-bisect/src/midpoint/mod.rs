@@ -0,0 +1,62 @@
+use gix_hash::ObjectId;
+
+bitflags::bitflags! {
+    /// The flags used in the graph for finding the [midpoint](crate::midpoint()).
+    #[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+    pub struct Flags: u8 {
+        /// The commit is reachable from the bad commit.
+        const BAD = 1 << 0;
+        /// The commit is reachable from one of the good commits.
+        const GOOD = 1 << 1;
+    }
+}
+
+/// The error returned by the [`midpoint()`](crate::midpoint()) function.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The commit {id} could not be found")]
+    MissingCommit { id: ObjectId },
+    #[error("The bad commit {bad} is an ancestor of a good commit")]
+    BadIsGood { bad: ObjectId },
+    #[error(transparent)]
+    Graph(#[from] gix_revwalk::graph::get_or_insert_default::Error),
+}
+
+/// The result of the [`midpoint()`](crate::midpoint()) function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The commit to test next, which splits the commits that may be the first bad one in half.
+    Next {
+        /// The commit to test.
+        id: ObjectId,
+        /// The amount of commits left to test after this one, in the worst case.
+        remaining: usize,
+        /// The approximate amount of steps it will take to find the first bad commit after this one.
+        steps: usize,
+    },
+    /// The first bad commit was found, as there is nothing left to test.
+    FirstBad {
+        /// The first commit that was bad.
+        id: ObjectId,
+    },
+    /// Only skipped commits are left to test, and the first bad commit is one of `candidates`.
+    OnlySkipped {
+        /// The bad commit followed by all skipped commits which may be the first bad one.
+        candidates: Vec<ObjectId>,
+    },
+}
+
+/// Return the approximate amount of steps it takes to find the first bad commit among `count` commits,
+/// just like `git` computes it.
+pub fn estimate_steps(count: usize) -> usize {
+    if count < 3 {
+        return 0;
+    }
+    let n = count.ilog2() as usize;
+    let e = 1 << n;
+    let x = count - e;
+    if e < 3 * x { n } else { n - 1 }
+}
+
+pub(crate) mod function;
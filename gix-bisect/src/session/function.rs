@@ -0,0 +1,406 @@
+use std::{ffi::OsString, path::Path};
+
+use bstr::{BStr, BString, ByteVec};
+use gix_hash::ObjectId;
+use gix_object::FindExt;
+use gix_ref::{
+    Target,
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+};
+
+use crate::{
+    BISECT_EXPECTED_REV, State, log,
+    midpoint::Outcome,
+    session::{Checkout, Error, Options},
+    state::{self, Mark, Terms},
+};
+
+/// Start a bisection in the `git_dir` of `refs` and persist its state, with `start_point` being the short name of the branch
+/// that is checked out, or the hexadecimal id of the commit `HEAD` points to if it is detached.
+/// It's what [`reset()`] returns once the bisection is done.
+///
+/// `bad` and `good` are the commits known to be bad and good, if any, and `objects` is used to obtain their summaries
+/// for the [bisect log](crate::log).
+/// `committer` is only needed if reflogs are written for all references.
+///
+/// Use [`next()`] to learn which commit to test.
+pub fn start<'a>(
+    start_point: &BStr,
+    bad: Option<ObjectId>,
+    good: &[ObjectId],
+    options: Options,
+    refs: &gix_ref::file::Store,
+    objects: &impl gix_object::Find,
+    committer: impl Into<Option<gix_actor::SignatureRef<'a>>>,
+) -> Result<State, Error> {
+    let git_dir = refs.git_dir();
+    if State::read(refs)?.is_some() {
+        return Err(Error::InProgress);
+    }
+    options.terms.validate()?;
+    let mut state = State::new(start_point, options.terms);
+    state.first_parent = options.first_parent;
+    state.write(git_dir)?;
+
+    let mut args = Vec::new();
+    if state.terms != Terms::default() {
+        args.push(format!("--term-old={}", state.terms.good).into());
+        args.push(format!("--term-new={}", state.terms.bad).into());
+    }
+    if state.first_parent {
+        args.push("--first-parent".into());
+    }
+    let mut text = BString::default();
+    let Some(bad) = bad else {
+        // Without a bad commit, good ones can't be passed as arguments and are marked instead.
+        text.push_str(log::Command::Start { args }.to_bstring());
+        text.push_str(status(&state));
+        log::append(git_dir, &text).map_err(Error::Log)?;
+        return if good.is_empty() {
+            Ok(state)
+        } else {
+            mark(Mark::Good, good, refs, objects, committer)
+        };
+    };
+
+    for (mark, id) in std::iter::once((Mark::Bad, &bad)).chain(good.iter().map(|id| (Mark::Good, id))) {
+        text.push_str(mark_comment(&state.terms, mark, id, objects)?);
+        args.push(id.to_string().into());
+    }
+    text.push_str(log::Command::Start { args }.to_bstring());
+    edit_references(
+        refs,
+        std::iter::once(update(state.terms.reference_name(Mark::Bad, &bad), bad)).chain(
+            good.iter()
+                .map(|id| update(state.terms.reference_name(Mark::Good, id), *id)),
+        ),
+        committer,
+    )?;
+    let state = State::read(refs)?.ok_or(Error::NotInProgress)?;
+    text.push_str(status(&state));
+    log::append(git_dir, &text).map_err(Error::Log)?;
+    Ok(state)
+}
+
+/// Mark all `ids` with `mark` in the bisection in progress in the `git_dir` of `refs`, and return the updated state.
+///
+/// Note that only a single commit can be marked as [bad](Mark::Bad) at a time, and that it replaces the previous one.
+/// `objects` is used to obtain the summaries of commits for the [bisect log](crate::log), and `committer`
+/// is only needed if reflogs are written for all references.
+pub fn mark<'a>(
+    mark: Mark,
+    ids: &[ObjectId],
+    refs: &gix_ref::file::Store,
+    objects: &impl gix_object::Find,
+    committer: impl Into<Option<gix_actor::SignatureRef<'a>>>,
+) -> Result<State, Error> {
+    let state = State::read(refs)?.ok_or(Error::NotInProgress)?;
+    if mark == Mark::Bad && ids.len() > 1 {
+        return Err(Error::MultipleBad {
+            term: state.terms.bad.clone(),
+        });
+    }
+    edit_references(
+        refs,
+        ids.iter().map(|id| update(state.terms.reference_name(mark, id), *id)),
+        committer,
+    )?;
+
+    let mut text = BString::default();
+    for id in ids {
+        text.push_str(mark_comment(&state.terms, mark, id, objects)?);
+        text.push_str(
+            log::Command::Mark {
+                term: state.terms.name(mark).to_owned(),
+                revs: vec![id.to_string().into()],
+            }
+            .to_bstring(),
+        );
+    }
+    let state = State::read(refs)?.ok_or(Error::NotInProgress)?;
+    text.push_str(status(&state));
+    log::append(refs.git_dir(), &text).map_err(Error::Log)?;
+    Ok(state)
+}
+
+/// Find the commit to test next in the bisection in progress in the `git_dir` of `refs`, using `objects` and the optional
+/// commit-graph `cache` to traverse the commit graph.
+///
+/// If there is a commit to test, [`BISECT_EXPECTED_REV`] is set to it, and it's up to the caller to check it out.
+/// Otherwise, the first bad commit or the remaining candidates are recorded in the [bisect log](crate::log).
+/// `committer` is only needed if reflogs are written for all references.
+pub fn next<'a>(
+    refs: &gix_ref::file::Store,
+    objects: &impl gix_object::Find,
+    cache: Option<&gix_commitgraph::Graph>,
+    committer: impl Into<Option<gix_actor::SignatureRef<'a>>>,
+) -> Result<Outcome, Error> {
+    let state = State::read(refs)?.ok_or(Error::NotInProgress)?;
+    let bad = state.bad.ok_or_else(|| Error::NeedsBad {
+        term: state.terms.bad.clone(),
+    })?;
+    if state.good.is_empty() {
+        return Err(Error::NeedsGood {
+            term: state.terms.good.clone(),
+        });
+    }
+
+    let mut graph = gix_revwalk::Graph::new(objects, cache);
+    let outcome = crate::midpoint(bad, &state.good, &state.skipped, state.first_parent, &mut graph)?;
+    let mut text = BString::default();
+    match &outcome {
+        Outcome::Next { id, .. } => {
+            edit_references(
+                refs,
+                Some(update(BISECT_EXPECTED_REV.try_into().expect("valid"), *id)),
+                committer,
+            )?;
+        }
+        Outcome::FirstBad { id } => {
+            text.push_str(log::commit_comment(
+                "first bad commit".into(),
+                id,
+                summary(objects, id)?.as_ref(),
+            ));
+        }
+        Outcome::OnlySkipped { candidates } => {
+            text.push_str("# only skipped commits left to test\n");
+            for id in candidates {
+                text.push_str(log::commit_comment(
+                    "possible first bad commit".into(),
+                    id,
+                    summary(objects, id)?.as_ref(),
+                ));
+            }
+        }
+    }
+    log::append(refs.git_dir(), &text).map_err(Error::Log)?;
+    Ok(outcome)
+}
+
+/// Conclude the bisection in progress in the `git_dir` of `refs` by removing all of its state, and return the start point
+/// that was passed to [`start()`], or `None` if there was no bisection in progress.
+///
+/// It's up to the caller to check out the start point again.
+pub fn reset(refs: &gix_ref::file::Store) -> Result<Option<BString>, Error> {
+    let state = State::read(refs)?;
+    let platform = refs.iter()?;
+    let mut names = Vec::new();
+    for reference in platform.prefixed(state::REFS_PREFIX.try_into().expect("valid"))? {
+        names.push(reference?.name);
+    }
+    if refs.try_find_loose(BISECT_EXPECTED_REV).ok().flatten().is_some() {
+        names.push(BISECT_EXPECTED_REV.try_into().expect("valid"));
+    }
+    edit_references(
+        refs,
+        names.into_iter().map(|name| RefEdit {
+            change: Change::Delete {
+                expected: PreviousValue::Any,
+                log: RefLog::AndReference,
+            },
+            name,
+            deref: false,
+        }),
+        None,
+    )?;
+    State::remove(refs.git_dir())?;
+    Ok(state.map(|state| state.start))
+}
+
+/// Restore a bisection from the commands in the bisect `log`, as previously written by us or by `git`, after [resetting](reset())
+/// any bisection in progress in the `git_dir` of `refs`.
+///
+/// `start_point` is the same as in [`start()`], and `resolve(rev)` is used to turn the revisions in the log into object ids.
+/// `objects` is used to obtain the summaries of commits for the [bisect log](crate::log), which is rewritten in the process,
+/// and `committer` is only needed if reflogs are written for all references.
+///
+/// Return the restored state, or `None` if the log didn't start a bisection.
+pub fn replay<'a>(
+    log: &[u8],
+    start_point: &BStr,
+    refs: &gix_ref::file::Store,
+    objects: &impl gix_object::Find,
+    mut resolve: impl FnMut(&BStr) -> Option<ObjectId>,
+    committer: impl Into<Option<gix_actor::SignatureRef<'a>>>,
+) -> Result<Option<State>, Error> {
+    let commands = log::parse(log)?;
+    reset(refs)?;
+    let committer = committer.into();
+    let mut resolve = |rev: &BString| resolve(rev.as_ref()).ok_or_else(|| Error::ResolveRevision { rev: rev.clone() });
+    let mut state = None;
+    for command in commands {
+        state = Some(match command {
+            log::Command::Start { args } => {
+                let mut options = Options::default();
+                let mut revs = Vec::new();
+                for arg in &args {
+                    if let Some(term) = arg.strip_prefix(b"--term-new=").or(arg.strip_prefix(b"--term-bad=")) {
+                        options.terms.bad = term.into();
+                    } else if let Some(term) = arg.strip_prefix(b"--term-old=").or(arg.strip_prefix(b"--term-good=")) {
+                        options.terms.good = term.into();
+                    } else if arg == "--first-parent" {
+                        options.first_parent = true;
+                    } else if arg == "--no-checkout" || arg == "--" {
+                        continue;
+                    } else if arg.starts_with(b"-") {
+                        return Err(Error::UnsupportedOption { option: arg.clone() });
+                    } else {
+                        revs.push(resolve(arg)?);
+                    }
+                }
+                let (bad, good) = match revs.split_first() {
+                    Some((bad, good)) => (Some(*bad), good),
+                    None => (None, &[][..]),
+                };
+                start(start_point, bad, good, options, refs, objects, committer)?
+            }
+            log::Command::Mark { term, revs } => {
+                let terms = state
+                    .as_ref()
+                    .map(|state: &State| &state.terms)
+                    .ok_or(Error::NotInProgress)?;
+                let mark = terms.mark(term.as_ref()).ok_or(Error::UnknownTerm { term })?;
+                let ids = revs.iter().map(&mut resolve).collect::<Result<Vec<_>, _>>()?;
+                self::mark(mark, &ids, refs, objects, committer)?
+            }
+        });
+    }
+    Ok(state)
+}
+
+/// Bisect automatically by running the shell `command` in `workdir` on each commit to test, until the first bad commit is found,
+/// or until only skipped commits are left.
+///
+/// Before each run, `checkout(id)` is called to check out the commit to test into `workdir`.
+/// The exit code of `command` decides how the commit is [marked](mark()):
+///
+/// * `0` marks it as good.
+/// * `125` skips it as it can't be tested.
+/// * Any other code from `1` to `127` marks it as bad.
+/// * Everything else, like codes of `128` and above, aborts the bisection with an error that contains the code,
+///   just like a command that was terminated by a signal.
+///
+/// `objects`, `cache` and `committer` are used as in [`next()`].
+pub fn run<'a>(
+    command: impl Into<OsString>,
+    workdir: &Path,
+    checkout: &mut Checkout<'_>,
+    refs: &gix_ref::file::Store,
+    objects: &impl gix_object::Find,
+    cache: Option<&gix_commitgraph::Graph>,
+    committer: impl Into<Option<gix_actor::SignatureRef<'a>>>,
+) -> Result<Outcome, Error> {
+    let command = command.into();
+    let committer = committer.into();
+    loop {
+        let id = match next(refs, objects, cache, committer)? {
+            Outcome::Next { id, .. } => id,
+            outcome => return Ok(outcome),
+        };
+        checkout(&id).map_err(|source| Error::Checkout { source, id })?;
+
+        let status = std::process::Command::from(gix_command::prepare(command.clone()).with_shell().with_context(
+            gix_command::Context {
+                git_dir: Some(refs.git_dir().to_owned()),
+                worktree_dir: Some(workdir.to_owned()),
+                ..Default::default()
+            },
+        ))
+        .current_dir(workdir)
+        .status()
+        .map_err(|source| Error::SpawnCommand {
+            source,
+            command: command.to_string_lossy().into_owned(),
+        })?;
+        let mark = match status.code() {
+            Some(0) => Mark::Good,
+            Some(125) => Mark::Skip,
+            Some(1..=127) => Mark::Bad,
+            // Codes of 128 and above are used by shells for commands that died from a signal, and by `git` when it aborts.
+            Some(code) => {
+                return Err(Error::CommandAborted {
+                    command: command.to_string_lossy().into_owned(),
+                    code,
+                });
+            }
+            None => {
+                return Err(Error::CommandTerminated {
+                    command: command.to_string_lossy().into_owned(),
+                    status,
+                });
+            }
+        };
+        self::mark(mark, &[id], refs, objects, committer)?;
+    }
+}
+
+/// Return the comment that describes `id` being marked with `mark`.
+fn mark_comment(
+    terms: &Terms,
+    mark: Mark,
+    id: &gix_hash::oid,
+    objects: &impl gix_object::Find,
+) -> Result<BString, Error> {
+    Ok(log::commit_comment(
+        terms.name(mark),
+        id,
+        summary(objects, id)?.as_ref(),
+    ))
+}
+
+/// Return the status line `git` writes while it's waiting for bad or good commits, or nothing if both are known.
+fn status(state: &State) -> BString {
+    let status = match (state.bad.is_some(), state.good.len()) {
+        (true, 0) => "waiting for good commit(s), bad commit known".into(),
+        (false, 0) => "waiting for both good and bad commits".into(),
+        (false, 1) => "waiting for bad commit, 1 good commit known".into(),
+        (false, count) => format!("waiting for bad commit, {count} good commits known"),
+        (true, _) => return BString::default(),
+    };
+    format!("# status: {status}\n").into()
+}
+
+fn summary(objects: &impl gix_object::Find, id: &gix_hash::oid) -> Result<BString, Error> {
+    Ok(objects
+        .find_commit(id, &mut Vec::new())?
+        .message()
+        .summary()
+        .into_owned())
+}
+
+/// Create an edit to set the reference `name` to `id`.
+fn update(name: gix_ref::FullName, id: ObjectId) -> RefEdit {
+    RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: false,
+                message: "".into(),
+            },
+            expected: PreviousValue::Any,
+            new: Target::Object(id),
+        },
+        name,
+        deref: false,
+    }
+}
+
+fn edit_references<'a>(
+    refs: &gix_ref::file::Store,
+    edits: impl IntoIterator<Item = RefEdit>,
+    committer: impl Into<Option<gix_actor::SignatureRef<'a>>>,
+) -> Result<(), Error> {
+    let edits: Vec<_> = edits.into_iter().collect();
+    if edits.is_empty() {
+        return Ok(());
+    }
+    refs.transaction()
+        .prepare(
+            edits,
+            gix_lock::acquire::Fail::Immediately,
+            gix_lock::acquire::Fail::Immediately,
+        )?
+        .commit(committer)?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use gix_hash::ObjectId;
+
+use crate::state::Terms;
+
+/// The error returned by functions in the [session](crate::session) module.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("A bisection is already in progress")]
+    InProgress,
+    #[error("There is no bisection in progress")]
+    NotInProgress,
+    #[error("Only a single commit can be marked as '{term}' at a time")]
+    MultipleBad { term: bstr::BString },
+    #[error("A '{term}' commit is needed to find the next commit to test")]
+    NeedsBad { term: bstr::BString },
+    #[error("At least one '{term}' commit is needed to find the next commit to test")]
+    NeedsGood { term: bstr::BString },
+    #[error("The bisect log refers to the unknown term '{term}'")]
+    UnknownTerm { term: bstr::BString },
+    #[error("The bisect log refers to the unsupported option '{option}'")]
+    UnsupportedOption { option: bstr::BString },
+    #[error("The revision '{rev}' in the bisect log could not be resolved")]
+    ResolveRevision { rev: bstr::BString },
+    #[error("Could not run the test command '{command}'")]
+    SpawnCommand { source: std::io::Error, command: String },
+    #[error("The test command '{command}' exited with code {code}, aborting the bisection")]
+    CommandAborted { command: String, code: i32 },
+    #[error("The test command '{command}' was terminated with {status}, aborting the bisection")]
+    CommandTerminated {
+        command: String,
+        status: std::process::ExitStatus,
+    },
+    #[error("Could not check out commit {id} for testing")]
+    Checkout {
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+        id: ObjectId,
+    },
+    #[error("Could not write the bisect log")]
+    Log(#[source] std::io::Error),
+    #[error(transparent)]
+    Terms(#[from] crate::state::terms::Error),
+    #[error(transparent)]
+    ParseLog(#[from] crate::log::parse::Error),
+    #[error(transparent)]
+    ReadState(#[from] crate::state::read::Error),
+    #[error(transparent)]
+    WriteState(#[from] crate::state::write::Error),
+    #[error(transparent)]
+    Midpoint(#[from] crate::midpoint::Error),
+    #[error(transparent)]
+    FindCommit(#[from] gix_object::find::existing_object::Error),
+    #[error(transparent)]
+    DecodeCommit(#[from] gix_object::decode::Error),
+    #[error(transparent)]
+    IterReferences(#[from] gix_ref::packed::buffer::open::Error),
+    #[error(transparent)]
+    IterReferencesPrefixed(#[from] std::io::Error),
+    #[error(transparent)]
+    Reference(#[from] gix_ref::file::iter::loose_then_packed::Error),
+    #[error(transparent)]
+    PrepareTransaction(#[from] gix_ref::file::transaction::prepare::Error),
+    #[error(transparent)]
+    CommitTransaction(#[from] gix_ref::file::transaction::commit::Error),
+}
+
+/// A way to configure [`start()`].
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// The terms to use for bad and good commits.
+    pub terms: Terms,
+    /// If `true`, only the first parent of merge commits is followed when choosing commits to test.
+    pub first_parent: bool,
+}
+
+/// The type of function to check out a commit before testing it in [`run()`].
+pub type Checkout<'a> =
+    dyn FnMut(&gix_hash::oid) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> + 'a;
+
+pub(super) mod function;
+pub use function::{mark, next, replay, reset, run, start};
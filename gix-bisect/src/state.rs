@@ -0,0 +1,357 @@
+//! Read and write the state of a bisection in progress from and to the `.git/BISECT_*` files and references in `refs/bisect/`.
+//!
+//! The layout is the one used by `git`, so a bisection started here can be continued by `git`, and vice versa.
+use std::path::Path;
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_hash::ObjectId;
+
+/// The file which marks a bisection to be in progress, holding the name of the branch or the commit that was
+/// checked out when it started.
+pub const BISECT_START: &str = "BISECT_START";
+/// The file holding the terms used for *bad* and *good* commits, one per line.
+pub const BISECT_TERMS: &str = "BISECT_TERMS";
+/// The file holding the pathspecs to limit the bisection to, which are unsupported and always empty when written by us.
+pub const BISECT_NAMES: &str = "BISECT_NAMES";
+/// The file whose presence indicates that only first parents should be followed.
+pub const BISECT_FIRST_PARENT: &str = "BISECT_FIRST_PARENT";
+/// Files that `git` may write while bisecting and which are removed when the bisection ends.
+const OTHER_FILES: &[&str] = &["BISECT_ANCESTORS_OK", "BISECT_RUN", "BISECT_HEAD"];
+
+/// The prefix of all references that record the decisions made during the bisection.
+pub const REFS_PREFIX: &str = "refs/bisect/";
+/// The term used for commits which are skipped as they can't be tested, which is the same for all [terms](Terms).
+pub const SKIP: &str = "skip";
+
+/// The decision about a commit that was tested.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Mark {
+    /// The commit has the property we are looking for, like a bug or a performance regression.
+    Bad,
+    /// The commit doesn't have the property we are looking for.
+    Good,
+    /// The commit can't be tested.
+    Skip,
+}
+
+/// The names used for [bad](Mark::Bad) and [good](Mark::Good) commits, which default to `bad` and `good`.
+///
+/// Custom terms help when the property to find isn't a bug, like `slow` and `fast`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Terms {
+    /// The term for commits that have the property we are looking for, also known as *new* commits.
+    pub bad: BString,
+    /// The term for commits that don't have the property we are looking for, also known as *old* commits.
+    pub good: BString,
+}
+
+impl Default for Terms {
+    fn default() -> Self {
+        Terms {
+            bad: "bad".into(),
+            good: "good".into(),
+        }
+    }
+}
+
+///
+pub mod terms {
+    use bstr::BString;
+
+    /// The error returned by [`Terms::validate()`](super::Terms::validate()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The terms for bad and good commits must be different, but both are '{term}'")]
+        Identical { term: BString },
+        #[error("'{term}' is a reserved word and can't be used as term")]
+        Reserved { term: BString },
+        #[error("'{term}' can't be used as term as it can't be part of a reference name")]
+        InvalidName {
+            source: gix_validate::reference::name::Error,
+            term: BString,
+        },
+    }
+}
+
+impl Terms {
+    /// Assure the terms can be used to name references and commands, just like `git` would.
+    pub fn validate(&self) -> Result<(), terms::Error> {
+        if self.bad == self.good {
+            return Err(terms::Error::Identical { term: self.bad.clone() });
+        }
+        for (term, allowed) in [(&self.bad, ["bad", "new"]), (&self.good, ["good", "old"])] {
+            let reserved = [
+                "help",
+                "start",
+                "skip",
+                "next",
+                "reset",
+                "visualize",
+                "view",
+                "replay",
+                "log",
+                "run",
+                "terms",
+                "bad",
+                "new",
+                "good",
+                "old",
+            ];
+            if reserved.iter().any(|word| term == word) && !allowed.iter().any(|word| term == word) {
+                return Err(terms::Error::Reserved { term: term.clone() });
+            }
+            let mut name = BString::from(REFS_PREFIX);
+            name.push_str(term);
+            gix_validate::reference::name(name.as_ref()).map_err(|source| terms::Error::InvalidName {
+                source,
+                term: term.clone(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Return the term used for `mark`.
+    pub fn name(&self, mark: Mark) -> &BStr {
+        match mark {
+            Mark::Bad => self.bad.as_ref(),
+            Mark::Good => self.good.as_ref(),
+            Mark::Skip => SKIP.into(),
+        }
+    }
+
+    /// Return the mark identified by `term`, or `None` if it isn't one of our terms.
+    pub fn mark(&self, term: &BStr) -> Option<Mark> {
+        if term == self.bad {
+            Some(Mark::Bad)
+        } else if term == self.good {
+            Some(Mark::Good)
+        } else if term == SKIP {
+            Some(Mark::Skip)
+        } else {
+            None
+        }
+    }
+
+    /// Return the name of the reference that records `id` as `mark`.
+    ///
+    /// Note that there is only one reference for [bad](Mark::Bad) commits, whereas all other marks get their own reference
+    /// for each marked commit.
+    pub fn reference_name(&self, mark: Mark, id: &gix_hash::oid) -> gix_ref::FullName {
+        let mut name = BString::from(REFS_PREFIX);
+        name.push_str(self.name(mark));
+        if mark != Mark::Bad {
+            name.push_str(format!("-{id}"));
+        }
+        name.try_into().expect("terms were validated and hex is always valid")
+    }
+}
+
+/// The persisted state of a bisection in progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    /// The short name of the branch that was checked out when the bisection started, or the hexadecimal id of the commit
+    /// if `HEAD` was detached.
+    pub start: BString,
+    /// The terms used for bad and good commits.
+    pub terms: Terms,
+    /// If `true`, only the first parent of merge commits is followed when choosing commits to test.
+    pub first_parent: bool,
+    /// The commit that is known to be bad, which is the most recent one that was marked as such.
+    pub bad: Option<ObjectId>,
+    /// All commits that were marked as good.
+    pub good: Vec<ObjectId>,
+    /// All commits that were skipped as they can't be tested.
+    pub skipped: Vec<ObjectId>,
+}
+
+///
+pub mod read {
+    use std::path::PathBuf;
+
+    use bstr::BString;
+
+    /// The error returned by [`State::read()`](super::State::read()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read bisect state file at '{}'", path.display())]
+        Io { source: std::io::Error, path: PathBuf },
+        #[error("The bisect terms in '{}' are malformed: {content:?}", path.display())]
+        Terms { content: BString, path: PathBuf },
+        #[error(transparent)]
+        IterReferences(#[from] gix_ref::packed::buffer::open::Error),
+        #[error(transparent)]
+        IterReferencesPrefixed(#[from] std::io::Error),
+        #[error(transparent)]
+        Reference(#[from] gix_ref::file::iter::loose_then_packed::Error),
+    }
+}
+
+/// The error returned by [`State::write()`].
+pub mod write {
+    /// The error returned by [`State::write()`](super::State::write()).
+    #[derive(Debug, thiserror::Error)]
+    #[error("Could not write bisect state file at '{}'", path.display())]
+    pub struct Error {
+        /// The underlying error.
+        pub source: std::io::Error,
+        /// The path we tried to write to.
+        pub path: std::path::PathBuf,
+    }
+}
+
+/// Lifecycle
+impl State {
+    /// Create a new instance for a bisection that starts at `start`, the short name of the checked out branch,
+    /// or the hexadecimal id of the commit `HEAD` points to.
+    pub fn new(start: impl Into<BString>, terms: Terms) -> Self {
+        State {
+            start: start.into(),
+            terms,
+            first_parent: false,
+            bad: None,
+            good: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Read the state of the bisection in progress from the `.git` directory of `refs` and its references,
+    /// or return `None` if there is no such bisection.
+    pub fn read(refs: &gix_ref::file::Store) -> Result<Option<Self>, read::Error> {
+        let git_dir = refs.git_dir();
+        let Some(start) = read_optional(&git_dir.join(BISECT_START))? else {
+            return Ok(None);
+        };
+        let terms_path = git_dir.join(BISECT_TERMS);
+        let terms = match read_optional(&terms_path)? {
+            None => Terms::default(),
+            Some(content) => {
+                let mut lines = content.lines();
+                match (lines.next(), lines.next()) {
+                    (Some(bad), Some(good)) if !bad.is_empty() && !good.is_empty() => Terms {
+                        bad: bad.into(),
+                        good: good.into(),
+                    },
+                    _ => {
+                        return Err(read::Error::Terms {
+                            content,
+                            path: terms_path,
+                        });
+                    }
+                }
+            }
+        };
+
+        let mut state = State {
+            start: start.trim().into(),
+            first_parent: git_dir.join(BISECT_FIRST_PARENT).is_file(),
+            ..State::new(BString::default(), terms)
+        };
+        let platform = refs.iter()?;
+        for reference in platform.prefixed(REFS_PREFIX.try_into().expect("valid"))? {
+            let reference = reference?;
+            let Some(id) = reference.target.try_id().map(ToOwned::to_owned) else {
+                continue;
+            };
+            let name = &reference.name.as_bstr()[REFS_PREFIX.len()..];
+            if name == state.terms.bad {
+                state.bad = Some(id);
+            } else if is_marked(name, state.terms.good.as_ref()) {
+                state.good.push(id);
+            } else if is_marked(name, SKIP.into()) {
+                state.skipped.push(id);
+            }
+        }
+        Ok(Some(state))
+    }
+
+    /// Write the files describing the bisection into `git_dir`.
+    ///
+    /// Note that the marked commits are stored as references, which are [managed separately](crate::session::mark()).
+    pub fn write(&self, git_dir: &Path) -> Result<(), write::Error> {
+        let mut start = self.start.clone();
+        start.push(b'\n');
+        write_file(&git_dir.join(BISECT_START), &start)?;
+        let mut terms = BString::default();
+        for term in [&self.terms.bad, &self.terms.good] {
+            terms.push_str(term);
+            terms.push(b'\n');
+        }
+        write_file(&git_dir.join(BISECT_TERMS), &terms)?;
+        write_file(&git_dir.join(BISECT_NAMES), b"\n")?;
+        let first_parent = git_dir.join(BISECT_FIRST_PARENT);
+        if self.first_parent {
+            write_file(&first_parent, b"")
+        } else {
+            remove_file_if_present(&first_parent)
+        }
+    }
+
+    /// Remove all files describing the bisection from `git_dir`, including the [bisect log](crate::BISECT_LOG).
+    ///
+    /// Note that the references in `refs/bisect/` are [managed separately](crate::session::reset()).
+    pub fn remove(git_dir: &Path) -> Result<(), write::Error> {
+        for name in [
+            BISECT_START,
+            BISECT_TERMS,
+            BISECT_NAMES,
+            BISECT_FIRST_PARENT,
+            crate::BISECT_LOG,
+        ]
+        .iter()
+        .chain(OTHER_FILES)
+        {
+            remove_file_if_present(&git_dir.join(name))?;
+        }
+        Ok(())
+    }
+}
+
+/// Utilities
+impl State {
+    /// Return `true` if `id` was marked as good or bad, or was skipped.
+    pub fn is_marked(&self, id: &gix_hash::oid) -> bool {
+        self.bad.as_deref() == Some(id)
+            || self
+                .good
+                .iter()
+                .chain(&self.skipped)
+                .any(|marked| marked.as_ref() == id)
+    }
+}
+
+/// Return `true` if `name` is `<term>-<hex>`.
+fn is_marked(name: &BStr, term: &BStr) -> bool {
+    name.strip_prefix(term.as_bytes())
+        .and_then(|rest| rest.strip_prefix(b"-"))
+        .is_some_and(|hex| ObjectId::from_hex(hex).is_ok())
+}
+
+fn read_optional(path: &Path) -> Result<Option<BString>, read::Error> {
+    match std::fs::read(path) {
+        Ok(content) => Ok(Some(content.into())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(read::Error::Io {
+            source,
+            path: path.to_owned(),
+        }),
+    }
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<(), write::Error> {
+    std::fs::write(path, content).map_err(|source| write::Error {
+        source,
+        path: path.to_owned(),
+    })
+}
+
+fn remove_file_if_present(path: &Path) -> Result<(), write::Error> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(write::Error {
+            source: err,
+            path: path.to_owned(),
+        }),
+        _ => Ok(()),
+    }
+}
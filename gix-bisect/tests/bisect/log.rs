@@ -0,0 +1,67 @@
+use gix_bisect::log::{Command, parse};
+
+#[test]
+fn parse_skips_comments_and_unquotes_arguments() -> crate::Result {
+    let commands = parse(
+        b"# bad: [abc] subject\n\
+          git bisect start '--term-old=it'\\''s fast' '--term-new=slow' 'HEAD' 'HEAD~7'\n\
+          # status: waiting for good commit(s), bad commit known\n\
+          \n\
+          git bisect skip abc def\n\
+          git-bisect fast 1234\n",
+    )?;
+    assert_eq!(
+        commands,
+        [
+            Command::Start {
+                args: vec![
+                    "--term-old=it's fast".into(),
+                    "--term-new=slow".into(),
+                    "HEAD".into(),
+                    "HEAD~7".into()
+                ]
+            },
+            Command::Mark {
+                term: "skip".into(),
+                revs: vec!["abc".into(), "def".into()]
+            },
+            Command::Mark {
+                term: "fast".into(),
+                revs: vec!["1234".into()]
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn serialized_commands_can_be_parsed() -> crate::Result {
+    let commands = [
+        Command::Start { args: Vec::new() },
+        Command::Start {
+            args: vec!["--first-parent".into(), "it's".into()],
+        },
+        Command::Mark {
+            term: "good".into(),
+            revs: vec!["abc".into()],
+        },
+    ];
+    let serialized: Vec<_> = commands.iter().map(Command::to_bstring).collect();
+    assert_eq!(
+        serialized,
+        [
+            "git bisect start\n",
+            "git bisect start '--first-parent' 'it'\\''s'\n",
+            "git bisect good abc\n"
+        ]
+    );
+    assert_eq!(parse(&serialized.concat())?, commands);
+    Ok(())
+}
+
+#[test]
+fn parse_errors() {
+    for input in ["git bisect\n", "git commit\n", "git bisect start 'unterminated\n"] {
+        assert!(parse(input.as_bytes()).is_err(), "{input:?}");
+    }
+}
@@ -0,0 +1,67 @@
+use gix_hash::ObjectId;
+use gix_ref::file::ReferenceExt;
+
+pub use gix_testtools::Result;
+
+mod log;
+mod midpoint;
+mod session;
+
+struct Fixture {
+    _tmp: gix_testtools::tempfile::TempDir,
+    odb: gix_odb::Handle,
+    refs: gix_ref::file::Store,
+    workdir: std::path::PathBuf,
+}
+
+impl Fixture {
+    /// Open the repository in the `name` directory of the fixture.
+    fn new(name: &str) -> Result<Self> {
+        let tmp = gix_testtools::scripted_fixture_writable("make_bisect_repo.sh")?;
+        let workdir = tmp.path().join(name);
+        let git_dir = workdir.join(".git");
+        let object_hash = gix_testtools::object_hash();
+        let odb = gix_odb::at_opts(
+            git_dir.join("objects"),
+            None,
+            gix_odb::store::init::Options {
+                object_hash,
+                ..Default::default()
+            },
+        )?;
+        let refs = gix_ref::file::Store::at(
+            git_dir,
+            gix_ref::store::init::Options {
+                write_reflog: gix_ref::store::WriteReflog::Normal,
+                object_hash,
+                ..Default::default()
+            },
+        );
+        Ok(Fixture {
+            _tmp: tmp,
+            odb,
+            refs,
+            workdir,
+        })
+    }
+
+    fn id(&self, name: &str) -> ObjectId {
+        self.refs
+            .find(name)
+            .expect("reference exists")
+            .peel_to_id(&self.refs, &self.odb)
+            .expect("peelable")
+    }
+
+    fn ids(&self, names: &[&str]) -> Vec<ObjectId> {
+        names.iter().map(|name| self.id(name)).collect()
+    }
+
+    fn graph(&self) -> gix_revwalk::Graph<'_, '_, gix_revwalk::graph::Commit<gix_bisect::midpoint::Flags>> {
+        gix_revwalk::Graph::new(&self.odb, None)
+    }
+
+    fn read_log(&self) -> String {
+        std::fs::read_to_string(self.refs.git_dir().join(gix_bisect::BISECT_LOG)).expect("log exists")
+    }
+}
@@ -0,0 +1,128 @@
+use gix_bisect::midpoint::{Error, Outcome, estimate_steps};
+
+use crate::Fixture;
+
+#[test]
+fn linear_history_is_halved() -> crate::Result {
+    let fixture = Fixture::new("linear")?;
+    let mut graph = fixture.graph();
+    let outcome = gix_bisect::midpoint(fixture.id("c10"), &fixture.ids(&["c1"]), &[], false, &mut graph)?;
+    assert_eq!(
+        outcome,
+        Outcome::Next {
+            id: fixture.id("c5"),
+            remaining: 4,
+            steps: 2
+        },
+        "c2 to c10 are candidates, and of c5 and c6 the older one is chosen, just like git does"
+    );
+
+    let outcome = gix_bisect::midpoint(
+        fixture.id("c10"),
+        &fixture.ids(&["c1", "c5"]),
+        &fixture.ids(&["c8"]),
+        false,
+        &mut graph,
+    )?;
+    assert_eq!(
+        outcome,
+        Outcome::Next {
+            id: fixture.id("c7"),
+            remaining: 2,
+            steps: 1
+        },
+        "skipped commits are never chosen, even if they are the midpoint"
+    );
+    Ok(())
+}
+
+#[test]
+fn first_bad_and_only_skipped() -> crate::Result {
+    let fixture = Fixture::new("linear")?;
+    let mut graph = fixture.graph();
+    let outcome = gix_bisect::midpoint(fixture.id("c10"), &fixture.ids(&["c9", "c2"]), &[], false, &mut graph)?;
+    assert_eq!(outcome, Outcome::FirstBad { id: fixture.id("c10") });
+
+    let outcome = gix_bisect::midpoint(
+        fixture.id("c7"),
+        &fixture.ids(&["c4"]),
+        &fixture.ids(&["c5", "c6"]),
+        false,
+        &mut graph,
+    )?;
+    assert_eq!(
+        outcome,
+        Outcome::OnlySkipped {
+            candidates: fixture.ids(&["c7", "c6", "c5"])
+        }
+    );
+
+    assert!(matches!(
+        gix_bisect::midpoint(fixture.id("c3"), &fixture.ids(&["c5"]), &[], false, &mut graph),
+        Err(Error::BadIsGood { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn merges_are_weighted_by_all_reachable_candidates() -> crate::Result {
+    let fixture = Fixture::new("merge")?;
+    let mut graph = fixture.graph();
+    let outcome = gix_bisect::midpoint(fixture.id("c5"), &fixture.ids(&["c1"]), &[], false, &mut graph)?;
+    assert_eq!(
+        outcome,
+        Outcome::Next {
+            id: fixture.id("s2"),
+            remaining: 3,
+            steps: 2
+        },
+        "8 candidates, and s2 reaches c2, c3, s1 and itself"
+    );
+
+    let outcome = gix_bisect::midpoint(fixture.id("c5"), &fixture.ids(&["c1"]), &[], true, &mut graph)?;
+    assert_eq!(
+        outcome,
+        Outcome::Next {
+            id: fixture.id("c3"),
+            remaining: 2,
+            steps: 1
+        },
+        "the side branch isn't considered when following only first parents"
+    );
+
+    let outcome = gix_bisect::midpoint(fixture.id("merge"), &fixture.ids(&["c4", "s2"]), &[], false, &mut graph)?;
+    assert_eq!(
+        outcome,
+        Outcome::Next {
+            id: fixture.id("s3"),
+            remaining: 0,
+            steps: 0
+        }
+    );
+
+    let outcome = gix_bisect::midpoint(
+        fixture.id("c5"),
+        &fixture.ids(&["c1"]),
+        &fixture.ids(&["s1", "s2"]),
+        false,
+        &mut graph,
+    )?;
+    assert_eq!(
+        outcome,
+        Outcome::Next {
+            id: fixture.id("s3"),
+            remaining: 2,
+            steps: 2
+        },
+        "s3 and c4 split the candidates equally well, and the older one is chosen even though it reaches more commits"
+    );
+    Ok(())
+}
+
+#[test]
+fn steps_are_estimated_like_git() {
+    assert_eq!(
+        [1, 2, 3, 4, 5, 6, 7, 8, 9, 16, 24, 1000].map(estimate_steps),
+        [0, 0, 1, 1, 1, 2, 2, 2, 2, 3, 4, 9]
+    );
+}
@@ -0,0 +1,323 @@
+use gix_bisect::{
+    BISECT_EXPECTED_REV, State,
+    midpoint::Outcome,
+    session::{self, Error, Options},
+    state::{Mark, Terms},
+};
+use gix_hash::ObjectId;
+
+use crate::Fixture;
+
+#[test]
+fn start_mark_and_next_interoperate_with_git() -> crate::Result {
+    let fixture = Fixture::new("linear")?;
+    let [c1, c5, c6, c10] = ["c1", "c5", "c6", "c10"].map(|name| fixture.id(name));
+    let state = session::start(
+        "main".into(),
+        Some(c10),
+        &[c1],
+        Default::default(),
+        &fixture.refs,
+        &fixture.odb,
+        None,
+    )?;
+    assert_eq!(state.bad, Some(c10));
+    assert_eq!(
+        fixture.read_log(),
+        format!("# bad: [{c10}] c10\n# good: [{c1}] c1\ngit bisect start '{c10}' '{c1}'\n")
+    );
+    assert!(matches!(
+        session::start(
+            "main".into(),
+            None,
+            &[],
+            Default::default(),
+            &fixture.refs,
+            &fixture.odb,
+            None
+        ),
+        Err(Error::InProgress)
+    ));
+
+    let outcome = session::next(&fixture.refs, &fixture.odb, None, None)?;
+    assert_eq!(
+        outcome,
+        Outcome::Next {
+            id: c5,
+            remaining: 4,
+            steps: 2
+        }
+    );
+    assert_eq!(fixture.id(BISECT_EXPECTED_REV), c5);
+
+    session::mark(Mark::Skip, &[c5], &fixture.refs, &fixture.odb, None)?;
+    assert!(
+        gix_testtools::run_git(&fixture.workdir, &["bisect", "good", &c6.to_string()])?.success(),
+        "git understands our state"
+    );
+    let state = State::read(&fixture.refs)?.expect("in progress");
+    assert_eq!(state.start, "main");
+    assert_eq!(state.terms, Terms::default());
+    assert_eq!(state.bad, Some(c10));
+    assert_eq!(state.skipped, [c5]);
+    let mut good = state.good.clone();
+    good.sort();
+    let mut expected = vec![c1, c6];
+    expected.sort();
+    assert_eq!(good, expected, "and we understand what git writes");
+    assert!(
+        fixture.read_log().ends_with(&format!(
+            "# skip: [{c5}] c5\ngit bisect skip {c5}\n# good: [{c6}] c6\ngit bisect good {c6}\n"
+        )),
+        "both logs look the same"
+    );
+
+    assert_eq!(session::reset(&fixture.refs)?, Some("main".into()));
+    assert!(State::read(&fixture.refs)?.is_none());
+    assert!(
+        fixture
+            .refs
+            .iter()?
+            .prefixed(b"refs/bisect/".try_into()?)?
+            .next()
+            .is_none()
+    );
+    assert!(fixture.refs.try_find(BISECT_EXPECTED_REV)?.is_none());
+    assert!(!fixture.refs.git_dir().join(gix_bisect::BISECT_LOG).exists());
+    assert_eq!(session::reset(&fixture.refs)?, None, "resetting twice is fine");
+    Ok(())
+}
+
+#[test]
+fn custom_terms_and_status_lines() -> crate::Result {
+    let fixture = Fixture::new("linear")?;
+    let [c1, c10] = ["c1", "c10"].map(|name| fixture.id(name));
+    let terms = |bad: &str, good: &str| Options {
+        terms: Terms {
+            bad: bad.into(),
+            good: good.into(),
+        },
+        first_parent: false,
+    };
+    for (bad, good) in [("skip", "fast"), ("slow", "slow"), ("good", "fast"), ("slow", "fa st")] {
+        assert!(
+            matches!(
+                session::start(
+                    "main".into(),
+                    None,
+                    &[],
+                    terms(bad, good),
+                    &fixture.refs,
+                    &fixture.odb,
+                    None
+                ),
+                Err(Error::Terms(_))
+            ),
+            "{bad} and {good} are invalid"
+        );
+    }
+
+    session::start(
+        "main".into(),
+        None,
+        &[],
+        terms("slow", "fast"),
+        &fixture.refs,
+        &fixture.odb,
+        None,
+    )?;
+    assert!(matches!(
+        session::next(&fixture.refs, &fixture.odb, None, None),
+        Err(Error::NeedsBad { .. })
+    ));
+    session::mark(Mark::Bad, &[c10], &fixture.refs, &fixture.odb, None)?;
+    assert!(matches!(
+        session::next(&fixture.refs, &fixture.odb, None, None),
+        Err(Error::NeedsGood { .. })
+    ));
+    let state = session::mark(Mark::Good, &[c1], &fixture.refs, &fixture.odb, None)?;
+    assert_eq!(state, State::read(&fixture.refs)?.expect("in progress"));
+    assert_eq!(
+        fixture.read_log(),
+        format!(
+            "git bisect start '--term-old=fast' '--term-new=slow'\n\
+             # status: waiting for both good and bad commits\n\
+             # slow: [{c10}] c10\n\
+             git bisect slow {c10}\n\
+             # status: waiting for good commit(s), bad commit known\n\
+             # fast: [{c1}] c1\n\
+             git bisect fast {c1}\n"
+        ),
+        "the status always uses the default terms, just like git"
+    );
+    assert_eq!(
+        fixture.refs.find("refs/bisect/slow")?.target.try_id(),
+        Some(c10.as_ref())
+    );
+    assert!(matches!(
+        session::mark(Mark::Bad, &[c1, c10], &fixture.refs, &fixture.odb, None),
+        Err(Error::MultipleBad { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn replay_restores_state_written_by_git() -> crate::Result {
+    let fixture = Fixture::new("in-progress")?;
+    let expected_state = State::read(&fixture.refs)?.expect("git started a bisection");
+    assert_eq!(expected_state.start, "main");
+    assert_eq!(expected_state.skipped, [fixture.id("c5")]);
+    let expected_next = fixture.id(BISECT_EXPECTED_REV);
+
+    let log = std::fs::read(fixture.refs.git_dir().join(gix_bisect::BISECT_LOG))?;
+    let resolve = |rev: &gix_object::bstr::BStr| {
+        ObjectId::from_hex(rev)
+            .ok()
+            .or_else(|| Some(fixture.id(&rev.to_string())))
+    };
+    let state = session::replay(&log, "main".into(), &fixture.refs, &fixture.odb, resolve, None)?;
+    assert_eq!(state, State::read(&fixture.refs)?);
+    assert_eq!(State::read(&fixture.refs)?, Some(expected_state));
+
+    let outcome = session::next(&fixture.refs, &fixture.odb, None, None)?;
+    assert_eq!(
+        outcome,
+        Outcome::Next {
+            id: expected_next,
+            remaining: 1,
+            steps: 1
+        },
+        "we choose the same commit as git did"
+    );
+
+    assert!(matches!(
+        session::replay(
+            b"git bisect good abc\n",
+            "main".into(),
+            &fixture.refs,
+            &fixture.odb,
+            resolve,
+            None
+        ),
+        Err(Error::NotInProgress)
+    ));
+    assert!(matches!(
+        session::replay(
+            b"git bisect start --no-such-option\n",
+            "main".into(),
+            &fixture.refs,
+            &fixture.odb,
+            resolve,
+            None
+        ),
+        Err(Error::UnsupportedOption { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn run_tests_each_commit_with_a_command() -> crate::Result {
+    let fixture = Fixture::new("linear")?;
+    let names: Vec<_> = (1..=10).map(|n| (fixture.id(&format!("c{n}")), n)).collect();
+    let value_file = fixture.workdir.join("value");
+    let checked_out = std::cell::RefCell::new(Vec::new());
+    let mut checkout = |id: &gix_hash::oid| -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let n = names
+            .iter()
+            .find(|(candidate, _)| candidate.as_ref() == id)
+            .expect("known")
+            .1;
+        checked_out.borrow_mut().push(n);
+        std::fs::write(&value_file, format!("{n}\n"))?;
+        Ok(())
+    };
+
+    session::start(
+        "main".into(),
+        Some(fixture.id("c10")),
+        &[fixture.id("c1")],
+        Default::default(),
+        &fixture.refs,
+        &fixture.odb,
+        None,
+    )?;
+    let outcome = session::run(
+        r#"test "$(cat value)" -lt 7"#,
+        &fixture.workdir,
+        &mut checkout,
+        &fixture.refs,
+        &fixture.odb,
+        None,
+        None,
+    )?;
+    let [c6, c7] = ["c6", "c7"].map(|name| fixture.id(name));
+    assert_eq!(outcome, Outcome::FirstBad { id: c7 });
+    assert_eq!(*checked_out.borrow(), [5, 7, 6]);
+    assert!(
+        fixture
+            .read_log()
+            .ends_with(&format!("git bisect good {c6}\n# first bad commit: [{c7}] c7\n")),
+        "the result is logged"
+    );
+
+    session::reset(&fixture.refs)?;
+    session::start(
+        "main".into(),
+        Some(fixture.id("c10")),
+        &[fixture.id("c1")],
+        Default::default(),
+        &fixture.refs,
+        &fixture.odb,
+        None,
+    )?;
+    let outcome = session::run(
+        r#"v=$(cat value); test $v = 6 && exit 125; test $v -lt 7"#,
+        &fixture.workdir,
+        &mut checkout,
+        &fixture.refs,
+        &fixture.odb,
+        None,
+        None,
+    )?;
+    assert_eq!(
+        outcome,
+        Outcome::OnlySkipped {
+            candidates: vec![c7, c6]
+        }
+    );
+    assert!(
+        fixture
+            .read_log()
+            .ends_with(&format!("# only skipped commits left to test\n# possible first bad commit: [{c7}] c7\n# possible first bad commit: [{c6}] c6\n")),
+    );
+
+    session::reset(&fixture.refs)?;
+    session::start(
+        "main".into(),
+        Some(fixture.id("c10")),
+        &[fixture.id("c1")],
+        Default::default(),
+        &fixture.refs,
+        &fixture.odb,
+        None,
+    )?;
+    let log_before = fixture.read_log();
+    for expected_code in [128, 129, 255] {
+        let err = session::run(
+            format!("exit {expected_code}"),
+            &fixture.workdir,
+            &mut checkout,
+            &fixture.refs,
+            &fixture.odb,
+            None,
+            None,
+        )
+        .expect_err("codes of 128 and above abort");
+        assert!(
+            matches!(err, Error::CommandAborted { code, .. } if code == expected_code),
+            "the code is passed on, got {err:?}"
+        );
+    }
+    assert_eq!(fixture.read_log(), log_before, "nothing was marked");
+    Ok(())
+}
@@ -0,0 +1,45 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+tick=0
+function commit() {
+  local file=$1 content=$2 name=$3
+  tick=$((tick + 1))
+  echo "$content" > "$file"
+  git add "$file"
+  GIT_COMMITTER_DATE="2000-01-02 00:00:$(printf %02d $tick) +0000" git commit -q -m "$name"
+  git tag "$name"
+}
+
+function linear_history() {
+  for n in $(seq 1 10); do
+    commit value "$n" "c$n"
+  done
+}
+
+(git init -q linear && cd linear
+  linear_history
+)
+
+(git init -q merge && cd merge
+  for n in 1 2 3; do
+    commit value "$n" "c$n"
+  done
+  git checkout -q -b side
+  commit side 1 s1
+  commit side 2 s2
+  commit side 3 s3
+  git checkout -q main
+  commit value 4 c4
+  tick=$((tick + 1))
+  GIT_COMMITTER_DATE="2000-01-02 00:00:$(printf %02d $tick) +0000" git merge -q --no-ff side -m merge
+  git tag merge
+  commit value 5 c5
+)
+
+(git init -q in-progress && cd in-progress
+  linear_history
+  git bisect start c10 c1 >/dev/null
+  git bisect skip >/dev/null
+  git bisect good >/dev/null
+)
@@ -0,0 +1,219 @@
+use gix_hash::ObjectId;
+
+use crate::Header;
+
+/// The error returned by [`create()`](crate::create()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    FindExisting(#[from] gix_object::find::existing::Error),
+    #[error(transparent)]
+    FindExistingObject(#[from] gix_object::find::existing_object::Error),
+    #[error("Could not decode the object {id}")]
+    Decode {
+        id: ObjectId,
+        source: gix_object::decode::Error,
+    },
+    #[error(transparent)]
+    Walk(#[from] gix_traverse::commit::simple::Error),
+    #[error("The bundle would be empty as all references are reachable from the excluded commits")]
+    Empty,
+    #[error("Could not generate the pack of the bundle")]
+    Pack(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("Operation interrupted")]
+    Interrupted,
+}
+
+/// Options for use in [`create()`](crate::create()).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    /// The kind of hash used by the object database, which is also used by the bundle.
+    pub object_hash: gix_hash::Kind,
+    /// The amount of threads to use when creating pack entries, or `None` to use all logical cores.
+    pub thread_limit: Option<usize>,
+}
+
+/// The outcome of [`create()`](crate::create()).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The header that was written, with the references that made it into the bundle.
+    pub header: Header,
+    /// The amount of objects in the pack of the bundle.
+    pub num_objects: usize,
+}
+
+pub(super) mod function {
+    use std::{
+        collections::HashSet,
+        io::Write,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use gix_features::progress::{Count, DynNestedProgress, Progress};
+    use gix_hash::ObjectId;
+    use gix_object::FindExt;
+    use gix_pack::data::output;
+
+    use super::{Error, Options, Outcome};
+    use crate::{Header, Prerequisite, Ref, Version};
+
+    /// Write a bundle to `out` which provides `refs` along with all objects reachable from them in `objects`, except
+    /// for the ones reachable from the `exclude`d commits, while providing `progress` and checking `should_interrupt`
+    /// to abort early.
+    ///
+    /// Parents of the bundled commits which are excluded become [prerequisites](Header::prerequisites) of the bundle.
+    /// References that are reachable from the excluded commits are omitted, and it's an error if no reference remains.
+    /// This is similar to `git bundle create <file> <refs>… ^<exclude>…`.
+    ///
+    /// The pack isn't thin, so deltas are only made against objects in the bundle.
+    pub fn create<Find>(
+        objects: Find,
+        refs: &[Ref],
+        exclude: &[ObjectId],
+        out: &mut dyn Write,
+        progress: &mut dyn DynNestedProgress,
+        should_interrupt: &AtomicBool,
+        Options {
+            object_hash,
+            thread_limit,
+        }: Options,
+    ) -> Result<Outcome, Error>
+    where
+        Find: gix_pack::Find + gix_object::Find + Clone + Send + 'static,
+    {
+        use output::count::objects::ObjectExpansion::{AsIs, TreeAdditionsComparedToAncestor, TreeContents};
+        let _span = gix_features::trace::coarse!("gix_bundle::create()");
+        let mut buf = Vec::new();
+
+        let mut peeled_refs = Vec::with_capacity(refs.len());
+        for r in refs {
+            let mut tags = Vec::new();
+            let mut id = r.id;
+            let kind = loop {
+                let object = objects.find(&id, &mut buf)?;
+                if object.kind != gix_object::Kind::Tag {
+                    break object.kind;
+                }
+                tags.push(id);
+                id = gix_object::TagRefIter::from_bytes(object.data, object_hash)
+                    .target_id()
+                    .map_err(|source| Error::Decode { id, source })?;
+            };
+            peeled_refs.push((r, tags, id, kind));
+        }
+
+        let tips = peeled_refs
+            .iter()
+            .filter(|(_, _, _, kind)| *kind == gix_object::Kind::Commit)
+            .map(|(_, _, id, _)| *id);
+        let mut commits = Vec::new();
+        let mut parents = Vec::new();
+        for info in gix_traverse::commit::Simple::new(tips, &objects).hide(exclude.iter().copied())? {
+            let info = info?;
+            parents.extend(info.parent_ids.iter().copied());
+            commits.push(info.id);
+        }
+        let sent: HashSet<_> = commits.iter().copied().collect();
+
+        let mut header = Header {
+            // Only SHA-1 can be used with the v2 format, as it has no way to declare the object format.
+            version: if object_hash.len_in_bytes() == 20 {
+                Version::V2
+            } else {
+                Version::V3
+            },
+            object_hash,
+            filter: None,
+            prerequisites: Vec::new(),
+            refs: Vec::new(),
+        };
+        let mut tags = Vec::new();
+        let mut trees_and_blobs = Vec::new();
+        for (r, ref_tags, id, kind) in peeled_refs {
+            if kind == gix_object::Kind::Commit && !sent.contains(&id) {
+                continue;
+            }
+            if kind != gix_object::Kind::Commit {
+                trees_and_blobs.push(id);
+            }
+            tags.extend(ref_tags);
+            header.refs.push(r.clone());
+        }
+        if header.refs.is_empty() {
+            return Err(Error::Empty);
+        }
+
+        let mut edges = HashSet::new();
+        for parent in parents {
+            if sent.contains(&parent) || edges.contains(&parent) {
+                continue;
+            }
+            let commit = objects.find_commit(&parent, &mut buf)?;
+            header.prerequisites.push(Prerequisite {
+                id: parent,
+                comment: commit.message_summary().into_owned(),
+            });
+            edges.insert(commit.tree());
+            edges.insert(parent);
+        }
+
+        let mut counting = progress.add_child("counting".into());
+        counting.init(None, gix_features::progress::count("objects"));
+        let count = |ids: &[ObjectId], expansion| -> Result<Vec<output::Count>, Error> {
+            if ids.is_empty() {
+                return Ok(Vec::new());
+            }
+            Ok(output::count::objects_unthreaded(
+                &objects,
+                &mut ids.iter().copied().map(Ok),
+                &counting,
+                should_interrupt,
+                expansion,
+            )
+            .map_err(|err| Error::Pack(err.into()))?
+            .0)
+        };
+        let mut counts = count(&commits, TreeAdditionsComparedToAncestor)?;
+        // Counting the changes to a parent also yields the parent and its tree, which the receiver already has.
+        counts.retain(|count| !edges.contains(&count.id));
+        counts.extend(count(&trees_and_blobs, TreeContents)?);
+        counts.extend(count(&tags, AsIs)?);
+        let mut seen = HashSet::new();
+        counts.retain(|count| seen.insert(count.id));
+        drop(counting);
+
+        header.write_to(out)?;
+        let num_objects = counts.len();
+        let entries = gix_features::parallel::InOrderIter::from(output::entry::iter_from_counts(
+            counts,
+            objects,
+            Box::new(progress.add_child("creating entries".into())),
+            output::entry::iter_from_counts::Options {
+                thread_limit,
+                mode: output::entry::iter_from_counts::Mode::PackCopyAndBaseObjects,
+                allow_thin_pack: false,
+                chunk_size: 1000,
+                version: Default::default(),
+            },
+        ));
+        let mut write_progress = progress.add_child("writing".into());
+        write_progress.init(None, gix_features::progress::bytes());
+        for written in output::bytes::FromEntriesIter::new(
+            entries,
+            &mut *out,
+            num_objects as u32,
+            gix_pack::data::Version::V2,
+            object_hash,
+        ) {
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            write_progress.inc_by(written.map_err(|err| Error::Pack(err.into()))? as usize);
+        }
+        out.flush()?;
+        Ok(Outcome { header, num_objects })
+    }
+}
@@ -0,0 +1,138 @@
+use std::io::BufRead;
+
+use bstr::{BString, ByteSlice};
+use gix_hash::ObjectId;
+
+use crate::{Header, Prerequisite, Ref, Version};
+
+/// The error returned by [`Header::from_read()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read the bundle header")]
+    Io(#[from] std::io::Error),
+    #[error("Expected a bundle signature like '# v2 git bundle', got {line:?}")]
+    Signature { line: BString },
+    #[error("The bundle header ended before the empty line separating it from the pack")]
+    UnexpectedEof,
+    #[error("The bundle requires the unknown capability {name:?}")]
+    UnknownCapability { name: BString },
+    #[error("The object format {format:?} of the bundle is unsupported")]
+    UnsupportedObjectFormat { format: BString },
+    #[error("The line {line:?} isn't a valid prerequisite or reference")]
+    Malformed { line: BString },
+    #[error("The object id in line {line:?} doesn't match the object format {object_hash} of the bundle")]
+    ObjectId { line: BString, object_hash: gix_hash::Kind },
+    #[error("The reference name in line {line:?} is invalid")]
+    RefName {
+        line: BString,
+        source: gix_validate::reference::name::Error,
+    },
+}
+
+/// Lifecycle
+impl Header {
+    /// Read a bundle header from `read`, and return it along with the amount of bytes it took, which is where the pack starts.
+    ///
+    /// `read` is left positioned at the beginning of the pack.
+    pub fn from_read(read: &mut dyn BufRead) -> Result<(Self, u64), Error> {
+        let mut buf = Vec::new();
+        let mut consumed = read_line(read, &mut buf)?;
+        let version = match buf.as_slice() {
+            b"# v2 git bundle" => Version::V2,
+            b"# v3 git bundle" => Version::V3,
+            _ => {
+                return Err(Error::Signature {
+                    line: buf.as_bstr().into(),
+                });
+            }
+        };
+
+        let mut object_format = None;
+        let mut filter = None;
+        let mut num_read = read_line(read, &mut buf)?;
+        if version == Version::V3 {
+            while let Some(capability) = buf.strip_prefix(b"@") {
+                let (name, value) = capability.split_once_str(b"=").unwrap_or((capability, b""));
+                match name {
+                    b"object-format" => object_format = Some(parse_object_format(value)?),
+                    b"filter" => filter = Some(value.into()),
+                    _ => return Err(Error::UnknownCapability { name: name.into() }),
+                }
+                consumed += num_read;
+                num_read = read_line(read, &mut buf)?;
+            }
+        }
+
+        let mut header = Header {
+            version,
+            object_hash: match object_format {
+                Some(object_hash) => object_hash,
+                None => parse_object_format(b"sha1")?,
+            },
+            filter,
+            prerequisites: Vec::new(),
+            refs: Vec::new(),
+        };
+        loop {
+            if num_read == 0 {
+                return Err(Error::UnexpectedEof);
+            }
+            consumed += num_read;
+            let line = buf.as_slice();
+            if line.is_empty() {
+                break;
+            }
+
+            let malformed = || Error::Malformed { line: line.into() };
+            let (is_prerequisite, line_without_prefix) = match line.strip_prefix(b"-") {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let (hex, rest) = line_without_prefix
+                .split_once_str(b" ")
+                .unwrap_or((line_without_prefix, b""));
+            let id = ObjectId::from_hex(hex).map_err(|_| malformed())?;
+            if id.kind() != header.object_hash {
+                return Err(Error::ObjectId {
+                    line: line.into(),
+                    object_hash: header.object_hash,
+                });
+            }
+            if is_prerequisite {
+                header.prerequisites.push(Prerequisite {
+                    id,
+                    comment: rest.into(),
+                });
+            } else {
+                if rest.is_empty() {
+                    return Err(malformed());
+                }
+                let name = gix_ref::FullName::try_from(rest.as_bstr()).map_err(|source| Error::RefName {
+                    line: line.into(),
+                    source,
+                })?;
+                header.refs.push(Ref { name, id });
+            }
+            num_read = read_line(read, &mut buf)?;
+        }
+        Ok((header, consumed))
+    }
+}
+
+/// Read a line into `buf` without its terminator and return the amount of bytes read, or 0 at the end of the input.
+fn read_line(read: &mut dyn BufRead, buf: &mut Vec<u8>) -> std::io::Result<u64> {
+    buf.clear();
+    let num_read = read.read_until(b'\n', buf)?;
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+    }
+    Ok(num_read as u64)
+}
+
+fn parse_object_format(name: &[u8]) -> Result<gix_hash::Kind, Error> {
+    name.to_str()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .ok_or_else(|| Error::UnsupportedObjectFormat { format: name.into() })
+}
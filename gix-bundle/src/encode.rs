@@ -0,0 +1,39 @@
+use std::io::Write;
+
+use crate::{Header, Version};
+
+/// Serialization
+impl Header {
+    /// Write this header in the format of its [version](Header::version) to `out`, including the empty line that
+    /// separates it from the pack.
+    ///
+    /// Note that `v2` headers can't declare their object format or filter, so these are only written for `v3` headers.
+    pub fn write_to(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        match self.version {
+            Version::V2 => out.write_all(b"# v2 git bundle\n")?,
+            Version::V3 => {
+                out.write_all(b"# v3 git bundle\n")?;
+                writeln!(out, "@object-format={}", self.object_hash)?;
+                if let Some(filter) = &self.filter {
+                    out.write_all(b"@filter=")?;
+                    out.write_all(filter)?;
+                    out.write_all(b"\n")?;
+                }
+            }
+        }
+        for prerequisite in &self.prerequisites {
+            write!(out, "-{}", prerequisite.id)?;
+            if !prerequisite.comment.is_empty() {
+                out.write_all(b" ")?;
+                out.write_all(&prerequisite.comment)?;
+            }
+            out.write_all(b"\n")?;
+        }
+        for r in &self.refs {
+            write!(out, "{} ", r.id)?;
+            out.write_all(r.name.as_bstr())?;
+            out.write_all(b"\n")?;
+        }
+        out.write_all(b"\n")
+    }
+}
@@ -0,0 +1,88 @@
+//! Read and write git bundles, files that carry references along with a pack of the objects they need, like
+//! the ones produced by `git bundle create`.
+//!
+//! A bundle starts with a [`Header`] listing the commits the receiving repository must already have, its
+//! [prerequisites](Header::prerequisites), as well as the [references](Header::refs) it provides. The pack follows
+//! right after it.
+//!
+//! * [`Header::from_read()`] and [`Header::write_to()`] decode and encode headers of `v2` and `v3` bundles.
+//! * [`File::at()`] opens a bundle on disk, and [`File::write_pack_to_directory()`] indexes its pack into an object database,
+//!   which should only be done once [`Header::verify_prerequisites()`] succeeded.
+//! * [`create()`] writes a bundle with the objects reachable from a set of references, excluding what's reachable from
+//!   the commits the receiver is known to have.
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use bstr::BString;
+use gix_hash::ObjectId;
+
+/// The version of the bundle format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum Version {
+    /// The original format, which only supports SHA-1.
+    V2,
+    /// The format that adds capabilities to the header, like the object format to use.
+    V3,
+}
+
+/// A commit the repository receiving a bundle must have, as its objects aren't contained in the bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub struct Prerequisite {
+    /// The id of the commit.
+    pub id: ObjectId,
+    /// A comment for human consumption, typically the subject line of the commit, which may be empty.
+    pub comment: BString,
+}
+
+/// A reference provided by a bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub struct Ref {
+    /// The full name of the reference, like `refs/heads/main` or `HEAD`.
+    pub name: gix_ref::FullName,
+    /// The object the reference points to, which may be an annotated tag.
+    pub id: ObjectId,
+}
+
+/// The header of a bundle, which precedes the pack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// The version of the format.
+    pub version: Version,
+    /// The kind of hash used for all object ids in the bundle, as declared by the `@object-format` capability
+    /// of `v3` bundles, or SHA-1 for `v2` bundles.
+    pub object_hash: gix_hash::Kind,
+    /// The object filter used to produce the pack as declared by the `@filter` capability, which makes it a partial bundle.
+    pub filter: Option<BString>,
+    /// The commits the receiving repository must have.
+    pub prerequisites: Vec<Prerequisite>,
+    /// The references to provide to the receiving repository.
+    pub refs: Vec<Ref>,
+}
+
+/// A bundle on disk, along with its parsed header.
+#[derive(Debug, Clone)]
+pub struct File {
+    /// The path to the bundle.
+    pub path: PathBuf,
+    /// The header of the bundle.
+    pub header: Header,
+    /// The offset in bytes at which the pack starts.
+    pub pack_offset: u64,
+}
+
+///
+pub mod decode;
+///
+pub mod encode;
+///
+pub mod open;
+///
+pub mod unpack;
+///
+pub mod verify;
+
+///
+pub mod create;
+pub use create::function::create;
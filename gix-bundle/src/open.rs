@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use crate::{File, Header, decode};
+
+/// The error returned by [`File::at()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open the bundle at {path:?}")]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Could not decode the header of the bundle at {path:?}")]
+    Decode {
+        path: std::path::PathBuf,
+        source: decode::Error,
+    },
+}
+
+/// Lifecycle
+impl File {
+    /// Open the bundle at `path` and read its header.
+    pub fn at(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path).map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        let (header, pack_offset) =
+            Header::from_read(&mut std::io::BufReader::new(file)).map_err(|source| Error::Decode {
+                path: path.to_owned(),
+                source,
+            })?;
+        Ok(File {
+            path: path.to_owned(),
+            header,
+            pack_offset,
+        })
+    }
+
+    /// Return `true` if the file at `path` starts with the signature of a bundle.
+    ///
+    /// This is useful to tell bundles apart from other files without parsing their header.
+    pub fn is_bundle(path: &Path) -> bool {
+        use std::io::Read;
+        let mut signature = [0; 16];
+        std::fs::File::open(path)
+            .and_then(|mut file| file.read_exact(&mut signature))
+            .is_ok_and(|()| matches!(&signature, b"# v2 git bundle\n" | b"# v3 git bundle\n"))
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    io::{BufReader, Seek, SeekFrom},
+    path::Path,
+    sync::atomic::AtomicBool,
+};
+
+use gix_features::progress::DynNestedProgress;
+
+use crate::File;
+
+/// The error returned by [`File::write_pack_to_directory()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open the bundle at {path:?} to read its pack")]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    WritePack(#[from] gix_pack::bundle::write::Error),
+}
+
+/// Options for use in [`File::write_pack_to_directory()`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    /// The amount of threads to use when indexing the pack, or `None` to use all logical cores.
+    pub thread_limit: Option<usize>,
+    /// The version of the pack index to write.
+    pub index_version: gix_pack::index::Version,
+}
+
+impl File {
+    /// Write the pack of this bundle along with an index into `directory`, typically the `pack` directory of an object database,
+    /// while providing `progress` and checking `should_interrupt` to abort early.
+    ///
+    /// `objects` is used to look up the base objects of deltas that aren't contained in the pack, as bundles
+    /// typically contain thin packs. Thus, it should only be called once [prerequisites were verified](crate::Header::verify_prerequisites()).
+    ///
+    /// Note that a `.keep` file is written along with the pack, which should be removed once references point to its objects.
+    pub fn write_pack_to_directory(
+        &self,
+        directory: &Path,
+        progress: &mut dyn DynNestedProgress,
+        should_interrupt: &AtomicBool,
+        objects: impl gix_object::Find,
+        Options {
+            thread_limit,
+            index_version,
+        }: Options,
+    ) -> Result<gix_pack::bundle::write::Outcome, Error> {
+        let io_err = |source| Error::Io {
+            path: self.path.clone(),
+            source,
+        };
+        let mut file = std::fs::File::open(&self.path).map_err(io_err)?;
+        file.seek(SeekFrom::Start(self.pack_offset)).map_err(io_err)?;
+        Ok(gix_pack::Bundle::write_to_directory(
+            &mut BufReader::with_capacity(64 * 1024, file),
+            Some(directory),
+            progress,
+            should_interrupt,
+            Some(objects),
+            gix_pack::bundle::write::Options {
+                thread_limit,
+                iteration_mode: gix_pack::data::input::Mode::Verify,
+                index_version,
+                object_hash: self.header.object_hash,
+            },
+        )?)
+    }
+}
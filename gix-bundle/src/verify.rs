@@ -0,0 +1,36 @@
+use gix_hash::ObjectId;
+
+use crate::Header;
+
+/// The error returned by [`Header::verify_prerequisites()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Find(#[from] gix_object::find::Error),
+    #[error("The repository lacks {} prerequisite commit(s) of the bundle: {}", ids.len(), ids.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    MissingPrerequisites { ids: Vec<ObjectId> },
+}
+
+impl Header {
+    /// Assure that all [prerequisites](Header::prerequisites) of the bundle are commits in `objects`, which is required
+    /// to be able to use the objects of its pack.
+    ///
+    /// Note that it's not checked whether the history of the prerequisites is complete.
+    pub fn verify_prerequisites(&self, objects: &dyn gix_object::FindHeader) -> Result<(), Error> {
+        let mut missing = Vec::new();
+        for prerequisite in &self.prerequisites {
+            let is_commit = objects
+                .try_header(&prerequisite.id)?
+                .is_some_and(|header| header.kind == gix_object::Kind::Commit);
+            if !is_commit {
+                missing.push(prerequisite.id);
+            }
+        }
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::MissingPrerequisites { ids: missing })
+        }
+    }
+}
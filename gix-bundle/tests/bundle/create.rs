@@ -0,0 +1,139 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_bundle::{File, Ref, Version, create};
+
+use crate::{fixture, odb, rev_parse};
+
+fn bundle_ref(name: &str, id: gix_hash::ObjectId) -> Ref {
+    Ref {
+        name: name.try_into().expect("valid"),
+        id,
+    }
+}
+
+fn options() -> create::Options {
+    create::Options {
+        object_hash: gix_testtools::object_hash(),
+        thread_limit: Some(1),
+    }
+}
+
+#[test]
+fn incremental_bundle_can_be_unbundled_by_git() -> crate::Result {
+    let dir = gix_testtools::scripted_fixture_writable("make_bundle_repo.sh")?;
+    let base = dir.path().join("base");
+    let main = rev_parse(&base, "main")?;
+    let c1 = rev_parse(&base, "v1^{commit}")?;
+
+    let mut out = Vec::new();
+    let outcome = gix_bundle::create(
+        odb(&base.join(".git").join("objects"))?,
+        &[bundle_ref("refs/heads/main", main)],
+        &[c1],
+        &mut out,
+        &mut gix_features::progress::Discard,
+        &AtomicBool::default(),
+        options(),
+    )?;
+    assert_eq!(outcome.num_objects, 7, "2 commits, 3 trees and 2 blobs");
+    let expected_version = if gix_testtools::object_hash().len_in_bytes() == 20 {
+        Version::V2
+    } else {
+        Version::V3
+    };
+    assert_eq!(outcome.header.version, expected_version);
+    assert_eq!(
+        outcome.header,
+        File::at(dir.path().join("incremental.bundle"))?.header,
+        "the header matches the one git wrote"
+    );
+
+    let bundle_path = dir.path().join("created.bundle");
+    std::fs::write(&bundle_path, &out)?;
+    let partial = dir.path().join("partial.git");
+    gix_testtools::git(&partial, "bundle verify ../created.bundle")?;
+    gix_testtools::git(&partial, "bundle unbundle ../created.bundle")?;
+    gix_testtools::git(&partial, &format!("update-ref refs/heads/main {main}"))?;
+    gix_testtools::git(&partial, "fsck --connectivity-only")?;
+    Ok(())
+}
+
+#[test]
+fn full_bundle_with_tags_round_trips() -> crate::Result {
+    let dir = fixture()?;
+    let base = dir.join("base");
+    let refs = [
+        bundle_ref("refs/heads/main", rev_parse(&base, "main")?),
+        bundle_ref("refs/tags/v1", rev_parse(&base, "refs/tags/v1")?),
+    ];
+
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let bundle_path = tmp.path().join("created.bundle");
+    let mut out = std::fs::File::create(&bundle_path)?;
+    let outcome = gix_bundle::create(
+        odb(&base.join(".git").join("objects"))?,
+        &refs,
+        &[],
+        &mut out,
+        &mut gix_features::progress::Discard,
+        &AtomicBool::default(),
+        options(),
+    )?;
+    assert_eq!(outcome.num_objects, 11, "everything, including the annotated tag");
+    assert!(outcome.header.prerequisites.is_empty());
+
+    let file = File::at(&bundle_path)?;
+    assert_eq!(file.header, outcome.header);
+    let objects_dir = tmp.path().join("objects");
+    std::fs::create_dir_all(objects_dir.join("pack"))?;
+    let written = file.write_pack_to_directory(
+        &objects_dir.join("pack"),
+        &mut gix_features::progress::Discard,
+        &AtomicBool::default(),
+        odb(&objects_dir)?,
+        Default::default(),
+    )?;
+    assert_eq!(written.index.num_objects, 11);
+    Ok(())
+}
+
+#[test]
+fn refs_reachable_from_excluded_commits_are_omitted() -> crate::Result {
+    let dir = fixture()?;
+    let base = dir.join("base");
+    let main = rev_parse(&base, "main")?;
+    let topic = rev_parse(&base, "topic")?;
+    let objects = odb(&base.join(".git").join("objects"))?;
+
+    let mut out = Vec::new();
+    let outcome = gix_bundle::create(
+        objects.clone(),
+        &[
+            bundle_ref("refs/heads/main", main),
+            bundle_ref("refs/heads/topic", topic),
+        ],
+        &[topic],
+        &mut out,
+        &mut gix_features::progress::Discard,
+        &AtomicBool::default(),
+        options(),
+    )?;
+    assert_eq!(outcome.header.refs, [bundle_ref("refs/heads/main", main)]);
+    assert_eq!(outcome.header.prerequisites.len(), 1);
+    assert_eq!(outcome.header.prerequisites[0].id, topic);
+    assert_eq!(outcome.header.prerequisites[0].comment, "c2");
+    assert_eq!(outcome.num_objects, 4, "a commit, 2 trees and a blob");
+
+    let err = gix_bundle::create(
+        objects,
+        &[bundle_ref("refs/heads/topic", topic)],
+        &[main],
+        &mut Vec::new(),
+        &mut gix_features::progress::Discard,
+        &AtomicBool::default(),
+        options(),
+    )
+    .expect_err("nothing to bundle");
+    assert!(matches!(err, create::Error::Empty), "{err:?}");
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_bundle::{File, verify};
+use gix_object::FindExt;
+
+use crate::{fixture, odb, rev_parse};
+
+#[test]
+fn at() -> crate::Result {
+    let dir = fixture()?;
+    let file = File::at(dir.join("all.bundle"))?;
+    assert_eq!(file.header.refs.len(), 4);
+    assert_eq!(&std::fs::read(&file.path)?[file.pack_offset as usize..][..4], b"PACK");
+
+    assert!(File::is_bundle(&dir.join("v3.bundle")));
+    assert!(!File::is_bundle(&dir.join("base").join("a")), "not a bundle");
+    assert!(!File::is_bundle(&dir.join("does-not-exist")));
+    assert!(File::at(dir.join("base").join("a")).is_err());
+    Ok(())
+}
+
+#[test]
+fn verify_prerequisites() -> crate::Result {
+    let dir = fixture()?;
+    let file = File::at(dir.join("incremental.bundle"))?;
+    file.header
+        .verify_prerequisites(&odb(&dir.join("partial.git").join("objects"))?)?;
+    file.header
+        .verify_prerequisites(&odb(&dir.join("base").join(".git").join("objects"))?)?;
+
+    let empty = gix_testtools::tempfile::TempDir::new()?;
+    let err = file
+        .header
+        .verify_prerequisites(&odb(empty.path())?)
+        .expect_err("nothing is there");
+    let verify::Error::MissingPrerequisites { ids } = err else {
+        panic!("unexpected error: {err:?}")
+    };
+    assert_eq!(ids, [rev_parse(&dir.join("base"), "v1^{commit}")?]);
+
+    File::at(dir.join("all.bundle"))?
+        .header
+        .verify_prerequisites(&odb(empty.path())?)
+        .expect("there is nothing to verify without prerequisites");
+    Ok(())
+}
+
+#[test]
+fn write_pack_to_directory_with_all_objects() -> crate::Result {
+    let dir = fixture()?;
+    let file = File::at(dir.join("all.bundle"))?;
+    let objects_dir = gix_testtools::tempfile::TempDir::new()?;
+    let pack_dir = objects_dir.path().join("pack");
+    std::fs::create_dir(&pack_dir)?;
+    let outcome = file.write_pack_to_directory(
+        &pack_dir,
+        &mut gix_features::progress::Discard,
+        &AtomicBool::default(),
+        odb(objects_dir.path())?,
+        Default::default(),
+    )?;
+    assert_eq!(outcome.index.num_objects, 11, "3 commits, 4 trees, 3 blobs and a tag");
+    assert!(outcome.keep_path.is_some_and(|path| path.is_file()));
+
+    let objects = odb(objects_dir.path())?;
+    let mut buf = Vec::new();
+    for r in &file.header.refs {
+        objects.find(&r.id, &mut buf)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn write_pack_to_directory_with_prerequisites() -> crate::Result {
+    let dir = gix_testtools::scripted_fixture_writable("make_bundle_repo.sh")?;
+    let file = File::at(dir.path().join("incremental.bundle"))?;
+    let objects_dir = dir.path().join("partial.git").join("objects");
+    let objects = odb(&objects_dir)?;
+    file.header.verify_prerequisites(&objects)?;
+
+    let outcome = file.write_pack_to_directory(
+        &objects_dir.join("pack"),
+        &mut gix_features::progress::Discard,
+        &AtomicBool::default(),
+        objects,
+        Default::default(),
+    )?;
+    assert_eq!(outcome.index.num_objects, 7, "2 commits, 3 trees and 2 blobs");
+
+    let main = rev_parse(&dir.path().join("base"), "main")?;
+    gix_testtools::git(
+        dir.path().join("partial.git"),
+        &format!("update-ref refs/heads/main {main}"),
+    )?;
+    gix_testtools::git(dir.path().join("partial.git"), "fsck --connectivity-only")?;
+    Ok(())
+}
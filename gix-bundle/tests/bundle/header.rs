@@ -0,0 +1,115 @@
+use gix_bundle::{Header, Version, decode};
+
+use crate::{fixture, rev_parse};
+
+fn header_of(name: &str) -> crate::Result<(Header, Vec<u8>)> {
+    let data = std::fs::read(fixture()?.join(name))?;
+    let (header, pack_offset) = Header::from_read(&mut data.as_slice())?;
+    assert_eq!(
+        &data[pack_offset as usize..][..4],
+        b"PACK",
+        "the pack follows right after the header"
+    );
+    Ok((header, data[..pack_offset as usize].to_vec()))
+}
+
+#[test]
+fn decode_all_refs() -> crate::Result {
+    let (header, raw) = header_of("all.bundle")?;
+    let base = fixture()?.join("base");
+    assert_eq!(header.object_hash, gix_testtools::object_hash());
+    assert!(header.prerequisites.is_empty());
+    assert_eq!(header.filter, None);
+    let refs: Vec<_> = header
+        .refs
+        .iter()
+        .map(|r| (r.name.as_bstr().to_string(), r.id))
+        .collect();
+    assert_eq!(
+        refs,
+        [
+            ("refs/heads/main".into(), rev_parse(&base, "main")?),
+            ("refs/heads/topic".into(), rev_parse(&base, "topic")?),
+            ("refs/tags/v1".into(), rev_parse(&base, "refs/tags/v1")?),
+            ("HEAD".into(), rev_parse(&base, "HEAD")?),
+        ]
+    );
+
+    let mut out = Vec::new();
+    header.write_to(&mut out)?;
+    assert_eq!(out, raw, "encoding yields exactly what git wrote");
+    Ok(())
+}
+
+#[test]
+fn decode_prerequisites() -> crate::Result {
+    let (header, raw) = header_of("incremental.bundle")?;
+    let base = fixture()?.join("base");
+    assert_eq!(header.prerequisites.len(), 1);
+    assert_eq!(header.prerequisites[0].id, rev_parse(&base, "v1^{commit}")?);
+    assert_eq!(header.prerequisites[0].comment, "c1");
+    assert_eq!(header.refs.len(), 1);
+    assert_eq!(header.refs[0].name.as_bstr(), "refs/heads/main");
+
+    let mut out = Vec::new();
+    header.write_to(&mut out)?;
+    assert_eq!(out, raw);
+    Ok(())
+}
+
+#[test]
+fn decode_v3() -> crate::Result {
+    let (header, raw) = header_of("v3.bundle")?;
+    assert_eq!(header.version, Version::V3);
+    assert_eq!(header.object_hash, gix_testtools::object_hash());
+    assert_eq!(header.refs.len(), 1);
+
+    let mut out = Vec::new();
+    header.write_to(&mut out)?;
+    assert_eq!(out, raw);
+    Ok(())
+}
+
+#[test]
+fn decode_v3_filter() -> crate::Result {
+    let hex = gix_testtools::object_hash().null().to_string();
+    let data = format!(
+        "# v3 git bundle\n@object-format={}\n@filter=blob:none\n{hex} refs/heads/main\n\nPACK",
+        gix_testtools::object_hash()
+    );
+    let (header, pack_offset) = Header::from_read(&mut data.as_bytes())?;
+    assert_eq!(header.filter, Some("blob:none".into()));
+    assert_eq!(&data[pack_offset as usize..], "PACK");
+    Ok(())
+}
+
+#[test]
+fn decode_errors() {
+    let hex = gix_testtools::object_hash().null().to_string();
+    let v3 = format!("# v3 git bundle\n@object-format={}\n", gix_testtools::object_hash());
+    for (input, expected) in [
+        ("# v4 git bundle\n\n".to_string(), "Signature"),
+        ("# v3 git bundle\n@unknown\n\n".into(), "UnknownCapability"),
+        (
+            "# v3 git bundle\n@object-format=md5\n\n".into(),
+            "UnsupportedObjectFormat",
+        ),
+        (format!("{v3}{hex} refs/heads/main\n"), "UnexpectedEof"),
+        (format!("{v3}{hex}\n\n"), "Malformed"),
+        ("# v2 git bundle\n-abc comment\n\n".into(), "Malformed"),
+        (format!("{v3}{hex} refs/heads/a..b\n\n"), "RefName"),
+        ("# v2 git bundle\n@object-format=sha1\n\n".into(), "Malformed"),
+    ] {
+        let err = Header::from_read(&mut input.as_bytes()).expect_err("invalid input");
+        let name = match err {
+            decode::Error::Signature { .. } => "Signature",
+            decode::Error::UnknownCapability { .. } => "UnknownCapability",
+            decode::Error::UnsupportedObjectFormat { .. } => "UnsupportedObjectFormat",
+            decode::Error::UnexpectedEof => "UnexpectedEof",
+            decode::Error::Malformed { .. } => "Malformed",
+            decode::Error::RefName { .. } => "RefName",
+            decode::Error::ObjectId { .. } | decode::Error::Io(_) => "other",
+        };
+        assert_eq!(name, expected, "{input:?}");
+    }
+}
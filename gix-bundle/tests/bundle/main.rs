@@ -0,0 +1,34 @@
+use std::path::{Path, PathBuf};
+
+pub use gix_testtools::Result;
+
+mod create;
+mod file;
+mod header;
+
+fn fixture() -> Result<PathBuf> {
+    gix_testtools::scripted_fixture_read_only("make_bundle_repo.sh")
+}
+
+fn odb(objects_dir: &Path) -> Result<gix_odb::HandleArc> {
+    let mut objects = gix_odb::at_opts(
+        objects_dir,
+        Vec::new(),
+        gix_odb::store::init::Options {
+            object_hash: gix_testtools::object_hash(),
+            ..Default::default()
+        },
+    )?
+    .into_arc()?;
+    // Creating packs requires to find the location of objects within packs.
+    objects.prevent_pack_unload();
+    Ok(objects)
+}
+
+fn rev_parse(repo: &Path, spec: &str) -> Result<gix_hash::ObjectId> {
+    Ok(gix_hash::ObjectId::from_hex(
+        gix_testtools::git(repo, &format!("rev-parse {spec}"))?
+            .trim()
+            .as_bytes(),
+    )?)
+}
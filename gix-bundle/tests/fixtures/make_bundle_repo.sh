@@ -0,0 +1,27 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q base
+(cd base
+  git checkout -q -b main
+  echo a > a
+  git add a && git commit -q -m c1
+  git tag -a -m "annotated" v1
+)
+
+git clone -q --bare --no-local base partial.git
+
+(cd base
+  echo b > b
+  git add b && git commit -q -m c2
+  git branch topic
+  mkdir dir
+  echo c > dir/c
+  git add dir && git commit -q -m "c3
+
+with a body"
+
+  git bundle create -q ../all.bundle --all
+  git bundle create -q ../incremental.bundle main ^v1
+  git bundle create -q --version=3 ../v3.bundle main
+)
@@ -0,0 +1,84 @@
+use std::{io, path::Path};
+
+use bstr::{BString, ByteSlice};
+
+use crate::{Entry, FetchHead};
+
+/// The error returned by [`FetchHead::from_bytes()`] and [`FetchHead::at()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read the FETCH_HEAD file")]
+    Io(#[from] io::Error),
+    #[error("The line {line:?} in FETCH_HEAD is malformed")]
+    Malformed { line: BString },
+}
+
+/// Lifecycle
+impl FetchHead {
+    /// Parse `data` in the format of the `FETCH_HEAD` file, with ids of kind `object_hash`.
+    pub fn from_bytes(data: &[u8], object_hash: gix_hash::Kind) -> Result<Self, Error> {
+        let entries = data
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                Entry::from_line(line, object_hash).ok_or_else(|| Error::Malformed {
+                    line: line.as_bstr().to_owned(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(FetchHead { entries })
+    }
+
+    /// Read the `FETCH_HEAD` file at `path` with ids of kind `object_hash`, or return an empty instance
+    /// if it doesn't exist.
+    pub fn at(path: &Path, object_hash: gix_hash::Kind) -> Result<Self, Error> {
+        match std::fs::read(path) {
+            Ok(data) => Self::from_bytes(&data, object_hash),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl Entry {
+    /// Parse a single `line` of a `FETCH_HEAD` file without its line terminator.
+    fn from_line(line: &[u8], object_hash: gix_hash::Kind) -> Option<Self> {
+        let (hex, rest) = line.split_once_str(b"\t")?;
+        let (marker, description) = rest.split_once_str(b"\t")?;
+        if hex.len() != object_hash.len_in_hex() {
+            return None;
+        }
+        let for_merge = match marker {
+            b"" => true,
+            b"not-for-merge" => false,
+            _ => return None,
+        };
+        let (name, url) = parse_description(description)?;
+        Some(Entry {
+            id: gix_hash::ObjectId::from_hex(hex).ok()?,
+            for_merge,
+            name,
+            url: url.into(),
+        })
+    }
+}
+
+/// Split `description` into the full name of the fetched reference and the URL it was fetched from.
+fn parse_description(description: &[u8]) -> Option<(BString, &[u8])> {
+    let Some(quoted) = description.find_byte(b'\'') else {
+        return Some(("HEAD".into(), description));
+    };
+    let prefix = match &description[..quoted] {
+        b"" => "",
+        b"branch " => "refs/heads/",
+        b"tag " => "refs/tags/",
+        b"remote-tracking branch " => "refs/remotes/",
+        _ => return Some(("HEAD".into(), description)),
+    };
+    let rest = &description[quoted + 1..];
+    let end = rest.find(b"' of ")?;
+    let mut name = BString::from(prefix);
+    name.extend_from_slice(&rest[..end]);
+    Some((name, &rest[end + b"' of ".len()..]))
+}
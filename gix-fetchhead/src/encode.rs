@@ -0,0 +1,40 @@
+use std::io;
+
+use crate::{Entry, FetchHead, Kind};
+
+/// Serialization
+impl FetchHead {
+    /// Serialize all entries into `out` in the format of the `FETCH_HEAD` file.
+    pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        for entry in &self.entries {
+            entry.write_to(&mut out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialization
+impl Entry {
+    /// Serialize this entry as a line of the `FETCH_HEAD` file into `out`, including the trailing newline.
+    pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        write!(out, "{}\t", self.id)?;
+        if !self.for_merge {
+            out.write_all(b"not-for-merge")?;
+        }
+        out.write_all(b"\t")?;
+        if let Some(short_name) = self.short_name() {
+            let kind = match self.kind() {
+                Kind::Branch => "branch ",
+                Kind::Tag => "tag ",
+                Kind::RemoteTrackingBranch => "remote-tracking branch ",
+                Kind::Head | Kind::Other => "",
+            };
+            out.write_all(kind.as_bytes())?;
+            out.write_all(b"'")?;
+            out.write_all(short_name)?;
+            out.write_all(b"' of ")?;
+        }
+        out.write_all(&self.url)?;
+        out.write_all(b"\n")
+    }
+}
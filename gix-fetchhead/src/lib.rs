@@ -1 +1,113 @@
+//! Read and write `.git/FETCH_HEAD`, which records what was fetched by `git fetch` for use by `git merge FETCH_HEAD`
+//! and `git pull`.
+//!
+//! Each line of the file is an [`Entry`] with the id of a fetched object, whether it should be merged, and a description
+//! with the name of the fetched reference and the URL it was fetched from, like
+//!
+//! ```text
+//! <id>\t\tbranch 'main' of https://example.com/repo
+//! <id>\tnot-for-merge\ttag 'v1.0' of https://example.com/repo
+//! ```
+//!
+//! `git merge FETCH_HEAD` merges all entries that are [marked for merge](Entry::for_merge), and only
+//! considers the first entry if there are none.
+#![deny(missing_docs)]
 #![forbid(unsafe_code)]
+
+use bstr::{BStr, BString};
+
+///
+pub mod decode;
+mod encode;
+
+/// A line of the `FETCH_HEAD` file for a fetched object.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Entry {
+    /// The id of the fetched object, which is the id of the tag object for annotated tags.
+    pub id: gix_hash::ObjectId,
+    /// If `true`, the object should be merged by `git merge FETCH_HEAD`, and it's marked as `not-for-merge` otherwise.
+    pub for_merge: bool,
+    /// The full name of the fetched reference on the remote, like `refs/heads/main`, or `HEAD`.
+    pub name: BString,
+    /// The URL the object was fetched from, as it is displayed to the user.
+    ///
+    /// Use [`display_url()`] to turn a fetch URL into this form.
+    pub url: BString,
+}
+
+/// The kind of reference an [`Entry`] was fetched from, as used in its description.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// The `HEAD` reference, which isn't named in the description.
+    Head,
+    /// A reference in `refs/heads/`.
+    Branch,
+    /// A reference in `refs/tags/`.
+    Tag,
+    /// A reference in `refs/remotes/`.
+    RemoteTrackingBranch,
+    /// Any other reference, which is named by its full name.
+    Other,
+}
+
+/// The parsed contents of `.git/FETCH_HEAD`, holding entries in the order they were written.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct FetchHead {
+    /// All entries, with the ones to merge typically written first.
+    pub entries: Vec<Entry>,
+}
+
+/// Access
+impl FetchHead {
+    /// Return all entries that should be merged by `git merge FETCH_HEAD`.
+    pub fn for_merge(&self) -> impl Iterator<Item = &Entry> + '_ {
+        self.entries.iter().filter(|entry| entry.for_merge)
+    }
+}
+
+/// Access
+impl Entry {
+    /// Return the kind of reference this entry was fetched from.
+    pub fn kind(&self) -> Kind {
+        self.kind_and_short_name().0
+    }
+
+    /// Return the name of the fetched reference as shown in the description, without the prefix implied by [its kind](Self::kind()),
+    /// or `None` if it's `HEAD`.
+    pub fn short_name(&self) -> Option<&BStr> {
+        self.kind_and_short_name().1
+    }
+
+    fn kind_and_short_name(&self) -> (Kind, Option<&BStr>) {
+        use bstr::ByteSlice;
+        if self.name == "HEAD" {
+            return (Kind::Head, None);
+        }
+        for (prefix, kind) in [
+            ("refs/heads/", Kind::Branch),
+            ("refs/tags/", Kind::Tag),
+            ("refs/remotes/", Kind::RemoteTrackingBranch),
+        ] {
+            if let Some(short_name) = self.name.strip_prefix(prefix.as_bytes()) {
+                return (kind, Some(short_name.as_bstr()));
+            }
+        }
+        (Kind::Other, Some(self.name.as_ref()))
+    }
+}
+
+/// Return `url` as it's written into `FETCH_HEAD` by `git`, which is without trailing slashes and without `.git` suffix.
+///
+/// Note that `url` should not contain credentials, which `git` removes before writing it.
+pub fn display_url(url: &BStr) -> &BStr {
+    let mut url: &[u8] = url.as_ref();
+    while let Some(stripped) = url.strip_suffix(b"/") {
+        url = stripped;
+    }
+    if url.len() > 5 {
+        if let Some(stripped) = url.strip_suffix(b".git") {
+            url = stripped;
+        }
+    }
+    url.into()
+}
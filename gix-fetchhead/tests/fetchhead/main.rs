@@ -0,0 +1,162 @@
+use std::path::PathBuf;
+
+use gix_fetchhead::{Entry, FetchHead, Kind};
+pub use gix_testtools::Result;
+
+fn fixture() -> Result<PathBuf> {
+    gix_testtools::scripted_fixture_read_only("make_fetch_head.sh")
+}
+
+/// Read the copy of `FETCH_HEAD` named `name`, as written by `git fetch`, along with its parsed form.
+fn fetch_head(name: &str) -> Result<(Vec<u8>, FetchHead)> {
+    let data = std::fs::read(fixture()?.join(name))?;
+    let fetch_head = FetchHead::from_bytes(&data, gix_testtools::object_hash())?;
+    Ok((data, fetch_head))
+}
+
+fn names_and_merge_status(fetch_head: &FetchHead) -> Vec<(&str, bool)> {
+    fetch_head
+        .entries
+        .iter()
+        .map(|entry| (std::str::from_utf8(&entry.name).expect("valid UTF-8"), entry.for_merge))
+        .collect()
+}
+
+fn round_trip(data: &[u8], fetch_head: &FetchHead) -> Result {
+    let mut out = Vec::new();
+    fetch_head.write_to(&mut out)?;
+    assert_eq!(
+        out.as_slice(),
+        data,
+        "writing the file again yields exactly what git wrote"
+    );
+    Ok(())
+}
+
+#[test]
+fn configured_remote() -> Result {
+    let (data, fetch_head) = fetch_head("default")?;
+    assert_eq!(
+        names_and_merge_status(&fetch_head),
+        [("refs/heads/main", true), ("refs/heads/feature", false)]
+    );
+    let main = &fetch_head.entries[0];
+    assert_eq!(main.kind(), Kind::Branch);
+    assert_eq!(main.short_name().expect("not HEAD"), "main");
+    assert_eq!(main.url, "../remote", "the trailing slash and .git suffix are removed");
+    assert_eq!(fetch_head.for_merge().count(), 1);
+    round_trip(&data, &fetch_head)
+}
+
+#[test]
+fn head_and_tags() -> Result {
+    let (data, fetch_head) = fetch_head("tags")?;
+    assert_eq!(
+        names_and_merge_status(&fetch_head),
+        [
+            ("HEAD", true),
+            ("refs/tags/lightweight", false),
+            ("refs/tags/v1", false)
+        ]
+    );
+    assert_eq!(fetch_head.entries[0].kind(), Kind::Head);
+    assert_eq!(fetch_head.entries[0].short_name(), None);
+    assert_eq!(fetch_head.entries[0].url, "../remote");
+    assert_eq!(fetch_head.entries[2].kind(), Kind::Tag);
+    assert_ne!(
+        fetch_head.entries[2].id, fetch_head.entries[0].id,
+        "annotated tags are recorded with the id of the tag object"
+    );
+    round_trip(&data, &fetch_head)
+}
+
+#[test]
+fn explicit_refspecs_and_other_references() -> Result {
+    let (data, fetch_head) = fetch_head("explicit")?;
+    assert_eq!(
+        names_and_merge_status(&fetch_head),
+        [
+            ("refs/heads/main", true),
+            ("refs/heads/feature", true),
+            ("refs/notes/commits", true)
+        ]
+    );
+    assert_eq!(fetch_head.entries[2].kind(), Kind::Other);
+    assert_eq!(
+        fetch_head.entries[2].short_name().expect("not HEAD"),
+        "refs/notes/commits"
+    );
+    round_trip(&data, &fetch_head)
+}
+
+#[test]
+fn appended_fetches() -> Result {
+    let (data, fetch_head) = fetch_head("append")?;
+    assert_eq!(
+        names_and_merge_status(&fetch_head),
+        [("HEAD", true), ("refs/remotes/origin/main", true)]
+    );
+    let appended = &fetch_head.entries[1];
+    assert_eq!(appended.kind(), Kind::RemoteTrackingBranch);
+    assert_eq!(appended.short_name().expect("not HEAD"), "origin/main");
+    assert_eq!(appended.url, ".");
+    round_trip(&data, &fetch_head)
+}
+
+#[test]
+fn missing_file_is_empty() -> Result {
+    let fetch_head = FetchHead::at(&fixture()?.join("does-not-exist"), gix_testtools::object_hash())?;
+    assert!(fetch_head.entries.is_empty());
+    Ok(())
+}
+
+#[test]
+fn malformed_lines() {
+    let hash = gix_hash::Kind::Sha1;
+    let id = hash.null().to_string();
+    for line in [
+        "abc\t\tbranch 'main' of url".to_string(),
+        format!("{id}\tmerge\tbranch 'main' of url"),
+        format!("{id} branch 'main' of url"),
+        format!("{id}\t\tbranch 'main url"),
+    ] {
+        assert!(
+            matches!(
+                FetchHead::from_bytes(line.as_bytes(), hash),
+                Err(gix_fetchhead::decode::Error::Malformed { .. })
+            ),
+            "{line}"
+        );
+    }
+}
+
+#[test]
+fn display_url() {
+    for (input, expected) in [
+        ("https://example.com/repo.git", "https://example.com/repo"),
+        ("https://example.com/repo.git//", "https://example.com/repo"),
+        ("../remote/", "../remote"),
+        ("a.git", "a.git"),
+        ("/a.git", "/a"),
+        (".", "."),
+    ] {
+        assert_eq!(gix_fetchhead::display_url(input.into()), expected, "{input}");
+    }
+}
+
+#[test]
+fn write_entry() -> Result {
+    let entry = Entry {
+        id: gix_hash::Kind::Sha1.null(),
+        for_merge: false,
+        name: "refs/pull/1/head".into(),
+        url: "https://example.com/repo".into(),
+    };
+    let mut out = Vec::new();
+    entry.write_to(&mut out)?;
+    assert_eq!(
+        out,
+        b"0000000000000000000000000000000000000000\tnot-for-merge\t'refs/pull/1/head' of https://example.com/repo\n"
+    );
+    Ok(())
+}
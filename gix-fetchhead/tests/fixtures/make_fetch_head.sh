@@ -0,0 +1,31 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q remote.git
+(cd remote.git
+  git checkout -q -b main
+  touch file && git add file && git commit -q -m first
+  git tag -a -m "annotated" v1
+  git checkout -q -b feature
+  echo feature >file && git commit -q -am feature
+  git tag lightweight
+  git checkout -q main
+  git update-ref refs/notes/commits HEAD
+)
+
+git clone -q --no-tags remote.git/ clone
+(cd clone
+  git remote set-url origin ../remote.git/
+  git fetch -q origin
+  cp .git/FETCH_HEAD ../default
+
+  git fetch -q --tags ../remote.git/
+  cp .git/FETCH_HEAD ../tags
+
+  git fetch -q ../remote.git/ main feature refs/notes/commits
+  cp .git/FETCH_HEAD ../explicit
+
+  git fetch -q ../remote.git/
+  git fetch -q --append . refs/remotes/origin/main
+  cp .git/FETCH_HEAD ../append
+)
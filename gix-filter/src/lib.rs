@@ -15,6 +15,9 @@ use bstr::BString;
 pub use encoding_rs as encoding;
 /// The `gix-attributes` crate whose types are mentioned in the public API of [Pipeline::convert_to_worktree()].
 pub use gix_attributes as attributes;
+/// The `gix-lfs` crate whose store is used in [`pipeline::Options::lfs`].
+#[cfg(feature = "lfs")]
+pub use gix_lfs as lfs;
 
 /// a filter to replace `$Id$` with a git-hash of the buffer.
 pub mod ident;
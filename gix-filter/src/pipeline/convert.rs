@@ -40,6 +40,9 @@ pub mod to_git {
         ReadProcessOutputToBuffer(#[from] std::io::Error),
         #[error("Could not allocate buffer")]
         OutOfMemory(#[from] std::collections::TryReserveError),
+        #[cfg(feature = "lfs")]
+        #[error(transparent)]
+        Lfs(#[from] gix_lfs::clean::Error),
     }
 }
 
@@ -59,9 +62,16 @@ pub mod to_worktree {
         Driver(#[from] crate::driver::apply::Error),
         #[error(transparent)]
         Configuration(#[from] super::configuration::Error),
+        #[cfg(feature = "lfs")]
+        #[error(transparent)]
+        Lfs(#[from] gix_lfs::smudge::Error),
     }
 }
 
+/// The name of the filter driver that is handled natively if [`Options::lfs`](super::Options::lfs) is set.
+#[cfg(feature = "lfs")]
+const LFS_DRIVER: &str = "lfs";
+
 /// Access
 impl Pipeline {
     /// Convert a `src` stream (to be found at `rela_path`) to a representation suitable for storage in `git`
@@ -106,6 +116,16 @@ impl Pipeline {
             },
         )?;
 
+        #[cfg(feature = "lfs")]
+        let driver = match (driver, self.options.lfs.as_ref()) {
+            (Some(driver), Some(store)) if driver.name == LFS_DRIVER => {
+                self.bufs.clear();
+                gix_lfs::clean(store, &mut src, &mut self.bufs.src)?;
+                in_src_buffer = true;
+                None
+            }
+            (driver, _) => driver,
+        };
         if let Some(driver) = driver {
             if let Some(mut read) = self.processes.apply(
                 driver,
@@ -212,6 +232,23 @@ impl Pipeline {
             bufs.swap();
         }
 
+        #[cfg(feature = "lfs")]
+        let driver = match (driver, self.options.lfs.as_ref()) {
+            (Some(driver), Some(store)) if driver.name == LFS_DRIVER => {
+                let (src, dest) = bufs.src_and_dest();
+                match gix_lfs::smudge(store, src, dest)? {
+                    gix_lfs::smudge::Outcome::Smudged(_) => {
+                        bufs.swap();
+                        None
+                    }
+                    gix_lfs::smudge::Outcome::Unchanged => None,
+                    // `gix` downloads missing objects in batches before checking out if it knows the LFS server.
+                    // Otherwise, let the configured driver obtain the object, just like `git-lfs` downloads it while smudging.
+                    gix_lfs::smudge::Outcome::Missing(_) => Some(driver),
+                }
+            }
+            (driver, _) => driver,
+        };
         if let Some(driver) = driver {
             let (mut src, _dest) = bufs.src_and_dest();
             if let Some(maybe_delayed) = self.processes.apply_delayed(
@@ -29,6 +29,14 @@ pub struct Options {
     pub encodings_with_roundtrip_check: Vec<&'static encoding_rs::Encoding>,
     /// The object hash to use when applying the `ident` filter.
     pub object_hash: gix_hash::Kind,
+    /// If set, files using the filter driver named `lfs` are cleaned and smudged with this store natively,
+    /// instead of invoking the `git-lfs` program configured for the driver.
+    ///
+    /// Objects that are missing in the store are smudged by the configured driver instead, which typically downloads them.
+    /// Without a `smudge` or `process` command, their pointer is checked out, and they have to be
+    /// [downloaded](gix_lfs::transfer::download()) first.
+    #[cfg(feature = "lfs")]
+    pub lfs: Option<gix_lfs::Store>,
 }
 
 /// Context that typically doesn't change throughout the lifetime of a pipeline, for use with `process` filters.
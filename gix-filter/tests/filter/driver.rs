@@ -70,7 +70,7 @@ pub(crate) mod apply {
 
     use crate::driver::{DRIVER, shutdown::extract_client};
 
-    fn driver_no_process() -> Driver {
+    pub(crate) fn driver_no_process() -> Driver {
         let mut driver = driver_with_process();
         driver.process = None;
         driver
@@ -0,0 +1,152 @@
+use std::{io::Read, path::Path};
+
+use bstr::ByteSlice;
+use gix_filter::{
+    lfs::{Pointer, Store},
+    pipeline::CrlfRoundTripCheck,
+};
+
+use serial_test::serial;
+
+use crate::pipeline::pipeline;
+
+fn git_lfs_driver() -> gix_filter::Driver {
+    gix_filter::Driver {
+        name: "lfs".into(),
+        clean: Some("git-lfs clean -- %f".into()),
+        smudge: Some("git-lfs smudge -- %f".into()),
+        process: Some("git-lfs filter-process".into()),
+        required: true,
+    }
+}
+
+fn lfs_pipeline(
+    driver: gix_filter::Driver,
+) -> gix_testtools::Result<(
+    gix_worktree::Stack,
+    gix_filter::Pipeline,
+    Store,
+    gix_testtools::tempfile::TempDir,
+)> {
+    let (cache, mut pipe) = pipeline("lfs", || {
+        (vec![driver], Vec::new(), CrlfRoundTripCheck::Fail, Default::default())
+    })?;
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let store = Store::at(tmp.path().join("lfs"));
+    pipe.options_mut().lfs = Some(store.clone());
+    Ok((cache, pipe, store, tmp))
+}
+
+#[test]
+fn clean_and_smudge_natively_without_invoking_git_lfs() -> gix_testtools::Result {
+    let (mut cache, mut pipe, store, _tmp) = lfs_pipeline(git_lfs_driver())?;
+    let mut attributes = |path: &bstr::BStr, attrs: &mut gix_attributes::search::Outcome| {
+        cache
+            .at_entry(path, None, &gix_object::find::Never)
+            .expect("cannot fail")
+            .matching_attributes(attrs);
+    };
+
+    let pointer = pipe
+        .convert_to_git(&b"hello world"[..], Path::new("file.bin"), &mut attributes, &mut |_| {
+            Ok(None)
+        })?
+        .as_bytes()
+        .expect("the pointer is in memory")
+        .to_owned();
+    let expected = Pointer::from_bytes(&pointer)?;
+    assert_eq!(expected.size, 11);
+    assert!(store.contains(&expected), "the content is now in the LFS store");
+
+    let out = pipe.convert_to_git(&b"hello world"[..], Path::new("file.txt"), &mut attributes, &mut |_| {
+        Ok(None)
+    })?;
+    assert!(!out.is_changed(), "files without the `lfs` filter aren't affected");
+    drop(out);
+
+    let out = pipe.convert_to_worktree(
+        &pointer,
+        "file.bin".into(),
+        &mut attributes,
+        gix_filter::driver::apply::Delay::Forbid,
+    )?;
+    assert!(out.is_changed());
+    assert_eq!(out.as_bytes().expect("in memory").as_bstr(), "hello world");
+    Ok(())
+}
+
+#[serial]
+#[test]
+fn missing_objects_are_smudged_by_the_configured_driver() -> gix_testtools::Result {
+    let (mut cache, mut pipe, store, _tmp) = lfs_pipeline(gix_filter::Driver {
+        name: "lfs".into(),
+        ..crate::driver::apply::driver_no_process()
+    })?;
+    let mut attributes = |path: &bstr::BStr, attrs: &mut gix_attributes::search::Outcome| {
+        cache
+            .at_entry(path, None, &gix_object::find::Never)
+            .expect("cannot fail")
+            .matching_attributes(attrs);
+    };
+
+    let pointer = Pointer {
+        oid: gix_hash::ObjectId::from_hex(b"b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")?,
+        size: 11,
+    }
+    .to_bstring();
+    assert!(!store.contains(&Pointer::from_bytes(&pointer)?));
+
+    let mut out = pipe.convert_to_worktree(
+        &pointer,
+        "file.bin".into(),
+        &mut attributes,
+        gix_filter::driver::apply::Delay::Forbid,
+    )?;
+    assert!(out.is_changed());
+    let mut buf = Vec::new();
+    out.read_to_end(&mut buf)?;
+    let expected: Vec<u8> = pointer
+        .lines_with_terminator()
+        .flat_map(|line| ["➡".as_bytes(), line].concat())
+        .collect();
+    assert_eq!(
+        buf.as_bstr(),
+        expected.as_bstr(),
+        "the driver receives the pointer, just like `git-lfs smudge` which would download the object"
+    );
+    Ok(())
+}
+
+#[test]
+fn missing_objects_are_checked_out_as_pointer_without_driver_commands() -> gix_testtools::Result {
+    let (mut cache, mut pipe, _store, _tmp) = lfs_pipeline(gix_filter::Driver {
+        name: "lfs".into(),
+        clean: None,
+        smudge: None,
+        process: None,
+        required: false,
+    })?;
+    let mut attributes = |path: &bstr::BStr, attrs: &mut gix_attributes::search::Outcome| {
+        cache
+            .at_entry(path, None, &gix_object::find::Never)
+            .expect("cannot fail")
+            .matching_attributes(attrs);
+    };
+
+    let pointer = Pointer {
+        oid: gix_hash::ObjectId::from_hex(b"b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")?,
+        size: 11,
+    }
+    .to_bstring();
+    let out = pipe.convert_to_worktree(
+        &pointer,
+        "file.bin".into(),
+        &mut attributes,
+        gix_filter::driver::apply::Delay::Forbid,
+    )?;
+    assert!(
+        !out.is_changed(),
+        "without the object in the store and a way to obtain it, the pointer is checked out instead"
+    );
+    Ok(())
+}
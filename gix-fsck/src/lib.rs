@@ -1,7 +1,7 @@
 //! A library for performing object database integrity and connectivity checks
 #![deny(unsafe_code, missing_docs)]
 
-use std::collections::VecDeque;
+use std::{collections::VecDeque, sync::Arc};
 
 use gix_hash::ObjectId;
 use gix_hashtable::HashSet;
@@ -19,6 +19,8 @@ where
     missing_cb: F,
     /// Set of Object IDs already (or about to be) scanned during the check
     seen: HashSet,
+    /// Set of Object IDs that may be missing as they are promised by a promisor remote.
+    promised: Arc<HashSet>,
     /// A buffer to keep a single object at a time.
     buf: Vec<u8>,
 }
@@ -34,17 +36,28 @@ where
             db,
             missing_cb,
             seen: HashSet::default(),
+            promised: Default::default(),
             buf: Default::default(),
         }
     }
 
+    /// Treat the objects in `promised` as present even if they are missing, as they can be obtained from a promisor remote
+    /// in a partial clone.
+    ///
+    /// Such a set can be obtained with `gix_odb::Store::promised_objects()`, which caches it.
+    pub fn with_promised_objects(mut self, promised: impl Into<Arc<HashSet>>) -> Self {
+        self.promised = promised.into();
+        self
+    }
+
     /// Run the connectivity check on the provided commit `oid`.
     ///
     /// ### Algorithm
     ///
     /// Walk the trees and blobs referenced by the commit and verify they exist in the ODB.
     /// Any objects previously encountered by this instance will be skipped silently.
-    /// Any referenced blobs that are not present in the ODB will result in a call to the  `missing_cb`.
+    /// Any referenced blobs that are not present in the ODB will result in a call to the  `missing_cb`,
+    /// unless they are [promised](Self::with_promised_objects()).
     /// Missing commits or trees will cause an error to be returned.
     ///     - TODO: consider how to handle a missing commit (invoke `missing_cb`, or possibly return a Result?)
     pub fn check_commit(&mut self, oid: &ObjectId) -> Result<(), gix_object::find::existing_object::Error> {
@@ -72,7 +85,9 @@ where
     /// if they have not been `seen` yet.
     fn check_tree(&mut self, oid: &ObjectId, tree_ids: &mut VecDeque<ObjectId>) {
         let Ok(tree) = self.db.find_tree(oid, &mut self.buf) else {
-            (self.missing_cb)(oid, Kind::Tree);
+            if !self.promised.contains(oid) {
+                (self.missing_cb)(oid, Kind::Tree);
+            }
             return;
         };
 
@@ -84,7 +99,7 @@ where
                 }
                 EntryKind::Blob | EntryKind::BlobExecutable | EntryKind::Link => {
                     let blob_id = entry_ref.oid.to_owned();
-                    if self.seen.insert(blob_id) {
+                    if self.seen.insert(blob_id) && !self.promised.contains(&blob_id) {
                         check_blob(&self.db, &blob_id, &mut self.missing_cb);
                     }
                 }
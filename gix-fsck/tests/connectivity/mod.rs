@@ -7,13 +7,28 @@ use std::sync::LazyLock;
 use crate::hex_to_id;
 
 fn check_missing<'a>(repo_name: &str, commits: impl IntoIterator<Item = &'a ObjectId>) -> HashMap<ObjectId, Kind> {
+    check_missing_inner(repo_name, commits, false)
+}
+
+fn check_missing_unless_promised<'a>(
+    repo_name: &str,
+    commits: impl IntoIterator<Item = &'a ObjectId>,
+) -> HashMap<ObjectId, Kind> {
+    check_missing_inner(repo_name, commits, true)
+}
+
+fn check_missing_inner<'a>(
+    repo_name: &str,
+    commits: impl IntoIterator<Item = &'a ObjectId>,
+    use_promised_objects: bool,
+) -> HashMap<ObjectId, Kind> {
+    let objects_dir = gix_testtools::scripted_fixture_read_only("make_test_repos.sh")
+        .expect("fixture path")
+        .join(repo_name)
+        .join(".git")
+        .join("objects");
     let db = {
-        let fixture_path = gix_testtools::scripted_fixture_read_only("make_test_repos.sh")
-            .expect("fixture path")
-            .join(repo_name)
-            .join(".git")
-            .join("objects");
-        let mut db = gix_odb::at(fixture_path).expect("valid odb");
+        let mut db = gix_odb::at(&objects_dir).expect("valid odb");
         db.refresh_never();
         db
     };
@@ -23,7 +38,12 @@ fn check_missing<'a>(repo_name: &str, commits: impl IntoIterator<Item = &'a Obje
         missing.try_insert(*oid, kind).expect("no duplicate oid");
     };
 
-    let mut check = Connectivity::new(db, record_missing_and_assert_no_duplicate);
+    let promised = if use_promised_objects {
+        gix_odb::promisor::promised_objects(&objects_dir, gix_hash::Kind::Sha1, &db).expect("promisor packs are valid")
+    } else {
+        Default::default()
+    };
+    let mut check = Connectivity::new(db, record_missing_and_assert_no_duplicate).with_promised_objects(promised);
     for commit in commits.into_iter() {
         check.check_commit(commit).expect("commit is present");
     }
@@ -82,3 +102,45 @@ fn missing_trees() {
     );
     assert_eq!(check_missing("treeless", all_commits()), expected);
 }
+
+#[test]
+fn promised_blobs_are_not_missing() {
+    assert_eq!(
+        check_missing_unless_promised("blobless", all_commits()),
+        HashMap::default(),
+        "all blobs are promised by the promisor pack"
+    );
+}
+
+#[test]
+fn promised_trees_are_not_missing() {
+    assert_eq!(
+        check_missing_unless_promised("treeless", all_commits()),
+        HashMap::default(),
+        "the missing trees are referenced by commits in the promisor pack"
+    );
+}
+
+#[test]
+fn promised_objects_are_computed_once_per_set_of_promisor_packs() {
+    let objects_dir = gix_testtools::scripted_fixture_read_only("make_test_repos.sh")
+        .expect("fixture path")
+        .join("blobless")
+        .join(".git")
+        .join("objects");
+    let db = gix_odb::at(&objects_dir).expect("valid odb");
+
+    let promised = db.store_ref().promised_objects(&db).expect("promisor packs are valid");
+    assert_eq!(
+        *promised,
+        gix_odb::promisor::promised_objects(&objects_dir, gix_hash::Kind::Sha1, &db).expect("promisor packs are valid"),
+        "the cached set is the same as the computed one"
+    );
+    assert!(
+        std::sync::Arc::ptr_eq(
+            &promised,
+            &db.store_ref().promised_objects(&db).expect("promisor packs are valid")
+        ),
+        "the set is reused as long as the promisor packs don't change"
+    );
+}
@@ -0,0 +1,196 @@
+use std::{ffi::OsString, path::Path};
+
+use bstr::{BStr, BString};
+
+/// Everything `git` passes to a hook when running it, other than the context of the repository it runs in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    /// The name of the hook to run, like `pre-commit`.
+    pub name: &'static str,
+    /// The arguments to pass to the hook.
+    pub args: Vec<OsString>,
+    /// Environment variables to set in addition to those of the context the hook runs in.
+    pub env: Vec<(OsString, OsString)>,
+    /// The data to feed to the hook through its standard input, or `None` if standard input should be closed.
+    pub stdin: Option<BString>,
+}
+
+/// Where the message of a commit comes from, as passed to the `prepare-commit-msg` hook.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MessageSource {
+    /// The message was given on the command-line, like with `-m` or `-F`.
+    Message,
+    /// The message was initialized from a template, like with `-t` or `commit.template`.
+    Template,
+    /// The commit is a merge, or `.git/MERGE_MSG` exists.
+    Merge,
+    /// `.git/SQUASH_MSG` exists.
+    Squash,
+    /// The message was taken from the given commit, like with `-c`, `-C` or `--amend`.
+    Commit(gix_hash::ObjectId),
+}
+
+/// The update of a remote reference by a push, as passed to the `pre-push` hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushUpdate {
+    /// The full name of the local reference that is pushed, or `None` if the remote reference is deleted.
+    pub local_ref: Option<BString>,
+    /// The object the remote reference is set to, which is null if the remote reference is deleted.
+    pub local_id: gix_hash::ObjectId,
+    /// The full name of the remote reference that is updated.
+    pub remote_ref: BString,
+    /// The object the remote reference currently points to, which is null if it doesn't exist yet.
+    pub remote_id: gix_hash::ObjectId,
+}
+
+/// The state of a reference transaction, as passed to the `reference-transaction` hook.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TransactionState {
+    /// The transaction is about to be prepared, before any locks were taken.
+    Preparing,
+    /// All reference updates are locked and the transaction is about to be committed.
+    Prepared,
+    /// The transaction was committed and all references have their new values.
+    Committed,
+    /// The transaction was aborted and no reference was changed.
+    Aborted,
+}
+
+impl TransactionState {
+    /// Return the name of the state as passed to the hook.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionState::Preparing => "preparing",
+            TransactionState::Prepared => "prepared",
+            TransactionState::Committed => "committed",
+            TransactionState::Aborted => "aborted",
+        }
+    }
+}
+
+/// The update of a reference in a transaction, as passed to the `reference-transaction` hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefUpdate {
+    /// The object the reference pointed to before the update, which is null if it didn't exist or if it isn't known.
+    pub previous_id: gix_hash::ObjectId,
+    /// The object the reference points to after the update, which is null if it is deleted.
+    pub new_id: gix_hash::ObjectId,
+    /// The full name of the updated reference.
+    pub name: BString,
+}
+
+/// Lifecycle
+impl Invocation {
+    /// Create an invocation of the hook called `name`, without arguments or standard input.
+    pub fn new(name: &'static str) -> Self {
+        Invocation {
+            name,
+            args: Vec::new(),
+            env: Vec::new(),
+            stdin: None,
+        }
+    }
+
+    /// The `pre-commit` hook, which runs before a commit is created and may prevent it by failing.
+    pub fn pre_commit() -> Self {
+        Self::new("pre-commit")
+    }
+
+    /// The `prepare-commit-msg` hook, which may edit the commit message in `message_file` before the user is asked
+    /// to edit it, and which is told where the message came from with `source`, if known.
+    pub fn prepare_commit_msg(message_file: &Path, source: Option<MessageSource>) -> Self {
+        let mut out = Self::new("prepare-commit-msg");
+        out.args.push(message_file.into());
+        if let Some(source) = source {
+            out.args.push(
+                match source {
+                    MessageSource::Message => "message",
+                    MessageSource::Template => "template",
+                    MessageSource::Merge => "merge",
+                    MessageSource::Squash => "squash",
+                    MessageSource::Commit(_) => "commit",
+                }
+                .into(),
+            );
+            if let MessageSource::Commit(id) = source {
+                out.args.push(id.to_string().into());
+            }
+        }
+        out
+    }
+
+    /// The `commit-msg` hook, which may edit or reject the final commit message in `message_file`.
+    pub fn commit_msg(message_file: &Path) -> Self {
+        let mut out = Self::new("commit-msg");
+        out.args.push(message_file.into());
+        out
+    }
+
+    /// The `post-checkout` hook, which runs after the worktree was updated to `new_head` from `previous_head`.
+    /// `is_branch_checkout` is `true` if `HEAD` was changed, and `false` if only files were checked out.
+    pub fn post_checkout(previous_head: &gix_hash::oid, new_head: &gix_hash::oid, is_branch_checkout: bool) -> Self {
+        let mut out = Self::new("post-checkout");
+        out.args.extend([
+            previous_head.to_string().into(),
+            new_head.to_string().into(),
+            if is_branch_checkout { "1" } else { "0" }.into(),
+        ]);
+        out
+    }
+
+    /// The `pre-push` hook, which runs before `updates` are pushed to the remote called `remote_name` at `remote_url`,
+    /// and which may prevent the push by failing.
+    ///
+    /// If the push doesn't use a named remote, `git` passes the URL as `remote_name` as well.
+    pub fn pre_push(remote_name: &BStr, remote_url: &BStr, updates: &[PushUpdate]) -> Self {
+        let mut out = Self::new("pre-push");
+        out.args.extend(
+            [gix_path::from_bstr(remote_name), gix_path::from_bstr(remote_url)].map(|arg| arg.into_owned().into()),
+        );
+        let mut stdin = BString::default();
+        for update in updates {
+            stdin.extend_from_slice(
+                update
+                    .local_ref
+                    .as_ref()
+                    .map_or(b"(delete)".as_slice(), |name| name.as_slice()),
+            );
+            stdin.push(b' ');
+            stdin.extend_from_slice(update.local_id.to_string().as_bytes());
+            stdin.push(b' ');
+            stdin.extend_from_slice(&update.remote_ref);
+            stdin.push(b' ');
+            stdin.extend_from_slice(update.remote_id.to_string().as_bytes());
+            stdin.push(b'\n');
+        }
+        out.stdin = Some(stdin);
+        out
+    }
+
+    /// The `reference-transaction` hook, which runs whenever a reference transaction with `updates` reaches `state`.
+    /// It may abort the transaction by failing if `state` is [`TransactionState::Prepared`].
+    pub fn reference_transaction(state: TransactionState, updates: &[RefUpdate]) -> Self {
+        let mut out = Self::new("reference-transaction");
+        out.args.push(state.as_str().into());
+        let mut stdin = BString::default();
+        for update in updates {
+            stdin.extend_from_slice(update.previous_id.to_string().as_bytes());
+            stdin.push(b' ');
+            stdin.extend_from_slice(update.new_id.to_string().as_bytes());
+            stdin.push(b' ');
+            stdin.extend_from_slice(&update.name);
+            stdin.push(b'\n');
+        }
+        out.stdin = Some(stdin);
+        out
+    }
+}
+
+/// Builder
+impl Invocation {
+    /// Add `key` with `value` to the environment of the hook.
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+}
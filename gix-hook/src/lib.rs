@@ -0,0 +1,52 @@
+//! Discover and run git hooks, akin to `git hook run`.
+//!
+//! Hooks are executables named after the event they handle, like `pre-commit`, which are found in a hooks directory.
+//! That's `$GIT_DIR/hooks` by default, or the directory configured with `core.hooksPath`.
+//!
+//! * [`find()`] looks up a hook by name and only returns it if it can be executed, so sample hooks are ignored.
+//! * An [`Invocation`] holds the arguments, environment and standard input `git` passes to a hook, and can be created
+//!   for the most common hooks with its typed constructors.
+//! * [`run()`] runs a hook with an invocation just like `git` would, and returns its exit status.
+//!
+//! Whether hooks should run at all, for instance depending on the trust in the repository they belong to,
+//! is up to the caller.
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+
+use std::path::{Path, PathBuf};
+
+///
+pub mod invocation;
+pub use invocation::Invocation;
+
+///
+pub mod run;
+pub use run::function::run;
+
+/// Return the path to the hook called `name` in `hooks_dir` if it exists and is executable, or `None` otherwise.
+///
+/// On Windows, where there is no executable bit, each file is considered executable and `<name>.exe` is tried as well.
+pub fn find(hooks_dir: &Path, name: &str) -> Option<PathBuf> {
+    let path = hooks_dir.join(name);
+    if is_executable(&path) {
+        return Some(path);
+    }
+    if cfg!(windows) {
+        let path = hooks_dir.join(format!("{name}.exe"));
+        if is_executable(&path) {
+            return Some(path);
+        }
+    }
+    None
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+
+/// The error returned by [`run()`](crate::run()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not run the hook at {path:?}")]
+    Spawn { path: PathBuf, source: std::io::Error },
+    #[error("Could not write to the standard input of the hook at {path:?}")]
+    Stdin { path: PathBuf, source: std::io::Error },
+    #[error("Could not wait for the hook at {path:?} to finish")]
+    Wait { path: PathBuf, source: std::io::Error },
+}
+
+/// Options for use in [`run()`](crate::run()).
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The directory to run the hook in, which is the root of the worktree, or the `.git` directory of a bare repository.
+    pub cwd: PathBuf,
+    /// Information about the repository the hook runs in, which is passed through its environment.
+    ///
+    /// `git` always sets [`git_dir`](gix_command::Context::git_dir), so hooks can run `git` commands on the same repository.
+    pub context: gix_command::Context,
+}
+
+pub(super) mod function {
+    use std::{io::Write, path::Path, process::Stdio};
+
+    use super::{Error, Options};
+    use crate::Invocation;
+
+    /// Run the executable `hook`, typically obtained with [`find()`](crate::find()), with everything `invocation` provides
+    /// and in the repository described by `options`, and return its exit status once it finished.
+    /// A relative `hook` path is relative to the current working directory, not to [`Options::cwd`].
+    ///
+    /// Just like `git`, the hook isn't run with a shell, its standard output is redirected to standard error, and
+    /// its standard input is closed unless [`Invocation::stdin`] is set.
+    /// Hooks that exit without reading all of their input are not considered an error.
+    pub fn run(
+        hook: &Path,
+        invocation: &Invocation,
+        Options { cwd, context }: Options,
+    ) -> Result<std::process::ExitStatus, Error> {
+        // The hook runs in `cwd`, so it must not be relative to the current working directory.
+        let hook = std::path::absolute(hook).map_err(|source| Error::Spawn {
+            path: hook.to_owned(),
+            source,
+        })?;
+        let hook = hook.as_path();
+        let mut cmd: std::process::Command = gix_command::prepare(hook)
+            .args(invocation.args.iter().cloned())
+            .with_context(context)
+            .stdin(if invocation.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(std::io::stderr().into())
+            .into();
+        cmd.envs(invocation.env.iter().map(|(key, value)| (key, value)))
+            .current_dir(cwd);
+        gix_trace::debug!(hook = invocation.name, cmd = ?cmd);
+
+        let mut child = cmd.spawn().map_err(|source| Error::Spawn {
+            path: hook.to_owned(),
+            source,
+        })?;
+        if let Some((data, mut stdin)) = invocation.stdin.as_ref().zip(child.stdin.take()) {
+            match stdin.write_all(data) {
+                Ok(()) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => {}
+                Err(source) => {
+                    return Err(Error::Stdin {
+                        path: hook.to_owned(),
+                        source,
+                    });
+                }
+            }
+        }
+        child.wait().map_err(|source| Error::Wait {
+            path: hook.to_owned(),
+            source,
+        })
+    }
+}
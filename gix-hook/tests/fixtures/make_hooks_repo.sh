@@ -0,0 +1,33 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git commit -q --allow-empty -m init
+
+hooks=.git/hooks
+rm -f $hooks/*
+
+# Record everything a hook receives into the directory at `$HOOK_OUT`, and exit with `$HOOK_EXIT`.
+# If `$HOOK_IGNORE_INPUT` is set, exit right away without reading standard input.
+cat >$hooks/pre-commit <<'HOOK'
+#!/bin/sh
+test -n "${HOOK_IGNORE_INPUT:-}" && exit 0
+{
+  echo "name: $(basename "$0")"
+  echo "cwd: $(pwd)"
+  echo "git-dir: $GIT_DIR"
+  for arg in "$@"; do echo "arg: $arg"; done
+} >"$HOOK_OUT/invocation"
+cat >"$HOOK_OUT/stdin"
+echo "to stdout"
+exit "${HOOK_EXIT:-0}"
+HOOK
+chmod +x $hooks/pre-commit
+
+for hook in pre-push reference-transaction; do
+  cp $hooks/pre-commit $hooks/$hook
+done
+
+cp $hooks/pre-commit $hooks/post-checkout
+chmod -x $hooks/post-checkout
+cp $hooks/pre-commit $hooks/commit-msg.sample
@@ -0,0 +1,31 @@
+use crate::fixture;
+
+#[test]
+fn executable_hooks_are_found() -> crate::Result {
+    let hooks = fixture()?.join(".git/hooks");
+    for name in ["pre-commit", "pre-push", "reference-transaction"] {
+        assert_eq!(gix_hook::find(&hooks, name), Some(hooks.join(name)));
+    }
+    Ok(())
+}
+
+#[test]
+fn missing_hooks_and_samples_are_not_found() -> crate::Result {
+    let hooks = fixture()?.join(".git/hooks");
+    assert_eq!(gix_hook::find(&hooks, "commit-msg"), None, "a sample isn't a hook");
+    assert_eq!(gix_hook::find(&hooks.join("missing"), "pre-commit"), None);
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn hooks_without_executable_bit_are_ignored() -> crate::Result {
+    let hooks = fixture()?.join(".git/hooks");
+    assert!(hooks.join("post-checkout").is_file());
+    assert_eq!(
+        gix_hook::find(&hooks, "post-checkout"),
+        None,
+        "git would only print a hint"
+    );
+    Ok(())
+}
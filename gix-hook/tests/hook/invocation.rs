@@ -0,0 +1,126 @@
+use std::{ffi::OsString, path::Path};
+
+use gix_hook::{
+    Invocation,
+    invocation::{MessageSource, PushUpdate, RefUpdate, TransactionState},
+};
+
+use crate::hex;
+
+fn args(invocation: &Invocation) -> Vec<OsString> {
+    invocation.args.clone()
+}
+
+#[test]
+fn commit_hooks() {
+    let invocation = Invocation::pre_commit();
+    assert_eq!(invocation.name, "pre-commit");
+    assert!(invocation.args.is_empty());
+    assert_eq!(invocation.stdin, None, "stdin is closed");
+
+    let file = Path::new(".git/COMMIT_EDITMSG");
+    let invocation = Invocation::commit_msg(file);
+    assert_eq!(invocation.name, "commit-msg");
+    assert_eq!(args(&invocation), [file]);
+
+    let invocation = Invocation::prepare_commit_msg(file, None);
+    assert_eq!(invocation.name, "prepare-commit-msg");
+    assert_eq!(args(&invocation), [file]);
+    assert_eq!(
+        args(&Invocation::prepare_commit_msg(file, Some(MessageSource::Squash))),
+        [file.as_os_str(), "squash".as_ref()]
+    );
+    let id = gix_hash::ObjectId::from_hex(hex(1).as_bytes()).expect("valid");
+    assert_eq!(
+        args(&Invocation::prepare_commit_msg(file, Some(MessageSource::Commit(id)))),
+        [file.as_os_str(), "commit".as_ref(), hex(1).as_ref()]
+    );
+}
+
+#[test]
+fn post_checkout() {
+    let (previous, new) = (id(1), id(2));
+    let invocation = Invocation::post_checkout(&previous, &new, true);
+    assert_eq!(invocation.name, "post-checkout");
+    assert_eq!(args(&invocation), [hex(1), hex(2), "1".into()].map(OsString::from));
+    assert_eq!(
+        args(&Invocation::post_checkout(&previous, &new, false))[2],
+        "0",
+        "file checkouts are flagged with 0"
+    );
+}
+
+#[test]
+fn pre_push_feeds_updates_through_stdin() {
+    let null = gix_hash::ObjectId::null(gix_testtools::object_hash());
+    let invocation = Invocation::pre_push(
+        "origin".into(),
+        "https://example.com/repo".into(),
+        &[
+            PushUpdate {
+                local_ref: Some("refs/heads/main".into()),
+                local_id: id(1),
+                remote_ref: "refs/heads/main".into(),
+                remote_id: id(2),
+            },
+            PushUpdate {
+                local_ref: None,
+                local_id: null,
+                remote_ref: "refs/heads/gone".into(),
+                remote_id: id(3),
+            },
+        ],
+    );
+    assert_eq!(invocation.name, "pre-push");
+    assert_eq!(args(&invocation), ["origin", "https://example.com/repo"]);
+    assert_eq!(
+        invocation.stdin.expect("set"),
+        format!(
+            "refs/heads/main {} refs/heads/main {}\n(delete) {null} refs/heads/gone {}\n",
+            hex(1),
+            hex(2),
+            hex(3)
+        )
+    );
+}
+
+#[test]
+fn reference_transaction_feeds_updates_through_stdin() {
+    let null = gix_hash::ObjectId::null(gix_testtools::object_hash());
+    let invocation = Invocation::reference_transaction(
+        TransactionState::Prepared,
+        &[
+            RefUpdate {
+                previous_id: null,
+                new_id: id(1),
+                name: "refs/heads/new".into(),
+            },
+            RefUpdate {
+                previous_id: id(2),
+                new_id: null,
+                name: "refs/tags/deleted".into(),
+            },
+        ],
+    );
+    assert_eq!(invocation.name, "reference-transaction");
+    assert_eq!(args(&invocation), ["prepared"]);
+    assert_eq!(
+        invocation.stdin.expect("set"),
+        format!(
+            "{null} {} refs/heads/new\n{} {null} refs/tags/deleted\n",
+            hex(1),
+            hex(2)
+        )
+    );
+    for (state, expected) in [
+        (TransactionState::Preparing, "preparing"),
+        (TransactionState::Committed, "committed"),
+        (TransactionState::Aborted, "aborted"),
+    ] {
+        assert_eq!(state.as_str(), expected);
+    }
+}
+
+fn id(byte: u8) -> gix_hash::ObjectId {
+    gix_hash::ObjectId::from_hex(hex(byte).as_bytes()).expect("valid")
+}
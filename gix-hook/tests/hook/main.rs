@@ -0,0 +1,17 @@
+use std::path::PathBuf;
+
+pub use gix_testtools::Result;
+
+mod find;
+mod invocation;
+mod run;
+
+fn fixture() -> Result<PathBuf> {
+    Ok(gix_path::realpath(gix_testtools::scripted_fixture_read_only(
+        "make_hooks_repo.sh",
+    )?)?)
+}
+
+fn hex(byte: u8) -> String {
+    gix_hash::ObjectId::from_bytes_or_panic(&vec![byte; gix_testtools::object_hash().len_in_bytes()]).to_string()
+}
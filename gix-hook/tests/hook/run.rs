@@ -0,0 +1,80 @@
+use gix_hook::{Invocation, invocation::RefUpdate, run::Options};
+
+use crate::{fixture, hex};
+
+fn options(repo: &std::path::Path) -> Options {
+    Options {
+        cwd: repo.to_owned(),
+        context: gix_command::Context {
+            git_dir: Some(repo.join(".git")),
+            ..Default::default()
+        },
+    }
+}
+
+fn run(invocation: Invocation) -> crate::Result<(std::process::ExitStatus, String, String)> {
+    let repo = fixture()?;
+    let out = gix_testtools::tempfile::TempDir::new()?;
+    let hook = gix_hook::find(&repo.join(".git/hooks"), invocation.name).expect("hook exists");
+    let status = gix_hook::run(&hook, &invocation.env("HOOK_OUT", out.path()), options(&repo))?;
+    Ok((
+        status,
+        std::fs::read_to_string(out.path().join("invocation"))?,
+        std::fs::read_to_string(out.path().join("stdin"))?,
+    ))
+}
+
+#[test]
+fn cwd_env_and_closed_stdin() -> crate::Result {
+    let repo = fixture()?;
+    let (status, invocation, stdin) = run(Invocation::pre_commit())?;
+    assert!(status.success());
+    assert_eq!(
+        invocation,
+        format!(
+            "name: pre-commit\ncwd: {}\ngit-dir: {}\n",
+            repo.display(),
+            repo.join(".git").display()
+        )
+    );
+    assert_eq!(stdin, "", "stdin is closed if there is no input");
+    Ok(())
+}
+
+#[test]
+fn arguments_and_stdin() -> crate::Result {
+    let update = RefUpdate {
+        previous_id: gix_hash::ObjectId::null(gix_testtools::object_hash()),
+        new_id: gix_hash::ObjectId::from_hex(hex(1).as_bytes())?,
+        name: "refs/heads/main".into(),
+    };
+    let invocation = Invocation::reference_transaction(gix_hook::invocation::TransactionState::Committed, &[update]);
+    let expected_stdin = invocation.stdin.clone().expect("set");
+    let (status, invocation, stdin) = run(invocation)?;
+    assert!(status.success());
+    assert!(invocation.starts_with("name: reference-transaction\n"));
+    assert!(invocation.ends_with("arg: committed\n"));
+    assert_eq!(stdin, expected_stdin);
+    Ok(())
+}
+
+#[test]
+fn failing_hooks_report_their_exit_status() -> crate::Result {
+    let (status, _, _) = run(Invocation::pre_commit().env("HOOK_EXIT", "3"))?;
+    assert_eq!(status.code(), Some(3));
+    Ok(())
+}
+
+#[test]
+fn hooks_may_ignore_their_input() -> crate::Result {
+    let repo = fixture()?;
+    let hook = gix_hook::find(&repo.join(".git/hooks"), "pre-push").expect("hook exists");
+    let mut invocation = Invocation::pre_push("origin".into(), "url".into(), &[]);
+    invocation.stdin = Some(vec![b'x'; 1024 * 1024].into());
+    let status = gix_hook::run(&hook, &invocation.env("HOOK_IGNORE_INPUT", "1"), options(&repo))?;
+    assert!(
+        status.success(),
+        "the hook exits before reading its input, which isn't an error when feeding it"
+    );
+    Ok(())
+}
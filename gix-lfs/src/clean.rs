@@ -0,0 +1,44 @@
+use std::io;
+
+/// The error returned by [`clean()`](crate::clean()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read the content to clean")]
+    Read(#[source] io::Error),
+    #[error(transparent)]
+    Store(#[from] crate::store::Error),
+    #[error("Could not write the pointer")]
+    Write(#[source] io::Error),
+}
+
+pub(super) mod function {
+    use std::io::{Read, Write};
+
+    use super::Error;
+    use crate::{Pointer, Store, pointer};
+
+    /// Read the content of a worktree file from `src`, put it into `store` and write the pointer to it into `out`,
+    /// returning the pointer as well.
+    ///
+    /// Content that already is a pointer is written to `out` unchanged, and empty content stays empty
+    /// with `None` being returned, both like `git-lfs` does.
+    pub fn clean(store: &Store, mut src: impl Read, mut out: impl Write) -> Result<Option<Pointer>, Error> {
+        let mut head = Vec::new();
+        src.by_ref()
+            .take(pointer::MAX_SIZE as u64 + 1)
+            .read_to_end(&mut head)
+            .map_err(Error::Read)?;
+        if head.is_empty() {
+            return Ok(None);
+        }
+        if let Ok(pointer) = Pointer::from_bytes(&head) {
+            out.write_all(&head).map_err(Error::Write)?;
+            return Ok(Some(pointer));
+        }
+
+        let pointer = store.insert(head.as_slice().chain(src))?;
+        pointer.write_to(&mut out).map_err(Error::Write)?;
+        Ok(Some(pointer))
+    }
+}
@@ -1 +1,30 @@
+//! Handle files tracked with [Git LFS](https://git-lfs.com) natively, without the `git-lfs` program.
+//!
+//! Instead of their content, the object database only contains a small [`Pointer`] to the content of such files,
+//! which is kept in a local [`Store`] under `.git/lfs/objects` and exchanged with the LFS server of a remote
+//! using its [batch API](transfer).
+//!
+//! * [`clean()`] stores the content of a worktree file and replaces it with a pointer, as needed when adding files.
+//! * [`smudge()`] replaces a pointer with the content it points to, as needed when checking out files.
+//! * [`transfer::download()`] and [`transfer::upload()`] exchange objects with a server, with multiple transfers at a time.
+#![deny(missing_docs)]
 #![forbid(unsafe_code)]
+
+///
+pub mod pointer;
+pub use pointer::Pointer;
+
+///
+pub mod store;
+pub use store::Store;
+
+///
+pub mod clean;
+pub use clean::function::clean;
+
+///
+pub mod smudge;
+pub use smudge::function::smudge;
+
+///
+pub mod transfer;
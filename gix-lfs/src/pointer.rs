@@ -0,0 +1,127 @@
+use std::io;
+
+use bstr::{BString, ByteSlice};
+
+/// The version of the pointer format written by us, and the one `git-lfs` writes.
+pub const VERSION: &str = "https://git-lfs.github.com/spec/v1";
+
+/// Versions of pointers written by pre-release versions of `git-lfs`, which are still accepted.
+const LEGACY_VERSIONS: &[&str] = &["https://hawser.github.com/spec/v1", "http://git-media.io/v/2"];
+
+/// Pointers are never larger than this many bytes, which is how much of a file needs to be read to see if it's a pointer.
+pub const MAX_SIZE: usize = 1024;
+
+/// A pointer to the content of a file that is stored outside the object database, which is what the object database
+/// contains instead of the content.
+///
+/// It's serialized like this:
+///
+/// ```text
+/// version https://git-lfs.github.com/spec/v1
+/// oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393
+/// size 12345
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pointer {
+    /// The SHA-256 hash of the content.
+    pub oid: gix_hash::ObjectId,
+    /// The size of the content in bytes.
+    pub size: u64,
+}
+
+///
+pub mod decode {
+    use bstr::BString;
+
+    /// The error returned by [`Pointer::from_bytes()`](crate::Pointer::from_bytes()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Pointers are at most {} bytes large, got {size} bytes", super::MAX_SIZE)]
+        TooLarge { size: usize },
+        #[error("Pointers must start with a version line")]
+        MissingVersion,
+        #[error("The pointer version {version:?} is unknown")]
+        UnknownVersion { version: BString },
+        #[error("The line {line:?} of the pointer is malformed")]
+        Malformed { line: BString },
+        #[error("The pointer lacks the '{key}' key")]
+        MissingKey { key: &'static str },
+        #[error("The object id {oid:?} is not a SHA-256 hash")]
+        UnsupportedOid { oid: BString },
+        #[error("The size {size:?} is not a valid number")]
+        InvalidSize { size: BString },
+    }
+}
+
+/// Lifecycle
+impl Pointer {
+    /// Parse `data` as pointer file.
+    ///
+    /// Extension keys, as well as keys unknown to us, are ignored.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, decode::Error> {
+        use decode::Error;
+        if data.len() > MAX_SIZE {
+            return Err(Error::TooLarge { size: data.len() });
+        }
+        let mut lines = data.lines();
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(b"version "))
+            .ok_or(Error::MissingVersion)?;
+        if version != VERSION.as_bytes() && !LEGACY_VERSIONS.iter().any(|v| version == v.as_bytes()) {
+            return Err(Error::UnknownVersion {
+                version: version.into(),
+            });
+        }
+
+        let (mut oid, mut size) = (None, None);
+        for line in lines {
+            let (key, value) = line
+                .split_once_str(b" ")
+                .filter(|(key, value)| !key.is_empty() && !value.is_empty())
+                .ok_or_else(|| Error::Malformed { line: line.into() })?;
+            match key {
+                b"oid" => {
+                    oid = Some(
+                        value
+                            .strip_prefix(b"sha256:")
+                            .filter(|hex| hex.len() == gix_hash::Kind::Sha256.len_in_hex())
+                            .and_then(|hex| gix_hash::ObjectId::from_hex(hex).ok())
+                            .ok_or_else(|| Error::UnsupportedOid { oid: value.into() })?,
+                    );
+                }
+                b"size" => {
+                    size = Some(
+                        value
+                            .to_str()
+                            .ok()
+                            .filter(|size| size.bytes().all(|b| b.is_ascii_digit()))
+                            .and_then(|size| size.parse().ok())
+                            .ok_or_else(|| Error::InvalidSize { size: value.into() })?,
+                    );
+                }
+                _ => {}
+            }
+        }
+        Ok(Pointer {
+            oid: oid.ok_or(Error::MissingKey { key: "oid" })?,
+            size: size.ok_or(Error::MissingKey { key: "size" })?,
+        })
+    }
+}
+
+/// Serialization
+impl Pointer {
+    /// Serialize this instance as pointer file into `out`, just like `git-lfs` would.
+    pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        write!(out, "version {VERSION}\noid sha256:{}\nsize {}\n", self.oid, self.size)
+    }
+
+    /// Return this instance serialized as pointer file.
+    pub fn to_bstring(&self) -> BString {
+        let mut buf = Vec::new();
+        self.write_to(&mut buf).expect("writing to a vector never fails");
+        buf.into()
+    }
+}
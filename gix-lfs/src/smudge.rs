@@ -0,0 +1,90 @@
+use std::io;
+
+use crate::Pointer;
+
+/// The error returned by [`smudge()`](crate::smudge()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read the pointer")]
+    Read(#[source] io::Error),
+    #[error("Could not read object {oid} from the LFS store")]
+    ReadObject { oid: gix_hash::ObjectId, source: io::Error },
+    #[error("Could not write the smudged content")]
+    Write(#[source] io::Error),
+}
+
+/// What [`smudge()`](crate::smudge()) did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The input wasn't a pointer and was written unchanged.
+    Unchanged,
+    /// The content of the object at `pointer` was written.
+    Smudged(Pointer),
+    /// The object at `pointer` isn't present in the store, so the pointer itself was written.
+    ///
+    /// It has to be [downloaded](crate::transfer::download()) first to be available.
+    Missing(Pointer),
+}
+
+pub(super) mod function {
+    use std::io::{self, Read, Write};
+
+    use super::{Error, Outcome};
+    use crate::{Pointer, Store, pointer};
+
+    /// Read a pointer from `src` and write the content of the object it points to from `store` into `out`.
+    ///
+    /// Input that isn't a pointer is written to `out` unchanged, and so is a pointer to an object that isn't
+    /// in `store`, which is what `git-lfs` does with `GIT_LFS_SKIP_SMUDGE` set.
+    pub fn smudge(store: &Store, mut src: impl Read, mut out: impl Write) -> Result<Outcome, Error> {
+        let mut head = Vec::new();
+        src.by_ref()
+            .take(pointer::MAX_SIZE as u64 + 1)
+            .read_to_end(&mut head)
+            .map_err(Error::Read)?;
+        let pointer = match Pointer::from_bytes(&head) {
+            Ok(pointer) => pointer,
+            Err(_) => {
+                out.write_all(&head).map_err(Error::Write)?;
+                copy(&mut src, &mut out)?;
+                return Ok(Outcome::Unchanged);
+            }
+        };
+
+        let mut object = match store.open(&pointer) {
+            Ok(Some(object)) => object,
+            Ok(None) => {
+                out.write_all(&head).map_err(Error::Write)?;
+                return Ok(Outcome::Missing(pointer));
+            }
+            Err(err) => {
+                return Err(Error::ReadObject {
+                    oid: pointer.oid,
+                    source: err,
+                });
+            }
+        };
+        copy(&mut object, &mut out).map_err(|err| match err {
+            Error::Read(source) => Error::ReadObject {
+                oid: pointer.oid,
+                source,
+            },
+            err => err,
+        })?;
+        Ok(Outcome::Smudged(pointer))
+    }
+
+    fn copy(src: &mut impl Read, out: &mut impl Write) -> Result<(), Error> {
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let read = match src.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(Error::Read(err)),
+            };
+            out.write_all(&buf[..read]).map_err(Error::Write)?;
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::Pointer;
+
+/// The error returned by [`Store::insert()`] and [`Store::insert_verified()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read content or write it into a temporary file")]
+    Io(#[from] io::Error),
+    #[error("Could not move the object into place at '{}'", path.display())]
+    Persist { path: PathBuf, source: io::Error },
+    #[error("Expected object {} with {} bytes, but got {} with {} bytes", expected.oid, expected.size, actual.oid, actual.size)]
+    Mismatch { expected: Pointer, actual: Pointer },
+}
+
+/// The local store of the content of LFS objects, typically at `.git/lfs`, which is shared by all worktrees of a repository.
+///
+/// Objects are stored at `objects/<hex[0..2]>/<hex[2..4]>/<hex>`, and temporary files are kept in `tmp/`,
+/// just like `git-lfs` does.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Store {
+    dir: PathBuf,
+}
+
+/// Lifecycle
+impl Store {
+    /// Create a new instance for the store at `dir`, which doesn't have to exist yet.
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Store { dir: dir.into() }
+    }
+}
+
+/// Access
+impl Store {
+    /// The directory the store is located in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Return the path at which the object with `oid` is stored, whether it exists or not.
+    pub fn object_path(&self, oid: &gix_hash::oid) -> PathBuf {
+        let hex = oid.to_hex().to_string();
+        let mut path = self.dir.join("objects");
+        path.push(&hex[..2]);
+        path.push(&hex[2..4]);
+        path.push(hex);
+        path
+    }
+
+    /// Return `true` if the object `pointer` points to is present with the expected size.
+    pub fn contains(&self, pointer: &Pointer) -> bool {
+        std::fs::metadata(self.object_path(&pointer.oid)).is_ok_and(|meta| meta.is_file() && meta.len() == pointer.size)
+    }
+
+    /// Open the object `pointer` points to for reading, or return `None` if it doesn't exist.
+    pub fn open(&self, pointer: &Pointer) -> io::Result<Option<std::fs::File>> {
+        match std::fs::File::open(self.object_path(&pointer.oid)) {
+            Ok(file) => Ok(Some(file)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Mutation
+impl Store {
+    /// Read all of `content` and store it as object, returning the pointer to it.
+    ///
+    /// Storing an object that already exists is a no-op.
+    pub fn insert(&self, content: impl Read) -> Result<Pointer, Error> {
+        let (pointer, tempfile) = self.write_tempfile(content)?;
+        self.persist(&pointer, tempfile)?;
+        Ok(pointer)
+    }
+
+    /// Read all of `content` and store it as object if it matches `expected`, as needed for objects received from
+    /// untrusted sources.
+    pub fn insert_verified(&self, expected: &Pointer, content: impl Read) -> Result<(), Error> {
+        let (actual, tempfile) = self.write_tempfile(content)?;
+        if actual != *expected {
+            return Err(Error::Mismatch {
+                expected: *expected,
+                actual,
+            });
+        }
+        self.persist(&actual, tempfile)
+    }
+
+    fn write_tempfile(
+        &self,
+        mut content: impl Read,
+    ) -> Result<(Pointer, gix_tempfile::Handle<gix_tempfile::handle::Writable>), Error> {
+        let mut tempfile = gix_tempfile::new(
+            self.dir.join("tmp"),
+            gix_tempfile::ContainingDirectory::CreateAllRaceProof(Default::default()),
+            gix_tempfile::AutoRemove::Tempfile,
+        )?;
+        let mut hasher = gix_hash::hasher(gix_hash::Kind::Sha256);
+        let mut buf = vec![0; 64 * 1024];
+        let mut size = 0;
+        loop {
+            let read = match content.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+            hasher.update(&buf[..read]);
+            tempfile.write_all(&buf[..read])?;
+            size += read as u64;
+        }
+        let oid = hasher
+            .try_finalize()
+            .expect("only SHA-1 can detect collisions and fail");
+        Ok((Pointer { oid, size }, tempfile))
+    }
+
+    fn persist(
+        &self,
+        pointer: &Pointer,
+        tempfile: gix_tempfile::Handle<gix_tempfile::handle::Writable>,
+    ) -> Result<(), Error> {
+        let path = self.object_path(&pointer.oid);
+        if self.contains(pointer) {
+            return Ok(());
+        }
+        let res = match path.parent() {
+            Some(parent) => std::fs::create_dir_all(parent),
+            None => Ok(()),
+        };
+        res.and_then(|()| tempfile.persist(&path).map(|_| ()).map_err(|err| err.error))
+            .map_err(|source| Error::Persist { path, source })
+    }
+}
@@ -0,0 +1,70 @@
+//! The JSON messages of the batch API.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The operation to ask the server about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Download,
+    Upload,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Request<'a> {
+    pub operation: Operation,
+    pub transfers: &'a [&'a str],
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub ref_name: Option<Ref<'a>>,
+    pub objects: Vec<ObjectSpec>,
+    pub hash_algo: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Ref<'a> {
+    pub name: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ObjectSpec {
+    pub oid: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Response {
+    #[serde(default)]
+    pub transfer: Option<String>,
+    #[serde(default)]
+    pub objects: Vec<Object>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Object {
+    pub oid: String,
+    #[serde(default)]
+    pub actions: BTreeMap<String, Action>,
+    #[serde(default)]
+    pub error: Option<ObjectError>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Action {
+    pub href: String,
+    #[serde(default)]
+    pub header: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ObjectError {
+    pub code: u16,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// The error response of the API for failed requests.
+#[derive(Debug, Deserialize)]
+pub struct ErrorResponse {
+    pub message: String,
+}
@@ -0,0 +1,278 @@
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+};
+
+use bstr::BString;
+
+use super::{
+    Body, Endpoint, Error, Http, MEDIA_TYPE, Method, Options, Outcome, Request, Response,
+    batch::{self, Action, Operation},
+};
+use crate::{Pointer, Store};
+
+/// Download all objects of `pointers` that aren't yet in `store` from the LFS server at `endpoint`, using `http`
+/// to perform the requests and `options` to control the transfers.
+///
+/// The content of each object is verified before it's put into `store`.
+pub fn download(
+    http: &impl Http,
+    endpoint: &Endpoint,
+    pointers: impl IntoIterator<Item = Pointer>,
+    store: &Store,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    let mut pointers: Vec<_> = pointers.into_iter().collect();
+    pointers.sort();
+    pointers.dedup();
+    let total = pointers.len();
+    pointers.retain(|pointer| !store.contains(pointer));
+    let mut out = Outcome {
+        transferred: Vec::new(),
+        skipped: total - pointers.len(),
+    };
+
+    for chunk in pointers.chunks(options.batch_size.max(1)) {
+        let actions = request_batch(http, endpoint, Operation::Download, chunk, options)?
+            .into_iter()
+            .map(|(pointer, mut actions)| {
+                actions
+                    .remove("download")
+                    .map(|action| (pointer, action))
+                    .ok_or(Error::MissingAction {
+                        oid: pointer.oid,
+                        action: "download",
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let transferred = in_parallel(&actions, options.concurrency, |(pointer, action)| {
+            let response = send(http, Method::Get, action, Vec::new(), Body::Empty)?;
+            store.insert_verified(pointer, response.body)?;
+            Ok(*pointer)
+        })?;
+        out.transferred.extend(transferred);
+    }
+    out.transferred.sort();
+    Ok(out)
+}
+
+/// Upload all objects of `pointers` from `store` to the LFS server at `endpoint` unless it already has them, using `http`
+/// to perform the requests and `options` to control the transfers.
+///
+/// All objects must be present in `store`.
+pub fn upload(
+    http: &impl Http,
+    endpoint: &Endpoint,
+    pointers: impl IntoIterator<Item = Pointer>,
+    store: &Store,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    let mut pointers: Vec<_> = pointers.into_iter().collect();
+    pointers.sort();
+    pointers.dedup();
+    if let Some(missing) = pointers.iter().find(|pointer| !store.contains(pointer)) {
+        return Err(Error::MissingObject { oid: missing.oid });
+    }
+    let mut out = Outcome::default();
+
+    for chunk in pointers.chunks(options.batch_size.max(1)) {
+        let mut actions = Vec::new();
+        for (pointer, mut object_actions) in request_batch(http, endpoint, Operation::Upload, chunk, options)? {
+            match object_actions.remove("upload") {
+                Some(upload) => actions.push((pointer, upload, object_actions.remove("verify"))),
+                None => out.skipped += 1,
+            }
+        }
+        let transferred = in_parallel(&actions, options.concurrency, |(pointer, upload, verify)| {
+            let object = store
+                .open(pointer)
+                .and_then(|object| {
+                    object.ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "object vanished"))
+                })
+                .map_err(|err| Error::ReadObject {
+                    oid: pointer.oid,
+                    source: err,
+                })?;
+            send(
+                http,
+                Method::Put,
+                upload,
+                vec![("Content-Type".into(), "application/octet-stream".into())],
+                Body::Read {
+                    read: Box::new(object),
+                    len: pointer.size,
+                },
+            )?;
+            if let Some(verify) = verify {
+                let body = serde_json::to_vec(&batch::ObjectSpec {
+                    oid: pointer.oid.to_string(),
+                    size: pointer.size,
+                })?;
+                send(http, Method::Post, verify, json_headers(), Body::Bytes(body))?;
+            }
+            Ok(*pointer)
+        })?;
+        out.transferred.extend(transferred);
+    }
+    out.transferred.sort();
+    Ok(out)
+}
+
+/// The actions of an object by name, like `download`.
+type Actions = BTreeMap<String, Action>;
+
+/// Ask the server about `objects` for `operation` and return the actions for each of them.
+fn request_batch(
+    http: &impl Http,
+    endpoint: &Endpoint,
+    operation: Operation,
+    objects: &[Pointer],
+    options: &Options,
+) -> Result<Vec<(Pointer, Actions)>, Error> {
+    let body = serde_json::to_vec(&batch::Request {
+        operation,
+        transfers: &["basic"],
+        ref_name: options.ref_name.as_deref().map(|name| batch::Ref { name }),
+        objects: objects
+            .iter()
+            .map(|pointer| batch::ObjectSpec {
+                oid: pointer.oid.to_string(),
+                size: pointer.size,
+            })
+            .collect(),
+        hash_algo: "sha256",
+    })?;
+    let url = format!("{}/objects/batch", endpoint.url.trim_end_matches('/'));
+    let mut headers = json_headers();
+    headers.extend(endpoint.headers.iter().cloned());
+    let response = perform(
+        http,
+        Request {
+            method: Method::Post,
+            url,
+            headers,
+            body: Body::Bytes(body),
+        },
+    )?;
+    let response: batch::Response = serde_json::from_reader(response.body)?;
+    if let Some(name) = response.transfer.filter(|name| name != "basic") {
+        return Err(Error::UnsupportedTransfer { name });
+    }
+
+    response
+        .objects
+        .into_iter()
+        .filter_map(|object| {
+            let oid = match gix_hash::ObjectId::from_hex(object.oid.as_bytes()) {
+                Ok(oid) => oid,
+                Err(_) => return Some(Err(Error::InvalidOid { oid: object.oid })),
+            };
+            // Only objects we asked for are considered, with the size we know.
+            let pointer = *objects.iter().find(|pointer| pointer.oid == oid)?;
+            Some(match object.error {
+                Some(err) => Err(Error::Object {
+                    oid,
+                    code: err.code,
+                    message: err.message,
+                }),
+                None => Ok((pointer, object.actions)),
+            })
+        })
+        .collect()
+}
+
+fn json_headers() -> Vec<(String, String)> {
+    vec![
+        ("Accept".into(), MEDIA_TYPE.into()),
+        ("Content-Type".into(), MEDIA_TYPE.into()),
+    ]
+}
+
+/// Perform `action` with `method`, `headers` and `body`.
+fn send(
+    http: &impl Http,
+    method: Method,
+    action: &Action,
+    mut headers: Vec<(String, String)>,
+    body: Body,
+) -> Result<Response, Error> {
+    headers.extend(action.header.iter().map(|(name, value)| (name.clone(), value.clone())));
+    perform(
+        http,
+        Request {
+            method,
+            url: action.href.clone(),
+            headers,
+            body,
+        },
+    )
+}
+
+/// Perform `request` and fail if the response doesn't indicate success.
+fn perform(http: &impl Http, request: Request) -> Result<Response, Error> {
+    let url = request.url.clone();
+    let mut response = http.request(request).map_err(|err| Error::Http {
+        url: url.clone(),
+        source: err,
+    })?;
+    if !(200..300).contains(&response.status) {
+        let mut body = Vec::new();
+        response
+            .body
+            .read_to_end(&mut body)
+            .map_err(|err| Error::ReadResponse {
+                url: url.clone(),
+                source: err,
+            })?;
+        let message = serde_json::from_slice::<batch::ErrorResponse>(&body)
+            .map_or_else(|_| BString::from(body), |err| err.message.into());
+        return Err(Error::Status {
+            url,
+            status: response.status,
+            message,
+        });
+    }
+    Ok(response)
+}
+
+/// Call `transfer` on each of `items` with up to `concurrency` threads, and return the results in no particular order.
+///
+/// Upon the first error, no new transfer is started and the error is returned once the ongoing ones finished.
+fn in_parallel<T: Sync>(
+    items: &[T],
+    concurrency: usize,
+    transfer: impl Fn(&T) -> Result<Pointer, Error> + Sync,
+) -> Result<Vec<Pointer>, Error> {
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let out = Mutex::new(Vec::with_capacity(items.len()));
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..concurrency.clamp(1, items.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| -> Result<(), Error> {
+                    while !failed.load(Ordering::Relaxed) {
+                        let Some(item) = items.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                            break;
+                        };
+                        match transfer(item) {
+                            Ok(pointer) => out.lock().expect("no panic while holding the lock").push(pointer),
+                            Err(err) => {
+                                failed.store(true, Ordering::Relaxed);
+                                return Err(err);
+                            }
+                        }
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .try_for_each(|thread| thread.join().expect("transfers don't panic"))
+    })?;
+    Ok(out.into_inner().expect("no panic while holding the lock"))
+}
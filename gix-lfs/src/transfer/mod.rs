@@ -0,0 +1,205 @@
+//! Exchange objects with an LFS server using its [batch API](https://github.com/git-lfs/git-lfs/blob/main/docs/api/batch.md).
+//!
+//! For each batch of objects, the server is asked where to download them from or upload them to,
+//! and the objects are then transferred with the `basic` transfer adapter, [`Options::concurrency`] at a time.
+//! The HTTP requests themselves are performed by an implementation of [`Http`].
+use std::io::Read;
+
+use bstr::BString;
+
+use crate::Pointer;
+
+mod batch;
+mod function;
+pub use function::{download, upload};
+
+#[cfg(feature = "http-client-reqwest")]
+mod reqwest;
+#[cfg(feature = "http-client-reqwest")]
+pub use self::reqwest::Client;
+
+/// The media type of requests to and responses of the batch API.
+pub const MEDIA_TYPE: &str = "application/vnd.git-lfs+json";
+
+/// The HTTP method of a [`Request`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    /// Used to download objects.
+    Get,
+    /// Used for batch requests and to verify uploads.
+    Post,
+    /// Used to upload objects.
+    Put,
+}
+
+impl Method {
+    /// Return the name of the method as used in HTTP requests.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+        }
+    }
+}
+
+/// The body of a [`Request`].
+pub enum Body {
+    /// There is no body.
+    Empty,
+    /// The body is fully in memory.
+    Bytes(Vec<u8>),
+    /// The body is streamed from `read`, which provides exactly `len` bytes.
+    Read {
+        /// The source of the body.
+        read: Box<dyn Read + Send>,
+        /// The amount of bytes `read` provides, for use in the `Content-Length` header.
+        len: u64,
+    },
+}
+
+/// An HTTP request to be performed by an [`Http`] implementation.
+pub struct Request {
+    /// The method to use.
+    pub method: Method,
+    /// The URL to send the request to.
+    pub url: String,
+    /// Additional headers as pairs of name and value.
+    pub headers: Vec<(String, String)>,
+    /// The body to send.
+    pub body: Body,
+}
+
+/// The response to a [`Request`].
+pub struct Response {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The body of the response.
+    pub body: Box<dyn Read + Send>,
+}
+
+/// The error returned by an [`Http`] implementation.
+pub type HttpError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// A blocking HTTP client to perform requests with.
+///
+/// It's shared by all threads that perform transfers at the same time.
+pub trait Http: Sync {
+    /// Perform `request` and return the response, regardless of its status code.
+    fn request(&self, request: Request) -> Result<Response, HttpError>;
+}
+
+/// The location of the LFS server of a remote.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    /// The base URL of the server API, like `https://example.com/repo.git/info/lfs`.
+    pub url: String,
+    /// Headers to send with each batch request, like `Authorization`.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Endpoint {
+    /// Derive the endpoint from the `url` of a remote just like `git-lfs` does if `lfs.url` isn't set,
+    /// or return `None` if there is no way to talk to a server via HTTP at `url`, like for local paths.
+    ///
+    /// For `https://example.com/repo` this is `https://example.com/repo.git/info/lfs`, and SSH URLs
+    /// like `git@example.com:repo.git` are turned into the same HTTPS URL.
+    pub fn from_remote_url(url: &str) -> Option<Self> {
+        let url = if url.starts_with("https://") || url.starts_with("http://") {
+            url.to_owned()
+        } else if let Some(rest) = url.strip_prefix("ssh://") {
+            let (host, path) = rest.split_once('/')?;
+            let host = host.rsplit_once('@').map_or(host, |(_user, host)| host);
+            let host = host.split_once(':').map_or(host, |(host, _port)| host);
+            format!("https://{host}/{path}")
+        } else if !url.contains("://") {
+            let (host, path) = url.split_once(':')?;
+            let host = host.rsplit_once('@').map_or(host, |(_user, host)| host);
+            if host.is_empty() || host.contains('/') {
+                return None;
+            }
+            format!("https://{host}/{}", path.trim_start_matches('/'))
+        } else {
+            return None;
+        };
+        let url = url.trim_end_matches('/');
+        let url = match url.strip_suffix(".git") {
+            Some(_) => format!("{url}/info/lfs"),
+            None => format!("{url}.git/info/lfs"),
+        };
+        Some(Endpoint {
+            url,
+            headers: Vec::new(),
+        })
+    }
+}
+
+/// Configure how to transfer objects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// The amount of objects to transfer at the same time, like `lfs.concurrentTransfers`.
+    pub concurrency: usize,
+    /// The maximum amount of objects to ask the server about in a single batch request.
+    pub batch_size: usize,
+    /// The name of the reference the objects are transferred for, like `refs/heads/main`, which servers may use for
+    /// authorization.
+    pub ref_name: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            concurrency: 8,
+            batch_size: 100,
+            ref_name: None,
+        }
+    }
+}
+
+/// What was done by [`download()`] or [`upload()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outcome {
+    /// The objects that were transferred, sorted by id.
+    pub transferred: Vec<Pointer>,
+    /// The amount of objects that didn't need a transfer, as they were already present locally when downloading,
+    /// or on the server when uploading.
+    pub skipped: usize,
+}
+
+/// The error returned by [`download()`] and [`upload()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The request to '{url}' failed")]
+    Http { url: String, source: HttpError },
+    #[error("The request to '{url}' failed with status {status}: {message:?}")]
+    Status { url: String, status: u16, message: BString },
+    #[error("Could not read the response of '{url}'")]
+    ReadResponse { url: String, source: std::io::Error },
+    #[error("The batch request could not be serialized or its response could not be deserialized")]
+    Json(#[from] serde_json::Error),
+    #[error("The server wants to use the unsupported transfer adapter '{name}'")]
+    UnsupportedTransfer { name: String },
+    #[error("The server responded with an invalid object id: {oid:?}")]
+    InvalidOid { oid: String },
+    #[error("The server reported an error for object {oid} with code {code}: {message}")]
+    Object {
+        oid: gix_hash::ObjectId,
+        code: u16,
+        message: String,
+    },
+    #[error("The server didn't provide the '{action}' action for object {oid}")]
+    MissingAction {
+        oid: gix_hash::ObjectId,
+        action: &'static str,
+    },
+    #[error("Object {oid} can't be uploaded as it isn't in the local store")]
+    MissingObject { oid: gix_hash::ObjectId },
+    #[error("Could not read object {oid} from the local store")]
+    ReadObject {
+        oid: gix_hash::ObjectId,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Store(#[from] crate::store::Error),
+}
@@ -0,0 +1,44 @@
+use super::{Body, Http, HttpError, Method, Request, Response};
+
+/// An [`Http`] implementation using a blocking `reqwest` client.
+#[derive(Debug, Clone)]
+pub struct Client {
+    client: reqwest::blocking::Client,
+}
+
+impl Client {
+    /// Create a new instance without timeout, as transfers of large objects can take a long time.
+    pub fn new() -> Result<Self, reqwest::Error> {
+        Ok(reqwest::blocking::Client::builder().timeout(None).build()?.into())
+    }
+}
+
+impl From<reqwest::blocking::Client> for Client {
+    fn from(client: reqwest::blocking::Client) -> Self {
+        Client { client }
+    }
+}
+
+impl Http for Client {
+    fn request(&self, request: Request) -> Result<Response, HttpError> {
+        let method = match request.method {
+            Method::Get => reqwest::Method::GET,
+            Method::Post => reqwest::Method::POST,
+            Method::Put => reqwest::Method::PUT,
+        };
+        let mut builder = self.client.request(method, &request.url);
+        for (name, value) in request.headers {
+            builder = builder.header(name, value);
+        }
+        builder = match request.body {
+            Body::Empty => builder,
+            Body::Bytes(bytes) => builder.body(bytes),
+            Body::Read { read, len } => builder.body(reqwest::blocking::Body::sized(read, len)),
+        };
+        let response = builder.send()?;
+        Ok(Response {
+            status: response.status().as_u16(),
+            body: Box::new(response),
+        })
+    }
+}
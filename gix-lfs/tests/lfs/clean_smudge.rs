@@ -0,0 +1,111 @@
+use gix_lfs::{Pointer, smudge::Outcome, store};
+
+use crate::{hello_world, store};
+
+#[test]
+fn clean_stores_content_like_git_lfs() -> crate::Result {
+    let (store, _tmp) = store()?;
+    let mut out = Vec::new();
+    let pointer = gix_lfs::clean(&store, &b"hello world"[..], &mut out)?;
+    assert_eq!(pointer, Some(hello_world()));
+    assert_eq!(out, hello_world().to_bstring());
+
+    let path = store.object_path(&hello_world().oid);
+    assert_eq!(
+        path.strip_prefix(store.dir())?,
+        std::path::Path::new("objects/b9/4d/b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
+    );
+    assert_eq!(std::fs::read(path)?, b"hello world");
+    assert!(store.contains(&hello_world()));
+    assert!(!store.contains(&Pointer {
+        size: 10,
+        ..hello_world()
+    }));
+
+    out.clear();
+    assert_eq!(
+        gix_lfs::clean(&store, &b"hello world"[..], &mut out)?,
+        Some(hello_world()),
+        "cleaning existing content again is fine"
+    );
+    assert!(
+        std::fs::read_dir(store.dir().join("tmp"))?.next().is_none(),
+        "no temporary file is left behind"
+    );
+    Ok(())
+}
+
+#[test]
+fn clean_passes_pointers_and_empty_content_through() -> crate::Result {
+    let (store, _tmp) = store()?;
+    let pointer = hello_world().to_bstring();
+    let mut out = Vec::new();
+    assert_eq!(
+        gix_lfs::clean(&store, pointer.as_slice(), &mut out)?,
+        Some(hello_world())
+    );
+    assert_eq!(out, pointer);
+    assert!(!store.contains(&hello_world()), "pointers aren't stored");
+
+    out.clear();
+    assert_eq!(gix_lfs::clean(&store, &b""[..], &mut out)?, None);
+    assert!(out.is_empty(), "empty files stay empty");
+    Ok(())
+}
+
+#[test]
+fn clean_large_content() -> crate::Result {
+    let (store, _tmp) = store()?;
+    let content: Vec<u8> = (0..200_000u32).map(|n| (n % 251) as u8).collect();
+    let mut out = Vec::new();
+    let pointer = gix_lfs::clean(&store, content.as_slice(), &mut out)?.expect("not empty");
+    assert_eq!(pointer.size, content.len() as u64);
+
+    let mut smudged = Vec::new();
+    assert_eq!(
+        gix_lfs::smudge(&store, out.as_slice(), &mut smudged)?,
+        Outcome::Smudged(pointer)
+    );
+    assert_eq!(smudged, content);
+    Ok(())
+}
+
+#[test]
+fn smudge() -> crate::Result {
+    let (store, _tmp) = store()?;
+    let pointer = hello_world().to_bstring();
+    let mut out = Vec::new();
+    assert_eq!(
+        gix_lfs::smudge(&store, pointer.as_slice(), &mut out)?,
+        Outcome::Missing(hello_world())
+    );
+    assert_eq!(out, pointer, "missing objects leave the pointer in place");
+
+    store.insert(&b"hello world"[..])?;
+    out.clear();
+    assert_eq!(
+        gix_lfs::smudge(&store, pointer.as_slice(), &mut out)?,
+        Outcome::Smudged(hello_world())
+    );
+    assert_eq!(out, b"hello world");
+
+    out.clear();
+    assert_eq!(
+        gix_lfs::smudge(&store, &b"not a pointer"[..], &mut out)?,
+        Outcome::Unchanged
+    );
+    assert_eq!(out, b"not a pointer");
+    Ok(())
+}
+
+#[test]
+fn insert_verified() -> crate::Result {
+    let (store, _tmp) = store()?;
+    let err = store.insert_verified(&hello_world(), &b"hello there"[..]).unwrap_err();
+    assert!(matches!(err, store::Error::Mismatch { .. }));
+    assert!(!store.contains(&hello_world()));
+
+    store.insert_verified(&hello_world(), &b"hello world"[..])?;
+    assert!(store.contains(&hello_world()));
+    Ok(())
+}
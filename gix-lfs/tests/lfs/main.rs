@@ -0,0 +1,23 @@
+use gix_lfs::{Pointer, Store};
+pub use gix_testtools::Result;
+
+mod clean_smudge;
+mod pointer;
+mod transfer;
+
+/// The pointer to `hello world`.
+fn hello_world() -> Pointer {
+    Pointer {
+        oid: oid("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"),
+        size: 11,
+    }
+}
+
+fn oid(hex: &str) -> gix_hash::ObjectId {
+    gix_hash::ObjectId::from_hex(hex.as_bytes()).expect("valid SHA-256 hex")
+}
+
+fn store() -> Result<(Store, gix_testtools::tempfile::TempDir)> {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    Ok((Store::at(tmp.path().join("lfs")), tmp))
+}
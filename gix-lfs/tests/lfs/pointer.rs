@@ -0,0 +1,76 @@
+use gix_lfs::{Pointer, pointer::decode::Error};
+
+use crate::hello_world;
+
+const HELLO_WORLD: &str = "version https://git-lfs.github.com/spec/v1
+oid sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9
+size 11
+";
+
+#[test]
+fn round_trip() -> crate::Result {
+    let pointer = Pointer::from_bytes(HELLO_WORLD.as_bytes())?;
+    assert_eq!(pointer, hello_world());
+    assert_eq!(
+        pointer.to_bstring(),
+        HELLO_WORLD,
+        "this is exactly what `git-lfs` writes"
+    );
+    Ok(())
+}
+
+#[test]
+fn legacy_versions_and_extensions_are_accepted() -> crate::Result {
+    for version in ["https://hawser.github.com/spec/v1", "http://git-media.io/v/2"] {
+        let data = HELLO_WORLD.replace("https://git-lfs.github.com/spec/v1", version);
+        assert_eq!(Pointer::from_bytes(data.as_bytes())?, hello_world());
+    }
+
+    let data = "version https://git-lfs.github.com/spec/v1
+ext-0-foo sha256:0000000000000000000000000000000000000000000000000000000000000000
+oid sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9
+size 11";
+    assert_eq!(
+        Pointer::from_bytes(data.as_bytes())?,
+        hello_world(),
+        "extensions are ignored, and so is the lack of a trailing newline"
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_pointers() {
+    fn err(data: &str) -> Error {
+        Pointer::from_bytes(data.as_bytes()).unwrap_err()
+    }
+    const V1: &str = "version https://git-lfs.github.com/spec/v1\n";
+
+    assert!(matches!(err(""), Error::MissingVersion));
+    assert!(matches!(err("hello world"), Error::MissingVersion));
+    assert!(matches!(
+        err("version https://example.com/spec/v2\n"),
+        Error::UnknownVersion { .. }
+    ));
+    assert!(matches!(
+        err(&format!("{V1}size 11\n")),
+        Error::MissingKey { key: "oid" }
+    ));
+    assert!(matches!(
+        err(&format!(
+            "{V1}oid sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\n"
+        )),
+        Error::MissingKey { key: "size" }
+    ));
+    assert!(matches!(
+        err(&format!("{V1}oid sha1:b94d27b9\n")),
+        Error::UnsupportedOid { .. }
+    ));
+    assert!(matches!(err(&format!("{V1}size -1\n")), Error::InvalidSize { .. }));
+    assert!(matches!(err(&format!("{V1}size\n")), Error::Malformed { .. }));
+
+    let large = format!("{HELLO_WORLD}{}", "x".repeat(gix_lfs::pointer::MAX_SIZE));
+    assert!(matches!(
+        Pointer::from_bytes(large.as_bytes()),
+        Err(Error::TooLarge { .. })
+    ));
+}
@@ -0,0 +1,238 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use gix_lfs::{
+    Pointer,
+    transfer::{Client, Endpoint, Error, Options, download, upload},
+};
+
+use crate::{hello_world, store};
+
+mod server;
+use server::Server;
+
+/// Create `count` distinct contents along with the pointers to them.
+fn contents(count: usize) -> crate::Result<Vec<(Pointer, Vec<u8>)>> {
+    let (store, _tmp) = store()?;
+    (0..count)
+        .map(|n| {
+            let content = format!("content of object {n}\n").into_bytes();
+            Ok((store.insert(content.as_slice())?, content))
+        })
+        .collect()
+}
+
+fn serve(contents: &[(Pointer, Vec<u8>)]) -> crate::Result<Server> {
+    Ok(Server::start(
+        contents
+            .iter()
+            .map(|(pointer, content)| (pointer.oid.to_string(), content.clone())),
+        Duration::from_millis(100),
+    )?)
+}
+
+fn options(concurrency: usize, batch_size: usize) -> Options {
+    Options {
+        concurrency,
+        batch_size,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn endpoint_from_remote_url() {
+    for (url, expected) in [
+        (
+            "https://example.com/repo",
+            Some("https://example.com/repo.git/info/lfs"),
+        ),
+        (
+            "https://example.com/repo.git",
+            Some("https://example.com/repo.git/info/lfs"),
+        ),
+        (
+            "https://example.com/repo.git/",
+            Some("https://example.com/repo.git/info/lfs"),
+        ),
+        (
+            "http://example.com:8080/repo",
+            Some("http://example.com:8080/repo.git/info/lfs"),
+        ),
+        (
+            "git@example.com:org/repo.git",
+            Some("https://example.com/org/repo.git/info/lfs"),
+        ),
+        (
+            "ssh://git@example.com:22/org/repo",
+            Some("https://example.com/org/repo.git/info/lfs"),
+        ),
+        ("/path/to/repo", None),
+        ("file:///path/to/repo", None),
+    ] {
+        assert_eq!(
+            Endpoint::from_remote_url(url).map(|endpoint| endpoint.url).as_deref(),
+            expected,
+            "{url}"
+        );
+    }
+}
+
+#[test]
+fn download_in_parallel_and_in_batches() -> crate::Result {
+    let contents = contents(7)?;
+    let server = serve(&contents)?;
+    let (store, _tmp) = store()?;
+    store.insert(contents[0].1.as_slice())?;
+
+    let out = download(
+        &Client::new()?,
+        &server.endpoint(),
+        contents.iter().map(|(pointer, _)| *pointer).chain(Some(contents[1].0)),
+        &store,
+        &options(4, 4),
+    )?;
+    assert_eq!(out.skipped, 1, "objects that are already present aren't downloaded");
+    let mut expected: Vec<_> = contents[1..].iter().map(|(pointer, _)| *pointer).collect();
+    expected.sort();
+    assert_eq!(out.transferred, expected, "duplicates are only downloaded once");
+    for (pointer, content) in &contents {
+        assert_eq!(&std::fs::read(store.object_path(&pointer.oid))?, content);
+    }
+
+    assert_eq!(
+        server.state.batches.load(Ordering::SeqCst),
+        2,
+        "6 objects are requested in batches of 4"
+    );
+    let peak = server.state.peak.load(Ordering::SeqCst);
+    assert!(
+        (2..=4).contains(&peak),
+        "up to 4 downloads happen at the same time, got {peak}"
+    );
+    Ok(())
+}
+
+#[test]
+fn download_verifies_content() -> crate::Result {
+    let server = Server::start(
+        Some((hello_world().oid.to_string(), b"hello there".to_vec())),
+        Duration::ZERO,
+    )?;
+    let (store, _tmp) = store()?;
+    let err = download(
+        &Client::new()?,
+        &server.endpoint(),
+        Some(hello_world()),
+        &store,
+        &Options::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::Store(gix_lfs::store::Error::Mismatch { .. })));
+    assert!(!store.contains(&hello_world()));
+    Ok(())
+}
+
+#[test]
+fn download_of_missing_object_fails() -> crate::Result {
+    let server = serve(&[])?;
+    let (store, _tmp) = store()?;
+    let err = download(
+        &Client::new()?,
+        &server.endpoint(),
+        Some(hello_world()),
+        &store,
+        &Options::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::Object { code: 404, .. }), "{err:?}");
+    Ok(())
+}
+
+#[test]
+fn unauthorized_requests_fail_with_the_message_of_the_server() -> crate::Result {
+    let server = serve(&[])?;
+    let (store, _tmp) = store()?;
+    let endpoint = Endpoint {
+        headers: Vec::new(),
+        ..server.endpoint()
+    };
+    let err = download(
+        &Client::new()?,
+        &endpoint,
+        Some(hello_world()),
+        &store,
+        &Options::default(),
+    )
+    .unwrap_err();
+    match err {
+        Error::Status { status, message, .. } => {
+            assert_eq!(status, 401);
+            assert_eq!(message, "Credentials needed");
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn upload_in_parallel_with_verification() -> crate::Result {
+    let contents = contents(6)?;
+    let server = serve(&contents[..1])?;
+    let (store, _tmp) = store()?;
+    for (_, content) in &contents {
+        store.insert(content.as_slice())?;
+    }
+
+    let out = upload(
+        &Client::new()?,
+        &server.endpoint(),
+        contents.iter().map(|(pointer, _)| *pointer),
+        &store,
+        &options(3, 100),
+    )?;
+    assert_eq!(out.skipped, 1, "the server already has the first object");
+    let mut expected: Vec<_> = contents[1..].iter().map(|(pointer, _)| *pointer).collect();
+    expected.sort();
+    assert_eq!(out.transferred, expected);
+    for (pointer, content) in &contents {
+        assert_eq!(server.object(&pointer.oid).as_ref(), Some(content));
+    }
+
+    let mut verified = server.state.verified.lock().unwrap().clone();
+    verified.sort();
+    assert_eq!(
+        verified,
+        expected
+            .iter()
+            .map(|pointer| pointer.oid.to_string())
+            .collect::<Vec<_>>(),
+        "each upload is verified"
+    );
+    assert_eq!(server.state.batches.load(Ordering::SeqCst), 1);
+    let peak = server.state.peak.load(Ordering::SeqCst);
+    assert!(
+        (2..=3).contains(&peak),
+        "up to 3 uploads happen at the same time, got {peak}"
+    );
+    Ok(())
+}
+
+#[test]
+fn upload_needs_all_objects_locally() -> crate::Result {
+    let server = serve(&[])?;
+    let (store, _tmp) = store()?;
+    let err = upload(
+        &Client::new()?,
+        &server.endpoint(),
+        Some(hello_world()),
+        &store,
+        &Options::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::MissingObject { .. }));
+    assert_eq!(
+        server.state.batches.load(Ordering::SeqCst),
+        0,
+        "the server isn't contacted"
+    );
+    Ok(())
+}
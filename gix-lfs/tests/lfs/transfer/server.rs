@@ -0,0 +1,198 @@
+//! A minimal LFS server implementing the batch API and the `basic` transfer adapter over HTTP/1.1.
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use serde_json::{Value, json};
+
+/// The token clients have to authorize with, both for batch requests and for transfers.
+pub const TOKEN: &str = "Bearer secret";
+
+#[derive(Default)]
+pub struct State {
+    /// Object content by hex id.
+    pub objects: Mutex<BTreeMap<String, Vec<u8>>>,
+    /// The hex ids of verified uploads.
+    pub verified: Mutex<Vec<String>>,
+    /// The amount of batch requests received.
+    pub batches: AtomicUsize,
+    /// How long each transfer takes at least.
+    pub delay: Duration,
+    active: AtomicUsize,
+    /// The most transfers that were performed at the same time.
+    pub peak: AtomicUsize,
+}
+
+pub struct Server {
+    pub state: Arc<State>,
+    base: String,
+}
+
+impl Server {
+    /// Start a server in the background that keeps running until the end of the process.
+    pub fn start(objects: impl IntoIterator<Item = (String, Vec<u8>)>, delay: Duration) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let base = format!("http://{}", listener.local_addr()?);
+        let state = Arc::new(State {
+            objects: Mutex::new(objects.into_iter().collect()),
+            delay,
+            ..Default::default()
+        });
+        std::thread::spawn({
+            let (state, base) = (state.clone(), base.clone());
+            move || {
+                for stream in listener.incoming().flatten() {
+                    let (state, base) = (state.clone(), base.clone());
+                    std::thread::spawn(move || handle(stream, &state, &base));
+                }
+            }
+        });
+        Ok(Server { state, base })
+    }
+
+    /// The URL of the LFS API of the repository served at `/repo.git`.
+    pub fn endpoint(&self) -> gix_lfs::transfer::Endpoint {
+        gix_lfs::transfer::Endpoint {
+            headers: vec![("Authorization".into(), TOKEN.into())],
+            ..gix_lfs::transfer::Endpoint::from_remote_url(&format!("{}/repo", self.base)).expect("HTTP URLs are valid")
+        }
+    }
+
+    pub fn object(&self, oid: &gix_hash::oid) -> Option<Vec<u8>> {
+        self.state.objects.lock().unwrap().get(&oid.to_string()).cloned()
+    }
+}
+
+fn handle(stream: TcpStream, state: &State, base: &str) {
+    let mut reader = BufReader::new(stream.try_clone().expect("can clone sockets"));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).expect("valid request");
+    let mut headers = BTreeMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).expect("valid header");
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').expect("valid header");
+        headers.insert(name.to_ascii_lowercase(), value.trim().to_owned());
+    }
+    let mut body = vec![
+        0;
+        headers
+            .get("content-length")
+            .map_or(0, |len| len.parse().expect("number"))
+    ];
+    reader.read_exact(&mut body).expect("complete body");
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().expect("method"), parts.next().expect("path"));
+    let (status, content) = if headers.get("authorization").map(String::as_str) != Some(TOKEN) {
+        (401, json!({"message": "Credentials needed"}).to_string().into_bytes())
+    } else {
+        route(state, base, method, path, body)
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        content.len()
+    )
+    .and_then(|()| stream.write_all(&content))
+    .expect("can write response");
+}
+
+fn route(state: &State, base: &str, method: &str, path: &str, body: Vec<u8>) -> (u16, Vec<u8>) {
+    match (method, path) {
+        ("POST", "/repo.git/info/lfs/objects/batch") => {
+            state.batches.fetch_add(1, Ordering::SeqCst);
+            (
+                200,
+                batch(state, base, serde_json::from_slice(&body).expect("valid JSON")),
+            )
+        }
+        ("POST", "/verify") => {
+            let object: Value = serde_json::from_slice(&body).expect("valid JSON");
+            let oid = object["oid"].as_str().expect("oid").to_owned();
+            let has_object = state.objects.lock().unwrap().contains_key(&oid);
+            state.verified.lock().unwrap().push(oid);
+            (if has_object { 200 } else { 404 }, Vec::new())
+        }
+        (method, path) => {
+            let Some(oid) = path.strip_prefix("/objects/") else {
+                return (404, Vec::new());
+            };
+            let _transfer = Transfer::new(state);
+            std::thread::sleep(state.delay);
+            let mut objects = state.objects.lock().unwrap();
+            match method {
+                "GET" => objects.get(oid).map_or((404, Vec::new()), |data| (200, data.clone())),
+                "PUT" => {
+                    objects.insert(oid.to_owned(), body);
+                    (200, Vec::new())
+                }
+                _ => (405, Vec::new()),
+            }
+        }
+    }
+}
+
+fn batch(state: &State, base: &str, request: Value) -> Vec<u8> {
+    let objects = state.objects.lock().unwrap();
+    let auth = json!({"Authorization": TOKEN});
+    let objects: Vec<_> = request["objects"]
+        .as_array()
+        .expect("objects")
+        .iter()
+        .map(|object| {
+            let oid = object["oid"].as_str().expect("oid");
+            let size = &object["size"];
+            let href = format!("{base}/objects/{oid}");
+            match (
+                request["operation"].as_str().expect("operation"),
+                objects.contains_key(oid),
+            ) {
+                ("download", true) => {
+                    json!({"oid": oid, "size": size, "actions": {"download": {"href": href, "header": auth}}})
+                }
+                ("download", false) => {
+                    json!({"oid": oid, "size": size, "error": {"code": 404, "message": "Object does not exist"}})
+                }
+                ("upload", true) => json!({"oid": oid, "size": size}),
+                ("upload", false) => json!({"oid": oid, "size": size, "actions": {
+                    "upload": {"href": href, "header": auth},
+                    "verify": {"href": format!("{base}/verify"), "header": auth}
+                }}),
+                (operation, _) => panic!("unknown operation: {operation}"),
+            }
+        })
+        .collect();
+    json!({"transfer": "basic", "objects": objects, "hash_algo": "sha256"})
+        .to_string()
+        .into_bytes()
+}
+
+/// Keeps track of the amount of ongoing transfers.
+struct Transfer<'a>(&'a State);
+
+impl<'a> Transfer<'a> {
+    fn new(state: &'a State) -> Self {
+        let active = state.active.fetch_add(1, Ordering::SeqCst) + 1;
+        state.peak.fetch_max(active, Ordering::SeqCst);
+        Transfer(state)
+    }
+}
+
+impl Drop for Transfer<'_> {
+    fn drop(&mut self) {
+        self.0.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,129 @@
+use std::borrow::Cow;
+
+use base64::Engine;
+use bstr::{BStr, BString, ByteSlice};
+
+use crate::info::Error;
+
+/// Decode `data` encoded as `quoted-printable`, with `_` meaning space if it's part of a `header`.
+/// Invalid escapes are kept as is.
+pub(crate) fn quoted_printable(data: &[u8], header: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut pos = 0;
+    while pos < data.len() {
+        match data[pos] {
+            b'=' => {
+                let rest = &data[pos + 1..];
+                if let Some(len) = [b"\r\n".as_slice(), b"\n"]
+                    .into_iter()
+                    .find_map(|newline| rest.starts_with(newline).then_some(newline.len()))
+                {
+                    pos += 1 + len;
+                    continue;
+                }
+                match rest.get(..2).and_then(|hex| Some((hex_digit(hex[0])?, hex_digit(hex[1])?))) {
+                    Some((high, low)) => {
+                        out.push(high << 4 | low);
+                        pos += 3;
+                    }
+                    None => {
+                        out.push(b'=');
+                        pos += 1;
+                    }
+                }
+            }
+            b'_' if header => {
+                out.push(b' ');
+                pos += 1;
+            }
+            byte => {
+                out.push(byte);
+                pos += 1;
+            }
+        }
+    }
+    out
+}
+
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+/// Decode `data` encoded as `base64`, ignoring all whitespace.
+pub(crate) fn base64(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let data: Vec<u8> = data.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+    Ok(base64::engine::general_purpose::STANDARD.decode(data)?)
+}
+
+/// Convert `data` in `charset` to UTF-8, or return it unchanged if it's UTF-8 already.
+pub(crate) fn to_utf8<'a>(data: &'a [u8], charset: &BStr) -> Result<Cow<'a, [u8]>, Error> {
+    let encoding = encoding_rs::Encoding::for_label(charset.trim()).ok_or_else(|| Error::UnknownCharset {
+        charset: charset.to_owned(),
+    })?;
+    if encoding == encoding_rs::UTF_8 {
+        return Ok(data.into());
+    }
+    Ok(match encoding.decode_without_bom_handling(data).0 {
+        Cow::Borrowed(text) => Cow::Borrowed(text.as_bytes()),
+        Cow::Owned(text) => Cow::Owned(text.into_bytes()),
+    })
+}
+
+/// Decode all encoded words like `=?UTF-8?q?J=C3=B6rg?=` in the header `value` as described in RFC 2047
+/// and return it as UTF-8, assuming `charset` for text that isn't encoded unless it's valid UTF-8 already.
+pub(crate) fn header(value: &[u8], charset: Option<&BStr>) -> Result<BString, Error> {
+    let mut out = BString::default();
+    let mut cursor = value;
+    let mut previous_was_encoded = false;
+    while !cursor.is_empty() {
+        let Some((start, end, text)) = next_encoded_word(cursor)? else {
+            push_text(&mut out, cursor, charset)?;
+            break;
+        };
+        let between = &cursor[..start];
+        if !(previous_was_encoded && between.iter().all(u8::is_ascii_whitespace)) {
+            push_text(&mut out, between, charset)?;
+        }
+        out.extend_from_slice(&text);
+        previous_was_encoded = true;
+        cursor = &cursor[end..];
+    }
+    Ok(out)
+}
+
+fn push_text(out: &mut BString, text: &[u8], charset: Option<&BStr>) -> Result<(), Error> {
+    match charset.filter(|_| text.to_str().is_err()) {
+        Some(charset) => out.extend_from_slice(&to_utf8(text, charset)?),
+        None => out.extend_from_slice(text),
+    }
+    Ok(())
+}
+
+/// Find the next well-formed encoded word in `data` and return its start and end, along with its decoded text.
+fn next_encoded_word(data: &[u8]) -> Result<Option<(usize, usize, Vec<u8>)>, Error> {
+    let mut offset = 0;
+    while let Some(start) = data[offset..].find(b"=?").map(|pos| pos + offset) {
+        offset = start + 2;
+        let mut fields = data[offset..].splitn_str(3, b"?");
+        let (Some(charset), Some(encoding), Some(rest)) = (fields.next(), fields.next(), fields.next()) else {
+            break;
+        };
+        let Some(text_len) = rest.find(b"?=") else {
+            break;
+        };
+        let text = &rest[..text_len];
+        if charset.is_empty() || text.contains(&b' ') {
+            continue;
+        }
+        let decoded = match encoding {
+            b"q" | b"Q" => quoted_printable(text, true),
+            b"b" | b"B" => base64(text)?,
+            _ => continue,
+        };
+        let end = offset + charset.len() + 1 + encoding.len() + 1 + text_len + 2;
+        // Language tags as in `UTF-8*en` are ignored.
+        let charset = charset.split_str(b"*").next().unwrap_or_default();
+        return Ok(Some((start, end, to_utf8(&decoded, charset.as_bstr())?.into_owned())));
+    }
+    Ok(None)
+}
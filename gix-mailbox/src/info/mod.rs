@@ -0,0 +1,76 @@
+use bstr::{BString, ByteSlice};
+
+/// The error returned by [`Info::from_bytes()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The message has no author as it lacks a 'From' header")]
+    MissingAuthor,
+    #[error("The message lacks a 'Date' header")]
+    MissingDate,
+    #[error("Could not parse the date {date:?}")]
+    Date { date: BString, source: gix_date::Error },
+    #[error("The character set {charset:?} is unknown")]
+    UnknownCharset { charset: BString },
+    #[error("Could not decode base64 encoded data")]
+    Base64(#[from] base64::DecodeError),
+}
+
+/// Options for use in [`Info::from_bytes()`].
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// If `true`, the subject is taken as is, similar to `git mailinfo -k`.
+    ///
+    /// Otherwise, leading `Re:`, whitespace and anything in brackets, like `[PATCH v2 1/3]`, are removed.
+    pub keep_subject: bool,
+    /// If `true`, only remove bracketed prefixes of the subject that contain `PATCH`, similar to `git mailinfo -b`.
+    ///
+    /// This has no effect if [`keep_subject`](Self::keep_subject) is set.
+    pub keep_non_patch_brackets: bool,
+    /// If `true`, discard the part of the body before a scissors line like `-- >8 --`, similar to `git mailinfo --scissors`.
+    pub scissors: bool,
+    /// If `true`, the commit message is kept in the character set of the message instead of converting it to UTF-8,
+    /// similar to `git mailinfo -n`.
+    ///
+    /// Headers are always converted to UTF-8.
+    pub keep_encoding: bool,
+}
+
+/// The information extracted from a message, similar to what `git mailinfo` provides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Info {
+    /// The author of the patch along with the time at which it was authored, taken from the `From` and `Date` headers
+    /// or from headers at the beginning of the body which take precedence.
+    pub author: gix_actor::Signature,
+    /// The subject of the message, which is the first line of the commit message, with prefixes like `[PATCH 1/2]` removed
+    /// unless configured otherwise.
+    pub subject: BString,
+    /// The rest of the commit message after the subject, which is empty or ends in a newline.
+    pub body: BString,
+    /// The value of the `Message-Id` header, if present.
+    pub message_id: Option<BString>,
+    /// Everything after the commit message, beginning with the `---` separator or the first line of the patch,
+    /// or empty if there is no patch.
+    pub patch: BString,
+}
+
+mod parse;
+
+impl Info {
+    /// Return the commit message, which is the [subject](Self::subject) followed by the [body](Self::body)
+    /// in a paragraph of its own.
+    pub fn message(&self) -> BString {
+        let mut message = self.subject.clone();
+        message.push(b'\n');
+        if !self.body.is_empty() {
+            message.push(b'\n');
+            message.extend_from_slice(&self.body);
+        }
+        message
+    }
+
+    /// Return an iterator over the trailers in the last paragraph of the [body](Self::body), like `Signed-off-by`.
+    pub fn trailers(&self) -> gix_object::commit::message::body::Trailers<'_> {
+        gix_object::commit::message::BodyRef::from_bytes(self.body.as_bytes()).trailers()
+    }
+}
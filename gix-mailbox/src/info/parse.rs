@@ -0,0 +1,461 @@
+use std::borrow::Cow;
+
+use bstr::{BStr, BString, ByteSlice};
+
+use super::{Error, Info, Options};
+use crate::{decode, split::function::is_from_line};
+
+impl Info {
+    /// Parse `message`, which is a single message of a mailbox as returned by [`split()`](crate::split()), and extract
+    /// the patch along with its metadata similar to `git mailinfo`, configured by `options`.
+    ///
+    /// A leading line separating it from other messages, as found in the files written by `git format-patch`, is skipped.
+    pub fn from_bytes(message: &[u8], options: Options) -> Result<Self, Error> {
+        let message = match message.find_byte(b'\n') {
+            Some(pos) if is_from_line(&message[..=pos]) => &message[pos + 1..],
+            _ => message,
+        };
+        let (headers, body) = headers_and_body(message);
+        let headers = Headers::from_bytes(headers);
+        let charset = headers
+            .get("content-type")
+            .and_then(|value| parameter(value, "charset"));
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .map(|value| decode::header(value, charset.as_ref().map(AsRef::as_ref)))
+                .transpose()
+        };
+        let fields = Fields {
+            from: header("from")?,
+            date: header("date")?,
+            subject: header("subject")?,
+        };
+        let message_id = header("message-id")?.map(|id| id.trim().into());
+
+        let mut body_state = Body {
+            options,
+            ..Default::default()
+        };
+        body_state.push_entity(&headers, body)?;
+        let Body {
+            message: body,
+            patch,
+            in_body,
+            ..
+        } = body_state;
+
+        let from = in_body.from.or(fields.from).ok_or(Error::MissingAuthor)?;
+        let (name, email) = parse_from(from.as_ref());
+        let date = in_body.date.or(fields.date).ok_or(Error::MissingDate)?;
+        let time = parse_date(date.as_ref())?;
+        let mut subject = in_body.subject.or(fields.subject).unwrap_or_default();
+        if !options.keep_subject {
+            subject = cleanup_subject(subject.as_ref(), options.keep_non_patch_brackets);
+        }
+        Ok(Info {
+            author: gix_actor::Signature { name, email, time },
+            subject,
+            body: trim_empty_lines(body.as_ref()),
+            message_id,
+            patch,
+        })
+    }
+}
+
+/// The fields that can be specified in the headers of the message, or at the beginning of its body.
+#[derive(Default)]
+struct Fields {
+    from: Option<BString>,
+    date: Option<BString>,
+    subject: Option<BString>,
+}
+
+impl Fields {
+    fn get_mut(&mut self, name: &str) -> &mut Option<BString> {
+        match name {
+            "From" => &mut self.from,
+            "Date" => &mut self.date,
+            _ => &mut self.subject,
+        }
+    }
+}
+
+/// Unfolded headers, in order.
+struct Headers(Vec<(BString, BString)>);
+
+impl Headers {
+    fn from_bytes(data: &[u8]) -> Self {
+        let mut headers = Vec::<(BString, BString)>::new();
+        for line in data.lines() {
+            if line.first().is_some_and(|b| *b == b' ' || *b == b'\t') {
+                if let Some((_, value)) = headers.last_mut() {
+                    fold(value, line);
+                }
+                continue;
+            }
+            if let Some((name, value)) = line.split_once_str(b":") {
+                headers.push((name.trim().into(), value.trim().into()));
+            }
+        }
+        Headers(headers)
+    }
+
+    /// Return the value of the first header with `name`, which is compared case-insensitively.
+    fn get(&self, name: &str) -> Option<&BStr> {
+        self.0
+            .iter()
+            .find_map(|(key, value)| key.eq_ignore_ascii_case(name.as_bytes()).then_some(value.as_bstr()))
+    }
+}
+
+/// Append the continuation `line` to `value`, just like `git mailinfo` does it.
+fn fold(value: &mut BString, line: &[u8]) {
+    let trimmed = value.trim_end().len();
+    value.truncate(trimmed);
+    value.push(b' ');
+    value.extend_from_slice(line[1..].trim_end());
+}
+
+/// Split `data` at the first empty line into headers and body.
+fn headers_and_body(data: &[u8]) -> (&[u8], &[u8]) {
+    let mut pos = 0;
+    for line in data.lines_with_terminator() {
+        if line.trim_end_with(|c| c == '\n' || c == '\r').is_empty() {
+            return (&data[..pos], &data[pos + line.len()..]);
+        }
+        pos += line.len();
+    }
+    (data, &[])
+}
+
+/// Return the media type of a `Content-Type` header `value`, like `text/plain`, in lowercase.
+fn media_type(value: &BStr) -> BString {
+    value
+        .split_str(b";")
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+        .into()
+}
+
+/// Return the parameter `name` of a `Content-Type` header `value`, like the `charset` in `text/plain; charset=UTF-8`.
+fn parameter(value: &BStr, name: &str) -> Option<BString> {
+    value.split_str(b";").skip(1).find_map(|param| {
+        let (key, value) = param.split_once_str(b"=")?;
+        if !key.trim().eq_ignore_ascii_case(name.as_bytes()) {
+            return None;
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix(b"\"")
+            .and_then(|value| value.strip_suffix(b"\""))
+            .unwrap_or(value);
+        Some(value.into())
+    })
+}
+
+/// Return the parts of a multipart `body` that are delimited by `boundary`, without preamble and epilogue.
+fn parts<'a>(body: &'a [u8], boundary: &[u8]) -> Vec<&'a [u8]> {
+    let mut delimiter = b"--".to_vec();
+    delimiter.extend_from_slice(boundary);
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut pos = 0;
+    for line in body.lines_with_terminator() {
+        let line_start = pos;
+        pos += line.len();
+        let Some(rest) = line.trim_end().strip_prefix(delimiter.as_slice()) else {
+            continue;
+        };
+        let is_end = match rest {
+            b"" => false,
+            b"--" => true,
+            _ => continue,
+        };
+        if let Some(start) = start.take() {
+            parts.push(&body[start..line_start]);
+        }
+        if is_end {
+            return parts;
+        }
+        start = Some(pos);
+    }
+    if let Some(start) = start {
+        parts.push(&body[start..]);
+    }
+    parts
+}
+
+/// The state of processing the body of a message, line by line, which is split into commit message and patch.
+#[derive(Default)]
+struct Body {
+    options: Options,
+    message: BString,
+    patch: BString,
+    in_patch: bool,
+    /// Headers at the beginning of the body which override the ones of the message.
+    in_body: Fields,
+    /// If `true`, no more headers are allowed at the beginning of the body.
+    past_headers: bool,
+    /// The name of the last header seen in the body, in case it's continued on the next line.
+    last_header: Option<&'static str>,
+}
+
+impl Body {
+    /// Process an entity of the message, i.e. the message itself or one of its parts, with the given `headers` and `body`.
+    fn push_entity(&mut self, headers: &Headers, body: &[u8]) -> Result<(), Error> {
+        let content_type = headers.get("content-type");
+        if let Some(boundary) = content_type
+            .filter(|value| media_type(value).starts_with(b"multipart/"))
+            .and_then(|value| parameter(value, "boundary"))
+        {
+            for part in parts(body, &boundary) {
+                let (headers, body) = headers_and_body(part);
+                self.push_entity(&Headers::from_bytes(headers), body)?;
+            }
+            return Ok(());
+        }
+
+        let encoding = headers
+            .get("content-transfer-encoding")
+            .map(|value| value.trim().to_ascii_lowercase());
+        let data: Cow<'_, [u8]> = match encoding.as_deref() {
+            Some(b"quoted-printable") => decode::quoted_printable(body, false).into(),
+            Some(b"base64") => decode::base64(body)?.into(),
+            _ => body.into(),
+        };
+        let charset = content_type
+            .and_then(|value| parameter(value, "charset"))
+            .filter(|_| !self.options.keep_encoding);
+        for line in data.lines_with_terminator() {
+            self.push_line(line, charset.as_ref().map(AsRef::as_ref))?;
+        }
+        Ok(())
+    }
+
+    fn push_line(&mut self, line: &[u8], charset: Option<&BStr>) -> Result<(), Error> {
+        if self.in_patch || is_patch_break(line) {
+            self.in_patch = true;
+            self.patch.extend_from_slice(line);
+            return Ok(());
+        }
+        let line = match charset {
+            Some(charset) => decode::to_utf8(line, charset)?,
+            None => line.into(),
+        };
+        if !self.past_headers && self.push_in_body_header(&line, charset)? {
+            return Ok(());
+        }
+        if self.options.scissors && is_scissors_line(&line) {
+            self.message.clear();
+            self.in_body = Fields::default();
+            self.past_headers = false;
+            self.last_header = None;
+            return Ok(());
+        }
+        self.message.extend_from_slice(&line);
+        Ok(())
+    }
+
+    /// Return `true` if `line` was consumed as header at the beginning of the body, or as empty line around them.
+    fn push_in_body_header(&mut self, line: &[u8], charset: Option<&BStr>) -> Result<bool, Error> {
+        if line.trim().is_empty() {
+            if self.last_header.is_some() {
+                self.past_headers = true;
+            }
+            return Ok(true);
+        }
+        if let Some(name) = self.last_header.filter(|_| line[0] == b' ' || line[0] == b'\t') {
+            if let Some(value) = self.in_body.get_mut(name) {
+                fold(value, &decode::header(line, charset)?);
+            }
+            return Ok(true);
+        }
+        for name in ["From", "Date", "Subject"] {
+            let Some(value) = line
+                .get(..name.len() + 1)
+                .filter(|prefix| prefix[..name.len()].eq_ignore_ascii_case(name.as_bytes()) && prefix[name.len()] == b':')
+                .map(|_| &line[name.len() + 1..])
+            else {
+                continue;
+            };
+            *self.in_body.get_mut(name) = Some(decode::header(value.trim(), charset)?);
+            self.last_header = Some(name);
+            return Ok(true);
+        }
+        self.past_headers = true;
+        Ok(false)
+    }
+}
+
+/// Return `true` if `line` starts the patch, using the same heuristic as `git mailinfo`.
+fn is_patch_break(line: &[u8]) -> bool {
+    if line.starts_with(b"diff -") || line.starts_with(b"Index: ") {
+        return true;
+    }
+    if line.len() < 4 || !line.starts_with(b"---") {
+        return false;
+    }
+    if line[3] == b' ' && line.get(4).is_some_and(|b| !b.is_ascii_whitespace()) {
+        return true;
+    }
+    line[3..]
+        .iter()
+        .find(|b| **b == b'\n' || !b.is_ascii_whitespace())
+        .is_some_and(|b| *b == b'\n')
+}
+
+/// Return `true` if `line` looks like `-- >8 --`, using the same heuristic as `git mailinfo`.
+fn is_scissors_line(line: &[u8]) -> bool {
+    let (mut scissors, mut gap, mut perforation) = (0, 0, 0);
+    let mut in_perforation = false;
+    let (mut first_nonblank, mut last_nonblank) = (None, None);
+    let mut pos = 0;
+    while pos < line.len() {
+        let c = line[pos];
+        if c.is_ascii_whitespace() {
+            if in_perforation {
+                perforation += 1;
+                gap += 1;
+            }
+            pos += 1;
+            continue;
+        }
+        last_nonblank = Some(pos);
+        first_nonblank.get_or_insert(pos);
+        if c == b'-' {
+            in_perforation = true;
+            perforation += 1;
+            pos += 1;
+            continue;
+        }
+        if [b">8", b"8<", b">%", b"%<"].iter().any(|mark| line[pos..].starts_with(*mark)) {
+            in_perforation = true;
+            perforation += 2;
+            scissors += 2;
+            last_nonblank = Some(pos + 1);
+            pos += 2;
+            continue;
+        }
+        in_perforation = false;
+        pos += 1;
+    }
+    let visible = first_nonblank
+        .zip(last_nonblank)
+        .map_or(0, |(first, last)| last - first + 1);
+    scissors != 0 && visible >= 8 && visible < perforation * 3 && gap * 2 < perforation
+}
+
+/// Parse the author name and email from a `From` header `value` like `Name <email>`, `email (Name)` or `email`.
+fn parse_from(value: &BStr) -> (BString, BString) {
+    let value = value.trim();
+    let (name, email): (BString, BString) = if let Some((name, rest)) = value.split_once_str(b"<")
+        && let Some((email, _)) = rest.split_once_str(b">")
+    {
+        (name.into(), email.trim().into())
+    } else if let Some(email) = value
+        .split(u8::is_ascii_whitespace)
+        .find(|token| token.contains(&b'@'))
+    {
+        let start = email.as_ptr() as usize - value.as_ptr() as usize;
+        let mut name = value[..start].to_vec();
+        name.extend_from_slice(&value[start + email.len()..]);
+        let name = name.trim().to_vec();
+        let name = name
+            .strip_prefix(b"(")
+            .and_then(|name| name.strip_suffix(b")"))
+            .unwrap_or(&name);
+        (name.into(), email.into())
+    } else {
+        (value.into(), BString::default())
+    };
+    let name = unquote(name.trim());
+    if name.is_empty() {
+        (email.clone(), email)
+    } else {
+        (name, email)
+    }
+}
+
+/// Remove the quotes around `name` along with the escapes within them.
+fn unquote(name: &[u8]) -> BString {
+    let Some(quoted) = name.strip_prefix(b"\"").and_then(|name| name.strip_suffix(b"\"")) else {
+        return name.into();
+    };
+    let mut out = BString::default();
+    let mut bytes = quoted.iter();
+    while let Some(&b) = bytes.next() {
+        match b {
+            b'\\' => out.extend(bytes.next()),
+            b => out.push(b),
+        }
+    }
+    out
+}
+
+/// Parse `date` like `Mon, 17 Sep 2001 00:00:00 +0200`, ignoring trailing comments like `(CEST)`.
+fn parse_date(date: &BStr) -> Result<gix_date::Time, Error> {
+    let mut trimmed = date.trim();
+    if trimmed.ends_with(b")")
+        && let Some(pos) = trimmed.rfind_byte(b'(')
+    {
+        trimmed = trimmed[..pos].trim_end();
+    }
+    gix_date::parse(&trimmed.to_str_lossy(), None).map_err(|err| Error::Date {
+        date: date.to_owned(),
+        source: err.into_inner(),
+    })
+}
+
+/// Remove leading `Re:`, whitespace and bracketed prefixes like `[PATCH v2 1/3]` from `subject`, just like
+/// `git mailinfo` does, but keep brackets that don't mention `PATCH` if `keep_non_patch_brackets` is set.
+fn cleanup_subject(subject: &BStr, keep_non_patch_brackets: bool) -> BString {
+    let mut subject = subject.to_vec();
+    let mut at = 0;
+    while at < subject.len() {
+        match subject[at] {
+            b'r' | b'R'
+                if subject.len() > at + 3 && subject[at + 1].eq_ignore_ascii_case(&b'e') && subject[at + 2] == b':' =>
+            {
+                subject.drain(at..at + 3);
+                continue;
+            }
+            b' ' | b'\t' | b':' => {
+                subject.remove(at);
+                continue;
+            }
+            b'[' => {
+                if let Some(len) = subject[at..].find_byte(b']').map(|pos| pos + 1) {
+                    if !keep_non_patch_brackets || (len >= 7 && subject[at..at + len].contains_str(b"PATCH")) {
+                        subject.drain(at..at + len);
+                    } else {
+                        at += len;
+                        if subject.get(at).is_some_and(u8::is_ascii_whitespace) {
+                            at += 1;
+                        }
+                    }
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        break;
+    }
+    subject.trim().into()
+}
+
+/// Remove empty lines at the beginning and end of `message`, and assure it ends in a newline unless it's empty.
+fn trim_empty_lines(message: &BStr) -> BString {
+    let start = message
+        .lines_with_terminator()
+        .take_while(|line| line.trim().is_empty())
+        .map(<[u8]>::len)
+        .sum::<usize>();
+    let mut message: BString = message[start..].trim_end().into();
+    if !message.is_empty() {
+        message.push(b'\n');
+    }
+    message
+}
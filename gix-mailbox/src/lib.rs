@@ -0,0 +1,32 @@
+//! Split mailboxes into messages and extract patches along with their metadata, akin to `git mailsplit` and `git mailinfo`.
+//!
+//! The workflow is as follows:
+//!
+//! * [Split](split()) a mailbox, like the one produced by `git format-patch --stdout`, into its messages.
+//! * [Parse](Info::from_bytes()) each message to obtain its author as [`gix_actor::Signature`], its subject without
+//!   prefixes like `[PATCH 1/2]`, the body of the commit message along with its [trailers](Info::trailers()),
+//!   and the patch itself, ready to be applied.
+//!
+//! Headers encoded according to RFC 2047 are decoded, as are message bodies encoded as `quoted-printable` or `base64`,
+//! and the commit message is converted to UTF-8 from the character set of the message.
+//! The parts of `multipart` messages are processed in order, so patches sent as attachment are found as well.
+//!
+//! ### Deviation
+//!
+//! * Maildir directories aren't supported, but each of their files is a message that can be parsed directly.
+//! * Character sets are decoded according to the [encoding standard](https://encoding.spec.whatwg.org), which knows fewer
+//!   character sets than `iconv` as used by `git`.
+//! * The commit message isn't cleaned up beyond removing leading and trailing empty lines, leaving it to the caller to
+//!   do what `git am` does with `git stripspace`.
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+
+///
+pub mod split;
+pub use split::function::split;
+
+///
+pub mod info;
+pub use info::Info;
+
+mod decode;
@@ -0,0 +1,102 @@
+/// Options for use in [`split()`](crate::split()).
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// If `true`, keep the carriage return of lines ending in `\r\n`, which are otherwise converted to end in `\n`.
+    pub keep_cr: bool,
+    /// If `true`, the mailbox is in the `mboxrd` format and lines starting with `>From `, preceded by any amount of `>`,
+    /// are unescaped by removing one `>`.
+    pub mboxrd: bool,
+}
+
+/// An iterator over the messages of a mailbox, as returned by [`split()`](crate::split()).
+pub struct Messages<'a> {
+    pub(crate) cursor: &'a [u8],
+    pub(crate) options: Options,
+}
+
+pub(super) mod function {
+    use bstr::{BString, ByteSlice};
+
+    use super::{Messages, Options};
+
+    /// Split `mailbox` into its messages, similar to `git mailsplit`, configured by `options`.
+    ///
+    /// Messages are separated by lines like `From <anything> Mon Sep 17 00:00:00 2001`, which are kept as first line of
+    /// each message just like `git mailsplit` does, and which are skipped by [`Info::from_bytes()`](crate::Info::from_bytes()).
+    /// If `mailbox` doesn't start with such a line after skipping empty lines, it's returned as single message.
+    pub fn split(mailbox: &[u8], options: Options) -> Messages<'_> {
+        let mut cursor = mailbox;
+        while let Some(rest) = cursor.strip_prefix(b"\n").or_else(|| cursor.strip_prefix(b"\r\n")) {
+            cursor = rest;
+        }
+        if !is_from_line(first_line(cursor)) {
+            cursor = mailbox;
+        }
+        Messages { cursor, options }
+    }
+
+    impl Iterator for Messages<'_> {
+        type Item = BString;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.cursor.is_empty() {
+                return None;
+            }
+            let mut message = BString::default();
+            let mut is_first_line = true;
+            while !self.cursor.is_empty() {
+                let line = first_line(self.cursor);
+                if !std::mem::take(&mut is_first_line) && is_from_line(line) {
+                    break;
+                }
+                self.cursor = &self.cursor[line.len()..];
+                let mut line = line;
+                if self.options.mboxrd {
+                    let quotes = line.iter().take_while(|b| **b == b'>').count();
+                    if quotes > 0 && line[quotes..].starts_with(b"From ") {
+                        line = &line[1..];
+                    }
+                }
+                match line.strip_suffix(b"\r\n").filter(|_| !self.options.keep_cr) {
+                    Some(line) => {
+                        message.extend_from_slice(line);
+                        message.push(b'\n');
+                    }
+                    None => message.extend_from_slice(line),
+                }
+            }
+            Some(message)
+        }
+    }
+
+    /// Return the first line of `data` including its newline.
+    fn first_line(data: &[u8]) -> &[u8] {
+        data.find_byte(b'\n').map_or(data, |pos| &data[..=pos])
+    }
+
+    /// Return `true` if `line` looks like the separator of messages in a mailbox, using the same heuristic as `git mailsplit`:
+    /// it starts with `From ` and ends in a time and a year.
+    pub(crate) fn is_from_line(line: &[u8]) -> bool {
+        let line = line.trim_end_with(|c| c == '\n' || c == '\r');
+        if line.len() < 20 || !line.starts_with(b"From ") {
+            return false;
+        }
+        let Some(colon) = line[5..line.len() - 1].rfind_byte(b':').map(|pos| pos + 5) else {
+            return false;
+        };
+        let digit = |pos: usize| line.get(pos).is_some_and(u8::is_ascii_digit);
+        if colon < 4 || ![colon - 4, colon - 2, colon - 1, colon + 1, colon + 2].into_iter().all(digit) {
+            return false;
+        }
+        let year: Vec<u8> = line[colon + 3..]
+            .trim_start()
+            .iter()
+            .copied()
+            .take_while(u8::is_ascii_digit)
+            .collect();
+        year.to_str()
+            .ok()
+            .and_then(|year| year.parse::<u32>().ok())
+            .is_some_and(|year| year > 90)
+    }
+}
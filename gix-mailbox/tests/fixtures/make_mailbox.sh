@@ -0,0 +1,97 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q repo
+(cd repo
+  seq 10 > file
+  git add file && git commit -q -m base && git tag base
+
+  seq 11 > file
+  git commit -q -am "first change" -m "With a body that explains it." -m "Signed-off-by: A U Thor <author@example.com>"
+
+  seq 12 > file
+  GIT_AUTHOR_NAME="Jörg Ümlaut" GIT_AUTHOR_EMAIL=joerg@example.com \
+    git commit -q -am "[tag] second change with a rather long subject that needs to be folded, and ümlauts" -m "A body with ümlauts."
+
+  echo "From the beginning" >> file
+  git commit -q -am "third change" -m "From the beginning of the line, which needs escaping in mboxrd."
+
+  git format-patch -q --stdout base > ../series.mbox
+  git format-patch -q --stdout --pretty=mboxrd base > ../series.mboxrd
+  git format-patch -q --stdout --attach base > ../attached.mbox
+  git format-patch -q --stdout -1 --from="Sender <sender@example.com>" > ../inbody-from.mbox
+)
+
+cat > quoted-printable.mbox <<'MAIL'
+From: =?ISO-8859-1?Q?J=F6rg?= Ümlaut <joerg@example.com>
+Date: Mon, 17 Sep 2001 12:30:00 +0200 (CEST)
+Subject: Re: [RFC PATCH v2 3/7] decode
+ =?utf-8?b?w7xtbGF1dHM=?= in headers
+Message-Id: <id@example.com>
+MIME-Version: 1.0
+Content-Type: text/plain; charset="iso-8859-1"
+Content-Transfer-Encoding: quoted-printable
+
+Bodies with =FCmlauts and soft line br=
+eaks are decoded.
+
+Acked-by: J=F6rg <joerg@example.com>
+---
+ file | 2 +-
+ 1 file changed, 1 insertion(+), 1 deletion(-)
+
+diff --git a/file b/file
+--- a/file
++++ b/file
+@@ -1 +1 @@
+-a
++=E4
+MAIL
+
+cat > base64.mbox <<'MAIL'
+From: "Doe, Jane" <jane@example.com>
+Date: Tue, 18 Sep 2001 08:00:00 -0700
+Subject: [PATCH] base64
+Content-Type: text/plain; charset=utf-8
+Content-Transfer-Encoding: base64
+
+TWVzc2FnZSB3aXRoIMO8bWxhdXRzLgoKLS0tCiBmaWxlIHwgMiArLQoKZGlmZiAtLWdpdCBhL2Zp
+bGUgYi9maWxlCi0tLSBhL2ZpbGUKKysrIGIvZmlsZQpAQCAtMSArMSBAQAotYQorYgo=
+MAIL
+
+cat > scissors.mbox <<'MAIL'
+From: jane@example.com (Jane Doe)
+Date: Wed, 19 Sep 2001 10:00:00 +0000
+Subject: Re: question
+
+Discussion that doesn't belong into the commit.
+
+-- >8 --
+Subject: [PATCH] the real subject
+From: Someone Else <else@example.com>
+
+The commit message.
+---
+diff --git a/file b/file
+--- a/file
++++ b/file
+@@ -1 +1 @@
+-a
++b
+MAIL
+
+for mbox in *.mbox *.mboxrd; do
+  args=()
+  case "$mbox" in
+    *.mboxrd) args=(--mboxrd) ;;
+  esac
+  mkdir "$mbox.split"
+  git mailsplit -b "${args[@]}" -o"$mbox.split" "$mbox" >/dev/null
+  for mail in "$mbox.split"/*; do
+    args=(-u)
+    case "$mbox" in
+      scissors.mbox) args+=(--scissors) ;;
+    esac
+    git mailinfo "${args[@]}" "$mail.msg" "$mail.patch" < "$mail" > "$mail.info"
+  done
+done
@@ -0,0 +1,210 @@
+use bstr::{BStr, ByteSlice};
+use gix_mailbox::{Info, info};
+
+use crate::{fixture, git_messages};
+
+fn parse(message: &str, options: info::Options) -> Result<Info, info::Error> {
+    Info::from_bytes(message.as_bytes(), options)
+}
+
+/// Return the value of the field `name` in the output of `git mailinfo`.
+fn field<'a>(info: &'a [u8], name: &str) -> &'a BStr {
+    info.lines()
+        .find_map(|line| line.strip_prefix(format!("{name}: ").as_bytes()))
+        .unwrap_or_default()
+        .as_bstr()
+}
+
+#[test]
+fn like_git_mailinfo() -> crate::Result {
+    let root = fixture()?;
+    for name in [
+        "series.mbox",
+        "series.mboxrd",
+        "attached.mbox",
+        "inbody-from.mbox",
+        "quoted-printable.mbox",
+        "base64.mbox",
+        "scissors.mbox",
+    ] {
+        for path in git_messages(&root, name)? {
+            let info = Info::from_bytes(
+                &std::fs::read(&path)?,
+                info::Options {
+                    scissors: name == "scissors.mbox",
+                    ..Default::default()
+                },
+            )?;
+            let read = |extension: &str| std::fs::read(path.with_extension(extension));
+            let expected = read("info")?;
+            let path = path.display();
+            assert_eq!(info.author.name, field(&expected, "Author"), "{path}");
+            assert_eq!(info.author.email, field(&expected, "Email"), "{path}");
+            assert_eq!(info.subject, field(&expected, "Subject"), "{path}");
+            let date = field(&expected, "Date");
+            assert_eq!(
+                info.author.time.format(gix_date::time::format::GIT_RFC2822)?,
+                date.strip_suffix(b" (CEST)").unwrap_or(date).as_bstr(),
+                "{path}"
+            );
+            assert_eq!(info.body.trim_end(), read("msg")?.trim_end(), "{path}");
+            if name == "attached.mbox" {
+                assert_eq!(
+                    info.patch.trim_end(),
+                    read("patch")?.trim_end(),
+                    "{path}: git adds an empty line at the end of multipart messages"
+                );
+            } else {
+                assert_eq!(info.patch, read("patch")?, "{path}");
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn message_and_trailers() -> crate::Result {
+    let root = fixture()?;
+    let messages = git_messages(&root, "series.mbox")?;
+    let info = Info::from_bytes(&std::fs::read(&messages[0])?, Default::default())?;
+    assert_eq!(
+        info.message(),
+        "first change\n\nWith a body that explains it.\n\nSigned-off-by: A U Thor <author@example.com>\n"
+    );
+    let trailers: Vec<_> = info
+        .trailers()
+        .map(|trailer| (trailer.token.to_string(), trailer.value.to_string()))
+        .collect();
+    assert_eq!(
+        trailers,
+        [(
+            "Signed-off-by".to_string(),
+            "A U Thor <author@example.com>".to_string()
+        )]
+    );
+    assert_eq!(info.message_id, None, "git format-patch only adds message ids when threading");
+
+    let messages = git_messages(&root, "quoted-printable.mbox")?;
+    let info = Info::from_bytes(&std::fs::read(&messages[0])?, Default::default())?;
+    let trailer = info.trailers().next().expect("one trailer");
+    assert_eq!(trailer.value.as_ref(), "Jörg <joerg@example.com>", "trailers are decoded too");
+    assert_eq!(info.message_id.expect("set"), "<id@example.com>");
+    assert_eq!(info.author.time.seconds, 1000722600);
+    assert_eq!(info.author.time.offset, 2 * 60 * 60);
+
+    let info = parse(
+        "From: a@example.com\nDate: Mon, 17 Sep 2001 00:00:00 +0000\nSubject: only a subject\n\n---\n",
+        Default::default(),
+    )?;
+    assert_eq!(info.message(), "only a subject\n");
+    assert_eq!(info.author.name, "a@example.com", "the email is the name if there is no name");
+    assert_eq!(info.patch, "---\n");
+    Ok(())
+}
+
+#[test]
+fn subject_cleanup() -> crate::Result {
+    let subject = |subject: &str, options: info::Options| -> Result<String, info::Error> {
+        Ok(parse(
+            &format!("From: a@example.com\nDate: Mon, 17 Sep 2001 00:00:00 +0000\nSubject: {subject}\n\nbody\n"),
+            options,
+        )?
+        .subject
+        .to_string())
+    };
+    assert_eq!(subject("Re: re: [PATCH 1/2] [tag] subject [x]", Default::default())?, "subject [x]");
+    assert_eq!(
+        subject(
+            "Re: [PATCH 1/2] [tag] subject",
+            info::Options {
+                keep_non_patch_brackets: true,
+                ..Default::default()
+            }
+        )?,
+        "[tag] subject"
+    );
+    assert_eq!(
+        subject(
+            "Re: [PATCH 1/2] subject",
+            info::Options {
+                keep_subject: true,
+                ..Default::default()
+            }
+        )?,
+        "Re: [PATCH 1/2] subject"
+    );
+    Ok(())
+}
+
+#[test]
+fn multipart_messages_are_decoded_part_by_part() -> crate::Result {
+    let info = parse(
+        "From: a@example.com
+Date: Mon, 17 Sep 2001 00:00:00 +0000
+Subject: [PATCH] multipart
+Content-Type: multipart/mixed; boundary=\"xyz\"
+
+This is the preamble.
+--xyz
+Content-Type: text/plain; charset=iso-8859-1
+Content-Transfer-Encoding: quoted-printable
+
+M=FCller was here.
+--xyz
+Content-Type: text/x-patch; charset=iso-8859-1
+Content-Disposition: attachment; filename=\"0001.patch\"
+
+---
++M\u{fc}ller
+--xyz--
+This is the epilogue.
+",
+        Default::default(),
+    )?;
+    assert_eq!(info.body, "Müller was here.\n");
+    assert_eq!(
+        info.patch, "---\n+M\u{fc}ller\n",
+        "patches are kept as they are"
+    );
+
+    let info = parse(
+        "From: a@example.com\nDate: Mon, 17 Sep 2001 00:00:00 +0000\nSubject: a\nContent-Type: text/plain; charset=latin1\n\nM\u{fc}ller\n",
+        info::Options {
+            keep_encoding: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(info.body, "M\u{fc}ller\n", "the encoding can be kept");
+    Ok(())
+}
+
+#[test]
+fn errors() {
+    let date = "Date: Mon, 17 Sep 2001 00:00:00 +0000\n";
+    assert!(matches!(
+        parse(&format!("{date}Subject: a\n\nbody\n"), Default::default()),
+        Err(info::Error::MissingAuthor)
+    ));
+    assert!(matches!(
+        parse("From: a@example.com\nSubject: a\n\nbody\n", Default::default()),
+        Err(info::Error::MissingDate)
+    ));
+    assert!(matches!(
+        parse("From: a@example.com\nDate: yesterday-ish\n\nbody\n", Default::default()),
+        Err(info::Error::Date { .. })
+    ));
+    assert!(matches!(
+        parse(
+            &format!("From: a@example.com\n{date}Content-Type: text/plain; charset=unknown\n\nbody\n"),
+            Default::default()
+        ),
+        Err(info::Error::UnknownCharset { charset }) if charset == "unknown"
+    ));
+    assert!(matches!(
+        parse(
+            &format!("From: a@example.com\n{date}Content-Transfer-Encoding: base64\n\n!!!!\n"),
+            Default::default()
+        ),
+        Err(info::Error::Base64(_))
+    ));
+}
@@ -0,0 +1,20 @@
+use std::path::{Path, PathBuf};
+
+pub use gix_testtools::Result;
+
+mod info;
+mod split;
+
+fn fixture() -> Result<PathBuf> {
+    gix_testtools::scripted_fixture_read_only("make_mailbox.sh")
+}
+
+/// Return the messages `git mailsplit` produced for the mailbox `name`, in order.
+fn git_messages(root: &Path, name: &str) -> Result<Vec<PathBuf>> {
+    let mut messages: Vec<_> = std::fs::read_dir(root.join(format!("{name}.split")))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    messages.retain(|path| path.extension().is_none());
+    messages.sort();
+    Ok(messages)
+}
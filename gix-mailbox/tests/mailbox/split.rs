@@ -0,0 +1,89 @@
+use gix_mailbox::{split, split::Options};
+
+use crate::{fixture, git_messages};
+
+#[test]
+fn like_git_mailsplit() -> crate::Result {
+    let root = fixture()?;
+    for (name, mboxrd, count) in [
+        ("series.mbox", false, 3),
+        ("series.mboxrd", true, 3),
+        ("attached.mbox", false, 3),
+        ("quoted-printable.mbox", false, 1),
+    ] {
+        let messages: Vec<_> = split(
+            &std::fs::read(root.join(name))?,
+            Options {
+                mboxrd,
+                ..Default::default()
+            },
+        )
+        .collect();
+        let expected = git_messages(&root, name)?
+            .iter()
+            .map(std::fs::read)
+            .collect::<std::io::Result<Vec<_>>>()?;
+        assert_eq!(messages.len(), count, "{name}");
+        assert_eq!(messages, expected, "{name}: messages are the same as the ones of git");
+    }
+    Ok(())
+}
+
+#[test]
+fn mboxrd_unescapes_from_lines() {
+    let mbox = "From 1 Mon Sep 17 00:00:00 2001\nSubject: a\n\n>From here\n>>From there\n> From elsewhere\n";
+    let messages: Vec<_> = split(
+        mbox.as_bytes(),
+        Options {
+            mboxrd: true,
+            ..Default::default()
+        },
+    )
+    .collect();
+    assert_eq!(
+        messages,
+        ["From 1 Mon Sep 17 00:00:00 2001\nSubject: a\n\nFrom here\n>From there\n> From elsewhere\n"]
+    );
+
+    let messages: Vec<_> = split(mbox.as_bytes(), Default::default()).collect();
+    assert_eq!(
+        messages,
+        ["From 1 Mon Sep 17 00:00:00 2001\nSubject: a\n\n>From here\n>>From there\n> From elsewhere\n"],
+        "unescaping is only done for mboxrd"
+    );
+}
+
+#[test]
+fn separators_and_line_endings() {
+    let mbox = "\nFrom 1 Mon Sep 17 00:00:00 2001\r\nSubject: a\r\n\r\nFrom the body\r\nFrom 2 Mon Sep 17 00:00:00 2001\nSubject: b\n";
+    let messages: Vec<_> = split(mbox.as_bytes(), Default::default()).collect();
+    assert_eq!(
+        messages,
+        [
+            "From 1 Mon Sep 17 00:00:00 2001\nSubject: a\n\nFrom the body\n",
+            "From 2 Mon Sep 17 00:00:00 2001\nSubject: b\n"
+        ],
+        "lines starting with 'From ' need to look like a separator"
+    );
+
+    let messages: Vec<_> = split(
+        mbox.as_bytes(),
+        Options {
+            keep_cr: true,
+            ..Default::default()
+        },
+    )
+    .collect();
+    assert_eq!(
+        messages[0],
+        "From 1 Mon Sep 17 00:00:00 2001\r\nSubject: a\r\n\r\nFrom the body\r\n"
+    );
+
+    let messages: Vec<_> = split(b"Subject: a\n\nbody\n", Default::default()).collect();
+    assert_eq!(
+        messages,
+        ["Subject: a\n\nbody\n"],
+        "input without separator is a single message"
+    );
+    assert_eq!(split(b"", Default::default()).count(), 0);
+}
@@ -0,0 +1,45 @@
+/// The error returned by [`find()`](crate::find()).
+pub type Error = gix_object::find::existing_object::Error;
+
+pub(super) mod function {
+    use gix_object::FindExt;
+
+    use super::Error;
+
+    /// Return the blob with the note of `object` in the notes tree `tree`, looking up trees in `objects`,
+    /// or `None` if `object` has no note.
+    ///
+    /// Only the trees on the way to the note are read, no matter which fan-out the notes tree uses.
+    pub fn find(
+        tree: &gix_hash::oid,
+        object: &gix_hash::oid,
+        objects: &impl gix_object::Find,
+    ) -> Result<Option<gix_hash::ObjectId>, Error> {
+        let hex = object.to_hex().to_string();
+        let mut rest = hex.as_bytes();
+        let mut tree = tree.to_owned();
+        let mut buf = Vec::new();
+        loop {
+            let mut subtree = None;
+            for entry in objects.find_tree(&tree, &mut buf)?.entries {
+                if entry.filename.len() == rest.len() {
+                    if entry.mode.is_blob() && entry.filename.eq_ignore_ascii_case(rest) {
+                        return Ok(Some(entry.oid.to_owned()));
+                    }
+                } else if entry.filename.len() == 2
+                    && entry.mode.is_tree()
+                    && entry.filename.eq_ignore_ascii_case(&rest[..2])
+                {
+                    subtree = Some(entry.oid.to_owned());
+                }
+            }
+            match subtree {
+                Some(id) => {
+                    tree = id;
+                    rest = &rest[2..];
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use bstr::{BString, ByteSlice};
+use gix_object::FindExt;
+
+use crate::{NonNote, Notes};
+
+/// The error returned by [`Notes::from_tree()`].
+pub type Error = gix_object::find::existing_object::Error;
+
+/// Lifecycle
+impl Notes {
+    /// Read all notes of the notes tree `tree`, looking up trees in `objects`.
+    ///
+    /// Notes are recognized at any level of fan-out, and all other entries are retained as [non-notes](Notes::non_notes()).
+    pub fn from_tree(tree: &gix_hash::oid, objects: &impl gix_object::Find) -> Result<Self, Error> {
+        let mut out = Notes::new(tree.kind());
+        out.read_tree(tree, &mut BString::default(), &mut String::new(), objects)?;
+        Ok(out)
+    }
+
+    /// Read the notes in `tree`, which is at `path` in the notes tree, with `prefix` being the hexadecimal
+    /// characters of annotated objects made up by the directory names in `path`.
+    fn read_tree(
+        &mut self,
+        tree: &gix_hash::oid,
+        path: &mut BString,
+        prefix: &mut String,
+        objects: &impl gix_object::Find,
+    ) -> Result<(), Error> {
+        let hex_len = self.object_hash.len_in_hex();
+        let tree: gix_object::Tree = objects.find_tree(tree, &mut Vec::new())?.into();
+        for entry in tree.entries {
+            let name = entry.filename.as_bstr();
+            let is_hex = name.iter().all(u8::is_ascii_hexdigit);
+            if name.len() == hex_len - prefix.len() {
+                if entry.mode.is_blob() && is_hex {
+                    let hex = format!("{prefix}{}", name.to_str_lossy().to_ascii_lowercase());
+                    let object = gix_hash::ObjectId::from_hex(hex.as_bytes()).expect("valid hex of the right length");
+                    self.notes.insert(object, entry.oid);
+                    continue;
+                }
+            } else if name.len() == 2 && entry.mode.is_tree() && is_hex {
+                let (path_len, prefix_len) = (path.len(), prefix.len());
+                path.extend_from_slice(name);
+                path.push(b'/');
+                prefix.push_str(&name.to_str_lossy().to_ascii_lowercase());
+                self.read_tree(&entry.oid, path, prefix, objects)?;
+                path.truncate(path_len);
+                prefix.truncate(prefix_len);
+                continue;
+            }
+            let mut entry_path = path.clone();
+            entry_path.extend_from_slice(name);
+            self.non_notes.push(NonNote {
+                path: entry_path,
+                mode: entry.mode,
+                id: entry.oid,
+            });
+        }
+        Ok(())
+    }
+}
@@ -1 +1,61 @@
+//! Read, write and merge git notes, which associate metadata with any object and keep its history with commits.
+//!
+//! Notes are stored in a tree, typically referred to by the commit at `refs/notes/commits`, which maps the hexadecimal
+//! name of each annotated object to the blob with its note. To keep trees small, names may be split into directories
+//! of two hexadecimal characters each, so the note of `abcd…` may be found at `abcd…`, `ab/cd…`, `ab/cd/…` and so on.
+//! This is called *fan-out*.
+//!
+//! * [`find()`] looks up the note of a single object without reading the whole notes tree.
+//! * [`Notes`] holds all notes of a tree in memory to [read](Notes::from_tree()), change and [write](Notes::write_tree())
+//!   them, just like `git notes add` or `git notes remove` would.
+//! * [`merge()`](merge()) merges notes with one of the [strategies](merge::Strategy) `git notes merge` supports.
+//!
+//! Creating commits for notes trees and updating references is up to the caller.
+//!
+//! ### Deviation
+//!
+//! * When writing notes, the fan-out is computed from all notes, while `git` keeps the fan-out of directories
+//!   it didn't look into. Thus, the fan-out may decrease after removing notes, which `git` wouldn't do.
+//! * If an object has multiple notes at different levels of fan-out, only one of them is used while `git`
+//!   concatenates them.
+#![deny(missing_docs)]
 #![forbid(unsafe_code)]
+
+use std::collections::BTreeMap;
+
+use bstr::BString;
+
+///
+pub mod find;
+pub use find::function::find;
+
+///
+pub mod from_tree;
+
+///
+pub mod write;
+
+///
+pub mod merge;
+pub use merge::function::merge;
+
+mod notes;
+
+/// All notes of a notes tree, mapping annotated objects to the blobs with their notes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notes {
+    object_hash: gix_hash::Kind,
+    notes: BTreeMap<gix_hash::ObjectId, gix_hash::ObjectId>,
+    non_notes: Vec<NonNote>,
+}
+
+/// An entry in a notes tree that isn't a note, which is retained when writing notes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonNote {
+    /// The path of the entry relative to the root of the notes tree.
+    pub path: BString,
+    /// The mode of the entry.
+    pub mode: gix_object::tree::EntryMode,
+    /// The object the entry points to.
+    pub id: gix_hash::ObjectId,
+}
@@ -0,0 +1,171 @@
+/// The error returned by [`merge()`](crate::merge()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    FindBlob(#[from] gix_object::find::existing_object::Error),
+    #[error(transparent)]
+    WriteBlob(#[from] gix_object::write::Error),
+}
+
+/// Determine how to resolve conflicting changes to the note of the same object, like `notes.mergeStrategy`.
+///
+/// The `manual` strategy of `git`, which is its default, isn't supported as it needs a worktree to resolve conflicts in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Strategy {
+    /// Keep our version of the note, which may also be its removal.
+    Ours,
+    /// Use their version of the note, which may also be its removal.
+    Theirs,
+    /// Concatenate our note and their note, separated by an empty line.
+    ///
+    /// If the note was removed on one side, the other version is used.
+    Union,
+    /// Concatenate the lines of our note and their note, sort them, and remove duplicates as well as empty lines.
+    CatSortUniq,
+}
+
+impl Strategy {
+    /// Return the name of the strategy as used in `notes.mergeStrategy` or `git notes merge --strategy`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Strategy::Ours => "ours",
+            Strategy::Theirs => "theirs",
+            Strategy::Union => "union",
+            Strategy::CatSortUniq => "cat_sort_uniq",
+        }
+    }
+}
+
+impl std::str::FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ours" => Strategy::Ours,
+            "theirs" => Strategy::Theirs,
+            "union" => Strategy::Union,
+            "cat_sort_uniq" => Strategy::CatSortUniq,
+            unknown => return Err(format!("Unknown notes merge strategy: '{unknown}'")),
+        })
+    }
+}
+
+pub(super) mod function {
+    use bstr::ByteSlice;
+    use gix_object::FindExt;
+
+    use super::{Error, Strategy};
+    use crate::Notes;
+
+    /// Merge the changes from `base` to `theirs` into `ours`, all of which are notes of notes trees, and use `strategy`
+    /// to resolve notes that were changed differently on both sides. `objects` is used to read notes, and to
+    /// write those that were combined.
+    ///
+    /// This is what `git notes merge` does after finding the notes of the merge-base of the notes commits in `base`.
+    /// If there is no merge-base, `base` is empty.
+    ///
+    /// Non-notes are taken from `ours`.
+    pub fn merge(
+        base: &Notes,
+        ours: &Notes,
+        theirs: &Notes,
+        strategy: Strategy,
+        objects: &(impl gix_object::Find + gix_object::Write),
+    ) -> Result<Notes, Error> {
+        let mut out = ours.clone();
+        let mut changed: Vec<_> = base
+            .notes
+            .keys()
+            .chain(theirs.notes.keys())
+            .filter(|object| base.get(object) != theirs.get(object))
+            .copied()
+            .collect();
+        changed.sort();
+        changed.dedup();
+
+        for object in changed {
+            let (base_note, our_note, their_note) = (base.get(&object), ours.get(&object), theirs.get(&object));
+            if our_note == their_note {
+                continue;
+            }
+            if our_note == base_note {
+                out.set(object, their_note.map(ToOwned::to_owned));
+                continue;
+            }
+            match strategy {
+                Strategy::Ours => {}
+                Strategy::Theirs => out.set(object, their_note.map(ToOwned::to_owned)),
+                Strategy::Union => {
+                    let Some(their_note) = their_note else { continue };
+                    let note = match our_note {
+                        Some(our_note) => concatenate(our_note, their_note, objects)?,
+                        None => their_note.to_owned(),
+                    };
+                    out.set(object, Some(note));
+                }
+                Strategy::CatSortUniq => {
+                    let note = match our_note {
+                        Some(our_note) => cat_sort_uniq(our_note, their_note, objects)?,
+                        None => match their_note {
+                            Some(their_note) => their_note.to_owned(),
+                            None => continue,
+                        },
+                    };
+                    out.set(object, Some(note));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Append the note `theirs` to the note `ours`, separated by an empty line, and return the blob with the result.
+    fn concatenate(
+        ours: &gix_hash::oid,
+        theirs: &gix_hash::oid,
+        objects: &(impl gix_object::Find + gix_object::Write),
+    ) -> Result<gix_hash::ObjectId, Error> {
+        let mut buf = Vec::new();
+        let their_data = objects.find_blob(theirs, &mut buf)?.data.to_owned();
+        if their_data.is_empty() {
+            return Ok(ours.to_owned());
+        }
+        let mut data = objects.find_blob(ours, &mut buf)?.data.to_owned();
+        if data.is_empty() {
+            return Ok(theirs.to_owned());
+        }
+        if data.last() == Some(&b'\n') {
+            data.pop();
+        }
+        data.extend_from_slice(b"\n\n");
+        data.extend_from_slice(&their_data);
+        Ok(objects.write_buf(gix_object::Kind::Blob, &data)?)
+    }
+
+    /// Combine the lines of the notes `ours` and `theirs`, if present, sort them and remove duplicates as well as
+    /// empty lines, and return the blob with the result.
+    fn cat_sort_uniq(
+        ours: &gix_hash::oid,
+        theirs: Option<&gix_hash::oid>,
+        objects: &(impl gix_object::Find + gix_object::Write),
+    ) -> Result<gix_hash::ObjectId, Error> {
+        let mut buf = Vec::new();
+        let mut lines = Vec::new();
+        for note in std::iter::once(ours).chain(theirs) {
+            let data = objects.find_blob(note, &mut buf)?.data;
+            lines.extend(
+                data.split_str("\n")
+                    .filter(|line| !line.is_empty())
+                    .map(ToOwned::to_owned),
+            );
+        }
+        lines.sort();
+        lines.dedup();
+        let mut data = Vec::new();
+        for line in lines {
+            data.extend_from_slice(&line);
+            data.push(b'\n');
+        }
+        Ok(objects.write_buf(gix_object::Kind::Blob, &data)?)
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{NonNote, Notes};
+
+/// Lifecycle
+impl Notes {
+    /// Create an empty instance for notes of objects with hashes of kind `object_hash`.
+    pub fn new(object_hash: gix_hash::Kind) -> Self {
+        Notes {
+            object_hash,
+            notes: Default::default(),
+            non_notes: Vec::new(),
+        }
+    }
+}
+
+/// Access
+impl Notes {
+    /// The kind of hash of all annotated objects and notes.
+    pub fn object_hash(&self) -> gix_hash::Kind {
+        self.object_hash
+    }
+
+    /// Return the blob with the note of `object`, if there is one.
+    pub fn get(&self, object: &gix_hash::oid) -> Option<&gix_hash::oid> {
+        self.notes.get(object).map(AsRef::as_ref)
+    }
+
+    /// Iterate over all annotated objects and the blobs with their notes, ordered by annotated object.
+    pub fn iter(&self) -> impl Iterator<Item = (&gix_hash::oid, &gix_hash::oid)> + '_ {
+        self.notes.iter().map(|(object, note)| (object.as_ref(), note.as_ref()))
+    }
+
+    /// Return the amount of notes.
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    /// Return `true` if there is no note.
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Return all entries of the notes tree that aren't notes.
+    pub fn non_notes(&self) -> &[NonNote] {
+        &self.non_notes
+    }
+}
+
+/// Mutation
+impl Notes {
+    /// Set the note of `object` to the blob `note`, and return the blob with the previous note, if there was one.
+    pub fn insert(&mut self, object: gix_hash::ObjectId, note: gix_hash::ObjectId) -> Option<gix_hash::ObjectId> {
+        self.notes.insert(object, note)
+    }
+
+    /// Remove the note of `object`, and return the blob with the note if there was one.
+    pub fn remove(&mut self, object: &gix_hash::oid) -> Option<gix_hash::ObjectId> {
+        self.notes.remove(object)
+    }
+
+    /// Set the note of `object` to `note`, or remove it if `note` is `None`.
+    pub(crate) fn set(&mut self, object: gix_hash::ObjectId, note: Option<gix_hash::ObjectId>) {
+        match note {
+            Some(note) => {
+                self.insert(object, note);
+            }
+            None => {
+                self.remove(&object);
+            }
+        }
+    }
+}
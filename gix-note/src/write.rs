@@ -0,0 +1,72 @@
+use bstr::{BString, ByteSlice};
+
+use crate::Notes;
+
+/// The error returned by [`Notes::write_tree()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Edit(#[from] gix_object::tree::editor::Error),
+    #[error(transparent)]
+    Write(#[from] gix_object::write::Error),
+}
+
+/// Output
+impl Notes {
+    /// Write all notes and non-notes as notes tree into `objects` and return its id.
+    ///
+    /// Notes are placed using the same fan-out as `git` would, which increases with the amount of notes.
+    pub fn write_tree(
+        &self,
+        objects: &(impl gix_object::Find + gix_object::Write),
+    ) -> Result<gix_hash::ObjectId, Error> {
+        let mut editor = gix_object::tree::Editor::new(Default::default(), objects, self.object_hash);
+        let annotated: Vec<_> = self.notes.keys().copied().collect();
+        let mut fanouts = Vec::with_capacity(annotated.len());
+        compute_fanout(&annotated, 0, 0, &mut fanouts);
+        let mut path = BString::default();
+        for ((object, note), fanout) in self.notes.iter().zip(fanouts) {
+            let hex = object.to_hex().to_string();
+            path.clear();
+            for level in 0..fanout {
+                path.extend_from_slice(&hex.as_bytes()[level * 2..][..2]);
+                path.push(b'/');
+            }
+            path.extend_from_slice(&hex.as_bytes()[fanout * 2..]);
+            editor.upsert(path.split_str("/"), gix_object::tree::EntryKind::Blob, *note)?;
+        }
+        for non_note in &self.non_notes {
+            editor.upsert(non_note.path.split_str("/"), non_note.mode.kind(), non_note.id)?;
+        }
+        Ok(editor.write(|tree| objects.write(tree))?)
+    }
+}
+
+/// Push the fan-out of each of the sorted `objects` to `out`, which all share their first `depth` hexadecimal characters
+/// and whose directories use at least `fanout`.
+///
+/// This mirrors what `git` does: The fan-out increases by one if each of the 16 possible characters at an even `depth`
+/// that is covered by the current fan-out is shared by at least two objects.
+fn compute_fanout(objects: &[gix_hash::ObjectId], depth: usize, mut fanout: usize, out: &mut Vec<usize>) {
+    let nibble = |id: &gix_hash::ObjectId| {
+        let byte = id.as_bytes()[depth / 2];
+        if depth % 2 == 0 { byte >> 4 } else { byte & 0xf }
+    };
+    if depth % 2 == 0 && depth <= 2 * fanout {
+        let mut counts = [0usize; 16];
+        for id in objects {
+            counts[nibble(id) as usize] += 1;
+        }
+        if counts.iter().all(|count| *count >= 2) {
+            fanout += 1;
+        }
+    }
+    for group in objects.chunk_by(|a, b| nibble(a) == nibble(b)) {
+        if group.len() == 1 {
+            out.push(fanout);
+        } else {
+            compute_fanout(group, depth + 1, fanout, out);
+        }
+    }
+}
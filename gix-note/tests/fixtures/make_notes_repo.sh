@@ -0,0 +1,55 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+for i in $(seq 300); do
+  echo "$i" >"f$i"
+done
+git add . && git commit -q -m files
+
+function blob() {
+  git rev-parse "HEAD:f$1"
+}
+
+git notes add -m "note for HEAD" HEAD
+
+# Enough notes for `git` to use a fan-out of one level.
+git notes --ref many add -m "one of many" "$(blob 1)"
+git ls-tree HEAD | while read -r _ _ id _; do
+  echo "$(blob 1) $id"
+done | grep -v "^$(blob 1) $(blob 1)$" | git notes --ref many copy --stdin
+
+# A note with a fan-out of two levels and an entry that isn't a note, as `git` would never write it itself.
+obj=$(git rev-parse HEAD)
+note=$(echo "deep note" | git hash-object -w --stdin)
+readme=$(echo "not a note" | git hash-object -w --stdin)
+inner=$(printf "100644 blob %s\t%s\n" "$note" "${obj:4}" | git mktree)
+middle=$(printf "040000 tree %s\t%s\n" "$inner" "${obj:2:2}" | git mktree)
+root=$(printf "040000 tree %s\t%s\n100644 blob %s\tREADME\n" "$middle" "${obj:0:2}" "$readme" | git mktree)
+git update-ref refs/notes/deep "$(git commit-tree -m deep "$root")"
+
+# Diverging notes for merging.
+for i in 1 2 3 4 5; do
+  git notes --ref base add -m "base $i" -m "shared" "$(blob $i)"
+done
+git update-ref refs/notes/ours refs/notes/base
+git update-ref refs/notes/theirs refs/notes/base
+
+git notes --ref ours add -f -m "ours 1" "$(blob 1)"
+git notes --ref ours add -f -m "ours 2" -m "shared" "$(blob 2)"
+git notes --ref ours remove "$(blob 3)"
+git notes --ref ours add -f -m "same 5" "$(blob 5)"
+git notes --ref ours add -m "ours 6" -m "shared" "$(blob 6)"
+git notes --ref ours add -m "ours 7" "$(blob 7)"
+
+git notes --ref theirs add -f -m "theirs 2" -m "shared" "$(blob 2)"
+git notes --ref theirs add -f -m "theirs 3" "$(blob 3)"
+git notes --ref theirs add -f -m "theirs 4" "$(blob 4)"
+git notes --ref theirs add -f -m "same 5" "$(blob 5)"
+git notes --ref theirs add -m "theirs 6" -m "shared" "$(blob 6)"
+git notes --ref theirs add -m "theirs 8" "$(blob 8)"
+
+for strategy in ours theirs union cat_sort_uniq; do
+  git update-ref "refs/notes/merged-$strategy" refs/notes/ours
+  git notes --ref "merged-$strategy" merge -q -s "$strategy" refs/notes/theirs
+done
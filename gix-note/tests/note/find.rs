@@ -0,0 +1,35 @@
+use crate::{blob, fixture, note_text, notes_tree, odb, rev_parse};
+
+#[test]
+fn without_fanout() -> crate::Result {
+    let tmp = fixture()?;
+    let repo = tmp.path();
+    let odb = odb(repo)?;
+    let tree = notes_tree(repo, "refs/notes/commits")?;
+    let note = gix_note::find(&tree, &rev_parse(repo, "HEAD")?, &odb)?.expect("HEAD is annotated");
+    assert_eq!(note_text(&odb, &note)?, "note for HEAD\n");
+    assert_eq!(
+        gix_note::find(&tree, &blob(repo, 1)?, &odb)?,
+        None,
+        "objects without note are not found"
+    );
+    Ok(())
+}
+
+#[test]
+fn with_fanout() -> crate::Result {
+    let tmp = fixture()?;
+    let repo = tmp.path();
+    let odb = odb(repo)?;
+    let tree = notes_tree(repo, "refs/notes/many")?;
+    for n in [1, 150, 300] {
+        let note = gix_note::find(&tree, &blob(repo, n)?, &odb)?.expect("all blobs are annotated");
+        assert_eq!(note_text(&odb, &note)?, "one of many\n");
+    }
+    assert_eq!(gix_note::find(&tree, &rev_parse(repo, "HEAD")?, &odb)?, None);
+
+    let tree = notes_tree(repo, "refs/notes/deep")?;
+    let note = gix_note::find(&tree, &rev_parse(repo, "HEAD")?, &odb)?.expect("found at the second level");
+    assert_eq!(note_text(&odb, &note)?, "deep note\n");
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::path::Path;
+
+pub use gix_testtools::Result;
+
+mod find;
+mod merge;
+mod notes;
+
+/// A writable copy of the fixture repository, as notes are written into its object database.
+fn fixture() -> Result<gix_testtools::tempfile::TempDir> {
+    gix_testtools::scripted_fixture_writable("make_notes_repo.sh")
+}
+
+/// Open the object database of the repository at `repo`.
+fn odb(repo: &Path) -> Result<gix_odb::Handle> {
+    Ok(gix_odb::at_opts(
+        repo.join(".git/objects"),
+        None,
+        gix_odb::store::init::Options {
+            object_hash: gix_testtools::object_hash(),
+            ..Default::default()
+        },
+    )?)
+}
+
+/// Resolve `spec` in the repository at `repo` using `git`.
+fn rev_parse(repo: &Path, spec: &str) -> Result<gix_hash::ObjectId> {
+    let hex = gix_testtools::git(repo, &format!("rev-parse {spec}"))?;
+    Ok(gix_hash::ObjectId::from_hex(hex.trim().as_bytes())?)
+}
+
+/// Return the tree of the notes commit at `notes_ref`.
+fn notes_tree(repo: &Path, notes_ref: &str) -> Result<gix_hash::ObjectId> {
+    rev_parse(repo, &format!("{notes_ref}^{{tree}}"))
+}
+
+/// Return the id of the blob of the file `f<n>` in `HEAD`, which all annotated blobs are.
+fn blob(repo: &Path, n: usize) -> Result<gix_hash::ObjectId> {
+    rev_parse(repo, &format!("HEAD:f{n}"))
+}
+
+fn note_text(odb: &impl gix_object::Find, note: &gix_hash::oid) -> Result<String> {
+    use gix_object::FindExt;
+    Ok(String::from_utf8(
+        odb.find_blob(note, &mut Vec::new())?.data.to_owned(),
+    )?)
+}
@@ -0,0 +1,42 @@
+use gix_note::{Notes, merge::Strategy};
+
+use crate::{fixture, notes_tree, odb};
+
+#[test]
+fn strategies_match_git() -> crate::Result {
+    let tmp = fixture()?;
+    let repo = tmp.path();
+    let odb = odb(repo)?;
+    let notes = |name: &str| -> crate::Result<Notes> { Ok(Notes::from_tree(&notes_tree(repo, name)?, &odb)?) };
+    let (base, ours, theirs) = (
+        notes("refs/notes/base")?,
+        notes("refs/notes/ours")?,
+        notes("refs/notes/theirs")?,
+    );
+    for strategy in [Strategy::Ours, Strategy::Theirs, Strategy::Union, Strategy::CatSortUniq] {
+        let merged = gix_note::merge(&base, &ours, &theirs, strategy, &odb)?;
+        let expected = notes_tree(repo, &format!("refs/notes/merged-{}", strategy.as_str()))?;
+        assert_eq!(merged.write_tree(&odb)?, expected, "{strategy:?}");
+    }
+    Ok(())
+}
+
+#[test]
+fn without_changes_on_their_side_ours_is_kept() -> crate::Result {
+    let tmp = fixture()?;
+    let repo = tmp.path();
+    let odb = odb(repo)?;
+    let ours = Notes::from_tree(&notes_tree(repo, "refs/notes/ours")?, &odb)?;
+    let base = Notes::from_tree(&notes_tree(repo, "refs/notes/base")?, &odb)?;
+    let merged = gix_note::merge(&base, &ours, &base, Strategy::Theirs, &odb)?;
+    assert_eq!(merged, ours);
+    Ok(())
+}
+
+#[test]
+fn strategy_names() {
+    for strategy in [Strategy::Ours, Strategy::Theirs, Strategy::Union, Strategy::CatSortUniq] {
+        assert_eq!(strategy.as_str().parse::<Strategy>(), Ok(strategy));
+    }
+    assert!("manual".parse::<Strategy>().is_err());
+}
@@ -0,0 +1,85 @@
+use gix_note::Notes;
+
+use crate::{blob, fixture, notes_tree, odb, rev_parse};
+
+#[test]
+fn from_tree_and_write_tree_roundtrip() -> crate::Result {
+    let tmp = fixture()?;
+    let repo = tmp.path();
+    let odb = odb(repo)?;
+    for (notes_ref, expected_len) in [("refs/notes/commits", 1), ("refs/notes/many", 300)] {
+        let tree = notes_tree(repo, notes_ref)?;
+        let notes = Notes::from_tree(&tree, &odb)?;
+        assert_eq!(notes.len(), expected_len);
+        assert!(notes.non_notes().is_empty());
+        assert_eq!(
+            notes.write_tree(&odb)?,
+            tree,
+            "{notes_ref}: the same fan-out as `git` is used"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn non_notes_are_retained_and_fanout_is_recomputed() -> crate::Result {
+    let tmp = fixture()?;
+    let repo = tmp.path();
+    let odb = odb(repo)?;
+    let head = rev_parse(repo, "HEAD")?;
+    let notes = Notes::from_tree(&notes_tree(repo, "refs/notes/deep")?, &odb)?;
+    assert_eq!(notes.len(), 1);
+    assert!(
+        notes.get(&head).is_some(),
+        "notes at the second level of fan-out are found"
+    );
+    assert_eq!(notes.non_notes().len(), 1);
+    assert_eq!(notes.non_notes()[0].path, "README");
+
+    let tree = notes.write_tree(&odb)?;
+    let roundtrip = Notes::from_tree(&tree, &odb)?;
+    assert_eq!(roundtrip, notes);
+    assert_eq!(
+        gix_testtools::git(repo, &format!("ls-tree --name-only {tree}"))?,
+        format!("{head}\nREADME\n"),
+        "a single note doesn't need fan-out"
+    );
+    Ok(())
+}
+
+#[test]
+fn insert_and_remove() -> crate::Result {
+    let tmp = fixture()?;
+    let repo = tmp.path();
+    let odb = odb(repo)?;
+    let mut notes = Notes::from_tree(&notes_tree(repo, "refs/notes/many")?, &odb)?;
+    let (first, second) = (blob(repo, 1)?, blob(repo, 2)?);
+    let previous = notes.insert(first, second);
+    assert!(previous.is_some(), "the previous note is returned");
+    assert_eq!(notes.get(&first), Some(second.as_ref()));
+    assert_eq!(
+        notes.remove(&second),
+        previous,
+        "the note of `second` is the same as the one `first` had"
+    );
+    assert_eq!(notes.remove(&second), None);
+    assert_eq!(notes.len(), 299);
+
+    let tree = notes.write_tree(&odb)?;
+    let roundtrip = Notes::from_tree(&tree, &odb)?;
+    assert_eq!(roundtrip, notes);
+    assert_eq!(gix_note::find(&tree, &first, &odb)?, Some(second));
+    assert_eq!(gix_note::find(&tree, &second, &odb)?, None);
+    Ok(())
+}
+
+#[test]
+fn empty() -> crate::Result {
+    let tmp = fixture()?;
+    let repo = tmp.path();
+    let odb = odb(repo)?;
+    let notes = Notes::new(gix_testtools::object_hash());
+    assert!(notes.is_empty());
+    assert_eq!(notes.write_tree(&odb)?, gix_testtools::object_hash().empty_tree());
+    Ok(())
+}
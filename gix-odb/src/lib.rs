@@ -64,6 +64,8 @@ pub use store_impls::{dynamic as store, loose};
 
 pub mod alternate;
 
+pub mod promisor;
+
 /// A way to access objects along with pre-configured thread-local caches for packed base objects as well as objects themselves.
 ///
 /// By default, no cache will be used.
@@ -170,6 +172,10 @@ pub struct Store {
     object_hash: gix_hash::Kind,
     /// The maximum size of a single allocation caused by user-controlled on-disk pack data.
     alloc_limit_bytes: Option<usize>,
+    /// If set, objects that are missing will be obtained from a promisor remote on demand.
+    pub(crate) promisor: parking_lot::RwLock<Option<Arc<dyn promisor::Fetch>>>,
+    /// The objects promised by the promisor packs whose sorted index paths are stored alongside, to avoid recomputing them.
+    pub(crate) promised_objects: parking_lot::Mutex<Option<(Vec<PathBuf>, Arc<gix_hashtable::HashSet>)>>,
 }
 
 /// Create a new cached handle to the object store with support for additional options.
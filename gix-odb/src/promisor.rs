@@ -0,0 +1,147 @@
+//! Support for partial clones, whose missing objects are promised to be available from a *promisor remote*.
+//!
+//! Packs received from a promisor remote are marked with a `.promisor` file next to them. All objects in these packs,
+//! along with all objects they refer to, are considered *promised* and may thus be missing without the object database
+//! being corrupt. If a [`Fetch`] implementation is set with [`Store::set_promisor()`](crate::Store::set_promisor()),
+//! missing objects will be obtained from the promisor remote on demand.
+use std::path::{Path, PathBuf};
+
+use gix_hash::ObjectId;
+use gix_object::bstr::BStr;
+
+/// The file extension of files that mark packs as received from a promisor remote.
+pub const EXTENSION: &str = "promisor";
+
+/// Obtain objects that are missing in a partial clone from a promisor remote, and write them into the object database.
+pub trait Fetch: Send + Sync {
+    /// Fetch all objects in `ids` from the promisor remote and make them available in the object database,
+    /// or fail if that wasn't possible.
+    fn fetch(&self, ids: &[ObjectId]) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+}
+
+impl std::fmt::Debug for dyn Fetch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("dyn promisor::Fetch")
+    }
+}
+
+/// Mark the pack at `pack_path`, the path to either its index or its data file, as received from a promisor remote
+/// by writing a `.promisor` file next to it.
+///
+/// Each of the `refs` is written on its own line, similar to what `git` does, for informational purposes only.
+pub fn mark_pack<'a>(pack_path: &Path, refs: impl IntoIterator<Item = (ObjectId, &'a BStr)>) -> std::io::Result<()> {
+    let mut content = Vec::new();
+    for (id, name) in refs {
+        content.extend_from_slice(id.to_hex().to_string().as_bytes());
+        content.push(b' ');
+        content.extend_from_slice(name);
+        content.push(b'\n');
+    }
+    std::fs::write(pack_path.with_extension(EXTENSION), content)
+}
+
+/// Return `true` if the pack at `pack_path`, the path to either its index or its data file, was received from a promisor remote.
+pub fn is_promisor_pack(pack_path: &Path) -> bool {
+    pack_path.with_extension(EXTENSION).is_file()
+}
+
+///
+pub mod promised_objects {
+    /// The error returned by [`promised_objects()`](super::promised_objects()) and [`Store::promised_objects()`](crate::Store::promised_objects()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not list the pack directory at '{}'", path.display())]
+        ReadDir {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+        #[error(transparent)]
+        OpenIndex(#[from] gix_pack::index::init::Error),
+        #[error(transparent)]
+        FindHeader(#[from] gix_object::find::existing::Error),
+        #[error(transparent)]
+        Find(#[from] gix_object::find::existing_iter::Error),
+        #[error(transparent)]
+        Decode(#[from] gix_object::decode::Error),
+    }
+}
+
+/// Return the ids of all objects that commits and trees in promisor packs of the object database at `objects_dir` refer to,
+/// using `objects` to read them.
+///
+/// These are the objects which may legitimately be missing in a partial clone as they can be obtained from the promisor remote.
+/// Only commits and trees are decoded, as these are the objects whose links may be missing due to a filter.
+/// The returned set is empty if there are no promisor packs.
+///
+/// Use [`Store::promised_objects()`](crate::Store::promised_objects()) to avoid recomputing the set if the promisor packs didn't change.
+pub fn promised_objects(
+    objects_dir: &Path,
+    object_hash: gix_hash::Kind,
+    objects: &dyn gix_object::FindObjectOrHeader,
+) -> Result<gix_hashtable::HashSet, promised_objects::Error> {
+    promised_by_packs(&promisor_packs(objects_dir)?, object_hash, objects)
+}
+
+/// Return the sorted paths to the indices of all promisor packs in `objects_dir`.
+pub(crate) fn promisor_packs(objects_dir: &Path) -> Result<Vec<PathBuf>, promised_objects::Error> {
+    let pack_dir = objects_dir.join("pack");
+    let entries = match std::fs::read_dir(&pack_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => return Err(promised_objects::Error::ReadDir { path: pack_dir, source }),
+    };
+    let mut out = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|source| promised_objects::Error::ReadDir {
+                path: pack_dir.clone(),
+                source,
+            })?
+            .path();
+        if path.extension().is_some_and(|ext| ext == EXTENSION) {
+            out.push(path.with_extension("idx"));
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// Collect the outgoing links of all commits and trees in the packs whose `indices` are given.
+pub(crate) fn promised_by_packs(
+    indices: &[PathBuf],
+    object_hash: gix_hash::Kind,
+    objects: &dyn gix_object::FindObjectOrHeader,
+) -> Result<gix_hashtable::HashSet, promised_objects::Error> {
+    use gix_object::FindExt;
+
+    let mut out = gix_hashtable::HashSet::default();
+    let mut buf = Vec::new();
+    for index in indices {
+        let index = gix_pack::index::File::at(index, object_hash)?;
+        for entry in index.iter() {
+            // Only the header is needed to skip blobs and tags without decompressing them.
+            let header = objects
+                .try_header(&entry.oid)
+                .map_err(gix_object::find::existing::Error::Find)?
+                .ok_or_else(|| gix_object::find::existing::Error::NotFound { oid: entry.oid })?;
+            match header.kind {
+                gix_object::Kind::Blob | gix_object::Kind::Tag => {}
+                gix_object::Kind::Tree => {
+                    for tree_entry in objects.find_tree_iter(&entry.oid, &mut buf)? {
+                        let tree_entry = tree_entry?;
+                        if !tree_entry.mode.is_commit() {
+                            out.insert(tree_entry.oid.to_owned());
+                        }
+                    }
+                }
+                gix_object::Kind::Commit => {
+                    let mut commit = objects.find_commit_iter(&entry.oid, &mut buf)?;
+                    out.insert(commit.tree_id()?);
+                    out.extend(commit.parent_ids());
+                }
+            }
+        }
+    }
+    Ok(out)
+}
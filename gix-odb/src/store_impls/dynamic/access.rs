@@ -1,4 +1,6 @@
-use crate::Store;
+use std::sync::Arc;
+
+use crate::{Store, promisor};
 
 impl Store {
     /// The root path at which we expect to find all objects and packs, and which is the source of the
@@ -21,4 +23,37 @@ impl Store {
     pub fn replacements(&self) -> impl Iterator<Item = (gix_hash::ObjectId, gix_hash::ObjectId)> + '_ {
         self.replacements.iter().copied()
     }
+
+    /// Set `promisor` to obtain objects that are missing from a promisor remote when they are looked up by handles that are
+    /// allowed to [refresh](crate::store::RefreshMode::AfterAllIndicesLoaded), and return the previous value.
+    ///
+    /// Note that checking for the existence of objects never fetches them.
+    pub fn set_promisor(&self, promisor: Option<Arc<dyn promisor::Fetch>>) -> Option<Arc<dyn promisor::Fetch>> {
+        std::mem::replace(&mut *self.promisor.write(), promisor)
+    }
+
+    /// Return the implementation to obtain missing objects from a promisor remote, if one was set.
+    pub fn promisor(&self) -> Option<Arc<dyn promisor::Fetch>> {
+        self.promisor.read().clone()
+    }
+
+    /// Return the ids of all objects that commits and trees in promisor packs refer to, using `objects` to read them.
+    ///
+    /// The set is computed like [`promised_objects()`](promisor::promised_objects()), but only once for as long as the
+    /// promisor packs don't change, so repeated calls are cheap.
+    pub fn promised_objects(
+        &self,
+        objects: &dyn gix_object::FindObjectOrHeader,
+    ) -> Result<Arc<gix_hashtable::HashSet>, promisor::promised_objects::Error> {
+        let packs = promisor::promisor_packs(&self.path)?;
+        let mut cache = self.promised_objects.lock();
+        if let Some((cached_packs, promised)) = cache.as_ref() {
+            if *cached_packs == packs {
+                return Ok(promised.clone());
+            }
+        }
+        let promised = Arc::new(promisor::promised_by_packs(&packs, self.object_hash, objects)?);
+        *cache = Some((packs, promised.clone()));
+        Ok(promised)
+    }
 }
@@ -44,6 +44,12 @@ pub(crate) mod error {
             /// The original object to lookup
             id: gix_hash::ObjectId,
         },
+        #[error("Could not obtain the missing object {id} from the promisor remote")]
+        Promisor {
+            source: Box<dyn std::error::Error + Send + Sync + 'static>,
+            /// The object that was missing
+            id: gix_hash::ObjectId,
+        },
     }
 
     #[derive(Copy, Clone)]
@@ -111,6 +117,7 @@ where
             }
         }
 
+        let mut fetched_from_promisor = false;
         'outer: loop {
             {
                 let marker = snapshot.marker;
@@ -303,11 +310,54 @@ where
                     *snapshot = new_snapshot;
                     self.clear_cache();
                 }
-                None => return Ok(None),
+                None => {
+                    if recursion.is_none() && !fetched_from_promisor && self.fetch_from_promisor(id)? {
+                        fetched_from_promisor = true;
+                        continue;
+                    }
+                    return Ok(None);
+                }
             }
         }
     }
 
+    /// Obtain the missing object `id` from the promisor remote, if there is one and if we are allowed to refresh,
+    /// and return `true` if it was fetched and another lookup is worth trying.
+    pub(crate) fn fetch_from_promisor(&self, id: &gix_hash::oid) -> Result<bool, Error> {
+        if matches!(self.refresh, super::RefreshMode::Never) {
+            return Ok(false);
+        }
+        let Some(promisor) = self.store.promisor() else {
+            return Ok(false);
+        };
+        promisor.fetch(&[id.to_owned()]).map_err(|source| Error::Promisor {
+            source,
+            id: id.to_owned(),
+        })?;
+        Ok(true)
+    }
+
+    /// Obtain all objects in `ids` which don't exist in the object database from the promisor remote at once, which
+    /// is more efficient than fetching each of them on demand when they are looked up.
+    ///
+    /// This does nothing if there is no promisor remote, or if this handle is configured to [never refresh](super::RefreshMode::Never).
+    pub fn fetch_missing(&self, ids: impl IntoIterator<Item = gix_hash::ObjectId>) -> Result<(), Error> {
+        use gix_pack::Find;
+        if matches!(self.refresh, super::RefreshMode::Never) {
+            return Ok(());
+        }
+        let Some(promisor) = self.store.promisor() else {
+            return Ok(());
+        };
+        let missing: Vec<_> = ids.into_iter().filter(|id| !self.contains(id)).collect();
+        let Some(first) = missing.first().copied() else {
+            return Ok(());
+        };
+        promisor
+            .fetch(&missing)
+            .map_err(|source| Error::Promisor { source, id: first })
+    }
+
     pub(crate) fn clear_cache(&self) {
         self.packed_object_count.borrow_mut().take();
     }
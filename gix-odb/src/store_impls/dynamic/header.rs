@@ -37,6 +37,7 @@ where
             }
         }
 
+        let mut fetched_from_promisor = false;
         'outer: loop {
             {
                 let marker = snapshot.marker;
@@ -174,7 +175,13 @@ where
                     *snapshot = new_snapshot;
                     self.clear_cache();
                 }
-                None => return Ok(None),
+                None => {
+                    if recursion.is_none() && !fetched_from_promisor && self.fetch_from_promisor(id)? {
+                        fetched_from_promisor = true;
+                        continue;
+                    }
+                    return Ok(None);
+                }
             }
         }
     }
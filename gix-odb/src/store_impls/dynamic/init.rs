@@ -141,6 +141,8 @@ impl Store {
             num_handles_stable: Default::default(),
             num_handles_unstable: Default::default(),
             num_disk_state_consolidation: Default::default(),
+            promisor: Default::default(),
+            promised_objects: Default::default(),
         })
     }
 }
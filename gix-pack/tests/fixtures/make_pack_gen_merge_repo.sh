@@ -0,0 +1,17 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git checkout -q -b main
+echo base >base && git add base && git commit -qm base
+
+git checkout -q -b side
+echo side >side && git add side && git commit -qm side
+
+git checkout -q main
+echo main >main && git add main && git commit -qm main
+
+# the merge adds a file that is new to both parents
+git merge -q --no-commit side
+echo resolution >resolution && git add resolution
+git commit -qm merge
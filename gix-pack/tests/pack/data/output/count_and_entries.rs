@@ -367,6 +367,42 @@ fn traversals() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn tree_additions_of_merge_commits_are_compared_to_all_parents() -> crate::Result {
+    let object_hash = object_hash();
+    let db = db(DbKind::MergeCommit, object_hash)?;
+    let merge = std::fs::read_to_string(
+        crate::scripted_fixture_read_only("make_pack_gen_merge_repo.sh")?.join(".git/refs/heads/main"),
+    )?;
+    let merge = gix_hash::ObjectId::from_hex(merge.trim().as_bytes())?;
+
+    let (counts, _stats) = count::objects_unthreaded(
+        &db,
+        &mut std::iter::once(Ok::<_, Box<dyn std::error::Error + Send + Sync + 'static>>(merge)),
+        &progress::Discard,
+        &AtomicBool::new(false),
+        count::objects::ObjectExpansion::TreeAdditionsComparedToAncestor,
+    )?;
+
+    let mut buf = Vec::new();
+    let mut actual_blobs = Vec::new();
+    for count in &counts {
+        if db.find(&count.id, &mut buf)?.0.kind == gix_object::Kind::Blob {
+            actual_blobs.push(count.id);
+        }
+    }
+    actual_blobs.sort();
+
+    let blob = |content: &str| gix_object::compute_hash(object_hash, gix_object::Kind::Blob, content.as_bytes());
+    let mut expected_blobs = vec![blob("main\n")?, blob("side\n")?, blob("resolution\n")?];
+    expected_blobs.sort();
+    assert_eq!(
+        actual_blobs, expected_blobs,
+        "blobs that are new compared to any parent are counted, even if they were seen in the diff to a previous parent"
+    );
+    Ok(())
+}
+
 #[test]
 #[cfg(all(not(feature = "wasm"), feature = "streaming-input"))]
 fn empty_pack_is_allowed() {
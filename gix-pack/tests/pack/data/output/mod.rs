@@ -30,6 +30,7 @@ fn size_of_count() {
 enum DbKind {
     DeterministicGeneratedContent,
     DeterministicGeneratedContentMultiIndex,
+    MergeCommit,
 }
 
 fn db(kind: DbKind, object_hash: gix_hash::Kind) -> crate::Result<gix_odb::HandleArc> {
@@ -37,6 +38,7 @@ fn db(kind: DbKind, object_hash: gix_hash::Kind) -> crate::Result<gix_odb::Handl
     let name = match kind {
         DeterministicGeneratedContent => "make_pack_gen_repo.sh",
         DeterministicGeneratedContentMultiIndex => "make_pack_gen_repo_multi_index.sh",
+        MergeCommit => "make_pack_gen_merge_repo.sh",
     };
     let path: PathBuf = crate::scripted_fixture_read_only(name)?.join(".git").join("objects");
     gix_odb::Store::at_opts(
@@ -0,0 +1,109 @@
+use std::borrow::Cow;
+
+use bstr::{BString, ByteSlice};
+use gix_features::progress::Progress;
+use gix_transport::client::Capabilities;
+
+use super::{Error, List};
+#[cfg(feature = "async-client")]
+use crate::transport::client::async_io::{self, ReadlineBufRead, TransportV2Ext as _};
+#[cfg(feature = "blocking-client")]
+use crate::transport::client::blocking_io::{self, ReadlineBufRead, TransportV2Ext as _};
+use crate::{Command, command::Feature};
+
+/// A command to obtain the [list of bundles](List) a server advertises.
+///
+/// It acts as a utility to separate the invocation into the shared blocking portion,
+/// and the one that performs IO either blocking or `async`.
+///
+/// It should only be invoked if the server [advertised](super::is_advertised()) the command.
+pub struct BundleUriCommand<'a> {
+    capabilities: &'a Capabilities,
+    features: Vec<Feature>,
+}
+
+impl<'a> BundleUriCommand<'a> {
+    /// Build a command to obtain the bundle list from the given server `capabilities`,
+    /// using `agent` information to identify ourselves.
+    pub fn new(capabilities: &'a Capabilities, agent: (&'static str, Option<Cow<'static, str>>)) -> Self {
+        let mut features = Command::BundleUri.default_features(gix_transport::Protocol::V2, capabilities);
+        features.push(agent);
+        Self { capabilities, features }
+    }
+
+    /// Invoke a bundle-uri V2 command on `transport`.
+    ///
+    /// `progress` is used to provide feedback.
+    /// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+    #[cfg(feature = "async-client")]
+    pub async fn invoke_async(
+        self,
+        mut transport: impl async_io::Transport,
+        progress: &mut impl Progress,
+        trace: bool,
+    ) -> Result<List, Error> {
+        let _span = gix_features::trace::detail!("gix_protocol::BundleUriCommand::invoke_async()");
+        Command::BundleUri.validate_argument_prefixes(
+            gix_transport::Protocol::V2,
+            self.capabilities,
+            &[],
+            &self.features,
+        )?;
+
+        progress.step();
+        progress.set_name("list bundles".into());
+        let mut reader = transport
+            .invoke(
+                Command::BundleUri.as_str(),
+                self.features.into_iter(),
+                None::<std::iter::Empty<BString>>,
+                trace,
+            )
+            .await?;
+        let mut lines = Vec::new();
+        while let Some(line) = reader
+            .readline()
+            .await
+            .transpose()?
+            .transpose()?
+            .and_then(|l| l.as_bstr())
+        {
+            lines.push(line.trim_end().as_bstr().to_owned());
+        }
+        Ok(List::from_lines(lines.iter().map(AsRef::as_ref))?)
+    }
+
+    /// Invoke a bundle-uri V2 command on `transport`.
+    ///
+    /// `progress` is used to provide feedback.
+    /// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+    #[cfg(feature = "blocking-client")]
+    pub fn invoke_blocking(
+        self,
+        mut transport: impl blocking_io::Transport,
+        progress: &mut impl Progress,
+        trace: bool,
+    ) -> Result<List, Error> {
+        let _span = gix_features::trace::detail!("gix_protocol::BundleUriCommand::invoke_blocking()");
+        Command::BundleUri.validate_argument_prefixes(
+            gix_transport::Protocol::V2,
+            self.capabilities,
+            &[],
+            &self.features,
+        )?;
+
+        progress.step();
+        progress.set_name("list bundles".into());
+        let mut reader = transport.invoke(
+            Command::BundleUri.as_str(),
+            self.features.into_iter(),
+            None::<std::iter::Empty<BString>>,
+            trace,
+        )?;
+        let mut lines = Vec::new();
+        while let Some(line) = reader.readline().transpose()?.transpose()?.and_then(|l| l.as_bstr()) {
+            lines.push(line.trim_end().as_bstr().to_owned());
+        }
+        Ok(List::from_lines(lines.iter().map(AsRef::as_ref))?)
+    }
+}
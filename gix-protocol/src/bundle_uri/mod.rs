@@ -0,0 +1,252 @@
+//! Types and functions for the `bundle-uri` command of protocol version 2, which lets servers advertise bundles
+//! that clients can download from elsewhere, like a CDN, before fetching what's still missing from the server itself.
+//!
+//! The server responds with a [bundle list](List) made of `key=value` lines which use the same keys as the `bundle.*`
+//! section of a git configuration file, like `bundle.version=1` or `bundle.<id>.uri=<uri>`.
+//!
+//! See [the documentation of the bundle URI design](https://github.com/git/git/blob/master/Documentation/technical/bundle-uri.adoc)
+//! for details.
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+
+/// The only version of the bundle list format that is known.
+const VERSION: &str = "1";
+
+/// Describes how the bundles of a [`List`] relate to each other.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum Mode {
+    /// All bundles are needed to obtain the complete set of objects, as they build upon each other.
+    #[default]
+    All,
+    /// Each bundle is sufficient on its own, so clients may use any one of them, for example the one closest to them.
+    Any,
+}
+
+impl Mode {
+    /// Parse the mode from `name`, or return `None` if it's unknown.
+    pub fn from_bytes(name: &[u8]) -> Option<Self> {
+        Some(match name {
+            b"all" => Mode::All,
+            b"any" => Mode::Any,
+            _ => return None,
+        })
+    }
+
+    /// Return the name of the mode as used in bundle lists.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::All => "all",
+            Mode::Any => "any",
+        }
+    }
+}
+
+/// A hint on how to use the bundles of a [`List`] efficiently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum Heuristic {
+    /// Each bundle has a [creation token](Bundle::creation_token), and bundles with higher tokens build on the ones
+    /// with lower tokens, so bundles should be applied in ascending order of their tokens.
+    CreationToken,
+}
+
+impl Heuristic {
+    /// Parse the heuristic from `name`, or return `None` if it's unknown.
+    pub fn from_bytes(name: &[u8]) -> Option<Self> {
+        (name == b"creationToken").then_some(Heuristic::CreationToken)
+    }
+
+    /// Return the name of the heuristic as used in bundle lists.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Heuristic::CreationToken => "creationToken",
+        }
+    }
+}
+
+/// A single bundle within a [`List`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub struct Bundle {
+    /// The identifier of the bundle, which is unique within its list.
+    pub id: BString,
+    /// The location to download the bundle from.
+    ///
+    /// It may also point to another bundle list, and may be relative to the location of the list it's contained in.
+    pub uri: BString,
+    /// A number which is larger for bundles that were created later, for use with [`Heuristic::CreationToken`].
+    pub creation_token: Option<u64>,
+}
+
+/// A list of bundles, as advertised by a server or stored in a configuration file.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct List {
+    /// How the bundles relate to each other.
+    pub mode: Mode,
+    /// An optional hint on how to use the bundles.
+    pub heuristic: Option<Heuristic>,
+    /// The bundles in the order in which they were listed.
+    pub bundles: Vec<Bundle>,
+}
+
+///
+pub mod parse {
+    use bstr::BString;
+
+    /// The error returned by [`List::from_lines()`](super::List::from_lines()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Expected a line like 'bundle.version=1', got {line:?}")]
+        Malformed { line: BString },
+        #[error("The bundle list version {version:?} is unsupported")]
+        UnsupportedVersion { version: BString },
+        #[error("The bundle list mode {mode:?} is unknown")]
+        UnknownMode { mode: BString },
+        #[error("The creation token {token:?} of bundle {id:?} isn't a number")]
+        CreationToken { id: BString, token: BString },
+        #[error("The bundle {id:?} doesn't have a URI")]
+        MissingUri { id: BString },
+    }
+}
+
+/// Lifecycle
+impl List {
+    /// Parse a bundle list from `lines` of the form `key=value`, like `bundle.mode=all`, as sent by a server in response
+    /// to the `bundle-uri` command.
+    ///
+    /// Keys are matched case-insensitively except for the bundle identifier, and unknown keys are ignored just like
+    /// unknown heuristics are.
+    pub fn from_lines<'a>(lines: impl IntoIterator<Item = &'a BStr>) -> Result<Self, parse::Error> {
+        use parse::Error;
+        let mut list = List::default();
+        let mut uris = Vec::<Option<BString>>::new();
+        for line in lines {
+            let malformed = || Error::Malformed { line: line.to_owned() };
+            let (key, value) = line.split_once_str(b"=").ok_or_else(malformed)?;
+            let key = match key.get(..7) {
+                Some(prefix) if prefix.eq_ignore_ascii_case(b"bundle.") => &key[7..],
+                _ => return Err(malformed()),
+            };
+            match key.rsplit_once_str(b".") {
+                None => {
+                    if key.eq_ignore_ascii_case(b"version") {
+                        if value != VERSION.as_bytes() {
+                            return Err(Error::UnsupportedVersion { version: value.into() });
+                        }
+                    } else if key.eq_ignore_ascii_case(b"mode") {
+                        list.mode = Mode::from_bytes(value).ok_or_else(|| Error::UnknownMode { mode: value.into() })?;
+                    } else if key.eq_ignore_ascii_case(b"heuristic") {
+                        list.heuristic = Heuristic::from_bytes(value);
+                    }
+                }
+                Some((id, key)) => {
+                    if id.is_empty() {
+                        return Err(malformed());
+                    }
+                    let idx = match list.bundles.iter().position(|b| b.id == id) {
+                        Some(idx) => idx,
+                        None => {
+                            list.bundles.push(Bundle {
+                                id: id.into(),
+                                uri: BString::default(),
+                                creation_token: None,
+                            });
+                            uris.push(None);
+                            list.bundles.len() - 1
+                        }
+                    };
+                    if key.eq_ignore_ascii_case(b"uri") {
+                        uris[idx] = Some(value.into());
+                    } else if key.eq_ignore_ascii_case(b"creationToken") {
+                        let bundle = &mut list.bundles[idx];
+                        bundle.creation_token = Some(
+                            value
+                                .to_str()
+                                .ok()
+                                .and_then(|token| token.parse().ok())
+                                .ok_or_else(|| Error::CreationToken {
+                                    id: bundle.id.clone(),
+                                    token: value.into(),
+                                })?,
+                        );
+                    }
+                }
+            }
+        }
+        for (bundle, uri) in list.bundles.iter_mut().zip(uris) {
+            bundle.uri = uri.ok_or_else(|| Error::MissingUri { id: bundle.id.clone() })?;
+        }
+        Ok(list)
+    }
+}
+
+/// Serialization
+impl List {
+    /// Return the list as `key=value` lines, suitable for sending them in response to the `bundle-uri` command and
+    /// for parsing them with [`from_lines()`](Self::from_lines()).
+    pub fn to_lines(&self) -> Vec<BString> {
+        let mut lines = vec![
+            format!("bundle.version={VERSION}").into(),
+            format!("bundle.mode={}", self.mode.as_str()).into(),
+        ];
+        if let Some(heuristic) = self.heuristic {
+            lines.push(format!("bundle.heuristic={}", heuristic.as_str()).into());
+        }
+        for bundle in &self.bundles {
+            let key = |name: &str| {
+                let mut line = BString::from("bundle.");
+                line.push_str(&bundle.id);
+                line.push_byte(b'.');
+                line.push_str(name);
+                line.push_byte(b'=');
+                line
+            };
+            let mut line = key("uri");
+            line.push_str(&bundle.uri);
+            lines.push(line);
+            if let Some(token) = bundle.creation_token {
+                let mut line = key("creationToken");
+                line.push_str(token.to_string());
+                lines.push(line);
+            }
+        }
+        lines
+    }
+}
+
+/// Return `true` if the server advertised the `bundle-uri` command in its `capabilities`.
+pub fn is_advertised(capabilities: &gix_transport::client::Capabilities) -> bool {
+    capabilities.contains(crate::Command::BundleUri.as_str())
+}
+
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+mod error {
+    /// The error returned by invoking a [`super::function::BundleUriCommand`].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error(transparent)]
+        Transport(#[from] gix_transport::client::Error),
+        #[error(transparent)]
+        DecodePacketline(#[from] gix_transport::packetline::decode::Error),
+        #[error("Could not parse the bundle list sent by the server")]
+        Parse(#[from] super::parse::Error),
+        #[error(transparent)]
+        ArgumentValidation(#[from] crate::command::validate_argument_prefixes::Error),
+    }
+
+    impl gix_transport::IsSpuriousError for Error {
+        fn is_spurious(&self) -> bool {
+            match self {
+                Error::Io(err) => err.is_spurious(),
+                Error::Transport(err) => err.is_spurious(),
+                _ => false,
+            }
+        }
+    }
+}
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+pub use error::Error;
+
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+pub(crate) mod function;
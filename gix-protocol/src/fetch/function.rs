@@ -52,6 +52,7 @@ pub async fn fetch<P, T, E>(
         shallow,
         tags,
         reject_shallow_remote,
+        filter,
     }: Options<'_>,
 ) -> Result<Option<Outcome>, Error>
 where
@@ -85,6 +86,13 @@ where
         }
         arguments.use_include_tag();
     }
+    if let Some(spec) = filter {
+        if arguments.can_use_filter() {
+            arguments.filter(spec);
+        } else {
+            gix_trace::warn!("filtering not recognized by server, ignoring");
+        }
+    }
     let (shallow_commits, mut shallow_lock) = add_shallow_args(&mut arguments, shallow, &shallow_file)?;
 
     let negotiate_span = gix_trace::detail!(
@@ -127,8 +135,7 @@ where
                 if sideband_all {
                     setup_remote_progress(&mut progress, &mut reader, should_interrupt);
                 }
-                let response =
-                    crate::fetch::Response::from_line_reader(protocol_version, &mut reader, is_done, !is_done).await?;
+                let response = crate::fetch::Response::from_line_reader(protocol_version, &mut reader, is_done).await?;
                 let has_pack = response.has_pack();
                 previous_response = Some(response);
                 if has_pack {
@@ -139,6 +146,12 @@ where
                     }
                     break 'negotiation reader;
                 }
+                if is_done {
+                    // The server won't answer to more haves after `done`, so waiting for another response would block forever.
+                    return Err(Error::Negotiate(negotiate::Error::NegotiationFailed {
+                        rounds: rounds.len(),
+                    }));
+                }
             };
             // This needs drop if tracing is compiled in. We just don't know it.
             #[allow(clippy::drop_non_drop)]
@@ -9,7 +9,7 @@ use std::borrow::Cow;
 
 use gix_date::SecondsSinceUnixEpoch;
 use gix_negotiate::Flags;
-use gix_ref::file::ReferenceExt;
+use gix_ref::store::ReferenceExt;
 
 use crate::fetch::{RefMap, Shallow, Tags, refmap};
 
@@ -28,7 +28,7 @@ pub enum Error {
     #[error(transparent)]
     IO(#[from] std::io::Error),
     #[error(transparent)]
-    InitRefIter(#[from] gix_ref::file::iter::loose_then_packed::Error),
+    InitRefIter(#[from] gix_ref::store::iter::Error),
     #[error(transparent)]
     PeelToId(#[from] gix_ref::peel::to_id::Error),
     #[error(transparent)]
@@ -120,7 +120,7 @@ pub struct Round {
 #[allow(clippy::too_many_arguments)]
 pub fn mark_complete_and_common_ref<Out, F, E>(
     objects: &(impl gix_object::Find + gix_object::FindHeader + gix_object::Exists),
-    refs: &gix_ref::file::Store,
+    refs: &gix_ref::store::Handle,
     alternates: impl FnOnce() -> Result<Out, E>,
     negotiator: &mut dyn gix_negotiate::Negotiator,
     graph: &mut gix_negotiate::Graph<'_, '_>,
@@ -130,7 +130,7 @@ pub fn mark_complete_and_common_ref<Out, F, E>(
 ) -> Result<Action, Error>
 where
     E: Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
-    Out: Iterator<Item = (gix_ref::file::Store, F)>,
+    Out: Iterator<Item = (gix_ref::store::Handle, F)>,
     F: gix_object::Find,
 {
     let _span = gix_trace::detail!("mark_complete_and_common_ref", mappings = ref_map.mappings.len());
@@ -364,7 +364,7 @@ fn mark_recent_complete_commits(
 }
 
 fn mark_all_refs_in_repo(
-    store: &gix_ref::file::Store,
+    store: &gix_ref::store::Handle,
     objects: &impl gix_object::Find,
     graph: &mut gix_negotiate::Graph<'_, '_>,
     queue: &mut Queue,
@@ -439,7 +439,8 @@ pub mod one_round {
 ///
 /// The operation is performed with `negotiator` and `graph`, sending the amount of `haves_to_send` after possibly
 /// making the common commits (as sent by the remote) known to `negotiator` using `previous_response`, if this isn't the first round.
-/// All [commits we have](crate::fetch::Arguments::have()) are added to `arguments` accordingly.
+/// All [commits we have](crate::fetch::Arguments::have()) are added to `arguments` accordingly, unless the remote signalled
+/// that it's `ready` to send a pack in `previous_response`, which ends the negotiation without sending more haves.
 ///
 /// Returns information about this round, and `true` if we are done and should stop negotiating *after* the `arguments` have
 /// been sent to the remote one last time.
@@ -451,6 +452,7 @@ pub fn one_round(
     previous_response: Option<&crate::fetch::Response>,
 ) -> Result<(Round, bool), Error> {
     let mut seen_ack = false;
+    let mut seen_ready = false;
     if let Some(response) = previous_response {
         use crate::fetch::response::Acknowledgement;
         for ack in response.acknowledgements() {
@@ -463,8 +465,9 @@ pub fn one_round(
                     }
                 }
                 Acknowledgement::Ready => {
-                    // NOTE: In git, there is some logic dealing with whether to expect a DELIM or FLUSH package,
-                    //       but we handle this with peeking.
+                    // The server has enough common commits to send a pack, and would only send more `ready` acknowledgements
+                    // if we kept negotiating. This only happens in V1 as in V2, the pack would have been sent right away.
+                    seen_ready = true;
                 }
                 Acknowledgement::Nak => {}
             }
@@ -480,9 +483,11 @@ pub fn one_round(
     }
 
     let mut haves_added = 0;
-    for have_id in (0..state.haves_to_send).map_while(|_| negotiator.next_have(graph)) {
-        arguments.have(have_id?);
-        haves_added += 1;
+    if !seen_ready {
+        for have_id in (0..state.haves_to_send).map_while(|_| negotiator.next_have(graph)) {
+            arguments.have(have_id?);
+            haves_added += 1;
+        }
     }
     // Note that we are differing from the git implementation, which does an extra-round of with no new haves sent at all.
     // For us, it seems better to just say we are done when we know we are done, as potentially additional acks won't affect the
@@ -498,7 +503,7 @@ pub fn one_round(
         haves_to_send: state.haves_to_send,
         previous_response_had_at_least_one_in_common: seen_ack,
     };
-    let is_done = haves_added != state.haves_to_send || (state.seen_ack && state.in_vain >= 256);
+    let is_done = seen_ready || haves_added != state.haves_to_send || (state.seen_ack && state.in_vain >= 256);
     state.adjust_window_size();
 
     Ok((round, is_done))
@@ -201,30 +201,45 @@ impl Response {
 
 #[cfg(any(feature = "async-client", feature = "blocking-client"))]
 impl Response {
-    /// with a friendly server, we just assume that a non-ack line is a pack line
-    /// which is our hint to stop here.
-    fn parse_v1_ack_or_shallow_or_assume_pack(
+    /// Parse `line` of a V1 response, which is either an acknowledgement or a shallow update, and keep it in `acks` or `shallows`
+    /// respectively.
+    ///
+    /// Return `None` if `line` is the first line of the pack, which is only valid if the `client_expects_pack` as it sent `done`,
+    /// or `Some(true)` if `line` is the last one of the response.
+    /// Otherwise, `Some(false)` indicates that more lines are to be read.
+    ///
+    /// Without `done`, each batch of `have` lines sent by the client is answered by the server with acknowledgements terminated by `NAK`.
+    /// After `done`, we keep reading acknowledgements until the pack starts, as the server may still answer previous batches first.
+    /// This way we never try to read more than the server sends, which would block forever on stateful connections.
+    fn parse_v1_line(
         acks: &mut Vec<Acknowledgement>,
         shallows: &mut Vec<ShallowUpdate>,
-        peeked_line: &str,
-    ) -> bool {
-        match Acknowledgement::from_line(peeked_line) {
-            Ok(ack) => match ack.id() {
-                Some(id) => {
-                    if !acks.iter().any(|a| a.id() == Some(id)) {
-                        acks.push(ack);
+        line: &str,
+        client_expects_pack: bool,
+    ) -> Result<Option<bool>, Error> {
+        match Acknowledgement::from_line(line) {
+            Ok(ack) => {
+                let is_last = ack == Acknowledgement::Nak && !client_expects_pack;
+                match ack.id() {
+                    Some(id) => {
+                        if !acks.iter().any(|a| a.id() == Some(id)) {
+                            acks.push(ack);
+                        }
                     }
+                    None => acks.push(ack),
                 }
-                None => acks.push(ack),
-            },
-            Err(_) => match shallow_update_from_line(peeked_line) {
+                Ok(Some(is_last))
+            }
+            Err(err) => match shallow_update_from_line(line) {
                 Ok(shallow) => {
                     shallows.push(shallow);
+                    Ok(Some(false))
                 }
-                Err(_) => return true,
+                // Anything else starts the pack, which is only sent after the client is done.
+                Err(_) if client_expects_pack => Ok(None),
+                Err(_) => Err(err),
             },
         }
-        false
     }
 }
 
@@ -16,6 +16,11 @@ pub struct Options<'a> {
     /// If `true`, if we fetch from a remote that only offers shallow clones, the operation will fail with an error
     /// instead of writing the shallow boundary to the shallow file.
     pub reject_shallow_remote: bool,
+    /// If set, the filter specification like `blob:none` to ask the server to omit objects with, resulting in a partial clone
+    /// whose missing objects are promised by the remote.
+    ///
+    /// If the server doesn't support filters, it will be ignored like `git` does, and all objects will be received.
+    pub filter: Option<&'a str>,
 }
 
 /// For use in [`crate::Handshake::prepare_lsrefs_or_extract_refmap()`] and [`fetch`](crate::fetch()).
@@ -49,14 +54,14 @@ mod with_fetch {
         Objects: gix_object::Find + gix_object::FindHeader + gix_object::Exists,
         Alternates: FnOnce() -> Result<AlternatesOut, AlternatesErr>,
         AlternatesErr: Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
-        AlternatesOut: Iterator<Item = (gix_ref::file::Store, Find)>,
+        AlternatesOut: Iterator<Item = (gix_ref::store::Handle, Find)>,
         Find: gix_object::Find,
     {
         /// Access to the object database.
         /// *Note* that the `exists()` calls must not trigger a refresh of the ODB packs as plenty of them might fail, i.e. find on object.
         pub objects: &'a Objects,
         /// Access to the git references database.
-        pub refs: &'a gix_ref::file::Store,
+        pub refs: &'a gix_ref::store::Handle,
         /// A function that returns an iterator over `(refs, objects)` for each alternate repository, to assure all known objects are added also according to their tips.
         pub alternates: Alternates,
         /// The implementation that performs the negotiation later, i.e. prepare wants and haves.
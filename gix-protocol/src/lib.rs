@@ -7,7 +7,9 @@
 //! * execute a [`Command`]
 //!     - [list references](LsRefsCommand)
 //!          - create a mapping between [refspecs and references](fetch::RefMap)
+//!     - [list bundles](BundleUriCommand) to download from elsewhere before fetching
 //!     - [receive a pack](fetch())
+//!     - [send a pack](push()), which is only available with the `blocking-client` feature
 //!
 //! ## Feature Flags
 #![cfg_attr(
@@ -30,6 +32,8 @@ pub enum Command {
     LsRefs,
     /// Fetch a pack.
     Fetch,
+    /// List bundles to download before fetching.
+    BundleUri,
 }
 pub mod command;
 
@@ -50,6 +54,23 @@ pub mod fetch;
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
 pub use fetch::function::fetch;
 
+///
+pub mod push;
+#[cfg(feature = "blocking-client")]
+pub use push::function::push;
+
+///
+#[cfg(feature = "upload-pack")]
+pub mod upload_pack;
+#[cfg(feature = "upload-pack")]
+pub use upload_pack::function::serve as upload_pack;
+
+///
+#[cfg(feature = "receive-pack")]
+pub mod receive_pack;
+#[cfg(feature = "receive-pack")]
+pub use receive_pack::function::serve as receive_pack;
+
 mod remote_progress;
 pub use remote_progress::RemoteProgress;
 
@@ -69,5 +90,10 @@ pub mod ls_refs;
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
 pub use ls_refs::function::LsRefsCommand;
 
+///
+pub mod bundle_uri;
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+pub use bundle_uri::function::BundleUriCommand;
+
 mod util;
 pub use util::*;
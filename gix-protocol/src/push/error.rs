@@ -0,0 +1,35 @@
+/// The error returned by [`push()`](crate::push()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Client(#[from] crate::transport::client::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Pushing requires protocol version 0 or 1, but the server responded with {actual:?}")]
+    UnsupportedProtocolVersion { actual: crate::transport::Protocol },
+    #[error("Server lack feature {feature:?}: {description}")]
+    MissingServerFeature {
+        feature: &'static str,
+        description: &'static str,
+    },
+    #[error("The remote uses {remote} object hashes, while {local} hashes are to be pushed")]
+    IncompatibleObjectHash {
+        local: gix_hash::Kind,
+        remote: bstr::BString,
+    },
+    #[error("Failed to write the pack to send to the remote")]
+    WritePack(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("Could not decode the status report of the remote")]
+    Report(#[from] crate::push::report::decode::Error),
+}
+
+impl crate::transport::IsSpuriousError for Error {
+    fn is_spurious(&self) -> bool {
+        match self {
+            Error::Client(err) => err.is_spurious(),
+            Error::Io(err) => err.is_spurious(),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    io::{Read, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_features::progress::DynNestedProgress;
+use gix_transport::{
+    client::{
+        MessageKind, WriteMode,
+        blocking_io::{ExtendedBufRead, HandleProgress, Transport},
+    },
+    packetline::{PacketLineRef, blocking_io::StreamingPeekableIter},
+};
+
+use crate::push::{Command, Context, Error, Options, ProgressId, Report};
+
+/// Send `commands` to update references on the remote along with a pack written by `write_pack`, using a `transport` on which
+/// a [handshake](crate::handshake()) for the `receive-pack` service was performed, as passed with `Context`.
+///
+/// `write_pack(pack_write, progress, interrupt)` is called to write a complete pack with the objects needed by the remote
+/// to perform the updates. It isn't called if all `commands` delete references.
+/// `progress` and `should_interrupt` are passed to all potentially long-running parts of the operation, and progress sent by
+/// the remote is forwarded to `progress` as well.
+///
+/// `Options` define additional parts of this `push` operation, which cause an error if the remote doesn't support them.
+///
+/// Return `Ok(None)` if `commands` was empty, in which case nothing is sent, or if the remote doesn't support sending
+/// a status report. Otherwise, return the report of the remote, which has to be checked to learn if the updates were performed.
+///
+/// As opposed to a full `git push`, this operation does *not*…
+///
+/// * …determine the updates or objects to send
+/// * …update local remote-tracking references
+///
+/// **Note that the interaction ends with this call** as the remote doesn't accept any other command, but an empty `commands`
+/// list leaves it up to the caller to [indicate the end of the interaction](crate::indicate_end_of_interaction()).
+pub fn push<P, T, E>(
+    commands: &[Command],
+    write_pack: impl FnOnce(&mut dyn Write, &mut dyn DynNestedProgress, &AtomicBool) -> Result<(), E>,
+    mut progress: P,
+    should_interrupt: &AtomicBool,
+    Context {
+        handshake,
+        transport,
+        user_agent,
+        trace_packetlines,
+    }: Context<'_, T>,
+    Options { atomic, push_options }: Options,
+) -> Result<Option<Report>, Error>
+where
+    P: gix_features::progress::NestedProgress,
+    P::SubProgress: 'static,
+    T: Transport,
+    E: Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
+{
+    let _span = gix_trace::coarse!("gix_protocol::push()");
+    if handshake.server_protocol_version == gix_transport::Protocol::V2 {
+        return Err(Error::UnsupportedProtocolVersion {
+            actual: handshake.server_protocol_version,
+        });
+    }
+    let Some(first) = commands.first() else {
+        return Ok(None);
+    };
+
+    let capabilities = &handshake.capabilities;
+    let mut features = Vec::new();
+    let report_status = ["report-status-v2", "report-status"]
+        .into_iter()
+        .find(|name| capabilities.contains(name));
+    features.extend(report_status);
+    let sideband = capabilities.contains("side-band-64k");
+    if sideband {
+        features.push("side-band-64k");
+    }
+    if atomic {
+        if !capabilities.contains("atomic") {
+            return Err(Error::MissingServerFeature {
+                feature: "atomic",
+                description: "Atomic pushes aren't supported by the remote",
+            });
+        }
+        features.push("atomic");
+    }
+    if !push_options.is_empty() {
+        if !capabilities.contains("push-options") {
+            return Err(Error::MissingServerFeature {
+                feature: "push-options",
+                description: "Push options can't be transmitted to the remote",
+            });
+        }
+        features.push("push-options");
+    }
+    if commands.iter().any(Command::is_delete) {
+        if !capabilities.contains("delete-refs") {
+            return Err(Error::MissingServerFeature {
+                feature: "delete-refs",
+                description: "References can't be deleted on the remote",
+            });
+        }
+        features.push("delete-refs");
+    }
+
+    let mut capabilities_line = BString::from(features.join(" "));
+    let object_hash = first.new.kind();
+    let remote_object_format = capabilities.capability("object-format").and_then(|c| c.value());
+    if remote_object_format.unwrap_or("sha1".into()) != object_hash.to_string().as_str() {
+        return Err(Error::IncompatibleObjectHash {
+            local: object_hash,
+            remote: remote_object_format.unwrap_or("sha1".into()).into(),
+        });
+    }
+    if remote_object_format.is_some() {
+        capabilities_line.push_str(format!(" object-format={object_hash}"));
+    }
+    if let (name, Some(value)) = &user_agent {
+        capabilities_line.push_str(format!(" {name}={value}"));
+    }
+
+    let mut request = transport.request(
+        WriteMode::OneLfTerminatedLinePerWriteCall,
+        MessageKind::Flush,
+        trace_packetlines,
+    )?;
+    for (idx, command) in commands.iter().enumerate() {
+        let mut line = BString::from(format!("{} {} ", command.old, command.new));
+        line.push_str(&command.name);
+        if idx == 0 {
+            line.push_byte(0);
+            line.push_str(capabilities_line.trim_start());
+        }
+        request.write_all(&line)?;
+    }
+    request.write_message(MessageKind::Flush)?;
+    if !push_options.is_empty() {
+        for option in &push_options {
+            request.write_all(option)?;
+        }
+        request.write_message(MessageKind::Flush)?;
+    }
+
+    let (mut writer, mut reader) = request.into_parts();
+    if !commands.iter().all(Command::is_delete) {
+        write_pack(&mut writer, &mut progress, should_interrupt).map_err(|err| Error::WritePack(err.into()))?;
+    }
+    writer.flush()?;
+    drop(writer);
+
+    if report_status.is_none() {
+        return Ok(None);
+    }
+    let mut lines = Vec::new();
+    if sideband {
+        setup_remote_progress(&mut progress, &mut reader, should_interrupt);
+        let mut report = Vec::new();
+        reader.read_to_end(&mut report)?;
+        let mut report = StreamingPeekableIter::new(report.as_slice(), &[PacketLineRef::Flush], trace_packetlines);
+        while let Some(line) = report.read_line() {
+            lines.push(to_line(line?)?);
+        }
+    } else {
+        while let Some(line) = reader.readline() {
+            lines.push(to_line(line?)?);
+        }
+    }
+    Ok(Some(Report::from_lines(lines.iter().map(AsRef::<BStr>::as_ref))?))
+}
+
+fn to_line(line: Result<PacketLineRef<'_>, gix_transport::packetline::decode::Error>) -> std::io::Result<BString> {
+    Ok(line
+        .map_err(std::io::Error::other)?
+        .as_bstr()
+        .map(ToOwned::to_owned)
+        .unwrap_or_default())
+}
+
+fn setup_remote_progress<'a>(
+    progress: &mut dyn DynNestedProgress,
+    reader: &mut Box<dyn ExtendedBufRead<'a> + Unpin + 'a>,
+    should_interrupt: &'a AtomicBool,
+) {
+    reader.set_progress_handler(Some(Box::new({
+        let mut remote_progress = progress.add_child_with_id("remote".to_string(), ProgressId::RemoteProgress.into());
+        move |is_err: bool, data: &[u8]| {
+            crate::RemoteProgress::translate_to_progress(is_err, data, &mut remote_progress);
+            if should_interrupt.load(Ordering::Relaxed) {
+                std::ops::ControlFlow::Break(())
+            } else {
+                std::ops::ControlFlow::Continue(())
+            }
+        }
+    }) as HandleProgress<'a>));
+}
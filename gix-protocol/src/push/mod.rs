@@ -0,0 +1,100 @@
+//! A module providing the primitives to send a pack along with reference updates to a remote, the `send-pack` side of a push.
+//!
+//! ### Order for sending a pack
+//!
+//! * [handshake](crate::handshake()) with [`Service::ReceivePack`](crate::transport::Service::ReceivePack)
+//! * [send reference updates and the pack](crate::push())
+//!
+//! The remote doesn't accept further commands after a push, so the interaction is over thereafter.
+//!
+//! Note that determining which updates to send, and which objects to put into the pack, is left to the caller.
+//! The same is true for updating local remote-tracking references once the remote has accepted the updates.
+use bstr::{BStr, BString};
+use gix_hash::ObjectId;
+
+/// A single reference update to request from the remote.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Command {
+    /// The value the remote reference currently has, or the null id if it is expected not to exist.
+    ///
+    /// The remote refuses the update if the reference doesn't have this value, which is the basis for `--force-with-lease`.
+    pub old: ObjectId,
+    /// The value to set the remote reference to, or the null id to delete it.
+    pub new: ObjectId,
+    /// The full name of the reference on the remote, like `refs/heads/main`.
+    pub name: BString,
+}
+
+impl Command {
+    /// Return `true` if this command deletes the remote reference.
+    pub fn is_delete(&self) -> bool {
+        self.new.is_null()
+    }
+
+    /// Return `true` if this command creates the remote reference.
+    pub fn is_create(&self) -> bool {
+        self.old.is_null()
+    }
+
+    /// The name of the reference on the remote.
+    pub fn name(&self) -> &BStr {
+        self.name.as_ref()
+    }
+}
+
+/// Options for use in [`push()`](crate::push()).
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// If `true`, ask the remote to apply all reference updates or none of them.
+    ///
+    /// This fails if the remote doesn't support the `atomic` capability.
+    pub atomic: bool,
+    /// Strings to transmit to the remote for use by its hooks, which is what `git push --push-option` does.
+    ///
+    /// This fails if these are non-empty and the remote doesn't support the `push-options` capability.
+    pub push_options: Vec<BString>,
+}
+
+/// A utility type to configure [`push()`](crate::push()).
+#[cfg(feature = "blocking-client")]
+pub struct Context<'a, T> {
+    /// The outcome of the handshake performed with the remote using the `receive-pack` service.
+    pub handshake: &'a crate::Handshake,
+    /// The transport to send the reference updates and the pack through.
+    pub transport: &'a mut T,
+    /// How to self-identify during the push.
+    ///
+    /// This could be read from the `gitoxide.userAgent` configuration variable.
+    pub user_agent: (&'static str, Option<std::borrow::Cow<'static, str>>),
+    /// If `true`, output all packetlines using the `gix-trace` machinery.
+    pub trace_packetlines: bool,
+}
+
+///
+pub mod report;
+pub use report::Report;
+
+#[cfg(feature = "blocking-client")]
+mod error;
+#[cfg(feature = "blocking-client")]
+pub use error::Error;
+
+#[cfg(feature = "blocking-client")]
+pub(crate) mod function;
+
+/// The progress ids used in during various steps of the push operation.
+///
+/// Use this information to selectively extract the progress of interest in case the parent application has custom visualization.
+#[derive(Debug, Copy, Clone)]
+pub enum ProgressId {
+    /// The progress name is defined by the remote and the progress messages it sets while it receives the pack.
+    RemoteProgress,
+}
+
+impl From<ProgressId> for gix_features::progress::Id {
+    fn from(v: ProgressId) -> Self {
+        match v {
+            ProgressId::RemoteProgress => *b"PURP",
+        }
+    }
+}
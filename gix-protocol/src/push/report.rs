@@ -0,0 +1,141 @@
+use bstr::{BStr, BString, ByteSlice};
+use gix_hash::ObjectId;
+
+/// The status report sent by the remote after receiving reference updates and a pack, as negotiated with
+/// the `report-status` or `report-status-v2` capabilities.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    /// `None` if the pack was unpacked successfully, or the error message of the remote otherwise.
+    pub unpack_error: Option<BString>,
+    /// The status of each reference update, in the order the remote sent them.
+    pub refs: Vec<RefStatus>,
+}
+
+/// The status of a single reference update as reported by the remote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RefStatus {
+    /// The full name of the reference on the remote as it was sent in the command.
+    pub name: BString,
+    /// `None` if the update was performed, or the reason for rejecting it otherwise.
+    pub error: Option<BString>,
+    /// Additional information about the update, only sent with `report-status-v2` if the remote performed it
+    /// differently than it was requested, or if the update was forced.
+    ///
+    /// There may be multiple if a single command caused multiple updates, which is possible with `proc-receive` hooks.
+    pub updates: Vec<Update>,
+}
+
+impl RefStatus {
+    /// Return `true` if the remote accepted the update.
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Details about an update that was performed for a [reference status](RefStatus), as sent by means of `option` lines
+/// with `report-status-v2`.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Update {
+    /// The name of the reference that was actually updated, if it differs from the one in the command.
+    pub name: Option<BString>,
+    /// The value the reference had before the update, if it differs from the one in the command.
+    pub old: Option<ObjectId>,
+    /// The value the reference has after the update, if it differs from the one in the command.
+    pub new: Option<ObjectId>,
+    /// If `true`, the update wasn't a fast-forward.
+    pub forced: bool,
+}
+
+///
+pub mod decode {
+    use bstr::BString;
+
+    /// The error returned by [`Report::from_lines()`](super::Report::from_lines()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The status report was empty")]
+        Empty,
+        #[error("Expected the unpack status like 'unpack ok', got {line:?}")]
+        Unpack { line: BString },
+        #[error("Could not parse line {line:?} of the status report")]
+        Line { line: BString },
+        #[error("The option line {line:?} wasn't preceded by the status of a reference")]
+        OrphanedOption { line: BString },
+        #[error("Could not decode the object id in line {line:?}")]
+        Id {
+            line: BString,
+            source: gix_hash::decode::Error,
+        },
+    }
+}
+
+impl Report {
+    /// Parse a report from the packet-`lines` that the remote sent, excluding the trailing flush packet.
+    ///
+    /// Trailing newlines are ignored, and `option` lines as sent by `report-status-v2` are supported as well.
+    pub fn from_lines<'a>(lines: impl IntoIterator<Item = &'a BStr>) -> Result<Self, decode::Error> {
+        use decode::Error;
+        let mut lines = lines
+            .into_iter()
+            .map(|line| line.trim_end_with(|c| c == '\n').as_bstr());
+        let line = lines.next().ok_or(Error::Empty)?;
+        let unpack_error = match line.strip_prefix(b"unpack ") {
+            Some(b"ok") => None,
+            Some(message) => Some(message.into()),
+            None => return Err(Error::Unpack { line: line.into() }),
+        };
+
+        let mut refs = Vec::<RefStatus>::new();
+        for line in lines {
+            if let Some(name) = line.strip_prefix(b"ok ") {
+                refs.push(RefStatus {
+                    name: name.into(),
+                    error: None,
+                    updates: Vec::new(),
+                });
+            } else if let Some(rest) = line.strip_prefix(b"ng ") {
+                let (name, message) = rest.split_once_str(" ").unwrap_or((rest, b"failed"));
+                refs.push(RefStatus {
+                    name: name.into(),
+                    error: Some(message.into()),
+                    updates: Vec::new(),
+                });
+            } else if let Some(option) = line.strip_prefix(b"option ") {
+                let status = refs
+                    .last_mut()
+                    .ok_or_else(|| Error::OrphanedOption { line: line.into() })?;
+                let (key, value) = option.split_once_str(" ").unwrap_or((option, b""));
+                let starts_update = key == b"refname" || status.updates.is_empty();
+                if starts_update {
+                    status.updates.push(Update::default());
+                }
+                let update = status.updates.last_mut().expect("just made sure there is one");
+                let id = || {
+                    ObjectId::from_hex(value).map_err(|err| Error::Id {
+                        line: line.into(),
+                        source: err,
+                    })
+                };
+                match key {
+                    b"refname" => update.name = Some(value.into()),
+                    b"old-oid" => update.old = Some(id()?),
+                    b"new-oid" => update.new = Some(id()?),
+                    b"forced-update" => update.forced = true,
+                    _ => return Err(Error::Line { line: line.into() }),
+                }
+            } else {
+                return Err(Error::Line { line: line.into() });
+            }
+        }
+        Ok(Report { unpack_error, refs })
+    }
+
+    /// Return `true` if the pack was unpacked and all reference updates were performed.
+    pub fn is_ok(&self) -> bool {
+        self.unpack_error.is_none() && self.refs.iter().all(RefStatus::is_ok)
+    }
+}
@@ -0,0 +1,34 @@
+use bstr::BString;
+
+/// The error returned by [`serve()`](crate::receive_pack()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    DecodePacketline(#[from] gix_transport::packetline::decode::Error),
+    #[error("Could not open the packed references to advertise them")]
+    PackedRefsOpen(#[from] gix_ref::packed::buffer::open::Error),
+    #[error("Could not read a reference to advertise")]
+    IterRefs(#[from] gix_ref::file::iter::loose_then_packed::Error),
+    #[error("Expected a command like '<old-id> <new-id> <ref-name>', got {line:?}")]
+    ParseCommand { line: BString },
+    #[error("Could not decode the object id in line {line:?}")]
+    DecodeObjectId {
+        line: BString,
+        source: gix_hash::decode::Error,
+    },
+    #[error("The client uses {actual} object hashes, but the repository uses {expected}")]
+    ObjectFormat { expected: gix_hash::Kind, actual: BString },
+    #[error("Pushing from shallow repositories isn't supported")]
+    ShallowUnsupported,
+    #[error("The client didn't terminate its {section} with a flush packet")]
+    Unterminated { section: &'static str },
+    #[error("Could not create the quarantine object directory")]
+    Quarantine(#[source] std::io::Error),
+    #[error("Could not move received objects from the quarantine directory into the object database")]
+    Migrate(#[source] std::io::Error),
+    #[error("Operation interrupted")]
+    Interrupted,
+}
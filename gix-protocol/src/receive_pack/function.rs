@@ -0,0 +1,500 @@
+use std::{
+    cell::Cell,
+    io::{Read, Write},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_hash::ObjectId;
+use gix_object::FindExt;
+use gix_ref::{
+    FullName, Target,
+    file::ReferenceExt,
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+};
+use gix_transport::packetline::{
+    Channel, PacketLineRef,
+    blocking_io::{StreamingPeekableIter, encode},
+};
+
+use super::{Context, Error, Options, Outcome, quarantine::Quarantine};
+use crate::push::{Command, Report, report::RefStatus};
+
+/// The amount of bytes that fit into a single sideband packet line, which is the maximum data length minus the band byte.
+const MAX_BAND_DATA_LEN: usize = 65515;
+
+/// Serve a push with `receive-pack` using protocol version 0 or 1, reading the request from `read` and writing responses
+/// to `write`.
+///
+/// All references in `Context::refs` are advertised right away, along with the capabilities of the server.
+/// Thereafter, reference update commands and the pack are received, and the references are updated.
+/// If the client ends the interaction without sending commands, nothing is done.
+///
+/// `progress` and `should_interrupt` are passed to all potentially long-running parts of the operation.
+///
+/// ### Checks
+///
+/// The received pack is written into a quarantine directory within `Context::objects_dir`, which is deleted unless
+/// at least one reference is updated. Before that, all objects reachable from each new reference value that aren't
+/// reachable from any existing reference must be present, or the update is rejected with `missing necessary objects`.
+/// Updates are also rejected if the reference name is invalid, or if the reference doesn't have the value the client
+/// expects it to have.
+///
+/// With the `atomic` capability all updates are performed in a single transaction, so either all of them succeed or none.
+/// Otherwise, each update is performed on its own.
+///
+/// The result of each update is returned, and sent to the client if it asked for `report-status` or `report-status-v2`.
+/// Errors that prevent handling the request entirely, like protocol errors, are returned without informing the client.
+///
+/// ### Shortcomings
+///
+/// * Pushes from shallow repositories aren't supported.
+/// * Deltas in the received pack can refer to base objects in the same pack only by offset, as negotiated with `ofs-delta`.
+/// * There is no way to run hooks or to otherwise decide which updates to perform.
+pub fn serve<R, W, P>(
+    mut read: R,
+    mut write: W,
+    ctx: Context<'_>,
+    mut progress: P,
+    should_interrupt: &AtomicBool,
+    options: Options,
+) -> Result<Outcome, Error>
+where
+    R: Read,
+    W: Write,
+    P: gix_features::progress::NestedProgress,
+    P::SubProgress: 'static,
+{
+    let _span = gix_trace::coarse!("gix_protocol::receive_pack::serve()");
+    let advertised = advertise(&mut write, &ctx, &options)?;
+    let Some(request) = read_request(
+        &mut StreamingPeekableIter::new(&mut read, &[], options.trace_packetlines),
+        ctx.object_hash,
+    )?
+    else {
+        return Ok(Outcome {
+            commands: Vec::new(),
+            push_options: Vec::new(),
+            report: Report {
+                unpack_error: None,
+                refs: Vec::new(),
+            },
+        });
+    };
+
+    let mut errors: Vec<Option<BString>> = vec![None; request.commands.len()];
+    let mut unpack_error = None;
+    let mut received = None;
+    if request.commands.iter().any(|cmd| !cmd.is_delete()) {
+        let quarantine = Quarantine::new(ctx.objects_dir).map_err(Error::Quarantine)?;
+        let objects = quarantine.objects(ctx.object_hash).map_err(Error::Quarantine)?;
+        let pack = gix_pack::Bundle::write_to_directory(
+            &mut std::io::BufReader::new(&mut read),
+            Some(&quarantine.pack_dir()),
+            &mut progress,
+            should_interrupt,
+            Some(objects.clone()),
+            gix_pack::bundle::write::Options {
+                thread_limit: options.thread_limit,
+                iteration_mode: gix_pack::data::input::Mode::Verify,
+                index_version: Default::default(),
+                object_hash: ctx.object_hash,
+            },
+        );
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        match pack {
+            Ok(pack) => received = Some((quarantine, objects, pack)),
+            Err(err) => unpack_error = Some(BString::from(err.to_string())),
+        }
+    }
+
+    let mut names = Vec::with_capacity(request.commands.len());
+    let hidden = received
+        .as_ref()
+        .map(|(_, objects, _)| peel_to_commits(objects, &advertised, ctx.object_hash))
+        .unwrap_or_default();
+    for (cmd, error) in request.commands.iter().zip(&mut errors) {
+        let name = FullName::try_from(cmd.name.clone())
+            .ok()
+            .filter(|name| name.as_bstr().starts_with(b"refs/"));
+        *error = if unpack_error.is_some() {
+            Some("unpacker error".into())
+        } else if name.is_none() {
+            Some("funny refname".into())
+        } else if received.as_ref().is_some_and(|(_, objects, _)| {
+            !cmd.is_delete() && !is_connected(objects, cmd.new, &hidden, ctx.object_hash)
+        }) {
+            Some("missing necessary objects".into())
+        } else {
+            None
+        };
+        names.push(name);
+    }
+    if request.atomic && errors.iter().any(Option::is_some) {
+        for error in errors.iter_mut().filter(|error| error.is_none()) {
+            *error = Some("atomic push failure".into());
+        }
+    }
+
+    let mut keep_path = None;
+    if let Some((quarantine, _, pack)) = &received {
+        if let (Some(data_path), Some(index_path), true) =
+            (&pack.data_path, &pack.index_path, errors.iter().any(Option::is_none))
+        {
+            keep_path = quarantine
+                .migrate(ctx.objects_dir, data_path, index_path, pack.keep_path.as_deref())
+                .map_err(Error::Migrate)?;
+        }
+    }
+    drop(received);
+
+    let edits =
+        request
+            .commands
+            .iter()
+            .zip(names)
+            .zip(&errors)
+            .enumerate()
+            .filter_map(|(idx, ((cmd, name), error))| {
+                error
+                    .is_none()
+                    .then(|| (idx, edit(cmd, name.expect("valid names without error"))))
+            });
+    if request.atomic {
+        let (indices, edits): (Vec<_>, Vec<_>) = edits.unzip();
+        if let Err(err) = update_refs(ctx.refs, edits, ctx.committer) {
+            for idx in indices {
+                errors[idx] = Some(err.clone());
+            }
+        }
+    } else {
+        for (idx, edit) in edits.collect::<Vec<_>>() {
+            if let Err(err) = update_refs(ctx.refs, Some(edit), ctx.committer) {
+                errors[idx] = Some(err);
+            }
+        }
+    }
+    if let Some(keep_path) = keep_path {
+        std::fs::remove_file(keep_path)?;
+    }
+
+    let report = Report {
+        unpack_error,
+        refs: request
+            .commands
+            .iter()
+            .zip(errors)
+            .map(|(cmd, error)| RefStatus {
+                name: cmd.name.clone(),
+                error,
+                updates: Vec::new(),
+            })
+            .collect(),
+    };
+    if request.has_capability("report-status") || request.has_capability("report-status-v2") {
+        write_report(&report, request.has_capability("side-band-64k"), &mut write)?;
+    }
+    Ok(Outcome {
+        commands: request.commands,
+        push_options: request.push_options,
+        report,
+    })
+}
+
+/// Write all references and capabilities, and return the ids of all advertised references.
+fn advertise(out: &mut dyn Write, ctx: &Context<'_>, options: &Options) -> Result<Vec<ObjectId>, Error> {
+    let capabilities = format!(
+        "report-status report-status-v2 delete-refs side-band-64k quiet atomic ofs-delta push-options object-format={} agent={}",
+        ctx.object_hash, options.agent
+    );
+    let packed = ctx.refs.cached_packed_buffer()?;
+    let mut refs = Vec::new();
+    for reference in ctx.refs.iter()?.all()? {
+        let mut reference = reference?;
+        let name = reference.name.clone();
+        // Symbolic references are advertised with the value of the reference they point to, unless they are dangling.
+        if let Ok(id) = reference.follow_to_object_packed(ctx.refs, packed.as_deref().map(|packed| &**packed)) {
+            refs.push((name, id));
+        }
+    }
+
+    let mut lines: Vec<BString> = refs.iter().map(|(name, id)| format!("{id} {name}").into()).collect();
+    if lines.is_empty() {
+        lines.push(format!("{} capabilities^{{}}", ctx.object_hash.null()).into());
+    }
+    lines[0].push_byte(0);
+    lines[0].push_str(capabilities);
+    for line in &lines {
+        encode::text_to_write(line, &mut *out)?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    out.flush()?;
+    Ok(refs.into_iter().map(|(_, id)| id).collect())
+}
+
+/// A request to update references as sent by the client.
+struct Request {
+    commands: Vec<Command>,
+    capabilities: Vec<BString>,
+    push_options: Vec<BString>,
+    atomic: bool,
+}
+
+impl Request {
+    fn has_capability(&self, name: &str) -> bool {
+        self.capabilities.iter().any(|c| c == name)
+    }
+
+    fn capability(&self, name: &str) -> Option<&BStr> {
+        self.capabilities.iter().find_map(|c| {
+            c.strip_prefix(name.as_bytes())
+                .and_then(|rest| rest.strip_prefix(b"="))
+                .map(ByteSlice::as_bstr)
+        })
+    }
+}
+
+/// Read the commands and push-options of the client, or return `None` if it didn't send any commands.
+fn read_request(
+    lines: &mut StreamingPeekableIter<impl Read>,
+    object_hash: gix_hash::Kind,
+) -> Result<Option<Request>, Error> {
+    let mut request = Request {
+        commands: Vec::new(),
+        capabilities: Vec::new(),
+        push_options: Vec::new(),
+        atomic: false,
+    };
+    loop {
+        let line = match next_line(lines)? {
+            Some(PacketLineRef::Flush) => break,
+            None if request.commands.is_empty() => break,
+            Some(PacketLineRef::Data(line)) => line.trim_end_with(|c| c == '\n').as_bstr(),
+            None | Some(_) => return Err(Error::Unterminated { section: "commands" }),
+        };
+        let line = match line.split_once_str(b"\0") {
+            Some((line, capabilities)) => {
+                if request.commands.is_empty() {
+                    request.capabilities = capabilities
+                        .split_str(b" ")
+                        .filter(|c| !c.is_empty())
+                        .map(Into::into)
+                        .collect();
+                }
+                line.as_bstr()
+            }
+            None => line,
+        };
+        if line.starts_with(b"shallow ") {
+            return Err(Error::ShallowUnsupported);
+        }
+        request.commands.push(parse_command(line, object_hash)?);
+    }
+    if request.commands.is_empty() {
+        return Ok(None);
+    }
+    if let Some(format) = request.capability("object-format") {
+        if format != object_hash.to_string().as_bytes() {
+            return Err(Error::ObjectFormat {
+                expected: object_hash,
+                actual: format.to_owned(),
+            });
+        }
+    }
+    request.atomic = request.has_capability("atomic");
+
+    if request.has_capability("push-options") {
+        loop {
+            match next_line(lines)? {
+                Some(PacketLineRef::Flush) => break,
+                Some(PacketLineRef::Data(line)) => request.push_options.push(line.trim_end_with(|c| c == '\n').into()),
+                None | Some(_) => {
+                    return Err(Error::Unterminated {
+                        section: "push-options",
+                    });
+                }
+            }
+        }
+    }
+    Ok(Some(request))
+}
+
+/// Return the next line, or `None` on EOF.
+fn next_line(lines: &mut StreamingPeekableIter<impl Read>) -> Result<Option<PacketLineRef<'_>>, Error> {
+    match lines.read_line() {
+        None => Ok(None),
+        Some(Err(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+        Some(line) => Ok(Some(line??)),
+    }
+}
+
+fn parse_command(line: &BStr, object_hash: gix_hash::Kind) -> Result<Command, Error> {
+    let mut tokens = line.splitn_str(3, b" ");
+    let (Some(old), Some(new), Some(name)) = (tokens.next(), tokens.next(), tokens.next()) else {
+        return Err(Error::ParseCommand { line: line.to_owned() });
+    };
+    let id = |hex: &[u8]| -> Result<ObjectId, Error> {
+        let id = ObjectId::from_hex(hex).map_err(|source| Error::DecodeObjectId {
+            line: line.to_owned(),
+            source,
+        })?;
+        if id.kind() != object_hash {
+            return Err(Error::ObjectFormat {
+                expected: object_hash,
+                actual: id.kind().to_string().into(),
+            });
+        }
+        Ok(id)
+    };
+    Ok(Command {
+        old: id(old)?,
+        new: id(new)?,
+        name: name.into(),
+    })
+}
+
+/// Peel `ids` to the commits they point to, skipping all that don't point to commits.
+fn peel_to_commits(objects: &gix_odb::Handle, ids: &[ObjectId], object_hash: gix_hash::Kind) -> Vec<ObjectId> {
+    let mut buf = Vec::new();
+    ids.iter()
+        .filter_map(|id| peel_to_non_tag(objects, *id, object_hash, &mut buf))
+        .filter_map(|(id, kind)| (kind == gix_object::Kind::Commit).then_some(id))
+        .collect()
+}
+
+/// Follow the chain of tags starting at `id` and return the first object that isn't a tag, or `None` if an object is missing.
+fn peel_to_non_tag(
+    objects: &gix_odb::Handle,
+    mut id: ObjectId,
+    object_hash: gix_hash::Kind,
+    buf: &mut Vec<u8>,
+) -> Option<(ObjectId, gix_object::Kind)> {
+    loop {
+        let object = gix_object::Find::try_find(objects, &id, buf).ok()??;
+        match object.kind {
+            gix_object::Kind::Tag => {
+                id = gix_object::TagRefIter::from_bytes(object.data, object_hash)
+                    .target_id()
+                    .ok()?;
+            }
+            kind => return Some((id, kind)),
+        }
+    }
+}
+
+/// Return `true` if `new` and all objects reachable from it are present in `objects`, assuming that everything reachable
+/// from the `hidden` commits is present.
+fn is_connected(objects: &gix_odb::Handle, new: ObjectId, hidden: &[ObjectId], object_hash: gix_hash::Kind) -> bool {
+    let mut buf = Vec::new();
+    let Some((id, kind)) = peel_to_non_tag(objects, new, object_hash, &mut buf) else {
+        return false;
+    };
+    match kind {
+        gix_object::Kind::Commit => {}
+        gix_object::Kind::Tree => return objects.find_tree_iter(&id, &mut buf).is_ok(),
+        gix_object::Kind::Blob | gix_object::Kind::Tag => return true,
+    }
+
+    let missing = Cell::new(false);
+    let mut connectivity = gix_fsck::Connectivity::new(objects, |_: &ObjectId, _| missing.set(true));
+    let Ok(walk) = gix_traverse::commit::Simple::new(Some(id), objects).hide(hidden.iter().copied()) else {
+        return false;
+    };
+    for info in walk {
+        let Ok(info) = info else {
+            return false;
+        };
+        if connectivity.check_commit(&info.id).is_err() || missing.get() {
+            return false;
+        }
+    }
+    true
+}
+
+fn edit(cmd: &Command, name: FullName) -> RefEdit {
+    let previous = |otherwise| {
+        if cmd.is_create() {
+            otherwise
+        } else {
+            PreviousValue::MustExistAndMatch(Target::Object(cmd.old))
+        }
+    };
+    RefEdit {
+        change: if cmd.is_delete() {
+            Change::Delete {
+                expected: previous(PreviousValue::MustExist),
+                log: RefLog::AndReference,
+            }
+        } else {
+            Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: false,
+                    message: "push".into(),
+                },
+                expected: previous(PreviousValue::MustNotExist),
+                new: Target::Object(cmd.new),
+            }
+        },
+        name,
+        deref: true,
+    }
+}
+
+/// Apply `edits` in a single transaction, and return the error message to report on failure.
+fn update_refs(
+    refs: &gix_ref::file::Store,
+    edits: impl IntoIterator<Item = RefEdit>,
+    committer: Option<gix_actor::SignatureRef<'_>>,
+) -> Result<(), BString> {
+    // Use the same default timeouts as `git` for acquiring locks of loose references and the packed-refs file.
+    refs.transaction()
+        .prepare(
+            edits,
+            gix_lock::acquire::Fail::AfterDurationWithBackoff(Duration::from_millis(100)),
+            gix_lock::acquire::Fail::AfterDurationWithBackoff(Duration::from_secs(1)),
+        )
+        .map_err(|err| BString::from(err.to_string()))?
+        .commit(committer)
+        .map_err(|err| BString::from(err.to_string()))?;
+    Ok(())
+}
+
+fn write_report(report: &Report, sideband: bool, out: &mut dyn Write) -> std::io::Result<()> {
+    let mut buf = Vec::new();
+    let mut line = BString::from("unpack ");
+    line.push_str(
+        report
+            .unpack_error
+            .as_ref()
+            .map_or(b"ok".as_slice(), |err| err.as_slice()),
+    );
+    encode::text_to_write(&line, &mut buf)?;
+    for status in &report.refs {
+        line.clear();
+        match &status.error {
+            None => line.push_str(b"ok "),
+            Some(_) => line.push_str(b"ng "),
+        }
+        line.push_str(&status.name);
+        if let Some(error) = &status.error {
+            line.push_byte(b' ');
+            line.push_str(error);
+        }
+        encode::text_to_write(&line, &mut buf)?;
+    }
+    encode::flush_to_write(&mut buf)?;
+
+    if sideband {
+        for chunk in buf.chunks(MAX_BAND_DATA_LEN) {
+            encode::band_to_write(Channel::Data, chunk, &mut *out)?;
+        }
+        encode::flush_to_write(&mut *out)?;
+    } else {
+        out.write_all(&buf)?;
+    }
+    out.flush()
+}
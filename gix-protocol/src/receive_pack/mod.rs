@@ -0,0 +1,72 @@
+//! A module providing the server side of pushes, the `receive-pack` service, speaking protocol version 0 and 1.
+//!
+//! The server communicates through any `Read` and `Write` pair, which makes it usable with in-process transports
+//! as well as with sockets or the standard input and output of a process.
+//!
+//! ### Order of operations
+//!
+//! * [advertise references and capabilities](crate::receive_pack()) to the client
+//! * receive reference update commands, push-options and a pack
+//! * write the pack into a quarantine object directory and check that all objects needed by the updated
+//!   references are present
+//! * move the objects into the object database of the repository and update references
+//! * send a status report to the client
+//!
+//! Note that transport-specific preambles, like the request line sent to a `git daemon`, have to be handled by the caller.
+use std::path::Path;
+
+use bstr::BString;
+
+use crate::push::{Command, Report};
+
+/// The information needed to [serve](crate::receive_pack()) a push.
+pub struct Context<'a> {
+    /// The references to advertise and to update.
+    pub refs: &'a gix_ref::file::Store,
+    /// The `objects` directory of the repository, into which received objects are moved once they passed all checks.
+    pub objects_dir: &'a Path,
+    /// The kind of hash used by the repository, which is also the one that clients have to use.
+    pub object_hash: gix_hash::Kind,
+    /// The identity to use in reflog entries, which is required if the reference store writes reflogs.
+    pub committer: Option<gix_actor::SignatureRef<'a>>,
+}
+
+/// Options for use in [`serve()`](crate::receive_pack()).
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The name of the server to advertise as `agent` capability, like `git/gix-0.1`.
+    pub agent: String,
+    /// The amount of threads to use when indexing the received pack, or `None` to use all logical cores.
+    pub thread_limit: Option<usize>,
+    /// If `true`, output all packetlines using the `gix-trace` machinery.
+    pub trace_packetlines: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            agent: crate::agent(concat!("gix/", env!("CARGO_PKG_VERSION"))),
+            thread_limit: None,
+            trace_packetlines: false,
+        }
+    }
+}
+
+/// The outcome of a [push that was served](crate::receive_pack()).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The reference updates requested by the client, in the order they were received.
+    ///
+    /// It's empty if the client had nothing to push.
+    pub commands: Vec<Command>,
+    /// The push-options sent by the client for use by hooks, like with `git push --push-option`.
+    pub push_options: Vec<BString>,
+    /// The status of the push with one entry per command, as it was sent to the client if it asked for it.
+    pub report: Report,
+}
+
+mod error;
+pub use error::Error;
+
+pub(crate) mod function;
+mod quarantine;
@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+
+/// A temporary object directory inside of the object database of a repository to receive objects into.
+///
+/// It lists the object database it's contained in as alternate, so all objects of the repository are visible through it.
+/// Unless its objects are [migrated](Quarantine::migrate()), it's deleted along with everything in it when dropped.
+pub(super) struct Quarantine {
+    dir: PathBuf,
+}
+
+impl Quarantine {
+    /// Create a new quarantine directory within `objects_dir`, named `incoming-<suffix>` like `git` does.
+    pub fn new(objects_dir: &Path) -> std::io::Result<Self> {
+        let objects_dir = std::path::absolute(objects_dir)?;
+        let mut attempt = 0;
+        let dir = loop {
+            let dir = objects_dir.join(format!("incoming-{}-{attempt}", std::process::id()));
+            match std::fs::create_dir(&dir) {
+                Ok(()) => break dir,
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => attempt += 1,
+                Err(err) => return Err(err),
+            }
+        };
+        let quarantine = Quarantine { dir };
+        std::fs::create_dir_all(quarantine.dir.join("info"))?;
+        std::fs::create_dir(quarantine.pack_dir())?;
+        let mut alternate = gix_path::into_bstr(objects_dir).into_owned();
+        alternate.push(b'\n');
+        std::fs::write(quarantine.dir.join("info").join("alternates"), alternate)?;
+        Ok(quarantine)
+    }
+
+    /// The directory to write packs to.
+    pub fn pack_dir(&self) -> PathBuf {
+        self.dir.join("pack")
+    }
+
+    /// Open the object database of the quarantine, which includes all objects of the repository.
+    pub fn objects(&self, object_hash: gix_hash::Kind) -> std::io::Result<gix_odb::Handle> {
+        gix_odb::at_opts(
+            &self.dir,
+            Vec::new(),
+            gix_odb::store::init::Options {
+                object_hash,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Move the pack at `data_path` along with its `index_path` and `keep_path` into the pack directory of `objects_dir`,
+    /// and return the path to the moved `.keep` file which prevents the pack from being collected.
+    ///
+    /// The index is moved last to assure readers only see complete packs.
+    pub fn migrate(
+        &self,
+        objects_dir: &Path,
+        data_path: &Path,
+        index_path: &Path,
+        keep_path: Option<&Path>,
+    ) -> std::io::Result<Option<PathBuf>> {
+        let pack_dir = objects_dir.join("pack");
+        std::fs::create_dir_all(&pack_dir)?;
+        let destination = |path: &Path| pack_dir.join(path.file_name().expect("pack files have a name"));
+        if destination(data_path).is_file() {
+            // The repository already has this pack, and with it all of its objects.
+            return Ok(None);
+        }
+        let moved_keep_path = keep_path
+            .map(|path| std::fs::rename(path, destination(path)).map(|()| destination(path)))
+            .transpose()?;
+        std::fs::rename(data_path, destination(data_path))?;
+        std::fs::rename(index_path, destination(index_path))?;
+        Ok(moved_keep_path)
+    }
+}
+
+impl Drop for Quarantine {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
@@ -0,0 +1,56 @@
+use bstr::BString;
+use gix_hash::ObjectId;
+
+/// The error returned by [`serve()`](crate::upload_pack::serve()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    DecodePacketline(#[from] gix_transport::packetline::decode::Error),
+    #[error("Expected a command like 'command=fetch', got {line:?}")]
+    ExpectedCommand { line: BString },
+    #[error("Unknown command {command:?}")]
+    UnknownCommand { command: BString },
+    #[error("Unexpected argument {argument:?} for command {command:?}")]
+    UnknownArgument { command: &'static str, argument: BString },
+    #[error("The request for command {command:?} didn't end with a flush packet")]
+    UnterminatedRequest { command: BString },
+    #[error("The client uses {actual} object hashes, but the repository uses {expected}")]
+    ObjectFormat { expected: gix_hash::Kind, actual: BString },
+    #[error("Could not decode the object id in line {line:?}")]
+    DecodeObjectId {
+        line: BString,
+        source: gix_hash::decode::Error,
+    },
+    #[error("Could not parse the value of argument {line:?}")]
+    InvalidArgumentValue { line: BString },
+    #[error(transparent)]
+    Filter(#[from] crate::upload_pack::filter::Error),
+    #[error("The 'deepen-relative' argument isn't supported")]
+    DeepenRelativeUnsupported,
+    #[error("'deepen' can't be combined with 'deepen-since' or 'deepen-not'")]
+    DeepenConflict,
+    #[error("{name:?} passed with 'deepen-not' isn't a reference")]
+    DeepenNotUnknownRef { name: BString },
+    #[error("not our ref {id}")]
+    NotOurRef { id: ObjectId },
+    #[error(transparent)]
+    FindObject(#[from] gix_object::find::existing::Error),
+    #[error(transparent)]
+    FindCommit(#[from] gix_object::find::existing_iter::Error),
+    #[error(transparent)]
+    FindHeader(#[from] gix_object::find::Error),
+    #[error("Could not decode commit {id}")]
+    DecodeCommit {
+        id: ObjectId,
+        source: gix_object::decode::Error,
+    },
+    #[error(transparent)]
+    Walk(#[from] gix_traverse::commit::simple::Error),
+    #[error("Failed to create the pack to send")]
+    Pack(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("Operation interrupted")]
+    Interrupted,
+}
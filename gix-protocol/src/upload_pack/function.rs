@@ -0,0 +1,408 @@
+use std::{
+    io::{Read, Write},
+    sync::atomic::AtomicBool,
+};
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_hash::ObjectId;
+use gix_transport::packetline::{
+    PacketLineRef,
+    blocking_io::{StreamingPeekableIter, encode},
+};
+
+use super::{Arguments, Context, Error, Filter, Options, pack};
+use crate::handshake::Ref;
+
+/// Serve fetches with `upload-pack` using protocol version 2, reading requests from `read` and writing responses to `write`.
+///
+/// The capabilities are advertised right away, and thereafter requests are answered until the client ends the interaction
+/// with an empty request or by closing the connection.
+///
+/// `Context` provides the references to advertise, along with the object database to create packs from.
+/// `progress` and `should_interrupt` are passed to all potentially long-running parts of the operation, while the progress
+/// of pack creation is also sent to clients unless they opt out of it.
+///
+/// If a request can't be served, the reason is sent to the client as `ERR` packet line before returning the error.
+///
+/// ### Negotiation
+///
+/// The server acknowledges all `have` lines sent by the client that refer to objects it has, and signals that it is `ready`
+/// to send a pack as soon as one of them was found. Pack creation then excludes all objects reachable from these.
+///
+/// ### Shortcomings
+///
+/// * Packs are created by copying existing pack entries, and objects that aren't yet packed are sent as they are.
+///   Thus, `thin-pack` and `ofs-delta` don't have an effect.
+/// * `deepen-relative` and `tree:<depth>` filters other than `tree:0` aren't supported.
+pub fn serve<R, W, Find, P>(
+    read: R,
+    mut write: W,
+    ctx: Context<'_, Find>,
+    mut progress: P,
+    should_interrupt: &AtomicBool,
+    options: Options,
+) -> Result<(), Error>
+where
+    R: Read,
+    W: Write,
+    Find: gix_pack::Find + gix_object::Find + gix_object::FindHeader + Clone + Send + 'static,
+    P: gix_features::progress::NestedProgress,
+    P::SubProgress: 'static,
+{
+    let _span = gix_trace::coarse!("gix_protocol::upload_pack::serve()");
+    let mut lines = StreamingPeekableIter::new(read, &[], options.trace_packetlines);
+    let res = advertise(&mut write, ctx.object_hash, &options)
+        .map_err(Error::from)
+        .and_then(|()| {
+            while let Some(request) = read_request(&mut lines)? {
+                check_object_format(&request, ctx.object_hash)?;
+                match request.command.as_bytes() {
+                    b"ls-refs" => ls_refs(&request, ctx.refs, &mut write)?,
+                    b"fetch" => fetch(&request, &ctx, &mut write, &mut progress, should_interrupt, &options)?,
+                    b"bundle-uri" if options.bundle_list.is_some() => {
+                        bundle_uri(&request, options.bundle_list.as_ref().expect("checked"), &mut write)?;
+                    }
+                    _ => {
+                        return Err(Error::UnknownCommand {
+                            command: request.command,
+                        });
+                    }
+                }
+                write.flush()?;
+            }
+            Ok(())
+        });
+    if let Err(err) = &res {
+        if !matches!(err, Error::Io(_)) {
+            encode::error_to_write(format!("upload-pack: {err}").as_bytes(), &mut write).ok();
+            write.flush().ok();
+        }
+    }
+    res
+}
+
+fn advertise(out: &mut dyn Write, object_hash: gix_hash::Kind, options: &Options) -> std::io::Result<()> {
+    for line in [
+        "version 2".into(),
+        format!("agent={}", options.agent),
+        "ls-refs=unborn".into(),
+        "fetch=shallow filter".into(),
+        "server-option".into(),
+        format!("object-format={object_hash}"),
+    ]
+    .into_iter()
+    .chain(options.bundle_list.is_some().then(|| "bundle-uri".into()))
+    {
+        encode::text_to_write(line.as_bytes(), &mut *out)?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    out.flush()
+}
+
+/// A request made of a `command` along with its `capabilities` and `arguments`.
+struct Request {
+    command: BString,
+    capabilities: Vec<BString>,
+    arguments: Vec<BString>,
+}
+
+impl Request {
+    fn capability(&self, name: &str) -> Option<&BStr> {
+        self.capabilities.iter().find_map(|c| {
+            c.strip_prefix(name.as_bytes())
+                .and_then(|rest| rest.strip_prefix(b"="))
+                .map(ByteSlice::as_bstr)
+        })
+    }
+}
+
+/// Read the next request, or return `None` if the client ended the interaction.
+fn read_request(lines: &mut StreamingPeekableIter<impl Read>) -> Result<Option<Request>, Error> {
+    let command = match next_line(lines)? {
+        None | Some(PacketLineRef::Flush) => return Ok(None),
+        Some(PacketLineRef::Data(line)) => {
+            let line = line.trim_end().as_bstr();
+            match line.strip_prefix(b"command=") {
+                Some(command) => command.as_bstr().to_owned(),
+                None => return Err(Error::ExpectedCommand { line: line.to_owned() }),
+            }
+        }
+        Some(line) => {
+            return Err(Error::ExpectedCommand {
+                line: format!("{line:?}").into(),
+            });
+        }
+    };
+    let mut request = Request {
+        command,
+        capabilities: Vec::new(),
+        arguments: Vec::new(),
+    };
+    let mut in_arguments = false;
+    loop {
+        match next_line(lines)? {
+            Some(PacketLineRef::Data(line)) => {
+                let line = line.trim_end().as_bstr().to_owned();
+                if in_arguments {
+                    request.arguments.push(line);
+                } else {
+                    request.capabilities.push(line);
+                }
+            }
+            Some(PacketLineRef::Delimiter) if !in_arguments => in_arguments = true,
+            Some(PacketLineRef::Flush) => break,
+            Some(_) | None => {
+                return Err(Error::UnterminatedRequest {
+                    command: request.command,
+                });
+            }
+        }
+    }
+    Ok(Some(request))
+}
+
+/// Return the next line, or `None` on EOF.
+fn next_line(lines: &mut StreamingPeekableIter<impl Read>) -> Result<Option<PacketLineRef<'_>>, Error> {
+    match lines.read_line() {
+        None => Ok(None),
+        Some(Err(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+        Some(line) => Ok(Some(line??)),
+    }
+}
+
+fn check_object_format(request: &Request, object_hash: gix_hash::Kind) -> Result<(), Error> {
+    match request.capability("object-format") {
+        Some(format) if format != object_hash.to_string().as_bytes() => Err(Error::ObjectFormat {
+            expected: object_hash,
+            actual: format.to_owned(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn bundle_uri(request: &Request, list: &crate::bundle_uri::List, out: &mut dyn Write) -> Result<(), Error> {
+    if let Some(argument) = request.arguments.first() {
+        return Err(Error::UnknownArgument {
+            command: "bundle-uri",
+            argument: argument.clone(),
+        });
+    }
+    for line in list.to_lines() {
+        encode::text_to_write(&line, &mut *out)?;
+    }
+    encode::flush_to_write(out)?;
+    Ok(())
+}
+
+fn ls_refs(request: &Request, refs: &[Ref], out: &mut dyn Write) -> Result<(), Error> {
+    let (mut symrefs, mut peel, mut unborn) = (false, false, false);
+    let mut prefixes = Vec::new();
+    for argument in &request.arguments {
+        match argument.as_bytes() {
+            b"symrefs" => symrefs = true,
+            b"peel" => peel = true,
+            b"unborn" => unborn = true,
+            _ => match argument.strip_prefix(b"ref-prefix ") {
+                Some(prefix) => prefixes.push(prefix.as_bstr()),
+                None => {
+                    return Err(Error::UnknownArgument {
+                        command: "ls-refs",
+                        argument: argument.clone(),
+                    });
+                }
+            },
+        }
+    }
+
+    let mut line = BString::default();
+    for r in refs {
+        let (name, _, _) = r.unpack();
+        if !prefixes.is_empty() && !prefixes.iter().any(|prefix| name.starts_with(prefix)) {
+            continue;
+        }
+        line.clear();
+        let (target, peeled) = match r {
+            Ref::Direct { full_ref_name, object } => {
+                line.push_str(format!("{object} {full_ref_name}"));
+                (None, None)
+            }
+            Ref::Peeled {
+                full_ref_name,
+                tag,
+                object,
+            } => {
+                line.push_str(format!("{tag} {full_ref_name}"));
+                (None, Some(object))
+            }
+            Ref::Symbolic {
+                full_ref_name,
+                target,
+                tag,
+                object,
+            } => {
+                line.push_str(format!("{} {full_ref_name}", tag.as_ref().unwrap_or(object)));
+                (Some(target), tag.as_ref().map(|_| object))
+            }
+            Ref::Unborn { full_ref_name, target } => {
+                if !unborn {
+                    continue;
+                }
+                line.push_str(format!("unborn {full_ref_name}"));
+                (Some(target), None)
+            }
+        };
+        if let Some(target) = target.filter(|_| symrefs) {
+            line.push_str(" symref-target:");
+            line.push_str(target);
+        }
+        if let Some(peeled) = peeled.filter(|_| peel) {
+            line.push_str(format!(" peeled:{peeled}"));
+        }
+        encode::text_to_write(&line, &mut *out)?;
+    }
+    encode::flush_to_write(out)?;
+    Ok(())
+}
+
+fn fetch<Find>(
+    request: &Request,
+    ctx: &Context<'_, Find>,
+    out: &mut dyn Write,
+    progress: &mut dyn gix_features::progress::DynNestedProgress,
+    should_interrupt: &AtomicBool,
+    options: &Options,
+) -> Result<(), Error>
+where
+    Find: gix_pack::Find + gix_object::Find + gix_object::FindHeader + Clone + Send + 'static,
+{
+    let args = parse_fetch_arguments(request, ctx.object_hash)?;
+    if !options.allow_unadvertised_wants {
+        let advertised = |id: &ObjectId| {
+            ctx.refs.iter().any(|r| {
+                let (_, target, peeled) = r.unpack();
+                target == Some(id.as_ref()) || peeled == Some(id.as_ref())
+            })
+        };
+        if let Some(id) = args.wants.iter().find(|id| !advertised(id)) {
+            return Err(Error::NotOurRef { id: *id });
+        }
+    }
+    if let Some(id) = args.wants.iter().find(|id| !gix_pack::Find::contains(&ctx.objects, id)) {
+        return Err(Error::NotOurRef { id: *id });
+    }
+
+    let common: Vec<_> = args
+        .haves
+        .iter()
+        .filter(|id| gix_pack::Find::contains(&ctx.objects, id))
+        .copied()
+        .collect();
+    if !args.done {
+        encode::text_to_write(b"acknowledgments", &mut *out)?;
+        if common.is_empty() {
+            encode::text_to_write(b"NAK", &mut *out)?;
+        }
+        for id in &common {
+            encode::text_to_write(format!("ACK {id}").as_bytes(), &mut *out)?;
+        }
+        if common.is_empty() {
+            encode::flush_to_write(out)?;
+            return Ok(());
+        }
+        encode::text_to_write(b"ready", &mut *out)?;
+        encode::delim_to_write(&mut *out)?;
+    }
+
+    let selection = pack::select(ctx, &args, &common)?;
+    if args.is_deepen() || !args.shallow.is_empty() {
+        encode::text_to_write(b"shallow-info", &mut *out)?;
+        for id in &selection.shallow {
+            encode::text_to_write(format!("shallow {id}").as_bytes(), &mut *out)?;
+        }
+        for id in &selection.unshallow {
+            encode::text_to_write(format!("unshallow {id}").as_bytes(), &mut *out)?;
+        }
+        encode::delim_to_write(&mut *out)?;
+    }
+    encode::text_to_write(b"packfile", &mut *out)?;
+    pack::write(
+        ctx,
+        selection,
+        args.filter,
+        &mut *out,
+        !args.no_progress,
+        progress,
+        should_interrupt,
+        options.thread_limit,
+    )?;
+    encode::flush_to_write(out)?;
+    Ok(())
+}
+
+fn parse_fetch_arguments(request: &Request, object_hash: gix_hash::Kind) -> Result<Arguments, Error> {
+    let mut args = Arguments::default();
+    for line in &request.arguments {
+        let (name, value) = match line.find_byte(b' ') {
+            Some(pos) => (&line[..pos], Some(line[pos + 1..].as_bstr())),
+            None => (line.as_slice(), None),
+        };
+        let id = || -> Result<ObjectId, Error> {
+            let value = value.ok_or_else(|| Error::InvalidArgumentValue { line: line.clone() })?;
+            let id = ObjectId::from_hex(value).map_err(|source| Error::DecodeObjectId {
+                line: line.clone(),
+                source,
+            })?;
+            if id.kind() != object_hash {
+                return Err(Error::ObjectFormat {
+                    expected: object_hash,
+                    actual: id.kind().to_string().into(),
+                });
+            }
+            Ok(id)
+        };
+        let number = || -> Result<u64, Error> {
+            value
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| Error::InvalidArgumentValue { line: line.clone() })
+        };
+        match (name, value) {
+            (b"want", _) => args.wants.push(id()?),
+            (b"have", _) => args.haves.push(id()?),
+            (b"shallow", _) => args.shallow.push(id()?),
+            (b"done", None) => args.done = true,
+            (b"no-progress", None) => args.no_progress = true,
+            (b"include-tag", None) => args.include_tag = true,
+            (b"thin-pack" | b"ofs-delta", None) => {}
+            (b"deepen", _) => {
+                args.deepen = Some(
+                    number()?
+                        .try_into()
+                        .ok()
+                        .filter(|depth| *depth > 0)
+                        .ok_or_else(|| Error::InvalidArgumentValue { line: line.clone() })?,
+                );
+            }
+            (b"deepen-since", _) => {
+                args.deepen_since = Some(
+                    number()?
+                        .try_into()
+                        .map_err(|_| Error::InvalidArgumentValue { line: line.clone() })?,
+                );
+            }
+            (b"deepen-not", Some(name)) => args.deepen_not.push(name.to_owned()),
+            (b"deepen-relative", None) => return Err(Error::DeepenRelativeUnsupported),
+            (b"filter", Some(spec)) => args.filter = Some(Filter::from_bytes(spec)?),
+            _ => {
+                return Err(Error::UnknownArgument {
+                    command: "fetch",
+                    argument: line.clone(),
+                });
+            }
+        }
+    }
+    if args.deepen.is_some() && (args.deepen_since.is_some() || !args.deepen_not.is_empty()) {
+        return Err(Error::DeepenConflict);
+    }
+    Ok(args)
+}
@@ -0,0 +1,148 @@
+//! A module providing the server side of fetches, the `upload-pack` service, speaking protocol version 2.
+//!
+//! The server communicates through any `Read` and `Write` pair, which makes it usable with in-process transports
+//! as well as with sockets or the standard input and output of a process.
+//!
+//! ### Order of operations
+//!
+//! * [advertise capabilities](crate::upload_pack::serve()) to the client
+//! * answer any amount of requests, each being one of
+//!     - `ls-refs` to list the references provided by the caller
+//!     - `fetch` to negotiate common commits and send a pack with the objects the client needs
+//!     - `bundle-uri` to list bundles to download from elsewhere, if [`Options::bundle_list`] is set
+//!
+//! The interaction ends once the client sends an empty request, or closes the connection.
+//!
+//! Note that transport-specific preambles, like the request line sent to a `git daemon`, have to be handled by the caller.
+use gix_hash::ObjectId;
+
+use crate::handshake::Ref;
+
+/// The information needed to [serve](crate::upload_pack::serve()) requests.
+pub struct Context<'a, Find> {
+    /// The references to advertise and to serve fetches for.
+    ///
+    /// Only objects these point to, directly or after peeling, can be fetched unless
+    /// [`Options::allow_unadvertised_wants`] is set.
+    pub refs: &'a [Ref],
+    /// The object database to obtain all objects to send from.
+    ///
+    /// Note that handles of `gix-odb` have to be configured with `prevent_pack_unload()` to be usable for creating packs.
+    pub objects: Find,
+    /// The kind of hash used by `objects`, which is also the one that clients have to use.
+    pub object_hash: gix_hash::Kind,
+}
+
+/// Options for use in [`serve()`](crate::upload_pack::serve()).
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The name of the server to advertise as `agent` capability, like `git/gix-0.1`.
+    pub agent: String,
+    /// The amount of threads to use when creating packs, or `None` to use all logical cores.
+    pub thread_limit: Option<usize>,
+    /// If `true`, clients may ask for objects that none of the references point to.
+    ///
+    /// Note that it's not checked if these are reachable from any of the references.
+    pub allow_unadvertised_wants: bool,
+    /// If `true`, output all packetlines using the `gix-trace` machinery.
+    pub trace_packetlines: bool,
+    /// If set, the `bundle-uri` command is advertised and answered with this list of bundles.
+    pub bundle_list: Option<crate::bundle_uri::List>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            agent: crate::agent(concat!("gix/", env!("CARGO_PKG_VERSION"))),
+            thread_limit: None,
+            allow_unadvertised_wants: false,
+            trace_packetlines: false,
+            bundle_list: None,
+        }
+    }
+}
+
+/// A specification of the objects to omit from a pack, as requested by the client with the `filter` argument of a `fetch`.
+///
+/// Objects that are wanted explicitly are never omitted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// `blob:none`, omit all blobs.
+    BlobNone,
+    /// `blob:limit=<n>`, omit all blobs whose size is at least the given amount of bytes.
+    BlobLimit(u64),
+    /// `tree:0`, omit all trees and blobs.
+    TreeNone,
+}
+
+///
+pub mod filter {
+    use bstr::{BStr, BString, ByteSlice};
+
+    use super::Filter;
+
+    /// The error returned by [`Filter::from_bytes()`].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The filter specification {spec:?} isn't supported")]
+        Unsupported { spec: BString },
+        #[error("The size limit in filter specification {spec:?} is invalid")]
+        InvalidLimit { spec: BString },
+    }
+
+    impl Filter {
+        /// Parse a filter `spec` like `blob:none` or `blob:limit=1m`.
+        pub fn from_bytes(spec: &BStr) -> Result<Self, Error> {
+            Ok(match spec.as_bytes() {
+                b"blob:none" => Filter::BlobNone,
+                b"tree:0" => Filter::TreeNone,
+                _ => match spec.strip_prefix(b"blob:limit=") {
+                    Some(limit) => Filter::BlobLimit(
+                        parse_size(limit).ok_or_else(|| Error::InvalidLimit { spec: spec.to_owned() })?,
+                    ),
+                    None => return Err(Error::Unsupported { spec: spec.to_owned() }),
+                },
+            })
+        }
+    }
+
+    /// Parse a size with an optional `k`, `m` or `g` unit, like `git` does.
+    fn parse_size(size: &[u8]) -> Option<u64> {
+        let (digits, factor) = match size.last()?.to_ascii_lowercase() {
+            b'k' => (&size[..size.len() - 1], 1024),
+            b'm' => (&size[..size.len() - 1], 1024 * 1024),
+            b'g' => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+            _ => (size, 1),
+        };
+        digits.to_str().ok()?.parse::<u64>().ok()?.checked_mul(factor)
+    }
+}
+
+/// The arguments of a `fetch` request as sent by the client.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Arguments {
+    pub wants: Vec<ObjectId>,
+    pub haves: Vec<ObjectId>,
+    pub done: bool,
+    pub no_progress: bool,
+    pub include_tag: bool,
+    pub shallow: Vec<ObjectId>,
+    pub deepen: Option<u32>,
+    pub deepen_since: Option<gix_date::SecondsSinceUnixEpoch>,
+    pub deepen_not: Vec<bstr::BString>,
+    pub filter: Option<Filter>,
+}
+
+impl Arguments {
+    /// Return `true` if the client wants to change the depth of its history.
+    pub fn is_deepen(&self) -> bool {
+        self.deepen.is_some() || self.deepen_since.is_some() || !self.deepen_not.is_empty()
+    }
+}
+
+mod error;
+pub use error::Error;
+
+pub(crate) mod function;
+mod pack;
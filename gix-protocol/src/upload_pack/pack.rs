@@ -0,0 +1,437 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io::Write,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use gix_features::progress::{Count, DynNestedProgress, Progress};
+use gix_hash::ObjectId;
+use gix_object::FindExt;
+use gix_pack::data::output;
+use gix_transport::packetline::{Channel, blocking_io::encode};
+
+use super::{Arguments, Context, Error, Filter};
+use crate::handshake::Ref;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// The amount of bytes that fit into a single sideband packet line, which is the maximum data length minus the band byte.
+const MAX_BAND_DATA_LEN: usize = 65515;
+
+/// The objects to send to a client, along with the changes to its shallow boundary.
+pub(crate) struct Selection {
+    /// Commits to send whose parents the client has or will receive, so only their changes need to be sent.
+    commits: Vec<ObjectId>,
+    /// Commits to send whose parents the client won't have, so their trees are sent in full.
+    boundary: Vec<ObjectId>,
+    /// Trees and blobs that were wanted directly, to be sent in full.
+    trees_and_blobs: Vec<ObjectId>,
+    /// Tags to send, without their targets.
+    tags: Vec<ObjectId>,
+    /// Commits the client has and which are parents of the commits to send, along with their trees.
+    edges: HashSet<ObjectId>,
+    /// Commits the client has, whose trees are excluded from trees sent in full.
+    common: Vec<ObjectId>,
+    /// The objects the client asked for explicitly, which are never filtered.
+    wanted: HashSet<ObjectId>,
+    /// Commits that are new shallow boundaries for the client.
+    pub shallow: Vec<ObjectId>,
+    /// Commits that the client had as shallow boundary, but which now get their parents.
+    pub unshallow: Vec<ObjectId>,
+}
+
+/// Determine the objects to send to satisfy `args` for a client that has `common` objects.
+pub(crate) fn select<Find>(ctx: &Context<'_, Find>, args: &Arguments, common: &[ObjectId]) -> Result<Selection, Error>
+where
+    Find: gix_object::Find + gix_object::FindHeader,
+{
+    let objects = &ctx.objects;
+    let mut buf = Vec::new();
+    let mut selection = Selection {
+        commits: Vec::new(),
+        boundary: Vec::new(),
+        trees_and_blobs: Vec::new(),
+        tags: Vec::new(),
+        edges: HashSet::new(),
+        common: Vec::new(),
+        wanted: args.wants.iter().copied().collect(),
+        shallow: Vec::new(),
+        unshallow: Vec::new(),
+    };
+
+    let mut want_commits = Vec::new();
+    for want in &args.wants {
+        let mut id = *want;
+        loop {
+            let object = objects.find(&id, &mut buf)?;
+            match object.kind {
+                gix_object::Kind::Tag => {
+                    selection.tags.push(id);
+                    id = gix_object::TagRefIter::from_bytes(object.data, ctx.object_hash)
+                        .target_id()
+                        .map_err(|source| Error::DecodeCommit { id, source })?;
+                }
+                gix_object::Kind::Commit => {
+                    want_commits.push(id);
+                    break;
+                }
+                gix_object::Kind::Tree | gix_object::Kind::Blob => {
+                    selection.trees_and_blobs.push(id);
+                    break;
+                }
+            }
+        }
+    }
+    for id in common {
+        if objects
+            .try_header(id)?
+            .is_some_and(|h| h.kind == gix_object::Kind::Commit)
+        {
+            selection.common.push(*id);
+        }
+    }
+
+    let mut parents_of_sent = Vec::new();
+    if args.is_deepen() {
+        let client_shallow: HashSet<_> = args.shallow.iter().copied().collect();
+        let deepen = Deepen::new(ctx, args)?;
+        let (kept, boundary) = deepen.traverse(objects, &want_commits, &mut buf)?;
+
+        // Find the commits the client has by walking from the common ones, but only within the history to send.
+        // The client doesn't have the parents of its shallow commits.
+        let mut hidden = HashSet::new();
+        let mut queue: VecDeque<_> = selection.common.iter().filter(|id| kept.contains(id)).collect();
+        while let Some(id) = queue.pop_front() {
+            if !hidden.insert(*id) || client_shallow.contains(id) || boundary.contains(id) {
+                continue;
+            }
+            queue.extend(kept.parents(id).iter().filter(|id| kept.contains(id)));
+        }
+
+        for (id, parents) in &kept.commits {
+            if hidden.contains(id) {
+                continue;
+            }
+            if boundary.contains(id) {
+                selection.boundary.push(*id);
+            } else {
+                selection.commits.push(*id);
+                parents_of_sent.extend(parents.iter().copied());
+            }
+        }
+        selection.shallow = boundary
+            .iter()
+            .filter(|id| !client_shallow.contains(*id))
+            .copied()
+            .collect();
+        selection.unshallow = args
+            .shallow
+            .iter()
+            .filter(|id| kept.contains(id) && !boundary.contains(*id))
+            .copied()
+            .collect();
+        selection.shallow.sort();
+    } else if !want_commits.is_empty() {
+        let walk = gix_traverse::commit::Simple::new(want_commits, objects).hide(selection.common.iter().copied())?;
+        for info in walk {
+            let info = info?;
+            parents_of_sent.extend(info.parent_ids.iter().copied());
+            selection.commits.push(info.id);
+        }
+    }
+
+    let sent: HashSet<_> = selection.commits.iter().chain(&selection.boundary).copied().collect();
+    for parent in parents_of_sent {
+        if sent.contains(&parent) || selection.edges.contains(&parent) {
+            continue;
+        }
+        let tree = objects
+            .find_commit_iter(&parent, &mut buf)?
+            .tree_id()
+            .map_err(|source| Error::DecodeCommit { id: parent, source })?;
+        selection.edges.insert(parent);
+        selection.edges.insert(tree);
+    }
+
+    if args.include_tag {
+        for r in ctx.refs {
+            if let Ref::Peeled { tag, object, .. }
+            | Ref::Symbolic {
+                tag: Some(tag), object, ..
+            } = r
+            {
+                if (sent.contains(object) || selection.trees_and_blobs.contains(object))
+                    && !selection.tags.contains(tag)
+                {
+                    selection.tags.push(*tag);
+                }
+            }
+        }
+    }
+    Ok(selection)
+}
+
+/// Write a pack with the objects of `selection` to `out` on the data sideband, omitting objects as specified by `filter`.
+///
+/// If `send_progress` is `true`, information about the pack is written to the progress sideband as well.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn write<Find>(
+    ctx: &Context<'_, Find>,
+    selection: Selection,
+    filter: Option<Filter>,
+    out: &mut dyn Write,
+    send_progress: bool,
+    progress: &mut dyn DynNestedProgress,
+    should_interrupt: &AtomicBool,
+    thread_limit: Option<usize>,
+) -> Result<(), Error>
+where
+    Find: gix_pack::Find + gix_object::FindHeader + Clone + Send + 'static,
+{
+    use output::count::objects::ObjectExpansion::{AsIs, TreeAdditionsComparedToAncestor, TreeContents};
+    let objects = &ctx.objects;
+    let (commit_expansion, full_expansion) = if filter == Some(Filter::TreeNone) {
+        (AsIs, AsIs)
+    } else {
+        (TreeAdditionsComparedToAncestor, TreeContents)
+    };
+
+    let mut counting = progress.add_child("counting".into());
+    counting.init(None, gix_features::progress::count("objects"));
+    let count = |ids: &[ObjectId], expansion| -> Result<Vec<output::Count>, Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(output::count::objects_unthreaded(
+            objects,
+            &mut ids.iter().copied().map(Ok),
+            &counting,
+            should_interrupt,
+            expansion,
+        )
+        .map_err(|err| Error::Pack(err.into()))?
+        .0)
+    };
+
+    let excluded: HashSet<_> = if full_expansion == TreeContents
+        && !selection.common.is_empty()
+        && !(selection.boundary.is_empty() && selection.trees_and_blobs.is_empty())
+    {
+        count(&selection.common, TreeContents)?
+            .into_iter()
+            .map(|count| count.id)
+            .collect()
+    } else {
+        HashSet::new()
+    };
+    let mut counts = count(&selection.commits, commit_expansion)?;
+    counts.retain(|count| !selection.edges.contains(&count.id));
+    counts.extend(
+        count(&selection.boundary, full_expansion)?
+            .into_iter()
+            .chain(count(&selection.trees_and_blobs, full_expansion)?)
+            .filter(|count| !excluded.contains(&count.id)),
+    );
+    counts.extend(count(&selection.tags, AsIs)?);
+
+    let mut seen = HashSet::new();
+    counts.retain(|count| seen.insert(count.id));
+    if let Some(filter @ (Filter::BlobNone | Filter::BlobLimit(_))) = filter {
+        let mut filtered = Vec::with_capacity(counts.len());
+        for count in counts {
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            if !selection.wanted.contains(&count.id) {
+                if let Some(header) = objects.try_header(&count.id)? {
+                    let omit = header.kind == gix_object::Kind::Blob
+                        && match filter {
+                            Filter::BlobLimit(limit) => header.size >= limit,
+                            _ => true,
+                        };
+                    if omit {
+                        continue;
+                    }
+                }
+            }
+            filtered.push(count);
+        }
+        counts = filtered;
+    }
+    drop(counting);
+
+    let mut out = std::io::BufWriter::with_capacity(MAX_BAND_DATA_LEN, BandWriter { out });
+    let num_objects = counts.len();
+    if send_progress {
+        out.get_mut()
+            .progress(format!("Enumerating objects: {num_objects}, done.\n").as_bytes())?;
+    }
+    let entries = gix_features::parallel::InOrderIter::from(output::entry::iter_from_counts(
+        counts,
+        objects.clone(),
+        Box::new(progress.add_child("creating entries".into())),
+        output::entry::iter_from_counts::Options {
+            thread_limit,
+            mode: output::entry::iter_from_counts::Mode::PackCopyAndBaseObjects,
+            allow_thin_pack: false,
+            chunk_size: 1000,
+            version: Default::default(),
+        },
+    ));
+    let mut write_progress = progress.add_child("writing".into());
+    write_progress.init(None, gix_features::progress::bytes());
+    for written in output::bytes::FromEntriesIter::new(
+        entries,
+        &mut out,
+        num_objects as u32,
+        gix_pack::data::Version::V2,
+        ctx.object_hash,
+    ) {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        write_progress.inc_by(written.map_err(|err| Error::Pack(Box::new(err) as BoxError))? as usize);
+    }
+    out.flush()?;
+    if send_progress {
+        out.get_mut()
+            .progress(format!("Total {num_objects} (delta 0), reused {num_objects} (delta 0)\n").as_bytes())?;
+    }
+    Ok(())
+}
+
+/// A writer to send all data on the data sideband, splitting it into packet lines as needed.
+struct BandWriter<'a> {
+    out: &'a mut dyn Write,
+}
+
+impl BandWriter<'_> {
+    fn progress(&mut self, message: &[u8]) -> std::io::Result<()> {
+        encode::band_to_write(Channel::Progress, message, &mut *self.out).map(|_| ())
+    }
+}
+
+impl Write for BandWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for chunk in buf.chunks(MAX_BAND_DATA_LEN) {
+            encode::band_to_write(Channel::Data, chunk, &mut *self.out)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// The ways the client asks to limit the history it receives.
+struct Deepen {
+    depth: Option<u32>,
+    since: Option<gix_date::SecondsSinceUnixEpoch>,
+    /// Commits reachable from the references passed with `deepen-not`.
+    excluded: HashSet<ObjectId>,
+}
+
+impl Deepen {
+    fn new<Find>(ctx: &Context<'_, Find>, args: &Arguments) -> Result<Self, Error>
+    where
+        Find: gix_object::Find,
+    {
+        let mut tips = Vec::new();
+        for name in &args.deepen_not {
+            let target = ctx
+                .refs
+                .iter()
+                .filter_map(|r| {
+                    let (full_name, target, peeled) = r.unpack();
+                    let matches = full_name == name
+                        || ["refs/heads/", "refs/tags/"]
+                            .iter()
+                            .any(|prefix| full_name.strip_prefix(prefix.as_bytes()) == Some(name.as_slice()));
+                    if !matches {
+                        return None;
+                    }
+                    peeled.or(target).map(ToOwned::to_owned)
+                })
+                .next()
+                .ok_or_else(|| Error::DeepenNotUnknownRef { name: name.clone() })?;
+            tips.push(target);
+        }
+        let mut excluded = HashSet::new();
+        if !tips.is_empty() {
+            for info in gix_traverse::commit::Simple::new(tips, &ctx.objects) {
+                excluded.insert(info?.id);
+            }
+        }
+        Ok(Deepen {
+            depth: args.deepen,
+            since: args.deepen_since,
+            excluded,
+        })
+    }
+
+    /// Traverse the history of `tips` as far as the client wants it, and return it along with the commits whose parents
+    /// aren't part of it.
+    fn traverse(
+        &self,
+        objects: &impl gix_object::Find,
+        tips: &[ObjectId],
+        buf: &mut Vec<u8>,
+    ) -> Result<(History, HashSet<ObjectId>), Error> {
+        let mut kept = History::default();
+        let mut boundary = HashSet::new();
+        let mut queue: VecDeque<_> = tips.iter().map(|id| (*id, 1)).collect();
+        let mut seen: HashSet<_> = tips.iter().copied().collect();
+        while let Some((id, depth)) = queue.pop_front() {
+            let commit = objects.find_commit_iter(&id, buf)?;
+            let parents: Vec<_> = commit.parent_ids().collect();
+            let is_boundary = match self.depth {
+                Some(max_depth) => depth >= max_depth,
+                None => {
+                    let mut is_boundary = false;
+                    for parent in &parents {
+                        let commit = objects.find_commit_iter(parent, buf)?;
+                        let time = commit
+                            .committer()
+                            .map_err(|source| Error::DecodeCommit { id: *parent, source })?
+                            .seconds();
+                        if self.since.is_some_and(|since| time < since) || self.excluded.contains(parent) {
+                            is_boundary = true;
+                            break;
+                        }
+                    }
+                    is_boundary
+                }
+            };
+            if is_boundary && !parents.is_empty() {
+                boundary.insert(id);
+            } else {
+                for parent in &parents {
+                    if seen.insert(*parent) {
+                        queue.push_back((*parent, depth + 1));
+                    }
+                }
+            }
+            kept.index.insert(id, kept.commits.len());
+            kept.commits.push((id, parents));
+        }
+        Ok((kept, boundary))
+    }
+}
+
+/// Commits along with their parents, in traversal order.
+#[derive(Default)]
+struct History {
+    commits: Vec<(ObjectId, Vec<ObjectId>)>,
+    index: HashMap<ObjectId, usize>,
+}
+
+impl History {
+    fn contains(&self, id: &ObjectId) -> bool {
+        self.index.contains_key(id)
+    }
+
+    fn parents(&self, id: &ObjectId) -> &[ObjectId] {
+        self.index.get(id).map_or(&[], |idx| &self.commits[*idx].1)
+    }
+}
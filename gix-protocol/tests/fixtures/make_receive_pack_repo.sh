@@ -0,0 +1,30 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q base
+(cd base
+  git checkout -q -b main
+  echo a > a
+  git add a && git commit -q -m c1
+  git tag -a -m "annotated" v1
+  git branch dev
+
+  echo b > b
+  git add b && git commit -q -m c2
+)
+
+git clone -q --bare base remote.git
+git clone -q base client
+(cd client
+  echo c > c
+  git add c && git commit -q -m c3
+
+  git checkout -q -b topic
+  mkdir dir && echo d > dir/d
+  git add dir && git commit -q -m c4
+  git tag -a -m "annotated" v2
+  git checkout -q main
+
+  git rev-parse v2 ^origin/main | git pack-objects -q --revs --delta-base-offset --stdout > ../topic.pack
+  git pack-objects -q --stdout < /dev/null > ../empty.pack
+)
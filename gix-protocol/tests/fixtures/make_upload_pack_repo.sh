@@ -0,0 +1,31 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+function commit_at() {
+  local date=$1 message=$2
+  GIT_COMMITTER_DATE="$date +0000" GIT_AUTHOR_DATE="$date +0000" git commit -q -m "$message"
+}
+
+git init -q repo
+(cd repo
+  git checkout -q -b main
+  echo a > a
+  mkdir dir && echo b > dir/b
+  git add . && commit_at "2000-01-01 00:00:00" c1
+  git tag -a -m "annotated" v1
+
+  echo a2 > a
+  seq 1 2000 > big
+  git add . && commit_at "2000-01-02 00:00:00" c2
+  git tag light
+
+  git checkout -q -b dev
+  echo d > d
+  git add . && commit_at "2000-01-03 00:00:00" c3
+
+  git checkout -q main
+  echo c > dir/c
+  git add . && commit_at "2000-01-04 00:00:00" c4
+
+  git for-each-ref --format='%(objectname) %(refname) %(*objectname)' > ../refs
+)
@@ -0,0 +1,155 @@
+use bstr::BString;
+use gix_protocol::bundle_uri::{Bundle, Heuristic, List, Mode, parse};
+
+fn lines(input: &[&str]) -> Vec<BString> {
+    input.iter().map(|line| BString::from(*line)).collect()
+}
+
+fn from_lines(input: &[&str]) -> Result<List, parse::Error> {
+    List::from_lines(lines(input).iter().map(AsRef::as_ref))
+}
+
+fn bundle(id: &str, uri: &str, creation_token: Option<u64>) -> Bundle {
+    Bundle {
+        id: id.into(),
+        uri: uri.into(),
+        creation_token,
+    }
+}
+
+#[test]
+fn from_lines_as_sent_by_git() -> crate::Result {
+    let list = from_lines(&[
+        "bundle.version=1",
+        "bundle.mode=any",
+        "bundle.heuristic=creationToken",
+        "bundle.unknown=ignored",
+        "bundle.eu.uri=https://eu.example.com/repo.bundle",
+        "bundle.eu.creationtoken=42",
+        "bundle.us.west.URI=../repo.bundle",
+        "bundle.us.west.filter=ignored",
+    ])?;
+    assert_eq!(
+        list,
+        List {
+            mode: Mode::Any,
+            heuristic: Some(Heuristic::CreationToken),
+            bundles: vec![
+                bundle("eu", "https://eu.example.com/repo.bundle", Some(42)),
+                bundle("us.west", "../repo.bundle", None),
+            ],
+        }
+    );
+
+    let list = from_lines(&["bundle.version=1", "bundle.heuristic=unknown"])?;
+    assert_eq!(
+        list,
+        List::default(),
+        "unknown heuristics are ignored, and the mode defaults to 'all'"
+    );
+    Ok(())
+}
+
+#[test]
+fn to_lines_round_trips() -> crate::Result {
+    let list = List {
+        mode: Mode::All,
+        heuristic: Some(Heuristic::CreationToken),
+        bundles: vec![
+            bundle("base", "base.bundle", Some(1)),
+            bundle("tip", "tip.bundle", None),
+        ],
+    };
+    let lines = list.to_lines();
+    assert_eq!(
+        lines,
+        [
+            "bundle.version=1",
+            "bundle.mode=all",
+            "bundle.heuristic=creationToken",
+            "bundle.base.uri=base.bundle",
+            "bundle.base.creationToken=1",
+            "bundle.tip.uri=tip.bundle",
+        ]
+    );
+    assert_eq!(List::from_lines(lines.iter().map(AsRef::as_ref))?, list);
+    Ok(())
+}
+
+#[test]
+fn from_lines_errors() {
+    for (input, expected) in [
+        ("bundle.version=2", "The bundle list version \"2\" is unsupported"),
+        ("bundle.mode=some", "The bundle list mode \"some\" is unknown"),
+        (
+            "bundle.a.creationToken=-1",
+            "The creation token \"-1\" of bundle \"a\" isn't a number",
+        ),
+        ("bundle.a.creationToken=1", "The bundle \"a\" doesn't have a URI"),
+        (
+            "remote.origin.url=foo",
+            "Expected a line like 'bundle.version=1', got \"remote.origin.url=foo\"",
+        ),
+        (
+            "bundle.mode",
+            "Expected a line like 'bundle.version=1', got \"bundle.mode\"",
+        ),
+    ] {
+        assert_eq!(from_lines(&[input]).unwrap_err().to_string(), expected, "{input}");
+    }
+}
+
+mod command {
+    use bstr::ByteSlice;
+    use gix_features::progress;
+    use gix_protocol::{
+        BundleUriCommand,
+        bundle_uri::{Heuristic, Mode},
+    };
+    #[cfg(feature = "async-client")]
+    use gix_transport::client::async_io::Transport;
+    #[cfg(feature = "blocking-client")]
+    use gix_transport::client::blocking_io::Transport;
+    use gix_transport::{Protocol, Service};
+
+    use super::bundle;
+    use crate::fetch::transport;
+
+    #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+    async fn invoke() -> crate::Result {
+        let mut transport = transport(
+            Vec::new(),
+            "v2/bundle-uri.response",
+            Protocol::V2,
+            gix_transport::client::git::ConnectMode::Daemon,
+        );
+        let capabilities = transport.handshake(Service::UploadPack, &[]).await?.capabilities;
+        assert!(gix_protocol::bundle_uri::is_advertised(&capabilities));
+
+        let command = BundleUriCommand::new(&capabilities, ("agent", Some("git/test".into())));
+        #[cfg(feature = "blocking-client")]
+        let list = command.invoke_blocking(&mut transport, &mut progress::Discard, false)?;
+        #[cfg(feature = "async-client")]
+        let list = command
+            .invoke_async(&mut transport, &mut progress::Discard, false)
+            .await?;
+        assert_eq!(list.mode, Mode::All);
+        assert_eq!(list.heuristic, Some(Heuristic::CreationToken));
+        assert_eq!(
+            list.bundles,
+            [
+                bundle("base", "https://cdn.example.com/base.bundle", Some(1)),
+                bundle("daily", "daily.bundle", Some(2)),
+            ]
+        );
+        assert_eq!(
+            transport.into_inner().1.as_bstr(),
+            "002fgit-upload-pack does/not/matter\0\0version=2\x000017command=bundle-uri
+0017object-format=sha1
+0013agent=git/test
+0000",
+            "there are no arguments, so no delimiter is sent"
+        );
+        Ok(())
+    }
+}
@@ -166,8 +166,7 @@ mod fetch_fn {
             let response = Response::from_line_reader(
                 protocol_version,
                 &mut reader,
-                true,  /* hack, telling us we don't want this delegate approach anymore */
-                false, /* just as much of a hack which causes us to expect a pack immediately */
+                true, /* hack, telling us we don't want this delegate approach anymore */
             )
             .await?;
             previous_response = if response.has_pack() {
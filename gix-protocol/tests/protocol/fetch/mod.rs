@@ -328,4 +328,6 @@ pub fn transport<W: std::io::Write>(
 
 pub mod response;
 mod v1;
+#[cfg(feature = "blocking-client")]
+mod v1_stateful;
 mod v2;
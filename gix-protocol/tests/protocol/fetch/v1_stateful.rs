@@ -0,0 +1,383 @@
+//! Protocol V1 negotiations over a stateful connection, with a scripted server on the other end of an in-memory pipe.
+//!
+//! As the connection stays open, reading more than the server sends in a response would block forever,
+//! which is why each fetch is guarded by a timeout.
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    num::NonZeroU32,
+    sync::{atomic::AtomicBool, mpsc::RecvTimeoutError},
+    time::Duration,
+};
+
+use gix_features::io::pipe;
+use gix_hash::ObjectId;
+use gix_protocol::fetch::{self, Arguments, Response, Shallow, Tags, negotiate, response::Acknowledgement};
+use gix_transport::{
+    Protocol, Service,
+    client::git::{ConnectMode, blocking_io::Connection},
+};
+
+const FLUSH: &str = "0000";
+const PACK: &[u8] = b"PACK and then some bytes that are never looked at";
+
+fn id(byte: u8) -> ObjectId {
+    ObjectId::from_hex(format!("{byte:02x}").repeat(20).as_bytes()).expect("valid hex")
+}
+
+fn want_line() -> String {
+    format!(
+        "want {} side-band-64k ofs-delta shallow multi_ack_detailed agent=git/oxide-test",
+        id(0xff)
+    )
+}
+
+fn have_lines(ids: impl IntoIterator<Item = u8>) -> Vec<String> {
+    ids.into_iter().map(|byte| format!("have {}", id(byte))).collect()
+}
+
+/// The server side of the connection, which verifies what the client sends and answers as instructed.
+struct Server {
+    read: pipe::Reader,
+    write: pipe::Writer,
+}
+
+impl Server {
+    fn advertise_refs(&mut self) {
+        let tip = id(0xff);
+        self.send([
+            format!("{tip} HEAD\0multi_ack_detailed side-band-64k ofs-delta shallow agent=git/2.51.0").as_str(),
+            &format!("{tip} refs/heads/main"),
+            FLUSH,
+        ]);
+    }
+
+    fn receive<'a>(&mut self, expected: impl IntoIterator<Item = &'a str>) {
+        for expected in expected {
+            let actual = self.read_line();
+            assert_eq!(actual, expected, "the client sent an unexpected line");
+        }
+    }
+
+    fn read_line(&mut self) -> String {
+        let mut hex_len = [0; 4];
+        self.read
+            .read_exact(&mut hex_len)
+            .expect("the client doesn't hang up early");
+        let len = usize::from_str_radix(std::str::from_utf8(&hex_len).expect("ascii"), 16).expect("valid length");
+        if len == 0 {
+            return FLUSH.into();
+        }
+        let mut data = vec![0; len - 4];
+        self.read
+            .read_exact(&mut data)
+            .expect("the client sends complete lines");
+        String::from_utf8(data).expect("utf8").trim_end_matches('\n').into()
+    }
+
+    fn send<'a>(&mut self, lines: impl IntoIterator<Item = &'a str>) {
+        for line in lines {
+            if line == FLUSH {
+                self.write.write_all(FLUSH.as_bytes())
+            } else {
+                writeln!(self.write, "{:04x}{line}", line.len() + 5)
+            }
+            .expect("the client is still listening");
+        }
+    }
+
+    fn send_pack(&mut self) {
+        write!(self.write, "{:04x}\x01", PACK.len() + 5).expect("the client is still listening");
+        self.write.write_all(PACK).expect("the client is still listening");
+        self.send([FLUSH]);
+    }
+}
+
+/// A negotiator which offers all `haves` in order, and remembers which ones the remote has in common with us.
+struct Haves {
+    haves: VecDeque<ObjectId>,
+    common: Vec<ObjectId>,
+}
+
+impl gix_negotiate::Negotiator for Haves {
+    fn known_common(
+        &mut self,
+        _id: ObjectId,
+        _graph: &mut gix_negotiate::Graph<'_, '_>,
+    ) -> Result<(), gix_negotiate::Error> {
+        Ok(())
+    }
+
+    fn add_tip(
+        &mut self,
+        _id: ObjectId,
+        _graph: &mut gix_negotiate::Graph<'_, '_>,
+    ) -> Result<(), gix_negotiate::Error> {
+        Ok(())
+    }
+
+    fn next_have(
+        &mut self,
+        _graph: &mut gix_negotiate::Graph<'_, '_>,
+    ) -> Option<Result<ObjectId, gix_negotiate::Error>> {
+        self.haves.pop_front().map(Ok)
+    }
+
+    fn in_common_with_remote(
+        &mut self,
+        id: ObjectId,
+        _graph: &mut gix_negotiate::Graph<'_, '_>,
+    ) -> Result<bool, gix_negotiate::Error> {
+        self.common.push(id);
+        Ok(true)
+    }
+}
+
+struct Negotiate {
+    negotiator: Haves,
+    graph: gix_negotiate::Graph<'static, 'static>,
+}
+
+impl fetch::Negotiate for Negotiate {
+    fn mark_complete_and_common_ref(&mut self) -> Result<negotiate::Action, negotiate::Error> {
+        Ok(negotiate::Action::MustNegotiate {
+            remote_ref_target_known: vec![false],
+        })
+    }
+
+    fn add_wants(&mut self, arguments: &mut Arguments, _remote_ref_target_known: &[bool]) -> bool {
+        arguments.want(id(0xff));
+        true
+    }
+
+    fn one_round(
+        &mut self,
+        state: &mut negotiate::one_round::State,
+        arguments: &mut Arguments,
+        previous_response: Option<&Response>,
+    ) -> Result<(negotiate::Round, bool), negotiate::Error> {
+        negotiate::one_round(
+            &mut self.negotiator,
+            &mut self.graph,
+            state,
+            arguments,
+            previous_response,
+        )
+    }
+}
+
+struct Outcome {
+    fetch: fetch::Outcome,
+    /// The commits that the remote acknowledged as common during negotiation.
+    common: Vec<ObjectId>,
+    pack: Vec<u8>,
+}
+
+/// Fetch from a server running `script` after advertising its refs, offering the commits `haves` during negotiation,
+/// with the shallow boundary being adjusted according to `shallow`.
+fn fetch(
+    haves: impl IntoIterator<Item = u8>,
+    shallow: Shallow,
+    shallow_file: std::path::PathBuf,
+    script: impl FnOnce(&mut Server) + Send + 'static,
+) -> Result<Outcome, fetch::Error> {
+    // Allow writes to be buffered like they would be by the operating system, so both sides can pipeline their messages.
+    let (client_write, server_read) = pipe::unidirectional(1024);
+    let (server_write, client_read) = pipe::unidirectional(1024);
+    let server = std::thread::spawn(move || {
+        let mut server = Server {
+            read: server_read,
+            write: server_write,
+        };
+        server.advertise_refs();
+        script(&mut server);
+    });
+
+    let haves: VecDeque<_> = haves.into_iter().map(id).collect();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let client = std::thread::spawn(move || {
+        let res = (|| {
+            let mut transport = Connection::new(
+                client_read,
+                client_write,
+                Protocol::V1,
+                "/repo.git",
+                None::<(&str, _)>,
+                ConnectMode::Process,
+                false,
+            );
+            let mut handshake = gix_protocol::handshake(
+                &mut transport,
+                Service::UploadPack,
+                crate::fetch::helper_unused,
+                Vec::new(),
+                &mut gix_features::progress::Discard,
+            )
+            .expect("the handshake succeeds");
+            assert_eq!(handshake.server_protocol_version, Protocol::V1);
+            let mut negotiate = Negotiate {
+                negotiator: Haves {
+                    haves,
+                    common: Vec::new(),
+                },
+                graph: gix_negotiate::Graph::new(gix_object::find::Never, None),
+            };
+            let mut pack = Vec::new();
+            let outcome = gix_protocol::fetch(
+                &mut negotiate,
+                |read, _progress, _should_interrupt| read.read_to_end(&mut pack).map(|_| true),
+                gix_features::progress::Discard,
+                &AtomicBool::default(),
+                fetch::Context {
+                    handshake: &mut handshake,
+                    transport: &mut transport,
+                    user_agent: ("agent", Some("git/oxide-test".into())),
+                    trace_packetlines: false,
+                },
+                fetch::Options {
+                    shallow_file,
+                    shallow: &shallow,
+                    tags: Tags::None,
+                    reject_shallow_remote: false,
+                    filter: None,
+                },
+            )?
+            .expect("there is something to fetch");
+            Ok(Outcome {
+                fetch: outcome,
+                common: negotiate.negotiator.common,
+                pack,
+            })
+        })();
+        tx.send(res).ok();
+    });
+
+    let res = match rx.recv_timeout(Duration::from_secs(10)) {
+        Ok(res) => res,
+        Err(RecvTimeoutError::Timeout) => panic!("the fetch hangs"),
+        Err(RecvTimeoutError::Disconnected) => {
+            std::panic::resume_unwind(client.join().expect_err("the client panicked"))
+        }
+    };
+    if let Err(panic) = server.join() {
+        std::panic::resume_unwind(panic);
+    }
+    res
+}
+
+#[test]
+fn multi_round_negotiation_ends_once_the_server_is_ready() -> crate::Result {
+    let dir = gix_testtools::tempfile::tempdir()?;
+    let out = fetch(1..=64, Shallow::NoChange, dir.path().join("shallow"), |server| {
+        server.receive([want_line().as_str(), FLUSH]);
+        server.receive(have_lines(1..=16).iter().map(String::as_str).chain(Some(FLUSH)));
+        server.send(["NAK"]);
+
+        server.receive(have_lines(17..=48).iter().map(String::as_str).chain(Some(FLUSH)));
+        server.send([
+            format!("ACK {} common", id(20)).as_str(),
+            &format!("ACK {} common", id(30)),
+            &format!("ACK {} ready", id(30)),
+            "NAK",
+        ]);
+
+        server.receive(["done"]);
+        server.send([format!("ACK {}", id(30)).as_str()]);
+        server.send_pack();
+    })?;
+
+    let rounds = &out.fetch.negotiate.rounds;
+    assert_eq!(rounds.len(), 3, "the server was ready after the second round");
+    assert_eq!(
+        rounds.iter().map(|r| r.haves_sent).collect::<Vec<_>>(),
+        [16, 32, 0],
+        "no more haves are sent once the server is ready"
+    );
+    assert_eq!(out.common, [id(20), id(30)]);
+    assert_eq!(
+        out.fetch.last_response.acknowledgements(),
+        [Acknowledgement::Common(id(30))]
+    );
+    assert_eq!(out.pack, PACK);
+    Ok(())
+}
+
+#[test]
+fn negotiation_without_common_commits_ends_with_done() -> crate::Result {
+    let dir = gix_testtools::tempfile::tempdir()?;
+    let out = fetch(1..=20, Shallow::NoChange, dir.path().join("shallow"), |server| {
+        server.receive([want_line().as_str(), FLUSH]);
+        server.receive(have_lines(1..=16).iter().map(String::as_str).chain(Some(FLUSH)));
+        server.send(["NAK"]);
+
+        server.receive(have_lines(17..=20).iter().map(String::as_str).chain(Some("done")));
+        server.send(["NAK"]);
+        server.send_pack();
+    })?;
+
+    assert_eq!(
+        out.fetch
+            .negotiate
+            .rounds
+            .iter()
+            .map(|r| r.haves_sent)
+            .collect::<Vec<_>>(),
+        [16, 4],
+        "the second round runs out of haves"
+    );
+    assert!(out.common.is_empty());
+    assert_eq!(out.fetch.last_response.acknowledgements(), [Acknowledgement::Nak]);
+    assert_eq!(out.pack, PACK);
+    Ok(())
+}
+
+#[test]
+fn shallow_updates_are_sent_before_acknowledgements() -> crate::Result {
+    let dir = gix_testtools::tempfile::tempdir()?;
+    let shallow_file = dir.path().join("shallow");
+    let out = fetch(
+        1..=3,
+        Shallow::DepthAtRemote(NonZeroU32::new(1).expect("non-zero")),
+        shallow_file.clone(),
+        |server| {
+            server.receive([want_line().as_str(), "deepen 1", FLUSH]);
+            server.send([format!("shallow {}", id(0xff)).as_str(), FLUSH]);
+
+            server.receive(have_lines(1..=3).iter().map(String::as_str).chain(Some("done")));
+            server.send([format!("ACK {} common", id(2)).as_str(), &format!("ACK {}", id(2))]);
+            server.send_pack();
+        },
+    )?;
+
+    assert_eq!(out.fetch.negotiate.rounds.len(), 1);
+    assert_eq!(
+        out.fetch.last_response.acknowledgements(),
+        [Acknowledgement::Common(id(2))],
+        "the final acknowledgement of the common commit is deduplicated"
+    );
+    assert_eq!(
+        out.fetch.last_response.shallow_updates(),
+        [gix_shallow::Update::Shallow(id(0xff))]
+    );
+    assert_eq!(std::fs::read_to_string(shallow_file)?, format!("{}\n", id(0xff)));
+    assert_eq!(out.pack, PACK);
+    Ok(())
+}
+
+#[test]
+fn unexpected_lines_during_negotiation_are_an_error_instead_of_the_start_of_a_pack() -> crate::Result {
+    let dir = gix_testtools::tempfile::tempdir()?;
+    let err = fetch(1..=20, Shallow::NoChange, dir.path().join("shallow"), |server| {
+        server.receive([want_line().as_str(), FLUSH]);
+        server.receive(have_lines(1..=16).iter().map(String::as_str).chain(Some(FLUSH)));
+        server.send(["something unexpected"]);
+    })
+    .err()
+    .expect("the negotiation fails");
+
+    assert!(
+        matches!(err, fetch::Error::FetchResponse(_)),
+        "the response can't be parsed, got {err:?}"
+    );
+    Ok(())
+}
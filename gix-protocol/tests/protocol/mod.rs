@@ -5,8 +5,14 @@ pub fn fixture_bytes(path: &str) -> Vec<u8> {
         .expect("fixture to be present and readable")
 }
 
+mod bundle_uri;
 mod command;
 pub mod fetch;
 mod handshake;
+mod push;
+#[cfg(feature = "blocking-client")]
+mod receive_pack;
+#[cfg(feature = "blocking-client")]
+mod upload_pack;
 pub use fetch::_impl::{FetchConnection, fetch};
 pub mod remote_progress;
@@ -0,0 +1,323 @@
+fn oid(hex: &str) -> gix_hash::ObjectId {
+    gix_hash::ObjectId::from_hex(hex.as_bytes()).expect("valid hex")
+}
+
+mod report_from_lines {
+    use bstr::ByteSlice;
+    use gix_protocol::push::{Report, report};
+
+    use super::oid;
+
+    fn parse(lines: &[&str]) -> Result<Report, report::decode::Error> {
+        Report::from_lines(lines.iter().map(|line| line.as_bytes().as_bstr()))
+    }
+
+    #[test]
+    fn v1() -> crate::Result {
+        let report = parse(&[
+            "unpack ok\n",
+            "ok refs/heads/main\n",
+            "ng refs/heads/feature non-fast-forward\n",
+            "ng refs/tags/v1\n",
+        ])?;
+        assert_eq!(report.unpack_error, None);
+        assert_eq!(
+            report.refs,
+            [
+                report::RefStatus {
+                    name: "refs/heads/main".into(),
+                    error: None,
+                    updates: Vec::new(),
+                },
+                report::RefStatus {
+                    name: "refs/heads/feature".into(),
+                    error: Some("non-fast-forward".into()),
+                    updates: Vec::new(),
+                },
+                report::RefStatus {
+                    name: "refs/tags/v1".into(),
+                    error: Some("failed".into()),
+                    updates: Vec::new(),
+                },
+            ]
+        );
+        assert!(!report.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn unpack_failure() -> crate::Result {
+        let report = parse(&["unpack index-pack abnormal exit", "ng refs/heads/main unpacker error"])?;
+        assert_eq!(
+            report.unpack_error.as_ref().map(|e| e.as_bstr()),
+            Some("index-pack abnormal exit".into())
+        );
+        assert!(!report.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn v2_options() -> crate::Result {
+        let report = parse(&[
+            "unpack ok",
+            "ok refs/heads/main",
+            "option forced-update",
+            "ok refs/for/main",
+            "option refname refs/changes/1",
+            "option old-oid 1111111111111111111111111111111111111111",
+            "option new-oid 2222222222222222222222222222222222222222",
+            "option refname refs/changes/2",
+            "option new-oid 3333333333333333333333333333333333333333",
+        ])?;
+        assert!(report.is_ok());
+        assert_eq!(
+            report.refs[0].updates,
+            [report::Update {
+                forced: true,
+                ..Default::default()
+            }]
+        );
+        assert_eq!(
+            report.refs[1].updates,
+            [
+                report::Update {
+                    name: Some("refs/changes/1".into()),
+                    old: Some(oid("1111111111111111111111111111111111111111")),
+                    new: Some(oid("2222222222222222222222222222222222222222")),
+                    forced: false,
+                },
+                report::Update {
+                    name: Some("refs/changes/2".into()),
+                    old: None,
+                    new: Some(oid("3333333333333333333333333333333333333333")),
+                    forced: false,
+                }
+            ],
+            "each refname starts a new update"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(parse(&[]), Err(report::decode::Error::Empty)));
+        assert!(matches!(
+            parse(&["ok refs/heads/main"]),
+            Err(report::decode::Error::Unpack { .. })
+        ));
+        assert!(matches!(
+            parse(&["unpack ok", "option forced-update"]),
+            Err(report::decode::Error::OrphanedOption { .. })
+        ));
+        assert!(matches!(
+            parse(&["unpack ok", "ok refs/heads/main", "option old-oid foo"]),
+            Err(report::decode::Error::Id { .. })
+        ));
+        assert!(matches!(
+            parse(&["unpack ok", "unknown refs/heads/main"]),
+            Err(report::decode::Error::Line { .. })
+        ));
+    }
+}
+
+#[cfg(feature = "blocking-client")]
+mod blocking_io {
+    use std::sync::atomic::AtomicBool;
+
+    use bstr::ByteSlice;
+    use gix_features::progress;
+    use gix_protocol::push::{Command, Context, Error, Options, report};
+    use gix_transport::client::git::ConnectMode;
+
+    use super::oid;
+    use crate::fetch::transport;
+
+    const MAIN: &str = "1111111111111111111111111111111111111111";
+    const OLD: &str = "2222222222222222222222222222222222222222";
+    const NEW: &str = "3333333333333333333333333333333333333333";
+
+    fn helper_unused(_action: gix_credentials::helper::Action) -> gix_credentials::protocol::Result {
+        panic!("Call to credentials helper is unexpected")
+    }
+
+    fn push(
+        fixture: &str,
+        commands: &[Command],
+        options: Options,
+    ) -> Result<(Option<report::Report>, Vec<u8>, bool), Error> {
+        let mut out = Vec::new();
+        let mut pack_written = false;
+        let res = {
+            let mut transport = transport(&mut out, fixture, gix_transport::Protocol::V1, ConnectMode::Process);
+            let handshake = gix_protocol::handshake(
+                &mut transport,
+                gix_transport::Service::ReceivePack,
+                helper_unused,
+                Vec::new(),
+                &mut progress::Discard,
+            )
+            .expect("handshake works with the fixture");
+            gix_protocol::push(
+                commands,
+                |out, _progress, _interrupt| {
+                    pack_written = true;
+                    out.write_all(b"PACK")
+                },
+                progress::Discard,
+                &AtomicBool::default(),
+                Context {
+                    handshake: &handshake,
+                    transport: &mut transport,
+                    user_agent: ("agent", Some("git/gitoxide".into())),
+                    trace_packetlines: false,
+                },
+                options,
+            )
+        };
+        res.map(|report| (report, out, pack_written))
+    }
+
+    #[test]
+    fn updates_and_deletions_with_all_features() -> crate::Result {
+        let (report, out, pack_written) = push(
+            "v1/push.response",
+            &[
+                Command {
+                    old: oid(MAIN),
+                    new: oid(NEW),
+                    name: "refs/heads/main".into(),
+                },
+                Command {
+                    old: oid(OLD),
+                    new: gix_hash::Kind::Sha1.null(),
+                    name: "refs/heads/old".into(),
+                },
+            ],
+            Options {
+                atomic: true,
+                push_options: vec!["ci.skip".into()],
+            },
+        )?;
+        assert!(pack_written, "a pack is needed for the update");
+        assert_eq!(
+            out.as_bstr(),
+            format!(
+                "00cb{MAIN} {NEW} refs/heads/main\0report-status-v2 side-band-64k atomic push-options delete-refs object-format=sha1 agent=git/gitoxide\n\
+                 0065{OLD} 0000000000000000000000000000000000000000 refs/heads/old\n\
+                 0000\
+                 000cci.skip\n\
+                 0000\
+                 PACK"
+            )
+            .as_bytes()
+            .as_bstr()
+        );
+
+        let report = report.expect("report was requested");
+        assert_eq!(report.unpack_error, None);
+        assert_eq!(
+            report.refs,
+            [
+                report::RefStatus {
+                    name: "refs/heads/main".into(),
+                    error: None,
+                    updates: vec![report::Update {
+                        forced: true,
+                        ..Default::default()
+                    }],
+                },
+                report::RefStatus {
+                    name: "refs/heads/old".into(),
+                    error: Some("deletion prohibited".into()),
+                    updates: Vec::new(),
+                }
+            ],
+            "the report is read from the first sideband channel"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn deletions_only_need_no_pack() -> crate::Result {
+        let (_report, out, pack_written) = push(
+            "v1/push.response",
+            &[Command {
+                old: oid(OLD),
+                new: gix_hash::Kind::Sha1.null(),
+                name: "refs/heads/old".into(),
+            }],
+            Options::default(),
+        )?;
+        assert!(!pack_written);
+        assert!(!out.ends_with(b"PACK"));
+        Ok(())
+    }
+
+    #[test]
+    fn without_sideband() -> crate::Result {
+        let (report, out, _) = push(
+            "v1/push-without-sideband.response",
+            &[Command {
+                old: oid(MAIN),
+                new: oid(NEW),
+                name: "refs/heads/main".into(),
+            }],
+            Options::default(),
+        )?;
+        assert!(
+            out.find(b"\0report-status agent=git/gitoxide\n").is_some(),
+            "only supported capabilities are requested"
+        );
+        assert!(report.expect("report was requested").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn missing_server_features() {
+        let update = Command {
+            old: oid(MAIN),
+            new: oid(NEW),
+            name: "refs/heads/main".into(),
+        };
+        for (commands, options, expected) in [
+            (
+                vec![update.clone()],
+                Options {
+                    atomic: true,
+                    ..Default::default()
+                },
+                "atomic",
+            ),
+            (
+                vec![update.clone()],
+                Options {
+                    push_options: vec!["option".into()],
+                    ..Default::default()
+                },
+                "push-options",
+            ),
+            (
+                vec![Command {
+                    new: gix_hash::Kind::Sha1.null(),
+                    ..update.clone()
+                }],
+                Options::default(),
+                "delete-refs",
+            ),
+        ] {
+            match push("v1/push-without-sideband.response", &commands, options) {
+                Err(Error::MissingServerFeature { feature, .. }) => assert_eq!(feature, expected),
+                res => panic!("expected missing feature {expected}, got {res:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn nothing_to_do() -> crate::Result {
+        let (report, out, pack_written) = push("v1/push.response", &[], Options::default())?;
+        assert!(report.is_none());
+        assert!(out.is_empty(), "nothing is sent");
+        assert!(!pack_written);
+        Ok(())
+    }
+}
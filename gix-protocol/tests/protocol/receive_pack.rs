@@ -0,0 +1,516 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use bstr::{BString, ByteSlice};
+use gix_features::progress;
+use gix_hash::ObjectId;
+use gix_packetline::{PacketLineRef, blocking_io::StreamingPeekableIter, blocking_io::encode};
+use gix_protocol::receive_pack::{Context, Error, Options, Outcome};
+
+struct Fixture {
+    _tmp: gix_testtools::tempfile::TempDir,
+    dir: PathBuf,
+}
+
+impl Fixture {
+    fn new() -> gix_testtools::Result<Self> {
+        let tmp = gix_testtools::scripted_fixture_writable("make_receive_pack_repo.sh")?;
+        Ok(Fixture {
+            dir: tmp.path().to_owned(),
+            _tmp: tmp,
+        })
+    }
+
+    fn remote(&self) -> PathBuf {
+        self.dir.join("remote.git")
+    }
+
+    fn serve(&self, input: &[u8]) -> (Result<Outcome, Error>, Response) {
+        let mut out = Vec::new();
+        let res = serve(&self.remote(), input, &mut out);
+        (res, Response::from_bytes(&out))
+    }
+
+    /// Resolve `spec` in the repository at `repo` relative to the fixture, or return `None` if it doesn't exist.
+    fn rev_parse(&self, repo: &str, spec: &str) -> Option<ObjectId> {
+        gix_testtools::git(self.dir.join(repo), &format!("rev-parse --verify -q {spec}"))
+            .ok()
+            .map(|id| oid(id.trim()))
+    }
+
+    fn id(&self, spec: &str) -> ObjectId {
+        self.rev_parse("client", spec).expect("spec exists in client")
+    }
+
+    fn pack(&self, name: &str) -> Vec<u8> {
+        std::fs::read(self.dir.join(name)).expect("pack exists")
+    }
+
+    /// Return the names of all files in the pack directory of the remote, along with all quarantine directories.
+    fn object_database_files(&self) -> gix_testtools::Result<Vec<String>> {
+        let objects = self.remote().join("objects");
+        let mut files = Vec::new();
+        for dir in [objects.join("pack"), objects] {
+            for entry in std::fs::read_dir(dir)? {
+                let name = entry?.file_name().to_string_lossy().into_owned();
+                if name.starts_with("pack-") || name.starts_with("incoming-") {
+                    files.push(name);
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+fn serve(remote: &Path, read: impl std::io::Read, write: impl std::io::Write) -> Result<Outcome, Error> {
+    let refs = gix_ref::file::Store::at(
+        remote.to_owned(),
+        gix_ref::store::init::Options {
+            write_reflog: gix_ref::store::WriteReflog::Always,
+            object_hash: gix_testtools::object_hash(),
+            ..Default::default()
+        },
+    );
+    gix_protocol::receive_pack(
+        read,
+        write,
+        Context {
+            refs: &refs,
+            objects_dir: &remote.join("objects"),
+            object_hash: gix_testtools::object_hash(),
+            committer: Some(gix_actor::SignatureRef {
+                name: "committer".into(),
+                email: "committer@example.com".into(),
+                time: "946684800 +0000",
+            }),
+        },
+        progress::Discard,
+        &AtomicBool::default(),
+        Options::default(),
+    )
+}
+
+fn oid(hex: &str) -> ObjectId {
+    ObjectId::from_hex(hex.as_bytes()).expect("valid object id")
+}
+
+/// Create a request with the given `commands` and `capabilities`, followed by `push_options` and the `pack`, if set.
+fn request(commands: &[String], capabilities: &str, push_options: &[&str], pack: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (idx, command) in commands.iter().enumerate() {
+        let line = if idx == 0 {
+            format!("{command}\0{capabilities}\n")
+        } else {
+            format!("{command}\n")
+        };
+        encode::data_to_write(line.as_bytes(), &mut out).expect("write to memory");
+    }
+    encode::flush_to_write(&mut out).expect("write to memory");
+    if !push_options.is_empty() {
+        for option in push_options {
+            encode::text_to_write(option.as_bytes(), &mut out).expect("write to memory");
+        }
+        encode::flush_to_write(&mut out).expect("write to memory");
+    }
+    out.extend_from_slice(pack);
+    out
+}
+
+struct Response {
+    /// The lines of the reference advertisement, excluding the trailing flush packet.
+    advertisement: Vec<BString>,
+    /// The lines of the status report, without the trailing flush packet, if one was sent.
+    report: Vec<BString>,
+}
+
+impl Response {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut lines = StreamingPeekableIter::new(bytes, &[PacketLineRef::Flush], false);
+        let advertisement = data_lines(&mut lines);
+        lines.reset();
+        let mut report = data_lines(&mut lines);
+        if report.first().is_some_and(|line| line.first() == Some(&1)) {
+            let inner: Vec<u8> = report.iter().flat_map(|line| line[1..].to_owned()).collect();
+            report = data_lines(&mut StreamingPeekableIter::new(
+                inner.as_slice(),
+                &[PacketLineRef::Flush],
+                false,
+            ));
+        }
+        Response { advertisement, report }
+    }
+}
+
+/// Read all data lines up to the next flush packet, or until the end of input.
+fn data_lines(lines: &mut StreamingPeekableIter<&[u8]>) -> Vec<BString> {
+    let mut out = Vec::new();
+    while let Some(line) = lines.read_line() {
+        match line {
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            line => {
+                let line = line.expect("no IO error").expect("valid packetline");
+                out.push(line.as_slice().expect("data line").trim_end_with(|c| c == '\n').into());
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn references_are_advertised_with_capabilities() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let (res, response) = fixture.serve(b"");
+    let outcome = res?;
+    assert!(outcome.commands.is_empty(), "the client didn't send anything");
+    assert!(outcome.report.refs.is_empty());
+
+    let expected: Vec<BString> = gix_testtools::git(fixture.remote(), "show-ref")?
+        .lines()
+        .map(Into::into)
+        .collect();
+    let (first, capabilities) = response.advertisement[0]
+        .split_once_str(b"\0")
+        .expect("capabilities on first line");
+    assert_eq!(first, expected[0]);
+    assert_eq!(&response.advertisement[1..], &expected[1..]);
+    let capabilities: Vec<_> = capabilities.split_str(b" ").map(ByteSlice::as_bstr).collect();
+    assert_eq!(
+        capabilities[..capabilities.len() - 1],
+        [
+            "report-status",
+            "report-status-v2",
+            "delete-refs",
+            "side-band-64k",
+            "quiet",
+            "atomic",
+            "ofs-delta",
+            "push-options",
+            format!("object-format={}", gix_testtools::object_hash()).as_str()
+        ]
+    );
+    assert!(capabilities.last().expect("agent").starts_with(b"agent=git/gix/"));
+    Ok(())
+}
+
+#[test]
+fn empty_repositories_advertise_capabilities_only() -> gix_testtools::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    gix_testtools::git(tmp.path(), "init -q --bare remote.git")?;
+    let mut out = Vec::new();
+    serve(&tmp.path().join("remote.git"), &b""[..], &mut out)?;
+    let response = Response::from_bytes(&out);
+    assert_eq!(response.advertisement.len(), 1);
+    assert!(
+        response.advertisement[0].starts_with(
+            format!(
+                "{} capabilities^{{}}\0report-status ",
+                gix_testtools::object_hash().null()
+            )
+            .as_bytes()
+        )
+    );
+    Ok(())
+}
+
+#[test]
+fn references_are_created_updated_and_deleted() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let (main, dev) = (
+        fixture.rev_parse("remote.git", "main").expect("present"),
+        fixture.rev_parse("remote.git", "dev").expect("present"),
+    );
+    let null = gix_testtools::object_hash().null();
+    let (new_main, topic, v2) = (fixture.id("main"), fixture.id("topic"), fixture.id("v2"));
+    let (res, response) = fixture.serve(&request(
+        &[
+            format!("{null} {topic} refs/heads/topic"),
+            format!("{main} {new_main} refs/heads/main"),
+            format!("{dev} {null} refs/heads/dev"),
+            format!("{null} {v2} refs/tags/v2"),
+        ],
+        "report-status side-band-64k",
+        &[],
+        &fixture.pack("topic.pack"),
+    ));
+    let outcome = res?;
+    assert!(outcome.report.is_ok());
+    assert_eq!(outcome.commands.len(), 4);
+    assert_eq!(
+        response.report,
+        [
+            "unpack ok",
+            "ok refs/heads/topic",
+            "ok refs/heads/main",
+            "ok refs/heads/dev",
+            "ok refs/tags/v2"
+        ],
+        "the report is sent through the sideband"
+    );
+
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/topic"), Some(topic));
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/main"), Some(new_main));
+    assert_eq!(fixture.rev_parse("remote.git", "refs/tags/v2"), Some(v2));
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/dev"), None);
+    gix_testtools::git(fixture.remote(), "fsck --strict --no-dangling")?;
+
+    let files = fixture.object_database_files()?;
+    assert_eq!(
+        files.len(),
+        2,
+        "a pack and its index, without quarantine or .keep file: {files:?}"
+    );
+    let reflog = std::fs::read_to_string(fixture.remote().join("logs/refs/heads/main"))?;
+    assert!(reflog.ends_with("\tpush\n"), "reflogs are written as configured");
+    Ok(())
+}
+
+#[test]
+fn updates_with_missing_objects_are_rejected() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let null = gix_testtools::object_hash().null();
+    let main = fixture.rev_parse("remote.git", "main").expect("present");
+    let (topic, new_main) = (fixture.id("topic"), fixture.id("main"));
+    let (res, response) = fixture.serve(&request(
+        &[
+            format!("{null} {topic} refs/heads/topic"),
+            format!("{main} {new_main} refs/heads/main"),
+        ],
+        "report-status",
+        &[],
+        &fixture.pack("empty.pack"),
+    ));
+    let outcome = res?;
+    assert!(!outcome.report.is_ok());
+    assert_eq!(
+        response.report,
+        [
+            "unpack ok",
+            "ng refs/heads/topic missing necessary objects",
+            "ng refs/heads/main missing necessary objects"
+        ]
+    );
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/topic"), None);
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/main"), Some(main));
+    assert_eq!(fixture.object_database_files()?, Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn stale_and_invalid_updates_are_rejected_individually() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let null = gix_testtools::object_hash().null();
+    let (topic, new_main) = (fixture.id("topic"), fixture.id("main"));
+    let (res, response) = fixture.serve(&request(
+        &[
+            format!("{null} {topic} refs/heads/topic"),
+            format!("{topic} {new_main} refs/heads/main"),
+            format!("{null} {topic} refs/heads/a..b"),
+            format!("{null} {topic} HEAD"),
+        ],
+        "report-status",
+        &[],
+        &fixture.pack("topic.pack"),
+    ));
+    let outcome = res?;
+    assert_eq!(response.report.len(), 5);
+    assert_eq!(response.report[..2], ["unpack ok", "ok refs/heads/topic"]);
+    assert!(
+        response.report[2].starts_with(b"ng refs/heads/main ") && response.report[2].contains_str("refs/heads/main"),
+        "the old value doesn't match: {}",
+        response.report[2]
+    );
+    assert_eq!(
+        response.report[3..],
+        ["ng refs/heads/a..b funny refname", "ng HEAD funny refname"]
+    );
+    assert_eq!(outcome.report.refs.iter().filter(|status| status.is_ok()).count(), 1);
+
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/topic"), Some(topic));
+    assert_ne!(fixture.rev_parse("remote.git", "refs/heads/main"), Some(new_main));
+    gix_testtools::git(fixture.remote(), "fsck --strict")?;
+    Ok(())
+}
+
+#[test]
+fn atomic_pushes_fail_entirely() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let null = gix_testtools::object_hash().null();
+    let main = fixture.rev_parse("remote.git", "main").expect("present");
+    let (topic, new_main) = (fixture.id("topic"), fixture.id("main"));
+    let (res, response) = fixture.serve(&request(
+        &[
+            format!("{null} {topic} refs/heads/topic"),
+            format!("{main} {new_main} refs/heads/main"),
+            format!("{null} {topic} refs/heads/a..b"),
+        ],
+        "report-status atomic",
+        &[],
+        &fixture.pack("topic.pack"),
+    ));
+    res?;
+    assert_eq!(
+        response.report,
+        [
+            "unpack ok",
+            "ng refs/heads/topic atomic push failure",
+            "ng refs/heads/main atomic push failure",
+            "ng refs/heads/a..b funny refname"
+        ]
+    );
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/topic"), None);
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/main"), Some(main));
+    assert_eq!(
+        fixture.object_database_files()?,
+        Vec::<String>::new(),
+        "the received objects are discarded"
+    );
+
+    let (res, response) = fixture.serve(&request(
+        &[
+            format!("{null} {topic} refs/heads/topic"),
+            format!("{topic} {new_main} refs/heads/main"),
+        ],
+        "report-status atomic",
+        &[],
+        &fixture.pack("topic.pack"),
+    ));
+    res?;
+    assert!(
+        response.report[1..]
+            .iter()
+            .all(|line| line.starts_with(b"ng ") && line.contains_str("refs/heads/main")),
+        "a failing transaction fails all updates: {:?}",
+        response.report
+    );
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/topic"), None);
+    Ok(())
+}
+
+#[test]
+fn push_options_are_received_and_reports_are_optional() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let null = gix_testtools::object_hash().null();
+    let dev = fixture.rev_parse("remote.git", "dev").expect("present");
+    let (res, response) = fixture.serve(&request(
+        &[format!("{dev} {null} refs/heads/dev")],
+        "report-status push-options",
+        &["ci.skip", "key=value with spaces"],
+        &[],
+    ));
+    let outcome = res?;
+    assert_eq!(outcome.push_options, ["ci.skip", "key=value with spaces"]);
+    assert_eq!(
+        response.report,
+        ["unpack ok", "ok refs/heads/dev"],
+        "no pack is needed for deletions"
+    );
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/dev"), None);
+
+    let main = fixture.rev_parse("remote.git", "main").expect("present");
+    let (res, response) = fixture.serve(&request(
+        &[format!("{main} {null} refs/heads/main")],
+        "delete-refs",
+        &[],
+        &[],
+    ));
+    assert!(res?.report.is_ok());
+    assert!(response.report.is_empty(), "the client didn't ask for a report");
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/main"), None);
+    Ok(())
+}
+
+#[test]
+fn invalid_requests_are_errors() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let null = gix_testtools::object_hash().null();
+
+    let (res, _) = fixture.serve(&request(&[format!("shallow {null}")], "report-status", &[], &[]));
+    assert!(matches!(res, Err(Error::ShallowUnsupported)));
+
+    let (res, _) = fixture.serve(&request(&[format!("{null} {null}")], "report-status", &[], &[]));
+    assert!(matches!(res, Err(Error::ParseCommand { .. })));
+
+    let other_kind = gix_hash::Kind::all()
+        .iter()
+        .find(|kind| **kind != gix_testtools::object_hash())
+        .expect("two kinds");
+    let (res, _) = fixture.serve(&request(
+        &[format!("{null} {null} refs/heads/main")],
+        &format!("object-format={other_kind}"),
+        &[],
+        &[],
+    ));
+    assert!(matches!(res, Err(Error::ObjectFormat { .. })));
+
+    let (res, _) = fixture.serve(&request(
+        &[format!("{null} {} refs/heads/main", other_kind.null())],
+        "",
+        &[],
+        &[],
+    ));
+    assert!(matches!(res, Err(Error::ObjectFormat { .. })));
+    Ok(())
+}
+
+/// Run `git` with `args` in `cwd`, with `{url}` being replaced with a `git://` URL to a daemon that serves a push to `remote`.
+fn git_via_daemon(remote: &Path, cwd: &Path, args: &str) -> gix_testtools::Result<Outcome> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("git://127.0.0.1:{}/remote.git", listener.local_addr()?.port());
+    let server = std::thread::spawn({
+        let remote = remote.to_owned();
+        move || -> Result<Outcome, Box<dyn std::error::Error + Send + Sync>> {
+            let (stream, _) = listener.accept()?;
+            let mut lines = StreamingPeekableIter::new(stream.try_clone()?, &[], false);
+            let request = lines.read_line().expect("daemon request")??;
+            assert!(
+                request
+                    .as_bstr()
+                    .expect("data")
+                    .starts_with(b"git-receive-pack /remote.git\0"),
+                "the daemon request is handled by the caller"
+            );
+            Ok(serve(&remote, lines.into_inner(), stream)?)
+        }
+    });
+    gix_testtools::git(cwd, &args.replace("{url}", &url))?;
+    server.join().expect("no panic")
+}
+
+#[test]
+fn git_can_push() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let client = fixture.dir.join("client");
+
+    let outcome = git_via_daemon(&fixture.remote(), &client, "push -q {url} main topic v2")?;
+    assert!(outcome.report.is_ok());
+    assert_eq!(outcome.commands.len(), 3);
+    for name in ["main", "topic", "v2"] {
+        assert_eq!(fixture.rev_parse("remote.git", name), Some(fixture.id(name)));
+    }
+    gix_testtools::git(fixture.remote(), "fsck --strict --no-dangling")?;
+
+    let outcome = git_via_daemon(
+        &fixture.remote(),
+        &client,
+        "push -q --atomic -o ci.skip {url} :dev :topic",
+    )?;
+    assert!(outcome.report.is_ok());
+    assert_eq!(outcome.push_options, ["ci.skip"]);
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/dev"), None);
+    assert_eq!(fixture.rev_parse("remote.git", "refs/heads/topic"), None);
+
+    gix_testtools::git(&client, "commit -q --amend -m rewritten")?;
+    let err = git_via_daemon(&fixture.remote(), &client, "push -q {url} main").expect_err("non-fast-forward");
+    assert!(
+        err.to_string().contains("non-fast-forward"),
+        "the client refuses to push non-fast-forward updates without --force"
+    );
+    let outcome = git_via_daemon(&fixture.remote(), &client, "push -q --force {url} main")?;
+    assert!(outcome.report.is_ok());
+    assert_eq!(fixture.rev_parse("remote.git", "main"), Some(fixture.id("main")));
+    gix_testtools::git(fixture.remote(), "fsck --strict")?;
+    Ok(())
+}
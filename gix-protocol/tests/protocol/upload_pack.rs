@@ -0,0 +1,656 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use bstr::ByteSlice;
+use gix_features::progress;
+use gix_hash::ObjectId;
+use gix_packetline::{PacketLineRef, blocking_io::StreamingPeekableIter, blocking_io::encode};
+use gix_protocol::{
+    handshake::Ref,
+    upload_pack::{Context, Error, Options},
+};
+
+struct Fixture {
+    dir: PathBuf,
+    refs: Vec<Ref>,
+    objects: gix_odb::HandleArc,
+}
+
+impl Fixture {
+    fn new() -> gix_testtools::Result<Self> {
+        let dir = gix_testtools::scripted_fixture_read_only("make_upload_pack_repo.sh")?;
+        let mut refs = Vec::new();
+        for line in std::fs::read_to_string(dir.join("refs"))?.lines() {
+            let mut tokens = line.split(' ');
+            let (id, name) = (oid(tokens.next().unwrap()), tokens.next().unwrap());
+            refs.push(match tokens.next().filter(|peeled| !peeled.is_empty()) {
+                Some(peeled) => Ref::Peeled {
+                    full_ref_name: name.into(),
+                    tag: id,
+                    object: oid(peeled),
+                },
+                None => Ref::Direct {
+                    full_ref_name: name.into(),
+                    object: id,
+                },
+            });
+        }
+        let main = refs
+            .iter()
+            .find_map(|r| match r {
+                Ref::Direct { full_ref_name, object } if full_ref_name == "refs/heads/main" => Some(*object),
+                _ => None,
+            })
+            .expect("main is present");
+        refs.insert(
+            0,
+            Ref::Symbolic {
+                full_ref_name: "HEAD".into(),
+                target: "refs/heads/main".into(),
+                tag: None,
+                object: main,
+            },
+        );
+        let mut objects = gix_odb::at_opts(
+            dir.join("repo/.git/objects"),
+            Vec::new(),
+            gix_odb::store::init::Options {
+                object_hash: gix_testtools::object_hash(),
+                ..Default::default()
+            },
+        )?
+        .into_arc()?;
+        objects.prevent_pack_unload();
+        Ok(Fixture { dir, refs, objects })
+    }
+
+    fn repo(&self) -> PathBuf {
+        self.dir.join("repo")
+    }
+
+    fn id(&self, rev: &str) -> ObjectId {
+        oid(self.git(&format!("rev-parse {rev}")).trim())
+    }
+
+    fn git(&self, args: &str) -> String {
+        gix_testtools::git(self.repo(), args).expect("git succeeds")
+    }
+
+    /// Return all objects listed by `git rev-list --objects` with `args`.
+    fn rev_list_objects(&self, args: &str) -> BTreeSet<ObjectId> {
+        self.git(&format!("rev-list --objects {args}"))
+            .lines()
+            .map(|line| oid(&line[..line.find(' ').unwrap_or(line.len())]))
+            .collect()
+    }
+
+    fn serve(&self, input: &[u8], options: Options) -> (Result<(), Error>, Response) {
+        let mut out = Vec::new();
+        let res = gix_protocol::upload_pack(
+            input,
+            &mut out,
+            Context {
+                refs: &self.refs,
+                objects: self.objects.clone(),
+                object_hash: self.objects.store_ref().object_hash(),
+            },
+            progress::Discard,
+            &AtomicBool::default(),
+            options,
+        );
+        (res, Response::from_bytes(&out))
+    }
+}
+
+fn oid(hex: &str) -> ObjectId {
+    ObjectId::from_hex(hex.as_bytes()).expect("valid hex")
+}
+
+/// Encode a request for `command` with `arguments`, optionally followed by more requests.
+fn request(command: &str, arguments: &[String]) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode::text_to_write(format!("command={command}").as_bytes(), &mut out).unwrap();
+    encode::text_to_write(b"agent=git/tests", &mut out).unwrap();
+    encode::delim_to_write(&mut out).unwrap();
+    for argument in arguments {
+        encode::text_to_write(argument.as_bytes(), &mut out).unwrap();
+    }
+    encode::flush_to_write(&mut out).unwrap();
+    out
+}
+
+fn args(arguments: &[&str]) -> Vec<String> {
+    arguments.iter().map(ToString::to_string).collect()
+}
+
+#[derive(Debug, Default)]
+struct Response {
+    /// All lines, with `0000` and `0001` for flush and delimiter packets.
+    lines: Vec<String>,
+    pack: Vec<u8>,
+    progress: Vec<String>,
+}
+
+impl Response {
+    fn from_bytes(data: &[u8]) -> Self {
+        let mut res = Response::default();
+        let mut lines = StreamingPeekableIter::new(data, &[], false);
+        let mut in_pack = false;
+        while let Some(line) = lines.read_line() {
+            let line = match line {
+                Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                line => line.expect("no IO error").expect("valid packetline"),
+            };
+            match line {
+                PacketLineRef::Data(data) if in_pack => match data[0] {
+                    1 => res.pack.extend_from_slice(&data[1..]),
+                    2 => res.progress.push(data[1..].to_str_lossy().into_owned()),
+                    band => unreachable!("unexpected band {band}"),
+                },
+                PacketLineRef::Data(data) => {
+                    in_pack = data == b"packfile\n";
+                    res.lines.push(data.trim_end().to_str_lossy().into_owned());
+                }
+                PacketLineRef::Flush => {
+                    in_pack = false;
+                    res.lines.push("0000".into());
+                }
+                PacketLineRef::Delimiter => res.lines.push("0001".into()),
+                PacketLineRef::ResponseEnd => res.lines.push("0002".into()),
+            }
+        }
+        res
+    }
+
+    /// Return all lines after the capability advertisement.
+    fn response_lines(&self) -> &[String] {
+        let end = self.lines.iter().position(|l| l == "0000").expect("advertisement");
+        &self.lines[end + 1..]
+    }
+
+    /// Index the received pack and return the ids of all objects in it.
+    fn pack_objects(&self, object_hash: gix_hash::Kind) -> gix_testtools::Result<BTreeSet<ObjectId>> {
+        let dir = gix_testtools::tempfile::TempDir::new()?;
+        let outcome = gix_pack::Bundle::write_to_directory(
+            &mut self.pack.as_slice(),
+            Some(dir.path()),
+            &mut progress::Discard,
+            &AtomicBool::default(),
+            None::<gix_object::find::Never>,
+            gix_pack::bundle::write::Options {
+                object_hash,
+                ..Default::default()
+            },
+        )?;
+        let index = gix_pack::index::File::at(outcome.index_path.expect("written"), object_hash)?;
+        Ok(index.iter().map(|entry| entry.oid).collect())
+    }
+}
+
+#[test]
+fn capabilities_are_advertised_and_the_end_of_interaction_is_honored() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let (res, response) = fixture.serve(b"0000", Options::default());
+    res?;
+    let object_hash = fixture.objects.store_ref().object_hash();
+    assert_eq!(
+        response.lines,
+        [
+            "version 2".to_string(),
+            format!(
+                "agent={}",
+                gix_protocol::agent(concat!("gix/", env!("CARGO_PKG_VERSION")))
+            ),
+            "ls-refs=unborn".into(),
+            "fetch=shallow filter".into(),
+            "server-option".into(),
+            format!("object-format={object_hash}"),
+            "0000".into(),
+        ]
+    );
+
+    let (res, response) = fixture.serve(b"", Options::default());
+    res?;
+    assert_eq!(response.lines.len(), 7, "EOF also ends the interaction");
+    Ok(())
+}
+
+#[test]
+fn bundle_uri_is_only_served_with_a_bundle_list() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let list = gix_protocol::bundle_uri::List {
+        heuristic: Some(gix_protocol::bundle_uri::Heuristic::CreationToken),
+        bundles: vec![gix_protocol::bundle_uri::Bundle {
+            id: "base".into(),
+            uri: "https://cdn.example.com/base.bundle".into(),
+            creation_token: Some(1),
+        }],
+        ..Default::default()
+    };
+    let (res, response) = fixture.serve(
+        &request("bundle-uri", &[]),
+        Options {
+            bundle_list: Some(list.clone()),
+            ..Default::default()
+        },
+    );
+    res?;
+    assert_eq!(response.lines[6], "bundle-uri", "advertised last");
+    assert_eq!(
+        response.response_lines(),
+        [
+            "bundle.version=1",
+            "bundle.mode=all",
+            "bundle.heuristic=creationToken",
+            "bundle.base.uri=https://cdn.example.com/base.bundle",
+            "bundle.base.creationToken=1",
+            "0000",
+        ]
+    );
+
+    let (res, response) = fixture.serve(&request("bundle-uri", &[]), Options::default());
+    assert!(matches!(res, Err(Error::UnknownCommand { .. })));
+    assert!(
+        !response.lines.iter().any(|line| line == "bundle-uri"),
+        "not advertised without a list"
+    );
+    Ok(())
+}
+
+#[test]
+fn ls_refs() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let (main, v1, c1) = (fixture.id("main"), fixture.id("v1"), fixture.id("v1^{}"));
+    let mut input = request("ls-refs", &[]);
+    input.extend(request(
+        "ls-refs",
+        &args(&["symrefs", "peel", "unborn", "ref-prefix HEAD", "ref-prefix refs/tags/"]),
+    ));
+    let (res, response) = fixture.serve(&input, Options::default());
+    res?;
+    assert_eq!(
+        response.response_lines(),
+        [
+            format!("{main} HEAD"),
+            format!("{} refs/heads/dev", fixture.id("dev")),
+            format!("{main} refs/heads/main"),
+            format!("{} refs/tags/light", fixture.id("light")),
+            format!("{v1} refs/tags/v1"),
+            "0000".into(),
+            format!("{main} HEAD symref-target:refs/heads/main"),
+            format!("{} refs/tags/light", fixture.id("light")),
+            format!("{v1} refs/tags/v1 peeled:{c1}"),
+            "0000".into(),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn ls_refs_with_unborn_head() -> gix_testtools::Result {
+    let mut fixture = Fixture::new()?;
+    fixture.refs = vec![Ref::Unborn {
+        full_ref_name: "HEAD".into(),
+        target: "refs/heads/main".into(),
+    }];
+    let mut input = request("ls-refs", &args(&["symrefs"]));
+    input.extend(request("ls-refs", &args(&["symrefs", "unborn"])));
+    let (res, response) = fixture.serve(&input, Options::default());
+    res?;
+    assert_eq!(
+        response.response_lines(),
+        ["0000", "unborn HEAD symref-target:refs/heads/main", "0000"],
+        "unborn refs are only listed if the client asks for them"
+    );
+    Ok(())
+}
+
+#[test]
+fn fetch_everything() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let object_hash = fixture.objects.store_ref().object_hash();
+    let main = fixture.id("main");
+    let (res, response) = fixture.serve(
+        &request("fetch", &[format!("want {main}"), "ofs-delta".into(), "done".into()]),
+        Options::default(),
+    );
+    res?;
+    assert_eq!(response.response_lines(), ["packfile", "0000"]);
+    assert_eq!(
+        response.pack_objects(object_hash)?,
+        fixture.rev_list_objects("main"),
+        "all objects reachable from main are sent"
+    );
+    assert_eq!(
+        response.progress,
+        [
+            "Enumerating objects: 13, done.\n",
+            "Total 13 (delta 0), reused 13 (delta 0)\n"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn fetch_with_negotiation() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let object_hash = fixture.objects.store_ref().object_hash();
+    let (main, dev, unknown) = (fixture.id("main"), fixture.id("dev"), object_hash.null());
+
+    let (res, response) = fixture.serve(
+        &request("fetch", &[format!("want {main}"), format!("have {unknown}")]),
+        Options::default(),
+    );
+    res?;
+    assert_eq!(
+        response.response_lines(),
+        ["acknowledgments", "NAK", "0000"],
+        "without common commits, the client has to continue the negotiation"
+    );
+
+    let (res, response) = fixture.serve(
+        &request(
+            "fetch",
+            &[
+                format!("want {main}"),
+                format!("have {unknown}"),
+                format!("have {dev}"),
+                "no-progress".into(),
+            ],
+        ),
+        Options::default(),
+    );
+    res?;
+    assert_eq!(
+        response.response_lines(),
+        [
+            "acknowledgments".to_string(),
+            format!("ACK {dev}"),
+            "ready".into(),
+            "0001".into(),
+            "packfile".into(),
+            "0000".into()
+        ]
+    );
+    assert!(response.progress.is_empty(), "progress is only sent if desired");
+    assert_eq!(
+        response.pack_objects(object_hash)?,
+        fixture.rev_list_objects("main --not dev"),
+        "only what's not reachable from common commits is sent"
+    );
+    Ok(())
+}
+
+#[test]
+fn fetch_with_include_tag() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let object_hash = fixture.objects.store_ref().object_hash();
+    let main = fixture.id("main");
+    let (res, response) = fixture.serve(
+        &request("fetch", &[format!("want {main}"), "include-tag".into(), "done".into()]),
+        Options::default(),
+    );
+    res?;
+    let mut expected = fixture.rev_list_objects("main");
+    expected.insert(fixture.id("v1"));
+    assert_eq!(
+        response.pack_objects(object_hash)?,
+        expected,
+        "annotated tags pointing to sent objects are included"
+    );
+    Ok(())
+}
+
+#[test]
+fn fetch_with_filter() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let object_hash = fixture.objects.store_ref().object_hash();
+    let main = fixture.id("main");
+    for (filter, rev_list_filter) in [
+        ("blob:none", "blob:none"),
+        ("blob:limit=1k", "blob:limit=1k"),
+        ("tree:0", "tree:0"),
+    ] {
+        let (res, response) = fixture.serve(
+            &request(
+                "fetch",
+                &[format!("want {main}"), format!("filter {filter}"), "done".into()],
+            ),
+            Options::default(),
+        );
+        res?;
+        assert_eq!(
+            response.pack_objects(object_hash)?,
+            fixture.rev_list_objects(&format!("--filter={rev_list_filter} main")),
+            "{filter}"
+        );
+    }
+
+    let big = fixture.id("main:big");
+    let (res, response) = fixture.serve(
+        &request(
+            "fetch",
+            &[format!("want {big}"), "filter blob:none".into(), "done".into()],
+        ),
+        Options {
+            allow_unadvertised_wants: true,
+            ..Default::default()
+        },
+    );
+    res?;
+    assert_eq!(
+        response.pack_objects(object_hash)?,
+        [big].into(),
+        "objects that are wanted explicitly are never filtered"
+    );
+    Ok(())
+}
+
+#[test]
+fn fetch_with_depth() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let object_hash = fixture.objects.store_ref().object_hash();
+    let (main, c2) = (fixture.id("main"), fixture.id("main~1"));
+    let (res, response) = fixture.serve(
+        &request("fetch", &[format!("want {main}"), "deepen 1".into(), "done".into()]),
+        Options::default(),
+    );
+    res?;
+    assert_eq!(
+        response.response_lines(),
+        [
+            "shallow-info".to_string(),
+            format!("shallow {main}"),
+            "0001".into(),
+            "packfile".into(),
+            "0000".into()
+        ]
+    );
+    assert_eq!(
+        response.pack_objects(object_hash)?,
+        fixture.rev_list_objects("--no-walk main"),
+        "the tip commit is sent with its whole tree"
+    );
+
+    let (res, response) = fixture.serve(
+        &request(
+            "fetch",
+            &[
+                format!("want {main}"),
+                format!("have {main}"),
+                format!("shallow {main}"),
+                "deepen 2".into(),
+                "done".into(),
+            ],
+        ),
+        Options::default(),
+    );
+    res?;
+    assert_eq!(
+        response.response_lines(),
+        [
+            "shallow-info".to_string(),
+            format!("shallow {c2}"),
+            format!("unshallow {main}"),
+            "0001".into(),
+            "packfile".into(),
+            "0000".into()
+        ],
+        "the previous shallow boundary is lifted"
+    );
+    assert!(response.pack_objects(object_hash)?.contains(&c2));
+
+    let (res, response) = fixture.serve(
+        &request(
+            "fetch",
+            &[format!("want {main}"), "deepen-not v1".into(), "done".into()],
+        ),
+        Options::default(),
+    );
+    res?;
+    assert_eq!(
+        response.response_lines()[..2],
+        ["shallow-info".to_string(), format!("shallow {c2}")],
+        "history reachable from the given reference is excluded"
+    );
+
+    let (res, response) = fixture.serve(
+        &request(
+            "fetch",
+            &[format!("want {main}"), "deepen-since 946771200".into(), "done".into()],
+        ),
+        Options::default(),
+    );
+    res?;
+    assert_eq!(
+        response.response_lines()[..2],
+        ["shallow-info".to_string(), format!("shallow {c2}")],
+        "commits older than 2000-01-02 are excluded"
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_requests_are_reported_to_the_client() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let object_hash = fixture.objects.store_ref().object_hash();
+    let blob = fixture.id("main:a");
+    let (res, response) = fixture.serve(
+        &request("fetch", &[format!("want {blob}"), "done".into()]),
+        Options::default(),
+    );
+    assert!(matches!(res, Err(Error::NotOurRef { id }) if id == blob));
+    assert_eq!(
+        response.response_lines(),
+        [format!("ERR upload-pack: not our ref {blob}")]
+    );
+
+    let (res, _) = fixture.serve(&request("push", &[]), Options::default());
+    assert!(matches!(res, Err(Error::UnknownCommand { .. })));
+
+    let (res, _) = fixture.serve(
+        &request(
+            "fetch",
+            &[format!("want {}", fixture.id("main")), "filter sparse:oid=main".into()],
+        ),
+        Options::default(),
+    );
+    assert!(matches!(res, Err(Error::Filter(_))));
+
+    let other_hash = gix_hash::Kind::all().iter().find(|kind| **kind != object_hash).copied();
+    if let Some(other_hash) = other_hash {
+        let mut input = Vec::new();
+        encode::text_to_write(b"command=ls-refs", &mut input)?;
+        encode::text_to_write(format!("object-format={other_hash}").as_bytes(), &mut input)?;
+        encode::flush_to_write(&mut input)?;
+        let (res, _) = fixture.serve(&input, Options::default());
+        assert!(matches!(res, Err(Error::ObjectFormat { .. })));
+    }
+    Ok(())
+}
+
+/// Run `git` with `args` in `cwd` against a `git daemon` emulated by the server, which handles a single connection.
+fn git_via_daemon(fixture: &Fixture, cwd: &Path, args: &str) -> gix_testtools::Result<String> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("git://127.0.0.1:{}/repo", listener.local_addr()?.port());
+    let server = std::thread::spawn({
+        let refs = fixture.refs.clone();
+        let objects = fixture.objects.clone();
+        move || -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            let (stream, _) = listener.accept()?;
+            let mut lines = StreamingPeekableIter::new(stream.try_clone()?, &[], false);
+            let request = lines.read_line().expect("daemon request")??;
+            assert!(
+                request.as_bstr().expect("data").starts_with(b"git-upload-pack /repo\0"),
+                "the daemon request is handled by the caller"
+            );
+            let object_hash = objects.store_ref().object_hash();
+            gix_protocol::upload_pack(
+                lines.into_inner(),
+                stream,
+                Context {
+                    refs: &refs,
+                    objects,
+                    object_hash,
+                },
+                progress::Discard,
+                &AtomicBool::default(),
+                Options::default(),
+            )?;
+            Ok(())
+        }
+    });
+    let out = gix_testtools::git(cwd, &format!("-c protocol.version=2 {}", args.replace("{url}", &url)))?;
+    server.join().expect("no panic")?;
+    Ok(out)
+}
+
+#[test]
+fn git_can_clone_and_fetch() -> gix_testtools::Result {
+    let fixture = Fixture::new()?;
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let cwd = tmp.path();
+
+    git_via_daemon(&fixture, cwd, "clone -q {url} full")?;
+    let full = cwd.join("full");
+    gix_testtools::git(&full, "fsck --strict")?;
+    assert_eq!(
+        oid(gix_testtools::git(&full, "rev-parse HEAD v1")?.lines().last().unwrap()),
+        fixture.id("v1"),
+        "tags are fetched along with branches"
+    );
+
+    git_via_daemon(&fixture, cwd, "clone -q --single-branch --branch dev {url} incremental")?;
+    let incremental = cwd.join("incremental");
+    git_via_daemon(&fixture, &incremental, "fetch -q {url} main")?;
+    gix_testtools::git(&incremental, "fsck --strict")?;
+    assert_eq!(
+        oid(gix_testtools::git(&incremental, "rev-parse FETCH_HEAD")?.trim()),
+        fixture.id("main")
+    );
+
+    git_via_daemon(&fixture, cwd, "clone -q --depth 1 {url} shallow")?;
+    let shallow = cwd.join("shallow");
+    let commit_count = || gix_testtools::git(&shallow, "rev-list --count HEAD");
+    assert_eq!(commit_count()?.trim(), "1");
+    git_via_daemon(&fixture, &shallow, "fetch -q --depth 2 {url} main")?;
+    gix_testtools::git(&shallow, "fsck")?;
+    assert_eq!(commit_count()?.trim(), "2", "the history was deepened");
+
+    git_via_daemon(&fixture, cwd, "clone -q --no-checkout --filter=blob:none {url} partial")?;
+    let partial = cwd.join("partial");
+    let missing = gix_testtools::git(&partial, "rev-list --objects --missing=print HEAD")?;
+    assert_eq!(
+        missing.lines().filter(|line| line.starts_with('?')).count(),
+        fixture.git("rev-list --objects main").lines().count()
+            - fixture
+                .git("rev-list --objects --filter=blob:none main")
+                .lines()
+                .count(),
+        "all blobs are omitted"
+    );
+    Ok(())
+}
@@ -1 +1,39 @@
+//! Rebase commits onto a new base, one by one, akin to `git rebase` with its *merge* backend.
+//!
+//! The workflow is as follows:
+//!
+//! * [`plan()`] the rebase to learn which commits to pick, and onto which commit.
+//! * [`merge::start()`] the rebase, which records [`ORIG_HEAD`](merge::ORIG_HEAD), detaches `HEAD` and persists
+//!   the [state](State) in `.git/rebase-merge`.
+//! * Optionally, create a [todo-list](todo::List) from the plan to let the user edit it, like `git rebase --interactive`.
+//! * [`merge::run()`] to execute one instruction after another until the rebase is done, until a conflict
+//!   stops it, or until an instruction [interrupts](merge::Outcome::Interrupted) it.
+//! * After conflicts were resolved, [`merge::resume()`] with the resolved tree, or [`merge::skip()`] the
+//!   conflicting commit, and [`merge::run()`] again.
+//! * Use [`merge::abort()`] to restore the state prior to the rebase.
+//!
+//! As the persisted state is compatible with what `git` writes, `git rebase --continue` can pick up where
+//! this crate left off, and vice versa.
+//!
+//! ### Deviation
+//!
+//! * Neither the index nor the worktree are touched, the caller is expected to check out the rebased commits
+//!   as needed, and to write conflicts into the index when a rebase stops.
+//! * Commits whose changes are already upstream are not detected upfront. Instead, they are dropped when
+//!   picking them doesn't change the tree.
+#![deny(missing_docs)]
 #![forbid(unsafe_code)]
+
+///
+pub mod plan;
+pub use plan::function::plan;
+
+///
+pub mod todo;
+
+///
+pub mod state;
+pub use state::State;
+
+///
+pub mod merge;
@@ -0,0 +1,765 @@
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_hash::ObjectId;
+use gix_object::FindExt;
+use gix_ref::{FullName, Target, store::ReferenceExt};
+use gix_sequencer::refs::{self, head_id, set_head};
+
+use crate::{
+    State,
+    merge::{Error, Interruption, LABEL_PREFIX, ORIG_HEAD, Options, Outcome, REBASE_HEAD},
+    plan::Plan,
+    state,
+    todo::{self, FixupMessage, Instruction},
+};
+
+/// Start a rebase of the commits in `plan`, where `head_name` is the name of the branch that is checked out, or `None` if
+/// `HEAD` is detached.
+///
+/// If `todo` is `None`, all commits of `plan` will be picked in order. Otherwise, the rebase is interactive and
+/// executes the instructions of the given todo-list, which typically was created with [`todo::List::from_plan()`]
+/// and then edited by the user.
+///
+/// This records `ORIG_HEAD`, detaches `HEAD` at [`onto`](Plan::onto) and writes the rebase state into the `git_dir` of `refs`.
+/// `objects` are used to obtain the summaries of the commits to pick, and `committer` is used for reflog entries
+/// which use `reflog_action` as prefix.
+///
+/// Use [`run()`] to execute the todo-list.
+pub fn start(
+    plan: &Plan,
+    todo: Option<todo::List>,
+    head_name: Option<FullName>,
+    refs: &gix_ref::store::Handle,
+    objects: &impl gix_object::Find,
+    committer: gix_actor::SignatureRef<'_>,
+    reflog_action: &BStr,
+) -> Result<State, Error> {
+    let git_dir = refs.git_dir();
+    if State::dir(git_dir).exists() {
+        return Err(Error::InProgress);
+    }
+
+    let interactive = todo.is_some();
+    let todo = match todo {
+        Some(todo) => todo,
+        None => todo::List::from_plan(plan, objects)?,
+    };
+    let state = State {
+        head_name,
+        onto: plan.onto,
+        orig_head: plan.orig_head,
+        todo,
+        done: todo::List::default(),
+        stopped_at: None,
+        rewritten: Vec::new(),
+        quiet: false,
+        interactive,
+    };
+    state.write(git_dir)?;
+
+    refs::edit(
+        refs,
+        [
+            refs::update(
+                ORIG_HEAD.try_into().expect("valid"),
+                Target::Object(plan.orig_head),
+                format!("{reflog_action} (start): updating ORIG_HEAD"),
+                false,
+            ),
+            refs::update(
+                "HEAD".try_into().expect("valid"),
+                Target::Object(plan.onto),
+                format!("{reflog_action} (start): checkout {}", plan.onto),
+                false,
+            ),
+        ],
+        committer,
+    )?;
+    Ok(state)
+}
+
+/// Execute the instructions of the rebase in `state` one by one, until all of them are done, until a conflict occurs,
+/// or until an instruction [interrupts](Outcome::Interrupted) the rebase.
+///
+/// Each commit is picked by merging its tree into the tree of `HEAD`, using the tree of its parent as merge-base.
+/// This is done using `diff_resource_cache` and `blob_merge` to compute and merge changes, and `objects` to read
+/// commits and write the merge result.
+/// Use `abbreviate_hash(id)` to shorten the given `id` for use in conflict markers.
+///
+/// Once all instructions are executed, the rebased branch is updated to point to the new `HEAD` which is made to point
+/// to the branch again, all labels are deleted, and the rebase state is removed.
+/// If a conflict stops the rebase, its state is written so that [`resume()`] or `git rebase --continue` can pick up from there.
+///
+/// Commits that start out empty are kept, while commits that become empty as their changes are already present are dropped,
+/// which also means that `reword` and `edit` don't interrupt the rebase for them.
+/// It's an error to call this function on a stopped rebase.
+///
+/// ### Deviation
+///
+/// * `squash` and `fixup -c` don't ask for the combined message to be edited, but combine the messages right away.
+///   Use [`reword()`] to change the message afterwards if needed.
+/// * `reset` only accepts labels and full object ids.
+#[allow(clippy::too_many_arguments)]
+pub fn run<'objects>(
+    state: &mut State,
+    refs: &gix_ref::store::Handle,
+    objects: &'objects (impl gix_object::FindObjectOrHeader + gix_object::Write),
+    diff_resource_cache: &mut gix_diff::blob::Platform,
+    blob_merge: &mut gix_merge::blob::Platform,
+    abbreviate_hash: &mut dyn FnMut(&gix_hash::oid) -> String,
+    options: &Options,
+) -> Result<Outcome<'objects>, Error> {
+    if let Some(id) = state.stopped_at {
+        return Err(Error::Stopped { id });
+    }
+    let git_dir = refs.git_dir();
+    let mut time_buf = gix_date::parse::TimeBuf::default();
+    let mut ctx = Context {
+        refs,
+        objects,
+        diff_resource_cache,
+        blob_merge,
+        abbreviate_hash,
+        options,
+        committer: options.committer.to_ref(&mut time_buf),
+        tree_diff_state: Default::default(),
+        buf: Vec::new(),
+    };
+
+    while let Some(instruction) = state.todo.instructions.pop_front() {
+        state.done.instructions.push_back(instruction.clone());
+        state.write(git_dir)?;
+
+        match instruction {
+            Instruction::Pick(commit) | Instruction::Reword(commit) | Instruction::Edit(commit) => {
+                match ctx.pick(state, commit.id, Mode::Pick)? {
+                    Picked::Commit => {}
+                    // There is no commit to reword or edit, and `HEAD` still is the commit of a previous instruction.
+                    Picked::Dropped => continue,
+                    Picked::Stopped(tree_merge) => {
+                        return Ok(Outcome::Stopped {
+                            id: commit.id,
+                            tree_merge,
+                        });
+                    }
+                }
+                let head = head_id(refs, objects)?;
+                match state.done.instructions.back() {
+                    Some(Instruction::Reword(_)) => {
+                        return Ok(Outcome::Interrupted(Interruption::Reword { id: commit.id, head }));
+                    }
+                    Some(Instruction::Edit(_)) => {
+                        return Ok(Outcome::Interrupted(Interruption::Edit { id: commit.id, head }));
+                    }
+                    _ => {}
+                }
+            }
+            Instruction::Squash(commit) | Instruction::Fixup { commit, .. } => {
+                let mode = match state.done.instructions.back() {
+                    Some(Instruction::Fixup { message, .. }) => Mode::Fixup(*message),
+                    _ => Mode::Squash,
+                };
+                let previous = state.done.instructions.range(..state.done.instructions.len() - 1);
+                if !previous.into_iter().any(creates_commit) {
+                    return Err(Error::NothingToAmend {
+                        command: instruction_name(mode),
+                    });
+                }
+                if let Picked::Stopped(tree_merge) = ctx.pick(state, commit.id, mode)? {
+                    return Ok(Outcome::Stopped {
+                        id: commit.id,
+                        tree_merge,
+                    });
+                }
+            }
+            Instruction::Drop(_) | Instruction::Noop => {}
+            Instruction::Exec(command) => return Ok(Outcome::Interrupted(Interruption::Exec { command })),
+            Instruction::Break => return Ok(Outcome::Interrupted(Interruption::Break)),
+            Instruction::Label(label) => {
+                let head = head_id(refs, objects)?;
+                refs::edit(
+                    refs,
+                    Some(refs::update(
+                        label_name(label.as_ref())?,
+                        Target::Object(head),
+                        format!("{} (label): {label}", options.reflog_action),
+                        false,
+                    )),
+                    ctx.committer,
+                )?;
+            }
+            Instruction::Reset(label) => {
+                let id = resolve_label(refs, objects, label.as_ref())?;
+                set_head(
+                    refs,
+                    id,
+                    format!("{} (reset): '{label}'", options.reflog_action),
+                    ctx.committer,
+                )?;
+            }
+            Instruction::Merge { message, label, .. } => {
+                if let Some((id, tree_merge)) = ctx.merge(state, message.map(|message| message.id), label.as_ref())? {
+                    return Ok(Outcome::Stopped { id, tree_merge });
+                }
+            }
+        }
+    }
+
+    finish(state, refs, objects, options)
+}
+
+/// Resume a rebase in `state` that stopped due to conflicts by committing `tree` with the message and author of the commit
+/// the rebase stopped at, using `objects` to read and write commits and `refs` to update `HEAD`.
+///
+/// The message and author are read from the rebase state and thus can be adjusted before resuming, just like with `git`.
+/// If the rebase stopped while picking a commit and `tree` is the same as the tree of `HEAD`, no commit is created
+/// and the commit is dropped.
+/// If it stopped during a `squash` or `fixup`, `HEAD` is amended instead, and if it stopped during a `merge`,
+/// a merge commit is created.
+///
+/// Returns the id of the newly created commit, if there was one.
+/// Use [`run()`] to continue with the next instruction thereafter.
+pub fn resume(
+    state: &mut State,
+    refs: &gix_ref::store::Handle,
+    objects: &(impl gix_object::Find + gix_object::Write),
+    tree: ObjectId,
+    options: &Options,
+) -> Result<Option<ObjectId>, Error> {
+    let id = state.stopped_at.ok_or(Error::NotStopped)?;
+    let git_dir = refs.git_dir();
+    let mut buf = Vec::new();
+    let mut commit = objects.find_commit(&id, &mut buf)?.to_owned()?;
+    let head = head_id(refs, objects)?;
+    let head_commit = objects.find_commit(&head, &mut buf)?.to_owned()?;
+
+    let parents = match state.done.instructions.back() {
+        Some(Instruction::Squash(_) | Instruction::Fixup { .. }) => {
+            commit.author = head_commit.author.clone();
+            Some(head_commit.parents.to_vec())
+        }
+        Some(Instruction::Merge { .. }) => {
+            commit.author = options.committer.clone();
+            commit.extra_headers.clear();
+            Some(vec![head, id])
+        }
+        _ => (tree != head_commit.tree).then(|| vec![head]),
+    };
+    if let Some((message, author)) = state::read_stopped_commit(git_dir)? {
+        commit.message = message;
+        commit.author = author;
+    }
+
+    let new_commit = match parents {
+        Some(parents) => {
+            let summary = commit.message_summary();
+            let new_commit = write_rewritten_commit(objects, commit, tree, &parents, options)?;
+            let mut time_buf = gix_date::parse::TimeBuf::default();
+            set_head(
+                refs,
+                new_commit,
+                format!("{} (continue): {summary}", options.reflog_action),
+                options.committer.to_ref(&mut time_buf),
+            )?;
+            match state.done.instructions.back() {
+                Some(Instruction::Merge { message: None, .. }) => {}
+                Some(Instruction::Merge {
+                    message: Some(message), ..
+                }) => record_rewritten(state, None, message.id, new_commit),
+                Some(Instruction::Squash(_) | Instruction::Fixup { .. }) => {
+                    record_rewritten(state, Some(head), id, new_commit);
+                }
+                _ => record_rewritten(state, None, id, new_commit),
+            }
+            Some(new_commit)
+        }
+        None => None,
+    };
+
+    clear_stopped(state, refs)?;
+    Ok(new_commit)
+}
+
+/// Change the message of the commit `HEAD` points to in the rebase in `state` to `message`, typically after the
+/// rebase was [interrupted](Interruption::Reword) to reword a commit, using `objects` to read and write commits and
+/// `refs` to update `HEAD`.
+///
+/// Returns the id of the rewritten commit.
+pub fn reword(
+    state: &mut State,
+    refs: &gix_ref::store::Handle,
+    objects: &(impl gix_object::Find + gix_object::Write),
+    message: &BStr,
+    options: &Options,
+) -> Result<ObjectId, Error> {
+    let head = head_id(refs, objects)?;
+    let mut buf = Vec::new();
+    let mut commit = objects.find_commit(&head, &mut buf)?.to_owned()?;
+    commit.message = message.to_owned();
+    let summary = commit.message_summary();
+    let parents = commit.parents.clone();
+    let tree = commit.tree;
+    let new_commit = write_rewritten_commit(objects, commit, tree, &parents, options)?;
+    let mut time_buf = gix_date::parse::TimeBuf::default();
+    set_head(
+        refs,
+        new_commit,
+        format!("{} (reword): {summary}", options.reflog_action),
+        options.committer.to_ref(&mut time_buf),
+    )?;
+    for (_, rewritten) in state.rewritten.iter_mut().filter(|(_, rewritten)| *rewritten == head) {
+        *rewritten = new_commit;
+    }
+    state.write(refs.git_dir())?;
+    Ok(new_commit)
+}
+
+/// Skip the commit the rebase in `state` stopped at, so that [`run()`] can continue with the next commit,
+/// using `refs` to remove `REBASE_HEAD`.
+pub fn skip(state: &mut State, refs: &gix_ref::store::Handle) -> Result<(), Error> {
+    if state.stopped_at.is_none() {
+        return Err(Error::NotStopped);
+    }
+    clear_stopped(state, refs)
+}
+
+/// Abort the rebase in `state` by pointing `HEAD` back to the branch or commit it was at when the rebase started, using
+/// `committer` and `reflog_action` for the reflog entries, and remove all labels and the rebase state.
+///
+/// Note that the rebased branch itself is never changed before the rebase finishes, so it isn't touched here either.
+pub fn abort(
+    state: State,
+    refs: &gix_ref::store::Handle,
+    committer: gix_actor::SignatureRef<'_>,
+    reflog_action: &BStr,
+) -> Result<(), Error> {
+    let edit = match state.head_name {
+        Some(name) => {
+            let message = format!("{reflog_action} (abort): returning to {}", name.as_bstr());
+            refs::update(
+                "HEAD".try_into().expect("valid"),
+                Target::Symbolic(name),
+                message,
+                false,
+            )
+        }
+        None => refs::update(
+            "HEAD".try_into().expect("valid"),
+            Target::Object(state.orig_head),
+            format!("{reflog_action} (abort): returning to {}", state.orig_head),
+            false,
+        ),
+    };
+    refs::edit(refs, Some(edit), committer)?;
+    remove_rebase_head(refs)?;
+    remove_labels(refs)?;
+    State::remove(refs.git_dir()).map_err(Error::RemoveState)
+}
+
+/// The way a commit is applied.
+#[derive(Clone, Copy)]
+enum Mode {
+    /// Create a new commit on top of `HEAD`.
+    Pick,
+    /// Amend `HEAD` and combine both messages.
+    Squash,
+    /// Amend `HEAD`, using the message as configured.
+    Fixup(FixupMessage),
+}
+
+/// What happened when picking a commit.
+enum Picked<'objects> {
+    /// `HEAD` now points to the picked commit, either as it was fast-forwarded to it or as it was newly created.
+    Commit,
+    /// The commit was dropped as its changes are already present in `HEAD`, which wasn't changed.
+    Dropped,
+    /// The rebase stopped due to conflicts, which are contained in the outcome of the tree-merge.
+    Stopped(gix_merge::tree::Outcome<'objects>),
+}
+
+/// Everything needed to pick and merge commits.
+struct Context<'a, 'objects, Objects> {
+    refs: &'a gix_ref::store::Handle,
+    objects: &'objects Objects,
+    diff_resource_cache: &'a mut gix_diff::blob::Platform,
+    blob_merge: &'a mut gix_merge::blob::Platform,
+    abbreviate_hash: &'a mut dyn FnMut(&gix_hash::oid) -> String,
+    options: &'a Options,
+    committer: gix_actor::SignatureRef<'a>,
+    tree_diff_state: gix_diff::tree::State,
+    buf: Vec<u8>,
+}
+
+impl<'objects, Objects> Context<'_, 'objects, Objects>
+where
+    Objects: gix_object::FindObjectOrHeader + gix_object::Write,
+{
+    /// Apply the changes of the commit with `id` to `HEAD` as defined by `mode`, and return what happened.
+    fn pick(&mut self, state: &mut State, id: ObjectId, mode: Mode) -> Result<Picked<'objects>, Error> {
+        let (refs, objects, options) = (self.refs, self.objects, self.options);
+        let git_dir = refs.git_dir();
+        let head = head_id(refs, objects)?;
+        let commit = objects.find_commit(&id, &mut self.buf)?.to_owned()?;
+        let summary = commit.message_summary();
+        let parent = match commit.parents.as_slice() {
+            [] => None,
+            [parent] => Some(*parent),
+            _ => return Err(Error::MergeCommit { id }),
+        };
+        let reflog_message = format!("{} ({}): {summary}", options.reflog_action, instruction_name(mode));
+
+        if matches!(mode, Mode::Pick) && parent == Some(head) && !options.force_rebase {
+            set_head(refs, id, reflog_message, self.committer)?;
+            record_rewritten(state, None, id, id);
+            state.write(git_dir)?;
+            return Ok(Picked::Commit);
+        }
+
+        let base_tree = match parent {
+            Some(parent) => objects.find_commit(&parent, &mut self.buf)?.tree(),
+            None => ObjectId::empty_tree(id.kind()),
+        };
+        let head_commit = objects.find_commit(&head, &mut self.buf)?.to_owned()?;
+        let short_id = (self.abbreviate_hash)(&id);
+        let their_label: BString = format!("{short_id} ({summary})").into();
+        let ancestor_label: BString = format!("parent of {their_label}").into();
+        let mut tree_merge = gix_merge::tree(
+            &base_tree,
+            &head_commit.tree,
+            &commit.tree,
+            gix_merge::blob::builtin_driver::text::Labels {
+                ancestor: Some(ancestor_label.as_ref()),
+                current: Some("HEAD".into()),
+                other: Some(their_label.as_ref()),
+            },
+            objects,
+            |buf| objects.write_buf(gix_object::Kind::Blob, buf),
+            &mut self.tree_diff_state,
+            self.diff_resource_cache,
+            self.blob_merge,
+            options.tree_merge.clone(),
+        )?;
+
+        let (message, author, parents) = match mode {
+            Mode::Pick => (commit.message.clone(), commit.author.clone(), vec![head]),
+            Mode::Squash | Mode::Fixup(_) => (
+                squash_message(head_commit.message.as_ref(), commit.message.as_ref(), mode),
+                head_commit.author.clone(),
+                head_commit.parents.to_vec(),
+            ),
+        };
+
+        if tree_merge.has_unresolved_conflicts(options.treat_as_unresolved) {
+            state.stopped_at = Some(id);
+            state.write(git_dir)?;
+            let mut author_time = gix_date::parse::TimeBuf::default();
+            state::write_stopped_commit(git_dir, message.as_ref(), author.to_ref(&mut author_time))?;
+            refs::edit(
+                refs,
+                Some(refs::update(
+                    REBASE_HEAD.try_into().expect("valid"),
+                    Target::Object(id),
+                    String::new(),
+                    false,
+                )),
+                self.committer,
+            )?;
+            return Ok(Picked::Stopped(tree_merge));
+        }
+
+        let tree = tree_merge.tree.write(|tree| objects.write(tree))?;
+        if matches!(mode, Mode::Pick) && tree == head_commit.tree && commit.tree != base_tree {
+            // Its changes are already contained in `HEAD`, which is what it was rewritten into.
+            record_rewritten(state, None, id, head);
+            state.write(git_dir)?;
+            return Ok(Picked::Dropped);
+        }
+        let new_commit = write_rewritten_commit(
+            objects,
+            gix_object::Commit {
+                message,
+                author,
+                ..commit
+            },
+            tree,
+            &parents,
+            options,
+        )?;
+        set_head(refs, new_commit, reflog_message, self.committer)?;
+        let amended = (!matches!(mode, Mode::Pick)).then_some(head);
+        record_rewritten(state, amended, id, new_commit);
+        state.write(git_dir)?;
+        Ok(Picked::Commit)
+    }
+
+    /// Merge the commit with the given `label` into `HEAD`, using the message of `original` if set, and return the commit we
+    /// stopped at along with the outcome of the tree-merge if there were conflicts.
+    fn merge(
+        &mut self,
+        state: &mut State,
+        original: Option<ObjectId>,
+        label: &BStr,
+    ) -> Result<Option<(ObjectId, gix_merge::tree::Outcome<'objects>)>, Error> {
+        let (refs, objects, options) = (self.refs, self.objects, self.options);
+        let git_dir = refs.git_dir();
+        let head = head_id(refs, objects)?;
+        let other = resolve_label(refs, objects, label)?;
+        let original = original
+            .map(|id| Ok::<_, Error>((id, objects.find_commit(&id, &mut self.buf)?.to_owned()?)))
+            .transpose()?;
+        let (message, author) = match &original {
+            Some((_, commit)) => (commit.message.clone(), commit.author.clone()),
+            None => (format!("Merge branch '{label}'\n").into(), options.committer.clone()),
+        };
+        let summary = gix_object::commit::MessageRef::from_bytes(&message)
+            .summary()
+            .into_owned();
+        let reflog_message = format!("{} (merge): {summary}", options.reflog_action);
+
+        if let Some((id, commit)) = &original {
+            if !options.force_rebase && commit.parents.as_slice() == [head, other] {
+                set_head(refs, *id, reflog_message, self.committer)?;
+                return Ok(None);
+            }
+        }
+
+        let mut graph = gix_revwalk::Graph::new(objects, None);
+        let outcome = gix_merge::commit(
+            head,
+            other,
+            gix_merge::blob::builtin_driver::text::Labels {
+                ancestor: None,
+                current: Some("HEAD".into()),
+                other: Some(label),
+            },
+            &mut graph,
+            self.diff_resource_cache,
+            self.blob_merge,
+            objects,
+            self.abbreviate_hash,
+            gix_merge::commit::Options {
+                allow_missing_merge_base: false,
+                tree_merge: options.tree_merge.clone(),
+                use_first_merge_base: false,
+            },
+        )?;
+        let mut tree_merge = outcome.tree_merge;
+
+        if tree_merge.has_unresolved_conflicts(options.treat_as_unresolved) {
+            state.stopped_at = Some(other);
+            state.write(git_dir)?;
+            let mut author_time = gix_date::parse::TimeBuf::default();
+            state::write_stopped_commit(git_dir, message.as_ref(), author.to_ref(&mut author_time))?;
+            refs::edit(
+                refs,
+                Some(refs::update(
+                    REBASE_HEAD.try_into().expect("valid"),
+                    Target::Object(other),
+                    String::new(),
+                    false,
+                )),
+                self.committer,
+            )?;
+            return Ok(Some((other, tree_merge)));
+        }
+
+        let tree = tree_merge.tree.write(|tree| objects.write(tree))?;
+        let new_commit = write_rewritten_commit(
+            objects,
+            gix_object::Commit {
+                tree,
+                parents: Default::default(),
+                author,
+                committer: options.committer.clone(),
+                encoding: original.as_ref().and_then(|(_, commit)| commit.encoding.clone()),
+                message,
+                extra_headers: Vec::new(),
+            },
+            tree,
+            &[head, other],
+            options,
+        )?;
+        set_head(refs, new_commit, reflog_message, self.committer)?;
+        if let Some((id, _)) = original {
+            state.rewritten.push((id, new_commit));
+            state.write(git_dir)?;
+        }
+        Ok(None)
+    }
+}
+
+fn instruction_name(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Pick => "pick",
+        Mode::Squash => "squash",
+        Mode::Fixup(_) => "fixup",
+    }
+}
+
+/// Return `true` if `instruction` leaves a commit that can be amended by `squash` or `fixup`.
+fn creates_commit(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Pick(_)
+            | Instruction::Reword(_)
+            | Instruction::Edit(_)
+            | Instruction::Squash(_)
+            | Instruction::Fixup { .. }
+            | Instruction::Merge { .. }
+    )
+}
+
+/// Combine the message of the commit to amend, `head`, with the message of the commit that is melded into it.
+fn squash_message(head: &BStr, other: &BStr, mode: Mode) -> BString {
+    match mode {
+        Mode::Pick | Mode::Fixup(FixupMessage::Keep) => head.to_owned(),
+        Mode::Fixup(FixupMessage::Replace | FixupMessage::ReplaceAndEdit) => todo::strip_fixup_subject(other),
+        Mode::Squash => {
+            let mut out = head.trim_end().as_bstr().to_owned();
+            out.push_str("\n\n");
+            out.push_str(todo::strip_fixup_subject(other).trim_end());
+            out.push(b'\n');
+            out
+        }
+    }
+}
+
+/// Record that `original` was rewritten into `new`.
+///
+/// If `new` is an amended version of `amended`, all commits that were previously rewritten into `amended`
+/// are now rewritten into `new` as well.
+fn record_rewritten(state: &mut State, amended: Option<ObjectId>, original: ObjectId, new: ObjectId) {
+    if let Some(amended) = amended {
+        for (_, rewritten) in state
+            .rewritten
+            .iter_mut()
+            .filter(|(_, rewritten)| *rewritten == amended)
+        {
+            *rewritten = new;
+        }
+    }
+    state.rewritten.push((original, new));
+}
+
+fn finish<'objects>(
+    state: &mut State,
+    refs: &gix_ref::store::Handle,
+    objects: &impl gix_object::Find,
+    options: &Options,
+) -> Result<Outcome<'objects>, Error> {
+    let head = head_id(refs, objects)?;
+    let mut time_buf = gix_date::parse::TimeBuf::default();
+    let committer = options.committer.to_ref(&mut time_buf);
+    if let Some(name) = &state.head_name {
+        let action = &options.reflog_action;
+        refs::edit(
+            refs,
+            [
+                refs::update(
+                    name.clone(),
+                    Target::Object(head),
+                    format!("{action} (finish): {} onto {}", name.as_bstr(), state.onto),
+                    false,
+                ),
+                refs::update(
+                    "HEAD".try_into().expect("valid"),
+                    Target::Symbolic(name.clone()),
+                    format!("{action} (finish): returning to {}", name.as_bstr()),
+                    false,
+                ),
+            ],
+            committer,
+        )?;
+    }
+    remove_rebase_head(refs)?;
+    remove_labels(refs)?;
+    State::remove(refs.git_dir()).map_err(Error::RemoveState)?;
+    Ok(Outcome::Finished {
+        head,
+        rewritten: std::mem::take(&mut state.rewritten),
+    })
+}
+
+fn write_rewritten_commit(
+    objects: &impl gix_object::Write,
+    mut commit: gix_object::Commit,
+    tree: ObjectId,
+    parents: &[ObjectId],
+    options: &Options,
+) -> Result<ObjectId, Error> {
+    commit.tree = tree;
+    commit.parents = parents.into();
+    commit.committer = options.committer.clone();
+    // The signature would not match the rewritten commit anymore.
+    commit
+        .extra_headers
+        .retain(|(name, _)| name != "gpgsig" && name != "gpgsig-sha256");
+    Ok(objects.write(&commit)?)
+}
+
+fn clear_stopped(state: &mut State, refs: &gix_ref::store::Handle) -> Result<(), Error> {
+    let git_dir = refs.git_dir();
+    state.stopped_at = None;
+    state::remove_stopped_commit(git_dir)?;
+    remove_rebase_head(refs)?;
+    state.write(git_dir)?;
+    Ok(())
+}
+
+fn label_name(label: &BStr) -> Result<FullName, Error> {
+    let mut name = BString::from(LABEL_PREFIX);
+    name.push_str(label);
+    FullName::try_from(name).map_err(|source| Error::InvalidLabel {
+        source,
+        name: label.to_owned(),
+    })
+}
+
+/// Resolve `label` to the commit it was set to, or interpret it as full object id.
+fn resolve_label(
+    refs: &gix_ref::store::Handle,
+    objects: &impl gix_object::Find,
+    label: &BStr,
+) -> Result<ObjectId, Error> {
+    if let Ok(name) = label_name(label) {
+        if let Some(mut reference) = refs.try_find(&name)? {
+            return Ok(reference.peel_to_id(refs, objects)?);
+        }
+    }
+    ObjectId::from_hex(label).map_err(|_| Error::UnknownLabel { name: label.to_owned() })
+}
+
+fn remove_labels(refs: &gix_ref::store::Handle) -> Result<(), Error> {
+    let labels = refs
+        .iter()?
+        .prefixed(LABEL_PREFIX.try_into().expect("valid"))?
+        .map(|reference| reference.map(|reference| reference.name))
+        .collect::<Result<Vec<_>, _>>()?;
+    if labels.is_empty() {
+        return Ok(());
+    }
+    Ok(refs::edit(refs, labels.into_iter().map(refs::delete), None)?)
+}
+
+fn remove_rebase_head(refs: &gix_ref::store::Handle) -> Result<(), Error> {
+    if refs.try_find(REBASE_HEAD)?.is_none() {
+        return Ok(());
+    }
+    Ok(refs::edit(
+        refs,
+        Some(refs::delete(REBASE_HEAD.try_into().expect("valid"))),
+        None,
+    )?)
+}
+
+trait MessageSummary {
+    fn message_summary(&self) -> BString;
+}
+
+impl MessageSummary for gix_object::Commit {
+    fn message_summary(&self) -> BString {
+        gix_object::commit::MessageRef::from_bytes(&self.message)
+            .summary()
+            .as_bytes()
+            .into()
+    }
+}
@@ -0,0 +1,129 @@
+use bstr::BString;
+use gix_hash::ObjectId;
+
+pub use gix_sequencer::ORIG_HEAD;
+/// The name of the reference that points to the commit that is currently being picked when the rebase stopped.
+pub const REBASE_HEAD: &str = "REBASE_HEAD";
+
+/// The error returned by functions in the [merge](crate::merge) module.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("A rebase is already in progress")]
+    InProgress,
+    #[error("The rebase is stopped at {id} and needs to be resumed or skipped first")]
+    Stopped { id: ObjectId },
+    #[error("The rebase isn't stopped and there is nothing to resume")]
+    NotStopped,
+    #[error("Merge commit {id} can't be picked")]
+    MergeCommit { id: ObjectId },
+    #[error("Cannot '{command}' without a previous commit")]
+    NothingToAmend { command: &'static str },
+    #[error("Could not resolve label or commit '{name}'")]
+    UnknownLabel { name: BString },
+    #[error("Label '{name}' is not a valid reference name")]
+    InvalidLabel {
+        source: gix_validate::reference::name::Error,
+        name: BString,
+    },
+    #[error(transparent)]
+    MergeCommits(#[from] gix_merge::commit::Error),
+    #[error("Could not list labels")]
+    ListLabels(#[from] gix_ref::store::iter::Error),
+    #[error(transparent)]
+    ReadState(#[from] crate::state::read::Error),
+    #[error(transparent)]
+    WriteState(#[from] crate::state::write::Error),
+    #[error("Could not remove the rebase state directory")]
+    RemoveState(#[source] std::io::Error),
+    #[error(transparent)]
+    FindObject(#[from] gix_object::find::existing_object::Error),
+    #[error(transparent)]
+    DecodeCommit(#[from] gix_object::decode::Error),
+    #[error(transparent)]
+    WriteObject(#[from] gix_object::write::Error),
+    #[error(transparent)]
+    MergeTree(#[from] gix_merge::tree::Error),
+    #[error(transparent)]
+    FindReference(#[from] gix_ref::store::find::Error),
+    #[error(transparent)]
+    PeelReference(#[from] gix_ref::peel::to_id::Error),
+    #[error(transparent)]
+    Refs(#[from] gix_sequencer::refs::Error),
+}
+
+/// The prefix of references created by the `label` instruction, which are removed once the rebase finishes.
+pub const LABEL_PREFIX: &str = "refs/rewritten/";
+
+/// A way to configure [`run()`] and [`resume()`].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Options to define how the trees of picked commits should be merged.
+    pub tree_merge: gix_merge::tree::Options,
+    /// Determine which conflicts should stop the rebase.
+    pub treat_as_unresolved: gix_merge::tree::TreatAsUnresolved,
+    /// The committer to use for all picked commits, and for reflog entries.
+    pub committer: gix_actor::Signature,
+    /// If `true`, always create new commits, even if the commit to pick already has the current `HEAD` as parent,
+    /// like `git rebase --force-rebase`.
+    /// Otherwise, such commits are fast-forwarded to.
+    pub force_rebase: bool,
+    /// The prefix used in all reflog messages, like `GIT_REFLOG_ACTION`, typically `rebase`.
+    pub reflog_action: BString,
+}
+
+/// The result of [`run()`].
+pub enum Outcome<'a> {
+    /// All commits were picked, the rebased reference was updated and the rebase state was removed.
+    Finished {
+        /// The commit the rebased reference, or `HEAD` if it was detached, now points to.
+        head: ObjectId,
+        /// Pairs of `(original, rewritten)` commits, one for each commit that was picked.
+        rewritten: Vec<(ObjectId, ObjectId)>,
+    },
+    /// Picking a commit led to conflicts and the rebase stopped.
+    ///
+    /// Once the conflicts are resolved, [resume](resume()) the rebase, or [skip](skip()) the commit instead.
+    Stopped {
+        /// The commit whose changes couldn't be applied without conflicts.
+        id: ObjectId,
+        /// The outcome of the tree-merge, which can be used to write the conflicts into the index
+        /// and to obtain the partially merged tree.
+        tree_merge: gix_merge::tree::Outcome<'a>,
+    },
+    /// An instruction of the todo-list asks to give control back to the caller.
+    ///
+    /// Call [`run()`] again to continue with the next instruction.
+    Interrupted(Interruption),
+}
+
+/// The reason for [`Outcome::Interrupted`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Interruption {
+    /// A commit was picked due to an `edit` instruction, and it should be amended by the caller as needed.
+    Edit {
+        /// The commit that was picked.
+        id: ObjectId,
+        /// The commit `HEAD` points to now, which may be amended.
+        head: ObjectId,
+    },
+    /// A commit was picked due to a `reword` instruction, and its message should be changed with [`reword()`].
+    Reword {
+        /// The commit that was picked.
+        id: ObjectId,
+        /// The commit `HEAD` points to now, whose message should be changed.
+        head: ObjectId,
+    },
+    /// The shell command of an `exec` instruction should be run by the caller.
+    ///
+    /// If it fails, the caller may want to add the instruction back to the front of the todo-list, just like `git` does.
+    Exec {
+        /// The command to run.
+        command: BString,
+    },
+    /// A `break` instruction was encountered.
+    Break,
+}
+
+pub(super) mod function;
+pub use function::{abort, resume, reword, run, skip, start};
@@ -0,0 +1,41 @@
+use gix_hash::ObjectId;
+
+use crate::plan::{Error, Options, Plan};
+
+/// Determine which commits reachable from `head`, but not from `upstream`, to pick onto a new base, using `graph`
+/// to compute merge-bases and `objects` to traverse the commits to pick.
+///
+/// `options` control where the commits are placed, which is `upstream` by default.
+/// Merge commits are skipped, and the returned commits are ordered such that the oldest commit comes first.
+pub fn plan(
+    upstream: ObjectId,
+    head: ObjectId,
+    graph: &mut gix_revwalk::Graph<'_, '_, gix_revwalk::graph::Commit<gix_revision::merge_base::Flags>>,
+    objects: &impl gix_object::Find,
+    Options { onto, keep_base }: Options,
+) -> Result<Plan, Error> {
+    let onto = match (onto, keep_base) {
+        (Some(_), true) => return Err(Error::KeepBaseWithOnto),
+        (Some(onto), false) => onto,
+        (None, false) => upstream,
+        (None, true) => *gix_revision::merge_base(upstream, &[head], graph)?
+            .ok_or(Error::NoMergeBase { upstream, head })?
+            .first(),
+    };
+
+    let mut commits = Vec::new();
+    for info in gix_traverse::commit::Simple::new(Some(head), objects).hide(Some(upstream))? {
+        let info = info?;
+        if info.parent_ids.len() > 1 {
+            continue;
+        }
+        commits.push(info.id);
+    }
+    commits.reverse();
+
+    Ok(Plan {
+        onto,
+        orig_head: head,
+        commits,
+    })
+}
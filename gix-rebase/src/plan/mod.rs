@@ -0,0 +1,43 @@
+use gix_hash::ObjectId;
+
+/// The error returned by [`plan()`](crate::plan()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The options --keep-base and --onto cannot be used together")]
+    KeepBaseWithOnto,
+    #[error("Could not find a merge-base between {upstream} and {head} to keep as base")]
+    NoMergeBase { upstream: ObjectId, head: ObjectId },
+    #[error("Failed to obtain the merge base between upstream and head")]
+    MergeBase(#[from] gix_revision::merge_base::Error),
+    #[error("Failed to traverse the commits to rebase")]
+    Traverse(#[from] gix_traverse::commit::simple::Error),
+}
+
+/// A way to configure [`plan()`](crate::plan()).
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Options {
+    /// The commit to put the picked commits onto, like `git rebase --onto <onto>`.
+    /// If `None`, the commits are put onto `upstream` unless [`keep_base`](Self::keep_base) is set.
+    pub onto: Option<ObjectId>,
+    /// If `true`, rebase onto the merge-base of `upstream` and `head`, like `git rebase --keep-base`.
+    /// This is useful to rewrite the commits of a branch without changing where it forked off.
+    ///
+    /// It's an error to set this if [`onto`](Self::onto) is also set.
+    pub keep_base: bool,
+}
+
+/// The result of [`plan()`](crate::plan()), describing which commits to pick onto which base.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    /// The commit the first picked commit will be placed on.
+    pub onto: ObjectId,
+    /// The commit that `HEAD` pointed to before the rebase.
+    pub orig_head: ObjectId,
+    /// The commits to pick, in order, with the oldest commit first.
+    ///
+    /// Merge commits are never included, just like `git rebase` without `--rebase-merges` would do.
+    pub commits: Vec<ObjectId>,
+}
+
+pub(super) mod function;
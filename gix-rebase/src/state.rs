@@ -0,0 +1,385 @@
+//! Read and write the state of a rebase in progress from and to the `.git/rebase-merge` directory.
+//!
+//! The layout is the one used by `git`, so a rebase started here can be continued by `git`, and vice versa.
+use std::path::{Path, PathBuf};
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_hash::ObjectId;
+
+use crate::todo;
+
+/// The name of the directory inside of the `.git` directory which holds the state of a rebase in progress.
+pub const DIR_NAME: &str = "rebase-merge";
+
+/// The value stored in the `head-name` file if the rebase started from a detached `HEAD`.
+const DETACHED_HEAD: &str = "detached HEAD";
+
+/// The persisted state of a rebase in progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    /// The name of the reference that was checked out when the rebase started, or `None` if `HEAD` was detached.
+    pub head_name: Option<gix_ref::FullName>,
+    /// The commit onto which the commits are picked.
+    pub onto: ObjectId,
+    /// The commit `HEAD` pointed to when the rebase started.
+    pub orig_head: ObjectId,
+    /// The instructions which still have to be executed, in order.
+    pub todo: todo::List,
+    /// The instructions which were executed already, with the last one being the one executed most recently.
+    pub done: todo::List,
+    /// The commit we stopped at, usually due to a conflict, or `None` if the rebase isn't stopped.
+    pub stopped_at: Option<ObjectId>,
+    /// Pairs of `(original, rewritten)` commits, one for each commit that was picked so far.
+    pub rewritten: Vec<(ObjectId, ObjectId)>,
+    /// If `true`, `git` will keep its output to a minimum when continuing the rebase.
+    pub quiet: bool,
+    /// If `true`, the rebase was started with a todo-list that was edited by the user, like `git rebase --interactive`.
+    pub interactive: bool,
+}
+
+/// The error returned by [`State::read()`].
+pub mod read {
+    use std::path::PathBuf;
+
+    use bstr::BString;
+
+    /// The error returned by [`State::read()`](super::State::read()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read rebase state file at '{}'", path.display())]
+        Io { source: std::io::Error, path: PathBuf },
+        #[error("Could not parse object id in '{}'", path.display())]
+        Id {
+            source: gix_hash::decode::Error,
+            path: PathBuf,
+        },
+        #[error("The name of the rebased reference '{name}' is invalid")]
+        HeadName {
+            source: gix_validate::reference::name::Error,
+            name: BString,
+        },
+        #[error("Could not parse the todo-list at '{}'", path.display())]
+        Todo {
+            source: crate::todo::parse::Error,
+            path: PathBuf,
+        },
+        #[error("Could not parse line {line_number} in '{}': {line:?}", path.display())]
+        Line {
+            line_number: usize,
+            line: BString,
+            path: PathBuf,
+        },
+    }
+}
+
+/// The error returned by [`State::write()`].
+pub mod write {
+    use std::path::PathBuf;
+
+    /// The error returned by [`State::write()`](super::State::write()).
+    #[derive(Debug, thiserror::Error)]
+    #[error("Could not write rebase state file at '{}'", path.display())]
+    pub struct Error {
+        /// The underlying error.
+        pub source: std::io::Error,
+        /// The path we tried to write to.
+        pub path: PathBuf,
+    }
+}
+
+/// Lifecycle
+impl State {
+    /// Return the directory within `git_dir` which holds the state of a rebase in progress.
+    pub fn dir(git_dir: &Path) -> PathBuf {
+        git_dir.join(DIR_NAME)
+    }
+
+    /// Read the state of the rebase in progress from `git_dir`, or return `None` if there is no such rebase.
+    pub fn read(git_dir: &Path) -> Result<Option<Self>, read::Error> {
+        let dir = Self::dir(git_dir);
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+        let head_name = {
+            let name = read_trimmed(&dir.join("head-name"))?;
+            if name == DETACHED_HEAD {
+                None
+            } else {
+                Some(
+                    gix_ref::FullName::try_from(name.clone())
+                        .map_err(|source| read::Error::HeadName { source, name })?,
+                )
+            }
+        };
+        let stopped_sha = dir.join("stopped-sha");
+        Ok(Some(State {
+            head_name,
+            onto: read_id(&dir.join("onto"))?,
+            orig_head: read_id(&dir.join("orig-head"))?,
+            todo: read_todo(&dir.join("git-rebase-todo"))?,
+            done: read_todo(&dir.join("done"))?,
+            stopped_at: stopped_sha.is_file().then(|| read_id(&stopped_sha)).transpose()?,
+            rewritten: read_rewritten(&dir.join("rewritten-list"))?,
+            quiet: read_optional(&dir.join("quiet"))?.is_some_and(|quiet| quiet.trim() == b"t"),
+            interactive: dir.join("interactive").is_file(),
+        }))
+    }
+
+    /// Write all state into its directory within `git_dir`, creating it if needed.
+    ///
+    /// Note that the information about the commit we [stopped at](Self::stopped_at) is written as well,
+    /// or removed if unset.
+    pub fn write(&self, git_dir: &Path) -> Result<(), write::Error> {
+        let dir = Self::dir(git_dir);
+        std::fs::create_dir_all(&dir).map_err(|source| write::Error {
+            source,
+            path: dir.clone(),
+        })?;
+
+        let head_name = self
+            .head_name
+            .as_ref()
+            .map_or(DETACHED_HEAD.into(), |name| name.as_bstr().to_owned());
+        write_line(&dir.join("head-name"), head_name.as_ref())?;
+        write_line(&dir.join("onto"), self.onto.to_string().as_str().into())?;
+        write_line(&dir.join("orig-head"), self.orig_head.to_string().as_str().into())?;
+        write_file(&dir.join("quiet"), if self.quiet { b"t\n" } else { b"\n" })?;
+        // Tell `git` to drop commits that become empty, just like we do.
+        write_file(&dir.join("drop_redundant_commits"), b"")?;
+        if self.interactive {
+            write_file(&dir.join("interactive"), b"")?;
+        }
+        write_file(&dir.join("git-rebase-todo"), &self.todo.to_bstring())?;
+        write_file(&dir.join("done"), &self.done.to_bstring())?;
+        write_line(
+            &dir.join("msgnum"),
+            self.current_step_number().to_string().as_str().into(),
+        )?;
+        write_line(&dir.join("end"), self.total_steps().to_string().as_str().into())?;
+
+        let mut rewritten = BString::default();
+        for (original, new) in &self.rewritten {
+            rewritten.push_str(format!("{original} {new}\n"));
+        }
+        write_file(&dir.join("rewritten-list"), &rewritten)?;
+
+        let stopped_sha = dir.join("stopped-sha");
+        match self.stopped_at {
+            Some(id) => write_line(&stopped_sha, id.to_string().as_str().into())?,
+            None => remove_file_if_present(&stopped_sha)?,
+        }
+        Ok(())
+    }
+
+    /// Remove the state directory from `git_dir`, which concludes the rebase.
+    pub fn remove(git_dir: &Path) -> std::io::Result<()> {
+        match std::fs::remove_dir_all(Self::dir(git_dir)) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+}
+
+/// Utilities
+impl State {
+    /// Return the number of the instruction that is currently executed, starting at 1, just like `msgnum` in the state directory.
+    pub fn current_step_number(&self) -> usize {
+        self.done.instructions.len()
+    }
+
+    /// Return the total amount of instructions to execute, just like `end` in the state directory.
+    pub fn total_steps(&self) -> usize {
+        self.done.instructions.len() + self.todo.instructions.len()
+    }
+}
+
+/// Write the files describing the commit we stopped at, so that `git` can commit the result once conflicts are resolved.
+///
+/// `message` is the message of the commit to create, and `author` is the original author of the picked commit.
+pub(crate) fn write_stopped_commit(
+    git_dir: &Path,
+    message: &BStr,
+    author: gix_actor::SignatureRef<'_>,
+) -> Result<(), write::Error> {
+    let dir = State::dir(git_dir);
+    write_file(&dir.join("message"), message)?;
+    write_file(&dir.join("author-script"), &author_script::encode(author))
+}
+
+/// Read the message and author of the commit we stopped at, as previously written by
+/// [`write_stopped_commit()`] or by `git`.
+pub(crate) fn read_stopped_commit(git_dir: &Path) -> Result<Option<(BString, gix_actor::Signature)>, read::Error> {
+    let dir = State::dir(git_dir);
+    let Some(message) = read_optional(&dir.join("message"))? else {
+        return Ok(None);
+    };
+    let path = dir.join("author-script");
+    let Some(script) = read_optional(&path)? else {
+        return Ok(None);
+    };
+    let author = author_script::decode(script.as_ref()).ok_or(read::Error::Line {
+        line_number: 1,
+        line: script,
+        path,
+    })?;
+    Ok(Some((message, author)))
+}
+
+/// Remove the files describing the commit we stopped at.
+pub(crate) fn remove_stopped_commit(git_dir: &Path) -> Result<(), write::Error> {
+    let dir = State::dir(git_dir);
+    for name in ["message", "author-script"] {
+        remove_file_if_present(&dir.join(name))?;
+    }
+    Ok(())
+}
+
+/// The `author-script` file contains shell variable assignments for the author of the commit to create.
+pub mod author_script {
+    use bstr::{BStr, BString, ByteSlice, ByteVec};
+
+    /// Encode `author` into the shell script `git` expects, with all values quoted for use in a shell.
+    pub fn encode(author: gix_actor::SignatureRef<'_>) -> BString {
+        let mut out = BString::default();
+        for (key, value) in [
+            ("GIT_AUTHOR_NAME", author.name),
+            ("GIT_AUTHOR_EMAIL", author.email),
+            ("GIT_AUTHOR_DATE", format!("@{}", author.time.trim()).as_str().into()),
+        ] {
+            out.push_str(key);
+            out.push(b'=');
+            out.extend_from_slice(&gix_quote::single(value));
+            out.push(b'\n');
+        }
+        out
+    }
+
+    /// Decode the author previously encoded with [`encode()`] or written by `git`, or return `None` if it is malformed.
+    pub fn decode(script: &BStr) -> Option<gix_actor::Signature> {
+        let mut name = None;
+        let mut email = None;
+        let mut time = None;
+        for line in script.lines().filter(|line| !line.trim().is_empty()) {
+            let (key, value) = line.split_once_str(b"=")?;
+            let value = unquote(value.as_bstr())?;
+            match key {
+                b"GIT_AUTHOR_NAME" => name = Some(value),
+                b"GIT_AUTHOR_EMAIL" => email = Some(value),
+                b"GIT_AUTHOR_DATE" => {
+                    let value = value.to_str().ok()?;
+                    time = Some(gix_date::parse_header(value.strip_prefix('@').unwrap_or(value))?);
+                }
+                _ => return None,
+            }
+        }
+        Some(gix_actor::Signature {
+            name: name?,
+            email: email?,
+            time: time?,
+        })
+    }
+
+    /// Undo the quoting of [`gix_quote::single()`].
+    fn unquote(mut value: &BStr) -> Option<BString> {
+        let mut out = BString::default();
+        while !value.is_empty() {
+            if let Some(rest) = value.strip_prefix(b"'") {
+                let end = rest.find_byte(b'\'')?;
+                out.extend_from_slice(&rest[..end]);
+                value = rest[end + 1..].as_bstr();
+            } else if let Some(rest) = value.strip_prefix(b"\\") {
+                let (escaped, rest) = rest.split_first()?;
+                out.push(*escaped);
+                value = rest.as_bstr();
+            } else {
+                return None;
+            }
+        }
+        Some(out)
+    }
+}
+
+fn read_todo(path: &Path) -> Result<todo::List, read::Error> {
+    let Some(content) = read_optional(path)? else {
+        return Ok(todo::List::default());
+    };
+    todo::List::from_bytes(&content).map_err(|source| read::Error::Todo {
+        source,
+        path: path.to_owned(),
+    })
+}
+
+fn read_rewritten(path: &Path) -> Result<Vec<(ObjectId, ObjectId)>, read::Error> {
+    let Some(content) = read_optional(path)? else {
+        return Ok(Vec::new());
+    };
+    let mut out = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parse_error = || read::Error::Line {
+            line_number: line_number + 1,
+            line: line.into(),
+            path: path.to_owned(),
+        };
+        let mut tokens = line.split_str(b" ").map(ObjectId::from_hex);
+        match (tokens.next(), tokens.next()) {
+            (Some(Ok(original)), Some(Ok(new))) => out.push((original, new)),
+            _ => return Err(parse_error()),
+        }
+    }
+    Ok(out)
+}
+
+fn read_id(path: &Path) -> Result<ObjectId, read::Error> {
+    let hex = read_trimmed(path)?;
+    ObjectId::from_hex(&hex).map_err(|source| read::Error::Id {
+        source,
+        path: path.to_owned(),
+    })
+}
+
+fn read_trimmed(path: &Path) -> Result<BString, read::Error> {
+    std::fs::read(path)
+        .map(|content| content.trim().into())
+        .map_err(|source| read::Error::Io {
+            source,
+            path: path.to_owned(),
+        })
+}
+
+fn read_optional(path: &Path) -> Result<Option<BString>, read::Error> {
+    match std::fs::read(path) {
+        Ok(content) => Ok(Some(content.into())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(read::Error::Io {
+            source,
+            path: path.to_owned(),
+        }),
+    }
+}
+
+fn write_line(path: &Path, line: &BStr) -> Result<(), write::Error> {
+    let mut content = line.to_owned();
+    content.push(b'\n');
+    write_file(path, &content)
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<(), write::Error> {
+    std::fs::write(path, content).map_err(|source| write::Error {
+        source,
+        path: path.to_owned(),
+    })
+}
+
+fn remove_file_if_present(path: &Path) -> Result<(), write::Error> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(write::Error {
+            source: err,
+            path: path.to_owned(),
+        }),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,443 @@
+//! A typed model of the todo-list of an interactive rebase, as stored in `.git/rebase-merge/git-rebase-todo`.
+//!
+//! Lists can be [parsed](List::from_bytes()), [written](List::write_to()), [generated](List::from_plan()) and
+//! [rearranged](List::autosquash()) to apply `fixup!`, `squash!` and `amend!` commits to their targets.
+use std::collections::VecDeque;
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_hash::ObjectId;
+use gix_object::FindExt;
+
+/// A commit along with its summary, as referred to by instructions that pick commits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    /// The id of the commit.
+    pub id: ObjectId,
+    /// The summary of the commit for display, which is informational only.
+    pub summary: BString,
+}
+
+/// Determine which message to use when applying a `fixup` instruction.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixupMessage {
+    /// Keep the message of the commit that is fixed up, like `fixup <commit>`.
+    #[default]
+    Keep,
+    /// Use the message of the fixup commit instead, like `fixup -C <commit>`.
+    Replace,
+    /// Use the message of the fixup commit and let the user edit it, like `fixup -c <commit>`.
+    ReplaceAndEdit,
+}
+
+/// The commit whose message should be used for a `merge` instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeMessage {
+    /// The original merge commit whose message to use.
+    pub id: ObjectId,
+    /// If `true`, the user should be able to edit the message, like `merge -c <commit>`.
+    /// Otherwise the message is used as is, like `merge -C <commit>`.
+    pub edit: bool,
+}
+
+/// A single line of a todo-list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    /// Apply the changes of a commit.
+    Pick(Commit),
+    /// Apply the changes of a commit, then stop to let the user change its message.
+    Reword(Commit),
+    /// Apply the changes of a commit, then stop to let the user amend it.
+    Edit(Commit),
+    /// Meld the changes of a commit into the previous commit, and combine their messages.
+    Squash(Commit),
+    /// Meld the changes of a commit into the previous commit, with `message` determining which message to keep.
+    Fixup {
+        /// The commit to meld into the previous one.
+        commit: Commit,
+        /// Which message to use for the combined commit.
+        message: FixupMessage,
+    },
+    /// Remove a commit.
+    Drop(Commit),
+    /// Run the given shell command.
+    Exec(BString),
+    /// Stop to let the user do whatever they want before continuing.
+    Break,
+    /// Label the current `HEAD` with the given name.
+    Label(BString),
+    /// Reset `HEAD` to the given label or commit.
+    Reset(BString),
+    /// Create a merge commit between `HEAD` and the commit with the given `label`.
+    Merge {
+        /// The commit whose message to use, or `None` to use a generated message.
+        message: Option<MergeMessage>,
+        /// The label of the commit to merge.
+        label: BString,
+        /// The summary of the original merge commit for display, which is informational only.
+        summary: BString,
+    },
+    /// Do nothing, which is used to indicate that a list is intentionally empty.
+    Noop,
+}
+
+/// The error returned by [`List::from_bytes()`].
+pub mod parse {
+    use bstr::BString;
+
+    /// The error returned by [`List::from_bytes()`](super::List::from_bytes()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Unknown command '{command}' in line {line_number}")]
+        UnknownCommand { command: BString, line_number: usize },
+        #[error("Missing argument to '{command}' in line {line_number}")]
+        MissingArgument { command: BString, line_number: usize },
+        #[error("Could not resolve '{name}' to a commit in line {line_number}")]
+        UnresolvedCommit { name: BString, line_number: usize },
+    }
+}
+
+/// A list of instructions for an interactive rebase, in the order they should be executed.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct List {
+    /// The instructions to execute in order.
+    pub instructions: VecDeque<Instruction>,
+}
+
+/// Lifecycle
+impl List {
+    /// Parse `input` in the format of a `git-rebase-todo` file, which is expected to use full object ids.
+    ///
+    /// Empty lines and comments starting with `#` are ignored.
+    pub fn from_bytes(input: &[u8]) -> Result<Self, parse::Error> {
+        Self::from_bytes_resolving(input, |name| ObjectId::from_hex(name).ok())
+    }
+
+    /// Like [`from_bytes()`](Self::from_bytes()), but use `resolve(name)` to turn the object name of each commit into
+    /// a full object id, which is useful for lists that were edited by a user who used abbreviated ids.
+    pub fn from_bytes_resolving(
+        input: &[u8],
+        mut resolve: impl FnMut(&BStr) -> Option<ObjectId>,
+    ) -> Result<Self, parse::Error> {
+        let mut instructions = VecDeque::new();
+        for (line_number, line) in input.lines().enumerate() {
+            let line_number = line_number + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(b"#") {
+                continue;
+            }
+            let (command, args) = match line.find_byteset(b" \t") {
+                Some(pos) => (line[..pos].as_bstr(), line[pos..].trim_start().as_bstr()),
+                None => (line.as_bstr(), b"".as_bstr()),
+            };
+            let missing_argument = || parse::Error::MissingArgument {
+                command: command.to_owned(),
+                line_number,
+            };
+            let mut commit = |args: &BStr| -> Result<Commit, parse::Error> {
+                let (name, summary) = split_word(args);
+                if name.is_empty() {
+                    return Err(missing_argument());
+                }
+                let id = resolve(name).ok_or_else(|| parse::Error::UnresolvedCommit {
+                    name: name.to_owned(),
+                    line_number,
+                })?;
+                Ok(Commit {
+                    id,
+                    summary: summary.to_owned(),
+                })
+            };
+            let instruction = match command.as_bytes() {
+                b"pick" | b"p" => Instruction::Pick(commit(args)?),
+                b"reword" | b"r" => Instruction::Reword(commit(args)?),
+                b"edit" | b"e" => Instruction::Edit(commit(args)?),
+                b"squash" | b"s" => Instruction::Squash(commit(args)?),
+                b"fixup" | b"f" => {
+                    let (flag, rest) = split_word(args);
+                    let (message, args) = match flag.as_bytes() {
+                        b"-C" => (FixupMessage::Replace, rest),
+                        b"-c" => (FixupMessage::ReplaceAndEdit, rest),
+                        _ => (FixupMessage::Keep, args),
+                    };
+                    Instruction::Fixup {
+                        commit: commit(args)?,
+                        message,
+                    }
+                }
+                b"drop" | b"d" => Instruction::Drop(commit(args)?),
+                b"exec" | b"x" if !args.is_empty() => Instruction::Exec(args.to_owned()),
+                b"break" | b"b" => Instruction::Break,
+                b"label" | b"l" if !args.is_empty() => Instruction::Label(split_word(args).0.to_owned()),
+                b"reset" | b"t" if !args.is_empty() => Instruction::Reset(split_word(args).0.to_owned()),
+                b"merge" | b"m" => {
+                    let (flag, rest) = split_word(args);
+                    let (message, args) = match flag.as_bytes() {
+                        b"-C" | b"-c" => {
+                            let Commit { id, .. } = commit(rest)?;
+                            (Some(MergeMessage { id, edit: flag == "-c" }), split_word(rest).1)
+                        }
+                        _ => (None, args),
+                    };
+                    let (label, summary) = split_word(args);
+                    if label.is_empty() {
+                        return Err(missing_argument());
+                    }
+                    Instruction::Merge {
+                        message,
+                        label: label.to_owned(),
+                        summary: summary
+                            .strip_prefix(b"#")
+                            .map_or(summary, |summary| summary.trim_start().as_bstr())
+                            .to_owned(),
+                    }
+                }
+                b"noop" => Instruction::Noop,
+                b"exec" | b"x" | b"label" | b"l" | b"reset" | b"t" => return Err(missing_argument()),
+                _ => {
+                    return Err(parse::Error::UnknownCommand {
+                        command: command.to_owned(),
+                        line_number,
+                    });
+                }
+            };
+            instructions.push_back(instruction);
+        }
+        Ok(List { instructions })
+    }
+
+    /// Create a list that picks all commits of `plan` in order, using `objects` to obtain their summaries.
+    pub fn from_plan(
+        plan: &crate::plan::Plan,
+        objects: &impl gix_object::Find,
+    ) -> Result<Self, gix_object::find::existing_object::Error> {
+        let mut buf = Vec::new();
+        let mut instructions = VecDeque::with_capacity(plan.commits.len());
+        for id in &plan.commits {
+            let commit = objects.find_commit(id, &mut buf)?;
+            instructions.push_back(Instruction::Pick(Commit {
+                id: *id,
+                summary: commit.message().summary().into_owned(),
+            }));
+        }
+        Ok(List { instructions })
+    }
+}
+
+/// Serialization
+impl List {
+    /// Write all instructions to `out` in the format of a `git-rebase-todo` file, using full object ids.
+    pub fn write_to(&self, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        for instruction in &self.instructions {
+            instruction.write_to(out)?;
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Return all instructions serialized in the format of a `git-rebase-todo` file.
+    pub fn to_bstring(&self) -> BString {
+        let mut buf = Vec::new();
+        self.write_to(&mut buf).expect("writing to memory never fails");
+        buf.into()
+    }
+}
+
+/// Editing
+impl List {
+    /// Move commits whose summary starts with `fixup! `, `squash! ` or `amend! ` right after the commit they refer to,
+    /// and turn them into `fixup`, `squash` or `fixup -C` instructions respectively, just like `git rebase --autosquash`.
+    ///
+    /// A commit is referred to if its summary matches the rest of the summary, if its id starts with it, or if its
+    /// summary starts with it, in that order of preference.
+    /// Fixups of fixups are applied to the commit the first fixup refers to.
+    pub fn autosquash(&mut self) {
+        let instructions: Vec<_> = std::mem::take(&mut self.instructions).into();
+        let mut target_of = vec![None; instructions.len()];
+        let mut fixups_of: Vec<Vec<usize>> = vec![Vec::new(); instructions.len()];
+        let mut squash_commands = vec![None; instructions.len()];
+
+        for idx in 0..instructions.len() {
+            let Instruction::Pick(commit) = &instructions[idx] else {
+                continue;
+            };
+            let Some((message, subject)) = fixup_subject(commit.summary.as_ref()) else {
+                continue;
+            };
+            let candidates = || {
+                instructions[..idx]
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, instruction)| instruction.commit().map(|commit| (idx, commit)))
+            };
+            let target = candidates()
+                .find(|(_, commit)| commit.summary == subject)
+                .or_else(|| {
+                    (subject.len() >= 4 && !subject.contains(&b' '))
+                        .then(|| {
+                            candidates()
+                                .find(|(_, commit)| commit.id.to_hex().to_string().as_bytes().starts_with(subject))
+                        })
+                        .flatten()
+                })
+                .or_else(|| candidates().find(|(_, commit)| commit.summary.starts_with(subject)));
+            let Some((target, _)) = target else {
+                continue;
+            };
+            let root = target_of[target].unwrap_or(target);
+            target_of[idx] = Some(root);
+            fixups_of[root].push(idx);
+            squash_commands[idx] = Some(message);
+        }
+
+        let mut instructions: Vec<_> = instructions.into_iter().map(Some).collect();
+        for idx in 0..instructions.len() {
+            if target_of[idx].is_some() {
+                continue;
+            }
+            self.instructions
+                .push_back(instructions[idx].take().expect("each instruction is taken once"));
+            for &fixup in &fixups_of[idx] {
+                let Some(Instruction::Pick(commit)) = instructions[fixup].take() else {
+                    unreachable!("only picks are fixups")
+                };
+                self.instructions.push_back(match squash_commands[fixup] {
+                    Some(None) => Instruction::Squash(commit),
+                    Some(Some(message)) => Instruction::Fixup { commit, message },
+                    None => unreachable!("all fixups have a command"),
+                });
+            }
+        }
+    }
+}
+
+/// Access
+impl Instruction {
+    /// Return the commit this instruction refers to, if it picks, drops or melds a commit.
+    pub fn commit(&self) -> Option<&Commit> {
+        match self {
+            Instruction::Pick(commit)
+            | Instruction::Reword(commit)
+            | Instruction::Edit(commit)
+            | Instruction::Squash(commit)
+            | Instruction::Fixup { commit, .. }
+            | Instruction::Drop(commit) => Some(commit),
+            Instruction::Exec(_)
+            | Instruction::Break
+            | Instruction::Label(_)
+            | Instruction::Reset(_)
+            | Instruction::Merge { .. }
+            | Instruction::Noop => None,
+        }
+    }
+
+    /// Return the name of the command of this instruction, as used in the todo-list.
+    pub fn command_name(&self) -> &'static str {
+        match self {
+            Instruction::Pick(_) => "pick",
+            Instruction::Reword(_) => "reword",
+            Instruction::Edit(_) => "edit",
+            Instruction::Squash(_) => "squash",
+            Instruction::Fixup { .. } => "fixup",
+            Instruction::Drop(_) => "drop",
+            Instruction::Exec(_) => "exec",
+            Instruction::Break => "break",
+            Instruction::Label(_) => "label",
+            Instruction::Reset(_) => "reset",
+            Instruction::Merge { .. } => "merge",
+            Instruction::Noop => "noop",
+        }
+    }
+
+    /// Write this instruction to `out` as a single line without trailing newline.
+    pub fn write_to(&self, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        out.write_all(self.command_name().as_bytes())?;
+        let write_commit = |out: &mut dyn std::io::Write, commit: &Commit| -> std::io::Result<()> {
+            write!(out, " {}", commit.id)?;
+            if !commit.summary.is_empty() {
+                out.write_all(b" ")?;
+                out.write_all(&commit.summary)?;
+            }
+            Ok(())
+        };
+        match self {
+            Instruction::Pick(commit)
+            | Instruction::Reword(commit)
+            | Instruction::Edit(commit)
+            | Instruction::Squash(commit)
+            | Instruction::Drop(commit) => write_commit(out, commit),
+            Instruction::Fixup { commit, message } => {
+                match message {
+                    FixupMessage::Keep => {}
+                    FixupMessage::Replace => out.write_all(b" -C")?,
+                    FixupMessage::ReplaceAndEdit => out.write_all(b" -c")?,
+                }
+                write_commit(out, commit)
+            }
+            Instruction::Exec(arg) | Instruction::Label(arg) | Instruction::Reset(arg) => {
+                out.write_all(b" ")?;
+                out.write_all(arg)
+            }
+            Instruction::Merge {
+                message,
+                label,
+                summary,
+            } => {
+                if let Some(MergeMessage { id, edit }) = message {
+                    write!(out, " {} {id}", if *edit { "-c" } else { "-C" })?;
+                }
+                out.write_all(b" ")?;
+                out.write_all(label)?;
+                if !summary.is_empty() {
+                    out.write_all(b" # ")?;
+                    out.write_all(summary)?;
+                }
+                Ok(())
+            }
+            Instruction::Break | Instruction::Noop => Ok(()),
+        }
+    }
+}
+
+/// If `summary` marks a commit for autosquashing, return how to apply it and the subject that identifies its target.
+/// The returned message is `None` for `squash!`, or the way to handle the message for `fixup!` and `amend!`.
+fn fixup_subject(summary: &BStr) -> Option<(Option<FixupMessage>, &BStr)> {
+    let (message, mut subject) = strip_fixup_prefix(summary)?;
+    // Commits may be fixups of fixups, with the first prefix determining what to do.
+    while let Some((_, rest)) = strip_fixup_prefix(subject) {
+        subject = rest;
+    }
+    Some((message, subject))
+}
+
+fn strip_fixup_prefix(summary: &BStr) -> Option<(Option<FixupMessage>, &BStr)> {
+    if let Some(rest) = summary.strip_prefix(b"fixup! ") {
+        Some((Some(FixupMessage::Keep), rest.as_bstr()))
+    } else if let Some(rest) = summary.strip_prefix(b"amend! ") {
+        Some((Some(FixupMessage::Replace), rest.as_bstr()))
+    } else {
+        summary.strip_prefix(b"squash! ").map(|rest| (None, rest.as_bstr()))
+    }
+}
+
+/// Split the first whitespace-separated word off `input`, returning it along with the trimmed remainder.
+fn split_word(input: &BStr) -> (&BStr, &BStr) {
+    match input.find_byteset(b" \t") {
+        Some(pos) => (input[..pos].as_bstr(), input[pos..].trim_start().as_bstr()),
+        None => (input, b"".as_bstr()),
+    }
+}
+
+/// Remove the subject line of `message` if it starts with a `squash!` or `amend!` marker, along with the
+/// blank lines following it, as these are meant to be replaced when the commits are combined.
+pub(crate) fn strip_fixup_subject(message: &BStr) -> BString {
+    if !(message.starts_with(b"squash! ") || message.starts_with(b"amend! ")) {
+        return message.to_owned();
+    }
+    let body = message
+        .find_byte(b'\n')
+        .map_or(b"".as_bstr(), |pos| message[pos..].as_bstr());
+    let mut out = BString::default();
+    out.push_str(body.trim_start());
+    out
+}
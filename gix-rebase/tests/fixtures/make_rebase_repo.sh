@@ -0,0 +1,46 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git checkout -q -b main
+
+function commit() {
+  local file=${1:?first argument is the file}
+  local content=${2:?second argument is the content}
+  echo "$content" > "$file"
+  git add "$file"
+  git commit -q -m "$file: $content"
+}
+
+commit a 1
+commit b 1
+git branch fork-point
+
+git checkout -q -b side
+commit s 1
+
+git checkout -q -b topic fork-point
+commit c 1
+git merge -q --no-ff -m "merge side" side
+commit d 1
+
+git checkout -q -b conflicting fork-point
+commit b conflict
+commit f 1
+
+git checkout -q -b already-upstream fork-point
+commit g 1
+commit h 1
+
+git checkout -q -b autosquash fork-point
+commit i 1
+commit j 1
+echo 2 > i && git add i && git commit -q -m "fixup! i: 1"
+echo 2 > j && git add j && git commit -q -m "squash! j: 1" -m "more about j"
+
+git checkout -q main
+commit a 2
+commit b 2
+commit g 1
+
+git checkout -q topic
@@ -0,0 +1,279 @@
+use gix_object::FindExt;
+use gix_rebase::{
+    State,
+    merge::{self, Error, Interruption, Outcome},
+    plan,
+    todo::{Commit, Instruction, List},
+};
+
+use crate::{
+    Fixture,
+    merge::{options, run},
+    signature,
+};
+
+fn start(fixture: &Fixture, branch: &str, edit: impl FnOnce(&mut List)) -> crate::Result<State> {
+    let plan = gix_rebase::plan(
+        fixture.id("main"),
+        fixture.id(branch),
+        &mut fixture.graph(),
+        &fixture.odb,
+        plan::Options::default(),
+    )?;
+    let mut todo = List::from_plan(&plan, &fixture.odb)?;
+    edit(&mut todo);
+    Ok(merge::start(
+        &plan,
+        Some(todo),
+        Some(format!("refs/heads/{branch}").try_into()?),
+        &fixture.refs,
+        &fixture.odb,
+        signature().to_ref(&mut Default::default()),
+        "rebase".into(),
+    )?)
+}
+
+fn message(fixture: &Fixture, id: &gix_hash::ObjectId) -> String {
+    let mut buf = Vec::new();
+    fixture
+        .odb
+        .find_commit(id, &mut buf)
+        .expect("exists")
+        .message
+        .to_string()
+}
+
+fn pick(fixture: &Fixture, branch: &str) -> Commit {
+    let id = fixture.id(branch);
+    Commit {
+        id,
+        summary: fixture.summary(&id),
+    }
+}
+
+#[test]
+fn autosquash_melds_fixups_and_squashes() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let mut state = start(&fixture, "autosquash", List::autosquash)?;
+    assert!(state.interactive);
+    assert_eq!(
+        state
+            .todo
+            .instructions
+            .iter()
+            .map(Instruction::command_name)
+            .collect::<Vec<_>>(),
+        ["pick", "fixup", "pick", "squash"]
+    );
+
+    let Outcome::Finished { head, rewritten } = run(&fixture, &mut state)? else {
+        panic!("no conflicts")
+    };
+    assert_eq!(
+        fixture.first_parent_summaries(head),
+        ["j: 1", "i: 1", "g: 1", "b: 2", "a: 2", "b: 1", "a: 1"]
+    );
+    assert_eq!(
+        message(&fixture, &head),
+        "j: 1\n\nmore about j\n",
+        "the subject of squash! commits is removed"
+    );
+    assert_eq!(rewritten.len(), 4, "every original commit is accounted for");
+    assert_eq!(
+        rewritten.iter().filter(|(_, new)| *new == head).count(),
+        2,
+        "squashed commits map to the final commit"
+    );
+
+    let mut buf = Vec::new();
+    let tree = fixture.odb.find_commit(&head, &mut buf)?.tree();
+    let tree = fixture.odb.find_tree(&tree, &mut buf)?.to_owned();
+    let blob = |name: &str| {
+        tree.entries
+            .iter()
+            .find(|entry| entry.filename == name)
+            .map(|entry| entry.oid)
+            .expect("present")
+    };
+    let mut blob_buf = Vec::new();
+    for name in ["i", "j"] {
+        assert_eq!(fixture.odb.find_blob(&blob(name), &mut blob_buf)?.data, b"2\n");
+    }
+    Ok(())
+}
+
+#[test]
+fn reword_edit_exec_and_break_interrupt() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let mut state = start(&fixture, "autosquash", |todo| {
+        let commits: Vec<_> = todo
+            .instructions
+            .drain(..)
+            .map(|instruction| instruction.commit().cloned().expect("only picks"))
+            .collect();
+        todo.instructions = [
+            Instruction::Reword(commits[1].clone()),
+            Instruction::Exec("make test".into()),
+            Instruction::Break,
+            Instruction::Edit(commits[0].clone()),
+        ]
+        .into();
+    })?;
+
+    let Outcome::Interrupted(Interruption::Reword { head, .. }) = run(&fixture, &mut state)? else {
+        panic!("reword interrupts")
+    };
+    assert_eq!(fixture.summary(&head), "j: 1");
+    let reworded = merge::reword(
+        &mut state,
+        &fixture.refs,
+        &fixture.odb,
+        "j: reworded\n".into(),
+        &options(),
+    )?;
+    assert_eq!(fixture.id("HEAD"), reworded);
+    assert_eq!(state.rewritten.last().map(|(_, new)| *new), Some(reworded));
+
+    assert_eq!(
+        interruption(run(&fixture, &mut state)?),
+        Some(Interruption::Exec {
+            command: "make test".into()
+        })
+    );
+    assert_eq!(interruption(run(&fixture, &mut state)?), Some(Interruption::Break));
+    assert_eq!(
+        State::read(fixture.refs.git_dir())?.as_ref(),
+        Some(&state),
+        "the state is written when interrupted"
+    );
+    let Outcome::Interrupted(Interruption::Edit { id, head }) = run(&fixture, &mut state)? else {
+        panic!("edit interrupts")
+    };
+    assert_eq!(fixture.summary(&id), "i: 1");
+    assert_eq!(fixture.id("HEAD"), head);
+
+    let Outcome::Finished { head, .. } = run(&fixture, &mut state)? else {
+        panic!("done")
+    };
+    assert_eq!(
+        fixture.first_parent_summaries(head),
+        ["i: 1", "j: reworded", "g: 1", "b: 2", "a: 2", "b: 1", "a: 1"]
+    );
+    Ok(())
+}
+
+fn interruption(outcome: Outcome<'_>) -> Option<Interruption> {
+    match outcome {
+        Outcome::Interrupted(interruption) => Some(interruption),
+        Outcome::Finished { .. } | Outcome::Stopped { .. } => None,
+    }
+}
+
+#[test]
+fn labels_reset_and_merge() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (i, j) = {
+        let mut buf = Vec::new();
+        let squash = fixture.id("autosquash");
+        let fixup = fixture
+            .odb
+            .find_commit(&squash, &mut buf)?
+            .parents()
+            .next()
+            .expect("parent");
+        let j = fixture
+            .odb
+            .find_commit(&fixup, &mut buf)?
+            .parents()
+            .next()
+            .expect("parent");
+        let i = fixture.odb.find_commit(&j, &mut buf)?.parents().next().expect("parent");
+        (i, j)
+    };
+    let mut state = start(&fixture, "autosquash", |todo| {
+        todo.instructions = [
+            Instruction::Label("onto".into()),
+            Instruction::Pick(Commit {
+                id: i,
+                summary: "i: 1".into(),
+            }),
+            Instruction::Label("with-i".into()),
+            Instruction::Reset("onto".into()),
+            Instruction::Pick(Commit {
+                id: j,
+                summary: "j: 1".into(),
+            }),
+            Instruction::Merge {
+                message: None,
+                label: "with-i".into(),
+                summary: "".into(),
+            },
+        ]
+        .into();
+    })?;
+    assert!(fixture.refs.try_find("refs/rewritten/onto")?.is_none());
+
+    let Outcome::Finished { head, .. } = run(&fixture, &mut state)? else {
+        panic!("no conflicts")
+    };
+    assert_eq!(message(&fixture, &head), "Merge branch 'with-i'\n");
+    let mut buf = Vec::new();
+    let parents: Vec<_> = fixture.odb.find_commit(&head, &mut buf)?.parents().collect();
+    assert_eq!(parents.len(), 2);
+    assert_eq!(fixture.first_parent_summaries(parents[0])[..2], ["j: 1", "g: 1"]);
+    assert_eq!(fixture.first_parent_summaries(parents[1])[..2], ["i: 1", "g: 1"]);
+    assert_eq!(fixture.id("autosquash"), head);
+    assert!(
+        fixture.refs.try_find("refs/rewritten/with-i")?.is_none(),
+        "labels are removed when the rebase is done"
+    );
+    Ok(())
+}
+
+#[test]
+fn squash_needs_a_previous_commit() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let commit = pick(&fixture, "conflicting");
+    let mut state = start(&fixture, "conflicting", |todo| {
+        todo.instructions = [Instruction::Squash(commit)].into();
+    })?;
+    assert!(matches!(
+        run(&fixture, &mut state),
+        Err(err) if matches!(err.downcast_ref::<Error>(), Some(Error::NothingToAmend { command: "squash" }))
+    ));
+    Ok(())
+}
+
+#[test]
+fn commits_dropped_as_empty_do_not_interrupt() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let h = pick(&fixture, "already-upstream");
+    let g = {
+        let mut buf = Vec::new();
+        let id = fixture
+            .odb
+            .find_commit(&h.id, &mut buf)?
+            .parents()
+            .next()
+            .expect("parent");
+        Commit {
+            id,
+            summary: fixture.summary(&id),
+        }
+    };
+    let mut state = start(&fixture, "already-upstream", |todo| {
+        todo.instructions = [Instruction::Edit(g.clone()), Instruction::Reword(h.clone())].into();
+    })?;
+
+    let Outcome::Interrupted(Interruption::Reword { id, head }) = run(&fixture, &mut state)? else {
+        panic!("only the reword interrupts as the commit to edit is already upstream")
+    };
+    assert_eq!(id, h.id);
+    assert_eq!(fixture.first_parent_summaries(head)[..3], ["h: 1", "g: 1", "b: 2"]);
+    assert_eq!(
+        state.rewritten,
+        [(g.id, fixture.id("main")), (h.id, head)],
+        "the dropped commit is rewritten into the commit that already has its changes"
+    );
+    Ok(())
+}
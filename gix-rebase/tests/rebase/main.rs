@@ -0,0 +1,134 @@
+use std::path::Path;
+
+use gix_hash::ObjectId;
+use gix_object::{FindExt, bstr::BString};
+use gix_ref::store::ReferenceExt;
+
+pub use gix_testtools::Result;
+
+mod interactive;
+mod merge;
+mod plan;
+mod state;
+mod todo;
+
+struct Fixture {
+    _tmp: gix_testtools::tempfile::TempDir,
+    odb: gix_odb::Handle,
+    refs: gix_ref::store::Handle,
+    root: std::path::PathBuf,
+}
+
+impl Fixture {
+    fn new() -> Result<Self> {
+        let tmp = gix_testtools::scripted_fixture_writable("make_rebase_repo.sh")?;
+        let root = tmp.path().to_owned();
+        let git_dir = root.join(".git");
+        let object_hash = gix_testtools::object_hash();
+        let odb = gix_odb::at_opts(
+            git_dir.join("objects"),
+            None,
+            gix_odb::store::init::Options {
+                object_hash,
+                ..Default::default()
+            },
+        )?;
+        let refs = gix_ref::Store::at(
+            git_dir,
+            gix_ref::store::init::Options {
+                write_reflog: gix_ref::store::WriteReflog::Normal,
+                object_hash,
+                ..Default::default()
+            },
+        )?
+        .to_handle();
+        Ok(Fixture {
+            _tmp: tmp,
+            odb,
+            refs,
+            root,
+        })
+    }
+
+    fn id(&self, name: &str) -> ObjectId {
+        self.refs
+            .find(name)
+            .expect("reference exists")
+            .peel_to_id(&self.refs, &self.odb)
+            .expect("peelable")
+    }
+
+    fn summary(&self, id: &ObjectId) -> BString {
+        let mut buf = Vec::new();
+        self.odb
+            .find_commit(id, &mut buf)
+            .expect("commit exists")
+            .message()
+            .summary()
+            .into_owned()
+    }
+
+    /// Return the summaries of all commits reachable from `tip` along their first parent, starting at `tip`.
+    fn first_parent_summaries(&self, tip: ObjectId) -> Vec<BString> {
+        let mut buf = Vec::new();
+        let mut out = Vec::new();
+        let mut next = Some(tip);
+        while let Some(id) = next {
+            let commit = self.odb.find_commit(&id, &mut buf).expect("commit exists");
+            out.push(commit.message().summary().into_owned());
+            next = commit.parents().next();
+        }
+        out
+    }
+
+    fn graph(&self) -> gix_revwalk::Graph<'_, '_, gix_revwalk::graph::Commit<gix_revision::merge_base::Flags>> {
+        gix_revwalk::Graph::new(&self.odb, None)
+    }
+}
+
+fn new_diff_resource_cache(root: &Path) -> gix_diff::blob::Platform {
+    gix_diff::blob::Platform::new(
+        Default::default(),
+        gix_diff::blob::Pipeline::new(Default::default(), Default::default(), Vec::new(), Default::default()),
+        Default::default(),
+        gix_worktree::Stack::new(
+            root,
+            gix_worktree::stack::State::AttributesStack(gix_worktree::stack::state::Attributes::default()),
+            Default::default(),
+            Vec::new(),
+            Vec::new(),
+        ),
+    )
+}
+
+fn new_blob_merge_platform(root: &Path) -> gix_merge::blob::Platform {
+    let attributes = gix_worktree::Stack::new(
+        root,
+        gix_worktree::stack::State::AttributesStack(gix_worktree::stack::state::Attributes::new(
+            Default::default(),
+            None,
+            gix_worktree::stack::state::attributes::Source::IdMapping,
+            Default::default(),
+        )),
+        gix_worktree::glob::pattern::Case::Sensitive,
+        Vec::new(),
+        Vec::new(),
+    );
+    let filter =
+        gix_merge::blob::Pipeline::new(Default::default(), gix_filter::Pipeline::default(), Default::default());
+    gix_merge::blob::Platform::new(
+        filter,
+        gix_merge::blob::pipeline::Mode::ToGit,
+        attributes,
+        vec![],
+        Default::default(),
+    )
+}
+
+fn signature() -> gix_actor::Signature {
+    gix_actor::Signature {
+        name: "committer".into(),
+        email: "committer@example.com".into(),
+        time: gix_date::Time::new(1_700_000_000, 3600),
+    }
+}
@@ -0,0 +1,278 @@
+use gix_object::FindExt;
+use gix_rebase::{
+    State,
+    merge::{self, Error, Options, Outcome},
+    plan,
+};
+use gix_ref::{FullName, store::ReferenceExt};
+
+use crate::{Fixture, new_blob_merge_platform, new_diff_resource_cache, signature};
+
+pub(crate) fn options() -> Options {
+    Options {
+        tree_merge: Default::default(),
+        treat_as_unresolved: gix_merge::tree::TreatAsUnresolved::git(),
+        committer: signature(),
+        force_rebase: false,
+        reflog_action: "rebase".into(),
+    }
+}
+
+fn start(fixture: &Fixture, branch: &str) -> crate::Result<State> {
+    let plan = gix_rebase::plan(
+        fixture.id("main"),
+        fixture.id(branch),
+        &mut fixture.graph(),
+        &fixture.odb,
+        plan::Options::default(),
+    )?;
+    let head_name: FullName = format!("refs/heads/{branch}").try_into()?;
+    Ok(merge::start(
+        &plan,
+        None,
+        Some(head_name),
+        &fixture.refs,
+        &fixture.odb,
+        signature().to_ref(&mut Default::default()),
+        "rebase".into(),
+    )?)
+}
+
+pub(crate) fn run<'a>(fixture: &'a Fixture, state: &mut State) -> crate::Result<Outcome<'a>> {
+    Ok(merge::run(
+        state,
+        &fixture.refs,
+        &fixture.odb,
+        &mut new_diff_resource_cache(&fixture.root),
+        &mut new_blob_merge_platform(&fixture.root),
+        &mut |id| id.to_hex_with_len(7).to_string(),
+        &options(),
+    )?)
+}
+
+fn reflog_messages(fixture: &Fixture, name: &str) -> Vec<String> {
+    let reference = fixture.refs.find(name).expect("exists");
+    reference
+        .log_iter(&fixture.refs)
+        .all()
+        .expect("readable")
+        .expect("present")
+        .map(|line| line.expect("valid").message.to_string())
+        .collect()
+}
+
+#[test]
+fn linear_rebase_updates_branch_and_writes_orig_head() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let orig_topic = fixture.id("topic");
+    let mut state = start(&fixture, "topic")?;
+    assert!(State::dir(fixture.refs.git_dir()).is_dir());
+    assert_eq!(fixture.id("ORIG_HEAD"), orig_topic);
+    assert_eq!(fixture.id("HEAD"), fixture.id("main"), "HEAD is detached at onto");
+    assert_eq!(state.total_steps(), 3);
+
+    let Outcome::Finished { head, rewritten } = run(&fixture, &mut state)? else {
+        panic!("there are no conflicts")
+    };
+    assert_eq!(fixture.id("topic"), head, "the branch is updated");
+    let head_ref = fixture.refs.find("HEAD")?;
+    assert_eq!(
+        head_ref.target.try_name().map(|name| name.as_bstr().to_string()),
+        Some("refs/heads/topic".into()),
+        "HEAD points to the branch again"
+    );
+    assert_eq!(rewritten.len(), 3);
+    assert_eq!(
+        fixture.first_parent_summaries(head),
+        ["d: 1", "c: 1", "s: 1", "g: 1", "b: 2", "a: 2", "b: 1", "a: 1"]
+    );
+    assert!(!State::dir(fixture.refs.git_dir()).exists(), "the state is removed");
+
+    let mut buf = Vec::new();
+    let commit = fixture.odb.find_commit(&head, &mut buf)?;
+    assert_eq!(commit.committer()?.name, "committer");
+    assert_eq!(commit.author()?.name, "author", "the original author is kept");
+
+    let messages = reflog_messages(&fixture, "HEAD");
+    assert_eq!(
+        &messages[messages.len() - 4..],
+        [
+            "rebase (start): checkout ".to_owned() + &fixture.id("main").to_string(),
+            "rebase (pick): s: 1".into(),
+            "rebase (pick): c: 1".into(),
+            "rebase (pick): d: 1".into(),
+        ],
+        "symbolic ref changes aren't logged, so returning to the branch doesn't show"
+    );
+    assert_eq!(
+        reflog_messages(&fixture, "refs/heads/topic").last().map(String::as_str),
+        Some(format!("rebase (finish): refs/heads/topic onto {}", fixture.id("main")).as_str())
+    );
+    Ok(())
+}
+
+#[test]
+fn commits_that_become_empty_are_dropped() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let mut state = start(&fixture, "already-upstream")?;
+    let Outcome::Finished { head, rewritten } = run(&fixture, &mut state)? else {
+        panic!("there are no conflicts")
+    };
+    assert_eq!(
+        fixture.first_parent_summaries(head),
+        ["h: 1", "g: 1", "b: 2", "a: 2", "b: 1", "a: 1"],
+        "only one commit is picked, the other one is dropped"
+    );
+    assert_eq!(rewritten.len(), 2, "every original commit is accounted for");
+    assert_eq!(
+        rewritten[0].1,
+        fixture.id("main"),
+        "the dropped commit is rewritten into the one that already has its changes"
+    );
+    Ok(())
+}
+
+#[test]
+fn conflicts_stop_and_can_be_resumed() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let conflicting = fixture.id("conflicting");
+    let mut state = start(&fixture, "conflicting")?;
+
+    let stopped_at = match run(&fixture, &mut state)? {
+        Outcome::Stopped { id, tree_merge } => {
+            assert_eq!(tree_merge.conflicts.len(), 1);
+            id
+        }
+        Outcome::Finished { .. } | Outcome::Interrupted(_) => unreachable!("b conflicts"),
+    };
+    assert_eq!(fixture.summary(&stopped_at), "b: conflict");
+    assert_eq!(fixture.id("REBASE_HEAD"), stopped_at);
+    assert_eq!(fixture.id("conflicting"), conflicting, "the branch is untouched");
+
+    let dir = State::dir(fixture.refs.git_dir());
+    assert_eq!(std::fs::read_to_string(dir.join("message"))?, "b: conflict\n");
+    assert!(
+        std::fs::read_to_string(dir.join("author-script"))?.starts_with("GIT_AUTHOR_NAME='author'\n"),
+        "this allows git to continue the rebase"
+    );
+    assert_eq!(
+        State::read(fixture.refs.git_dir())?.as_ref(),
+        Some(&state),
+        "the state on disk is up to date"
+    );
+
+    assert!(matches!(
+        run(&fixture, &mut state),
+        Err(err) if matches!(err.downcast_ref::<Error>(), Some(Error::Stopped { .. }))
+    ));
+
+    let mut buf = Vec::new();
+    let their_tree = fixture.odb.find_commit(&stopped_at, &mut buf)?.tree();
+    let new_commit = merge::resume(&mut state, &fixture.refs, &fixture.odb, their_tree, &options())?
+        .expect("the tree differs from HEAD");
+    assert_eq!(fixture.id("HEAD"), new_commit);
+    assert!(
+        fixture.refs.try_find(merge::REBASE_HEAD)?.is_none(),
+        "REBASE_HEAD is removed"
+    );
+
+    let Outcome::Finished { head, rewritten } = run(&fixture, &mut state)? else {
+        panic!("no more conflicts")
+    };
+    assert_eq!(rewritten.len(), 2);
+    assert_eq!(
+        fixture.first_parent_summaries(head),
+        ["f: 1", "b: conflict", "g: 1", "b: 2", "a: 2", "b: 1", "a: 1"]
+    );
+    assert_eq!(
+        reflog_messages(&fixture, "HEAD")
+            .iter()
+            .rev()
+            .nth(1)
+            .map(String::as_str),
+        Some("rebase (continue): b: conflict")
+    );
+    Ok(())
+}
+
+#[test]
+fn conflicts_can_be_skipped() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let mut state = start(&fixture, "conflicting")?;
+    assert!(matches!(run(&fixture, &mut state)?, Outcome::Stopped { .. }));
+
+    merge::skip(&mut state, &fixture.refs)?;
+    assert!(matches!(merge::skip(&mut state, &fixture.refs), Err(Error::NotStopped)));
+    let Outcome::Finished { head, .. } = run(&fixture, &mut state)? else {
+        panic!("no more conflicts")
+    };
+    assert_eq!(
+        fixture.first_parent_summaries(head),
+        ["f: 1", "g: 1", "b: 2", "a: 2", "b: 1", "a: 1"]
+    );
+    Ok(())
+}
+
+#[test]
+fn abort_restores_head() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let conflicting = fixture.id("conflicting");
+    let mut state = start(&fixture, "conflicting")?;
+    assert!(matches!(run(&fixture, &mut state)?, Outcome::Stopped { .. }));
+    assert!(
+        matches!(start(&fixture, "conflicting"), Err(err) if matches!(err.downcast_ref::<Error>(), Some(Error::InProgress)))
+    );
+
+    merge::abort(
+        state,
+        &fixture.refs,
+        signature().to_ref(&mut Default::default()),
+        "rebase".into(),
+    )?;
+    assert_eq!(fixture.id("HEAD"), conflicting);
+    assert_eq!(
+        fixture
+            .refs
+            .find("HEAD")?
+            .target
+            .try_name()
+            .map(|name| name.as_bstr().to_string()),
+        Some("refs/heads/conflicting".into())
+    );
+    assert!(fixture.refs.try_find(merge::REBASE_HEAD)?.is_none());
+    assert_eq!(State::read(fixture.refs.git_dir())?, None);
+    Ok(())
+}
+
+#[test]
+fn up_to_date_commits_are_fast_forwarded() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let topic = fixture.id("topic");
+    let plan = gix_rebase::plan(
+        fixture.id("fork-point"),
+        fixture.id("conflicting"),
+        &mut fixture.graph(),
+        &fixture.odb,
+        plan::Options::default(),
+    )?;
+    let mut state = merge::start(
+        &plan,
+        None,
+        None,
+        &fixture.refs,
+        &fixture.odb,
+        signature().to_ref(&mut Default::default()),
+        "rebase".into(),
+    )?;
+    let Outcome::Finished { head, rewritten } = run(&fixture, &mut state)? else {
+        panic!("no conflicts")
+    };
+    assert_eq!(head, fixture.id("conflicting"), "nothing was rewritten");
+    assert_eq!(rewritten.len(), 2);
+    assert!(
+        rewritten.iter().all(|(original, new)| original == new),
+        "fast-forwarded commits are rewritten into themselves"
+    );
+    assert_eq!(fixture.id("topic"), topic, "detached rebases don't update branches");
+    Ok(())
+}
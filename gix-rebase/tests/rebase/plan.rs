@@ -0,0 +1,67 @@
+use gix_rebase::plan::{Error, Options};
+
+use crate::Fixture;
+
+#[test]
+fn commits_are_oldest_first_and_skip_merges() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (main, topic) = (fixture.id("main"), fixture.id("topic"));
+    let plan = gix_rebase::plan(main, topic, &mut fixture.graph(), &fixture.odb, Options::default())?;
+
+    assert_eq!(plan.onto, main, "upstream is used by default");
+    assert_eq!(plan.orig_head, topic);
+    let summaries: Vec<_> = plan.commits.iter().map(|id| fixture.summary(id)).collect();
+    assert_eq!(summaries, ["s: 1", "c: 1", "d: 1"]);
+    Ok(())
+}
+
+#[test]
+fn onto_and_keep_base() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (main, conflicting) = (fixture.id("main"), fixture.id("conflicting"));
+
+    let plan = gix_rebase::plan(
+        main,
+        conflicting,
+        &mut fixture.graph(),
+        &fixture.odb,
+        Options {
+            keep_base: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(plan.onto, fixture.id("fork-point"), "the merge-base is kept");
+    assert_eq!(plan.commits.len(), 2);
+
+    let topic = fixture.id("topic");
+    let plan = gix_rebase::plan(
+        main,
+        conflicting,
+        &mut fixture.graph(),
+        &fixture.odb,
+        Options {
+            onto: Some(topic),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(plan.onto, topic);
+    assert_eq!(
+        plan.commits.len(),
+        2,
+        "the commits to pick don't depend on where they go"
+    );
+
+    let err = gix_rebase::plan(
+        main,
+        conflicting,
+        &mut fixture.graph(),
+        &fixture.odb,
+        Options {
+            onto: Some(topic),
+            keep_base: true,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::KeepBaseWithOnto));
+    Ok(())
+}
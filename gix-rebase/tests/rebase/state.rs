@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+use gix_hash::ObjectId;
+use gix_rebase::{
+    State,
+    state::author_script,
+    todo::{Commit, Instruction, List},
+};
+
+fn id(hex_char: char) -> ObjectId {
+    let hex: String = std::iter::repeat_n(hex_char, gix_testtools::object_hash().len_in_hex()).collect();
+    ObjectId::from_hex(hex.as_bytes()).expect("valid hex")
+}
+
+#[test]
+fn write_and_read_round_trip() -> crate::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    assert_eq!(State::read(tmp.path())?, None, "no rebase in progress");
+
+    let mut state = State {
+        head_name: Some("refs/heads/topic".try_into()?),
+        onto: id('a'),
+        orig_head: id('b'),
+        todo: List {
+            instructions: VecDeque::from([Instruction::Pick(Commit {
+                id: id('c'),
+                summary: "third".into(),
+            })]),
+        },
+        done: List {
+            instructions: VecDeque::from([
+                Instruction::Pick(Commit {
+                    id: id('d'),
+                    summary: "first".into(),
+                }),
+                Instruction::Pick(Commit {
+                    id: id('e'),
+                    summary: "second with spaces".into(),
+                }),
+            ]),
+        },
+        stopped_at: Some(id('e')),
+        rewritten: vec![(id('d'), id('f'))],
+        quiet: true,
+        interactive: true,
+    };
+    state.write(tmp.path())?;
+
+    let dir = State::dir(tmp.path());
+    assert_eq!(std::fs::read_to_string(dir.join("msgnum"))?, "2\n");
+    assert_eq!(std::fs::read_to_string(dir.join("end"))?, "3\n");
+    assert_eq!(
+        std::fs::read_to_string(dir.join("git-rebase-todo"))?,
+        format!("pick {} third\n", id('c'))
+    );
+    assert!(dir.join("interactive").is_file());
+    assert_eq!(State::read(tmp.path())?.as_ref(), Some(&state));
+
+    state.head_name = None;
+    state.stopped_at = None;
+    state.write(tmp.path())?;
+    assert_eq!(std::fs::read_to_string(dir.join("head-name"))?, "detached HEAD\n");
+    assert!(
+        !dir.join("stopped-sha").exists(),
+        "the stopped commit is removed if unset"
+    );
+    assert_eq!(State::read(tmp.path())?, Some(state));
+
+    State::remove(tmp.path())?;
+    assert_eq!(State::read(tmp.path())?, None);
+    State::remove(tmp.path())?;
+    Ok(())
+}
+
+#[test]
+fn todo_lines_may_use_abbreviations_and_comments() -> crate::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let state = State {
+        head_name: None,
+        onto: id('a'),
+        orig_head: id('b'),
+        todo: List::default(),
+        done: List::default(),
+        stopped_at: None,
+        rewritten: Vec::new(),
+        quiet: false,
+        interactive: false,
+    };
+    state.write(tmp.path())?;
+    std::fs::write(
+        State::dir(tmp.path()).join("git-rebase-todo"),
+        format!("# a comment\n\np {} summary\n", id('c')),
+    )?;
+    let state = State::read(tmp.path())?.expect("present");
+    assert_eq!(
+        state.todo.instructions,
+        [Instruction::Pick(Commit {
+            id: id('c'),
+            summary: "summary".into()
+        })]
+    );
+    Ok(())
+}
+
+#[test]
+fn author_script_round_trip() {
+    let author = gix_actor::Signature {
+        name: "A 'quoted' Name!".into(),
+        email: "author@example.com".into(),
+        time: gix_date::Time::new(1_112_912_053, -7 * 3600),
+    };
+    let script = author_script::encode(author.to_ref(&mut Default::default()));
+    assert_eq!(
+        script,
+        "GIT_AUTHOR_NAME='A '\\''quoted'\\'' Name'\\!''\nGIT_AUTHOR_EMAIL='author@example.com'\nGIT_AUTHOR_DATE='@1112912053 -0700'\n"
+    );
+    assert_eq!(author_script::decode(script.as_ref()), Some(author));
+    assert_eq!(author_script::decode("GIT_AUTHOR_NAME=unquoted\n".into()), None);
+}
@@ -0,0 +1,164 @@
+use std::collections::VecDeque;
+
+use gix_hash::ObjectId;
+use gix_object::bstr::ByteSlice;
+use gix_rebase::todo::{Commit, FixupMessage, Instruction, List, MergeMessage, parse};
+
+fn id(hex_char: char) -> ObjectId {
+    let hex: String = std::iter::repeat_n(hex_char, gix_testtools::object_hash().len_in_hex()).collect();
+    ObjectId::from_hex(hex.as_bytes()).expect("valid hex")
+}
+
+fn commit(hex_char: char, summary: &str) -> Commit {
+    Commit {
+        id: id(hex_char),
+        summary: summary.into(),
+    }
+}
+
+fn pick(hex_char: char, summary: &str) -> Instruction {
+    Instruction::Pick(commit(hex_char, summary))
+}
+
+#[test]
+fn all_commands_round_trip() -> crate::Result {
+    let input = format!(
+        "# a comment\n\
+         \n\
+         p {a} first\n\
+         reword {b} second\n\
+         e {c}\n\
+         s {d} fourth\n\
+         f -C {e} fifth\n\
+         fixup -c {f} sixth\n\
+         drop {a} first\n\
+         x cargo test --workspace\n\
+         b\n\
+         l onto\n\
+         t onto\n\
+         m -C {b} topic # Merge branch 'topic'\n\
+         merge other\n\
+         noop\n",
+        a = id('a'),
+        b = id('b'),
+        c = id('c'),
+        d = id('d'),
+        e = id('e'),
+        f = id('f'),
+    );
+    let list = List::from_bytes(input.as_bytes())?;
+    assert_eq!(
+        list.instructions,
+        [
+            pick('a', "first"),
+            Instruction::Reword(commit('b', "second")),
+            Instruction::Edit(commit('c', "")),
+            Instruction::Squash(commit('d', "fourth")),
+            Instruction::Fixup {
+                commit: commit('e', "fifth"),
+                message: FixupMessage::Replace,
+            },
+            Instruction::Fixup {
+                commit: commit('f', "sixth"),
+                message: FixupMessage::ReplaceAndEdit,
+            },
+            Instruction::Drop(commit('a', "first")),
+            Instruction::Exec("cargo test --workspace".into()),
+            Instruction::Break,
+            Instruction::Label("onto".into()),
+            Instruction::Reset("onto".into()),
+            Instruction::Merge {
+                message: Some(MergeMessage {
+                    id: id('b'),
+                    edit: false
+                }),
+                label: "topic".into(),
+                summary: "Merge branch 'topic'".into(),
+            },
+            Instruction::Merge {
+                message: None,
+                label: "other".into(),
+                summary: "".into(),
+            },
+            Instruction::Noop,
+        ]
+    );
+
+    let serialized = list.to_bstring();
+    assert_eq!(
+        serialized.lines().next(),
+        Some(format!("pick {} first", id('a')).as_bytes()),
+        "commands are always written in full"
+    );
+    assert_eq!(List::from_bytes(&serialized)?, list);
+    Ok(())
+}
+
+#[test]
+fn abbreviated_ids_can_be_resolved() -> crate::Result {
+    let list = List::from_bytes_resolving(b"pick abc summary\n", |name| (name == "abc").then(|| id('a')))?;
+    assert_eq!(list.instructions, [pick('a', "summary")]);
+
+    let err = List::from_bytes(b"pick abc summary\n").unwrap_err();
+    assert!(matches!(err, parse::Error::UnresolvedCommit { line_number: 1, .. }));
+    Ok(())
+}
+
+#[test]
+fn invalid_lines_are_rejected() {
+    assert!(matches!(
+        List::from_bytes(b"\nfrobnicate abc\n"),
+        Err(parse::Error::UnknownCommand { line_number: 2, .. })
+    ));
+    for input in ["pick", "exec", "label  ", "merge -C"] {
+        assert!(
+            matches!(
+                List::from_bytes(input.as_bytes()),
+                Err(parse::Error::MissingArgument { line_number: 1, .. } | parse::Error::UnresolvedCommit { .. })
+            ),
+            "{input:?} lacks an argument"
+        );
+    }
+}
+
+#[test]
+fn autosquash_moves_fixups_after_their_target() {
+    let mut list = List {
+        instructions: VecDeque::from([
+            pick('a', "first"),
+            pick('b', "second"),
+            pick('c', "fixup! first"),
+            pick('d', "squash! second"),
+            pick('e', "amend! fixup! first"),
+            pick('f', &format!("fixup! {}", &id('b').to_string()[..7])),
+            pick('1', "fixup! seco"),
+            pick('2', "fixup! unknown"),
+        ]),
+    };
+    list.autosquash();
+    assert_eq!(
+        list.instructions,
+        [
+            pick('a', "first"),
+            Instruction::Fixup {
+                commit: commit('c', "fixup! first"),
+                message: FixupMessage::Keep,
+            },
+            Instruction::Fixup {
+                commit: commit('e', "amend! fixup! first"),
+                message: FixupMessage::Replace,
+            },
+            pick('b', "second"),
+            Instruction::Squash(commit('d', "squash! second")),
+            Instruction::Fixup {
+                commit: commit('f', &format!("fixup! {}", &id('b').to_string()[..7])),
+                message: FixupMessage::Keep,
+            },
+            Instruction::Fixup {
+                commit: commit('1', "fixup! seco"),
+                message: FixupMessage::Keep,
+            },
+            pick('2', "fixup! unknown"),
+        ]
+    );
+}
@@ -14,6 +14,11 @@
 //!     * one reference maps to a file on disk
 //!   * **packed**
 //!     * references are stored in a single human-readable file, along with their targets if they are symbolic.
+//! * **reftable**
+//!   * references and their logs are stored in a stack of binary tables, accessible with `reftable::Store`
+//!     if the `reftable` feature is enabled.
+//!
+//! The [`Store`] abstracts over both, and is opened according to [`store::Backend`].
 //!
 //! ## Feature Flags
 #![cfg_attr(
@@ -29,6 +34,8 @@ use gix_object::bstr::{BStr, BString};
 
 #[path = "store/mod.rs"]
 mod store_impl;
+#[cfg(feature = "reftable")]
+pub use store_impl::reftable;
 pub use store_impl::{file, packed};
 
 mod fullname;
@@ -70,8 +77,24 @@ pub mod store {
             /// to avoid side effects. This only needs to be `true` on Windows, but can be `true` on other platforms
             /// if they need to remain compatible with Windows.
             pub prohibit_windows_device_names: bool,
+            /// The way references are stored, the equivalent of `extensions.refStorage`.
+            pub backend: super::Backend,
         }
     }
+
+    /// The storage format of references in a repository.
+    #[derive(Default, Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
+    pub enum Backend {
+        /// References are stored as loose files and in `packed-refs`, with reflogs in `logs/`.
+        #[default]
+        Files,
+        /// References and reflogs are stored in a stack of tables in the `reftable/` directory.
+        ///
+        /// Note that `FETCH_HEAD` and `MERGE_HEAD` are still stored as files as they can carry more than one value.
+        /// The [`Store`](crate::Store) can only be opened with this backend if the `reftable` feature is enabled.
+        Reftable,
+    }
+
     /// The way a file store handles the reflog
     #[derive(Default, Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
     pub enum WriteReflog {
@@ -85,33 +108,40 @@ pub mod store {
     }
 
     /// A thread-local handle for interacting with a [`Store`][crate::Store] to find and iterate references.
-    #[derive(Clone)]
-    #[allow(dead_code)]
-    pub(crate) struct Handle {
+    #[derive(Debug, Clone)]
+    pub struct Handle {
         /// A way to access shared state with the requirement that interior mutability doesn't leak or is incorporated into error types
         /// if it could. The latter can't happen if references to said internal aren't ever returned.
         state: handle::State,
     }
 
-    #[allow(dead_code)]
+    #[derive(Clone)]
     pub(crate) enum State {
-        Loose { store: file::Store },
+        Loose {
+            store: file::Store,
+        },
+        #[cfg(feature = "reftable")]
+        Reftable {
+            store: crate::reftable::Store,
+        },
     }
 
-    pub(crate) mod general;
+    ///
+    pub mod general;
 
     ///
     #[path = "general/handle/mod.rs"]
     mod handle;
-    pub use handle::find;
+    pub use handle::{ReferenceExt, find, iter, reflog, transaction};
 
     use crate::file;
 }
 
-/// The git reference store.
-/// TODO: Figure out if handles are needed at all, which depends on the ref-table implementation.
-#[allow(dead_code)]
-pub(crate) struct Store {
+/// The git reference store, independent of the way references are actually stored.
+///
+/// Use [handles](store::Handle) to find and iterate references, to read reflogs or to edit references in transactions.
+#[derive(Clone)]
+pub struct Store {
     inner: store::State,
 }
 
@@ -32,7 +32,8 @@ mod init {
     impl file::Store {
         /// Create a new instance at the given `git_dir`, which commonly is a standard git repository with a
         /// `refs/` subdirectory.
-        /// Use [`Options`](crate::store::init::Options) to adjust settings.
+        /// Use [`Options`](crate::store::init::Options) to adjust settings, whose [`backend`](crate::store::init::Options::backend)
+        /// is ignored as references are always stored in files.
         ///
         /// Note that if [`precompose_unicode`](crate::store::init::Options::precompose_unicode) is set in the options,
         /// the `git_dir` is also expected to use precomposed unicode, or else some operations that strip prefixes will fail.
@@ -43,6 +44,7 @@ mod init {
                 object_hash,
                 precompose_unicode,
                 prohibit_windows_device_names,
+                backend: _,
             }: crate::store::init::Options,
         ) -> Self {
             file::Store {
@@ -71,6 +73,7 @@ mod init {
                 object_hash,
                 precompose_unicode,
                 prohibit_windows_device_names,
+                backend: _,
             }: crate::store::init::Options,
         ) -> Self {
             file::Store {
@@ -16,7 +16,7 @@ pub struct Store {
     common_dir: Option<PathBuf>,
     /// The kind of hash to assume in a couple of situations. Note that currently we are able to read any valid hash from files
     /// which might want to change one day.
-    object_hash: gix_hash::Kind,
+    pub(crate) object_hash: gix_hash::Kind,
     /// The amount of bytes needed for `mmap` to be used to open packed refs.
     packed_buffer_mmap_threshold: u64,
 
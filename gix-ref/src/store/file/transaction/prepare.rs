@@ -8,8 +8,9 @@ use crate::{
             transaction::{Edit, PackedRefs},
         },
     },
-    transaction::{Change, LogChange, PreviousValue, RefEdit, RefEditsExt, RefLog},
+    transaction::{Change, LogChange, Mismatch, PreviousValue, RefEdit, RefEditsExt, RefLog},
 };
+use gix_object::bstr::BString;
 
 impl Transaction<'_, '_> {
     /// Read the current value of a reference from loose storage, falling back to packed refs.
@@ -92,37 +93,9 @@ impl Transaction<'_, '_> {
 
                 let existing_ref = Self::read_existing_ref(store, change.update.name.as_ref(), packed)?;
 
-                match (&expected, &existing_ref) {
-                    (PreviousValue::MustNotExist, _) => {
-                        panic!("BUG: MustNotExist constraint makes no sense if references are to be deleted")
-                    }
-                    (PreviousValue::ExistingMustMatch(_) | PreviousValue::Any, None)
-                    | (PreviousValue::MustExist | PreviousValue::Any, Some(_)) => {}
-                    (PreviousValue::MustExist | PreviousValue::MustExistAndMatch(_), None) => {
-                        return Err(Error::DeleteReferenceMustExist {
-                            full_name: change.name(),
-                        });
-                    }
-                    (
-                        PreviousValue::MustExistAndMatch(previous) | PreviousValue::ExistingMustMatch(previous),
-                        Some(existing),
-                    ) => {
-                        let actual = existing.target.clone();
-                        if *previous != actual {
-                            let expected = previous.clone();
-                            return Err(Error::ReferenceOutOfDate {
-                                full_name: change.name(),
-                                expected,
-                                actual,
-                            });
-                        }
-                    }
-                }
-
-                // Keep the previous value for the caller and ourselves. Maybe they want to keep a log of sorts.
-                if let Some(existing) = existing_ref {
-                    *expected = PreviousValue::MustExistAndMatch(existing.target);
-                }
+                expected
+                    .verify_deletion(existing_ref)
+                    .map_err(|err| Error::from_mismatch(change.update.name.as_bstr().to_owned(), err))?;
 
                 Some(lock)
             }
@@ -145,64 +118,9 @@ impl Transaction<'_, '_> {
 
                 let existing_ref = Self::read_existing_ref(store, change.update.name.as_ref(), packed)?;
 
-                match (&expected, &existing_ref) {
-                    (PreviousValue::Any, _)
-                    | (PreviousValue::MustExist, Some(_))
-                    | (PreviousValue::MustNotExist | PreviousValue::ExistingMustMatch(_), None) => {}
-                    (PreviousValue::MustExist, None) => {
-                        let expected = Target::Object(store.object_hash.null());
-                        let full_name = change.name();
-                        return Err(Error::MustExist { full_name, expected });
-                    }
-                    (PreviousValue::MustNotExist, Some(existing)) => {
-                        if existing.target != *new {
-                            let new = new.clone();
-                            return Err(Error::MustNotExist {
-                                full_name: change.name(),
-                                actual: existing.target.clone(),
-                                new,
-                            });
-                        }
-                    }
-                    (
-                        PreviousValue::MustExistAndMatch(previous) | PreviousValue::ExistingMustMatch(previous),
-                        Some(existing),
-                    ) => {
-                        if *previous != existing.target {
-                            let actual = existing.target.clone();
-                            let expected = previous.to_owned();
-                            let full_name = change.name();
-                            return Err(Error::ReferenceOutOfDate {
-                                full_name,
-                                actual,
-                                expected,
-                            });
-                        }
-                    }
-
-                    (PreviousValue::MustExistAndMatch(previous), None) => {
-                        let expected = previous.to_owned();
-                        let full_name = change.name();
-                        return Err(Error::MustExist { full_name, expected });
-                    }
-                }
-
-                fn new_would_change_existing(new: &Target, existing: &Target) -> (bool, bool) {
-                    match (new, existing) {
-                        (Target::Object(new), Target::Object(old)) => (old != new, false),
-                        (Target::Symbolic(new), Target::Symbolic(old)) => (old != new, true),
-                        (Target::Object(_), _) => (true, false),
-                        (Target::Symbolic(_), _) => (true, true),
-                    }
-                }
-
-                let (is_effective, is_symbolic) = if let Some(existing) = existing_ref {
-                    let (effective, is_symbolic) = new_would_change_existing(new, &existing.target);
-                    *expected = PreviousValue::MustExistAndMatch(existing.target);
-                    (effective, is_symbolic)
-                } else {
-                    (true, matches!(new, Target::Symbolic(_)))
-                };
+                let (is_effective, is_symbolic) = expected
+                    .verify_update(new, existing_ref, store.object_hash)
+                    .map_err(|err| Error::from_mismatch(change.update.name.as_bstr().to_owned(), err))?;
 
                 let keep_lock_for_loose_source_delete = direct_to_packed_refs && matches!(new, Target::Object(_));
                 if (is_effective && !direct_to_packed_refs) || is_symbolic {
@@ -518,3 +436,18 @@ mod error {
 }
 
 pub use error::Error;
+
+impl Error {
+    fn from_mismatch(full_name: BString, mismatch: Mismatch) -> Self {
+        match mismatch {
+            Mismatch::DeletedMustExist => Error::DeleteReferenceMustExist { full_name },
+            Mismatch::MustNotExist { actual, new } => Error::MustNotExist { full_name, actual, new },
+            Mismatch::MustExist { expected } => Error::MustExist { full_name, expected },
+            Mismatch::OutOfDate { expected, actual } => Error::ReferenceOutOfDate {
+                full_name,
+                expected,
+                actual,
+            },
+        }
+    }
+}
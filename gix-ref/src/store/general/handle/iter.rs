@@ -0,0 +1,110 @@
+use gix_path::RelativePath;
+
+use crate::{
+    Reference, file,
+    store::{self, handle},
+};
+
+/// A platform to create iterators over references, limited to the namespace of the [handle](store::Handle) it was created from.
+#[must_use = "Iterators should be obtained from this iterator platform"]
+pub struct Platform<'s> {
+    inner: PlatformInner<'s>,
+}
+
+enum PlatformInner<'s> {
+    Loose(file::iter::Platform<'s>),
+    #[cfg(feature = "reftable")]
+    Reftable(crate::reftable::iter::Platform<'s>),
+}
+
+impl<'s> Platform<'s> {
+    /// Return an iterator over all references, sorted by their name.
+    pub fn all(&self) -> Result<Iter<'_, 's>, Error> {
+        Ok(Iter {
+            inner: match &self.inner {
+                PlatformInner::Loose(platform) => IterInner::Loose(platform.all()?),
+                #[cfg(feature = "reftable")]
+                PlatformInner::Reftable(platform) => IterInner::Reftable(platform.all()?),
+            },
+        })
+    }
+
+    /// As [`all()`](Self::all()), but filters by `prefix`, i.e. `refs/heads/` or `refs/heads/feature-`.
+    ///
+    /// Prefixes are relative paths with slash-separated components.
+    pub fn prefixed(&self, prefix: &RelativePath) -> Result<Iter<'_, 's>, Error> {
+        Ok(Iter {
+            inner: match &self.inner {
+                PlatformInner::Loose(platform) => IterInner::Loose(platform.prefixed(prefix)?),
+                #[cfg(feature = "reftable")]
+                PlatformInner::Reftable(platform) => IterInner::Reftable(platform.prefixed(prefix)?),
+            },
+        })
+    }
+
+    /// Return an iterator over the pseudo references, like `HEAD` or `FETCH_HEAD`, or anything else suffixed with `HEAD`
+    /// of the current worktree, sorted by name.
+    pub fn pseudo(&self) -> Result<Iter<'_, 's>, Error> {
+        Ok(Iter {
+            inner: match &self.inner {
+                PlatformInner::Loose(platform) => IterInner::Loose(platform.pseudo()?),
+                #[cfg(feature = "reftable")]
+                PlatformInner::Reftable(platform) => IterInner::Reftable(platform.pseudo()?),
+            },
+        })
+    }
+}
+
+/// An iterator over references, sorted by their name.
+pub struct Iter<'p, 's> {
+    inner: IterInner<'p, 's>,
+}
+
+enum IterInner<'p, 's> {
+    Loose(file::iter::LooseThenPacked<'p, 's>),
+    #[cfg(feature = "reftable")]
+    Reftable(crate::reftable::iter::Iter),
+}
+
+impl Iterator for Iter<'_, '_> {
+    type Item = Result<Reference, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            IterInner::Loose(iter) => iter.next().map(|res| res.map_err(Into::into)),
+            #[cfg(feature = "reftable")]
+            IterInner::Reftable(iter) => iter.next().map(|res| res.map_err(Into::into)),
+        }
+    }
+}
+
+impl store::Handle {
+    /// Return a platform to obtain iterators over all references, or prefixed ones, limited to the namespace of this handle.
+    pub fn iter(&self) -> Result<Platform<'_>, Error> {
+        Ok(Platform {
+            inner: match &self.state {
+                handle::State::Loose { store } => PlatformInner::Loose(store.iter()?),
+                #[cfg(feature = "reftable")]
+                handle::State::Reftable { store } => PlatformInner::Reftable(store.iter()),
+            },
+        })
+    }
+}
+
+mod error {
+    /// The error returned by [`Handle::iter()`](crate::store::Handle::iter()) and the iterators it creates.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The packed-refs file could not be opened")]
+        PackedOpen(#[from] crate::packed::buffer::open::Error),
+        #[error("Loose references could not be listed")]
+        Io(#[from] std::io::Error),
+        #[error("A loose or packed reference could not be read")]
+        Loose(#[from] crate::file::iter::loose_then_packed::Error),
+        #[cfg(feature = "reftable")]
+        #[error("A reference could not be read from its reftable")]
+        Reftable(#[from] crate::reftable::iter::Error),
+    }
+}
+pub use error::Error;
@@ -1,9 +1,16 @@
-#![allow(dead_code)]
-use crate::{Namespace, store};
+use std::path::Path;
 
-#[derive(Clone)]
+use crate::{FullNameRef, Namespace, file, store, store::Backend, store::WriteReflog};
+
+#[derive(Debug, Clone)]
 pub(crate) enum State {
-    Loose { store: crate::file::Store },
+    Loose {
+        store: crate::file::Store,
+    },
+    #[cfg(feature = "reftable")]
+    Reftable {
+        store: crate::reftable::Store,
+    },
 }
 
 impl crate::Store {
@@ -28,16 +35,173 @@ impl crate::Store {
                         store
                     },
                 },
+                #[cfg(feature = "reftable")]
+                store::State::Reftable { store } => store::handle::State::Reftable {
+                    store: {
+                        let mut store = store.clone();
+                        store.set_namespace(namespace);
+                        store
+                    },
+                },
             },
         }
     }
 }
 
+impl From<file::Store> for store::Handle {
+    fn from(store: file::Store) -> Self {
+        store::Handle {
+            state: State::Loose { store },
+        }
+    }
+}
+
+#[cfg(feature = "reftable")]
+impl From<crate::reftable::Store> for store::Handle {
+    fn from(store: crate::reftable::Store) -> Self {
+        store::Handle {
+            state: State::Reftable { store },
+        }
+    }
+}
+
+/// Access
+impl store::Handle {
+    /// Return the way references are stored.
+    pub fn backend(&self) -> Backend {
+        match &self.state {
+            State::Loose { .. } => Backend::Files,
+            #[cfg(feature = "reftable")]
+            State::Reftable { .. } => Backend::Reftable,
+        }
+    }
+
+    /// Return the `.git` directory at which all references are loaded.
+    ///
+    /// For worktrees, this is the linked work-tree private ref location,
+    /// then [`common_dir()`](store::Handle::common_dir()) is `Some(parent_git_dir)`.
+    pub fn git_dir(&self) -> &Path {
+        match &self.state {
+            State::Loose { store } => store.git_dir(),
+            #[cfg(feature = "reftable")]
+            State::Reftable { store } => store.git_dir(),
+        }
+    }
+
+    /// If this is a linked work tree, there will be `Some(git_dir)` pointing to the parent repository.
+    pub fn common_dir(&self) -> Option<&Path> {
+        match &self.state {
+            State::Loose { store } => store.common_dir(),
+            #[cfg(feature = "reftable")]
+            State::Reftable { store } => store.common_dir(),
+        }
+    }
+
+    /// Return the [common directory](Self::common_dir()) if set, or the [`git_dir`](Self::git_dir()) otherwise.
+    pub fn common_dir_resolved(&self) -> &Path {
+        match &self.state {
+            State::Loose { store } => store.common_dir_resolved(),
+            #[cfg(feature = "reftable")]
+            State::Reftable { store } => store.common_dir_resolved(),
+        }
+    }
+
+    /// Return the namespace all read and write operations are limited to, if set.
+    pub fn namespace(&self) -> Option<&Namespace> {
+        match &self.state {
+            State::Loose { store } => store.namespace.as_ref(),
+            #[cfg(feature = "reftable")]
+            State::Reftable { store } => store.namespace(),
+        }
+    }
+
+    /// Limit all read and write operations to `namespace`, or see all references if `None`, returning the previous namespace.
+    pub fn set_namespace(&mut self, namespace: Option<Namespace>) -> Option<Namespace> {
+        match &mut self.state {
+            State::Loose { store } => std::mem::replace(&mut store.namespace, namespace),
+            #[cfg(feature = "reftable")]
+            State::Reftable { store } => {
+                let previous = store.namespace().cloned();
+                store.set_namespace(namespace);
+                previous
+            }
+        }
+    }
+
+    /// Return the way reflogs are written.
+    pub fn write_reflog(&self) -> WriteReflog {
+        match &self.state {
+            State::Loose { store } => store.write_reflog,
+            #[cfg(feature = "reftable")]
+            State::Reftable { store } => store.write_reflog(),
+        }
+    }
+
+    /// Set the way reflogs are written to `mode`.
+    pub fn set_write_reflog(&mut self, mode: WriteReflog) {
+        match &mut self.state {
+            State::Loose { store } => store.write_reflog = mode,
+            #[cfg(feature = "reftable")]
+            State::Reftable { store } => store.set_write_reflog(mode),
+        }
+    }
+
+    /// Return the underlying store if references are stored as loose files and in `packed-refs`.
+    pub fn as_file_store(&self) -> Option<&file::Store> {
+        match &self.state {
+            State::Loose { store } => Some(store),
+            #[cfg(feature = "reftable")]
+            State::Reftable { .. } => None,
+        }
+    }
+
+    /// Return the underlying store if references are stored in reftables.
+    #[cfg(feature = "reftable")]
+    pub fn as_reftable_store(&self) -> Option<&crate::reftable::Store> {
+        match &self.state {
+            State::Loose { .. } => None,
+            State::Reftable { store } => Some(store),
+        }
+    }
+
+    /// Returns `Some(true)` if the reference database is untouched since it was initialized, `Some(false)` if it was changed,
+    /// or `None` if this couldn't be determined.
+    ///
+    /// See [`file::Store::is_pristine()`] for details.
+    pub fn is_pristine(&self, default_ref: &FullNameRef) -> Option<bool> {
+        match &self.state {
+            State::Loose { store } => store.is_pristine(default_ref),
+            #[cfg(feature = "reftable")]
+            State::Reftable { store } => store.is_pristine(default_ref),
+        }
+    }
+
+    /// Return a possibly cached packed buffer with shared ownership, or `None` if there is none.
+    ///
+    /// Reftables don't have packed references, so for these `None` is always returned.
+    /// See [`file::Store::cached_packed_buffer()`] for details.
+    pub fn cached_packed_buffer(
+        &self,
+    ) -> Result<Option<file::packed::SharedBufferSnapshot>, crate::packed::buffer::open::Error> {
+        match &self.state {
+            State::Loose { store } => store.cached_packed_buffer(),
+            #[cfg(feature = "reftable")]
+            State::Reftable { .. } => Ok(None),
+        }
+    }
+}
+
 ///
 pub mod find;
 
-mod iter {
-    // impl store::Handle {
-    //     pub fn iter<'p, 's>(&'s self, packed: Option<&'p packed::Buffer>) -> std::io::Result<LooseThenPacked<'p, 's>> {
-    // }
-}
+///
+pub mod iter;
+
+mod raw_ext;
+pub use raw_ext::ReferenceExt;
+
+///
+pub mod reflog;
+
+///
+pub mod transaction;
@@ -0,0 +1,215 @@
+use std::collections::BTreeSet;
+
+use gix_hash::ObjectId;
+
+use crate::{
+    Reference, Target, packed, peel,
+    store::{self, find, handle, reflog},
+};
+
+pub trait Sealed {}
+impl Sealed for crate::Reference {}
+
+/// A trait to extend [Reference][crate::Reference] with functionality requiring a [store handle](store::Handle),
+/// independently of the way references are stored.
+///
+/// It's the equivalent of [`file::ReferenceExt`](crate::file::ReferenceExt) for [store handles](store::Handle).
+pub trait ReferenceExt: Sealed {
+    /// A step towards obtaining forward or reverse iterators on reference logs.
+    fn log_iter<'a, 's>(&'a self, store: &'s store::Handle) -> reflog::Platform<'a, 's>;
+
+    /// Returns true if a reflog exists in the given `store`.
+    fn log_exists(&self, store: &store::Handle) -> bool;
+
+    /// Follow all symbolic targets this reference might point to and peel the underlying object
+    /// to the end of the tag-chain, returning the first non-tag object the annotated tag points to,
+    /// using `objects` to access them and `store` to lookup symbolic references.
+    ///
+    /// Note that this method mutates `self` in place if it does not already point to a
+    /// non-symbolic object.
+    fn peel_to_id(
+        &mut self,
+        store: &store::Handle,
+        objects: &dyn gix_object::Find,
+    ) -> Result<ObjectId, peel::to_id::Error>;
+
+    /// Like [`ReferenceExt::peel_to_id()`], but with support for a known stable `packed` buffer to
+    /// use for resolving symbolic links.
+    ///
+    /// `packed` is ignored if references are stored in reftables.
+    fn peel_to_id_packed(
+        &mut self,
+        store: &store::Handle,
+        objects: &dyn gix_object::Find,
+        packed: Option<&packed::Buffer>,
+    ) -> Result<ObjectId, peel::to_id::Error>;
+
+    /// Like [`ReferenceExt::follow()`], but follows all symbolic references while gracefully handling loops,
+    /// altering this instance in place.
+    fn follow_to_object_packed(
+        &mut self,
+        store: &store::Handle,
+        packed: Option<&packed::Buffer>,
+    ) -> Result<ObjectId, peel::to_object::Error>;
+
+    /// Follow this symbolic reference one level and return the ref it refers to.
+    ///
+    /// Returns `None` if this is not a symbolic reference, hence the leaf of the chain.
+    fn follow(&self, store: &store::Handle) -> Option<Result<Reference, find::existing::Error>>;
+
+    /// Follow this symbolic reference one level and return the ref it refers to,
+    /// possibly providing access to `packed` references for lookup if it contains the referent.
+    ///
+    /// Returns `None` if this is not a symbolic reference, hence the leaf of the chain.
+    fn follow_packed(
+        &self,
+        store: &store::Handle,
+        packed: Option<&packed::Buffer>,
+    ) -> Option<Result<Reference, find::existing::Error>>;
+}
+
+impl ReferenceExt for Reference {
+    fn log_iter<'a, 's>(&'a self, store: &'s store::Handle) -> reflog::Platform<'a, 's> {
+        reflog::Platform {
+            store,
+            name: self.name.as_ref(),
+            buf: Vec::new(),
+        }
+    }
+
+    fn log_exists(&self, store: &store::Handle) -> bool {
+        store
+            .reflog_exists(self.name.as_ref())
+            .expect("infallible name conversion")
+    }
+
+    fn peel_to_id(
+        &mut self,
+        store: &store::Handle,
+        objects: &dyn gix_object::Find,
+    ) -> Result<ObjectId, peel::to_id::Error> {
+        let packed = store.cached_packed_buffer().map_err(|err| {
+            peel::to_id::Error::FollowToObject(peel::to_object::Error::FollowInStore(find::existing::Error::Find(
+                find::Error::Loose(crate::file::find::Error::PackedOpen(err)),
+            )))
+        })?;
+        self.peel_to_id_packed(store, objects, packed.as_ref().map(|b| &***b))
+    }
+
+    fn peel_to_id_packed(
+        &mut self,
+        store: &store::Handle,
+        objects: &dyn gix_object::Find,
+        packed: Option<&packed::Buffer>,
+    ) -> Result<ObjectId, peel::to_id::Error> {
+        match self.peeled {
+            Some(peeled) => {
+                self.target = Target::Object(peeled.to_owned());
+                Ok(peeled)
+            }
+            None => {
+                let mut oid = self.follow_to_object_packed(store, packed)?;
+                let mut buf = Vec::new();
+                let peeled_id = loop {
+                    let gix_object::Data {
+                        kind,
+                        data,
+                        object_hash: hash_kind,
+                    } = objects
+                        .try_find(&oid, &mut buf)?
+                        .ok_or_else(|| peel::to_id::Error::NotFound {
+                            oid,
+                            name: self.name.0.clone(),
+                        })?;
+                    match kind {
+                        gix_object::Kind::Tag => {
+                            oid = gix_object::TagRefIter::from_bytes(data, hash_kind)
+                                .target_id()
+                                .map_err(|_err| peel::to_id::Error::NotFound {
+                                    oid,
+                                    name: self.name.0.clone(),
+                                })?;
+                        }
+                        _ => break oid,
+                    }
+                };
+                self.peeled = Some(peeled_id);
+                self.target = Target::Object(peeled_id);
+                Ok(peeled_id)
+            }
+        }
+    }
+
+    fn follow_to_object_packed(
+        &mut self,
+        store: &store::Handle,
+        packed: Option<&packed::Buffer>,
+    ) -> Result<ObjectId, peel::to_object::Error> {
+        match self.target {
+            Target::Object(id) => Ok(id),
+            Target::Symbolic(_) => {
+                let mut seen = BTreeSet::new();
+                let cursor = &mut *self;
+                while let Some(next) = cursor.follow_packed(store, packed) {
+                    let next = next?;
+                    if seen.contains(&next.name) {
+                        return Err(peel::to_object::Error::Cycle {
+                            start_absolute: match &store.state {
+                                handle::State::Loose { store } => store.reference_path(cursor.name.as_ref()),
+                                #[cfg(feature = "reftable")]
+                                handle::State::Reftable { store } => store.git_dir().join(cursor.name.to_path()),
+                            },
+                        });
+                    }
+                    *cursor = next;
+                    seen.insert(cursor.name.clone());
+                    const MAX_REF_DEPTH: usize = 5;
+                    if seen.len() == MAX_REF_DEPTH {
+                        return Err(peel::to_object::Error::DepthLimitExceeded {
+                            max_depth: MAX_REF_DEPTH,
+                        });
+                    }
+                }
+                let oid = self.target.try_id().expect("peeled ref").to_owned();
+                Ok(oid)
+            }
+        }
+    }
+
+    fn follow(&self, store: &store::Handle) -> Option<Result<Reference, find::existing::Error>> {
+        let packed = match store
+            .cached_packed_buffer()
+            .map_err(|err| find::existing::Error::Find(find::Error::Loose(crate::file::find::Error::PackedOpen(err))))
+        {
+            Ok(packed) => packed,
+            Err(err) => return Some(Err(err)),
+        };
+        self.follow_packed(store, packed.as_ref().map(|b| &***b))
+    }
+
+    fn follow_packed(
+        &self,
+        store: &store::Handle,
+        packed: Option<&packed::Buffer>,
+    ) -> Option<Result<Reference, find::existing::Error>> {
+        match &self.target {
+            Target::Object(_) => None,
+            Target::Symbolic(full_name) => {
+                let res = match &store.state {
+                    handle::State::Loose { store } => store
+                        .try_find_packed(full_name.as_ref(), packed)
+                        .map_err(find::Error::from),
+                    #[cfg(feature = "reftable")]
+                    handle::State::Reftable { store } => store.try_find(full_name.as_ref()).map_err(find::Error::from),
+                };
+                match res {
+                    Ok(Some(next)) => Some(Ok(next)),
+                    Ok(None) => Some(Err(find::existing::Error::NotFound {
+                        name: full_name.to_path().to_owned(),
+                    })),
+                    Err(err) => Some(Err(find::existing::Error::Find(err))),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    FullNameRef,
+    file::log,
+    store::{self, handle},
+};
+
+/// The source of the log lines of a [reverse iterator](log::iter::Reverse) obtained from a [handle](store::Handle).
+pub enum Source {
+    /// The reflog file of a reference.
+    File(std::fs::File),
+    /// The reflog of a reference stored in a reftable, in the format of reflog files.
+    Buffer(std::io::Cursor<Vec<u8>>),
+}
+
+impl std::io::Read for Source {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Source::File(file) => file.read(buf),
+            Source::Buffer(buffer) => buffer.read(buf),
+        }
+    }
+}
+
+impl std::io::Seek for Source {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        match self {
+            Source::File(file) => file.seek(pos),
+            Source::Buffer(buffer) => buffer.seek(pos),
+        }
+    }
+}
+
+/// A platform to obtain forward or reverse iterators over the reflog of a reference, as obtained from a [handle](store::Handle).
+pub struct Platform<'a, 's> {
+    /// The store containing the reflogs.
+    pub store: &'s store::Handle,
+    /// The full name of the reference whose reflog to retrieve.
+    pub name: &'a FullNameRef,
+    /// A reusable buffer for storing log lines read from disk.
+    pub buf: Vec<u8>,
+}
+
+impl Platform<'_, '_> {
+    /// Return a reverse iterator over all log-lines, most recent to oldest.
+    pub fn rev(&mut self) -> Result<Option<log::iter::Reverse<'_, Source>>, Error> {
+        self.buf.clear();
+        self.buf.resize(1024 * 4, 0);
+        self.store.reflog_iter_rev(self.name, &mut self.buf)
+    }
+
+    /// Return a forward iterator over all log-lines, oldest to most recent.
+    pub fn all(&mut self) -> Result<Option<log::iter::Forward<'_>>, Error> {
+        self.buf.clear();
+        self.store.reflog_iter(self.name, &mut self.buf)
+    }
+}
+
+impl store::Handle {
+    /// Returns true if a reflog exists for the given reference `name`.
+    ///
+    /// See [`file::Store::reflog_exists()`](crate::file::Store::reflog_exists()) for details.
+    pub fn reflog_exists<'a, Name, E>(&self, name: Name) -> Result<bool, E>
+    where
+        Name: TryInto<&'a FullNameRef, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        match &self.state {
+            handle::State::Loose { store } => store.reflog_exists(name),
+            #[cfg(feature = "reftable")]
+            handle::State::Reftable { store } => store.reflog_exists(name),
+        }
+    }
+
+    /// Return a reflog reverse iterator for the given fully qualified `name`, reading chunks from the back into the fixed buffer `buf`.
+    ///
+    /// The iterator will traverse log entries from most recent to oldest.
+    /// Return `Ok(None)` if no reflog exists.
+    pub fn reflog_iter_rev<'a, 'b, Name, E>(
+        &self,
+        name: Name,
+        buf: &'b mut [u8],
+    ) -> Result<Option<log::iter::Reverse<'b, Source>>, Error>
+    where
+        Name: TryInto<&'a FullNameRef, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        let name: &FullNameRef = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        match &self.state {
+            handle::State::Loose { store } => {
+                let path = store.reflog_path(name);
+                if path.is_dir() {
+                    return Ok(None);
+                }
+                match std::fs::File::open(&path) {
+                    Ok(file) => Ok(Some(log::iter::reverse(Source::File(file), buf)?)),
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                    Err(err) => Err(err.into()),
+                }
+            }
+            #[cfg(feature = "reftable")]
+            handle::State::Reftable { store } => {
+                let mut lines = Vec::new();
+                Ok(
+                    if store
+                        .reflog_iter::<_, std::convert::Infallible>(name, &mut lines)?
+                        .is_some()
+                    {
+                        Some(log::iter::reverse(Source::Buffer(std::io::Cursor::new(lines)), buf)?)
+                    } else {
+                        None
+                    },
+                )
+            }
+        }
+    }
+
+    /// Return a reflog forward iterator for the given fully qualified `name`, using `buf` to hold the entire log.
+    ///
+    /// The iterator will traverse log entries from oldest to newest.
+    /// Return `Ok(None)` if no reflog exists.
+    pub fn reflog_iter<'a, 'b, Name, E>(
+        &self,
+        name: Name,
+        buf: &'b mut Vec<u8>,
+    ) -> Result<Option<log::iter::Forward<'b>>, Error>
+    where
+        Name: TryInto<&'a FullNameRef, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        let name: &FullNameRef = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        match &self.state {
+            handle::State::Loose { store } => Ok(store.reflog_iter::<_, std::convert::Infallible>(name, buf)?),
+            #[cfg(feature = "reftable")]
+            handle::State::Reftable { store } => Ok(store.reflog_iter::<_, std::convert::Infallible>(name, buf)?),
+        }
+    }
+}
+
+mod error {
+    /// The error returned by [`Handle::reflog_iter()`](crate::store::Handle::reflog_iter()) and
+    /// [`Handle::reflog_iter_rev()`](crate::store::Handle::reflog_iter_rev()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The reflog name or path is not a valid ref name")]
+        RefnameValidation(#[from] crate::name::Error),
+        #[error("The reflog file could not be read")]
+        Io(#[from] std::io::Error),
+        #[error("The reflog could not be read from its file")]
+        Loose(#[from] crate::file::log::Error),
+        #[cfg(feature = "reftable")]
+        #[error("The reflog could not be read from its reftable")]
+        Reftable(#[from] crate::reftable::reflog::Error),
+    }
+}
+pub use error::Error;
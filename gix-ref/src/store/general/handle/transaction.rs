@@ -0,0 +1,119 @@
+use crate::{
+    file,
+    store::{self, handle},
+    transaction::RefEdit,
+};
+
+/// A transaction to atomically edit references, limited to the namespace of the [handle](store::Handle) it was created from.
+#[derive(Debug)]
+pub struct Transaction<'s> {
+    inner: Inner<'s>,
+}
+
+#[derive(Debug)]
+enum Inner<'s> {
+    Loose(file::Transaction<'s, 's>),
+    #[cfg(feature = "reftable")]
+    Reftable(crate::reftable::Transaction<'s>),
+}
+
+impl<'s> Transaction<'s> {
+    /// Prepare for calling [`commit(…)`][Transaction::commit()] in a way that can be rolled back perfectly,
+    /// failing according to `ref_files_lock_fail_mode` and `packed_refs_lock_fail_mode` if locks can't be obtained.
+    ///
+    /// See [`file::Transaction::prepare()`] for details. Reftable stacks are locked according to `ref_files_lock_fail_mode`.
+    pub fn prepare(
+        self,
+        edits: impl IntoIterator<Item = RefEdit>,
+        ref_files_lock_fail_mode: gix_lock::acquire::Fail,
+        packed_refs_lock_fail_mode: gix_lock::acquire::Fail,
+    ) -> Result<Self, prepare::Error> {
+        Ok(Transaction {
+            inner: match self.inner {
+                Inner::Loose(t) => {
+                    Inner::Loose(t.prepare(edits, ref_files_lock_fail_mode, packed_refs_lock_fail_mode)?)
+                }
+                #[cfg(feature = "reftable")]
+                Inner::Reftable(t) => Inner::Reftable(t.prepare(edits, ref_files_lock_fail_mode)?),
+            },
+        })
+    }
+
+    /// Make all [prepared][Transaction::prepare()] permanent and return the performed edits, using `committer` for
+    /// reflog entries.
+    ///
+    /// See [`file::Transaction::commit()`] for details.
+    pub fn commit<'a>(
+        self,
+        committer: impl Into<Option<gix_actor::SignatureRef<'a>>>,
+    ) -> Result<Vec<RefEdit>, commit::Error> {
+        Ok(match self.inner {
+            Inner::Loose(t) => t.commit(committer)?,
+            #[cfg(feature = "reftable")]
+            Inner::Reftable(t) => t.commit(committer)?,
+        })
+    }
+
+    /// Configure the way packed refs are handled during the transaction.
+    ///
+    /// This has no effect if references are stored in reftables, which don't have packed references.
+    pub fn packed_refs(self, packed_refs: file::transaction::PackedRefs<'s>) -> Self {
+        Transaction {
+            inner: match self.inner {
+                Inner::Loose(t) => Inner::Loose(t.packed_refs(packed_refs)),
+                #[cfg(feature = "reftable")]
+                Inner::Reftable(t) => Inner::Reftable(t),
+            },
+        }
+    }
+
+    /// Rollback all intermediate state and return the `RefEdits` as we know them thus far.
+    pub fn rollback(self) -> Vec<RefEdit> {
+        match self.inner {
+            Inner::Loose(t) => t.rollback(),
+            #[cfg(feature = "reftable")]
+            Inner::Reftable(t) => t.rollback(),
+        }
+    }
+}
+
+impl store::Handle {
+    /// Open a transaction to atomically edit references, limited to the namespace of this handle.
+    pub fn transaction(&self) -> Transaction<'_> {
+        Transaction {
+            inner: match &self.state {
+                handle::State::Loose { store } => Inner::Loose(store.transaction()),
+                #[cfg(feature = "reftable")]
+                handle::State::Reftable { store } => Inner::Reftable(store.transaction()),
+            },
+        }
+    }
+}
+
+///
+pub mod prepare {
+    /// The error returned by [`Transaction::prepare()`](super::Transaction::prepare()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Loose(#[from] crate::file::transaction::prepare::Error),
+        #[cfg(feature = "reftable")]
+        #[error(transparent)]
+        Reftable(#[from] crate::reftable::transaction::prepare::Error),
+    }
+}
+
+///
+pub mod commit {
+    /// The error returned by [`Transaction::commit()`](super::Transaction::commit()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Loose(#[from] crate::file::transaction::commit::Error),
+        #[cfg(feature = "reftable")]
+        #[error(transparent)]
+        Reftable(#[from] crate::reftable::transaction::commit::Error),
+    }
+}
@@ -1,33 +1,92 @@
 use std::path::PathBuf;
 
 mod error {
-    /// The error returned by [`crate::Store::at()`].
+    /// The error returned by [`crate::Store::at()`] and [`crate::Store::for_linked_worktree()`].
     #[derive(Debug, thiserror::Error)]
     #[allow(missing_docs)]
     pub enum Error {
         #[error("There was an error accessing the store's directory")]
         Io(#[from] std::io::Error),
+        #[error("References are stored in reftables, which requires the 'reftable' feature")]
+        ReftableUnsupported,
     }
 }
 
 pub use error::Error;
 
-use crate::file;
+use crate::{file, store::Backend};
 
-#[allow(dead_code)]
 impl crate::Store {
     /// Create a new store at the given location, typically the `.git/` directory.
-    /// Use [`opts`](crate::store::init::Options) to adjust settings.
+    /// Use [`opts`](crate::store::init::Options) to adjust settings, and [`backend`](crate::store::init::Options::backend)
+    /// to select the way references are stored.
     ///
     /// Note that if [`precompose_unicode`](crate::store::init::Options::precompose_unicode) is set in the options,
     /// the `git_dir` is also expected to use precomposed unicode, or else some operations that strip prefixes will fail.
     pub fn at(git_dir: PathBuf, opts: crate::store::init::Options) -> Result<Self, Error> {
-        // for now, just try to read the directory - later we will do that naturally as we have to figure out if it's a ref-table or not.
+        // The backend is known from the configuration, so all we can do is to assure the directory is accessible.
         std::fs::read_dir(&git_dir)?;
         Ok(crate::Store {
-            inner: crate::store::State::Loose {
-                store: file::Store::at(git_dir, opts),
+            inner: match opts.backend {
+                Backend::Files => crate::store::State::Loose {
+                    store: file::Store::at(git_dir, opts),
+                },
+                #[cfg(feature = "reftable")]
+                Backend::Reftable => crate::store::State::Reftable {
+                    store: crate::reftable::Store::at(git_dir, opts),
+                },
+                #[cfg(not(feature = "reftable"))]
+                Backend::Reftable => return Err(Error::ReftableUnsupported),
             },
         })
     }
+
+    /// Like [`at()`](crate::Store::at()), but for _linked_ work-trees which use `git_dir` for their private references
+    /// and `common_dir` for shared references.
+    pub fn for_linked_worktree(
+        git_dir: PathBuf,
+        common_dir: PathBuf,
+        opts: crate::store::init::Options,
+    ) -> Result<Self, Error> {
+        std::fs::read_dir(&git_dir)?;
+        Ok(crate::Store {
+            inner: match opts.backend {
+                Backend::Files => crate::store::State::Loose {
+                    store: file::Store::for_linked_worktree(git_dir, common_dir, opts),
+                },
+                #[cfg(feature = "reftable")]
+                Backend::Reftable => crate::store::State::Reftable {
+                    store: crate::reftable::Store::for_linked_worktree(git_dir, common_dir, opts),
+                },
+                #[cfg(not(feature = "reftable"))]
+                Backend::Reftable => return Err(Error::ReftableUnsupported),
+            },
+        })
+    }
+
+    /// Return the way references are stored.
+    pub fn backend(&self) -> Backend {
+        match &self.inner {
+            crate::store::State::Loose { .. } => Backend::Files,
+            #[cfg(feature = "reftable")]
+            crate::store::State::Reftable { .. } => Backend::Reftable,
+        }
+    }
+}
+
+impl From<file::Store> for crate::Store {
+    fn from(store: file::Store) -> Self {
+        crate::Store {
+            inner: crate::store::State::Loose { store },
+        }
+    }
+}
+
+#[cfg(feature = "reftable")]
+impl From<crate::reftable::Store> for crate::Store {
+    fn from(store: crate::reftable::Store) -> Self {
+        crate::Store {
+            inner: crate::store::State::Reftable { store },
+        }
+    }
 }
@@ -3,3 +3,7 @@ pub mod file;
 
 ///
 pub mod packed;
+
+///
+#[cfg(feature = "reftable")]
+pub mod reftable;
@@ -0,0 +1,76 @@
+use gix_object::bstr::BString;
+
+use crate::{FullNameRef, PartialNameRef, Reference, name::is_pseudo_ref, store_impl::reftable};
+
+impl reftable::Store {
+    /// Find a single reference by the given `partial` name, which is required to be a valid reference name.
+    ///
+    /// Returns `Ok(None)` if no such ref exists.
+    /// The lookup follows the same rules as [`file::Store::try_find()`](crate::file::Store::try_find()).
+    pub fn try_find<'a, Name, E>(&self, partial: Name) -> Result<Option<Reference>, Error>
+    where
+        Name: TryInto<&'a PartialNameRef, Error = E>,
+        Error: From<E>,
+    {
+        let partial_name = partial.try_into()?;
+        let mut buf = BString::default();
+        for consider_pseudo_ref in [true, false] {
+            if !consider_pseudo_ref && !is_pseudo_ref(partial_name.as_bstr()) {
+                break;
+            }
+            for inbetween in &["", "tags", "heads", "remotes"] {
+                let full_name = partial_name.construct_full_name_ref(inbetween, &mut buf, consider_pseudo_ref);
+                if let Some(r) = self.find_full_name(full_name)? {
+                    return Ok(Some(r));
+                }
+                if consider_pseudo_ref && is_pseudo_ref(partial_name.as_bstr()) {
+                    break;
+                }
+            }
+        }
+        if partial_name.as_bstr() != "HEAD" {
+            let remote_head = partial_name.to_owned().join("HEAD".into()).expect("HEAD is valid name");
+            let full_name = remote_head
+                .as_ref()
+                .construct_full_name_ref("remotes", &mut buf, true /* consider-pseudo-ref */);
+            self.find_full_name(full_name)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn find_full_name(&self, full_name: &FullNameRef) -> Result<Option<Reference>, Error> {
+        if reftable::is_stored_as_file(full_name) {
+            return Ok(self.files.try_find_loose(full_name)?.map(Into::into));
+        }
+        let (base, name) = self.base_and_name(full_name);
+        let stack = self.stack_at(&base).map_err(reftable::Error::from)?;
+        let Some(r) = stack.find_ref(name.as_bstr()).map_err(reftable::Error::from)? else {
+            return Ok(None);
+        };
+        Ok(reftable::to_reference(r, full_name.to_owned(), self.namespace())?)
+    }
+}
+
+mod error {
+    use std::convert::Infallible;
+
+    /// The error returned by [`reftable::Store::try_find()`](crate::reftable::Store::try_find()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The ref name or path is not a valid ref name")]
+        RefnameValidation(#[from] crate::name::Error),
+        #[error("The reference could not be read from its reftable")]
+        Reftable(#[from] crate::reftable::Error),
+        #[error("A pseudo-ref that is stored as file could not be read")]
+        Loose(#[from] crate::file::find::Error),
+    }
+
+    impl From<Infallible> for Error {
+        fn from(_: Infallible) -> Self {
+            unreachable!("this impl is needed to allow passing a known valid partial path as parameter")
+        }
+    }
+}
+pub use error::Error;
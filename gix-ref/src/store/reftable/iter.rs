@@ -0,0 +1,208 @@
+use std::{cmp::Ordering, iter::Peekable};
+
+use gix_object::bstr::{BString, ByteSlice};
+use gix_path::RelativePath;
+
+use crate::{FullName, FullNameRef, Namespace, Reference, store_impl::reftable};
+
+/// A platform to create iterators over references stored in reftables.
+#[must_use = "Iterators should be obtained from this iterator platform"]
+pub struct Platform<'s> {
+    store: &'s reftable::Store,
+}
+
+impl Platform<'_> {
+    /// Return an iterator over all references, sorted by their name.
+    pub fn all(&self) -> Result<Iter, Error> {
+        let prefix: &[u8] = if self.store.namespace().is_some() {
+            b""
+        } else {
+            b"refs/"
+        };
+        self.store.iter_prefixed_inner(prefix)
+    }
+
+    /// As [`all()`](Self::all()), but filters by `prefix`, i.e. `refs/heads/` or `refs/heads/feature-`.
+    ///
+    /// Prefixes are relative paths with slash-separated components.
+    pub fn prefixed(&self, prefix: &RelativePath) -> Result<Iter, Error> {
+        self.store.iter_prefixed_inner(prefix.as_ref().as_bytes())
+    }
+
+    /// Return an iterator over the pseudo references, like `HEAD` or `FETCH_HEAD`, or anything else suffixed with `HEAD`
+    /// of the current worktree, sorted by name.
+    pub fn pseudo(&self) -> Result<Iter, Error> {
+        let store = self.store;
+        let files = reftable::PSEUDO_REFS_STORED_AS_FILE
+            .iter()
+            .filter_map(|name| {
+                store
+                    .files
+                    .try_find_loose(FullNameRef::new_unchecked((*name).into()))
+                    .map(|r| r.map(Into::into))
+                    .map_err(Error::from)
+                    .transpose()
+            })
+            .collect::<Vec<_>>();
+        Ok(Iter {
+            tables: Tables {
+                common: store
+                    .stack_at(store.git_dir())
+                    .map_err(reftable::Error::from)?
+                    .refs()
+                    .map_err(reftable::Error::from)?
+                    .peekable(),
+                worktree: None,
+                pseudo_only: true,
+                namespace: None,
+            }
+            .peekable(),
+            files: Some(files.into_iter().peekable()),
+        })
+    }
+}
+
+impl reftable::Store {
+    /// Return a platform to obtain iterators over all references, or prefixed ones, sorted by their name.
+    pub fn iter(&self) -> Platform<'_> {
+        Platform { store: self }
+    }
+
+    fn iter_prefixed_inner(&self, prefix: &[u8]) -> Result<Iter, Error> {
+        let prefix: BString = match self.namespace() {
+            None => prefix.into(),
+            Some(namespace) => {
+                let mut namespaced = namespace.as_bstr().to_owned();
+                namespaced.extend_from_slice(prefix);
+                namespaced
+            }
+        };
+        let refs = |base| -> Result<_, reftable::Error> { Ok(self.stack_at(base)?.refs_prefixed(&prefix)?.peekable()) };
+        Ok(Iter {
+            tables: Tables {
+                common: refs(self.common_dir_resolved())?,
+                worktree: self.common_dir().map(|_| refs(self.git_dir())).transpose()?,
+                pseudo_only: false,
+                namespace: self.namespace().cloned(),
+            }
+            .peekable(),
+            files: None,
+        })
+    }
+}
+
+/// An iterator over references stored in reftables, sorted by name, which also yields the pseudo-refs that are always
+/// stored as files when iterating pseudo-refs.
+pub struct Iter {
+    tables: Peekable<Tables>,
+    files: Option<Peekable<std::vec::IntoIter<Result<Reference, Error>>>>,
+}
+
+impl Iterator for Iter {
+    type Item = Result<Reference, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(files) = self.files.as_mut() else {
+            return self.tables.next().map(|res| res.map_err(Into::into));
+        };
+        let take_file = match (files.peek(), self.tables.peek()) {
+            (None, None) => return None,
+            (None, Some(_)) | (Some(_), Some(Err(_))) => false,
+            (Some(_), None) | (Some(Err(_)), Some(_)) => true,
+            (Some(Ok(file)), Some(Ok(table))) => file.name < table.name,
+        };
+        if take_file {
+            files.next()
+        } else {
+            self.tables.next().map(|res| res.map_err(Into::into))
+        }
+    }
+}
+
+/// The references of one or two reftable stacks, sorted by name.
+struct Tables {
+    /// The shared references, or the ones of the current worktree if there is no `worktree` stack.
+    common: Peekable<gix_reftable::stack::Refs>,
+    /// The worktree-private references of a linked worktree.
+    worktree: Option<Peekable<gix_reftable::stack::Refs>>,
+    /// If `true`, only pseudo-refs are returned.
+    pseudo_only: bool,
+    namespace: Option<Namespace>,
+}
+
+impl Tables {
+    fn convert(
+        &self,
+        r: Result<gix_reftable::Ref, gix_reftable::decode::Error>,
+    ) -> Result<Option<Reference>, reftable::Error> {
+        let r = r?;
+        let name = FullName::try_from(r.name.as_bstr()).map_err(|source| reftable::Error::InvalidReference {
+            name: r.name.clone(),
+            source,
+        })?;
+        reftable::to_reference(r, name, self.namespace.as_ref())
+    }
+
+    fn skip_unwanted(&mut self) {
+        fn is_worktree_private(name: &[u8]) -> bool {
+            FullNameRef::new_unchecked(name.as_bstr())
+                .category()
+                .is_some_and(|c| c.is_worktree_private())
+        }
+        let pseudo_only = self.pseudo_only;
+        let has_worktree = self.worktree.is_some();
+        while let Some(Ok(r)) = self.common.peek() {
+            let skip = if pseudo_only {
+                r.name.contains(&b'/') || !r.name.ends_with(b"HEAD")
+            } else {
+                has_worktree && is_worktree_private(&r.name)
+            };
+            if !skip {
+                break;
+            }
+            self.common.next();
+        }
+    }
+}
+
+impl Iterator for Tables {
+    type Item = Result<Reference, reftable::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.skip_unwanted();
+            let res = match self.worktree.as_mut() {
+                None => self.common.next()?,
+                Some(worktree) => match (self.common.peek(), worktree.peek()) {
+                    (None, None) => return None,
+                    (Some(_), None) | (Some(Err(_)), Some(_)) => self.common.next()?,
+                    (None, Some(_)) | (Some(_), Some(Err(_))) => worktree.next()?,
+                    (Some(Ok(common)), Some(Ok(private))) => match common.name.cmp(&private.name) {
+                        Ordering::Less => self.common.next()?,
+                        Ordering::Equal => {
+                            self.common.next();
+                            worktree.next()?
+                        }
+                        Ordering::Greater => worktree.next()?,
+                    },
+                },
+            };
+            if let Some(res) = self.convert(res).transpose() {
+                return Some(res);
+            }
+        }
+    }
+}
+
+mod error {
+    /// The error returned when creating or using an [`Iter`](super::Iter).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("A reference could not be read from its reftable")]
+        Reftable(#[from] crate::reftable::Error),
+        #[error("A pseudo-ref that is stored as file could not be read")]
+        Loose(#[from] crate::file::find::Error),
+    }
+}
+pub use error::Error;
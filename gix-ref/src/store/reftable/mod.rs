@@ -0,0 +1,279 @@
+use std::{borrow::Cow, path::Path};
+
+use gix_features::threading::OwnShared;
+use gix_object::bstr::{BString, ByteSlice};
+
+use crate::{FullName, FullNameRef, Namespace, Reference, Target, file};
+
+/// An up-to-date snapshot of a reftable stack.
+pub type SharedStackSnapshot = gix_fs::SharedFileSnapshot<gix_reftable::Stack>;
+
+type MutableSharedStack = OwnShared<gix_fs::SharedFileSnapshotMut<gix_reftable::Stack>>;
+
+/// A store for references which keeps them along with their logs in [reftables](gix_reftable), as selected by
+/// `extensions.refStorage=reftable`.
+///
+/// All references are stored in the stack of the common directory, except for worktree-private ones of linked worktrees
+/// which are stored in the stack of their private directory.
+/// `FETCH_HEAD` and `MERGE_HEAD` are always stored as files as they can carry more than one value.
+#[derive(Debug, Clone)]
+pub struct Store {
+    /// The store for the pseudo-refs that are always files, which also knows the directories and settings of this store.
+    files: file::Store,
+    /// The stack in the common directory which holds all shared references.
+    common: MutableSharedStack,
+    /// The stack with the worktree-private references of a linked worktree.
+    worktree: MutableSharedStack,
+}
+
+mod error {
+    use gix_object::bstr::BString;
+
+    /// The error returned when accessing references in a reftable stack.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The reftable stack could not be opened")]
+        Open(#[from] gix_reftable::stack::open::Error),
+        #[error("A reftable could not be read")]
+        Decode(#[from] gix_reftable::decode::Error),
+        #[error("The reftable stack contains the reference {name:?} with an invalid name or target")]
+        InvalidReference { name: BString, source: crate::name::Error },
+        #[error("The reflog entry of {name:?} could not be written in the format of reflog files")]
+        InvalidLogEntry { name: BString, source: std::io::Error },
+    }
+}
+pub use error::Error;
+
+mod init {
+    use std::path::PathBuf;
+
+    use crate::{file, store_impl::reftable};
+
+    impl reftable::Store {
+        /// Create a new instance for the repository at `git_dir`, whose stack is expected in the `reftable/` subdirectory.
+        /// Use [`Options`](crate::store::init::Options) to adjust settings, whose [`backend`](crate::store::init::Options::backend)
+        /// is ignored.
+        pub fn at(git_dir: PathBuf, opts: crate::store::init::Options) -> Self {
+            file::Store::at(git_dir, opts).into()
+        }
+
+        /// Like [`at()`][reftable::Store::at()], but for _linked_ work-trees which use `git_dir` for their private references
+        /// and `common_dir` for shared references.
+        pub fn for_linked_worktree(git_dir: PathBuf, common_dir: PathBuf, opts: crate::store::init::Options) -> Self {
+            file::Store::for_linked_worktree(git_dir, common_dir, opts).into()
+        }
+    }
+
+    impl From<file::Store> for reftable::Store {
+        fn from(files: file::Store) -> Self {
+            reftable::Store {
+                files,
+                common: gix_fs::SharedFileSnapshotMut::new().into(),
+                worktree: gix_fs::SharedFileSnapshotMut::new().into(),
+            }
+        }
+    }
+}
+
+mod access {
+    use std::path::Path;
+
+    use crate::{Namespace, Target, store::WriteReflog, store_impl::reftable};
+
+    /// Access
+    impl reftable::Store {
+        /// Return the `.git` directory of the repository, which holds the worktree-private references of linked worktrees.
+        pub fn git_dir(&self) -> &Path {
+            self.files.git_dir()
+        }
+
+        /// If this is a linked work tree, there will be `Some(git_dir)` pointing to the parent repository.
+        pub fn common_dir(&self) -> Option<&Path> {
+            self.files.common_dir()
+        }
+
+        /// Return the [common directory](Self::common_dir()) if set, or the [`git_dir`](Self::git_dir()) otherwise.
+        pub fn common_dir_resolved(&self) -> &Path {
+            self.files.common_dir_resolved()
+        }
+
+        /// Return the namespace all read and write operations are limited to, if set.
+        pub fn namespace(&self) -> Option<&Namespace> {
+            self.files.namespace.as_ref()
+        }
+
+        /// Limit all read and write operations to `namespace`, or see all references if `None`.
+        pub fn set_namespace(&mut self, namespace: Option<Namespace>) {
+            self.files.namespace = namespace;
+        }
+
+        /// Return the way reflogs are written.
+        pub fn write_reflog(&self) -> WriteReflog {
+            self.files.write_reflog
+        }
+
+        /// Set the way reflogs are written to `mode`.
+        pub fn set_write_reflog(&mut self, mode: WriteReflog) {
+            self.files.write_reflog = mode;
+        }
+
+        /// Returns `Some(true)` if this store is untouched since it was initialized, or `None` if it couldn't be read.
+        ///
+        /// This means that `HEAD` still points to `default_ref` and that there are no references in `refs/`.
+        pub fn is_pristine(&self, default_ref: &crate::FullNameRef) -> Option<bool> {
+            let head = self.try_find("HEAD").ok()??;
+            match head.target {
+                Target::Object(_) => return Some(false),
+                Target::Symbolic(name) => {
+                    if name.as_ref() != default_ref {
+                        return Some(false);
+                    }
+                }
+            }
+            if self.iter().all().ok()?.filter_map(Result::ok).next().is_some() {
+                return Some(false);
+            }
+            Some(true)
+        }
+    }
+}
+
+/// Stacks
+impl Store {
+    /// Return an up-to-date snapshot of the reftable stack holding all shared references.
+    ///
+    /// Use it to access reftable specific information, or to [compact](gix_reftable::Stack::compact()) it.
+    pub fn stack(&self) -> Result<SharedStackSnapshot, gix_reftable::stack::open::Error> {
+        self.stack_at(self.common_dir_resolved())
+    }
+
+    fn stack_options(&self) -> gix_reftable::stack::Options {
+        gix_reftable::stack::Options {
+            object_hash: self.files.object_hash,
+            auto_compaction: true,
+            ..Default::default()
+        }
+    }
+
+    /// Return the directory of the repository whose stack stores `name`, along with the name to use within that stack.
+    ///
+    /// The name is namespaced if needed.
+    pub(crate) fn base_and_name<'a>(&self, name: &'a FullNameRef) -> (Cow<'_, Path>, Cow<'a, FullNameRef>) {
+        let is_reflog = true;
+        let (base, name) = self.files.to_base_dir_and_relative_name(name, is_reflog);
+        (
+            base,
+            match self.namespace() {
+                None => Cow::Borrowed(name),
+                Some(namespace) => Cow::Owned(namespace.to_owned().into_namespaced_name(name)),
+            },
+        )
+    }
+
+    fn cached_stack(&self, base: &Path) -> Option<&MutableSharedStack> {
+        if base == self.common_dir_resolved() {
+            Some(&self.common)
+        } else if base == self.git_dir() {
+            Some(&self.worktree)
+        } else {
+            None
+        }
+    }
+
+    /// Return an up-to-date stack for the repository at `base`, which is cached if it's ours.
+    pub(crate) fn stack_at(&self, base: &Path) -> Result<SharedStackSnapshot, gix_reftable::stack::open::Error> {
+        let dir = base.join("reftable");
+        let Some(cached) = self.cached_stack(base) else {
+            return Ok(OwnShared::new(
+                gix_reftable::Stack::at(dir, self.stack_options())?.into(),
+            ));
+        };
+        let list = dir.join(gix_reftable::stack::LIST_FILE);
+        let snapshot = cached.recent_snapshot(
+            || list.metadata().and_then(|m| m.modified()).ok(),
+            || gix_reftable::Stack::at(&dir, self.stack_options()).map(Some),
+        )?;
+        Ok(match snapshot {
+            Some(snapshot) => snapshot,
+            None => OwnShared::new(gix_reftable::Stack::at(dir, self.stack_options())?.into()),
+        })
+    }
+
+    /// Forcefully reload the stack of the repository at `base` after it was changed by us.
+    pub(crate) fn force_refresh_stack(&self, base: &Path) -> Result<(), gix_reftable::stack::open::Error> {
+        let Some(cached) = self.cached_stack(base) else {
+            return Ok(());
+        };
+        let dir = base.join("reftable");
+        cached.force_refresh(|| {
+            let Ok(modified) = dir
+                .join(gix_reftable::stack::LIST_FILE)
+                .metadata()
+                .and_then(|m| m.modified())
+            else {
+                return Ok(None);
+            };
+            gix_reftable::Stack::at(&dir, self.stack_options()).map(|stack| Some((modified, stack)))
+        })
+    }
+}
+
+/// The pseudo-refs which are always stored as files, even in reftable repositories, sorted by name.
+pub(crate) const PSEUDO_REFS_STORED_AS_FILE: &[&str] = &["FETCH_HEAD", "MERGE_HEAD"];
+
+/// Return `true` if `name` is one of the pseudo-refs which are always stored as files, even in reftable repositories.
+pub(crate) fn is_stored_as_file(name: &FullNameRef) -> bool {
+    PSEUDO_REFS_STORED_AS_FILE
+        .iter()
+        .any(|stored_as_file| name.as_bstr() == stored_as_file.as_bytes())
+}
+
+/// Turn `r` into a reference named `name`, or `None` if it's a deletion, and strip `namespace` if set.
+pub(crate) fn to_reference(
+    r: gix_reftable::Ref,
+    name: FullName,
+    namespace: Option<&Namespace>,
+) -> Result<Option<Reference>, Error> {
+    use gix_reftable::RefValue;
+    let (target, peeled) = match r.value {
+        RefValue::Deletion => return Ok(None),
+        RefValue::Object(id) => (Target::Object(id), None),
+        RefValue::Peeled { target, peeled } => (Target::Object(target), Some(peeled)),
+        RefValue::Symbolic(target) => (
+            Target::Symbolic(
+                FullName::try_from(target.as_bstr()).map_err(|source| Error::InvalidReference {
+                    name: r.name.clone(),
+                    source,
+                })?,
+            ),
+            None,
+        ),
+    };
+    let mut r = Reference { name, target, peeled };
+    if let Some(namespace) = namespace {
+        r.strip_namespace(namespace);
+    }
+    Ok(Some(r))
+}
+
+/// Turn `target` into the value to store in a reftable.
+pub(crate) fn to_ref_value(target: &Target) -> gix_reftable::RefValue {
+    match target {
+        Target::Object(id) => gix_reftable::RefValue::Object(*id),
+        Target::Symbolic(name) => gix_reftable::RefValue::Symbolic(BString::from(name.as_bstr())),
+    }
+}
+
+///
+pub mod find;
+
+///
+pub mod iter;
+
+///
+pub mod reflog;
+
+///
+pub mod transaction;
+pub use transaction::Transaction;
@@ -0,0 +1,114 @@
+use std::io::Write;
+
+use crate::{FullNameRef, file::log, store_impl::reftable};
+
+impl reftable::Store {
+    /// Returns true if a reflog with at least one entry exists for the given reference `name`.
+    ///
+    /// Reflogs that can't be read are considered to not exist.
+    pub fn reflog_exists<'a, Name, E>(&self, name: Name) -> Result<bool, E>
+    where
+        Name: TryInto<&'a FullNameRef, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        Ok(self.reflog_lines(name.try_into()?, &mut Vec::new()).unwrap_or_default())
+    }
+
+    /// Return a reflog reverse iterator for the given fully qualified `name`, reading chunks from the back into the fixed buffer `buf`.
+    ///
+    /// The iterator will traverse log entries from most recent to oldest.
+    /// Return `Ok(None)` if no reflog exists.
+    pub fn reflog_iter_rev<'a, 'b, Name, E>(
+        &self,
+        name: Name,
+        buf: &'b mut [u8],
+    ) -> Result<Option<log::iter::Reverse<'b, std::io::Cursor<Vec<u8>>>>, Error>
+    where
+        Name: TryInto<&'a FullNameRef, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        let name: &FullNameRef = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        let mut lines = Vec::new();
+        Ok(if self.reflog_lines(name, &mut lines)? {
+            Some(log::iter::reverse(std::io::Cursor::new(lines), buf)?)
+        } else {
+            None
+        })
+    }
+
+    /// Return a reflog forward iterator for the given fully qualified `name` and write its entries into `buf`
+    /// in the format of reflog files.
+    ///
+    /// The iterator will traverse log entries from oldest to newest.
+    /// Return `Ok(None)` if no reflog exists.
+    pub fn reflog_iter<'a, 'b, Name, E>(
+        &self,
+        name: Name,
+        buf: &'b mut Vec<u8>,
+    ) -> Result<Option<log::iter::Forward<'b>>, Error>
+    where
+        Name: TryInto<&'a FullNameRef, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        let name: &FullNameRef = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        buf.clear();
+        Ok(self.reflog_lines(name, buf)?.then(|| log::iter::forward(buf)))
+    }
+
+    /// Write the reflog of `full_name` into `out` in the format of reflog files, oldest entry first,
+    /// and return `true` if it has at least one entry.
+    fn reflog_lines(&self, full_name: &FullNameRef, out: &mut Vec<u8>) -> Result<bool, reftable::Error> {
+        if reftable::is_stored_as_file(full_name) {
+            return Ok(false);
+        }
+        let (base, name) = self.base_and_name(full_name);
+        let stack = self.stack_at(&base)?;
+        let logs = stack.logs_for(name.as_bstr())?.collect::<Result<Vec<_>, _>>()?;
+        let mut has_entries = false;
+        for log in logs.iter().rev() {
+            let gix_reftable::LogValue::Update {
+                previous_oid,
+                new_oid,
+                signature,
+                message,
+            } = &log.value
+            else {
+                continue;
+            };
+            let message = message.strip_suffix(b"\n").unwrap_or(message);
+            write!(out, "{previous_oid} {new_oid} ")
+                .and_then(|_| signature.write_to(out))
+                .and_then(|_| {
+                    if message.is_empty() {
+                        writeln!(out)
+                    } else {
+                        out.push(b'\t');
+                        out.extend_from_slice(message);
+                        writeln!(out)
+                    }
+                })
+                .map_err(|source| reftable::Error::InvalidLogEntry {
+                    name: log.name.clone(),
+                    source,
+                })?;
+            has_entries = true;
+        }
+        Ok(has_entries)
+    }
+}
+
+mod error {
+    /// The error returned by [`reftable::Store::reflog_iter()`](crate::reftable::Store::reflog_iter()) and
+    /// [`reftable::Store::reflog_iter_rev()`](crate::reftable::Store::reflog_iter_rev()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The reflog name or path is not a valid ref name")]
+        RefnameValidation(#[from] crate::name::Error),
+        #[error("The reflog could not be read from its reftable")]
+        Reftable(#[from] crate::reftable::Error),
+        #[error("The reflog could not be prepared for reading")]
+        Io(#[from] std::io::Error),
+    }
+}
+pub use error::Error;
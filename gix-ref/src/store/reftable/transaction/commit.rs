@@ -0,0 +1,149 @@
+use gix_hash::{ObjectId, oid};
+use gix_object::bstr::BStr;
+
+use crate::{
+    FullNameRef, Target,
+    store::WriteReflog,
+    store_impl::reftable::{self, Transaction},
+    transaction::{Change, PreviousValue, RefEdit},
+};
+
+impl Transaction<'_> {
+    /// Make all [prepared][Transaction::prepare()] permanent and return the performed edits which represent the current
+    /// state of the affected refs in the ref store in that instant. Please note that the obtained edits may have been
+    /// adjusted to contain more dependent edits or additional information.
+    /// `committer` is used in the reflog and only if the reflog is actually written, which is why it is optional.
+    ///
+    /// All changes to a stack are written as a single table, along with their reflog entries, so each stack changes
+    /// atomically. The pseudo-refs that are always stored as files are changed last.
+    pub fn commit<'a>(self, committer: impl Into<Option<gix_actor::SignatureRef<'a>>>) -> Result<Vec<RefEdit>, Error> {
+        self.commit_inner(committer.into())
+    }
+
+    fn commit_inner(self, committer: Option<gix_actor::SignatureRef<'_>>) -> Result<Vec<RefEdit>, Error> {
+        let updates = self.updates.expect("BUG: must call prepare before commit");
+        let mut stacks = self.stacks;
+
+        for change in &updates {
+            assert!(!change.update.deref, "Deref mode is turned into splits and turned off");
+            let Change::Update { log, new, expected } = &change.update.change else {
+                continue;
+            };
+            let log_update = match new {
+                Target::Symbolic(_) => {
+                    // no reflog for symref changes as there is no OID involved which the reflog needs,
+                    // unless the ref is new and we can obtain a peeled id, as is the case when cloning.
+                    match expected {
+                        PreviousValue::ExistingMustMatch(Target::Object(oid)) => {
+                            Some((Some(ObjectId::null(oid.kind())), oid))
+                        }
+                        _ => None,
+                    }
+                }
+                Target::Object(new_oid) => {
+                    let previous = match expected {
+                        PreviousValue::MustExistAndMatch(Target::Object(oid)) => Some(oid.to_owned()),
+                        _ => None,
+                    }
+                    .or(change.leaf_referent_previous_oid);
+                    Some((previous, new_oid))
+                }
+            };
+            if let Some((previous, new_oid)) = log_update {
+                if previous.as_ref() != Some(new_oid) {
+                    let (base, _name) = self.store.base_and_name(change.update.name.as_ref());
+                    let transaction = stacks
+                        .iter_mut()
+                        .find_map(|(existing, transaction)| (*existing == *base).then_some(transaction))
+                        .expect("BUG: transactions of all affected stacks are created when preparing");
+                    self.store.reflog_append(
+                        transaction,
+                        change.update.name.as_ref(),
+                        previous,
+                        new_oid,
+                        committer,
+                        log.message.as_ref(),
+                        log.force_create_reflog,
+                    )?;
+                }
+            }
+        }
+
+        for (base, transaction) in stacks {
+            transaction.commit()?;
+            // Make our own changes visible right away, ignoring errors as the stack will be reloaded later anyway.
+            self.store.force_refresh_stack(&base).ok();
+        }
+
+        let mut edits: Vec<_> = updates.into_iter().map(|edit| edit.update).collect();
+        if let Some(files) = self.files {
+            edits.extend(files.commit(committer)?);
+        }
+        Ok(edits)
+    }
+}
+
+impl reftable::Store {
+    /// Add a log entry for the change of `name` from `previous_oid` to `new` to the `transaction` of the stack that stores it,
+    /// if reflogs are to be written for it.
+    #[allow(clippy::too_many_arguments)]
+    fn reflog_append(
+        &self,
+        transaction: &mut gix_reftable::stack::Transaction,
+        name: &FullNameRef,
+        previous_oid: Option<ObjectId>,
+        new: &oid,
+        committer: Option<gix_actor::SignatureRef<'_>>,
+        message: &BStr,
+        force_create_reflog: bool,
+    ) -> Result<(), Error> {
+        let (_base, name) = self.base_and_name(name);
+        let name = name.as_bstr();
+        let should_write = match self.write_reflog() {
+            WriteReflog::Always => true,
+            WriteReflog::Normal => {
+                force_create_reflog
+                    || self.files.should_autocreate_reflog(&gix_path::from_bstr(name))
+                    || transaction
+                        .stack()
+                        .logs_for(name)
+                        .map_err(reftable::Error::from)?
+                        .next()
+                        .is_some()
+            }
+            WriteReflog::Disable => false,
+        };
+        if should_write {
+            let committer = committer.ok_or(Error::MissingCommitter)?;
+            transaction.add_log(
+                name,
+                gix_reftable::LogValue::Update {
+                    previous_oid: previous_oid.unwrap_or_else(|| new.kind().null()),
+                    new_oid: new.to_owned(),
+                    signature: committer.trim().into(),
+                    message: message.to_owned(),
+                },
+            );
+        }
+        Ok(())
+    }
+}
+
+mod error {
+    use crate::file;
+
+    /// The error returned by [`Transaction::commit()`][super::Transaction::commit()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("reflog messages need a committer which isn't set")]
+        MissingCommitter,
+        #[error("The existing reflog could not be read from its reftable")]
+        Reftable(#[from] crate::reftable::Error),
+        #[error("The changes could not be written to the reftable stack")]
+        Commit(#[from] gix_reftable::stack::transaction::commit::Error),
+        #[error("The edits of pseudo-refs that are stored as files could not be committed")]
+        Files(#[from] file::transaction::commit::Error),
+    }
+}
+pub use error::Error;
@@ -0,0 +1,68 @@
+use std::{fmt::Formatter, path::PathBuf};
+
+use gix_hash::ObjectId;
+
+use crate::{file, store_impl::reftable, transaction::RefEdit};
+
+/// A transaction on a reftable store, which adds one table with all changes to each affected stack.
+pub struct Transaction<'s> {
+    store: &'s reftable::Store,
+    updates: Option<Vec<Edit>>,
+    /// The locked stacks along with the directory of the repository they belong to.
+    stacks: Vec<(PathBuf, gix_reftable::stack::Transaction)>,
+    /// The transaction for the pseudo-refs that are always stored as files, if any of them is edited.
+    files: Option<file::Transaction<'s, 's>>,
+}
+
+#[derive(Debug)]
+struct Edit {
+    update: RefEdit,
+    /// Set if this update is coming from a symbolic reference and used to make it appear like it is the one that is handled,
+    /// instead of the referent reference.
+    parent_index: Option<usize>,
+    /// For symbolic refs, this is the previous OID to put into the reflog instead of our own previous value. It's the
+    /// peeled value of the leaf referent.
+    leaf_referent_previous_oid: Option<ObjectId>,
+}
+
+impl std::borrow::Borrow<RefEdit> for Edit {
+    fn borrow(&self) -> &RefEdit {
+        &self.update
+    }
+}
+
+impl std::borrow::BorrowMut<RefEdit> for Edit {
+    fn borrow_mut(&mut self) -> &mut RefEdit {
+        &mut self.update
+    }
+}
+
+/// Edits
+impl reftable::Store {
+    /// Open a transaction to edit references, which are written atomically per stack once it's committed.
+    ///
+    /// The transaction inherits the parent namespace.
+    pub fn transaction(&self) -> Transaction<'_> {
+        Transaction {
+            store: self,
+            updates: None,
+            stacks: Vec::new(),
+            files: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for Transaction<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Transaction")
+            .field("store", self.store)
+            .field("edits", &self.updates.as_ref().map(Vec::len))
+            .finish_non_exhaustive()
+    }
+}
+
+///
+pub mod prepare;
+
+///
+pub mod commit;
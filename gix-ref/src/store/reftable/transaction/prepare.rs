@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+
+use gix_object::bstr::BString;
+
+use crate::{
+    store_impl::reftable::{self, Transaction, transaction::Edit},
+    transaction::{Change, Mismatch, RefEdit, RefEditsExt, RefLog},
+};
+
+impl Transaction<'_> {
+    /// Prepare for calling [`commit(…)`][Transaction::commit()] by locking all affected stacks, failing according to
+    /// `lock_fail_mode` if they are already locked, and verifying that all edits can be applied.
+    ///
+    /// If the operation succeeds, the transaction can be committed or dropped to cause a rollback automatically.
+    pub fn prepare(
+        self,
+        edits: impl IntoIterator<Item = RefEdit>,
+        lock_fail_mode: gix_lock::acquire::Fail,
+    ) -> Result<Self, Error> {
+        self.prepare_inner(&mut edits.into_iter(), lock_fail_mode)
+    }
+
+    fn prepare_inner(
+        mut self,
+        edits: &mut dyn Iterator<Item = RefEdit>,
+        lock_fail_mode: gix_lock::acquire::Fail,
+    ) -> Result<Self, Error> {
+        assert!(self.updates.is_none(), "BUG: Must not call prepare(…) multiple times");
+        let store = self.store;
+        let (file_edits, edits): (Vec<_>, Vec<_>) =
+            edits.partition(|edit| reftable::is_stored_as_file(edit.name.as_ref()));
+        if !file_edits.is_empty() {
+            self.files = Some(
+                store
+                    .files
+                    .transaction()
+                    .prepare(file_edits, lock_fail_mode, lock_fail_mode)?,
+            );
+        }
+
+        let mut updates: Vec<_> = edits
+            .into_iter()
+            .map(|update| Edit {
+                update,
+                parent_index: None,
+                leaf_referent_previous_oid: None,
+            })
+            .collect();
+        updates
+            .pre_process(
+                &mut |name| store.try_find(name).ok().flatten().map(|r| r.target),
+                &mut |idx, update| Edit {
+                    update,
+                    parent_index: Some(idx),
+                    leaf_referent_previous_oid: None,
+                },
+            )
+            .map_err(Error::PreprocessingFailed)?;
+
+        for cid in 0..updates.len() {
+            let change = &mut updates[cid];
+            Self::lock_stack_and_apply_change(store, &mut self.stacks, lock_fail_mode, change)?;
+
+            // traverse parent chain from leaf/peeled ref and set the leaf previous oid accordingly
+            // to help with their reflog entries
+            if let (Some(crate::TargetRef::Object(oid)), Some(parent_idx)) =
+                (change.update.change.previous_value(), change.parent_index)
+            {
+                let oid = oid.to_owned();
+                let mut parent_idx_cursor = Some(parent_idx);
+                while let Some(parent) = parent_idx_cursor.take().map(|idx| &mut updates[idx]) {
+                    parent_idx_cursor = parent.parent_index;
+                    parent.leaf_referent_previous_oid = Some(oid);
+                }
+            }
+        }
+        self.updates = Some(updates);
+        Ok(self)
+    }
+
+    /// Apply `change` to the transaction of the stack that stores it, which is locked and added to `stacks` on first use.
+    fn lock_stack_and_apply_change(
+        store: &reftable::Store,
+        stacks: &mut Vec<(PathBuf, gix_reftable::stack::Transaction)>,
+        lock_fail_mode: gix_lock::acquire::Fail,
+        change: &mut Edit,
+    ) -> Result<(), Error> {
+        let (base, name) = store.base_and_name(change.update.name.as_ref());
+        let transaction = match stacks.iter().position(|(existing, _)| *existing == *base) {
+            Some(idx) => &mut stacks[idx].1,
+            None => {
+                let stack = store.stack_at(&base).map_err(reftable::Error::from)?;
+                let transaction = stack.transaction(lock_fail_mode).map_err(|err| match err {
+                    gix_reftable::stack::transaction::Error::Lock(source) => Error::LockAcquire {
+                        source,
+                        full_name: change.update.name.as_bstr().to_owned(),
+                    },
+                    gix_reftable::stack::transaction::Error::CreateDir { source, .. } => Error::Io(source),
+                    gix_reftable::stack::transaction::Error::Open(err) => reftable::Error::from(err).into(),
+                })?;
+                stacks.push((base.into_owned(), transaction));
+                &mut stacks.last_mut().expect("just pushed").1
+            }
+        };
+        let existing_ref = match transaction
+            .stack()
+            .find_ref(name.as_bstr())
+            .map_err(reftable::Error::from)?
+        {
+            Some(r) => reftable::to_reference(r, change.update.name.clone(), None)?,
+            None => None,
+        };
+        let name = name.into_owned().into_inner();
+        let RefEdit {
+            name: full_name,
+            change,
+            ..
+        } = &mut change.update;
+        match change {
+            Change::Delete { expected, log } => {
+                expected
+                    .verify_deletion(existing_ref)
+                    .map_err(|err| Error::from_mismatch(full_name.as_bstr().to_owned(), err))?;
+                if *log == RefLog::AndReference {
+                    transaction.add_ref(name.clone(), gix_reftable::RefValue::Deletion);
+                }
+                transaction.delete_logs(&name).map_err(reftable::Error::from)?;
+            }
+            Change::Update { expected, new, log } => {
+                let (is_effective, _is_symbolic) =
+                    expected
+                        .verify_update(new, existing_ref, store.files.object_hash)
+                        .map_err(|err| Error::from_mismatch(full_name.as_bstr().to_owned(), err))?;
+                if is_effective && log.mode == RefLog::AndReference {
+                    transaction.add_ref(name, reftable::to_ref_value(new));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Rollback all intermediate state and return the `RefEdits` as we know them thus far.
+    ///
+    /// Note that they have been altered compared to what was initially provided as they have
+    /// been split and know about their current state.
+    ///
+    /// # Note
+    ///
+    /// A rollback happens automatically as this instance is dropped as well.
+    pub fn rollback(self) -> Vec<RefEdit> {
+        let mut edits: Vec<_> = self
+            .updates
+            .map(|updates| updates.into_iter().map(|u| u.update).collect())
+            .unwrap_or_default();
+        edits.extend(self.files.map(crate::file::Transaction::rollback).unwrap_or_default());
+        edits
+    }
+}
+
+mod error {
+    use gix_object::bstr::BString;
+
+    use crate::{Target, file};
+
+    /// The error returned by [`Transaction::prepare()`][super::Transaction::prepare()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Edit preprocessing failed with an error")]
+        PreprocessingFailed(#[source] std::io::Error),
+        #[error("The reftable stack could not be locked when changing reference {full_name:?}")]
+        LockAcquire {
+            source: gix_lock::acquire::Error,
+            full_name: BString,
+        },
+        #[error("An IO error occurred while applying an edit")]
+        Io(#[from] std::io::Error),
+        #[error("The reftable stack could not be read")]
+        Reftable(#[from] crate::reftable::Error),
+        #[error("The edits of pseudo-refs that are stored as files could not be prepared")]
+        Files(#[from] file::transaction::prepare::Error),
+        #[error("The reference {full_name:?} for deletion did not exist")]
+        DeleteReferenceMustExist { full_name: BString },
+        #[error(
+            "Reference {full_name:?} was not supposed to exist when writing it with value {new:?}, but actual content was {actual:?}"
+        )]
+        MustNotExist {
+            full_name: BString,
+            actual: Target,
+            new: Target,
+        },
+        #[error("Reference {full_name:?} was supposed to exist with value {expected}, but didn't.")]
+        MustExist { full_name: BString, expected: Target },
+        #[error("The reference {full_name:?} should have content {expected}, actual content was {actual}")]
+        ReferenceOutOfDate {
+            full_name: BString,
+            expected: Target,
+            actual: Target,
+        },
+    }
+}
+pub use error::Error;
+
+impl Error {
+    fn from_mismatch(full_name: BString, mismatch: Mismatch) -> Self {
+        match mismatch {
+            Mismatch::DeletedMustExist => Error::DeleteReferenceMustExist { full_name },
+            Mismatch::MustNotExist { actual, new } => Error::MustNotExist { full_name, actual, new },
+            Mismatch::MustExist { expected } => Error::MustExist { full_name, expected },
+            Mismatch::OutOfDate { expected, actual } => Error::ReferenceOutOfDate {
+                full_name,
+                expected,
+                actual,
+            },
+        }
+    }
+}
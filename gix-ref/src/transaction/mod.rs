@@ -132,3 +132,6 @@ pub enum RefLog {
 
 mod ext;
 pub use ext::RefEditsExt;
+
+mod verify;
+pub(crate) use verify::Mismatch;
@@ -0,0 +1,112 @@
+use crate::{Reference, Target, transaction::PreviousValue};
+
+/// The way the actual value of a reference contradicts the [expectation](PreviousValue) of an edit.
+#[derive(Debug)]
+pub(crate) enum Mismatch {
+    /// The reference to delete was supposed to exist, but didn't.
+    DeletedMustExist,
+    /// The reference wasn't supposed to exist, but did with a value that differs from the `new` one.
+    MustNotExist { actual: Target, new: Target },
+    /// The reference was supposed to exist with the `expected` value, but didn't.
+    MustExist { expected: Target },
+    /// The reference was supposed to have the `expected` value, but had the `actual` one.
+    OutOfDate { expected: Target, actual: Target },
+}
+
+impl PreviousValue {
+    /// Check that the `existing` reference matches our expectation for a reference that is to be deleted,
+    /// and remember its value so it can be used for the reflog or by the caller.
+    pub(crate) fn verify_deletion(&mut self, existing: Option<Reference>) -> Result<(), Mismatch> {
+        match (&*self, &existing) {
+            (PreviousValue::MustNotExist, _) => {
+                panic!("BUG: MustNotExist constraint makes no sense if references are to be deleted")
+            }
+            (PreviousValue::ExistingMustMatch(_) | PreviousValue::Any, None)
+            | (PreviousValue::MustExist | PreviousValue::Any, Some(_)) => {}
+            (PreviousValue::MustExist | PreviousValue::MustExistAndMatch(_), None) => {
+                return Err(Mismatch::DeletedMustExist);
+            }
+            (
+                PreviousValue::MustExistAndMatch(previous) | PreviousValue::ExistingMustMatch(previous),
+                Some(existing),
+            ) => {
+                if *previous != existing.target {
+                    return Err(Mismatch::OutOfDate {
+                        expected: previous.clone(),
+                        actual: existing.target.clone(),
+                    });
+                }
+            }
+        }
+
+        // Keep the previous value for the caller and ourselves. Maybe they want to keep a log of sorts.
+        if let Some(existing) = existing {
+            *self = PreviousValue::MustExistAndMatch(existing.target);
+        }
+        Ok(())
+    }
+
+    /// Check that the `existing` reference matches our expectation for a reference that is to be set to `new`,
+    /// and remember its value so it can be used for the reflog or by the caller.
+    /// `object_hash` is used to describe the expected value of references which must exist.
+    ///
+    /// Return `(is_effective, is_symbolic)` to learn if `new` changes the reference, and if the change involves a symbolic reference.
+    pub(crate) fn verify_update(
+        &mut self,
+        new: &Target,
+        existing: Option<Reference>,
+        object_hash: gix_hash::Kind,
+    ) -> Result<(bool, bool), Mismatch> {
+        match (&*self, &existing) {
+            (PreviousValue::Any, _)
+            | (PreviousValue::MustExist, Some(_))
+            | (PreviousValue::MustNotExist | PreviousValue::ExistingMustMatch(_), None) => {}
+            (PreviousValue::MustExist, None) => {
+                return Err(Mismatch::MustExist {
+                    expected: Target::Object(object_hash.null()),
+                });
+            }
+            (PreviousValue::MustNotExist, Some(existing)) => {
+                if existing.target != *new {
+                    return Err(Mismatch::MustNotExist {
+                        actual: existing.target.clone(),
+                        new: new.clone(),
+                    });
+                }
+            }
+            (
+                PreviousValue::MustExistAndMatch(previous) | PreviousValue::ExistingMustMatch(previous),
+                Some(existing),
+            ) => {
+                if *previous != existing.target {
+                    return Err(Mismatch::OutOfDate {
+                        expected: previous.to_owned(),
+                        actual: existing.target.clone(),
+                    });
+                }
+            }
+            (PreviousValue::MustExistAndMatch(previous), None) => {
+                return Err(Mismatch::MustExist {
+                    expected: previous.to_owned(),
+                });
+            }
+        }
+
+        fn new_would_change_existing(new: &Target, existing: &Target) -> (bool, bool) {
+            match (new, existing) {
+                (Target::Object(new), Target::Object(old)) => (old != new, false),
+                (Target::Symbolic(new), Target::Symbolic(old)) => (old != new, true),
+                (Target::Object(_), _) => (true, false),
+                (Target::Symbolic(_), _) => (true, true),
+            }
+        }
+
+        Ok(if let Some(existing) = existing {
+            let (effective, is_symbolic) = new_would_change_existing(new, &existing.target);
+            *self = PreviousValue::MustExistAndMatch(existing.target);
+            (effective, is_symbolic)
+        } else {
+            (true, matches!(new, Target::Symbolic(_)))
+        })
+    }
+}
@@ -21,3 +21,391 @@ fn is_send_and_sync() {
     assert_type(&store);
     assert_type(store);
 }
+
+mod handle {
+    use gix_date::parse::TimeBuf;
+    use gix_lock::acquire::Fail;
+    use gix_ref::store::find::existing;
+
+    use crate::file::{
+        store_options,
+        transaction::prepare_and_commit::{committer, create_at},
+    };
+
+    fn store_at(name: &str) -> crate::Result<gix_ref::Store> {
+        let path = crate::scripted_fixture_read_only(name)?;
+        Ok(gix_ref::Store::at(path.join(".git"), store_options())?)
+    }
+
+    #[test]
+    fn find_and_iterate_loose_and_packed_refs() -> crate::Result {
+        let store = store_at("make_packed_ref_repository_for_overlay.sh")?;
+        let handle = store.to_handle();
+        assert_eq!(handle.namespace(), None);
+
+        let main = handle.find("main")?;
+        assert_eq!(main.name.as_bstr(), "refs/heads/main", "packed refs are found");
+        assert_eq!(
+            handle.find("HEAD")?.target.try_name().expect("symbolic").as_bstr(),
+            "refs/heads/newer-as-loose",
+            "loose refs are found"
+        );
+        assert!(handle.try_find("does-not-exist")?.is_none());
+        assert!(matches!(
+            handle.find("does-not-exist"),
+            Err(existing::Error::NotFound { .. })
+        ));
+        assert!(matches!(
+            handle.try_find("invalid/../name"),
+            Err(gix_ref::store::find::Error::RefnameValidation(_))
+        ));
+
+        let file_store = crate::file::store_at("make_packed_ref_repository_for_overlay.sh")?;
+        assert_eq!(
+            names(handle.iter()?.all()?)?,
+            names(file_store.iter()?.all()?)?,
+            "the handle iterates exactly like the file store"
+        );
+        assert_eq!(
+            names(handle.iter()?.prefixed(b"refs/remotes/".try_into()?)?)?,
+            ["refs/remotes/origin/HEAD", "refs/remotes/origin/main"]
+        );
+        Ok(())
+    }
+
+    fn names(
+        iter: impl Iterator<Item = Result<gix_ref::Reference, impl std::error::Error + Send + Sync + 'static>>,
+    ) -> crate::Result<Vec<gix_ref::bstr::BString>> {
+        Ok(iter
+            .map(|r| r.map(|r| r.name.as_bstr().to_owned()))
+            .collect::<Result<_, _>>()?)
+    }
+
+    #[test]
+    fn transactions_and_reflogs_respect_the_namespace() -> crate::Result {
+        let dir = gix_testtools::tempfile::TempDir::new()?;
+        let store = gix_ref::Store::at(dir.path().into(), store_options())?;
+        let handle = store.to_handle_namespaced(Some(gix_ref::namespace::expand("ns")?));
+
+        let mut buf = TimeBuf::default();
+        let edits = handle
+            .transaction()
+            .prepare(Some(create_at("refs/heads/main")), Fail::Immediately, Fail::Immediately)?
+            .commit(committer().to_ref(&mut buf))?;
+        assert_eq!(
+            edits[0].name.as_bstr(),
+            "refs/heads/main",
+            "the namespace isn't observable"
+        );
+        assert!(
+            dir.path().join("refs/namespaces/ns/refs/heads/main").is_file(),
+            "but it is used on disk"
+        );
+
+        assert_eq!(handle.find("main")?.name.as_bstr(), "refs/heads/main");
+        assert!(
+            store.to_handle().try_find("main")?.is_none(),
+            "handles without namespace don't see it as branch"
+        );
+        assert_eq!(
+            store
+                .to_handle()
+                .find("refs/namespaces/ns/refs/heads/main")?
+                .name
+                .as_bstr(),
+            "refs/namespaces/ns/refs/heads/main"
+        );
+
+        assert!(handle.reflog_exists("refs/heads/main")?);
+        let mut log_buf = Vec::new();
+        let lines = handle
+            .reflog_iter("refs/heads/main", &mut log_buf)?
+            .expect("log was forced")
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].message, "log peeled");
+        let mut rev_buf = [0u8; 256];
+        assert_eq!(
+            handle
+                .reflog_iter_rev("refs/heads/main", &mut rev_buf)?
+                .expect("present")
+                .count(),
+            1
+        );
+        assert!(!store.to_handle().reflog_exists("refs/heads/main")?);
+        Ok(())
+    }
+
+    #[test]
+    fn missing_directories_are_an_error() {
+        let dir = gix_testtools::tempfile::TempDir::new().unwrap();
+        assert!(gix_ref::Store::at(dir.path().join("missing"), store_options()).is_err());
+    }
+
+    #[cfg(feature = "reftable")]
+    mod reftable {
+        use gix_date::parse::TimeBuf;
+        use gix_lock::acquire::Fail;
+        use gix_ref::{
+            Target,
+            store::{Backend, WriteReflog},
+            transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+        };
+
+        use super::names;
+        use crate::file::{
+            store_options,
+            transaction::prepare_and_commit::{committer, create_at},
+        };
+
+        fn reftable_options() -> gix_ref::store::init::Options {
+            gix_ref::store::init::Options {
+                write_reflog: WriteReflog::Normal,
+                backend: Backend::Reftable,
+                ..store_options()
+            }
+        }
+
+        #[test]
+        fn read_references_and_reflogs_written_by_git() -> crate::Result {
+            let dir = match crate::scripted_fixture_read_only("make_reftable_repo.sh") {
+                Ok(dir) => dir,
+                Err(_) if *gix_testtools::GIT_VERSION < (2, 45, 0) => {
+                    eprintln!("Fixture script failure ignored as it looks like Git isn't recent enough.");
+                    return Ok(());
+                }
+                Err(err) => return Err(err),
+            };
+            let main_id = gix_hash::ObjectId::from_hex(gix_testtools::git(&dir, "rev-parse main")?.trim().as_bytes())?;
+            let store = gix_ref::Store::at(dir.join("reftable-clone").join(".git"), reftable_options())?;
+            assert_eq!(store.backend(), Backend::Reftable);
+            let handle = store.to_handle();
+
+            assert_eq!(
+                handle.find("HEAD")?.target.try_name().expect("symbolic").as_bstr(),
+                "refs/heads/main",
+                "HEAD is read from the reftable, not from the stub file"
+            );
+            let main = handle.find("main")?;
+            assert_eq!(main.name.as_bstr(), "refs/heads/main");
+            assert_eq!(main.target.try_id(), Some(main_id.as_ref()));
+            assert_eq!(
+                handle.find("origin")?.name.as_bstr(),
+                "refs/remotes/origin/HEAD",
+                "partial names are looked up like in the file store"
+            );
+
+            assert_eq!(
+                names(handle.iter()?.all()?)?,
+                [
+                    "refs/heads/main",
+                    "refs/remotes/origin/HEAD",
+                    "refs/remotes/origin/main"
+                ]
+            );
+            assert_eq!(
+                names(handle.iter()?.prefixed(b"refs/remotes/".try_into()?)?)?,
+                ["refs/remotes/origin/HEAD", "refs/remotes/origin/main"]
+            );
+            assert_eq!(names(handle.iter()?.pseudo()?)?, ["HEAD"]);
+
+            assert!(handle.reflog_exists("refs/heads/main")?);
+            let mut buf = Vec::new();
+            let lines = handle
+                .reflog_iter("refs/heads/main", &mut buf)?
+                .expect("git writes a log when cloning")
+                .map(|line| line.map(gix_ref::log::Line::from))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines.len(), 1);
+            assert!(lines[0].previous_oid.is_null());
+            assert_eq!(lines[0].new_oid, main_id);
+            let mut rev_buf = [0u8; 256];
+            assert_eq!(
+                handle
+                    .reflog_iter_rev("refs/heads/main", &mut rev_buf)?
+                    .expect("present")
+                    .count(),
+                1
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn journey() -> crate::Result {
+            let tmp = gix_testtools::tempfile::TempDir::new()?;
+            let store = gix_ref::Store::at(tmp.path().into(), reftable_options())?;
+            let handle = store.to_handle();
+            assert!(handle.try_find("HEAD")?.is_none());
+
+            let mut buf = TimeBuf::default();
+            let fetch_head = RefEdit {
+                name: "FETCH_HEAD".try_into()?,
+                ..create_at("refs/heads/main")
+            };
+            handle
+                .transaction()
+                .prepare(
+                    [
+                        create_at("refs/heads/main"),
+                        RefEdit {
+                            change: Change::Update {
+                                log: LogChange::default(),
+                                expected: PreviousValue::MustNotExist,
+                                new: Target::Symbolic("refs/heads/main".try_into()?),
+                            },
+                            name: "HEAD".try_into()?,
+                            deref: false,
+                        },
+                        fetch_head,
+                    ],
+                    Fail::Immediately,
+                    Fail::Immediately,
+                )?
+                .commit(committer().to_ref(&mut buf))?;
+            assert!(tmp.path().join("reftable").join("tables.list").is_file());
+            assert!(
+                !tmp.path().join("refs").join("heads").join("main").exists(),
+                "no loose references are written"
+            );
+            assert!(
+                tmp.path().join("FETCH_HEAD").is_file(),
+                "FETCH_HEAD is always stored as file"
+            );
+            assert_eq!(
+                gix_ref::reftable::Store::at(tmp.path().into(), reftable_options())
+                    .stack()?
+                    .tables()
+                    .len(),
+                1,
+                "all changes to the stack are written as one table"
+            );
+
+            let main = handle.find("main")?;
+            assert_eq!(main.name.as_bstr(), "refs/heads/main");
+            assert_eq!(
+                handle.find("HEAD")?.target.try_name().expect("symbolic").as_bstr(),
+                "refs/heads/main"
+            );
+            assert_eq!(handle.find("FETCH_HEAD")?.target, main.target);
+            assert_eq!(names(handle.iter()?.all()?)?, ["refs/heads/main"]);
+            assert_eq!(names(handle.iter()?.pseudo()?)?, ["FETCH_HEAD", "HEAD"]);
+
+            let mut log_buf = Vec::new();
+            let lines = handle
+                .reflog_iter("refs/heads/main", &mut log_buf)?
+                .expect("log was forced")
+                .map(|line| line.map(gix_ref::log::Line::from))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines.len(), 1);
+            assert_eq!(lines[0].message, "log peeled");
+            assert_eq!(lines[0].signature, committer());
+            assert!(lines[0].previous_oid.is_null());
+            assert_eq!(Some(lines[0].new_oid), main.target.try_id().map(ToOwned::to_owned));
+            assert!(
+                !handle.reflog_exists("HEAD")?,
+                "symbolic refs don't get a log when they are created"
+            );
+
+            let err = handle
+                .transaction()
+                .prepare(
+                    Some(RefEdit {
+                        change: Change::Update {
+                            log: LogChange::default(),
+                            expected: PreviousValue::MustNotExist,
+                            new: Target::Symbolic("refs/heads/other".try_into()?),
+                        },
+                        name: "refs/heads/main".try_into()?,
+                        deref: false,
+                    }),
+                    Fail::Immediately,
+                    Fail::Immediately,
+                )
+                .unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    gix_ref::store::transaction::prepare::Error::Reftable(
+                        gix_ref::reftable::transaction::prepare::Error::MustNotExist { .. }
+                    )
+                ),
+                "expectations are verified against the reftable: {err:?}"
+            );
+
+            handle
+                .transaction()
+                .prepare(
+                    Some(RefEdit {
+                        change: Change::Delete {
+                            expected: PreviousValue::MustExistAndMatch(main.target),
+                            log: RefLog::AndReference,
+                        },
+                        name: main.name,
+                        deref: false,
+                    }),
+                    Fail::Immediately,
+                    Fail::Immediately,
+                )?
+                .commit(committer().to_ref(&mut buf))?;
+            assert!(handle.try_find("main")?.is_none());
+            assert!(
+                !handle.reflog_exists("refs/heads/main")?,
+                "logs are deleted with the reference"
+            );
+            assert_eq!(handle.iter()?.all()?.count(), 0);
+            Ok(())
+        }
+
+        #[test]
+        fn transactions_and_reflogs_respect_the_namespace() -> crate::Result {
+            let tmp = gix_testtools::tempfile::TempDir::new()?;
+            let store = gix_ref::Store::at(tmp.path().into(), reftable_options())?;
+            let handle = store.to_handle_namespaced(Some(gix_ref::namespace::expand("ns")?));
+
+            let mut buf = TimeBuf::default();
+            let edits = handle
+                .transaction()
+                .prepare(Some(create_at("refs/heads/main")), Fail::Immediately, Fail::Immediately)?
+                .commit(committer().to_ref(&mut buf))?;
+            assert_eq!(
+                edits[0].name.as_bstr(),
+                "refs/heads/main",
+                "the namespace isn't observable"
+            );
+
+            assert_eq!(handle.find("main")?.name.as_bstr(), "refs/heads/main");
+            assert_eq!(names(handle.iter()?.all()?)?, ["refs/heads/main"]);
+            assert!(handle.reflog_exists("refs/heads/main")?);
+
+            let unnamespaced = store.to_handle();
+            assert!(
+                unnamespaced.try_find("main")?.is_none(),
+                "handles without namespace don't see it as branch"
+            );
+            assert_eq!(
+                names(unnamespaced.iter()?.all()?)?,
+                ["refs/namespaces/ns/refs/heads/main"],
+                "but it is used in the reftable"
+            );
+            assert!(unnamespaced.reflog_exists("refs/namespaces/ns/refs/heads/main")?);
+            assert!(!unnamespaced.reflog_exists("refs/heads/main")?);
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "reftable"))]
+    fn reftables_need_the_reftable_feature() {
+        let dir = gix_testtools::tempfile::TempDir::new().unwrap();
+        assert!(matches!(
+            gix_ref::Store::at(
+                dir.path().into(),
+                gix_ref::store::init::Options {
+                    backend: gix_ref::store::Backend::Reftable,
+                    ..store_options()
+                }
+            ),
+            Err(gix_ref::store::general::init::Error::ReftableUnsupported)
+        ));
+    }
+}
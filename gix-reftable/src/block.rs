@@ -0,0 +1,347 @@
+//! Reading and writing of blocks, the unit of storage within a table which holds prefix-compressed records of a single kind.
+use std::borrow::Cow;
+
+use crate::{decode::Error, record, varint};
+
+/// The block holds references.
+pub(crate) const REF: u8 = b'r';
+/// The block maps object ids to the blocks of references pointing to them.
+pub(crate) const OBJ: u8 = b'o';
+/// The block holds compressed reference logs.
+pub(crate) const LOG: u8 = b'g';
+/// The block is part of an index of another section.
+pub(crate) const INDEX: u8 = b'i';
+
+/// The size of the header of each block, its type and its length.
+pub(crate) const HEADER_LEN: usize = 4;
+
+/// The amount of bytes a restart offset occupies.
+const RESTART_LEN: usize = 3;
+
+pub(crate) fn be24(data: &[u8]) -> usize {
+    (usize::from(data[0]) << 16) | (usize::from(data[1]) << 8) | usize::from(data[2])
+}
+
+pub(crate) fn put_be24(value: usize, out: &mut [u8]) {
+    out[0] = (value >> 16) as u8;
+    out[1] = (value >> 8) as u8;
+    out[2] = value as u8;
+}
+
+/// A decoded block, whose data starts at the beginning of the block, which for the first block of a table includes
+/// the table header.
+pub(crate) struct Block<'a> {
+    data: Cow<'a, [u8]>,
+    /// The offset of the block within the table.
+    pub offset: usize,
+    /// The type of records in this block.
+    pub kind: u8,
+    /// The offset at which the records start.
+    records_start: usize,
+    /// The offset at which the records end and the restart offsets start.
+    restarts_start: usize,
+    /// The amount of restart points.
+    restart_count: usize,
+    /// The amount of bytes the block occupies in the table, without padding.
+    pub stored_len: usize,
+}
+
+/// The position of the next record to read from a block, along with the key of the previous one to
+/// undo the prefix compression.
+#[derive(Clone, Default)]
+pub(crate) struct Cursor {
+    pos: usize,
+    key: Vec<u8>,
+}
+
+impl Cursor {
+    /// The key of the record read last.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+}
+
+impl<'a> Block<'a> {
+    /// Decode the block at `offset` of `table`, whose header starts `header_len` bytes after `offset`, which is only
+    /// non-zero for the first block.
+    /// Return `None` if `offset` is at or past `end`, the position at which the footer of the table starts.
+    pub fn at(table: &'a [u8], offset: usize, header_len: usize, end: usize) -> Result<Option<Self>, Error> {
+        let corrupt = |message| Error::Block {
+            offset: offset as u64,
+            message,
+        };
+        let header_start = offset + header_len;
+        if header_start + HEADER_LEN > end {
+            return Ok(None);
+        }
+        let kind = table[header_start];
+        if !matches!(kind, REF | OBJ | LOG | INDEX) {
+            return Err(corrupt("unknown block type"));
+        }
+        let block_len = be24(&table[header_start + 1..]);
+        let records_start = header_len + HEADER_LEN;
+        if block_len < records_start + 2 {
+            return Err(corrupt("block length is too small"));
+        }
+        let (data, stored_len) = if kind == LOG {
+            // Leave room for one more byte so the end of the stream can be observed.
+            let mut data = Vec::with_capacity(block_len + 1);
+            data.extend_from_slice(&table[offset..offset + records_start]);
+            data.resize(block_len + 1, 0);
+            let mut inflate = gix_features::zlib::Inflate::default();
+            let (status, consumed, written) = inflate
+                .once(&table[offset + records_start..end], &mut data[records_start..])
+                .map_err(|err| Error::Inflate {
+                    offset: offset as u64,
+                    source: err,
+                })?;
+            if status != gix_features::zlib::Status::StreamEnd || written != block_len - records_start {
+                return Err(corrupt("compressed log records don't match the block length"));
+            }
+            data.truncate(block_len);
+            (Cow::Owned(data), records_start + consumed)
+        } else {
+            if offset + block_len > end {
+                return Err(corrupt("block extends past the end of the table"));
+            }
+            (Cow::Borrowed(&table[offset..offset + block_len]), block_len)
+        };
+
+        let restart_count = usize::from(u16::from_be_bytes([data[block_len - 2], data[block_len - 1]]));
+        let restarts_start = (block_len - 2)
+            .checked_sub(restart_count * RESTART_LEN)
+            .filter(|start| *start >= records_start)
+            .ok_or_else(|| corrupt("restart offsets don't fit into the block"))?;
+        Ok(Some(Block {
+            data,
+            offset,
+            kind,
+            records_start,
+            restarts_start,
+            restart_count,
+            stored_len,
+        }))
+    }
+
+    /// Return the offset at which the next block starts, given the `block_size` of the table.
+    pub fn next_offset(&self, table: &[u8], block_size: usize) -> usize {
+        let end = self.offset + self.stored_len;
+        let is_unpadded = self.stored_len < block_size && table.get(end).is_some_and(|b| *b != 0);
+        if self.kind == LOG || block_size == 0 || is_unpadded {
+            end
+        } else {
+            self.offset + block_size
+        }
+    }
+
+    /// Return a copy of this block which doesn't borrow the table.
+    pub fn into_owned(self) -> Block<'static> {
+        Block {
+            data: Cow::Owned(self.data.into_owned()),
+            offset: self.offset,
+            kind: self.kind,
+            records_start: self.records_start,
+            restarts_start: self.restarts_start,
+            restart_count: self.restart_count,
+            stored_len: self.stored_len,
+        }
+    }
+
+    /// Return a cursor pointing to the first record.
+    pub fn start(&self) -> Cursor {
+        Cursor {
+            pos: self.records_start,
+            key: Vec::new(),
+        }
+    }
+
+    fn corrupt(&self, message: &'static str) -> Error {
+        Error::Block {
+            offset: self.offset as u64,
+            message,
+        }
+    }
+
+    fn restart_offset(&self, idx: usize) -> usize {
+        be24(&self.data[self.restarts_start + idx * RESTART_LEN..])
+    }
+
+    /// Decode the next record at `cursor` and advance it, or return `None` if there is none.
+    /// The key of the record is available through the cursor afterwards.
+    pub fn next(&self, cursor: &mut Cursor, ctx: &record::Context) -> Result<Option<record::Record>, Error> {
+        if cursor.pos >= self.restarts_start {
+            return Ok(None);
+        }
+        let (value_type, payload_start) = self.decode_key(cursor.pos, &mut cursor.key)?;
+        let data = &self.data[payload_start..self.restarts_start];
+        let (record, consumed) = record::decode(self.kind, &cursor.key, value_type, data, ctx)
+            .ok_or_else(|| self.corrupt("could not decode record"))?;
+        cursor.pos = payload_start + consumed;
+        Ok(Some(record))
+    }
+
+    /// Decode the key at `pos` into `key`, which holds the previous key, and return the value type along with the
+    /// position at which the value starts.
+    fn decode_key(&self, pos: usize, key: &mut Vec<u8>) -> Result<(u8, usize), Error> {
+        let data = &self.data[..self.restarts_start];
+        let mut pos = pos;
+        let mut next_varint = || {
+            let (value, consumed) = varint::decode(&data[pos..]).ok_or_else(|| self.corrupt("truncated record key"))?;
+            pos += consumed;
+            Ok::<_, Error>(value)
+        };
+        let prefix_len = next_varint()? as usize;
+        let suffix_and_type = next_varint()?;
+        let suffix_len = (suffix_and_type >> 3) as usize;
+        if prefix_len > key.len() {
+            return Err(self.corrupt("key prefix is longer than the previous key"));
+        }
+        let suffix = data
+            .get(pos..pos + suffix_len)
+            .ok_or_else(|| self.corrupt("truncated record key"))?;
+        key.truncate(prefix_len);
+        key.extend_from_slice(suffix);
+        Ok(((suffix_and_type & 0x7) as u8, pos + suffix_len))
+    }
+
+    /// Return a cursor from which the first record with a key greater than or equal to `want` will be read,
+    /// or `None` if there is no such record in this block.
+    pub fn seek(&self, want: &[u8], ctx: &record::Context) -> Result<Option<Cursor>, Error> {
+        // Find the first restart point whose key is greater than `want`, and start searching at the one before it.
+        let (mut lo, mut hi) = (0, self.restart_count);
+        let mut key = Vec::new();
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            key.clear();
+            self.decode_key(self.restart_offset(mid), &mut key)?;
+            if key.as_slice() > want {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        let mut cursor = Cursor {
+            pos: if lo == 0 {
+                self.records_start
+            } else {
+                self.restart_offset(lo - 1)
+            },
+            key: Vec::new(),
+        };
+        loop {
+            let before = cursor.clone();
+            if self.next(&mut cursor, ctx)?.is_none() {
+                return Ok(None);
+            }
+            if cursor.key.as_slice() >= want {
+                return Ok(Some(before));
+            }
+        }
+    }
+}
+
+/// Accumulates records into a block.
+pub(crate) struct Writer {
+    buf: Vec<u8>,
+    kind: u8,
+    /// The offset at which the block header starts, which is non-zero only for the first block to leave room for
+    /// the table header.
+    header_len: usize,
+    block_size: usize,
+    restart_interval: usize,
+    restarts: Vec<u32>,
+    since_restart: usize,
+    last_key: Vec<u8>,
+    count: usize,
+}
+
+impl Writer {
+    pub fn new(kind: u8, header_len: usize, block_size: usize, restart_interval: usize) -> Self {
+        let mut buf = Vec::with_capacity(block_size);
+        buf.resize(header_len, 0);
+        buf.extend_from_slice(&[kind, 0, 0, 0]);
+        Writer {
+            buf,
+            kind,
+            header_len,
+            block_size,
+            restart_interval,
+            restarts: Vec::new(),
+            since_restart: 0,
+            last_key: Vec::new(),
+            count: 0,
+        }
+    }
+
+    pub fn kind(&self) -> u8 {
+        self.kind
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The key of the record added last.
+    pub fn last_key(&self) -> &[u8] {
+        &self.last_key
+    }
+
+    /// Add a record with `key`, `value_type` and `value` and return `true`, or return `false` if it doesn't fit.
+    /// The first record of a log block is always accepted, even if it exceeds the block size.
+    pub fn add(&mut self, key: &[u8], value_type: u8, value: &[u8]) -> bool {
+        let is_restart = self.count == 0 || self.since_restart >= self.restart_interval;
+        let prefix_len = if is_restart {
+            0
+        } else {
+            key.iter().zip(&self.last_key).take_while(|(a, b)| a == b).count()
+        };
+        let suffix = &key[prefix_len..];
+        let mut record = Vec::with_capacity(suffix.len() + value.len() + 4);
+        varint::encode(prefix_len as u64, &mut record);
+        varint::encode(((suffix.len() as u64) << 3) | u64::from(value_type), &mut record);
+        record.extend_from_slice(suffix);
+        record.extend_from_slice(value);
+
+        let restarts = self.restarts.len() + usize::from(is_restart);
+        let len = self.buf.len() + record.len() + restarts * RESTART_LEN + 2;
+        let fits = len <= self.block_size && len < (1 << 24) && restarts <= usize::from(u16::MAX);
+        let is_oversized_first_log = self.count == 0 && self.kind == LOG;
+        if !fits && !is_oversized_first_log {
+            return false;
+        }
+        if is_restart {
+            self.restarts.push(self.buf.len() as u32);
+            self.since_restart = 0;
+        }
+        self.since_restart += 1;
+        self.count += 1;
+        self.buf.extend_from_slice(&record);
+        self.last_key.clear();
+        self.last_key.extend_from_slice(key);
+        true
+    }
+
+    /// Finish the block and return its bytes, with the first `header_len` bytes left for the table header.
+    /// Log blocks are compressed.
+    pub fn finish(mut self) -> Vec<u8> {
+        for restart in &self.restarts {
+            let mut offset = [0u8; RESTART_LEN];
+            put_be24(*restart as usize, &mut offset);
+            self.buf.extend_from_slice(&offset);
+        }
+        self.buf.extend_from_slice(&(self.restarts.len() as u16).to_be_bytes());
+        let block_len = self.buf.len();
+        put_be24(block_len, &mut self.buf[self.header_len + 1..]);
+        if self.kind != LOG {
+            return self.buf;
+        }
+
+        let records_start = self.header_len + HEADER_LEN;
+        let mut out = self.buf[..records_start].to_vec();
+        let mut deflate = gix_features::zlib::stream::deflate::Write::new(&mut out);
+        std::io::Write::write_all(&mut deflate, &self.buf[records_start..])
+            .and_then(|_| std::io::Write::flush(&mut deflate))
+            .expect("writing to memory doesn't fail");
+        out
+    }
+}
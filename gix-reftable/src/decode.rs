@@ -0,0 +1,24 @@
+/// The error returned when reading a [table](crate::Table) fails because its content is invalid.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The table is too short to hold a header and a footer")]
+    TooShort,
+    #[error("Expected the table to start with the 'REFT' signature")]
+    Signature,
+    #[error("Reftable version {version} is unsupported")]
+    UnsupportedVersion { version: u8 },
+    #[error("The hash function with id {id:?} is unsupported")]
+    UnsupportedHash { id: [u8; 4] },
+    #[error("The footer doesn't repeat the header of the table")]
+    FooterMismatch,
+    #[error("The checksum of the footer was expected to be {expected:08x}, but was {actual:08x}")]
+    Checksum { expected: u32, actual: u32 },
+    #[error("The block at offset {offset} is corrupt: {message}")]
+    Block { offset: u64, message: &'static str },
+    #[error("The log block at offset {offset} could not be decompressed")]
+    Inflate {
+        offset: u64,
+        source: gix_features::zlib::inflate::Error,
+    },
+}
@@ -0,0 +1,107 @@
+//! Read and write [reftables](https://git-scm.com/docs/reftable), the binary format git uses to store references and
+//! their logs as an alternative to loose files and `packed-refs` when `extensions.refStorage` is set to `reftable`.
+//!
+//! A reftable is an immutable file with sorted blocks of [references](Ref), optionally an index from object ids to the
+//! blocks of references pointing to them, and zlib-compressed blocks of [reference logs](Log). Records within a
+//! block are prefix-compressed against their predecessor, with uncompressed restart points in between to allow
+//! binary searches, and sections with more than a few blocks get an index to find the block holding a key quickly.
+//!
+//! * [`Table`] reads a single table, and [`write::Writer`] creates one.
+//! * [`Stack`] represents the `reftable/` directory of a repository, a stack of tables listed in `tables.list` where
+//!   newer tables override the records of older ones. It provides a merged view of all tables, adds new tables with
+//!   [transactions](stack::Transaction) and compacts them to keep the amount of tables logarithmic to the amount of
+//!   changes.
+#![deny(missing_docs, unsafe_code)]
+
+use bstr::BString;
+use gix_hash::ObjectId;
+
+/// A reference as stored in a reftable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub struct Ref {
+    /// The full name of the reference, like `refs/heads/main` or `HEAD`.
+    pub name: BString,
+    /// The update index of the change that wrote this record.
+    pub update_index: u64,
+    /// The value of the reference.
+    pub value: RefValue,
+}
+
+/// The value of a [reference](Ref).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum RefValue {
+    /// The reference was deleted, which hides its values in older tables.
+    Deletion,
+    /// The reference points to an object.
+    Object(ObjectId),
+    /// The reference points to an annotated tag, along with the object the tag ultimately points to.
+    Peeled {
+        /// The object the reference points to.
+        target: ObjectId,
+        /// The object obtained by peeling `target`.
+        peeled: ObjectId,
+    },
+    /// The reference points to another reference by its full name.
+    Symbolic(BString),
+}
+
+impl RefValue {
+    /// Return `true` if this value points to `id`, either directly or after peeling.
+    pub(crate) fn points_to(&self, id: &gix_hash::oid) -> bool {
+        match self {
+            RefValue::Object(target) => target.as_ref() == id,
+            RefValue::Peeled { target, peeled } => target.as_ref() == id || peeled.as_ref() == id,
+            RefValue::Deletion | RefValue::Symbolic(_) => false,
+        }
+    }
+}
+
+/// An entry of the log of a reference, as stored in a reftable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub struct Log {
+    /// The full name of the reference the entry belongs to.
+    pub name: BString,
+    /// The update index of the change that produced this entry, which orders the entries of a reference.
+    pub update_index: u64,
+    /// The content of the entry.
+    pub value: LogValue,
+}
+
+/// The content of a [log entry](Log).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum LogValue {
+    /// The entry was deleted, which hides the entry with the same update index in older tables.
+    Deletion,
+    /// The reference changed its value.
+    Update {
+        /// The previous value of the reference, or the null id if it was created.
+        previous_oid: ObjectId,
+        /// The new value of the reference, or the null id if it was deleted.
+        new_oid: ObjectId,
+        /// The name and email of the one making the change, along with the time at which it was made.
+        signature: gix_actor::Signature,
+        /// The message describing the change.
+        ///
+        /// Git stores it with a trailing newline, which is written by [`write::Writer::add_log()`] if it's missing.
+        message: BString,
+    },
+}
+
+mod varint;
+
+mod block;
+mod record;
+
+///
+pub mod decode;
+
+///
+pub mod table;
+pub use table::Table;
+
+///
+pub mod write;
+
+///
+pub mod stack;
+pub use stack::Stack;
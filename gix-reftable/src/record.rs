@@ -0,0 +1,248 @@
+//! Encoding and decoding of the values of records, whose keys are handled by blocks.
+use bstr::BString;
+use gix_hash::ObjectId;
+
+use crate::{Log, LogValue, Ref, RefValue, block, varint};
+
+/// Information about the table needed to decode records.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Context {
+    pub object_hash: gix_hash::Kind,
+    pub min_update_index: u64,
+}
+
+/// A decoded record.
+pub(crate) enum Record {
+    Ref(Ref),
+    Log(Log),
+    /// The positions of the ref blocks holding references that point to objects whose id starts with the key.
+    /// If empty, all ref blocks have to be searched.
+    Obj(Vec<u64>),
+    /// The position of the block whose last key is the key of this record.
+    Index(u64),
+}
+
+mod value_type {
+    pub const REF_DELETION: u8 = 0;
+    pub const REF_OBJECT: u8 = 1;
+    pub const REF_PEELED: u8 = 2;
+    pub const REF_SYMBOLIC: u8 = 3;
+
+    pub const LOG_DELETION: u8 = 0;
+    pub const LOG_UPDATE: u8 = 1;
+}
+
+/// A helper to consume `data` piece by piece.
+struct Input<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Input<'a> {
+    fn varint(&mut self) -> Option<u64> {
+        let (value, consumed) = varint::decode(&self.data[self.pos..])?;
+        self.pos += consumed;
+        Some(value)
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn sized_bytes(&mut self) -> Option<&'a [u8]> {
+        let len = usize::try_from(self.varint()?).ok()?;
+        self.bytes(len)
+    }
+
+    fn oid(&mut self, kind: gix_hash::Kind) -> Option<ObjectId> {
+        self.bytes(kind.len_in_bytes()).map(ObjectId::from_bytes_or_panic)
+    }
+}
+
+/// Decode the value of the record with `key` and `value_type` from the beginning of `data`, assuming it's stored in a
+/// block of `kind`, and return it along with the amount of bytes it occupied.
+pub(crate) fn decode(kind: u8, key: &[u8], value_type: u8, data: &[u8], ctx: &Context) -> Option<(Record, usize)> {
+    let mut input = Input { data, pos: 0 };
+    let record = match kind {
+        block::REF => {
+            let update_index = ctx.min_update_index.checked_add(input.varint()?)?;
+            let value = match value_type {
+                value_type::REF_DELETION => RefValue::Deletion,
+                value_type::REF_OBJECT => RefValue::Object(input.oid(ctx.object_hash)?),
+                value_type::REF_PEELED => RefValue::Peeled {
+                    target: input.oid(ctx.object_hash)?,
+                    peeled: input.oid(ctx.object_hash)?,
+                },
+                value_type::REF_SYMBOLIC => RefValue::Symbolic(input.sized_bytes()?.into()),
+                _ => return None,
+            };
+            Record::Ref(Ref {
+                name: key.into(),
+                update_index,
+                value,
+            })
+        }
+        block::LOG => {
+            let (name, update_index) = split_log_key(key)?;
+            let value = match value_type {
+                value_type::LOG_DELETION => LogValue::Deletion,
+                value_type::LOG_UPDATE => {
+                    let previous_oid = input.oid(ctx.object_hash)?;
+                    let new_oid = input.oid(ctx.object_hash)?;
+                    let name = input.sized_bytes()?.into();
+                    let email = input.sized_bytes()?.into();
+                    let seconds = i64::try_from(input.varint()?).ok()?;
+                    let offset = i16::from_be_bytes(input.bytes(2)?.try_into().ok()?);
+                    let message = input.sized_bytes()?.into();
+                    LogValue::Update {
+                        previous_oid,
+                        new_oid,
+                        signature: gix_actor::Signature {
+                            name,
+                            email,
+                            time: gix_date::Time {
+                                seconds,
+                                offset: offset_to_seconds(offset),
+                            },
+                        },
+                        message,
+                    }
+                }
+                _ => return None,
+            };
+            Record::Log(Log {
+                name: name.into(),
+                update_index,
+                value,
+            })
+        }
+        block::OBJ => {
+            let count = match value_type {
+                0 => usize::try_from(input.varint()?).ok()?,
+                count => usize::from(count),
+            };
+            let mut positions = Vec::with_capacity(count.min(data.len()));
+            let mut last = 0u64;
+            for idx in 0..count {
+                let value = input.varint()?;
+                last = if idx == 0 { value } else { last.checked_add(value)? };
+                positions.push(last);
+            }
+            Record::Obj(positions)
+        }
+        block::INDEX => Record::Index(input.varint()?),
+        _ => return None,
+    };
+    Some((record, input.pos))
+}
+
+/// Return the key under which the log of `name` at `update_index` is stored.
+pub(crate) fn log_key(name: &[u8], update_index: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(name.len() + 9);
+    key.extend_from_slice(name);
+    key.push(0);
+    key.extend_from_slice(&(u64::MAX - update_index).to_be_bytes());
+    key
+}
+
+/// Split a log `key` into the reference name and the update index.
+pub(crate) fn split_log_key(key: &[u8]) -> Option<(&[u8], u64)> {
+    let split = key.len().checked_sub(9)?;
+    let (name, rest) = key.split_at(split);
+    if rest[0] != 0 {
+        return None;
+    }
+    Some((name, u64::MAX - u64::from_be_bytes(rest[1..].try_into().ok()?)))
+}
+
+/// Encode the value of `r` and return its value type, assuming the table starts at `min_update_index`.
+pub(crate) fn encode_ref(r: &Ref, min_update_index: u64, out: &mut Vec<u8>) -> u8 {
+    varint::encode(r.update_index - min_update_index, out);
+    match &r.value {
+        RefValue::Deletion => value_type::REF_DELETION,
+        RefValue::Object(id) => {
+            out.extend_from_slice(id.as_slice());
+            value_type::REF_OBJECT
+        }
+        RefValue::Peeled { target, peeled } => {
+            out.extend_from_slice(target.as_slice());
+            out.extend_from_slice(peeled.as_slice());
+            value_type::REF_PEELED
+        }
+        RefValue::Symbolic(target) => {
+            put_sized_bytes(target, out);
+            value_type::REF_SYMBOLIC
+        }
+    }
+}
+
+/// Encode the value of `log` and return its value type.
+/// The message is normalized to end with exactly one newline, like git does.
+pub(crate) fn encode_log(log: &Log, out: &mut Vec<u8>) -> u8 {
+    match &log.value {
+        LogValue::Deletion => value_type::LOG_DELETION,
+        LogValue::Update {
+            previous_oid,
+            new_oid,
+            signature,
+            message,
+        } => {
+            out.extend_from_slice(previous_oid.as_slice());
+            out.extend_from_slice(new_oid.as_slice());
+            put_sized_bytes(&signature.name, out);
+            put_sized_bytes(&signature.email, out);
+            varint::encode(u64::try_from(signature.time.seconds).unwrap_or_default(), out);
+            out.extend_from_slice(&seconds_to_offset(signature.time.offset).to_be_bytes());
+            let mut message = message.as_slice();
+            while let Some(stripped) = message.strip_suffix(b"\n") {
+                message = stripped;
+            }
+            varint::encode(message.len() as u64 + 1, out);
+            out.extend_from_slice(message);
+            out.push(b'\n');
+            value_type::LOG_UPDATE
+        }
+    }
+}
+
+/// Encode `positions` of ref blocks as value of an obj record, and return its value type.
+pub(crate) fn encode_obj(positions: &[u64], out: &mut Vec<u8>) -> u8 {
+    let value_type = if (1..8).contains(&positions.len()) {
+        positions.len() as u8
+    } else {
+        varint::encode(positions.len() as u64, out);
+        0
+    };
+    let mut last = 0;
+    for (idx, position) in positions.iter().enumerate() {
+        varint::encode(if idx == 0 { *position } else { position - last }, out);
+        last = *position;
+    }
+    value_type
+}
+
+/// Encode the `position` of a block as value of an index record, and return its value type.
+pub(crate) fn encode_index(position: u64, out: &mut Vec<u8>) -> u8 {
+    varint::encode(position, out);
+    0
+}
+
+fn put_sized_bytes(bytes: &BString, out: &mut Vec<u8>) {
+    varint::encode(bytes.len() as u64, out);
+    out.extend_from_slice(bytes);
+}
+
+/// Convert a timezone offset as stored in tables, in decimal `hhmm` form, to seconds.
+fn offset_to_seconds(offset: i16) -> i32 {
+    let minutes = i32::from(offset.unsigned_abs() / 100) * 60 + i32::from(offset.unsigned_abs() % 100);
+    minutes * 60 * i32::from(offset.signum())
+}
+
+/// Convert a timezone offset in seconds to the decimal `hhmm` form stored in tables.
+fn seconds_to_offset(seconds: i32) -> i16 {
+    let minutes = seconds.unsigned_abs() / 60;
+    let hhmm = (minutes / 60 * 100 + minutes % 60) as i16;
+    if seconds < 0 { -hhmm } else { hhmm }
+}
@@ -0,0 +1,123 @@
+use std::{io::Write, ops::Range, sync::Arc};
+
+use crate::{
+    Stack, Table,
+    stack::{
+        Logs, Refs, table_name,
+        transaction::{self, commit},
+    },
+    write,
+};
+
+/// The error returned by [`Stack::compact()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Transaction(#[from] transaction::Error),
+    #[error(transparent)]
+    Commit(#[from] commit::Error),
+}
+
+impl Stack {
+    /// Lock the list of tables, failing according to `fail` if it's already locked, and merge all tables into one,
+    /// dropping all deleted references and log entries. Return the compacted stack.
+    pub fn compact(&self, fail: gix_lock::acquire::Fail) -> Result<Stack, Error> {
+        let transaction = self.transaction(fail)?;
+        let mut stack = transaction.stack;
+        if stack.tables.len() < 2 {
+            return Ok(stack);
+        }
+        let obsolete = stack.compact_range(0..stack.tables.len())?;
+        stack
+            .commit_list(transaction.lock, obsolete)
+            .map_err(commit::Error::from)?;
+        Ok(stack)
+    }
+
+    /// Compact the newest tables if they are too large in relation to the ones below them, so that each table is at
+    /// least twice as large as the one above it. Return the names of the tables that were replaced.
+    pub(super) fn auto_compact(&mut self) -> Result<Vec<String>, commit::Error> {
+        let sizes: Vec<_> = self.tables.iter().map(|t| t.payload_len()).collect();
+        match compaction_segment(&sizes) {
+            Some(range) => self.compact_range(range),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Merge the tables in `range` into a single one, and return the names of the tables it replaces.
+    /// Deletions are dropped if there are no older tables in which they could hide anything.
+    fn compact_range(&mut self, range: Range<usize>) -> Result<Vec<String>, commit::Error> {
+        let tables = &self.tables[range.clone()];
+        let keep_deletions = range.start > 0;
+        let min_update_index = tables.first().map_or(0, |t| t.min_update_index());
+        let max_update_index = tables.last().map_or(0, |t| t.max_update_index());
+        let refs = Refs::new(tables, b"", keep_deletions)?;
+        let logs = Logs::new(tables, None, keep_deletions)?;
+        let (name, table) = self.write_table(min_update_index, max_update_index, |out| {
+            for r in refs {
+                out.add_ref(&r?)?;
+            }
+            for log in logs {
+                out.add_log(&log?)?;
+            }
+            Ok(())
+        })?;
+        self.tables.splice(range.clone(), [table]);
+        Ok(self.names.splice(range, [name]).collect())
+    }
+
+    /// Write a new table for records with update indices from `min_update_index` to `max_update_index` into the
+    /// directory of the stack, with `add_records` adding all records to it.
+    /// Return the name of the new table and the table itself.
+    pub(super) fn write_table(
+        &self,
+        min_update_index: u64,
+        max_update_index: u64,
+        add_records: impl FnOnce(&mut write::Writer<Vec<u8>>) -> Result<(), commit::Error>,
+    ) -> Result<(String, Arc<Table>), commit::Error> {
+        let mut out = write::Writer::new(
+            Vec::new(),
+            self.options.object_hash,
+            min_update_index,
+            max_update_index,
+            self.options.write,
+        )?;
+        add_records(&mut out)?;
+        let data = out.finish()?;
+
+        let name = table_name(min_update_index, max_update_index);
+        let mut file = gix_tempfile::new(
+            &self.dir,
+            gix_tempfile::ContainingDirectory::Exists,
+            gix_tempfile::AutoRemove::Tempfile,
+        )?;
+        file.write_all(&data)?;
+        file.persist(self.dir.join(&name)).map_err(|err| err.error)?;
+        Ok((name, Arc::new(Table::from_bytes(data)?)))
+    }
+}
+
+/// Return the range of tables to compact, given the `sizes` of all tables from the oldest to the newest,
+/// so that each table is at least twice as large as the one after it, or `None` if no compaction is needed.
+///
+/// This is the same algorithm that git uses.
+fn compaction_segment(sizes: &[u64]) -> Option<Range<usize>> {
+    const FACTOR: u64 = 2;
+    // Find the newest table that is too large in relation to the one before it. Tables after it are fine
+    // and remain as they are.
+    let mut end = (1..sizes.len()).rev().find(|&i| sizes[i - 1] < sizes[i] * FACTOR)?;
+    let mut bytes = sizes[end];
+    let mut start = end;
+    // Walk towards older tables and extend the segment to each one smaller than twice the accumulated size of the
+    // tables after it. Keep going even if one is large enough, as an older one may still be too small.
+    for i in (1..=end).rev() {
+        let current = bytes;
+        bytes += sizes[i - 1];
+        if sizes[i - 1] < current * FACTOR {
+            start = i - 1;
+        }
+    }
+    end += 1;
+    (end - start > 1).then_some(start..end)
+}
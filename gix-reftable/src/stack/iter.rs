@@ -0,0 +1,146 @@
+use std::{cmp::Ordering, sync::Arc};
+
+use crate::{Log, LogValue, Ref, RefValue, Table, decode::Error, table};
+
+/// A record that can be merged across tables.
+trait Record {
+    fn cmp_key(&self, other: &Self) -> Ordering;
+    fn is_deletion(&self) -> bool;
+}
+
+impl Record for Ref {
+    fn cmp_key(&self, other: &Self) -> Ordering {
+        self.name.cmp(&other.name)
+    }
+
+    fn is_deletion(&self) -> bool {
+        self.value == RefValue::Deletion
+    }
+}
+
+impl Record for Log {
+    fn cmp_key(&self, other: &Self) -> Ordering {
+        self.name
+            .cmp(&other.name)
+            .then_with(|| other.update_index.cmp(&self.update_index))
+    }
+
+    fn is_deletion(&self) -> bool {
+        self.value == LogValue::Deletion
+    }
+}
+
+/// Merges the records of iterators over multiple tables, where records of newer tables hide the ones with the same key
+/// in older tables.
+struct Merged<I: Iterator> {
+    /// The iterators from the newest to the oldest table, along with the next record of each.
+    iters: Vec<(I, Option<I::Item>)>,
+    keep_deletions: bool,
+}
+
+impl<I, T> Merged<I>
+where
+    I: Iterator<Item = Result<T, Error>>,
+    T: Record,
+{
+    fn new(iters: impl DoubleEndedIterator<Item = Result<I, Error>>, keep_deletions: bool) -> Result<Self, Error> {
+        let mut iters = iters
+            .rev()
+            .map(|iter| iter.map(|iter| (iter, None)))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (iter, next) in &mut iters {
+            *next = iter.next();
+        }
+        Ok(Merged { iters, keep_deletions })
+    }
+
+    fn advance(&mut self, idx: usize) -> Option<I::Item> {
+        let (iter, next) = &mut self.iters[idx];
+        std::mem::replace(next, iter.next())
+    }
+
+    fn next_record(&mut self) -> Option<Result<T, Error>> {
+        loop {
+            let mut min: Option<usize> = None;
+            for idx in 0..self.iters.len() {
+                let record = match &self.iters[idx].1 {
+                    Some(Ok(record)) => record,
+                    Some(Err(_)) => {
+                        let err = self.iters[idx].1.take();
+                        self.iters.clear();
+                        return err;
+                    }
+                    None => continue,
+                };
+                let is_smaller = min.is_none_or(|min| match &self.iters[min].1 {
+                    Some(Ok(min_record)) => record.cmp_key(min_record) == Ordering::Less,
+                    _ => unreachable!("only indices of records are remembered"),
+                });
+                if is_smaller {
+                    min = Some(idx);
+                }
+            }
+            let Some(Ok(record)) = self.advance(min?) else {
+                unreachable!("the record was present")
+            };
+            for idx in 0..self.iters.len() {
+                if matches!(&self.iters[idx].1, Some(Ok(other)) if other.cmp_key(&record) == Ordering::Equal) {
+                    self.advance(idx);
+                }
+            }
+            if self.keep_deletions || !record.is_deletion() {
+                return Some(Ok(record));
+            }
+        }
+    }
+}
+
+/// An iterator over the references of a [`Stack`](crate::Stack), created by
+/// [`Stack::refs()`](crate::Stack::refs()) and [`Stack::refs_prefixed()`](crate::Stack::refs_prefixed()).
+pub struct Refs {
+    inner: Merged<table::Refs>,
+}
+
+impl Refs {
+    pub(crate) fn new(tables: &[Arc<Table>], prefix: &[u8], keep_deletions: bool) -> Result<Self, Error> {
+        Ok(Refs {
+            inner: Merged::new(tables.iter().map(|t| t.refs_prefixed(prefix)), keep_deletions)?,
+        })
+    }
+}
+
+impl Iterator for Refs {
+    type Item = Result<Ref, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_record()
+    }
+}
+
+/// An iterator over the log entries of a [`Stack`](crate::Stack), created by
+/// [`Stack::logs()`](crate::Stack::logs()) and [`Stack::logs_for()`](crate::Stack::logs_for()).
+pub struct Logs {
+    inner: Merged<table::Logs>,
+}
+
+impl Logs {
+    pub(crate) fn new(tables: &[Arc<Table>], name: Option<&[u8]>, keep_deletions: bool) -> Result<Self, Error> {
+        Ok(Logs {
+            inner: Merged::new(
+                tables.iter().map(|t| match name {
+                    Some(name) => t.logs_for(name),
+                    None => t.logs(),
+                }),
+                keep_deletions,
+            )?,
+        })
+    }
+}
+
+impl Iterator for Logs {
+    type Item = Result<Log, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_record()
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use gix_hash::oid;
+
+use crate::{Ref, RefValue, Table, decode, write};
+
+/// The name of the file listing the tables of a stack, from the oldest to the newest.
+pub const LIST_FILE: &str = "tables.list";
+
+/// The options to control how a [`Stack`] is read and written.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// The kind of hash all tables are expected to use.
+    pub object_hash: gix_hash::Kind,
+    /// How to write new tables.
+    pub write: write::Options,
+    /// If `true`, tables are compacted after each [transaction](Transaction) to keep their amount logarithmic to the
+    /// amount of changes, as git does.
+    pub auto_compaction: bool,
+}
+
+/// A stack of tables in a directory, listed in its `tables.list` file, which together represent all references and
+/// their logs.
+///
+/// Tables later in the list override the records of earlier ones. It's a snapshot of the state on disk at the time
+/// it was [opened](Stack::at()) or [reloaded](Stack::reload()).
+#[derive(Debug, Clone)]
+pub struct Stack {
+    dir: PathBuf,
+    options: Options,
+    /// The file names of the tables, from the oldest to the newest.
+    names: Vec<String>,
+    tables: Vec<Arc<Table>>,
+}
+
+///
+pub mod open {
+    /// The error returned by [`Stack::at()`](crate::Stack::at()) and [`Stack::reload()`](crate::Stack::reload()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read the list of tables at '{path}'")]
+        ReadList {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+        #[error(transparent)]
+        Table(#[from] crate::table::open::Error),
+        #[error("The table {name:?} uses object hash {actual}, but {expected} was expected")]
+        ObjectHash {
+            name: String,
+            actual: gix_hash::Kind,
+            expected: gix_hash::Kind,
+        },
+    }
+}
+
+mod iter;
+pub use iter::{Logs, Refs};
+
+///
+pub mod transaction;
+pub use transaction::Transaction;
+
+///
+pub mod compact;
+
+/// Lifecycle
+impl Stack {
+    /// Open the stack in `dir`, which is empty if `dir` or its `tables.list` file don't exist.
+    pub fn at(dir: impl Into<PathBuf>, options: Options) -> Result<Self, open::Error> {
+        let mut stack = Stack {
+            dir: dir.into(),
+            options,
+            names: Vec::new(),
+            tables: Vec::new(),
+        };
+        stack.reload()?;
+        Ok(stack)
+    }
+
+    /// Read the list of tables again to see changes made since this instance was created, and open all tables
+    /// that weren't open yet.
+    pub fn reload(&mut self) -> Result<(), open::Error> {
+        // Tables may be deleted after we read the list if it changes concurrently, so read it again in that case.
+        let mut attempts = 0;
+        loop {
+            let names = self.read_list()?;
+            match self.open_tables(&names) {
+                Ok(tables) => {
+                    self.names = names;
+                    self.tables = tables;
+                    return Ok(());
+                }
+                Err(open::Error::Table(crate::table::open::Error::Io { source, .. }))
+                    if source.kind() == std::io::ErrorKind::NotFound && attempts < 10 && self.read_list()? != names =>
+                {
+                    attempts += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn read_list(&self) -> Result<Vec<String>, open::Error> {
+        let path = self.dir.join(LIST_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(list) => Ok(list.lines().filter(|l| !l.is_empty()).map(ToOwned::to_owned).collect()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(open::Error::ReadList { path, source: err }),
+        }
+    }
+
+    fn open_tables(&self, names: &[String]) -> Result<Vec<Arc<Table>>, open::Error> {
+        names
+            .iter()
+            .map(|name| {
+                if let Some(idx) = self.names.iter().position(|existing| existing == name) {
+                    return Ok(self.tables[idx].clone());
+                }
+                let table = Table::at(&self.dir.join(name))?;
+                if table.object_hash() != self.options.object_hash {
+                    return Err(open::Error::ObjectHash {
+                        name: name.clone(),
+                        actual: table.object_hash(),
+                        expected: self.options.object_hash,
+                    });
+                }
+                Ok(Arc::new(table))
+            })
+            .collect()
+    }
+}
+
+/// Access
+impl Stack {
+    /// The directory holding the tables and their list.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The options used to read and write the stack.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// The tables of the stack, from the oldest to the newest.
+    pub fn tables(&self) -> &[Arc<Table>] {
+        &self.tables
+    }
+
+    /// The file names of the tables of the stack, from the oldest to the newest.
+    pub fn table_names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+
+    /// The update index the next table added to the stack would use.
+    pub fn next_update_index(&self) -> u64 {
+        self.tables.last().map_or(1, |t| t.max_update_index() + 1)
+    }
+}
+
+/// Lookup
+impl Stack {
+    /// Find the reference named `name`, or return `None` if it doesn't exist or was deleted.
+    pub fn find_ref(&self, name: &[u8]) -> Result<Option<Ref>, decode::Error> {
+        for table in self.tables.iter().rev() {
+            if let Some(r) = table.find_ref(name)? {
+                return Ok((r.value != RefValue::Deletion).then_some(r));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return an iterator over all references in the order of their names.
+    pub fn refs(&self) -> Result<Refs, decode::Error> {
+        self.refs_prefixed(b"")
+    }
+
+    /// Return an iterator over all references whose name starts with `prefix`, in the order of their names.
+    pub fn refs_prefixed(&self, prefix: &[u8]) -> Result<Refs, decode::Error> {
+        Refs::new(&self.tables, prefix, false)
+    }
+
+    /// Return an iterator over all log entries ordered by reference name and from the newest to the oldest entry.
+    pub fn logs(&self) -> Result<Logs, decode::Error> {
+        Logs::new(&self.tables, None, false)
+    }
+
+    /// Return an iterator over all log entries of the reference named `name`, from the newest to the oldest entry.
+    pub fn logs_for(&self, name: &[u8]) -> Result<Logs, decode::Error> {
+        Logs::new(&self.tables, Some(name), false)
+    }
+
+    /// Return all references that point to `id`, either directly or after peeling, in the order of their names.
+    pub fn refs_pointing_to(&self, id: &oid) -> Result<Vec<Ref>, decode::Error> {
+        let mut names = Vec::new();
+        for table in &self.tables {
+            names.extend(table.refs_pointing_to(id)?.into_iter().map(|r| r.name));
+        }
+        names.sort();
+        names.dedup();
+
+        let mut out = Vec::new();
+        for name in names {
+            // Newer tables may have changed or deleted the reference.
+            if let Some(r) = self.find_ref(&name)?.filter(|r| r.value.points_to(id)) {
+                out.push(r);
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Return a file name for a new table holding records with update indices from `min_update_index` to `max_update_index`.
+fn table_name(min_update_index: u64, max_update_index: u64) -> String {
+    use std::hash::{BuildHasher, Hasher};
+    let random = std::collections::hash_map::RandomState::new().build_hasher().finish() as u32;
+    format!("0x{min_update_index:012x}-0x{max_update_index:012x}-{random:08x}.ref")
+}
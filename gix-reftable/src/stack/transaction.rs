@@ -0,0 +1,190 @@
+use std::io::Write;
+
+use bstr::BString;
+
+use crate::{Log, LogValue, Ref, RefValue, Stack, stack::LIST_FILE};
+
+/// The error returned by [`Stack::transaction()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not create the directory of the stack at '{path}'")]
+    CreateDir {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Could not lock the list of tables")]
+    Lock(#[from] gix_lock::acquire::Error),
+    #[error(transparent)]
+    Open(#[from] super::open::Error),
+}
+
+///
+pub mod commit {
+    use bstr::BString;
+
+    /// The error returned by [`Transaction::commit()`](super::Transaction::commit()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The reference {name:?} was changed more than once")]
+        DuplicateRef { name: BString },
+        #[error("The log entry of {name:?} with update index {update_index} was added more than once")]
+        DuplicateLog { name: BString, update_index: u64 },
+        #[error("Could not read the existing tables")]
+        Decode(#[from] crate::decode::Error),
+        #[error(transparent)]
+        Write(#[from] crate::write::Error),
+        #[error("Could not write a table or the list of tables")]
+        Io(#[from] std::io::Error),
+    }
+}
+
+/// A set of changes to references and their logs that are added to a [`Stack`] as a new table, atomically.
+///
+/// It holds the lock on the list of tables until it's committed or dropped.
+pub struct Transaction {
+    pub(super) stack: Stack,
+    pub(super) lock: gix_lock::File,
+    update_index: u64,
+    refs: Vec<Ref>,
+    logs: Vec<Log>,
+}
+
+/// Lifecycle
+impl Stack {
+    /// Lock the list of tables, failing according to `fail` if it's already locked, and return a transaction that
+    /// sees the stack as it is on disk.
+    pub fn transaction(&self, fail: gix_lock::acquire::Fail) -> Result<Transaction, Error> {
+        std::fs::create_dir_all(&self.dir).map_err(|err| Error::CreateDir {
+            path: self.dir.clone(),
+            source: err,
+        })?;
+        let lock = gix_lock::File::acquire_to_update_resource(self.dir.join(LIST_FILE), fail, None)?;
+        let mut stack = self.clone();
+        stack.reload()?;
+        Ok(Transaction {
+            update_index: stack.next_update_index(),
+            stack,
+            lock,
+            refs: Vec::new(),
+            logs: Vec::new(),
+        })
+    }
+}
+
+impl Transaction {
+    /// The stack as it was when the transaction was started, which can't change while it's locked.
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    /// The update index all changes of this transaction will have.
+    pub fn update_index(&self) -> u64 {
+        self.update_index
+    }
+
+    /// Set the reference named `name` to `value`, which may also delete it.
+    pub fn add_ref(&mut self, name: impl Into<BString>, value: RefValue) {
+        self.refs.push(Ref {
+            name: name.into(),
+            update_index: self.update_index,
+            value,
+        });
+    }
+
+    /// Add a new entry with `value` to the log of the reference named `name`.
+    pub fn add_log(&mut self, name: impl Into<BString>, value: LogValue) {
+        self.logs.push(Log {
+            name: name.into(),
+            update_index: self.update_index,
+            value,
+        });
+    }
+
+    /// Delete all existing entries of the log of the reference named `name`.
+    pub fn delete_logs(&mut self, name: &[u8]) -> Result<(), crate::decode::Error> {
+        for log in self.stack.logs_for(name)? {
+            let log = log?;
+            self.logs.push(Log {
+                value: LogValue::Deletion,
+                ..log
+            });
+        }
+        Ok(())
+    }
+
+    /// Write all changes into a new table and add it to the stack, possibly compacting tables if
+    /// [enabled](super::Options::auto_compaction), and return the stack with the change applied.
+    ///
+    /// Nothing is written if there are no changes.
+    pub fn commit(self) -> Result<Stack, commit::Error> {
+        let Transaction {
+            mut stack,
+            lock,
+            update_index,
+            mut refs,
+            mut logs,
+        } = self;
+        refs.sort_by(|a, b| a.name.cmp(&b.name));
+        if let Some(pair) = refs.windows(2).find(|pair| pair[0].name == pair[1].name) {
+            return Err(commit::Error::DuplicateRef {
+                name: pair[0].name.clone(),
+            });
+        }
+        logs.sort_by(|a, b| a.name.cmp(&b.name).then(b.update_index.cmp(&a.update_index)));
+        if let Some(pair) = logs
+            .windows(2)
+            .find(|pair| pair[0].name == pair[1].name && pair[0].update_index == pair[1].update_index)
+        {
+            return Err(commit::Error::DuplicateLog {
+                name: pair[0].name.clone(),
+                update_index: pair[0].update_index,
+            });
+        }
+        if refs.is_empty() && logs.is_empty() {
+            return Ok(stack);
+        }
+
+        let (name, table) = stack.write_table(update_index, update_index, |out| {
+            for r in &refs {
+                out.add_ref(r)?;
+            }
+            for log in &logs {
+                out.add_log(log)?;
+            }
+            Ok(())
+        })?;
+        stack.names.push(name);
+        stack.tables.push(table);
+        let obsolete = if stack.options.auto_compaction {
+            // Compaction is an optimization, so the transaction still succeeds if it fails.
+            stack.auto_compact().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        stack.commit_list(lock, obsolete)?;
+        Ok(stack)
+    }
+}
+
+/// Utilities
+impl Stack {
+    /// Write the names of the tables of this instance into `lock` and commit it, then delete the tables in `obsolete`
+    /// which aren't listed anymore.
+    pub(super) fn commit_list(&self, mut lock: gix_lock::File, obsolete: Vec<String>) -> std::io::Result<()> {
+        let mut list = String::new();
+        for name in &self.names {
+            list.push_str(name);
+            list.push('\n');
+        }
+        lock.write_all(list.as_bytes())?;
+        lock.commit().map_err(|err| err.error)?;
+        for name in obsolete {
+            // Readers may still have the table open, which prevents deletion on some platforms, so it's left to be
+            // cleaned up later.
+            std::fs::remove_file(self.dir.join(name)).ok();
+        }
+        Ok(())
+    }
+}
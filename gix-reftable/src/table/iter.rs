@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use crate::{
+    Log, Ref, Table,
+    block::{self, Block, Cursor},
+    decode::Error,
+    record::{self, Record},
+};
+
+/// Reads all records of one section of a table, starting at a given block and cursor.
+struct Records {
+    table: Arc<Table>,
+    kind: u8,
+    block: Option<Block<'static>>,
+    cursor: Cursor,
+}
+
+impl Records {
+    fn new(table: Arc<Table>, kind: u8, want: &[u8]) -> Result<Self, Error> {
+        let (block, cursor) = match table.seek(kind, want)? {
+            Some((block, cursor)) => (Some(block.into_owned()), cursor),
+            None => (None, Cursor::default()),
+        };
+        Ok(Records {
+            table,
+            kind,
+            block,
+            cursor,
+        })
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>, Error> {
+        loop {
+            let Some(block) = &self.block else {
+                return Ok(None);
+            };
+            if let Some(record) = block.next(&mut self.cursor, &self.table.ctx())? {
+                return Ok(Some(record));
+            }
+            let offset = block.next_offset(self.table.data(), self.table.block_size() as usize);
+            self.block = self
+                .table
+                .block_at(offset)?
+                .filter(|b| b.kind == self.kind)
+                .map(Block::into_owned);
+            if let Some(block) = &self.block {
+                self.cursor = block.start();
+            }
+        }
+    }
+
+    /// Like [`Self::next_record()`], but stops the iteration after the first error.
+    fn next_fused(&mut self) -> Option<Result<Record, Error>> {
+        match self.next_record() {
+            Ok(record) => record.map(Ok),
+            Err(err) => {
+                self.block = None;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// An iterator over the references of a [`Table`], created by [`Table::refs()`] and [`Table::refs_prefixed()`].
+pub struct Refs {
+    records: Records,
+    prefix: Vec<u8>,
+}
+
+impl Refs {
+    pub(crate) fn new(table: Arc<Table>, prefix: &[u8]) -> Result<Self, Error> {
+        Ok(Refs {
+            records: Records::new(table, block::REF, prefix)?,
+            prefix: prefix.to_owned(),
+        })
+    }
+}
+
+impl Iterator for Refs {
+    type Item = Result<Ref, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.records.next_fused()? {
+            Ok(Record::Ref(r)) if r.name.starts_with(&self.prefix) => Some(Ok(r)),
+            Ok(_) => {
+                self.records.block = None;
+                None
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// An iterator over the log entries of a [`Table`], created by [`Table::logs()`] and [`Table::logs_for()`].
+pub struct Logs {
+    records: Records,
+    name: Option<Vec<u8>>,
+}
+
+impl Logs {
+    pub(crate) fn new(table: Arc<Table>, name: Option<&[u8]>) -> Result<Self, Error> {
+        let want = name.map(|name| record::log_key(name, u64::MAX)).unwrap_or_default();
+        Ok(Logs {
+            records: Records::new(table, block::LOG, &want)?,
+            name: name.map(ToOwned::to_owned),
+        })
+    }
+}
+
+impl Iterator for Logs {
+    type Item = Result<Log, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.records.next_fused()? {
+            Ok(Record::Log(log)) if self.name.as_ref().is_none_or(|name| log.name == *name) => Some(Ok(log)),
+            Ok(_) => {
+                self.records.block = None;
+                None
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use std::{path::Path, sync::Arc};
+
+use gix_hash::oid;
+
+use crate::{
+    Ref,
+    block::{self, Block, Cursor},
+    decode::Error,
+    record::{self, Record},
+};
+
+/// Tables larger than this are mapped into memory instead of being read.
+const MMAP_THRESHOLD: u64 = 32 * 1024;
+
+/// The size of the footer without the copy of the header it starts with.
+const FOOTER_LEN_WITHOUT_HEADER: usize = 5 * 8 + 4;
+
+enum Backing {
+    InMemory(Vec<u8>),
+    Mapped(memmap2::Mmap),
+}
+
+/// A single reftable, holding references and their logs.
+///
+/// Note that deleted references and log entries are represented by records as well, as they hide the values of
+/// the same keys in older tables of a [`Stack`](crate::Stack).
+pub struct Table {
+    data: Backing,
+    object_hash: gix_hash::Kind,
+    block_size: u32,
+    min_update_index: u64,
+    max_update_index: u64,
+    /// The length of the header, which is also where the first block header starts.
+    header_len: usize,
+    /// The offset at which the footer starts, which is where the blocks end.
+    footer_start: usize,
+    ref_index_pos: u64,
+    obj_pos: u64,
+    obj_id_len: usize,
+    obj_index_pos: u64,
+    log_pos: u64,
+    log_index_pos: u64,
+}
+
+impl std::fmt::Debug for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Table")
+            .field("object_hash", &self.object_hash)
+            .field("block_size", &self.block_size)
+            .field("min_update_index", &self.min_update_index)
+            .field("max_update_index", &self.max_update_index)
+            .field("len", &self.data().len())
+            .finish_non_exhaustive()
+    }
+}
+
+///
+pub mod open {
+    /// The error returned by [`Table::at()`](crate::Table::at()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read the table at '{path}'")]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+        #[error("The table at '{path}' is invalid")]
+        Decode {
+            path: std::path::PathBuf,
+            source: crate::decode::Error,
+        },
+    }
+}
+
+mod iter;
+pub use iter::{Logs, Refs};
+
+/// Lifecycle
+impl Table {
+    /// Open the table at `path`, which is mapped into memory if it's large enough.
+    pub fn at(path: &Path) -> Result<Self, open::Error> {
+        let io_err = |source| open::Error::Io {
+            path: path.to_owned(),
+            source,
+        };
+        let file = std::fs::File::open(path).map_err(io_err)?;
+        let backing = if file.metadata().map_err(io_err)?.len() <= MMAP_THRESHOLD {
+            let mut data = Vec::new();
+            std::io::Read::read_to_end(&mut &file, &mut data).map_err(io_err)?;
+            Backing::InMemory(data)
+        } else {
+            // SAFETY: we have to take the risk of somebody changing the file underneath. Tables are never changed once written.
+            #[allow(unsafe_code)]
+            Backing::Mapped(unsafe { memmap2::MmapOptions::new().map_copy_read_only(&file) }.map_err(io_err)?)
+        };
+        Self::from_backing(backing).map_err(|source| open::Error::Decode {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    /// Read a table from `data`, the complete content of a table file.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Error> {
+        Self::from_backing(Backing::InMemory(data))
+    }
+
+    fn from_backing(backing: Backing) -> Result<Self, Error> {
+        let data: &[u8] = match &backing {
+            Backing::InMemory(data) => data,
+            Backing::Mapped(map) => map,
+        };
+        let (object_hash, header_len) = parse_version(data)?;
+        let footer_len = header_len + FOOTER_LEN_WITHOUT_HEADER;
+        let footer_start = data
+            .len()
+            .checked_sub(footer_len)
+            .filter(|start| *start >= header_len)
+            .ok_or(Error::TooShort)?;
+        let footer = &data[footer_start..];
+        if footer[..header_len] != data[..header_len] {
+            return Err(Error::FooterMismatch);
+        }
+        let (footer_data, checksum) = footer.split_at(footer_len - 4);
+        let expected = u32::from_be_bytes(checksum.try_into().expect("4 bytes"));
+        let actual = gix_features::hash::crc32(footer_data);
+        if expected != actual {
+            return Err(Error::Checksum { expected, actual });
+        }
+
+        let be64 = |pos: usize| u64::from_be_bytes(data[pos..pos + 8].try_into().expect("8 bytes"));
+        let positions = footer_start + header_len;
+        let obj_pos_and_len = be64(positions + 8);
+        let table = Table {
+            object_hash,
+            block_size: block::be24(&data[5..]) as u32,
+            min_update_index: be64(8),
+            max_update_index: be64(16),
+            header_len,
+            footer_start,
+            ref_index_pos: be64(positions),
+            obj_pos: obj_pos_and_len >> 5,
+            obj_id_len: (obj_pos_and_len & 0x1f) as usize,
+            obj_index_pos: be64(positions + 16),
+            log_pos: be64(positions + 24),
+            log_index_pos: be64(positions + 32),
+            data: backing,
+        };
+        if [
+            table.ref_index_pos,
+            table.obj_pos,
+            table.obj_index_pos,
+            table.log_pos,
+            table.log_index_pos,
+        ]
+        .into_iter()
+        .any(|pos| pos >= footer_start as u64)
+        {
+            return Err(Error::Block {
+                offset: footer_start as u64,
+                message: "footer points past the end of the blocks",
+            });
+        }
+        Ok(table)
+    }
+}
+
+/// Parse the signature and version of the table header, and return the hash kind along with the length of the header.
+fn parse_version(data: &[u8]) -> Result<(gix_hash::Kind, usize), Error> {
+    if data.len() < 24 {
+        return Err(Error::TooShort);
+    }
+    if &data[..4] != b"REFT" {
+        return Err(Error::Signature);
+    }
+    let (id, header_len) = match data[4] {
+        1 => (*b"sha1", 24),
+        2 => (data[24..28].try_into().expect("4 bytes"), 28),
+        version => return Err(Error::UnsupportedVersion { version }),
+    };
+    let kind = match &id {
+        b"sha1" => 1,
+        b"s256" => 2,
+        _ => 0,
+    };
+    let object_hash = gix_hash::Kind::try_from(kind).map_err(|_| Error::UnsupportedHash { id })?;
+    Ok((object_hash, header_len))
+}
+
+/// Access
+impl Table {
+    /// The kind of hash used for all object ids in the table.
+    pub fn object_hash(&self) -> gix_hash::Kind {
+        self.object_hash
+    }
+
+    /// The size of the blocks in the table, or `0` if they are unpadded and may have any size.
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    /// The smallest update index of any record in the table.
+    pub fn min_update_index(&self) -> u64 {
+        self.min_update_index
+    }
+
+    /// The largest update index of any record in the table.
+    pub fn max_update_index(&self) -> u64 {
+        self.max_update_index
+    }
+
+    /// Return `true` if the table holds neither references nor logs.
+    pub fn is_empty(&self) -> bool {
+        self.footer_start == self.header_len
+    }
+
+    pub(crate) fn data(&self) -> &[u8] {
+        match &self.data {
+            Backing::InMemory(data) => data,
+            Backing::Mapped(map) => map,
+        }
+    }
+
+    /// The size of the table without its header and footer, as used to decide which tables to compact.
+    pub(crate) fn payload_len(&self) -> u64 {
+        (self.footer_start - self.header_len) as u64
+    }
+}
+
+/// Lookup
+impl Table {
+    /// Find the reference named `name`, which may also be a [deletion](RefValue::Deletion).
+    pub fn find_ref(&self, name: &[u8]) -> Result<Option<Ref>, Error> {
+        let Some((block, mut cursor)) = self.seek(block::REF, name)? else {
+            return Ok(None);
+        };
+        Ok(match block.next(&mut cursor, &self.ctx())? {
+            Some(Record::Ref(r)) if r.name == name => Some(r),
+            _ => None,
+        })
+    }
+
+    /// Return all references that point to `id`, either directly or after peeling, in the order of their names.
+    ///
+    /// This uses the object index of the table if it has one.
+    pub fn refs_pointing_to(&self, id: &oid) -> Result<Vec<Ref>, Error> {
+        let mut out = Vec::new();
+        let ctx = self.ctx();
+        let positions = if self.obj_pos > 0 {
+            let prefix = &id.as_bytes()[..self.obj_id_len.min(id.as_bytes().len())];
+            match self.seek(block::OBJ, prefix)? {
+                Some((block, mut cursor)) => match block.next(&mut cursor, &ctx)? {
+                    Some(Record::Obj(positions)) if cursor.key() == prefix => Some(positions),
+                    _ => return Ok(out),
+                },
+                None => return Ok(out),
+            }
+            .filter(|positions| !positions.is_empty())
+        } else {
+            None
+        };
+
+        match positions {
+            Some(positions) => {
+                for position in positions {
+                    let block = self.block_at(position as usize)?.ok_or(Error::Block {
+                        offset: position,
+                        message: "object index points past the end of the table",
+                    })?;
+                    let mut cursor = block.start();
+                    while let Some(record) = block.next(&mut cursor, &ctx)? {
+                        if let Record::Ref(r) = record {
+                            if r.value.points_to(id) {
+                                out.push(r);
+                            }
+                        }
+                    }
+                }
+            }
+            None => {
+                let mut offset = 0;
+                while let Some(block) = self.block_at(offset)?.filter(|b| b.kind == block::REF) {
+                    let mut cursor = block.start();
+                    while let Some(record) = block.next(&mut cursor, &ctx)? {
+                        if let Record::Ref(r) = record {
+                            if r.value.points_to(id) {
+                                out.push(r);
+                            }
+                        }
+                    }
+                    offset = block.next_offset(self.data(), self.block_size as usize);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Return an iterator over all references, including deletions, in the order of their names.
+    pub fn refs(self: &Arc<Self>) -> Result<Refs, Error> {
+        self.refs_prefixed(b"")
+    }
+
+    /// Return an iterator over all references whose name starts with `prefix`, including deletions,
+    /// in the order of their names.
+    pub fn refs_prefixed(self: &Arc<Self>, prefix: &[u8]) -> Result<Refs, Error> {
+        Refs::new(self.clone(), prefix)
+    }
+
+    /// Return an iterator over all log entries, including deletions, ordered by reference name and from the
+    /// newest to the oldest entry.
+    pub fn logs(self: &Arc<Self>) -> Result<Logs, Error> {
+        Logs::new(self.clone(), None)
+    }
+
+    /// Return an iterator over all log entries of the reference named `name`, including deletions,
+    /// from the newest to the oldest entry.
+    pub fn logs_for(self: &Arc<Self>, name: &[u8]) -> Result<Logs, Error> {
+        Logs::new(self.clone(), Some(name))
+    }
+}
+
+/// Utilities
+impl Table {
+    pub(crate) fn ctx(&self) -> record::Context {
+        record::Context {
+            object_hash: self.object_hash,
+            min_update_index: self.min_update_index,
+        }
+    }
+
+    /// Return the block at `offset`, or `None` if it's the end of the table.
+    pub(crate) fn block_at(&self, offset: usize) -> Result<Option<Block<'_>>, Error> {
+        let header_len = if offset == 0 { self.header_len } else { 0 };
+        Block::at(self.data(), offset, header_len, self.footer_start)
+    }
+
+    /// Return the offset of the first block of the section holding records of `kind`, along with the offset of its
+    /// index if there is one.
+    fn section(&self, kind: u8) -> Option<(usize, Option<usize>)> {
+        let first_block_kind = self.data().get(self.header_len).copied().filter(|_| !self.is_empty());
+        let index = |pos: u64| (pos > 0).then_some(pos as usize);
+        match kind {
+            block::REF => (first_block_kind == Some(block::REF)).then(|| (0, index(self.ref_index_pos))),
+            block::OBJ => (self.obj_pos > 0).then(|| (self.obj_pos as usize, index(self.obj_index_pos))),
+            block::LOG => (self.log_pos > 0 || first_block_kind == Some(block::LOG))
+                .then(|| (self.log_pos as usize, index(self.log_index_pos))),
+            _ => None,
+        }
+    }
+
+    /// Return the block of `kind` along with a cursor from which the first record with a key greater than or equal to
+    /// `want` can be read, or `None` if there is no such record.
+    pub(crate) fn seek(&self, kind: u8, want: &[u8]) -> Result<Option<(Block<'_>, Cursor)>, Error> {
+        let Some((start, index)) = self.section(kind) else {
+            return Ok(None);
+        };
+        let ctx = self.ctx();
+        let corrupt = |offset: usize, message| Error::Block {
+            offset: offset as u64,
+            message,
+        };
+        let mut offset = start;
+        if let Some(index) = index {
+            // The highest level of the index may span multiple blocks which are searched linearly,
+            // and each of its records points to the block whose last key it holds.
+            let mut top = index;
+            let mut target = None;
+            while let Some(block) = self.block_at(top)?.filter(|b| b.kind == block::INDEX) {
+                if let Some(mut cursor) = block.seek(want, &ctx)? {
+                    target = match block.next(&mut cursor, &ctx)? {
+                        Some(Record::Index(position)) => Some(position as usize),
+                        _ => return Err(corrupt(top, "expected index record")),
+                    };
+                    break;
+                }
+                top = block.next_offset(self.data(), self.block_size as usize);
+            }
+            let Some(mut position) = target else {
+                return Ok(None);
+            };
+            loop {
+                let block = self
+                    .block_at(position)?
+                    .ok_or_else(|| corrupt(position, "index points past the end of the table"))?;
+                if block.kind != block::INDEX {
+                    offset = position;
+                    break;
+                }
+                let mut cursor = block
+                    .seek(want, &ctx)?
+                    .ok_or_else(|| corrupt(position, "index doesn't contain the key it was expected to"))?;
+                position = match block.next(&mut cursor, &ctx)? {
+                    Some(Record::Index(position)) => position as usize,
+                    _ => return Err(corrupt(position, "expected index record")),
+                };
+            }
+        }
+
+        while let Some(block) = self.block_at(offset)?.filter(|b| b.kind == kind) {
+            if let Some(cursor) = block.seek(want, &ctx)? {
+                return Ok(Some((block, cursor)));
+            }
+            offset = block.next_offset(self.data(), self.block_size as usize);
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,33 @@
+//! The variable-length integer encoding used by reftables, which is the same as the one of offsets in packs.
+
+/// Decode a varint from the beginning of `data` and return it along with the amount of bytes it occupied,
+/// or `None` if `data` ended prematurely or the value overflowed.
+pub(crate) fn decode(data: &[u8]) -> Option<(u64, usize)> {
+    let mut bytes = data.iter();
+    let mut byte = *bytes.next()?;
+    let mut value = u64::from(byte & 0x7f);
+    let mut consumed = 1;
+    while byte & 0x80 != 0 {
+        byte = *bytes.next()?;
+        consumed += 1;
+        value = value.checked_add(1)?.checked_mul(128)? | u64::from(byte & 0x7f);
+    }
+    Some((value, consumed))
+}
+
+/// Append `value` to `out` as varint.
+pub(crate) fn encode(mut value: u64, out: &mut Vec<u8>) {
+    let mut buf = [0u8; 10];
+    let mut pos = buf.len() - 1;
+    buf[pos] = (value & 0x7f) as u8;
+    loop {
+        value >>= 7;
+        if value == 0 {
+            break;
+        }
+        value -= 1;
+        pos -= 1;
+        buf[pos] = 0x80 | (value & 0x7f) as u8;
+    }
+    out.extend_from_slice(&buf[pos..]);
+}
@@ -0,0 +1,388 @@
+use std::{collections::BTreeMap, io::Write};
+
+use bstr::BString;
+use gix_hash::ObjectId;
+
+use crate::{Log, LogValue, Ref, RefValue, block, record};
+
+/// The options to control how tables are [written](Writer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// The size of the blocks in bytes, at most 16MB.
+    ///
+    /// Larger blocks compress better, but need more time to search.
+    pub block_size: u32,
+    /// The amount of records after which a record is written without prefix compression, to allow binary searches
+    /// within a block.
+    pub restart_interval: u16,
+    /// If `true`, write an index from object ids to the blocks of references pointing to them, if the table is
+    /// large enough for its references to be indexed.
+    pub index_objects: bool,
+    /// If `true`, don't pad blocks to the block size, which makes tables smaller at the cost of not being able to
+    /// read blocks with a single aligned read.
+    pub unpadded: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            block_size: 4096,
+            restart_interval: 16,
+            index_objects: true,
+            unpadded: false,
+        }
+    }
+}
+
+/// The error returned by [`Writer`] methods.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("The record of {name:?} was added out of order or more than once")]
+    Unsorted { name: BString },
+    #[error("The update index {update_index} of {name:?} is outside of the range {min}..={max} of the table")]
+    UpdateIndex {
+        name: BString,
+        update_index: u64,
+        min: u64,
+        max: u64,
+    },
+    #[error("The reference {name:?} was added after the logs, but all references have to be added first")]
+    RefAfterLog { name: BString },
+    #[error("The reference {name:?} is too large to fit into a block of {block_size} bytes")]
+    RecordTooLarge { name: BString, block_size: u32 },
+    #[error("The object id {id} of {name:?} doesn't match the hash kind {object_hash} of the table")]
+    ObjectHash {
+        name: BString,
+        id: ObjectId,
+        object_hash: gix_hash::Kind,
+    },
+    #[error("The block size {block_size} must be larger than 0 and smaller than 16MB")]
+    BlockSize { block_size: u32 },
+}
+
+/// Writes a single table into a [`Write`] implementation.
+///
+/// References have to be added before logs, and each of them has to be added in the order of their keys.
+/// References are ordered by name, and logs by name and then from the newest to the oldest update index.
+pub struct Writer<W> {
+    out: W,
+    options: Options,
+    object_hash: gix_hash::Kind,
+    min_update_index: u64,
+    max_update_index: u64,
+    header: Vec<u8>,
+    /// The amount of bytes written so far.
+    pos: u64,
+    /// The amount of bytes to write before the next block that isn't a log block.
+    pending_padding: usize,
+    block: Option<block::Writer>,
+    /// The object ids of references in the current block.
+    block_ids: Vec<ObjectId>,
+    /// The last key and the position of each block of the current section.
+    index: Vec<(Vec<u8>, u64)>,
+    /// The positions of the blocks holding references to each object id.
+    obj_positions: BTreeMap<ObjectId, Vec<u64>>,
+    last_ref: Option<BString>,
+    last_log_key: Option<Vec<u8>>,
+    ref_index_pos: u64,
+    obj_pos: u64,
+    obj_id_len: usize,
+    obj_index_pos: u64,
+    log_pos: u64,
+    log_index_pos: u64,
+}
+
+/// Lifecycle
+impl<W: Write> Writer<W> {
+    /// Create a new instance to write a table with object ids of `object_hash` into `out`, configured by `options`.
+    ///
+    /// All references added to it must have update indices within `min_update_index..=max_update_index`.
+    pub fn new(
+        out: W,
+        object_hash: gix_hash::Kind,
+        min_update_index: u64,
+        max_update_index: u64,
+        options: Options,
+    ) -> Result<Self, Error> {
+        if options.block_size == 0 || options.block_size >= 1 << 24 {
+            return Err(Error::BlockSize {
+                block_size: options.block_size,
+            });
+        }
+        let mut header = Vec::with_capacity(28);
+        header.extend_from_slice(b"REFT");
+        header.push(if object_hash.len_in_bytes() == 20 { 1 } else { 2 });
+        let mut block_size = [0u8; 3];
+        block::put_be24(options.block_size as usize, &mut block_size);
+        header.extend_from_slice(&block_size);
+        header.extend_from_slice(&min_update_index.to_be_bytes());
+        header.extend_from_slice(&max_update_index.to_be_bytes());
+        if header[4] == 2 {
+            header.extend_from_slice(b"s256");
+        }
+        Ok(Writer {
+            out,
+            options: Options {
+                restart_interval: options.restart_interval.max(1),
+                ..options
+            },
+            object_hash,
+            min_update_index,
+            max_update_index,
+            header,
+            pos: 0,
+            pending_padding: 0,
+            block: None,
+            block_ids: Vec::new(),
+            index: Vec::new(),
+            obj_positions: BTreeMap::new(),
+            last_ref: None,
+            last_log_key: None,
+            ref_index_pos: 0,
+            obj_pos: 0,
+            obj_id_len: 0,
+            obj_index_pos: 0,
+            log_pos: 0,
+            log_index_pos: 0,
+        })
+    }
+
+    /// Write all remaining blocks along with the footer and return the output.
+    pub fn finish(mut self) -> Result<W, Error> {
+        if self.last_log_key.is_some() {
+            self.log_index_pos = self.finish_section()?;
+        } else {
+            self.finish_refs()?;
+        }
+        if self.pos == 0 {
+            self.out.write_all(&self.header)?;
+        }
+
+        let mut footer = self.header.clone();
+        for value in [
+            self.ref_index_pos,
+            (self.obj_pos << 5) | self.obj_id_len as u64,
+            self.obj_index_pos,
+            self.log_pos,
+            self.log_index_pos,
+        ] {
+            footer.extend_from_slice(&value.to_be_bytes());
+        }
+        let checksum = gix_features::hash::crc32(&footer);
+        footer.extend_from_slice(&checksum.to_be_bytes());
+        self.out.write_all(&footer)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Adding records
+impl<W: Write> Writer<W> {
+    /// Add the reference `r`, which must sort after all references added previously.
+    pub fn add_ref(&mut self, r: &Ref) -> Result<(), Error> {
+        if self.last_log_key.is_some() {
+            return Err(Error::RefAfterLog { name: r.name.clone() });
+        }
+        if self.last_ref.as_ref().is_some_and(|last| *last >= r.name) {
+            return Err(Error::Unsorted { name: r.name.clone() });
+        }
+        self.check_update_index(&r.name, r.update_index)?;
+        let ids = match &r.value {
+            RefValue::Object(id) => vec![*id],
+            RefValue::Peeled { target, peeled } => vec![*target, *peeled],
+            RefValue::Deletion | RefValue::Symbolic(_) => Vec::new(),
+        };
+        for id in &ids {
+            self.check_object_hash(&r.name, id)?;
+        }
+
+        let mut value = Vec::new();
+        let value_type = record::encode_ref(r, self.min_update_index, &mut value);
+        if !self.add_record(block::REF, &r.name, value_type, &value)? {
+            return Err(Error::RecordTooLarge {
+                name: r.name.clone(),
+                block_size: self.options.block_size,
+            });
+        }
+        self.block_ids.extend(ids);
+        self.last_ref = Some(r.name.clone());
+        Ok(())
+    }
+
+    /// Add the log entry `log`, which must sort after all log entries added previously, so it must belong to a
+    /// reference with a greater name, or to the same reference with a smaller update index.
+    ///
+    /// Unlike the one of references, its update index doesn't have to be within the range of the table, which allows
+    /// to delete entries of older tables.
+    pub fn add_log(&mut self, log: &Log) -> Result<(), Error> {
+        let key = record::log_key(&log.name, log.update_index);
+        if self.last_log_key.as_ref().is_some_and(|last| *last >= key) {
+            return Err(Error::Unsorted { name: log.name.clone() });
+        }
+        if let LogValue::Update {
+            previous_oid, new_oid, ..
+        } = &log.value
+        {
+            self.check_object_hash(&log.name, previous_oid)?;
+            self.check_object_hash(&log.name, new_oid)?;
+        }
+        if self.last_log_key.is_none() {
+            self.finish_refs()?;
+            self.log_pos = self.pos;
+        }
+
+        let mut value = Vec::new();
+        let value_type = record::encode_log(log, &mut value);
+        let added = self.add_record(block::LOG, &key, value_type, &value)?;
+        assert!(added, "log blocks accept records of any size");
+        self.last_log_key = Some(key);
+        Ok(())
+    }
+}
+
+/// Utilities
+impl<W: Write> Writer<W> {
+    fn check_update_index(&self, name: &BString, update_index: u64) -> Result<(), Error> {
+        if !(self.min_update_index..=self.max_update_index).contains(&update_index) {
+            return Err(Error::UpdateIndex {
+                name: name.clone(),
+                update_index,
+                min: self.min_update_index,
+                max: self.max_update_index,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_object_hash(&self, name: &BString, id: &ObjectId) -> Result<(), Error> {
+        if id.kind() != self.object_hash {
+            return Err(Error::ObjectHash {
+                name: name.clone(),
+                id: *id,
+                object_hash: self.object_hash,
+            });
+        }
+        Ok(())
+    }
+
+    /// Add a record to the current block of `kind`, starting a new one if it's full.
+    /// Return `false` if the record doesn't even fit into an empty block.
+    fn add_record(&mut self, kind: u8, key: &[u8], value_type: u8, value: &[u8]) -> Result<bool, Error> {
+        if let Some(block) = self.block.as_mut().filter(|b| b.kind() == kind) {
+            if block.add(key, value_type, value) {
+                return Ok(true);
+            }
+        }
+        self.flush_block()?;
+        let header_len = if self.pos == 0 { self.header.len() } else { 0 };
+        let mut block = block::Writer::new(
+            kind,
+            header_len,
+            self.options.block_size as usize,
+            self.options.restart_interval.into(),
+        );
+        let added = block.add(key, value_type, value);
+        self.block = Some(block);
+        Ok(added)
+    }
+
+    /// Write the current block if there is one, and record its position in the index of the section.
+    fn flush_block(&mut self) -> Result<(), Error> {
+        let Some(block) = self.block.take().filter(|b| !b.is_empty()) else {
+            return Ok(());
+        };
+        let kind = block.kind();
+        let last_key = block.last_key().to_owned();
+        let mut bytes = block.finish();
+        if self.pos == 0 {
+            bytes[..self.header.len()].copy_from_slice(&self.header);
+        } else if kind != block::LOG && self.pending_padding > 0 {
+            self.out.write_all(&vec![0; self.pending_padding])?;
+            self.pos += self.pending_padding as u64;
+        }
+        self.pending_padding = 0;
+
+        let position = self.pos;
+        self.out.write_all(&bytes)?;
+        self.pos += bytes.len() as u64;
+        if kind != block::LOG && !self.options.unpadded {
+            self.pending_padding = (self.options.block_size as usize).saturating_sub(bytes.len());
+        }
+        self.index.push((last_key, position));
+        for id in self.block_ids.drain(..) {
+            let positions = self.obj_positions.entry(id).or_default();
+            if positions.last() != Some(&position) {
+                positions.push(position);
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the current block and an index for the current section if it has enough blocks,
+    /// and return the position of the highest level of the index, or 0 if there is no index.
+    fn finish_section(&mut self) -> Result<u64, Error> {
+        self.flush_block()?;
+        let threshold = if self.options.unpadded { 1 } else { 3 };
+        let mut index_pos = 0;
+        let mut index = std::mem::take(&mut self.index);
+        while index.len() > threshold {
+            let level_start = self.pos + self.pending_padding as u64;
+            for (key, position) in index {
+                let mut value = Vec::new();
+                let value_type = record::encode_index(position, &mut value);
+                let added = self.add_record(block::INDEX, &key, value_type, &value)?;
+                assert!(added, "index records are small enough to fit into an empty block");
+            }
+            self.flush_block()?;
+            index_pos = level_start;
+            index = std::mem::take(&mut self.index);
+        }
+        Ok(index_pos)
+    }
+
+    /// Finish the section of references, and write the section of object ids if there is one.
+    fn finish_refs(&mut self) -> Result<(), Error> {
+        if self.last_ref.is_none() {
+            return Ok(());
+        }
+        self.ref_index_pos = self.finish_section()?;
+        let obj_positions = std::mem::take(&mut self.obj_positions);
+        if !self.options.index_objects || self.ref_index_pos == 0 || obj_positions.is_empty() {
+            return Ok(());
+        }
+
+        // Use the shortest prefix that still tells all object ids apart.
+        let ids: Vec<_> = obj_positions.keys().collect();
+        let common_prefix_len = ids
+            .windows(2)
+            .map(|pair| {
+                pair[0]
+                    .as_bytes()
+                    .iter()
+                    .zip(pair[1].as_bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0);
+        self.obj_id_len = (common_prefix_len + 1).clamp(2, self.object_hash.len_in_bytes());
+        self.obj_pos = self.pos + self.pending_padding as u64;
+        for (id, positions) in &obj_positions {
+            let key = &id.as_bytes()[..self.obj_id_len];
+            let mut value = Vec::new();
+            let value_type = record::encode_obj(positions, &mut value);
+            if !self.add_record(block::OBJ, key, value_type, &value)? {
+                // Without positions, readers have to search all blocks of references.
+                value.clear();
+                let value_type = record::encode_obj(&[], &mut value);
+                let added = self.add_record(block::OBJ, key, value_type, &value)?;
+                assert!(added, "obj records without positions fit into an empty block");
+            }
+        }
+        self.obj_index_pos = self.finish_section()?;
+        Ok(())
+    }
+}
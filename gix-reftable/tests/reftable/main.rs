@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use gix_hash::ObjectId;
+pub use gix_testtools::Result;
+
+mod stack;
+mod table;
+
+/// Return the path to a repository cloned with `--ref-format=reftable` along with the id of its `main` branch,
+/// or `None` if Git is too old to create it.
+fn reftable_repo() -> Result<Option<(PathBuf, ObjectId)>> {
+    let dir = match gix_testtools::scripted_fixture_read_only("make_reftable_repo.sh") {
+        Ok(dir) => dir,
+        Err(_) if *gix_testtools::GIT_VERSION < (2, 45, 0) => {
+            eprintln!("Fixture script failure ignored as it looks like Git isn't recent enough.");
+            return Ok(None);
+        }
+        Err(err) => return Err(err),
+    };
+    let main = ObjectId::from_hex(gix_testtools::git(&dir, "rev-parse main")?.trim().as_bytes())?;
+    Ok(Some((dir.join("reftable-clone").join(".git"), main)))
+}
+
+fn id(kind: gix_hash::Kind, byte: u8) -> ObjectId {
+    ObjectId::from_bytes_or_panic(&vec![byte; kind.len_in_bytes()])
+}
+
+fn signature(seconds: i64, offset: i32) -> gix_actor::Signature {
+    gix_actor::Signature {
+        name: "Name".into(),
+        email: "name@example.com".into(),
+        time: gix_date::Time { seconds, offset },
+    }
+}
@@ -0,0 +1,195 @@
+use bstr::BString;
+use gix_lock::acquire::Fail;
+use gix_reftable::{LogValue, RefValue, Stack, stack};
+
+use crate::{id, reftable_repo, signature};
+
+fn options(auto_compaction: bool) -> stack::Options {
+    stack::Options {
+        object_hash: gix_hash::Kind::Sha1,
+        auto_compaction,
+        ..Default::default()
+    }
+}
+
+fn ref_names(stack: &Stack) -> crate::Result<Vec<BString>> {
+    Ok(stack.refs()?.map(|r| r.map(|r| r.name)).collect::<Result<_, _>>()?)
+}
+
+fn update(byte: u8) -> LogValue {
+    LogValue::Update {
+        previous_oid: gix_hash::Kind::Sha1.null(),
+        new_oid: id(gix_hash::Kind::Sha1, byte),
+        signature: signature(0, 0),
+        message: format!("update {byte}").into(),
+    }
+}
+
+#[test]
+fn read_stack_written_by_git() -> crate::Result {
+    let Some((git_dir, main)) = reftable_repo()? else {
+        return Ok(());
+    };
+    let stack = Stack::at(
+        git_dir.join("reftable"),
+        stack::Options {
+            object_hash: gix_testtools::object_hash(),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(stack.tables().len(), 1);
+    assert_eq!(stack.next_update_index(), 5);
+    assert_eq!(
+        ref_names(&stack)?,
+        [
+            "HEAD",
+            "refs/heads/main",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main"
+        ]
+    );
+    assert_eq!(
+        stack.find_ref(b"refs/remotes/origin/main")?.expect("present").value,
+        RefValue::Object(main)
+    );
+    assert_eq!(stack.logs_for(b"HEAD")?.count(), 1);
+
+    let other_hash = if gix_testtools::object_hash() == gix_hash::Kind::Sha1 {
+        gix_hash::Kind::Sha256
+    } else {
+        gix_hash::Kind::Sha1
+    };
+    let err = Stack::at(
+        git_dir.join("reftable"),
+        stack::Options {
+            object_hash: other_hash,
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, stack::open::Error::ObjectHash { .. }), "{err:?}");
+    Ok(())
+}
+
+#[test]
+fn missing_stacks_are_empty() -> crate::Result {
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    let stack = Stack::at(dir.path().join("reftable"), options(false))?;
+    assert_eq!(stack.tables().len(), 0);
+    assert_eq!(stack.next_update_index(), 1);
+    assert_eq!(stack.refs()?.count(), 0);
+    assert_eq!(stack.find_ref(b"HEAD")?, None);
+    Ok(())
+}
+
+#[test]
+fn transactions_add_tables_which_override_older_ones() -> crate::Result {
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    let stack = Stack::at(dir.path().join("reftable"), options(false))?;
+
+    let mut transaction = stack.transaction(Fail::Immediately)?;
+    assert_eq!(transaction.update_index(), 1);
+    transaction.add_ref("refs/heads/main", RefValue::Object(id(gix_hash::Kind::Sha1, 1)));
+    transaction.add_ref("refs/heads/other", RefValue::Object(id(gix_hash::Kind::Sha1, 2)));
+    transaction.add_ref("HEAD", RefValue::Symbolic("refs/heads/main".into()));
+    transaction.add_log("refs/heads/main", update(1));
+    transaction.add_log("refs/heads/other", update(2));
+    let stack = transaction.commit()?;
+    assert_eq!(stack.tables().len(), 1);
+
+    let mut transaction = stack.transaction(Fail::Immediately)?;
+    assert!(
+        stack.transaction(Fail::Immediately).is_err(),
+        "the stack is locked while a transaction is in progress"
+    );
+    assert_eq!(transaction.update_index(), 2);
+    transaction.add_ref("refs/heads/main", RefValue::Object(id(gix_hash::Kind::Sha1, 3)));
+    transaction.add_ref("refs/heads/other", RefValue::Deletion);
+    transaction.add_log("refs/heads/main", update(3));
+    transaction.delete_logs(b"refs/heads/other")?;
+    let stack = transaction.commit()?;
+    assert_eq!(
+        stack.tables().len(),
+        2,
+        "without compaction, each transaction adds a table"
+    );
+
+    for stack in [stack.clone(), Stack::at(stack.dir(), options(false))?] {
+        assert_eq!(ref_names(&stack)?, ["HEAD", "refs/heads/main"]);
+        assert_eq!(
+            stack.find_ref(b"refs/heads/main")?.expect("present").value,
+            RefValue::Object(id(gix_hash::Kind::Sha1, 3))
+        );
+        assert_eq!(stack.find_ref(b"refs/heads/other")?, None, "deleted");
+        assert_eq!(
+            stack
+                .logs_for(b"refs/heads/main")?
+                .map(|log| log.map(|log| log.update_index))
+                .collect::<Result<Vec<_>, _>>()?,
+            [2, 1],
+            "newest first"
+        );
+        assert_eq!(stack.logs_for(b"refs/heads/other")?.count(), 0, "deleted as well");
+        assert_eq!(
+            stack.refs_pointing_to(&id(gix_hash::Kind::Sha1, 2))?,
+            [],
+            "the reference was deleted"
+        );
+    }
+
+    let stack = stack.compact(Fail::Immediately)?;
+    assert_eq!(stack.tables().len(), 1);
+    assert_eq!(ref_names(&stack)?, ["HEAD", "refs/heads/main"]);
+    assert_eq!(
+        stack.tables()[0].refs()?.count(),
+        2,
+        "deletions are dropped when compacting the whole stack"
+    );
+    assert_eq!(stack.tables()[0].logs()?.count(), 2);
+    assert_eq!(
+        std::fs::read_dir(stack.dir())?.count(),
+        2,
+        "only the list and the compacted table remain"
+    );
+
+    let mut transaction = stack.transaction(Fail::Immediately)?;
+    transaction.add_ref("refs/heads/a", RefValue::Deletion);
+    transaction.add_ref("refs/heads/a", RefValue::Deletion);
+    assert!(matches!(
+        transaction.commit(),
+        Err(stack::transaction::commit::Error::DuplicateRef { .. })
+    ));
+    let stack = stack.transaction(Fail::Immediately)?.commit()?;
+    assert_eq!(stack.tables().len(), 1, "empty transactions don't write tables");
+    Ok(())
+}
+
+#[test]
+fn auto_compaction_keeps_the_amount_of_tables_logarithmic() -> crate::Result {
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    let mut stack = Stack::at(dir.path(), options(true))?;
+    for idx in 0..100u32 {
+        let mut transaction = stack.transaction(Fail::Immediately)?;
+        transaction.add_ref(
+            format!("refs/heads/{idx:03}"),
+            RefValue::Object(id(gix_hash::Kind::Sha1, idx as u8)),
+        );
+        stack = transaction.commit()?;
+        assert!(stack.tables().len() <= 8, "{} tables", stack.tables().len());
+    }
+    let stack = Stack::at(dir.path(), options(false))?;
+    assert_eq!(stack.refs()?.count(), 100);
+    assert_eq!(
+        std::fs::read_dir(stack.dir())?.count(),
+        stack.tables().len() + 1,
+        "compacted tables are removed"
+    );
+    for (idx, table) in stack.tables().iter().enumerate().skip(1) {
+        assert_eq!(
+            table.min_update_index(),
+            stack.tables()[idx - 1].max_update_index() + 1,
+            "update indices are contiguous"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,328 @@
+use std::sync::Arc;
+
+use bstr::{BString, ByteSlice};
+use gix_reftable::{
+    Log, LogValue, Ref, RefValue, Table,
+    write::{self, Writer},
+};
+
+use crate::{id, reftable_repo, signature};
+
+fn names(refs: impl IntoIterator<Item = Result<Ref, gix_reftable::decode::Error>>) -> crate::Result<Vec<BString>> {
+    Ok(refs.into_iter().map(|r| r.map(|r| r.name)).collect::<Result<_, _>>()?)
+}
+
+fn write_table(kind: gix_hash::Kind, options: write::Options, refs: &[Ref], logs: &[Log]) -> crate::Result<Arc<Table>> {
+    let mut out = Writer::new(Vec::new(), kind, 1, 10, options)?;
+    for r in refs {
+        out.add_ref(r)?;
+    }
+    for log in logs {
+        out.add_log(log)?;
+    }
+    Ok(Arc::new(Table::from_bytes(out.finish()?)?))
+}
+
+#[test]
+fn read_table_written_by_git() -> crate::Result {
+    let Some((git_dir, main)) = reftable_repo()? else {
+        return Ok(());
+    };
+    let list = std::fs::read_to_string(git_dir.join("reftable").join("tables.list"))?;
+    let name = list.lines().next().expect("one table");
+    let table = Arc::new(Table::at(&git_dir.join("reftable").join(name))?);
+    assert_eq!(table.object_hash(), gix_testtools::object_hash());
+    assert_eq!(table.min_update_index(), 1);
+
+    assert_eq!(
+        names(table.refs()?)?,
+        [
+            "HEAD",
+            "refs/heads/main",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main"
+        ]
+    );
+    assert_eq!(
+        table.find_ref(b"HEAD")?.expect("present").value,
+        RefValue::Symbolic("refs/heads/main".into())
+    );
+    assert_eq!(
+        table.find_ref(b"refs/heads/main")?.expect("present").value,
+        RefValue::Object(main)
+    );
+    assert_eq!(table.find_ref(b"refs/heads/missing")?, None);
+    assert_eq!(table.find_ref(b"refs/heads")?, None, "only full names match");
+    assert_eq!(
+        names(table.refs_prefixed(b"refs/remotes/")?)?,
+        ["refs/remotes/origin/HEAD", "refs/remotes/origin/main"]
+    );
+    assert_eq!(
+        names(table.refs_pointing_to(&main)?.into_iter().map(Ok))?,
+        ["refs/heads/main", "refs/remotes/origin/main"]
+    );
+
+    let logs = table.logs_for(b"refs/heads/main")?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(logs.len(), 1);
+    match &logs[0].value {
+        LogValue::Update {
+            previous_oid,
+            new_oid,
+            message,
+            ..
+        } => {
+            assert!(previous_oid.is_null());
+            assert_eq!(*new_oid, main);
+            assert!(message.starts_with(b"clone: from "), "{message:?}");
+            assert!(message.ends_with(b"\n"), "git stores messages with a trailing newline");
+        }
+        LogValue::Deletion => unreachable!("the log was written by a clone"),
+    }
+    assert!(table.logs()?.count() >= 2, "HEAD and the branch have a log at least");
+    Ok(())
+}
+
+#[test]
+fn round_trip_with_indices() -> crate::Result {
+    for kind in gix_hash::Kind::all().iter().copied() {
+        let refs: Vec<_> = (0..2000u32)
+            .map(|idx| Ref {
+                name: format!("refs/heads/branch-{idx:05}").into(),
+                update_index: 1 + u64::from(idx % 10),
+                value: match idx % 4 {
+                    0 => RefValue::Object(id(kind, (idx % 251) as u8)),
+                    1 => RefValue::Peeled {
+                        target: id(kind, (idx % 251) as u8),
+                        peeled: id(kind, 252),
+                    },
+                    2 => RefValue::Symbolic(format!("refs/heads/branch-{:05}", idx - 1).into()),
+                    _ => RefValue::Deletion,
+                },
+            })
+            .collect();
+        let logs: Vec<_> = (0..100u32)
+            .flat_map(|idx| {
+                (1..=10u64).rev().map(move |update_index| Log {
+                    name: format!("refs/heads/branch-{idx:05}").into(),
+                    update_index,
+                    value: if update_index == 5 {
+                        LogValue::Deletion
+                    } else {
+                        LogValue::Update {
+                            previous_oid: id(kind, update_index as u8),
+                            new_oid: id(kind, update_index as u8 + 1),
+                            signature: signature(1_700_000_000 + update_index as i64, -7 * 3600 - 1800),
+                            message: format!("commit: change {update_index}").into(),
+                        }
+                    },
+                })
+            })
+            .collect();
+
+        for options in [
+            write::Options {
+                block_size: 256,
+                ..Default::default()
+            },
+            write::Options {
+                block_size: 256,
+                restart_interval: 3,
+                unpadded: true,
+                ..Default::default()
+            },
+            write::Options::default(),
+        ] {
+            let table = write_table(kind, options, &refs, &logs)?;
+            assert_eq!(table.object_hash(), kind);
+            assert_eq!(table.block_size(), options.block_size);
+
+            let actual = table.refs()?.collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(actual, refs, "deletions are retained in tables");
+            for r in refs.iter().step_by(7) {
+                assert_eq!(table.find_ref(&r.name)?.as_ref(), Some(r), "{options:?}");
+            }
+            assert_eq!(table.find_ref(b"refs/heads/branch-02000")?, None);
+            assert_eq!(table.find_ref(b"refs/heads/a")?, None);
+            assert_eq!(
+                names(table.refs_prefixed(b"refs/heads/branch-0199")?)?.len(),
+                10,
+                "{options:?}"
+            );
+            assert_eq!(table.refs_prefixed(b"refs/tags/")?.count(), 0);
+
+            let target = id(kind, 42);
+            let expected: Vec<_> = refs
+                .iter()
+                .filter(
+                    |r| matches!(&r.value, RefValue::Object(id) | RefValue::Peeled { target: id, .. } if *id == target),
+                )
+                .cloned()
+                .collect();
+            assert!(!expected.is_empty());
+            assert_eq!(table.refs_pointing_to(&target)?, expected, "{options:?}");
+            assert_eq!(table.refs_pointing_to(&id(kind, 252))?.len(), 500);
+            assert_eq!(table.refs_pointing_to(&id(kind, 253))?, []);
+
+            let actual = table.logs()?.collect::<Result<Vec<_>, _>>()?;
+            let expected_logs: Vec<_> = logs
+                .iter()
+                .cloned()
+                .map(|mut log| {
+                    if let LogValue::Update { message, .. } = &mut log.value {
+                        message.push(b'\n');
+                    }
+                    log
+                })
+                .collect();
+            assert_eq!(actual, expected_logs, "messages are normalized to end in a newline");
+            let for_branch = table
+                .logs_for(b"refs/heads/branch-00050")?
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(for_branch, expected_logs[500..510]);
+            assert_eq!(table.logs_for(b"refs/heads/branch-0005")?.count(), 0);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn empty_table() -> crate::Result {
+    for kind in gix_hash::Kind::all().iter().copied() {
+        let table = write_table(kind, Default::default(), &[], &[])?;
+        assert!(table.is_empty());
+        assert_eq!(table.object_hash(), kind);
+        assert_eq!(table.refs()?.count(), 0);
+        assert_eq!(table.logs()?.count(), 0);
+        assert_eq!(table.find_ref(b"HEAD")?, None);
+    }
+    Ok(())
+}
+
+#[test]
+fn logs_only() -> crate::Result {
+    let kind = gix_hash::Kind::Sha1;
+    let log = Log {
+        name: "HEAD".into(),
+        update_index: 3,
+        value: LogValue::Update {
+            previous_oid: id(kind, 1),
+            new_oid: id(kind, 2),
+            signature: signature(0, 2 * 3600),
+            message: "message\n".into(),
+        },
+    };
+    let table = write_table(kind, Default::default(), &[], std::slice::from_ref(&log))?;
+    assert_eq!(table.refs()?.count(), 0);
+    assert_eq!(table.logs()?.collect::<Result<Vec<_>, _>>()?, [log]);
+    Ok(())
+}
+
+#[test]
+fn writer_errors() -> crate::Result {
+    let kind = gix_hash::Kind::Sha1;
+    let r = |name: &str, update_index| Ref {
+        name: name.into(),
+        update_index,
+        value: RefValue::Object(id(kind, 1)),
+    };
+    let mut out = Writer::new(Vec::new(), kind, 2, 3, Default::default())?;
+    out.add_ref(&r("b", 2))?;
+    assert_eq!(
+        out.add_ref(&r("a", 2)).unwrap_err().to_string(),
+        "The record of \"a\" was added out of order or more than once"
+    );
+    assert_eq!(
+        out.add_ref(&r("b", 2)).unwrap_err().to_string(),
+        "The record of \"b\" was added out of order or more than once"
+    );
+    assert_eq!(
+        out.add_ref(&r("c", 4)).unwrap_err().to_string(),
+        "The update index 4 of \"c\" is outside of the range 2..=3 of the table"
+    );
+    assert_eq!(
+        out.add_ref(&Ref {
+            value: RefValue::Object(id(gix_hash::Kind::Sha256, 1)),
+            ..r("c", 3)
+        })
+        .unwrap_err()
+        .to_string(),
+        format!(
+            "The object id {} of \"c\" doesn't match the hash kind sha1 of the table",
+            id(gix_hash::Kind::Sha256, 1)
+        )
+    );
+    assert_eq!(
+        out.add_ref(&Ref {
+            value: RefValue::Symbolic("x".repeat(5000).into()),
+            ..r("c", 3)
+        })
+        .unwrap_err()
+        .to_string(),
+        "The reference \"c\" is too large to fit into a block of 4096 bytes"
+    );
+    out.add_log(&Log {
+        name: "a".into(),
+        update_index: 1,
+        value: LogValue::Deletion,
+    })?;
+    assert_eq!(
+        out.add_ref(&r("d", 3)).unwrap_err().to_string(),
+        "The reference \"d\" was added after the logs, but all references have to be added first"
+    );
+    Ok(())
+}
+
+#[test]
+fn large_log_messages_exceed_the_block_size() -> crate::Result {
+    let kind = gix_hash::Kind::Sha1;
+    let message: BString = "m".repeat(10_000).into();
+    let log = Log {
+        name: "refs/heads/main".into(),
+        update_index: 1,
+        value: LogValue::Update {
+            previous_oid: kind.null(),
+            new_oid: id(kind, 1),
+            signature: signature(0, 0),
+            message: message.clone(),
+        },
+    };
+    let table = write_table(kind, Default::default(), &[], std::slice::from_ref(&log))?;
+    let actual = table.logs()?.next().expect("present")?;
+    match actual.value {
+        LogValue::Update { message: actual, .. } => assert_eq!(actual.trim_end(), message),
+        LogValue::Deletion => unreachable!("an update was written"),
+    }
+    Ok(())
+}
+
+#[test]
+fn corrupt_tables_are_detected() -> crate::Result {
+    let kind = gix_hash::Kind::Sha1;
+    let mut out = Writer::new(Vec::new(), kind, 1, 1, Default::default())?;
+    out.add_ref(&Ref {
+        name: "HEAD".into(),
+        update_index: 1,
+        value: RefValue::Symbolic("refs/heads/main".into()),
+    })?;
+    let data = out.finish()?;
+
+    let mut checksum = data.clone();
+    *checksum.last_mut().expect("non-empty") ^= 1;
+    assert!(matches!(
+        Table::from_bytes(checksum),
+        Err(gix_reftable::decode::Error::Checksum { .. })
+    ));
+
+    let mut signature = data.clone();
+    signature[0] = b'X';
+    assert!(matches!(
+        Table::from_bytes(signature),
+        Err(gix_reftable::decode::Error::Signature)
+    ));
+
+    assert!(matches!(
+        Table::from_bytes(data[..40].to_vec()),
+        Err(gix_reftable::decode::Error::TooShort)
+    ));
+    Ok(())
+}
@@ -0,0 +1,265 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The `rr-cache` directory, typically `.git/rr-cache`, in which conflicts and their resolutions are recorded.
+///
+/// Each set of conflicts in a file is stored in a directory named after its [identifier](crate::conflict::Normalized::id).
+/// As different sets of conflicts may have the same identifier, each directory holds one or more *variants*, each of
+/// which is made of a `preimage`, the normalized file with conflict markers, and a `postimage`, the file after the conflict
+/// was resolved by the user.
+/// Variant `0` uses these names as is, while variant `N` uses `preimage.N` and `postimage.N`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// The kind of file recorded for each variant of a conflict.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Image {
+    /// The normalized file with conflict markers.
+    Preimage,
+    /// The file with resolved conflicts.
+    Postimage,
+}
+
+impl Image {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Image::Preimage => "preimage",
+            Image::Postimage => "postimage",
+        }
+    }
+}
+
+/// A recorded resolution that was applied successfully, as returned by [`Cache::resolve()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved {
+    /// The variant whose resolution could be applied.
+    pub variant: usize,
+    /// The file with its conflicts resolved.
+    pub content: Vec<u8>,
+}
+
+/// Lifecycle
+impl Cache {
+    /// Use `dir` as `rr-cache` directory, which doesn't have to exist yet.
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Cache { dir: dir.into() }
+    }
+
+    /// The `rr-cache` directory we operate on.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+/// Access
+impl Cache {
+    /// Return the path to the `image` of `variant` of the conflict with `id`.
+    pub fn path(&self, id: &gix_hash::oid, variant: usize, image: Image) -> PathBuf {
+        let name = match variant {
+            0 => image.as_str().into(),
+            _ => format!("{}.{variant}", image.as_str()),
+        };
+        self.id_dir(id).join(name)
+    }
+
+    /// Return `true` if `image` of `variant` of the conflict with `id` is recorded.
+    pub fn contains(&self, id: &gix_hash::oid, variant: usize, image: Image) -> bool {
+        self.path(id, variant, image).is_file()
+    }
+
+    /// Return all variants of the conflict with `id` that have a preimage, in ascending order.
+    pub fn variants(&self, id: &gix_hash::oid) -> io::Result<Vec<usize>> {
+        let entries = match std::fs::read_dir(self.id_dir(id)) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut variants = Vec::new();
+        for entry in entries {
+            let name = entry?.file_name();
+            let Some(variant) = name.to_str().and_then(|name| parse_variant(name, Image::Preimage)) else {
+                continue;
+            };
+            variants.push(variant);
+        }
+        variants.sort_unstable();
+        Ok(variants)
+    }
+
+    /// Try to apply each variant of the recorded resolutions of the conflict with `id` to `preimage`,
+    /// the [normalized](crate::conflict::normalize()) file with conflicts, and return the first one that merges cleanly,
+    /// or `None` if there is no such resolution.
+    ///
+    /// A successfully used resolution is marked as such to prevent it from being [garbage-collected](Self::gc()) too early.
+    pub fn resolve(&self, id: &gix_hash::oid, preimage: &[u8]) -> io::Result<Option<Resolved>> {
+        let mut content = Vec::new();
+        for variant in self.variants(id)? {
+            let postimage_path = self.path(id, variant, Image::Postimage);
+            let Some(postimage) = read_if_exists(&postimage_path)? else {
+                continue;
+            };
+            let Some(recorded_preimage) = read_if_exists(&self.path(id, variant, Image::Preimage))? else {
+                continue;
+            };
+            let resolution = gix_merge::blob::builtin_driver::text(
+                &mut content,
+                &mut imara_diff::InternedInput::default(),
+                Default::default(),
+                preimage,
+                &recorded_preimage,
+                &postimage,
+                Default::default(),
+            );
+            if resolution == gix_merge::blob::Resolution::Complete {
+                std::fs::File::options()
+                    .write(true)
+                    .open(&postimage_path)?
+                    .set_modified(SystemTime::now())?;
+                return Ok(Some(Resolved { variant, content }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Mutation
+impl Cache {
+    /// Record `preimage`, the [normalized](crate::conflict::normalize()) file with conflicts, as `variant` of the conflict
+    /// with `id`, or as a new variant if `variant` is `None`, and return the variant it was recorded as.
+    ///
+    /// A new variant has no resolution, which is recorded later with [`record_postimage()`](Self::record_postimage()).
+    pub fn record_preimage(&self, id: &gix_hash::oid, variant: Option<usize>, preimage: &[u8]) -> io::Result<usize> {
+        let variant = match variant {
+            Some(variant) => variant,
+            None => {
+                let used = self.variants(id)?;
+                let variant = (0..=used.len())
+                    .find(|variant| !used.contains(variant))
+                    .expect("one more candidate than used variants");
+                remove_if_exists(&self.path(id, variant, Image::Postimage))?;
+                variant
+            }
+        };
+        std::fs::create_dir_all(self.id_dir(id))?;
+        std::fs::write(self.path(id, variant, Image::Preimage), preimage)?;
+        Ok(variant)
+    }
+
+    /// Record `postimage`, the file with all conflicts resolved, as resolution of `variant` of the conflict with `id`.
+    pub fn record_postimage(&self, id: &gix_hash::oid, variant: usize, postimage: &[u8]) -> io::Result<()> {
+        std::fs::create_dir_all(self.id_dir(id))?;
+        std::fs::write(self.path(id, variant, Image::Postimage), postimage)
+    }
+
+    /// Forget the recorded resolution of `variant` of the conflict with `id`, similar to `git rerere forget`,
+    /// and return `true` if there was one.
+    pub fn forget(&self, id: &gix_hash::oid, variant: usize) -> io::Result<bool> {
+        remove_if_exists(&self.path(id, variant, Image::Postimage))
+    }
+
+    /// Remove `variant` of the conflict with `id` entirely, along with the directory of the conflict if it's empty afterwards.
+    pub fn remove(&self, id: &gix_hash::oid, variant: usize) -> io::Result<()> {
+        remove_if_exists(&self.path(id, variant, Image::Preimage))?;
+        remove_if_exists(&self.path(id, variant, Image::Postimage))?;
+        remove_dir_if_empty(&self.id_dir(id))
+    }
+
+    /// Remove all variants whose resolution was last used before `resolved_cutoff`, and all variants without resolution
+    /// that were recorded before `unresolved_cutoff`, similar to `git rerere gc`.
+    /// Return the amount of removed variants.
+    ///
+    /// `git` uses `gc.rerereResolved` and `gc.rerereUnresolved` to configure these, which default to 60 and 15 days ago respectively.
+    pub fn gc(&self, resolved_cutoff: SystemTime, unresolved_cutoff: SystemTime) -> io::Result<usize> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err),
+        };
+        let mut removed = 0;
+        for entry in entries {
+            let entry = entry?;
+            let Some(id) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| gix_hash::ObjectId::from_hex(name.as_bytes()).ok())
+            else {
+                continue;
+            };
+            for variant in self.variants(&id)? {
+                let (last_used, cutoff) = match modified_if_exists(&self.path(&id, variant, Image::Postimage))? {
+                    Some(last_used) => (last_used, resolved_cutoff),
+                    None => match modified_if_exists(&self.path(&id, variant, Image::Preimage))? {
+                        Some(created) => (created, unresolved_cutoff),
+                        None => continue,
+                    },
+                };
+                if last_used < cutoff {
+                    self.remove(&id, variant)?;
+                    removed += 1;
+                }
+            }
+            remove_dir_if_empty(&entry.path())?;
+        }
+        Ok(removed)
+    }
+}
+
+impl Cache {
+    fn id_dir(&self, id: &gix_hash::oid) -> PathBuf {
+        self.dir.join(id.to_hex().to_string())
+    }
+}
+
+/// Parse the variant from a file `name` of `image`, like `preimage` or `preimage.2`.
+fn parse_variant(name: &str, image: Image) -> Option<usize> {
+    match name.strip_prefix(image.as_str())? {
+        "" => Some(0),
+        suffix => {
+            let digits = suffix.strip_prefix('.')?;
+            if digits.starts_with('0') || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            digits.parse().ok()
+        }
+    }
+}
+
+fn read_if_exists(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn modified_if_exists(path: &Path) -> io::Result<Option<SystemTime>> {
+    match std::fs::metadata(path) {
+        Ok(meta) => meta.modified().map(Some),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<bool> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+fn remove_dir_if_empty(dir: &Path) -> io::Result<()> {
+    match std::fs::read_dir(dir) {
+        Ok(mut entries) => match entries.next() {
+            None => std::fs::remove_dir(dir),
+            Some(_) => Ok(()),
+        },
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,145 @@
+/// The error returned by [`normalize()`](crate::conflict::normalize()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(
+        "Could not parse conflict hunks as the conflict starting on line {line} is unterminated or its markers are out of order"
+    )]
+    Malformed { line: usize },
+    #[error(transparent)]
+    Hash(#[from] gix_hash::hasher::Error),
+}
+
+/// A file with conflict markers in its normalized form, as obtained by [`normalize()`](crate::conflict::normalize()).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Normalized {
+    /// The identifier of the conflicts, which is the name of the directory in the `rr-cache` to record them in.
+    pub id: gix_hash::ObjectId,
+    /// The file with all conflicts normalized, as it is stored as `preimage` in the `rr-cache`.
+    ///
+    /// Normalized conflicts have markers without labels, lack the section with the common ancestor, and have their sides
+    /// sorted so that the same conflict is found independently of which side was *ours* or *theirs*.
+    pub preimage: Vec<u8>,
+    /// The amount of top-level conflicts in the file.
+    pub conflicts: usize,
+}
+
+pub(super) mod function {
+    use bstr::ByteSlice;
+
+    use super::{Error, Normalized, is_marker, put_marker};
+
+    /// Normalize the conflicts in `data`, whose conflict markers are `marker_size` characters long, and compute
+    /// the identifier of all conflicts with a hash of kind `object_hash`, just like `git rerere` does.
+    ///
+    /// Return `None` if `data` has no conflict markers, or an error if they are malformed.
+    ///
+    /// Conflict markers are recognized no matter if they are labeled or not, so the output of
+    /// [`gix_merge::blob::builtin_driver::text()`] can be used directly.
+    /// Nested conflicts, as produced by recursive merges, are normalized as part of the side they are in,
+    /// but don't contribute to the identifier.
+    pub fn normalize(
+        data: &[u8],
+        marker_size: usize,
+        object_hash: gix_hash::Kind,
+    ) -> Result<Option<Normalized>, Error> {
+        let mut hasher = gix_hash::hasher(object_hash);
+        let mut preimage = Vec::with_capacity(data.len());
+        let mut conflicts = 0;
+        let mut lines = data.lines_with_terminator().enumerate();
+        while let Some((line_number, line)) = lines.next() {
+            if is_marker(line, b'<', marker_size) {
+                let (one, two) = conflict(&mut lines, marker_size).ok_or(Error::Malformed { line: line_number + 1 })?;
+                hasher.update(&one);
+                hasher.update(b"\0");
+                hasher.update(&two);
+                hasher.update(b"\0");
+                put_conflict(&mut preimage, &one, &two, marker_size);
+                conflicts += 1;
+            } else {
+                preimage.extend_from_slice(line);
+            }
+        }
+        if conflicts == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Normalized {
+            id: hasher.try_finalize()?,
+            preimage,
+            conflicts,
+        }))
+    }
+
+    #[derive(Copy, Clone, Eq, PartialEq)]
+    enum Hunk {
+        One,
+        Original,
+        Two,
+    }
+
+    /// Parse the conflict whose start marker was just consumed from `lines`, and return both of its sides,
+    /// ordered so that the first one sorts before the second one, or `None` if the conflict is malformed.
+    fn conflict<'a>(
+        lines: &mut impl Iterator<Item = (usize, &'a [u8])>,
+        marker_size: usize,
+    ) -> Option<(Vec<u8>, Vec<u8>)> {
+        let mut hunk = Hunk::One;
+        let (mut one, mut two) = (Vec::new(), Vec::new());
+        while let Some((_, line)) = lines.next() {
+            if is_marker(line, b'<', marker_size) {
+                let (nested_one, nested_two) = conflict(lines, marker_size)?;
+                let side = if hunk == Hunk::One { &mut one } else { &mut two };
+                put_conflict(side, &nested_one, &nested_two, marker_size);
+            } else if is_marker(line, b'|', marker_size) {
+                if hunk != Hunk::One {
+                    return None;
+                }
+                hunk = Hunk::Original;
+            } else if is_marker(line, b'=', marker_size) {
+                if hunk == Hunk::Two {
+                    return None;
+                }
+                hunk = Hunk::Two;
+            } else if is_marker(line, b'>', marker_size) {
+                if hunk != Hunk::Two {
+                    return None;
+                }
+                if one > two {
+                    std::mem::swap(&mut one, &mut two);
+                }
+                return Some((one, two));
+            } else {
+                match hunk {
+                    Hunk::One => one.extend_from_slice(line),
+                    Hunk::Original => {}
+                    Hunk::Two => two.extend_from_slice(line),
+                }
+            }
+        }
+        None
+    }
+
+    fn put_conflict(out: &mut Vec<u8>, one: &[u8], two: &[u8], marker_size: usize) {
+        put_marker(out, b'<', marker_size);
+        out.extend_from_slice(one);
+        put_marker(out, b'=', marker_size);
+        out.extend_from_slice(two);
+        put_marker(out, b'>', marker_size);
+    }
+}
+
+/// Return `true` if `line` is a conflict marker made of `marker_size` times `marker`, followed by whitespace
+/// which typically introduces a label.
+///
+/// Unlike `git`, which requires a label after `<` and `>` markers, a line ending right after the marker is accepted as well.
+pub(crate) fn is_marker(line: &[u8], marker: u8, marker_size: usize) -> bool {
+    line.len() > marker_size
+        && line[..marker_size].iter().all(|b| *b == marker)
+        && line[marker_size].is_ascii_whitespace()
+}
+
+/// Write a normalized conflict `marker` of `marker_size` characters, without label.
+pub(crate) fn put_marker(out: &mut Vec<u8>, marker: u8, marker_size: usize) {
+    out.extend(std::iter::repeat_n(marker, marker_size));
+    out.push(b'\n');
+}
@@ -0,0 +1,36 @@
+//! Record resolutions of merge conflicts and reuse them when the same conflicts are encountered again, akin to `git rerere`.
+//!
+//! The workflow is as follows:
+//!
+//! * A file with conflict markers is [normalized](conflict::normalize()) to obtain an identifier for its conflicts,
+//!   independently of the labels of the conflict markers and the order of the conflicting sides.
+//! * The normalized file is recorded as *preimage* in the [`Cache`], which is compatible to `.git/rr-cache`.
+//! * Once the user resolved the conflicts, the resolved file is recorded as *postimage*.
+//! * When the same conflicts are encountered again, the [recorded resolution is applied](Cache::resolve()) to the
+//!   conflicted file with a three-way merge.
+//!
+//! [`record()`] performs all of these steps for conflicted files in a worktree, just like `git rerere` does, and keeps track
+//! of conflicted paths in [`MergeRr`], the equivalent of `.git/MERGE_RR`.
+//!
+//! ### Deviation
+//!
+//! * Conflict markers without label, as produced by [`gix_merge::blob::builtin_driver::text()`] without labels, are recognized
+//!   while `git` would ignore them.
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+
+///
+pub mod conflict;
+pub use conflict::function::normalize;
+
+///
+pub mod cache;
+pub use cache::Cache;
+
+///
+pub mod merge_rr;
+pub use merge_rr::MergeRr;
+
+///
+pub mod record;
+pub use record::function::record;
@@ -0,0 +1,97 @@
+use std::{io, path::Path};
+
+use bstr::{BStr, BString, ByteSlice};
+
+/// The error returned by [`MergeRr::from_bytes()`] and [`MergeRr::at()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read the MERGE_RR file")]
+    Io(#[from] io::Error),
+    #[error("The entry {entry:?} in MERGE_RR is malformed")]
+    Malformed { entry: BString },
+}
+
+/// A conflicted path tracked in `MERGE_RR`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The repository-relative path of the conflicted file in the worktree.
+    pub path: BString,
+    /// The identifier of the conflicts in the file.
+    pub id: gix_hash::ObjectId,
+    /// The variant of the conflict in the [`Cache`](crate::Cache) that the preimage was recorded as.
+    pub variant: usize,
+}
+
+/// The state of a `git rerere` run, stored in `.git/MERGE_RR` to track conflicted paths until their resolution is recorded.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct MergeRr {
+    /// All tracked paths.
+    pub entries: Vec<Entry>,
+}
+
+/// Lifecycle
+impl MergeRr {
+    /// Parse `data` in the format of the `MERGE_RR` file, with identifiers of kind `object_hash`.
+    pub fn from_bytes(data: &[u8], object_hash: gix_hash::Kind) -> Result<Self, Error> {
+        let mut entries = Vec::new();
+        for entry in data.split_str(b"\0").filter(|entry| !entry.is_empty()) {
+            let malformed = || Error::Malformed {
+                entry: entry.as_bstr().to_owned(),
+            };
+            let (id, path) = entry.split_once_str(b"\t").ok_or_else(malformed)?;
+            let (hex, variant) = match id.split_once_str(b".") {
+                Some((hex, variant)) => (
+                    hex,
+                    variant
+                        .to_str()
+                        .ok()
+                        .and_then(|variant| variant.parse().ok())
+                        .ok_or_else(malformed)?,
+                ),
+                None => (id, 0),
+            };
+            if hex.len() != object_hash.len_in_hex() {
+                return Err(malformed());
+            }
+            entries.push(Entry {
+                path: path.into(),
+                id: gix_hash::ObjectId::from_hex(hex).map_err(|_| malformed())?,
+                variant,
+            });
+        }
+        Ok(MergeRr { entries })
+    }
+
+    /// Read the `MERGE_RR` file at `path` with identifiers of kind `object_hash`, or return an empty instance
+    /// if it doesn't exist.
+    pub fn at(path: &Path, object_hash: gix_hash::Kind) -> Result<Self, Error> {
+        match std::fs::read(path) {
+            Ok(data) => Self::from_bytes(&data, object_hash),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Access
+impl MergeRr {
+    /// Return the entry for `path`, if it's tracked.
+    pub fn find(&self, path: &BStr) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+
+    /// Serialize all entries into `out` in the format of the `MERGE_RR` file.
+    pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        for entry in &self.entries {
+            write!(out, "{}", entry.id)?;
+            if entry.variant != 0 {
+                write!(out, ".{}", entry.variant)?;
+            }
+            out.write_all(b"\t")?;
+            out.write_all(&entry.path)?;
+            out.write_all(b"\0")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use bstr::BString;
+
+/// The error returned by [`record()`](crate::record()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not access {path:?}")]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Hash(#[from] gix_hash::hasher::Error),
+}
+
+/// Options for use in [`record()`](crate::record()).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// The amount of characters that make up a conflict marker, typically [`gix_merge::blob::builtin_driver::text::Conflict::DEFAULT_MARKER_SIZE`].
+    pub marker_size: usize,
+    /// The kind of hash to compute conflict identifiers with, which is the one used by the repository.
+    pub object_hash: gix_hash::Kind,
+}
+
+/// The outcome of [`record()`](crate::record()), with repository-relative paths.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// Paths whose conflicts were seen for the first time and whose preimage was recorded.
+    pub recorded_preimages: Vec<BString>,
+    /// Paths that were resolved by the user and whose resolution was recorded.
+    pub recorded_resolutions: Vec<BString>,
+    /// Paths whose conflicts were resolved in the worktree by replaying a recorded resolution.
+    ///
+    /// These may be added to the index if `rerere.autoUpdate` is enabled.
+    pub resolved: Vec<BString>,
+}
+
+pub(super) mod function {
+    use std::path::Path;
+
+    use bstr::{BStr, BString, ByteSlice};
+
+    use super::{Error, Options, Outcome};
+    use crate::{Cache, MergeRr, merge_rr::Entry};
+
+    /// Record conflicts and resolutions of files in the worktree at `worktree_root`, and replay known resolutions,
+    /// just like `git rerere` does when invoked without a subcommand, using `cache` for storage and `state` to track
+    /// conflicted paths across invocations. `state` is typically read from and written back to `.git/MERGE_RR`.
+    ///
+    /// `conflicted_paths` are all repository-relative paths which are currently conflicted in the index.
+    ///
+    /// * Paths that aren't tracked in `state` yet and have conflict markers are added to it.
+    /// * Tracked paths without conflict markers are considered resolved by the user, so their content is recorded as
+    ///   postimage and they are removed from `state`.
+    /// * Tracked paths with conflict markers are resolved in the worktree if a recorded resolution applies cleanly,
+    ///   and are removed from `state`. Otherwise, their preimage is recorded.
+    ///
+    /// Paths whose conflict markers can't be parsed are ignored.
+    pub fn record<'a>(
+        conflicted_paths: impl IntoIterator<Item = &'a BStr>,
+        worktree_root: &Path,
+        cache: &Cache,
+        state: &mut MergeRr,
+        Options {
+            marker_size,
+            object_hash,
+        }: Options,
+    ) -> Result<Outcome, Error> {
+        let mut out = Outcome::default();
+        let mut new_conflicts = Vec::new();
+        for rela_path in conflicted_paths {
+            if state.find(rela_path).is_some() {
+                continue;
+            }
+            let Some(data) = read(worktree_root, rela_path)? else {
+                continue;
+            };
+            if let Ok(Some(normalized)) = crate::normalize(&data, marker_size, object_hash) {
+                new_conflicts.push((rela_path.to_owned(), normalized));
+            }
+        }
+
+        for entry in std::mem::take(&mut state.entries) {
+            let Some(data) = read(worktree_root, entry.path.as_bstr())? else {
+                continue;
+            };
+            match crate::normalize(&data, marker_size, object_hash) {
+                Ok(Some(normalized)) => {
+                    let (path, id, variant) = (entry.path, entry.id, entry.variant);
+                    replay_or_record(
+                        path,
+                        id,
+                        Some(variant),
+                        &normalized.preimage,
+                        worktree_root,
+                        cache,
+                        state,
+                        &mut out,
+                    )?;
+                }
+                Ok(None) => {
+                    cache
+                        .record_postimage(&entry.id, entry.variant, &data)
+                        .map_err(|err| io_error(cache.dir(), err))?;
+                    out.recorded_resolutions.push(entry.path);
+                }
+                Err(crate::conflict::Error::Malformed { .. }) => state.entries.push(entry),
+                Err(crate::conflict::Error::Hash(err)) => return Err(err.into()),
+            }
+        }
+
+        for (path, normalized) in new_conflicts {
+            replay_or_record(
+                path,
+                normalized.id,
+                None,
+                &normalized.preimage,
+                worktree_root,
+                cache,
+                state,
+                &mut out,
+            )?;
+        }
+        Ok(out)
+    }
+
+    /// Write the resolution of the conflict with `id` into the file at `rela_path` if a recorded resolution applies
+    /// to `preimage`, or record `preimage` as `variant` (or a new variant) and track `rela_path` in `state` otherwise.
+    #[allow(clippy::too_many_arguments)]
+    fn replay_or_record(
+        rela_path: BString,
+        id: gix_hash::ObjectId,
+        variant: Option<usize>,
+        preimage: &[u8],
+        worktree_root: &Path,
+        cache: &Cache,
+        state: &mut MergeRr,
+        out: &mut Outcome,
+    ) -> Result<(), Error> {
+        match cache.resolve(&id, preimage).map_err(|err| io_error(cache.dir(), err))? {
+            Some(resolved) => {
+                if let Some(variant) = variant.filter(|variant| *variant != resolved.variant) {
+                    cache.remove(&id, variant).map_err(|err| io_error(cache.dir(), err))?;
+                }
+                let path = worktree_root.join(gix_path::from_bstr(rela_path.as_bstr()));
+                std::fs::write(&path, &resolved.content).map_err(|err| io_error(&path, err))?;
+                out.resolved.push(rela_path);
+            }
+            None => {
+                let is_new = variant.is_none();
+                let variant = cache
+                    .record_preimage(&id, variant, preimage)
+                    .map_err(|err| io_error(cache.dir(), err))?;
+                if is_new {
+                    out.recorded_preimages.push(rela_path.clone());
+                }
+                state.entries.push(Entry {
+                    path: rela_path,
+                    id,
+                    variant,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn read(worktree_root: &Path, rela_path: &BStr) -> Result<Option<Vec<u8>>, Error> {
+        let path = worktree_root.join(gix_path::from_bstr(rela_path));
+        match std::fs::read(&path) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(io_error(&path, err)),
+        }
+    }
+
+    fn io_error(path: &Path, source: std::io::Error) -> Error {
+        Error::Io {
+            path: path.to_owned(),
+            source,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+function conflicting_branches() {
+  git init -q
+  git config rerere.enabled true
+  git config merge.conflictStyle diff3
+
+  seq 1 10 >file
+  seq 1 5 >other
+  git add . && git commit -q -m base
+
+  git checkout -q -b theirs
+  sed -i.bak -e 's/^3$/three-theirs/' -e 's/^8$/eight-theirs/' file && rm file.bak
+  git commit -q -am theirs
+
+  git checkout -q main
+  sed -i.bak -e 's/^3$/three-ours/' -e 's/^8$/eight-ours/' file && rm file.bak
+  git commit -q -am ours
+}
+
+# A merge with conflicts whose preimage was recorded, but which isn't resolved yet.
+mkdir recorded
+(cd recorded
+  conflicting_branches
+  git merge theirs >/dev/null 2>&1 || :
+)
+
+# The same conflicts, this time from the perspective of the other side.
+mkdir recorded-swapped
+(cd recorded-swapped
+  conflicting_branches
+  git checkout -q theirs
+  git merge main >/dev/null 2>&1 || :
+)
+
+# A resolution was recorded, and the merge was redone so `git rerere` could resolve the conflicts by itself.
+mkdir resolved
+(cd resolved
+  conflicting_branches
+  git merge theirs >/dev/null 2>&1 || :
+  seq 1 10 | sed -e 's/^3$/three-resolved/' -e 's/^8$/eight-resolved/' >file
+  git rerere 2>/dev/null
+  git add file && git commit -q -m merged
+
+  git reset -q --hard HEAD~1
+  git merge theirs >/dev/null 2>&1 || :
+)
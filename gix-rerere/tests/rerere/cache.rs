@@ -0,0 +1,156 @@
+use std::time::{Duration, SystemTime};
+
+use gix_merge::blob::builtin_driver::text::Labels;
+use gix_rerere::{Cache, cache::Image};
+
+use crate::{fixture, fixture_writable, merge_rr, normalize};
+
+#[test]
+fn resolve_with_resolution_recorded_by_git() -> crate::Result {
+    let tmp = fixture_writable()?;
+    let cache = Cache::at(tmp.path().join("resolved/.git/rr-cache"));
+    let conflicted = normalize(&std::fs::read(tmp.path().join("recorded-swapped/file"))?)?.expect("conflicts");
+
+    let postimage = cache.path(&conflicted.id, 0, Image::Postimage);
+    let long_ago = SystemTime::UNIX_EPOCH + Duration::from_secs(60);
+    std::fs::File::options()
+        .write(true)
+        .open(&postimage)?
+        .set_modified(long_ago)?;
+
+    let resolved = cache
+        .resolve(&conflicted.id, &conflicted.preimage)?
+        .expect("resolution applies");
+    assert_eq!(resolved.variant, 0);
+    assert_eq!(resolved.content, std::fs::read(tmp.path().join("resolved/file"))?);
+    assert!(
+        std::fs::metadata(postimage)?.modified()? > long_ago,
+        "using a resolution marks it as used"
+    );
+    Ok(())
+}
+
+#[test]
+fn resolve_conflicts_of_builtin_text_driver() -> crate::Result {
+    let root = fixture()?;
+    let cache = Cache::at(root.join("resolved/.git/rr-cache"));
+    let base = b"1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+    let ours = b"1\n2\nthree-ours\n4\n5\n6\n7\neight-ours\n9\n10\n";
+    let theirs = b"1\n2\nthree-theirs\n4\n5\n6\n7\neight-theirs\n9\n10\n";
+
+    for labels in [
+        Labels::default(),
+        Labels {
+            ancestor: Some("base".into()),
+            current: Some("ours".into()),
+            other: Some("theirs".into()),
+        },
+    ] {
+        let mut merged = Vec::new();
+        let resolution = gix_merge::blob::builtin_driver::text(
+            &mut merged,
+            &mut Default::default(),
+            labels,
+            ours,
+            base,
+            theirs,
+            Default::default(),
+        );
+        assert_eq!(resolution, gix_merge::blob::Resolution::Conflict);
+        let conflicted = normalize(&merged)?.expect("conflicts");
+        let resolved = cache
+            .resolve(&conflicted.id, &conflicted.preimage)?
+            .expect("resolution applies");
+        assert_eq!(resolved.content, std::fs::read(root.join("resolved/file"))?);
+    }
+    Ok(())
+}
+
+#[test]
+fn resolve_without_resolution() -> crate::Result {
+    let root = fixture()?;
+    let cache = Cache::at(root.join("recorded/.git/rr-cache"));
+    let id = merge_rr(&root.join("recorded"))?.entries[0].id;
+    assert_eq!(cache.variants(&id)?, [0]);
+    assert!(!cache.contains(&id, 0, Image::Postimage));
+    assert_eq!(
+        cache.resolve(&id, &std::fs::read(cache.path(&id, 0, Image::Preimage))?)?,
+        None
+    );
+    Ok(())
+}
+
+#[test]
+fn record_variants_and_forget() -> crate::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let cache = Cache::at(tmp.path().join("rr-cache"));
+    let id = gix_hash::ObjectId::empty_blob(gix_testtools::object_hash());
+    assert_eq!(cache.variants(&id)?, Vec::<usize>::new());
+
+    assert_eq!(
+        cache.record_preimage(&id, None, b"<<<<<<<\na\n=======\nb\n>>>>>>>\n")?,
+        0
+    );
+    assert_eq!(
+        cache.record_preimage(&id, None, b"<<<<<<<\nc\n=======\nd\n>>>>>>>\n")?,
+        1
+    );
+    assert_eq!(
+        cache.record_preimage(&id, Some(1), b"<<<<<<<\nc\n=======\ne\n>>>>>>>\n")?,
+        1
+    );
+    assert_eq!(cache.variants(&id)?, [0, 1]);
+    assert!(cache.path(&id, 1, Image::Preimage).ends_with("preimage.1"));
+
+    cache.record_postimage(&id, 1, b"c\n")?;
+    assert!(cache.contains(&id, 1, Image::Postimage));
+    assert!(cache.forget(&id, 1)?);
+    assert!(!cache.forget(&id, 1)?, "there is nothing to forget anymore");
+
+    cache.remove(&id, 0)?;
+    assert_eq!(
+        cache.record_preimage(&id, None, b"<<<<<<<\nf\n=======\ng\n>>>>>>>\n")?,
+        0,
+        "free variants are reused"
+    );
+    cache.remove(&id, 0)?;
+    cache.remove(&id, 1)?;
+    assert!(
+        !cache.dir().join(id.to_string()).exists(),
+        "empty directories are removed"
+    );
+    Ok(())
+}
+
+#[test]
+fn gc() -> crate::Result {
+    let tmp = fixture_writable()?;
+    let resolved = Cache::at(tmp.path().join("resolved/.git/rr-cache"));
+    let recorded = Cache::at(tmp.path().join("recorded/.git/rr-cache"));
+    let past = SystemTime::UNIX_EPOCH;
+    let future = SystemTime::now() + Duration::from_secs(60);
+
+    for cache in [&resolved, &recorded] {
+        assert_eq!(cache.gc(past, past)?, 0, "nothing is older than the cutoff");
+    }
+    assert_eq!(
+        resolved.gc(past, future)?,
+        0,
+        "the resolution is younger than the cutoff"
+    );
+    assert_eq!(recorded.gc(future, past)?, 0, "the preimage is younger than the cutoff");
+
+    assert_eq!(resolved.gc(future, past)?, 1);
+    assert_eq!(recorded.gc(past, future)?, 1);
+    assert_eq!(
+        std::fs::read_dir(recorded.dir())?.count(),
+        0,
+        "empty directories are removed"
+    );
+    assert_eq!(
+        std::fs::read_dir(resolved.dir())?.count(),
+        1,
+        "directories with unknown files, here `thisimage` left by `git`, are kept"
+    );
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use gix_rerere::conflict::Error;
+
+use crate::{fixture, merge_rr, normalize};
+
+#[test]
+fn normalization_matches_git() -> crate::Result {
+    let root = fixture()?;
+    let repo = root.join("recorded");
+    let normalized = normalize(&std::fs::read(repo.join("file"))?)?.expect("conflicts are present");
+
+    let state = merge_rr(&repo)?;
+    assert_eq!(state.entries.len(), 1);
+    assert_eq!(
+        normalized.id, state.entries[0].id,
+        "the identifier is the same as the one `git` computed"
+    );
+    assert_eq!(normalized.conflicts, 2);
+    assert_eq!(
+        normalized.preimage,
+        std::fs::read(
+            repo.join(".git/rr-cache")
+                .join(normalized.id.to_string())
+                .join("preimage")
+        )?,
+        "labels and the base section are removed"
+    );
+    Ok(())
+}
+
+#[test]
+fn sides_are_sorted() -> crate::Result {
+    let root = fixture()?;
+    let ours = normalize(&std::fs::read(root.join("recorded/file"))?)?.expect("conflicts");
+    let theirs = normalize(&std::fs::read(root.join("recorded-swapped/file"))?)?.expect("conflicts");
+    assert_eq!(ours, theirs, "it doesn't matter which side is ours");
+    Ok(())
+}
+
+#[test]
+fn labels_are_optional() -> crate::Result {
+    let labeled = normalize(b"a\n<<<<<<< ours\nb\n=======\nc\n>>>>>>> theirs\nd\n")?.expect("conflict");
+    let unlabeled = normalize(b"a\n<<<<<<<\nb\n=======\nc\n>>>>>>>\nd\n")?.expect("conflict");
+    assert_eq!(labeled, unlabeled);
+    assert_eq!(labeled.preimage, b"a\n<<<<<<<\nb\n=======\nc\n>>>>>>>\nd\n");
+    Ok(())
+}
+
+#[test]
+fn no_conflicts() -> crate::Result {
+    assert_eq!(normalize(b"")?, None);
+    assert_eq!(
+        normalize(b"<<<<<<<< too long\n=======\n>>>>>>>> too long\n")?,
+        None,
+        "markers must have exactly the configured size"
+    );
+    assert_eq!(
+        gix_rerere::normalize(
+            b"<<<<<<<< a\n========\nb\n>>>>>>>> b\n",
+            8,
+            gix_testtools::object_hash()
+        )?
+        .map(|n| n.preimage),
+        Some(b"<<<<<<<<\n========\nb\n>>>>>>>>\n".to_vec()),
+        "other marker sizes are supported as well"
+    );
+    Ok(())
+}
+
+#[test]
+fn nested_conflicts_are_normalized_but_not_hashed() -> crate::Result {
+    let nested = normalize(b"<<<<<<< a\nz\n<<<<<<< inner-a\ny\n=======\nx\n>>>>>>> inner-b\n=======\nb\n>>>>>>> b\n")?
+        .expect("conflict");
+    assert_eq!(
+        nested.preimage, b"<<<<<<<\nb\n=======\nz\n<<<<<<<\nx\n=======\ny\n>>>>>>>\n>>>>>>>\n",
+        "the nested conflict is part of its side, which is then sorted"
+    );
+    assert_eq!(nested.conflicts, 1);
+    Ok(())
+}
+
+#[test]
+fn malformed_conflicts() {
+    for (input, expected_line) in [
+        (&b"a\n<<<<<<< ours\nb\n"[..], 2),
+        (b"<<<<<<< ours\nb\n>>>>>>> theirs\n", 1),
+        (b"<<<<<<< ours\n=======\n||||||| base\n>>>>>>> theirs\n", 1),
+        (b"<<<<<<< ours\n=======\n=======\n>>>>>>> theirs\n", 1),
+    ] {
+        match normalize(input).unwrap_err().downcast::<Error>() {
+            Ok(err) => assert!(
+                matches!(*err, Error::Malformed { line } if line == expected_line),
+                "{input:?}: {err:?}"
+            ),
+            Err(err) => panic!("unexpected error: {err:?}"),
+        }
+    }
+}
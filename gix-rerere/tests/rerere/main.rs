@@ -0,0 +1,26 @@
+use std::path::{Path, PathBuf};
+
+use gix_rerere::MergeRr;
+pub use gix_testtools::Result;
+
+mod cache;
+mod conflict;
+mod merge_rr;
+mod record;
+
+fn fixture() -> Result<PathBuf> {
+    gix_testtools::scripted_fixture_read_only("make_rerere_repos.sh")
+}
+
+fn fixture_writable() -> Result<gix_testtools::tempfile::TempDir> {
+    gix_testtools::scripted_fixture_writable("make_rerere_repos.sh")
+}
+
+/// Read the `MERGE_RR` file of the repository at `repo`, as written by `git rerere`.
+fn merge_rr(repo: &Path) -> Result<MergeRr> {
+    Ok(MergeRr::at(&repo.join(".git/MERGE_RR"), gix_testtools::object_hash())?)
+}
+
+fn normalize(data: &[u8]) -> Result<Option<gix_rerere::conflict::Normalized>> {
+    Ok(gix_rerere::normalize(data, 7, gix_testtools::object_hash())?)
+}
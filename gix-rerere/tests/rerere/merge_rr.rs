@@ -0,0 +1,59 @@
+use gix_rerere::{MergeRr, merge_rr::Entry};
+
+#[test]
+fn round_trip() -> crate::Result {
+    let root = crate::fixture()?;
+    let path = root.join("recorded/.git/MERGE_RR");
+    let state = crate::merge_rr(&root.join("recorded"))?;
+    assert_eq!(state.entries.len(), 1);
+    assert_eq!(state.entries[0].path, "file");
+    assert_eq!(state.entries[0].variant, 0);
+
+    let mut buf = Vec::new();
+    state.write_to(&mut buf)?;
+    assert_eq!(buf, std::fs::read(path)?);
+    Ok(())
+}
+
+#[test]
+fn variants() -> crate::Result {
+    let id = gix_hash::ObjectId::empty_blob(gix_testtools::object_hash());
+    let state = MergeRr {
+        entries: vec![
+            Entry {
+                path: "a".into(),
+                id,
+                variant: 0,
+            },
+            Entry {
+                path: "dir/with\ttab".into(),
+                id,
+                variant: 2,
+            },
+        ],
+    };
+    let mut buf = Vec::new();
+    state.write_to(&mut buf)?;
+    assert_eq!(buf, format!("{id}\ta\0{id}.2\tdir/with\ttab\0").into_bytes());
+    assert_eq!(MergeRr::from_bytes(&buf, id.kind())?, state);
+    Ok(())
+}
+
+#[test]
+fn missing_file_is_empty() -> crate::Result {
+    let state = MergeRr::at("does-not-exist".as_ref(), gix_testtools::object_hash())?;
+    assert!(state.entries.is_empty());
+    Ok(())
+}
+
+#[test]
+fn malformed() {
+    let kind = gix_testtools::object_hash();
+    for input in [
+        &b"no-tab\0"[..],
+        b"abc\tpath\0",
+        b"not-hex-not-hex-not-hex-not-hex-not-hex-not-hex-not-hex-not-hex\tpath\0",
+    ] {
+        assert!(MergeRr::from_bytes(input, kind).is_err(), "{input:?}");
+    }
+}
@@ -0,0 +1,84 @@
+use gix_rerere::{Cache, MergeRr, cache::Image, record::Options};
+
+use crate::{fixture, fixture_writable, merge_rr};
+
+fn options() -> Options {
+    Options {
+        marker_size: 7,
+        object_hash: gix_testtools::object_hash(),
+    }
+}
+
+#[test]
+fn new_conflicts_are_recorded_like_git_does() -> crate::Result {
+    let root = fixture()?;
+    let tmp = fixture_writable()?;
+    let repo = tmp.path().join("recorded");
+    let cache = Cache::at(repo.join(".git/rr-cache"));
+    std::fs::remove_dir_all(cache.dir())?;
+
+    let mut state = MergeRr::default();
+    let out = gix_rerere::record(["file".into(), "missing".into()], &repo, &cache, &mut state, options())?;
+    assert_eq!(out.recorded_preimages, ["file"]);
+    assert!(out.recorded_resolutions.is_empty() && out.resolved.is_empty());
+    assert_eq!(
+        state,
+        merge_rr(&root.join("recorded"))?,
+        "the state is the same as the one of `git`"
+    );
+
+    let id = state.entries[0].id;
+    assert_eq!(
+        std::fs::read(cache.path(&id, 0, Image::Preimage))?,
+        std::fs::read(
+            root.join("recorded/.git/rr-cache")
+                .join(id.to_string())
+                .join("preimage")
+        )?
+    );
+
+    let out = gix_rerere::record(["file".into()], &repo, &cache, &mut state, options())?;
+    assert!(
+        out.recorded_preimages.is_empty(),
+        "tracked conflicts are only refreshed, and not recorded again"
+    );
+    assert_eq!(state.entries.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn resolutions_are_recorded_and_replayed() -> crate::Result {
+    let root = fixture()?;
+    let tmp = fixture_writable()?;
+    let repo = tmp.path().join("recorded");
+    let cache = Cache::at(repo.join(".git/rr-cache"));
+    let mut state = merge_rr(&repo)?;
+    let id = state.entries[0].id;
+
+    let resolution = std::fs::read(root.join("resolved/file"))?;
+    std::fs::write(repo.join("file"), &resolution)?;
+    let out = gix_rerere::record(["file".into()], &repo, &cache, &mut state, options())?;
+    assert_eq!(out.recorded_resolutions, ["file"]);
+    assert!(state.entries.is_empty(), "resolved paths aren't tracked anymore");
+    assert_eq!(std::fs::read(cache.path(&id, 0, Image::Postimage))?, resolution);
+
+    std::fs::copy(root.join("recorded-swapped/file"), repo.join("file"))?;
+    let out = gix_rerere::record(["file".into()], &repo, &cache, &mut state, options())?;
+    assert_eq!(out.resolved, ["file"], "the same conflicts are resolved automatically");
+    assert!(out.recorded_preimages.is_empty());
+    assert!(state.entries.is_empty());
+    assert_eq!(std::fs::read(repo.join("file"))?, resolution);
+    Ok(())
+}
+
+#[test]
+fn malformed_conflicts_are_ignored() -> crate::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    std::fs::write(tmp.path().join("file"), b"<<<<<<< ours\nunterminated\n")?;
+    let cache = Cache::at(tmp.path().join("rr-cache"));
+    let mut state = MergeRr::default();
+    let out = gix_rerere::record(["file".into()], tmp.path(), &cache, &mut state, options())?;
+    assert_eq!(out, Default::default());
+    assert!(state.entries.is_empty());
+    Ok(())
+}
@@ -1 +1,50 @@
+//! Cherry-pick or revert sequences of commits, akin to `git cherry-pick` and `git revert`, with the ability to stop
+//! on conflicts and to continue once they are resolved.
+//!
+//! The workflow is as follows:
+//!
+//! * [`sequence::start()`] a sequence of [steps](state::Step), which persists the [state](State) in `.git/sequencer`.
+//! * [`sequence::run()`] to apply one step after another until the sequence is done, or until a conflict stops it.
+//!   When stopped, [`CHERRY_PICK_HEAD`] or [`REVERT_HEAD`] point to the commit that couldn't be applied.
+//! * After conflicts were resolved in the index, [`sequence::resume()`] to commit the resolution, or [`sequence::skip()`]
+//!   the conflicting commit, and [`sequence::run()`] again.
+//! * Use [`sequence::abort()`] to restore `HEAD`, the index and the worktree to what they were before the sequence started,
+//!   or [`sequence::quit()`] to forget about the sequence while keeping everything as is.
+//!
+//! The index and worktree are updated through an implementation of the [`Worktree`] trait, which keeps this crate
+//! independent of how checkouts are performed.
+//!
+//! As the persisted state is compatible with what `git` writes, `git cherry-pick --continue` can pick up where
+//! this crate left off, and vice versa.
+//!
+//! ### Deviation
+//!
+//! * Commits that become empty are dropped unless [`allow_empty`](state::Options::allow_empty) is set, whereas `git`
+//!   would stop and ask what to do.
+#![deny(missing_docs)]
 #![forbid(unsafe_code)]
+
+/// The name of the reference that points to the commit that is cherry-picked while the sequence is stopped.
+pub const CHERRY_PICK_HEAD: &str = "CHERRY_PICK_HEAD";
+/// The name of the reference that points to the commit that is reverted while the sequence is stopped.
+pub const REVERT_HEAD: &str = "REVERT_HEAD";
+/// The name of the reference that points to the commit `HEAD` pointed to before it was moved by a potentially
+/// dangerous operation, like aborting a sequence.
+pub const ORIG_HEAD: &str = "ORIG_HEAD";
+
+///
+pub mod state;
+pub use state::State;
+
+///
+pub mod message;
+
+///
+pub mod worktree;
+pub use worktree::Worktree;
+
+///
+pub mod sequence;
+
+///
+pub mod refs;
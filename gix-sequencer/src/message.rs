@@ -0,0 +1,51 @@
+//! Create the messages of commits that were cherry-picked or reverted, the way `git` does.
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_hash::ObjectId;
+
+/// Return the message for a commit that reverts the commit with `id` and `message`.
+///
+/// If a merge commit is reverted, `mainline_parent` is the parent whose changes are kept.
+pub fn revert(id: &gix_hash::oid, message: &BStr, mainline_parent: Option<ObjectId>) -> BString {
+    let summary = gix_object::commit::MessageRef::from_bytes(message).summary();
+    let mut out = BString::default();
+    match summary
+        .strip_prefix(b"Revert \"")
+        .and_then(|rest| rest.strip_suffix(b"\""))
+    {
+        // Reverting a revert restores the original commit.
+        Some(original) => {
+            out.push_str("Reapply \"");
+            out.push_str(original);
+            out.push_str("\"");
+        }
+        None => {
+            out.push_str("Revert \"");
+            out.push_str(summary.as_ref());
+            out.push_str("\"");
+        }
+    }
+    out.push_str(format!("\n\nThis reverts commit {id}"));
+    match mainline_parent {
+        Some(parent) => out.push_str(format!(", reversing\nchanges made to {parent}.\n")),
+        None => out.push_str(".\n"),
+    }
+    out
+}
+
+/// Append a line to `message` which records that it was cherry-picked from the commit with `id`, like `git cherry-pick -x`.
+///
+/// If `message` ends with a block of trailers, the line is added to it, otherwise it's separated by an empty line.
+pub fn append_cherry_picked_from(message: &BStr, id: &gix_hash::oid) -> BString {
+    let mut out: BString = message.trim_end().into();
+    let last_paragraph = out.rfind(b"\n\n").map_or(b"".as_bstr(), |pos| out[pos + 2..].as_bstr());
+    let ends_with_trailers = !last_paragraph.is_empty()
+        && last_paragraph.lines().all(|line| {
+            line.find(b": ").is_some_and(|pos| {
+                let key = &line[..pos];
+                !key.is_empty() && key.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'-')
+            }) || line.starts_with(b"(cherry picked from commit ")
+        });
+    out.push_str(if ends_with_trailers { "\n" } else { "\n\n" });
+    out.push_str(format!("(cherry picked from commit {id})\n"));
+    out
+}
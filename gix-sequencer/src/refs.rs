@@ -0,0 +1,90 @@
+use gix_hash::ObjectId;
+use gix_ref::{
+    FullName, Target,
+    store::ReferenceExt,
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+};
+
+/// The error returned by [`head_id()`], [`set_head()`] and [`edit()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    FindExistingReference(#[from] gix_ref::store::find::existing::Error),
+    #[error(transparent)]
+    PeelReference(#[from] gix_ref::peel::to_id::Error),
+    #[error(transparent)]
+    PrepareTransaction(#[from] gix_ref::store::transaction::prepare::Error),
+    #[error(transparent)]
+    CommitTransaction(#[from] gix_ref::store::transaction::commit::Error),
+}
+
+/// Return the commit `HEAD` in `refs` points to, using `objects` to peel it.
+pub fn head_id(refs: &gix_ref::store::Handle, objects: &impl gix_object::Find) -> Result<ObjectId, Error> {
+    Ok(refs.find("HEAD")?.peel_to_id(refs, objects)?)
+}
+
+/// Point `HEAD` in `refs`, or the branch it refers to, to `id`, logging `message` as `committer`.
+pub fn set_head(
+    refs: &gix_ref::store::Handle,
+    id: ObjectId,
+    message: String,
+    committer: gix_actor::SignatureRef<'_>,
+) -> Result<(), Error> {
+    edit(
+        refs,
+        Some(update(
+            "HEAD".try_into().expect("valid"),
+            Target::Object(id),
+            message,
+            true,
+        )),
+        committer,
+    )
+}
+
+/// Create an edit to set the reference `name` to `new`, logging `message`, following symbolic references if `deref` is `true`.
+pub fn update(name: FullName, new: Target, message: String, deref: bool) -> RefEdit {
+    RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: false,
+                message: message.into(),
+            },
+            expected: PreviousValue::Any,
+            new,
+        },
+        name,
+        deref,
+    }
+}
+
+/// Create an edit to delete the reference `name` along with its reflog.
+pub fn delete(name: FullName) -> RefEdit {
+    RefEdit {
+        change: Change::Delete {
+            expected: PreviousValue::Any,
+            log: RefLog::AndReference,
+        },
+        name,
+        deref: false,
+    }
+}
+
+/// Apply `edits` to `refs` in a single transaction, failing immediately if a reference is locked, and
+/// using `committer` for reflog entries.
+pub fn edit<'a>(
+    refs: &gix_ref::store::Handle,
+    edits: impl IntoIterator<Item = RefEdit>,
+    committer: impl Into<Option<gix_actor::SignatureRef<'a>>>,
+) -> Result<(), Error> {
+    refs.transaction()
+        .prepare(
+            edits,
+            gix_lock::acquire::Fail::Immediately,
+            gix_lock::acquire::Fail::Immediately,
+        )?
+        .commit(committer)?;
+    Ok(())
+}
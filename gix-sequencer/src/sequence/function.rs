@@ -0,0 +1,410 @@
+use std::path::Path;
+
+use bstr::{BStr, BString, ByteSlice};
+use gix_hash::ObjectId;
+use gix_object::FindExt;
+use gix_ref::Target;
+
+use crate::{
+    CHERRY_PICK_HEAD, ORIG_HEAD, REVERT_HEAD, State, Worktree, message,
+    refs::{self, head_id, set_head},
+    sequence::{Error, Options, Outcome},
+    state::{self, Action, Step},
+};
+
+/// The name of the file in the `.git` directory which holds the message of the commit to create once conflicts are resolved.
+const MERGE_MSG: &str = "MERGE_MSG";
+
+/// Start a sequence that applies all `steps` in order with the given `options`, and write its state into the `git_dir`
+/// of `refs`, using `objects` to peel `HEAD`.
+///
+/// Use [`run()`] to apply the steps.
+pub fn start(
+    steps: Vec<Step>,
+    options: state::Options,
+    refs: &gix_ref::store::Handle,
+    objects: &impl gix_object::Find,
+) -> Result<State, Error> {
+    let git_dir = refs.git_dir();
+    if State::dir(git_dir).exists() || stopped_at(refs)?.is_some() {
+        return Err(Error::InProgress);
+    }
+    let head = head_id(refs, objects)?;
+    let state = State {
+        head,
+        todo: steps,
+        options,
+        abort_safety: Some(head),
+    };
+    state.write(git_dir)?;
+    Ok(state)
+}
+
+/// Apply the steps of the sequence in `state` one by one, until all of them are applied or until a conflict occurs.
+///
+/// Each commit is applied by merging it into the tree of `HEAD`, using the tree of its parent as merge-base, or the other way around
+/// if it is reverted.
+/// This is done using `diff_resource_cache` and `blob_merge` to compute and merge changes, and `objects` to read
+/// commits and write the merge result.
+/// Use `abbreviate_hash(id)` to shorten the given `id` for use in conflict markers.
+/// After each step, `worktree` is updated to match the new `HEAD`, or the merged tree if
+/// [`no_commit`](state::Options::no_commit) is set.
+///
+/// Once all steps are applied, the sequencer state is removed.
+/// If a conflict stops the sequence, the conflicts are written through `worktree`, and `CHERRY_PICK_HEAD` or `REVERT_HEAD`
+/// is set to the commit that couldn't be applied, so that [`resume()`] or `git cherry-pick --continue` can pick up from there.
+#[allow(clippy::too_many_arguments)]
+pub fn run<'objects>(
+    state: &mut State,
+    refs: &gix_ref::store::Handle,
+    objects: &'objects (impl gix_object::FindObjectOrHeader + gix_object::Write),
+    worktree: &mut dyn Worktree,
+    diff_resource_cache: &mut gix_diff::blob::Platform,
+    blob_merge: &mut gix_merge::blob::Platform,
+    abbreviate_hash: &mut dyn FnMut(&gix_hash::oid) -> String,
+    options: &Options,
+) -> Result<Outcome<'objects>, Error> {
+    if let Some((_, id)) = stopped_at(refs)? {
+        return Err(Error::Stopped { id });
+    }
+    let git_dir = refs.git_dir();
+    let mut time_buf = gix_date::parse::TimeBuf::default();
+    let committer = options.committer.to_ref(&mut time_buf);
+    let mut buf = Vec::new();
+    let mut tree_diff_state = gix_diff::tree::State::default();
+
+    while let Some(step) = state.todo.first().cloned() {
+        let head = head_id(refs, objects)?;
+        let head_tree = objects.find_commit(&head, &mut buf)?.tree();
+        let commit = objects.find_commit(&step.id, &mut buf)?.to_owned()?;
+        let parent = mainline_parent(step.id, &commit.parents, state.options.mainline)?;
+        let summary = gix_object::commit::MessageRef::from_bytes(&commit.message)
+            .summary()
+            .into_owned();
+
+        if step.action == Action::Pick && state.options.allow_ff && !state.options.no_commit && parent == Some(head) {
+            set_head(
+                refs,
+                step.id,
+                format!("{}: fast-forward", step.action.reflog_action()),
+                committer,
+            )?;
+            worktree.reset(&commit.tree).map_err(Error::Worktree)?;
+            state.abort_safety = Some(step.id);
+            state.todo.remove(0);
+            state.write(git_dir)?;
+            continue;
+        }
+
+        let parent_tree = match parent {
+            Some(parent) => objects.find_commit(&parent, &mut buf)?.tree(),
+            None => ObjectId::empty_tree(step.id.kind()),
+        };
+        let our_tree = if state.options.no_commit {
+            worktree.write_tree().map_err(Error::Worktree)?
+        } else {
+            head_tree
+        };
+        let commit_label: BString = format!("{} ({summary})", abbreviate_hash(&step.id)).into();
+        let parent_label: BString = format!("parent of {commit_label}").into();
+        let (base_tree, their_tree, ancestor_label, their_label) = match step.action {
+            Action::Pick => (parent_tree, commit.tree, &parent_label, &commit_label),
+            Action::Revert => (commit.tree, parent_tree, &commit_label, &parent_label),
+        };
+        let mut tree_merge = gix_merge::tree(
+            &base_tree,
+            &our_tree,
+            &their_tree,
+            gix_merge::blob::builtin_driver::text::Labels {
+                ancestor: Some(ancestor_label.as_ref()),
+                current: Some("HEAD".into()),
+                other: Some(their_label.as_ref()),
+            },
+            objects,
+            |buf| objects.write_buf(gix_object::Kind::Blob, buf),
+            &mut tree_diff_state,
+            diff_resource_cache,
+            blob_merge,
+            options.tree_merge.clone(),
+        )?;
+
+        let (message, author) = match step.action {
+            Action::Pick if state.options.record_origin => (
+                message::append_cherry_picked_from(commit.message.as_ref(), &step.id),
+                commit.author.clone(),
+            ),
+            Action::Pick => (commit.message.clone(), commit.author.clone()),
+            Action::Revert => (
+                message::revert(&step.id, commit.message.as_ref(), state.options.mainline.and(parent)),
+                options.committer.clone(),
+            ),
+        };
+
+        let tree = tree_merge.tree.write(|tree| objects.write(tree))?;
+        if tree_merge.has_unresolved_conflicts(options.treat_as_unresolved) {
+            worktree.write_conflicts(&tree, &tree_merge).map_err(Error::Worktree)?;
+            write_message(git_dir, message.as_ref())?;
+            refs::edit(
+                refs,
+                Some(refs::update(
+                    step.action.head_name().try_into().expect("valid"),
+                    Target::Object(step.id),
+                    String::new(),
+                    false,
+                )),
+                committer,
+            )?;
+            return Ok(Outcome::Stopped {
+                id: step.id,
+                action: step.action,
+                tree_merge,
+            });
+        }
+
+        if state.options.no_commit {
+            worktree.reset(&tree).map_err(Error::Worktree)?;
+        } else if tree != head_tree || state.options.allow_empty {
+            let new_commit = objects.write(&gix_object::Commit {
+                tree,
+                parents: [head].into(),
+                author,
+                committer: options.committer.clone(),
+                encoding: commit.encoding,
+                message,
+                extra_headers: Vec::new(),
+            })?;
+            let new_summary = summary_of(objects, &new_commit)?;
+            set_head(
+                refs,
+                new_commit,
+                format!("{}: {new_summary}", step.action.reflog_action()),
+                committer,
+            )?;
+            worktree.reset(&tree).map_err(Error::Worktree)?;
+            state.abort_safety = Some(new_commit);
+        }
+        state.todo.remove(0);
+        state.write(git_dir)?;
+    }
+
+    State::remove(git_dir).map_err(Error::RemoveState)?;
+    Ok(Outcome::Finished {
+        head: head_id(refs, objects)?,
+    })
+}
+
+/// Resume the sequence in `state` that stopped due to conflicts by committing the tree of the index obtained from `worktree`
+/// with the message prepared when stopping, using `objects` to read and write commits and `refs` to update `HEAD`.
+///
+/// The message is read from `.git/MERGE_MSG` and thus can be adjusted before resuming, just like with `git`, with lines starting
+/// with `#` being removed.
+/// If the tree is the same as the tree of `HEAD` and empty commits aren't [allowed](state::Options::allow_empty),
+/// or if [`no_commit`](state::Options::no_commit) is set, no commit is created.
+///
+/// Returns the id of the newly created commit, if there was one.
+/// Use [`run()`] to continue with the next step thereafter.
+#[doc(alias = "continue")]
+pub fn resume(
+    state: &mut State,
+    refs: &gix_ref::store::Handle,
+    objects: &(impl gix_object::Find + gix_object::Write),
+    worktree: &mut dyn Worktree,
+    options: &Options,
+) -> Result<Option<ObjectId>, Error> {
+    let (action, id) = stopped_at(refs)?.ok_or(Error::NotStopped)?;
+    let git_dir = refs.git_dir();
+    let tree = worktree.write_tree().map_err(Error::Worktree)?;
+    let head = head_id(refs, objects)?;
+    let mut buf = Vec::new();
+    let head_tree = objects.find_commit(&head, &mut buf)?.tree();
+
+    let new_commit = if state.options.no_commit || (tree == head_tree && !state.options.allow_empty) {
+        None
+    } else {
+        let commit = objects.find_commit(&id, &mut buf)?.to_owned()?;
+        let message = match read_message(git_dir)? {
+            Some(message) => message,
+            None => commit.message.clone(),
+        };
+        let author = match action {
+            Action::Pick => commit.author,
+            Action::Revert => options.committer.clone(),
+        };
+        let new_commit = objects.write(&gix_object::Commit {
+            tree,
+            parents: [head].into(),
+            author,
+            committer: options.committer.clone(),
+            encoding: commit.encoding,
+            message,
+            extra_headers: Vec::new(),
+        })?;
+        let summary = summary_of(objects, &new_commit)?;
+        let reflog_message = match action {
+            Action::Pick => format!("commit (cherry-pick): {summary}"),
+            Action::Revert => format!("commit: {summary}"),
+        };
+        let mut time_buf = gix_date::parse::TimeBuf::default();
+        set_head(
+            refs,
+            new_commit,
+            reflog_message,
+            options.committer.to_ref(&mut time_buf),
+        )?;
+        state.abort_safety = Some(new_commit);
+        Some(new_commit)
+    };
+
+    clear_stopped(refs)?;
+    if state.todo.first().is_some_and(|step| step.id == id) {
+        state.todo.remove(0);
+    }
+    state.write(git_dir)?;
+    Ok(new_commit)
+}
+
+/// Skip the commit the sequence in `state` stopped at by resetting `worktree` to the tree of `HEAD`, so that [`run()`]
+/// can continue with the next step.
+/// `refs` and `objects` are used to find `HEAD` and to remove `CHERRY_PICK_HEAD` or `REVERT_HEAD`.
+pub fn skip(
+    state: &mut State,
+    refs: &gix_ref::store::Handle,
+    objects: &impl gix_object::Find,
+    worktree: &mut dyn Worktree,
+) -> Result<(), Error> {
+    let (_, id) = stopped_at(refs)?.ok_or(Error::NotStopped)?;
+    let head = head_id(refs, objects)?;
+    let tree = objects.find_commit(&head, &mut Vec::new())?.tree();
+    worktree.reset(&tree).map_err(Error::Worktree)?;
+    clear_stopped(refs)?;
+    if state.todo.first().is_some_and(|step| step.id == id) {
+        state.todo.remove(0);
+    }
+    state.write(refs.git_dir())?;
+    Ok(())
+}
+
+/// Abort the sequence in `state` by moving `HEAD` back to the commit it pointed to when the sequence started,
+/// and by resetting `worktree` to it, using `committer` for reflog entries.
+/// `ORIG_HEAD` is set to the commit `HEAD` pointed to before aborting.
+///
+/// This fails if `HEAD` was moved since the last commit of the sequence was created, as the commits made in the
+/// meantime would be lost.
+pub fn abort(
+    state: State,
+    refs: &gix_ref::store::Handle,
+    objects: &impl gix_object::Find,
+    worktree: &mut dyn Worktree,
+    committer: gix_actor::SignatureRef<'_>,
+) -> Result<(), Error> {
+    let head = head_id(refs, objects)?;
+    if let Some(expected) = state.abort_safety.filter(|expected| *expected != head) {
+        return Err(Error::HeadMoved { expected, actual: head });
+    }
+    refs::edit(
+        refs,
+        [
+            refs::update(
+                ORIG_HEAD.try_into().expect("valid"),
+                Target::Object(head),
+                String::new(),
+                false,
+            ),
+            refs::update(
+                "HEAD".try_into().expect("valid"),
+                Target::Object(state.head),
+                format!("reset: moving to {}", state.head),
+                true,
+            ),
+        ],
+        committer,
+    )?;
+    let tree = objects.find_commit(&state.head, &mut Vec::new())?.tree();
+    worktree.reset(&tree).map_err(Error::Worktree)?;
+    quit(refs)
+}
+
+/// Forget about the sequence in progress by removing its state and `CHERRY_PICK_HEAD` or `REVERT_HEAD` using `refs`,
+/// while leaving `HEAD`, the index and the worktree as they are.
+pub fn quit(refs: &gix_ref::store::Handle) -> Result<(), Error> {
+    clear_stopped(refs)?;
+    State::remove(refs.git_dir()).map_err(Error::RemoveState)
+}
+
+/// Return the action and the commit a stopped cherry-pick or revert is stopped at, as indicated by the presence of
+/// `CHERRY_PICK_HEAD` or `REVERT_HEAD` in `refs`, or `None` if nothing is stopped.
+pub fn stopped_at(refs: &gix_ref::store::Handle) -> Result<Option<(Action, ObjectId)>, Error> {
+    for action in [Action::Pick, Action::Revert] {
+        if let Some(reference) = refs.try_find(action.head_name())? {
+            if let Some(id) = reference.target.try_id() {
+                return Ok(Some((action, id.to_owned())));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Return the parent of commit `id` with `parents` to use as base, according to `mainline`.
+///
+/// Like `git`, `mainline` is ignored for commits that aren't merges so it can be used with ranges that contain both.
+fn mainline_parent(id: ObjectId, parents: &[ObjectId], mainline: Option<usize>) -> Result<Option<ObjectId>, Error> {
+    match (parents, mainline) {
+        ([], _) => Ok(None),
+        ([parent], _) => Ok(Some(*parent)),
+        (_, None) => Err(Error::MergeWithoutMainline { id }),
+        (parents, Some(mainline)) => mainline
+            .checked_sub(1)
+            .and_then(|idx| parents.get(idx))
+            .copied()
+            .map(Some)
+            .ok_or(Error::InvalidMainline { id, mainline }),
+    }
+}
+
+fn summary_of(objects: &impl gix_object::Find, id: &gix_hash::oid) -> Result<BString, Error> {
+    Ok(objects
+        .find_commit(id, &mut Vec::new())?
+        .message()
+        .summary()
+        .into_owned())
+}
+
+fn write_message(git_dir: &Path, message: &BStr) -> Result<(), Error> {
+    let path = git_dir.join(MERGE_MSG);
+    std::fs::write(&path, message).map_err(|source| Error::Message { source, path })
+}
+
+/// Read the message to commit, with comment lines removed, or `None` if there is no such message.
+fn read_message(git_dir: &Path) -> Result<Option<BString>, Error> {
+    let path = git_dir.join(MERGE_MSG);
+    let content = match std::fs::read(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => return Err(Error::Message { source, path }),
+    };
+    let mut message = BString::default();
+    for line in content.lines_with_terminator().filter(|line| !line.starts_with(b"#")) {
+        message.extend_from_slice(line);
+    }
+    let mut message: BString = message.trim_end().into();
+    message.push(b'\n');
+    Ok(Some(message))
+}
+
+/// Remove `CHERRY_PICK_HEAD`, `REVERT_HEAD` and the prepared message.
+fn clear_stopped(refs: &gix_ref::store::Handle) -> Result<(), Error> {
+    let mut edits = Vec::new();
+    for name in [CHERRY_PICK_HEAD, REVERT_HEAD] {
+        if refs.try_find(name)?.is_some() {
+            edits.push(refs::delete(name.try_into().expect("valid")));
+        }
+    }
+    if !edits.is_empty() {
+        refs::edit(refs, edits, None)?;
+    }
+    let path = refs.git_dir().join(MERGE_MSG);
+    match std::fs::remove_file(&path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(Error::Message { source: err, path }),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,81 @@
+use gix_hash::ObjectId;
+
+use crate::state::Action;
+
+/// The error returned by functions in the [sequence](crate::sequence) module.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("A cherry-pick or revert is already in progress")]
+    InProgress,
+    #[error("The sequence is stopped at {id} and needs to be resumed or skipped first")]
+    Stopped { id: ObjectId },
+    #[error("No cherry-pick or revert is stopped and there is nothing to resume")]
+    NotStopped,
+    #[error("HEAD was moved to {actual} since the last commit {expected} was made, not rewinding")]
+    HeadMoved { expected: ObjectId, actual: ObjectId },
+    #[error("Commit {id} is a merge but no mainline was specified")]
+    MergeWithoutMainline { id: ObjectId },
+    #[error("Commit {id} does not have parent number {mainline}")]
+    InvalidMainline { id: ObjectId, mainline: usize },
+    #[error(transparent)]
+    ReadState(#[from] crate::state::read::Error),
+    #[error(transparent)]
+    WriteState(#[from] crate::state::write::Error),
+    #[error("Could not remove the sequencer state directory")]
+    RemoveState(#[source] std::io::Error),
+    #[error("Could not access the commit message at '{}'", path.display())]
+    Message {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error("Could not update the index or worktree")]
+    Worktree(#[source] crate::worktree::Error),
+    #[error(transparent)]
+    FindObject(#[from] gix_object::find::existing_object::Error),
+    #[error(transparent)]
+    DecodeCommit(#[from] gix_object::decode::Error),
+    #[error(transparent)]
+    WriteObject(#[from] gix_object::write::Error),
+    #[error(transparent)]
+    MergeTree(#[from] gix_merge::tree::Error),
+    #[error(transparent)]
+    FindReference(#[from] gix_ref::store::find::Error),
+    #[error(transparent)]
+    Refs(#[from] crate::refs::Error),
+}
+
+/// A way to configure [`run()`] and [`resume()`].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Options to define how the trees of commits should be merged.
+    pub tree_merge: gix_merge::tree::Options,
+    /// Determine which conflicts should stop the sequence.
+    pub treat_as_unresolved: gix_merge::tree::TreatAsUnresolved,
+    /// The committer to use for all created commits and for reflog entries, and the author of reverts.
+    pub committer: gix_actor::Signature,
+}
+
+/// The result of [`run()`].
+pub enum Outcome<'a> {
+    /// All steps were applied and the sequencer state was removed.
+    Finished {
+        /// The commit `HEAD` points to now.
+        head: ObjectId,
+    },
+    /// Applying a commit led to conflicts and the sequence stopped.
+    ///
+    /// The conflicts were written to the index and worktree.
+    /// Once they are resolved, [resume](resume()) the sequence, or [skip](skip()) the commit instead.
+    Stopped {
+        /// The commit whose changes couldn't be applied without conflicts.
+        id: ObjectId,
+        /// What was done with the commit.
+        action: Action,
+        /// The outcome of the tree-merge.
+        tree_merge: gix_merge::tree::Outcome<'a>,
+    },
+}
+
+pub(super) mod function;
+pub use function::{abort, quit, resume, run, skip, start, stopped_at};
@@ -0,0 +1,338 @@
+//! Read and write the state of a sequence in progress from and to the `.git/sequencer` directory.
+//!
+//! The layout is the one used by `git`, so a sequence started here can be continued by `git`, and vice versa.
+use std::path::{Path, PathBuf};
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_hash::ObjectId;
+
+/// The name of the directory inside of the `.git` directory which holds the state of a sequence in progress.
+pub const DIR_NAME: &str = "sequencer";
+
+/// What to do with a commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Apply the changes of the commit, like `git cherry-pick`.
+    Pick,
+    /// Undo the changes of the commit, like `git revert`.
+    Revert,
+}
+
+impl Action {
+    /// Return the name of the action as used in the todo-list and in reflog messages.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Pick => "pick",
+            Action::Revert => "revert",
+        }
+    }
+
+    /// Return the name of the reference which points to the commit the action is applied to while the sequence is stopped.
+    pub fn head_name(&self) -> &'static str {
+        match self {
+            Action::Pick => crate::CHERRY_PICK_HEAD,
+            Action::Revert => crate::REVERT_HEAD,
+        }
+    }
+
+    /// Return the prefix to use for reflog messages.
+    pub fn reflog_action(&self) -> &'static str {
+        match self {
+            Action::Pick => "cherry-pick",
+            Action::Revert => "revert",
+        }
+    }
+}
+
+/// A single commit to apply, as it is stored in the `todo` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// What to do with the commit.
+    pub action: Action,
+    /// The id of the commit to apply.
+    pub id: ObjectId,
+    /// The summary of the commit for display, which is informational only.
+    pub summary: BString,
+}
+
+/// Options that affect all steps of a sequence, as stored in the `opts` file.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// If `true`, apply the changes to the index and worktree without committing them, like `--no-commit`.
+    pub no_commit: bool,
+    /// The 1-based number of the parent to use as base when applying merge commits, like `--mainline`.
+    ///
+    /// It is ignored for commits that aren't merges.
+    pub mainline: Option<usize>,
+    /// If `true`, append a line with the id of the original commit to the messages of cherry-picked commits, like `-x`.
+    pub record_origin: bool,
+    /// If `true`, commits that are or become empty are committed nonetheless, like `--allow-empty`.
+    pub allow_empty: bool,
+    /// If `true`, fast-forward to the commit to pick if its parent is the current `HEAD`, like `--ff`.
+    pub allow_ff: bool,
+}
+
+/// The persisted state of a sequence in progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    /// The commit `HEAD` pointed to when the sequence started, which is restored when aborting.
+    pub head: ObjectId,
+    /// The commits which still have to be applied, in order.
+    ///
+    /// If the sequence stopped, the first step is the one that stopped it.
+    pub todo: Vec<Step>,
+    /// Options affecting all steps.
+    pub options: Options,
+    /// The commit `HEAD` pointed to after the last step was committed.
+    ///
+    /// If `HEAD` doesn't point to it anymore, it was moved by the user and aborting would lose their work.
+    pub abort_safety: Option<ObjectId>,
+}
+
+/// The error returned by [`State::read()`].
+pub mod read {
+    use std::path::PathBuf;
+
+    use bstr::BString;
+
+    /// The error returned by [`State::read()`](super::State::read()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read sequencer state file at '{}'", path.display())]
+        Io { source: std::io::Error, path: PathBuf },
+        #[error("Could not parse object id in '{}'", path.display())]
+        Id {
+            source: gix_hash::decode::Error,
+            path: PathBuf,
+        },
+        #[error("Could not parse line {line_number} in '{}': {line:?}", path.display())]
+        Line {
+            line_number: usize,
+            line: BString,
+            path: PathBuf,
+        },
+    }
+}
+
+/// The error returned by [`State::write()`].
+pub mod write {
+    use std::path::PathBuf;
+
+    /// The error returned by [`State::write()`](super::State::write()).
+    #[derive(Debug, thiserror::Error)]
+    #[error("Could not write sequencer state file at '{}'", path.display())]
+    pub struct Error {
+        /// The underlying error.
+        pub source: std::io::Error,
+        /// The path we tried to write to.
+        pub path: PathBuf,
+    }
+}
+
+/// Lifecycle
+impl State {
+    /// Return the directory within `git_dir` which holds the state of a sequence in progress.
+    pub fn dir(git_dir: &Path) -> PathBuf {
+        git_dir.join(DIR_NAME)
+    }
+
+    /// Read the state of the sequence in progress from `git_dir`, or return `None` if there is no such sequence.
+    ///
+    /// Note that all commits in the todo-list must be full object ids, see [`read_resolving()`](Self::read_resolving())
+    /// to read todo-lists written by `git`.
+    pub fn read(git_dir: &Path) -> Result<Option<Self>, read::Error> {
+        Self::read_resolving(git_dir, |name| ObjectId::from_hex(name).ok())
+    }
+
+    /// Like [`read()`](Self::read()), but use `resolve(name)` to turn the object names in the todo-list into full object ids,
+    /// which is required as `git` writes abbreviated ids.
+    pub fn read_resolving(
+        git_dir: &Path,
+        resolve: impl FnMut(&BStr) -> Option<ObjectId>,
+    ) -> Result<Option<Self>, read::Error> {
+        let dir = Self::dir(git_dir);
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+        let abort_safety = dir.join("abort-safety");
+        Ok(Some(State {
+            head: read_id(&dir.join("head"))?,
+            todo: read_todo(&dir.join("todo"), resolve)?,
+            options: read_options(&dir.join("opts"))?,
+            abort_safety: abort_safety.is_file().then(|| read_id(&abort_safety)).transpose()?,
+        }))
+    }
+
+    /// Write all state into its directory within `git_dir`, creating it if needed.
+    pub fn write(&self, git_dir: &Path) -> Result<(), write::Error> {
+        let dir = Self::dir(git_dir);
+        std::fs::create_dir_all(&dir).map_err(|source| write::Error {
+            source,
+            path: dir.clone(),
+        })?;
+
+        write_file(&dir.join("head"), format!("{}\n", self.head).as_bytes())?;
+        let mut todo = BString::default();
+        for step in &self.todo {
+            todo.push_str(format!("{} {} ", step.action.as_str(), step.id));
+            todo.extend_from_slice(&step.summary);
+            todo.push(b'\n');
+        }
+        write_file(&dir.join("todo"), &todo)?;
+        write_file(&dir.join("opts"), &encode_options(&self.options))?;
+        let abort_safety = dir.join("abort-safety");
+        match self.abort_safety {
+            Some(id) => write_file(&abort_safety, format!("{id}\n").as_bytes()),
+            None => match std::fs::remove_file(&abort_safety) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(write::Error {
+                    source: err,
+                    path: abort_safety,
+                }),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Remove the state directory from `git_dir`, which forgets about the sequence.
+    pub fn remove(git_dir: &Path) -> std::io::Result<()> {
+        match std::fs::remove_dir_all(Self::dir(git_dir)) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+}
+
+fn encode_options(options: &Options) -> BString {
+    let Options {
+        no_commit,
+        mainline,
+        record_origin,
+        allow_empty,
+        allow_ff,
+    } = *options;
+    let mut out = BString::from("[options]\n");
+    for (key, enabled) in [
+        ("no-commit", no_commit),
+        ("record-origin", record_origin),
+        ("allow-empty", allow_empty),
+        ("allow-ff", allow_ff),
+    ] {
+        if enabled {
+            out.push_str(format!("\t{key} = true\n"));
+        }
+    }
+    if let Some(mainline) = mainline {
+        out.push_str(format!("\tmainline = {mainline}\n"));
+    }
+    out
+}
+
+fn read_options(path: &Path) -> Result<Options, read::Error> {
+    let mut options = Options::default();
+    let Some(content) = read_optional(path)? else {
+        return Ok(options);
+    };
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(b"#") || line.starts_with(b";") || line.starts_with(b"[") {
+            continue;
+        }
+        let parse_error = || read::Error::Line {
+            line_number: line_number + 1,
+            line: line.into(),
+            path: path.to_owned(),
+        };
+        let (key, value) = match line.split_once_str(b"=") {
+            Some((key, value)) => (key.trim(), value.trim()),
+            // A key without value is `true` in git-config.
+            None => (line, b"true".as_slice()),
+        };
+        let flag = || match value {
+            b"true" | b"yes" | b"on" | b"1" => Ok(true),
+            b"false" | b"no" | b"off" | b"0" | b"" => Ok(false),
+            _ => Err(parse_error()),
+        };
+        match key {
+            b"no-commit" => options.no_commit = flag()?,
+            b"record-origin" => options.record_origin = flag()?,
+            b"allow-empty" => options.allow_empty = flag()?,
+            b"allow-ff" => options.allow_ff = flag()?,
+            b"mainline" => {
+                options.mainline = Some(
+                    value
+                        .to_str()
+                        .ok()
+                        .and_then(|value| value.parse().ok())
+                        .ok_or_else(parse_error)?,
+                );
+            }
+            // Options we don't support, like `signoff` or `strategy`, are ignored.
+            _ => {}
+        }
+    }
+    Ok(options)
+}
+
+fn read_todo(path: &Path, mut resolve: impl FnMut(&BStr) -> Option<ObjectId>) -> Result<Vec<Step>, read::Error> {
+    let Some(content) = read_optional(path)? else {
+        return Ok(Vec::new());
+    };
+    let mut out = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(b"#") {
+            continue;
+        }
+        let parse_error = || read::Error::Line {
+            line_number: line_number + 1,
+            line: line.into(),
+            path: path.to_owned(),
+        };
+        let mut tokens = line.splitn_str(3, b" ");
+        let action = match tokens.next() {
+            Some(b"pick" | b"p") => Action::Pick,
+            Some(b"revert") => Action::Revert,
+            _ => return Err(parse_error()),
+        };
+        let id = tokens
+            .next()
+            .and_then(|name| resolve(name.as_bstr()))
+            .ok_or_else(parse_error)?;
+        out.push(Step {
+            action,
+            id,
+            summary: tokens.next().unwrap_or_default().into(),
+        });
+    }
+    Ok(out)
+}
+
+fn read_id(path: &Path) -> Result<ObjectId, read::Error> {
+    let content = std::fs::read(path).map_err(|source| read::Error::Io {
+        source,
+        path: path.to_owned(),
+    })?;
+    ObjectId::from_hex(content.trim()).map_err(|source| read::Error::Id {
+        source,
+        path: path.to_owned(),
+    })
+}
+
+fn read_optional(path: &Path) -> Result<Option<BString>, read::Error> {
+    match std::fs::read(path) {
+        Ok(content) => Ok(Some(content.into())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(read::Error::Io {
+            source,
+            path: path.to_owned(),
+        }),
+    }
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<(), write::Error> {
+    std::fs::write(path, content).map_err(|source| write::Error {
+        source,
+        path: path.to_owned(),
+    })
+}
@@ -0,0 +1,22 @@
+use gix_hash::ObjectId;
+
+/// The error returned by implementations of [`Worktree`].
+pub type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Keep the index and the worktree in sync with the commits created by a sequence, and obtain the tree
+/// of the index once conflicts were resolved.
+///
+/// Implementations for bare repositories may keep the index in memory, and do nothing to the worktree.
+pub trait Worktree {
+    /// Make the index and the tracked files in the worktree match `tree`, just like `git reset --hard` would.
+    fn reset(&mut self, tree: &gix_hash::oid) -> Result<(), Error>;
+
+    /// Write the result of a merge that stopped the sequence into the index and the worktree, so a user can resolve it.
+    ///
+    /// `tree` is the written tree of `merge` with all conflict markers, and the conflicts of `merge` should be recorded
+    /// in the index, typically with [`index_changed_after_applying_conflicts()`](gix_merge::tree::Outcome::index_changed_after_applying_conflicts()).
+    fn write_conflicts(&mut self, tree: &gix_hash::oid, merge: &gix_merge::tree::Outcome<'_>) -> Result<(), Error>;
+
+    /// Write the index as tree and return its id, or fail if there are still unresolved conflicts.
+    fn write_tree(&mut self) -> Result<ObjectId, Error>;
+}
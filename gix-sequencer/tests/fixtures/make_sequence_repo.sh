@@ -0,0 +1,32 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git checkout -q -b main
+
+function commit() {
+  local file=${1:?first argument is the file}
+  local content=${2:?second argument is the content}
+  echo "$content" > "$file"
+  git add "$file"
+  git commit -q -m "$file: $content"
+}
+
+commit a 1
+commit b 1
+git branch base
+
+git checkout -q -b topic base
+commit d 1
+commit b conflict
+commit e 1
+
+git checkout -q -b side base
+commit s 1
+git checkout -q -b merged base
+commit m 1
+git merge -q --no-ff -m "merge side" side
+
+git checkout -q main
+commit b 2
+commit c 1
@@ -0,0 +1,190 @@
+use std::path::Path;
+
+use gix_hash::ObjectId;
+use gix_object::{FindExt, bstr::BString};
+use gix_ref::store::ReferenceExt;
+
+pub use gix_testtools::Result;
+
+mod message;
+mod sequence;
+mod state;
+
+struct Fixture {
+    _tmp: gix_testtools::tempfile::TempDir,
+    odb: gix_odb::Handle,
+    refs: gix_ref::store::Handle,
+    root: std::path::PathBuf,
+}
+
+impl Fixture {
+    fn new() -> Result<Self> {
+        let tmp = gix_testtools::scripted_fixture_writable("make_sequence_repo.sh")?;
+        let root = tmp.path().to_owned();
+        let git_dir = root.join(".git");
+        let object_hash = gix_testtools::object_hash();
+        let odb = gix_odb::at_opts(
+            git_dir.join("objects"),
+            None,
+            gix_odb::store::init::Options {
+                object_hash,
+                ..Default::default()
+            },
+        )?;
+        let refs = gix_ref::Store::at(
+            git_dir,
+            gix_ref::store::init::Options {
+                write_reflog: gix_ref::store::WriteReflog::Normal,
+                object_hash,
+                ..Default::default()
+            },
+        )?
+        .to_handle();
+        Ok(Fixture {
+            _tmp: tmp,
+            odb,
+            refs,
+            root,
+        })
+    }
+
+    fn id(&self, name: &str) -> ObjectId {
+        self.refs
+            .find(name)
+            .expect("reference exists")
+            .peel_to_id(&self.refs, &self.odb)
+            .expect("peelable")
+    }
+
+    fn tree(&self, commit: &ObjectId) -> ObjectId {
+        self.odb
+            .find_commit(commit, &mut Vec::new())
+            .expect("commit exists")
+            .tree()
+    }
+
+    fn message(&self, id: &ObjectId) -> BString {
+        self.odb
+            .find_commit(id, &mut Vec::new())
+            .expect("commit exists")
+            .message
+            .to_owned()
+    }
+
+    /// Return the summaries of all commits reachable from `tip` along their first parent, starting at `tip`.
+    fn first_parent_summaries(&self, tip: ObjectId) -> Vec<BString> {
+        let mut buf = Vec::new();
+        let mut out = Vec::new();
+        let mut next = Some(tip);
+        while let Some(id) = next {
+            let commit = self.odb.find_commit(&id, &mut buf).expect("commit exists");
+            out.push(commit.message().summary().into_owned());
+            next = commit.parents().next();
+        }
+        out
+    }
+
+    /// Return the names of all files in `tree`, which is expected to be flat.
+    fn file_names(&self, tree: &ObjectId) -> Vec<String> {
+        self.odb
+            .find_tree(tree, &mut Vec::new())
+            .expect("tree exists")
+            .entries
+            .iter()
+            .map(|entry| entry.filename.to_string())
+            .collect()
+    }
+}
+
+/// A worktree that only tracks the tree of its index.
+struct Worktree {
+    tree: ObjectId,
+    unresolved_conflicts: bool,
+}
+
+impl Worktree {
+    fn new(tree: ObjectId) -> Self {
+        Worktree {
+            tree,
+            unresolved_conflicts: false,
+        }
+    }
+
+    /// Resolve all conflicts by putting `tree` into the index.
+    fn resolve(&mut self, tree: ObjectId) {
+        self.tree = tree;
+        self.unresolved_conflicts = false;
+    }
+}
+
+impl gix_sequencer::Worktree for Worktree {
+    fn reset(&mut self, tree: &gix_hash::oid) -> std::result::Result<(), gix_sequencer::worktree::Error> {
+        self.resolve(tree.to_owned());
+        Ok(())
+    }
+
+    fn write_conflicts(
+        &mut self,
+        tree: &gix_hash::oid,
+        merge: &gix_merge::tree::Outcome<'_>,
+    ) -> std::result::Result<(), gix_sequencer::worktree::Error> {
+        assert!(!merge.conflicts.is_empty());
+        self.tree = tree.to_owned();
+        self.unresolved_conflicts = true;
+        Ok(())
+    }
+
+    fn write_tree(&mut self) -> std::result::Result<ObjectId, gix_sequencer::worktree::Error> {
+        if self.unresolved_conflicts {
+            return Err("the index has conflicts".into());
+        }
+        Ok(self.tree)
+    }
+}
+
+fn new_diff_resource_cache(root: &Path) -> gix_diff::blob::Platform {
+    gix_diff::blob::Platform::new(
+        Default::default(),
+        gix_diff::blob::Pipeline::new(Default::default(), Default::default(), Vec::new(), Default::default()),
+        Default::default(),
+        gix_worktree::Stack::new(
+            root,
+            gix_worktree::stack::State::AttributesStack(gix_worktree::stack::state::Attributes::default()),
+            Default::default(),
+            Vec::new(),
+            Vec::new(),
+        ),
+    )
+}
+
+fn new_blob_merge_platform(root: &Path) -> gix_merge::blob::Platform {
+    let attributes = gix_worktree::Stack::new(
+        root,
+        gix_worktree::stack::State::AttributesStack(gix_worktree::stack::state::Attributes::new(
+            Default::default(),
+            None,
+            gix_worktree::stack::state::attributes::Source::IdMapping,
+            Default::default(),
+        )),
+        gix_worktree::glob::pattern::Case::Sensitive,
+        Vec::new(),
+        Vec::new(),
+    );
+    let filter =
+        gix_merge::blob::Pipeline::new(Default::default(), gix_filter::Pipeline::default(), Default::default());
+    gix_merge::blob::Platform::new(
+        filter,
+        gix_merge::blob::pipeline::Mode::ToGit,
+        attributes,
+        vec![],
+        Default::default(),
+    )
+}
+
+fn signature() -> gix_actor::Signature {
+    gix_actor::Signature {
+        name: "committer".into(),
+        email: "committer@example.com".into(),
+        time: gix_date::Time::new(1_700_000_000, 3600),
+    }
+}
@@ -0,0 +1,44 @@
+use gix_hash::ObjectId;
+use gix_sequencer::message;
+
+fn id(hex_char: char) -> ObjectId {
+    let hex: String = std::iter::repeat_n(hex_char, gix_testtools::object_hash().len_in_hex()).collect();
+    ObjectId::from_hex(hex.as_bytes()).expect("valid hex")
+}
+
+#[test]
+fn revert() {
+    assert_eq!(
+        message::revert(&id('a'), "subject\n\nbody\n".into(), None),
+        format!("Revert \"subject\"\n\nThis reverts commit {}.\n", id('a'))
+    );
+    assert_eq!(
+        message::revert(&id('a'), "Revert \"subject\"\n".into(), None),
+        format!("Reapply \"subject\"\n\nThis reverts commit {}.\n", id('a')),
+        "reverting a revert reapplies the original"
+    );
+    assert_eq!(
+        message::revert(&id('a'), "merge\n".into(), Some(id('b'))),
+        format!(
+            "Revert \"merge\"\n\nThis reverts commit {}, reversing\nchanges made to {}.\n",
+            id('a'),
+            id('b')
+        )
+    );
+}
+
+#[test]
+fn append_cherry_picked_from() {
+    assert_eq!(
+        message::append_cherry_picked_from("subject\n".into(), &id('a')),
+        format!("subject\n\n(cherry picked from commit {})\n", id('a'))
+    );
+    assert_eq!(
+        message::append_cherry_picked_from("subject\n\nbody\n\nSigned-off-by: A <a@example.com>\n".into(), &id('a')),
+        format!(
+            "subject\n\nbody\n\nSigned-off-by: A <a@example.com>\n(cherry picked from commit {})\n",
+            id('a')
+        ),
+        "trailers are extended"
+    );
+}
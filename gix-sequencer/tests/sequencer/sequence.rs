@@ -0,0 +1,350 @@
+use gix_hash::ObjectId;
+use gix_ref::store::ReferenceExt;
+use gix_sequencer::{
+    CHERRY_PICK_HEAD, ORIG_HEAD, REVERT_HEAD, State,
+    sequence::{self, Error, Options, Outcome},
+    state::{self, Action, Step},
+};
+
+use crate::{Fixture, Worktree, new_blob_merge_platform, new_diff_resource_cache, signature};
+
+fn options() -> Options {
+    Options {
+        tree_merge: Default::default(),
+        treat_as_unresolved: gix_merge::tree::TreatAsUnresolved::git(),
+        committer: signature(),
+    }
+}
+
+/// Return steps to apply all commits of `range`, given as `(tip, hidden)`, oldest first.
+fn steps(fixture: &Fixture, action: Action, tip: &str, hidden: &str) -> Vec<Step> {
+    let mut ids = Vec::new();
+    let mut buf = Vec::new();
+    let hidden = fixture.id(hidden);
+    let mut next = Some(fixture.id(tip));
+    while let Some(id) = next.filter(|id| *id != hidden) {
+        ids.push(id);
+        next = gix_object::FindExt::find_commit(&fixture.odb, &id, &mut buf)
+            .expect("exists")
+            .parents()
+            .next();
+    }
+    ids.reverse();
+    ids.into_iter()
+        .map(|id| Step {
+            action,
+            id,
+            summary: fixture.first_parent_summaries(id).remove(0),
+        })
+        .collect()
+}
+
+fn start(fixture: &Fixture, steps: Vec<Step>, options: state::Options) -> crate::Result<(State, Worktree)> {
+    let state = sequence::start(steps, options, &fixture.refs, &fixture.odb)?;
+    let worktree = Worktree::new(fixture.tree(&fixture.id("HEAD")));
+    Ok((state, worktree))
+}
+
+fn run<'a>(fixture: &'a Fixture, state: &mut State, worktree: &mut Worktree) -> crate::Result<Outcome<'a>> {
+    Ok(sequence::run(
+        state,
+        &fixture.refs,
+        &fixture.odb,
+        worktree,
+        &mut new_diff_resource_cache(&fixture.root),
+        &mut new_blob_merge_platform(&fixture.root),
+        &mut |id| id.to_hex_with_len(7).to_string(),
+        &options(),
+    )?)
+}
+
+fn reflog_messages(fixture: &Fixture, name: &str) -> Vec<String> {
+    let reference = fixture.refs.find(name).expect("exists");
+    reference
+        .log_iter(&fixture.refs)
+        .all()
+        .expect("readable")
+        .expect("present")
+        .map(|line| line.expect("valid").message.to_string())
+        .collect()
+}
+
+fn stopped_id(outcome: Outcome<'_>) -> ObjectId {
+    match outcome {
+        Outcome::Stopped { id, tree_merge, .. } => {
+            assert_eq!(tree_merge.conflicts.len(), 1);
+            id
+        }
+        Outcome::Finished { .. } => unreachable!("expected conflicts"),
+    }
+}
+
+#[test]
+fn cherry_pick_stops_on_conflicts_and_resumes() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (mut state, mut worktree) = start(
+        &fixture,
+        steps(&fixture, Action::Pick, "topic", "base"),
+        Default::default(),
+    )?;
+    assert!(matches!(
+        sequence::start(Vec::new(), Default::default(), &fixture.refs, &fixture.odb),
+        Err(Error::InProgress)
+    ));
+
+    let stopped_at = stopped_id(run(&fixture, &mut state, &mut worktree)?);
+    assert_eq!(fixture.first_parent_summaries(stopped_at)[0], "b: conflict");
+    assert_eq!(fixture.id(CHERRY_PICK_HEAD), stopped_at);
+    assert_eq!(sequence::stopped_at(&fixture.refs)?, Some((Action::Pick, stopped_at)));
+    assert_eq!(
+        std::fs::read_to_string(fixture.refs.git_dir().join("MERGE_MSG"))?,
+        "b: conflict\n"
+    );
+    assert_eq!(state.todo.len(), 2, "the conflicting step is still the first one");
+    assert_eq!(State::read(fixture.refs.git_dir())?.as_ref(), Some(&state));
+    assert!(worktree.unresolved_conflicts);
+    assert!(matches!(
+        run(&fixture, &mut state, &mut worktree),
+        Err(err) if matches!(err.downcast_ref::<Error>(), Some(Error::Stopped { .. }))
+    ));
+    assert!(
+        matches!(
+            sequence::resume(&mut state, &fixture.refs, &fixture.odb, &mut worktree, &options()),
+            Err(Error::Worktree(_))
+        ),
+        "conflicts must be resolved first"
+    );
+
+    worktree.resolve(fixture.tree(&stopped_at));
+    std::fs::write(
+        fixture.refs.git_dir().join("MERGE_MSG"),
+        "b: resolved\n\n# Conflicts:\n#\tb\n",
+    )?;
+    let new_commit = sequence::resume(&mut state, &fixture.refs, &fixture.odb, &mut worktree, &options())?
+        .expect("the tree differs from HEAD");
+    assert_eq!(fixture.message(&new_commit), "b: resolved\n", "comments are removed");
+    assert!(fixture.refs.try_find(CHERRY_PICK_HEAD)?.is_none());
+    assert!(!fixture.refs.git_dir().join("MERGE_MSG").exists());
+
+    let Outcome::Finished { head } = run(&fixture, &mut state, &mut worktree)? else {
+        panic!("no more conflicts")
+    };
+    assert_eq!(fixture.id("main"), head, "the checked out branch is updated");
+    assert_eq!(
+        fixture.first_parent_summaries(head),
+        ["e: 1", "b: resolved", "d: 1", "c: 1", "b: 2", "b: 1", "a: 1"]
+    );
+    assert_eq!(worktree.tree, fixture.tree(&head), "the worktree follows HEAD");
+    assert_eq!(State::read(fixture.refs.git_dir())?, None, "the state is removed");
+
+    let messages = reflog_messages(&fixture, "refs/heads/main");
+    assert_eq!(
+        &messages[messages.len() - 3..],
+        [
+            "cherry-pick: d: 1",
+            "commit (cherry-pick): b: resolved",
+            "cherry-pick: e: 1"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn skip_drops_the_conflicting_commit() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (mut state, mut worktree) = start(
+        &fixture,
+        steps(&fixture, Action::Pick, "topic", "base"),
+        Default::default(),
+    )?;
+    stopped_id(run(&fixture, &mut state, &mut worktree)?);
+    sequence::skip(&mut state, &fixture.refs, &fixture.odb, &mut worktree)?;
+    assert!(!worktree.unresolved_conflicts, "the worktree was reset");
+    assert!(matches!(
+        sequence::skip(&mut state, &fixture.refs, &fixture.odb, &mut worktree),
+        Err(Error::NotStopped)
+    ));
+
+    let Outcome::Finished { head } = run(&fixture, &mut state, &mut worktree)? else {
+        panic!("no more conflicts")
+    };
+    assert_eq!(
+        fixture.first_parent_summaries(head),
+        ["e: 1", "d: 1", "c: 1", "b: 2", "b: 1", "a: 1"]
+    );
+    Ok(())
+}
+
+#[test]
+fn abort_restores_head_and_worktree() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let main = fixture.id("main");
+    let (mut state, mut worktree) = start(
+        &fixture,
+        steps(&fixture, Action::Pick, "topic", "base"),
+        Default::default(),
+    )?;
+    stopped_id(run(&fixture, &mut state, &mut worktree)?);
+    let picked = fixture.id("HEAD");
+    assert_ne!(picked, main);
+
+    sequence::abort(
+        state,
+        &fixture.refs,
+        &fixture.odb,
+        &mut worktree,
+        signature().to_ref(&mut Default::default()),
+    )?;
+    assert_eq!(fixture.id("main"), main, "the branch is rewound");
+    assert_eq!(fixture.id(ORIG_HEAD), picked);
+    assert_eq!(worktree.tree, fixture.tree(&main));
+    assert!(!worktree.unresolved_conflicts);
+    assert!(fixture.refs.try_find(CHERRY_PICK_HEAD)?.is_none());
+    assert_eq!(State::read(fixture.refs.git_dir())?, None);
+    assert_eq!(
+        reflog_messages(&fixture, "refs/heads/main").last(),
+        Some(&format!("reset: moving to {main}"))
+    );
+    Ok(())
+}
+
+#[test]
+fn abort_refuses_if_head_was_moved() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let (mut state, mut worktree) = start(
+        &fixture,
+        steps(&fixture, Action::Pick, "topic", "base"),
+        Default::default(),
+    )?;
+    stopped_id(run(&fixture, &mut state, &mut worktree)?);
+    let base = fixture.id("base");
+    fixture
+        .refs
+        .transaction()
+        .prepare(
+            Some(gix_ref::transaction::RefEdit {
+                change: gix_ref::transaction::Change::Update {
+                    log: Default::default(),
+                    expected: gix_ref::transaction::PreviousValue::Any,
+                    new: gix_ref::Target::Object(base),
+                },
+                name: "refs/heads/main".try_into()?,
+                deref: false,
+            }),
+            gix_lock::acquire::Fail::Immediately,
+            gix_lock::acquire::Fail::Immediately,
+        )?
+        .commit(Some(signature().to_ref(&mut Default::default())))?;
+
+    assert!(matches!(
+        sequence::abort(
+            state,
+            &fixture.refs,
+            &fixture.odb,
+            &mut worktree,
+            signature().to_ref(&mut Default::default()),
+        ),
+        Err(Error::HeadMoved { actual, .. }) if actual == base
+    ));
+    assert!(State::dir(fixture.refs.git_dir()).is_dir(), "nothing was changed");
+
+    sequence::quit(&fixture.refs)?;
+    assert_eq!(State::read(fixture.refs.git_dir())?, None);
+    assert_eq!(sequence::stopped_at(&fixture.refs)?, None);
+    assert_eq!(fixture.id("main"), base, "quitting leaves HEAD alone");
+    Ok(())
+}
+
+#[test]
+fn revert() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let main = fixture.id("main");
+    let (mut state, mut worktree) = start(
+        &fixture,
+        steps(&fixture, Action::Revert, "main", "base"),
+        Default::default(),
+    )?;
+    let Outcome::Finished { head } = run(&fixture, &mut state, &mut worktree)? else {
+        panic!("no conflicts")
+    };
+    assert_eq!(
+        fixture.message(&head),
+        format!("Revert \"c: 1\"\n\nThis reverts commit {main}.\n")
+    );
+    assert_eq!(fixture.tree(&head), fixture.tree(&fixture.id("base")));
+    let mut buf = Vec::new();
+    let commit = gix_object::FindExt::find_commit(&fixture.odb, &head, &mut buf)?;
+    assert_eq!(
+        commit.author()?.name,
+        "committer",
+        "reverts are authored by the committer"
+    );
+    assert_eq!(
+        reflog_messages(&fixture, "HEAD").last().map(String::as_str),
+        Some("revert: Revert \"c: 1\"")
+    );
+    assert_eq!(
+        sequence::stopped_at(&fixture.refs)?,
+        None,
+        "{REVERT_HEAD} is only set on conflicts"
+    );
+    Ok(())
+}
+
+#[test]
+fn merges_need_a_mainline() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let merged = fixture.id("merged");
+    let merge = vec![Step {
+        action: Action::Pick,
+        id: merged,
+        summary: "merge side".into(),
+    }];
+    let (mut state, mut worktree) = start(&fixture, merge.clone(), Default::default())?;
+    assert!(matches!(
+        run(&fixture, &mut state, &mut worktree),
+        Err(err) if matches!(err.downcast_ref::<Error>(), Some(Error::MergeWithoutMainline { .. }))
+    ));
+    sequence::quit(&fixture.refs)?;
+
+    let (mut state, mut worktree) = start(
+        &fixture,
+        merge,
+        state::Options {
+            mainline: Some(1),
+            record_origin: true,
+            ..Default::default()
+        },
+    )?;
+    let Outcome::Finished { head } = run(&fixture, &mut state, &mut worktree)? else {
+        panic!("no conflicts")
+    };
+    assert_eq!(fixture.file_names(&fixture.tree(&head)), ["a", "b", "c", "s"]);
+    assert_eq!(
+        fixture.message(&head),
+        format!("merge side\n\n(cherry picked from commit {merged})\n")
+    );
+    Ok(())
+}
+
+#[test]
+fn no_commit_accumulates_changes_in_the_index() -> crate::Result {
+    let fixture = Fixture::new()?;
+    let main = fixture.id("main");
+    let mut picks = steps(&fixture, Action::Pick, "topic", "base");
+    picks.remove(1);
+    let (mut state, mut worktree) = start(
+        &fixture,
+        picks,
+        state::Options {
+            no_commit: true,
+            ..Default::default()
+        },
+    )?;
+    let Outcome::Finished { head } = run(&fixture, &mut state, &mut worktree)? else {
+        panic!("no conflicts")
+    };
+    assert_eq!(head, main, "nothing was committed");
+    assert_eq!(fixture.file_names(&worktree.tree), ["a", "b", "c", "d", "e"]);
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use gix_hash::ObjectId;
+use gix_sequencer::{
+    State,
+    state::{Action, Options, Step},
+};
+
+fn id(hex_char: char) -> ObjectId {
+    let hex: String = std::iter::repeat_n(hex_char, gix_testtools::object_hash().len_in_hex()).collect();
+    ObjectId::from_hex(hex.as_bytes()).expect("valid hex")
+}
+
+#[test]
+fn write_and_read_round_trip() -> crate::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    assert_eq!(State::read(tmp.path())?, None, "no sequence in progress");
+
+    let mut state = State {
+        head: id('a'),
+        todo: vec![
+            Step {
+                action: Action::Pick,
+                id: id('b'),
+                summary: "first".into(),
+            },
+            Step {
+                action: Action::Revert,
+                id: id('c'),
+                summary: "second with spaces".into(),
+            },
+        ],
+        options: Options {
+            no_commit: true,
+            mainline: Some(2),
+            record_origin: true,
+            allow_empty: false,
+            allow_ff: true,
+        },
+        abort_safety: Some(id('d')),
+    };
+    state.write(tmp.path())?;
+
+    let dir = State::dir(tmp.path());
+    assert_eq!(
+        std::fs::read_to_string(dir.join("opts"))?,
+        "[options]\n\tno-commit = true\n\trecord-origin = true\n\tallow-ff = true\n\tmainline = 2\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("todo"))?,
+        format!("pick {} first\nrevert {} second with spaces\n", id('b'), id('c'))
+    );
+    assert_eq!(State::read(tmp.path())?.as_ref(), Some(&state));
+
+    state.abort_safety = None;
+    state.options = Options::default();
+    state.write(tmp.path())?;
+    assert!(!dir.join("abort-safety").exists(), "it's removed if unset");
+    assert_eq!(State::read(tmp.path())?.as_ref(), Some(&state));
+
+    State::remove(tmp.path())?;
+    assert_eq!(State::read(tmp.path())?, None);
+    Ok(())
+}
+
+#[test]
+fn state_written_by_git_can_be_read_with_abbreviated_ids() -> crate::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let dir = State::dir(tmp.path());
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join("head"), format!("{}\n", id('a')))?;
+    std::fs::write(dir.join("todo"), "pick bbbbbbb first\nrevert ccccccc second\n")?;
+    std::fs::write(dir.join("opts"), "[options]\n\trecord-origin = true\n\tsignoff\n")?;
+
+    assert!(
+        State::read(tmp.path()).is_err(),
+        "abbreviated ids can't be used without resolving them"
+    );
+    let state = State::read_resolving(tmp.path(), |name| match name.to_string().as_str() {
+        "bbbbbbb" => Some(id('b')),
+        "ccccccc" => Some(id('c')),
+        _ => None,
+    })?
+    .expect("present");
+    assert_eq!(state.head, id('a'));
+    assert_eq!(
+        state.todo.iter().map(|step| (step.action, step.id)).collect::<Vec<_>>(),
+        [(Action::Pick, id('b')), (Action::Revert, id('c'))]
+    );
+    assert_eq!(
+        state.options,
+        Options {
+            record_origin: true,
+            ..Default::default()
+        },
+        "unknown options are ignored"
+    );
+    assert_eq!(state.abort_safety, None);
+    Ok(())
+}
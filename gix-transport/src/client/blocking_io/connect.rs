@@ -13,6 +13,7 @@ pub(crate) mod function {
     /// [local repositories](crate::client::blocking_io::file::connect()),
     /// [repositories over ssh](crate::client::blocking_io::ssh::connect()),
     /// [git daemons](crate::client::blocking_io::connect::connect()),
+    /// [remote helpers](crate::client::blocking_io::remote_helper::connect()) for all other schemes,
     /// and if compiled in connections to [git repositories over https](crate::client::blocking_io::http::connect()).
     ///
     /// Use `options` to further control specifics of the transport resulting from the connection.
@@ -23,7 +24,12 @@ pub(crate) mod function {
     {
         let mut url = url.try_into().map_err(gix_url::parse::Error::from)?;
         Ok(match url.scheme {
-            gix_url::Scheme::Ext(_) => return Err(Error::UnsupportedScheme(url.scheme)),
+            gix_url::Scheme::Ext(_) => Box::new(crate::client::blocking_io::remote_helper::connect(
+                url,
+                options.version,
+                options.remote_helper,
+                options.trace,
+            )),
             gix_url::Scheme::File => {
                 if url.user().is_some() || url.password().is_some() || url.host().is_some() || url.port.is_some() {
                     return Err(Error::UnsupportedUrlTokens {
@@ -36,6 +42,18 @@ pub(crate) mod function {
                         .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?,
                 )
             }
+            #[cfg(feature = "ssh-client-native")]
+            gix_url::Scheme::Ssh if options.ssh.use_native_client() => Box::new({
+                crate::client::blocking_io::ssh::native::connect(
+                    url,
+                    options.version,
+                    crate::client::blocking_io::ssh::native::Options::from_env(),
+                    options.trace,
+                )
+                .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
+            }),
+            #[cfg(not(feature = "ssh-client-native"))]
+            gix_url::Scheme::Ssh if options.ssh.use_native_client() => return Err(Error::CompiledWithoutNativeSsh),
             gix_url::Scheme::Ssh => Box::new({
                 crate::client::blocking_io::ssh::connect(url, options.version, options.ssh, options.trace)
                     .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
@@ -327,6 +327,7 @@ mod tests {
                         command: Some("unrecognized".into()),
                         disallow_shell: false,
                         kind: None,
+                        native: false,
                     };
                     assert!(matches!(
                         ssh::connect(url, Protocol::V1, options, false),
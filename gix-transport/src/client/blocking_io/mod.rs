@@ -13,6 +13,9 @@ pub use bufread_ext::{ExtendedBufRead, HandleProgress, ReadlineBufRead};
 mod request;
 pub use request::RequestWriter;
 
+///
+pub mod remote_helper;
+
 ///
 pub mod ssh;
 
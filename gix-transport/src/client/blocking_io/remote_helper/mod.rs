@@ -0,0 +1,350 @@
+//! Support for `git`'s [remote-helper protocol](https://git-scm.com/docs/gitremote-helpers), which allows
+//! to reach remotes with custom URL schemes.
+//!
+//! A [`Helper`] can be implemented in-process, or it is a `git-remote-<scheme>` [program](Process) which is
+//! spawned and talked to using the text-based protocol on its standard input and output.
+//! Either way, the [`Transport`] maps what the helper can do onto the regular transport interface:
+//!
+//! * if the helper supports `stateless-connect` and protocol V2 is desired, requests are tunneled to it statelessly,
+//! * if it supports `connect`, the connection to the service is used as is,
+//! * otherwise `list` and `fetch` or `push` are used to emulate a V1 service, with the helper receiving or sending
+//!   objects in the local repository by itself.
+use std::{
+    ffi::OsString,
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+use bstr::{BStr, BString, ByteSlice};
+
+use crate::{Protocol, Service};
+
+mod process;
+pub use process::Process;
+
+mod transport;
+pub use transport::Transport;
+
+/// The error returned when interacting with a remote [`Helper`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Failed to invoke the remote helper program {command:?}")]
+    InvokeProgram { source: std::io::Error, command: OsString },
+    #[error("Could not communicate with the remote helper")]
+    Io(#[from] std::io::Error),
+    #[error("The remote helper stopped unexpectedly while responding to the '{command}' command")]
+    UnexpectedEof { command: &'static str },
+    #[error("The remote helper responded to the '{command}' command with an unexpected line: {line:?}")]
+    UnexpectedResponse { command: &'static str, line: BString },
+    #[error("The remote helper requires the '{name}' capability, which isn't supported")]
+    MandatoryCapability { name: BString },
+    #[error("The remote helper doesn't support the '{command}' command")]
+    Unsupported { command: &'static str },
+    #[error("The remote helper can neither connect to nor emulate the {} service", service.as_str())]
+    UnsupportedService { service: Service },
+    #[error("Shallow fetches are not supported by remote helpers which can only fetch objects")]
+    ShallowFetch,
+    #[error(transparent)]
+    Custom(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+/// The capabilities of a remote [`Helper`], as listed in response to the `capabilities` command.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The helper can download objects into the local repository with the `fetch` command.
+    pub fetch: bool,
+    /// The helper can send objects from the local repository with the `push` command.
+    pub push: bool,
+    /// The helper can connect to a service with the `connect` command.
+    pub connect: bool,
+    /// The helper can tunnel stateless requests to a protocol V2 service with the `stateless-connect` command.
+    pub stateless_connect: bool,
+    /// The helper can be configured with the `option` command.
+    pub option: bool,
+    /// The helper can tell if the objects obtained with `fetch` are connected.
+    pub check_connectivity: bool,
+    /// The helper can tell the object format of the remote when asked with `option object-format true`.
+    pub object_format: bool,
+    /// The `refspec <refspec>` capabilities, describing where the helper puts the refs it imports.
+    pub refspecs: Vec<BString>,
+    /// All other capabilities, which aren't used by the [`Transport`].
+    pub other: Vec<BString>,
+}
+
+impl Capabilities {
+    /// Parse capabilities from `lines` as produced by a helper in response to the `capabilities` command,
+    /// failing if a capability prefixed with `*` is mandatory but unknown.
+    pub fn from_lines<'a>(lines: impl IntoIterator<Item = &'a BStr>) -> Result<Self, Error> {
+        let mut out = Capabilities::default();
+        for line in lines {
+            let (mandatory, line) = match line.strip_prefix(b"*") {
+                Some(line) => (true, line.as_bstr()),
+                None => (false, line),
+            };
+            match line.as_bytes() {
+                b"fetch" => out.fetch = true,
+                b"push" => out.push = true,
+                b"connect" => out.connect = true,
+                b"stateless-connect" => out.stateless_connect = true,
+                b"option" => out.option = true,
+                b"check-connectivity" => out.check_connectivity = true,
+                b"object-format" => out.object_format = true,
+                _ => {
+                    if let Some(refspec) = line.strip_prefix(b"refspec ") {
+                        out.refspecs.push(refspec.into());
+                    } else if mandatory {
+                        return Err(Error::MandatoryCapability { name: line.to_owned() });
+                    } else {
+                        out.other.push(line.to_owned());
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// The response to the `option` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionResponse {
+    /// The option was set.
+    Ok,
+    /// The option isn't known to the helper.
+    Unsupported,
+    /// The option is known, but couldn't be set for the given reason.
+    Error(BString),
+}
+
+/// What a [`Ref`] points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefTarget {
+    /// The object with the given hexadecimal id.
+    Id(BString),
+    /// The ref with the given name, making this a symbolic ref.
+    Symbolic(BString),
+    /// The value isn't known to the helper, which is signalled with `?`.
+    Unknown,
+}
+
+/// A ref as listed by the `list` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ref {
+    /// The full name of the ref.
+    pub name: BString,
+    /// What the ref points to.
+    pub target: RefTarget,
+    /// Additional attributes, like `unchanged`.
+    pub attributes: Vec<BString>,
+}
+
+/// The response to the `list` command.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct List {
+    /// All listed refs, in order.
+    pub refs: Vec<Ref>,
+    /// The name of the object format of the remote, like `sha1`, if the helper indicated it.
+    pub object_format: Option<BString>,
+}
+
+impl List {
+    /// Parse the response of a helper to the `list` command from `lines`.
+    pub fn from_lines<'a>(lines: impl IntoIterator<Item = &'a BStr>) -> Result<Self, Error> {
+        let mut out = List::default();
+        for line in lines {
+            if let Some(keyword) = line.strip_prefix(b":") {
+                let mut tokens = keyword.splitn_str(2, b" ");
+                if tokens.next() == Some(b"object-format") {
+                    out.object_format = tokens.next().map(Into::into);
+                }
+                continue;
+            }
+            let mut tokens = line.split_str(b" ");
+            let (Some(value), Some(name)) = (tokens.next(), tokens.next()) else {
+                return Err(Error::UnexpectedResponse {
+                    command: "list",
+                    line: line.to_owned(),
+                });
+            };
+            out.refs.push(Ref {
+                name: name.into(),
+                target: match value {
+                    b"?" => RefTarget::Unknown,
+                    _ => match value.strip_prefix(b"@") {
+                        Some(target) => RefTarget::Symbolic(target.into()),
+                        None => RefTarget::Id(value.into()),
+                    },
+                },
+                attributes: tokens.map(Into::into).collect(),
+            });
+        }
+        Ok(out)
+    }
+}
+
+/// An object to obtain with the `fetch` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Want {
+    /// The hexadecimal id of the object.
+    pub id: BString,
+    /// The name of the ref pointing to the object, as listed by the helper.
+    pub name: BString,
+}
+
+/// The response to the `fetch` command.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct FetchOutcome {
+    /// Files which protect the fetched objects from being removed, and which should be deleted once refs point to them.
+    pub lock_files: Vec<PathBuf>,
+    /// If `true`, the helper verified that the fetched objects are connected.
+    pub connectivity_ok: bool,
+}
+
+/// A ref to update with the `push` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushSpec {
+    /// The local object or ref to push, or `None` to delete the remote ref.
+    pub src: Option<BString>,
+    /// The name of the remote ref to update.
+    pub dst: BString,
+    /// If `true`, the remote ref is updated even if that isn't a fast-forward.
+    pub force: bool,
+}
+
+/// The outcome of updating one remote ref with the `push` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushStatus {
+    /// The remote ref `dst` was updated.
+    Ok {
+        /// The name of the remote ref.
+        dst: BString,
+    },
+    /// The remote ref `dst` could not be updated, possibly for the given `reason`.
+    Error {
+        /// The name of the remote ref.
+        dst: BString,
+        /// Why the update failed, if known.
+        reason: Option<BString>,
+    },
+}
+
+/// The kind of connection to establish with [`Helper::connect()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectKind {
+    /// A bidirectional connection to the service, as with the `connect` command.
+    Connect,
+    /// A connection to a protocol V2 service which behaves like a stateless connection, as with the
+    /// `stateless-connect` command.
+    ///
+    /// After the capability advertisement, the response to each request must be followed by a response-end packet.
+    StatelessConnect,
+}
+
+/// A connection to a service established by a remote [`Helper`].
+pub struct Connection {
+    /// The data sent by the service.
+    pub reader: Box<dyn Read + Send>,
+    /// The channel to send data to the service.
+    pub writer: Box<dyn Write + Send>,
+}
+
+/// A remote helper which can be talked to in terms of the commands of the
+/// [remote-helper protocol](https://git-scm.com/docs/gitremote-helpers).
+///
+/// All commands but [`capabilities()`](Helper::capabilities()) are only called if the helper advertised the respective
+/// capability, and are unsupported by default.
+pub trait Helper {
+    /// Return the capabilities of the helper.
+    fn capabilities(&mut self) -> Result<Capabilities, Error>;
+
+    /// Set the option `name` to `value`, like `verbosity` to `1` or `progress` to `false`.
+    fn option(&mut self, _name: &str, _value: &str) -> Result<OptionResponse, Error> {
+        Ok(OptionResponse::Unsupported)
+    }
+
+    /// List all refs of the remote, for pushing to them if `for_push` is `true`.
+    fn list(&mut self, _for_push: bool) -> Result<List, Error> {
+        Err(Error::Unsupported { command: "list" })
+    }
+
+    /// Download all objects needed for the `wants` into the local repository.
+    fn fetch(&mut self, _wants: &[Want]) -> Result<FetchOutcome, Error> {
+        Err(Error::Unsupported { command: "fetch" })
+    }
+
+    /// Update the remote refs as described by `specs` with objects from the local repository, and return the outcome
+    /// for each remote ref.
+    fn push(&mut self, _specs: &[PushSpec]) -> Result<Vec<PushStatus>, Error> {
+        Err(Error::Unsupported { command: "push" })
+    }
+
+    /// Connect to `service` in the way indicated by `kind`, or return `None` if the helper can't and other commands
+    /// should be used instead.
+    fn connect(&mut self, _service: Service, _kind: ConnectKind) -> Result<Option<Connection>, Error> {
+        Err(Error::Unsupported { command: "connect" })
+    }
+}
+
+impl<T: Helper + ?Sized> Helper for Box<T> {
+    fn capabilities(&mut self) -> Result<Capabilities, Error> {
+        (**self).capabilities()
+    }
+
+    fn option(&mut self, name: &str, value: &str) -> Result<OptionResponse, Error> {
+        (**self).option(name, value)
+    }
+
+    fn list(&mut self, for_push: bool) -> Result<List, Error> {
+        (**self).list(for_push)
+    }
+
+    fn fetch(&mut self, wants: &[Want]) -> Result<FetchOutcome, Error> {
+        (**self).fetch(wants)
+    }
+
+    fn push(&mut self, specs: &[PushSpec]) -> Result<Vec<PushStatus>, Error> {
+        (**self).push(specs)
+    }
+
+    fn connect(&mut self, service: Service, kind: ConnectKind) -> Result<Option<Connection>, Error> {
+        (**self).connect(service, kind)
+    }
+}
+
+///
+pub mod connect {
+    use std::path::PathBuf;
+
+    use bstr::BString;
+
+    /// Options for connecting to a remote through a remote helper program.
+    #[derive(Debug, Default, Clone)]
+    pub struct Options {
+        /// The repository the helper should fetch objects into or push objects from, passed to it as `GIT_DIR`.
+        ///
+        /// Helpers which can only `fetch` or `push` can't work without it.
+        pub git_dir: Option<PathBuf>,
+        /// The name of the remote to pass to the helper, or `None` to pass the URL instead as `git` does for anonymous remotes.
+        pub remote_name: Option<BString>,
+    }
+}
+
+/// Connect to `url` through the `git-remote-<scheme>` program for its scheme, which is looked up in `PATH`,
+/// configured by `options`.
+///
+/// The helper is spawned when it's first needed, and the `desired_version` is passed to it in `GIT_PROTOCOL`.
+/// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+pub fn connect(
+    url: gix_url::Url,
+    desired_version: Protocol,
+    options: connect::Options,
+    trace: bool,
+) -> Transport<Process> {
+    let helper = Process::new(
+        format!("git-remote-{}", url.scheme.as_str()),
+        &url,
+        desired_version,
+        options,
+    );
+    Transport::new(helper, url, desired_version, trace)
+}
@@ -0,0 +1,263 @@
+use std::{
+    ffi::OsString,
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Stdio},
+};
+
+use bstr::{BString, ByteSlice, ByteVec};
+
+use super::{
+    Capabilities, ConnectKind, Connection, Error, FetchOutcome, Helper, List, OptionResponse, PushSpec, PushStatus,
+    Want, connect,
+};
+use crate::{Protocol, Service};
+
+/// A remote [`Helper`] implemented by a program like `git-remote-<scheme>`, which is spawned when it's first needed.
+///
+/// It's invoked with the name of the remote and its URL as arguments, and talked to on its standard input and output.
+pub struct Process {
+    program: OsString,
+    args: [OsString; 2],
+    envs: Vec<(&'static str, OsString)>,
+    running: Option<Running>,
+    /// Helpers whose standard input and output were handed out as connection, to be waited for when dropped.
+    connected: Vec<Child>,
+}
+
+struct Running {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Process {
+    /// Prepare to invoke `program` as helper for the remote at `url`, passing `desired_version` in `GIT_PROTOCOL`
+    /// and configured by `options`.
+    pub fn new(
+        program: impl Into<OsString>,
+        url: &gix_url::Url,
+        desired_version: Protocol,
+        options: connect::Options,
+    ) -> Self {
+        let url = url.to_bstring();
+        let remote = options.remote_name.unwrap_or_else(|| url.clone());
+        let mut envs = Vec::new();
+        if let Some(git_dir) = options.git_dir {
+            envs.push(("GIT_DIR", git_dir.into_os_string()));
+        }
+        if desired_version != Protocol::V1 {
+            envs.push(("GIT_PROTOCOL", format!("version={}", desired_version as usize).into()));
+        }
+        Process {
+            program: program.into(),
+            args: [
+                remote.to_os_str_lossy().into_owned(),
+                url.to_os_str_lossy().into_owned(),
+            ],
+            envs,
+            running: None,
+            connected: Vec::new(),
+        }
+    }
+
+    fn running(&mut self) -> Result<&mut Running, Error> {
+        if self.running.is_none() {
+            let mut cmd = std::process::Command::from(
+                gix_command::prepare(self.program.clone())
+                    .args(self.args.iter().cloned())
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped()),
+            );
+            cmd.envs(self.envs.iter().map(|(key, value)| (key, value)));
+            gix_features::trace::debug!(command = ?cmd, "gix_transport::remote_helper::Process");
+            let mut child = cmd.spawn().map_err(|err| Error::InvokeProgram {
+                source: err,
+                command: self.program.clone(),
+            })?;
+            self.running = Some(Running {
+                stdin: child.stdin.take().expect("stdin configured"),
+                stdout: BufReader::new(child.stdout.take().expect("stdout configured")),
+                child,
+            });
+        }
+        Ok(self.running.as_mut().expect("just set"))
+    }
+
+    fn send(&mut self, lines: impl IntoIterator<Item = BString>) -> Result<&mut Running, Error> {
+        let running = self.running()?;
+        for line in lines {
+            running.stdin.write_all(&line)?;
+            running.stdin.write_all(b"\n")?;
+        }
+        running.stdin.flush()?;
+        Ok(running)
+    }
+}
+
+impl Running {
+    fn read_line(&mut self, command: &'static str) -> Result<BString, Error> {
+        let mut line = Vec::new();
+        if self.stdout.read_until(b'\n', &mut line)? == 0 {
+            return Err(Error::UnexpectedEof { command });
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+        Ok(line.into())
+    }
+
+    /// Read lines until the blank line terminating the response to `command`.
+    fn read_lines(&mut self, command: &'static str) -> Result<Vec<BString>, Error> {
+        let mut out = Vec::new();
+        loop {
+            let line = self.read_line(command)?;
+            if line.is_empty() {
+                break Ok(out);
+            }
+            out.push(line);
+        }
+    }
+}
+
+impl Helper for Process {
+    fn capabilities(&mut self) -> Result<Capabilities, Error> {
+        let lines = self.send(Some("capabilities".into()))?.read_lines("capabilities")?;
+        Capabilities::from_lines(lines.iter().map(AsRef::as_ref))
+    }
+
+    fn option(&mut self, name: &str, value: &str) -> Result<OptionResponse, Error> {
+        let line = self
+            .send(Some(format!("option {name} {value}").into()))?
+            .read_line("option")?;
+        Ok(match line.as_bytes() {
+            b"ok" => OptionResponse::Ok,
+            b"unsupported" => OptionResponse::Unsupported,
+            _ => match line.strip_prefix(b"error") {
+                Some(message) => OptionResponse::Error(message.trim_start().into()),
+                None => {
+                    return Err(Error::UnexpectedResponse {
+                        command: "option",
+                        line,
+                    });
+                }
+            },
+        })
+    }
+
+    fn list(&mut self, for_push: bool) -> Result<List, Error> {
+        let command = if for_push { "list for-push" } else { "list" };
+        let lines = self.send(Some(command.into()))?.read_lines("list")?;
+        List::from_lines(lines.iter().map(AsRef::as_ref))
+    }
+
+    fn fetch(&mut self, wants: &[Want]) -> Result<FetchOutcome, Error> {
+        if wants.is_empty() {
+            return Ok(FetchOutcome::default());
+        }
+        let lines = self
+            .send(
+                wants
+                    .iter()
+                    .map(|want| {
+                        let mut line = BString::from("fetch ");
+                        line.push_str(&want.id);
+                        line.push_byte(b' ');
+                        line.push_str(&want.name);
+                        line
+                    })
+                    .chain(Some(BString::default())),
+            )?
+            .read_lines("fetch")?;
+        let mut out = FetchOutcome::default();
+        for line in lines {
+            if let Some(path) = line.strip_prefix(b"lock ") {
+                out.lock_files.push(path.to_path_lossy().into_owned());
+            } else if line == "connectivity-ok" {
+                out.connectivity_ok = true;
+            } else {
+                return Err(Error::UnexpectedResponse { command: "fetch", line });
+            }
+        }
+        Ok(out)
+    }
+
+    fn push(&mut self, specs: &[PushSpec]) -> Result<Vec<PushStatus>, Error> {
+        if specs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let lines = self
+            .send(
+                specs
+                    .iter()
+                    .map(|spec| {
+                        let mut line = BString::from(if spec.force { "push +" } else { "push " });
+                        if let Some(src) = &spec.src {
+                            line.push_str(src);
+                        }
+                        line.push_byte(b':');
+                        line.push_str(&spec.dst);
+                        line
+                    })
+                    .chain(Some(BString::default())),
+            )?
+            .read_lines("push")?;
+        lines
+            .into_iter()
+            .map(|line| {
+                if let Some(dst) = line.strip_prefix(b"ok ") {
+                    Ok(PushStatus::Ok { dst: dst.into() })
+                } else if let Some(rest) = line.strip_prefix(b"error ") {
+                    let mut tokens = rest.splitn_str(2, b" ");
+                    Ok(PushStatus::Error {
+                        dst: tokens.next().unwrap_or_default().into(),
+                        reason: tokens.next().map(Into::into),
+                    })
+                } else {
+                    Err(Error::UnexpectedResponse { command: "push", line })
+                }
+            })
+            .collect()
+    }
+
+    fn connect(&mut self, service: Service, kind: ConnectKind) -> Result<Option<Connection>, Error> {
+        let command = match kind {
+            ConnectKind::Connect => "connect",
+            ConnectKind::StatelessConnect => "stateless-connect",
+        };
+        let line = self
+            .send(Some(format!("{command} {}", service.as_str()).into()))?
+            .read_line(command)?;
+        if line == "fallback" {
+            return Ok(None);
+        }
+        if !line.is_empty() {
+            return Err(Error::UnexpectedResponse { command, line });
+        }
+        let Running { child, stdin, stdout } = self.running.take().expect("running after sending the command");
+        self.connected.push(child);
+        Ok(Some(Connection {
+            reader: Box::new(stdout),
+            writer: Box::new(stdin),
+        }))
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        if let Some(Running {
+            mut child,
+            mut stdin,
+            stdout,
+        }) = self.running.take()
+        {
+            // A blank line, or closing its input, is the signal for the helper to exit.
+            stdin.write_all(b"\n").ok();
+            drop(stdin);
+            drop(stdout);
+            child.wait().ok();
+        }
+        for mut child in self.connected.drain(..) {
+            child.wait().ok();
+        }
+    }
+}
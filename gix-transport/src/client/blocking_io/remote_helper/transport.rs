@@ -0,0 +1,562 @@
+use std::{
+    any::Any,
+    borrow::Cow,
+    collections::HashMap,
+    io::{Read, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use bstr::{BStr, BString, ByteSlice};
+
+use super::{Capabilities, ConnectKind, Error, Helper, List, OptionResponse, PushSpec, PushStatus, RefTarget, Want};
+use crate::{
+    Protocol, Service,
+    client::{
+        self, MessageKind, WriteMode,
+        blocking_io::{RequestWriter, SetServiceResponse},
+        capabilities::blocking_recv::Handshake,
+        git::blocking_io::Connection,
+    },
+    packetline::{
+        Channel, PacketLineRef,
+        blocking_io::{StreamingPeekableIter, encode},
+        decode,
+    },
+};
+
+/// The header of a pack without objects.
+const EMPTY_PACK_HEADER: &[u8] = b"PACK\0\0\0\x02\0\0\0\0";
+/// The SHA-1 checksum of [`EMPTY_PACK_HEADER`].
+const EMPTY_PACK_SHA1_TRAILER: [u8; 20] = [
+    0x02, 0x9d, 0x08, 0x82, 0x3b, 0xd8, 0xa8, 0xea, 0xb5, 0x10, 0xad, 0x6a, 0xc7, 0x5c, 0x82, 0x3c, 0xfd, 0x3e, 0xd3,
+    0x1e,
+];
+/// The SHA-256 checksum of [`EMPTY_PACK_HEADER`].
+const EMPTY_PACK_SHA256_TRAILER: [u8; 32] = [
+    0x7e, 0xd8, 0x90, 0xd8, 0xa4, 0x57, 0x60, 0xf3, 0xee, 0xcf, 0x73, 0x04, 0x5b, 0x1d, 0x10, 0x47, 0x08, 0x5a, 0xf4,
+    0x77, 0x6d, 0xc6, 0x83, 0xd7, 0x8e, 0xac, 0x82, 0x20, 0x3d, 0xf1, 0x99, 0x3f,
+];
+
+/// A blocking transport which talks to a remote through a remote [`Helper`].
+///
+/// If the helper can't connect to the service, a protocol V1 service is emulated using the `list`, `fetch` and `push`
+/// commands. Then the helper transfers objects to or from the local repository by itself, and the pack sent
+/// to the client when fetching is empty.
+/// When pushing, the remote refs are updated forcefully as the client already decided how to update them,
+/// but note that their previous values can't be verified.
+pub struct Transport<H> {
+    // NOTE: the state holds connections to the helper, and must be dropped before it.
+    state: State,
+    helper: H,
+    url: gix_url::Url,
+    desired_version: Protocol,
+    trace: bool,
+    capabilities: Option<Capabilities>,
+    lock_files: Vec<PathBuf>,
+}
+
+enum State {
+    Idle,
+    Connected(Connection<Box<dyn Read + Send>, Box<dyn Write + Send>>),
+    Stateless {
+        line_provider: StreamingPeekableIter<Box<dyn Read + Send>>,
+        writer: Box<dyn Write + Send>,
+        response_end_pending: bool,
+    },
+    Emulated {
+        service: Service,
+        line_provider: StreamingPeekableIter<Response>,
+        names_by_id: HashMap<BString, BString>,
+        object_format: Option<BString>,
+    },
+}
+
+impl<H: Helper> Transport<H> {
+    /// Create a new instance to reach the remote at `url` through `helper`, preferring `desired_version` when connecting.
+    /// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+    pub fn new(helper: H, url: gix_url::Url, desired_version: Protocol, trace: bool) -> Self {
+        Transport {
+            state: State::Idle,
+            helper,
+            url,
+            desired_version,
+            trace,
+            capabilities: None,
+            lock_files: Vec::new(),
+        }
+    }
+
+    /// Return the capabilities of the helper, asking it for them only once.
+    pub fn capabilities(&mut self) -> Result<&Capabilities, Error> {
+        if self.capabilities.is_none() {
+            self.capabilities = Some(self.helper.capabilities()?);
+        }
+        Ok(self.capabilities.as_ref().expect("just set"))
+    }
+
+    /// Set the option `name` to `value` in the helper, or return [`OptionResponse::Unsupported`] if it can't be configured.
+    ///
+    /// Options should be set before the handshake.
+    pub fn option(&mut self, name: &str, value: &str) -> Result<OptionResponse, Error> {
+        if !self.capabilities()?.option {
+            return Ok(OptionResponse::Unsupported);
+        }
+        self.helper.option(name, value)
+    }
+
+    /// Return the helper itself.
+    pub fn helper_mut(&mut self) -> &mut H {
+        &mut self.helper
+    }
+
+    fn handshake_inner(
+        &mut self,
+        service: Service,
+        extra_parameters: &[(&str, Option<&str>)],
+    ) -> Result<SetServiceResponse<'_>, client::Error> {
+        let capabilities = self.capabilities().map_err(into_client_error)?.clone();
+        if service == Service::UploadPack && self.desired_version == Protocol::V2 && capabilities.stateless_connect {
+            if let Some(connection) = self
+                .helper
+                .connect(service, ConnectKind::StatelessConnect)
+                .map_err(into_client_error)?
+            {
+                self.state = State::Stateless {
+                    line_provider: StreamingPeekableIter::new(connection.reader, &[PacketLineRef::Flush], self.trace),
+                    writer: connection.writer,
+                    response_end_pending: false,
+                };
+                let State::Stateless { line_provider, .. } = &mut self.state else {
+                    unreachable!("just set")
+                };
+                return handshake_from_lines(line_provider);
+            }
+        }
+        if capabilities.connect {
+            if let Some(connection) = self
+                .helper
+                .connect(service, ConnectKind::Connect)
+                .map_err(into_client_error)?
+            {
+                self.state = State::Connected(Connection::new_for_spawned_process(
+                    connection.reader,
+                    connection.writer,
+                    self.desired_version,
+                    self.url.path.clone(),
+                    self.trace,
+                ));
+                let State::Connected(connection) = &mut self.state else {
+                    unreachable!("just set")
+                };
+                return client::blocking_io::Transport::handshake(connection, service, extra_parameters);
+            }
+        }
+
+        let can_emulate = match service {
+            Service::UploadPack => capabilities.fetch,
+            Service::ReceivePack => capabilities.push,
+        };
+        if !can_emulate {
+            return Err(into_client_error(Error::UnsupportedService { service }));
+        }
+        if capabilities.option && capabilities.object_format {
+            self.helper.option("object-format", "true").map_err(into_client_error)?;
+        }
+        let list = self
+            .helper
+            .list(service == Service::ReceivePack)
+            .map_err(into_client_error)?;
+        let (advertisement, names_by_id) = advertisement(service, &list)?;
+        self.state = State::Emulated {
+            service,
+            line_provider: StreamingPeekableIter::new(
+                Response::ready(advertisement),
+                &[PacketLineRef::Flush],
+                self.trace,
+            ),
+            names_by_id,
+            object_format: list.object_format,
+        };
+        let State::Emulated { line_provider, .. } = &mut self.state else {
+            unreachable!("just set")
+        };
+        handshake_from_lines(line_provider)
+    }
+}
+
+impl<H: Helper> client::TransportWithoutIO for Transport<H> {
+    fn to_url(&self) -> Cow<'_, BStr> {
+        Cow::Owned(self.url.to_bstring())
+    }
+
+    fn connection_persists_across_multiple_requests(&self) -> bool {
+        !matches!(self.state, State::Stateless { .. } | State::Emulated { .. })
+    }
+
+    fn configure(&mut self, _config: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        Ok(())
+    }
+}
+
+impl<H: Helper> client::blocking_io::Transport for Transport<H> {
+    fn handshake<'a>(
+        &mut self,
+        service: Service,
+        extra_parameters: &'a [(&'a str, Option<&'a str>)],
+    ) -> Result<SetServiceResponse<'_>, client::Error> {
+        self.state = State::Idle;
+        self.handshake_inner(service, extra_parameters)
+    }
+
+    fn request(
+        &mut self,
+        write_mode: WriteMode,
+        on_into_read: MessageKind,
+        trace: bool,
+    ) -> Result<RequestWriter<'_>, client::Error> {
+        let Transport {
+            state,
+            helper,
+            lock_files,
+            trace: trace_lines,
+            ..
+        } = self;
+        match state {
+            State::Idle => Err(client::Error::MissingHandshake),
+            State::Connected(connection) => connection.request(write_mode, on_into_read, trace),
+            State::Stateless {
+                line_provider,
+                writer,
+                response_end_pending,
+            } => {
+                if *response_end_pending {
+                    // Skip whatever remains of the previous response.
+                    line_provider.reset_with(&[PacketLineRef::ResponseEnd]);
+                    while let Some(line) = line_provider.read_line() {
+                        line??;
+                    }
+                }
+                line_provider.reset_with(&[PacketLineRef::Flush]);
+                *response_end_pending = true;
+                Ok(RequestWriter::new_from_bufread(
+                    writer,
+                    Box::new(line_provider.as_read_without_sidebands()),
+                    write_mode,
+                    on_into_read,
+                    trace,
+                ))
+            }
+            State::Emulated {
+                service,
+                line_provider,
+                names_by_id,
+                object_format,
+            } => {
+                let response = Response::default();
+                *line_provider = StreamingPeekableIter::new(response.clone(), &[PacketLineRef::Flush], *trace_lines);
+                line_provider.fail_on_err_lines(true);
+                Ok(RequestWriter::new_from_bufread(
+                    EmulatedRequest {
+                        service: *service,
+                        helper,
+                        names_by_id,
+                        object_format: object_format.as_ref().map(AsRef::as_ref),
+                        lock_files,
+                        request: Vec::new(),
+                        response,
+                    },
+                    Box::new(line_provider.as_read_without_sidebands()),
+                    write_mode,
+                    on_into_read,
+                    trace,
+                ))
+            }
+        }
+    }
+}
+
+impl<H> Drop for Transport<H> {
+    fn drop(&mut self) {
+        self.state = State::Idle;
+        // With refs updated by now, the fetched objects don't need to be protected anymore.
+        for path in self.lock_files.drain(..) {
+            std::fs::remove_file(path).ok();
+        }
+    }
+}
+
+fn into_client_error(err: Error) -> client::Error {
+    client::Error::RemoteHelper(Box::new(err))
+}
+
+fn handshake_from_lines<T: Read>(
+    line_provider: &mut StreamingPeekableIter<T>,
+) -> Result<SetServiceResponse<'_>, client::Error> {
+    let Handshake {
+        capabilities,
+        refs,
+        protocol: actual_protocol,
+    } = Handshake::from_lines_with_version_detection(line_provider)?;
+    Ok(SetServiceResponse {
+        actual_protocol,
+        capabilities,
+        refs,
+    })
+}
+
+/// Produce a protocol V1 advertisement for `service` from the refs in `list`, along with a mapping of object ids to the
+/// first ref name pointing to them.
+fn advertisement(service: Service, list: &List) -> Result<(Vec<u8>, HashMap<BString, BString>), client::Error> {
+    let id_of = |name: &BStr| {
+        list.refs.iter().find_map(|r| match &r.target {
+            RefTarget::Id(id) if r.name == name => Some(id.clone()),
+            _ => None,
+        })
+    };
+    let mut capabilities = Vec::<BString>::new();
+    let mut refs = Vec::new();
+    for r in &list.refs {
+        let id = match &r.target {
+            RefTarget::Id(id) => id.clone(),
+            RefTarget::Symbolic(target) => {
+                let Some(id) = id_of(target.as_ref()) else { continue };
+                if service == Service::UploadPack {
+                    capabilities.push(format!("symref={}:{}", r.name, target).into());
+                }
+                id
+            }
+            RefTarget::Unknown => continue,
+        };
+        refs.push((id, r.name.clone()));
+    }
+    match service {
+        // These are required by the client, even though there is no negotiation and the pack is always empty.
+        Service::UploadPack => capabilities.extend(["multi_ack_detailed".into(), "side-band-64k".into()]),
+        Service::ReceivePack => capabilities.extend(["report-status".into(), "delete-refs".into()]),
+    }
+    if let Some(object_format) = &list.object_format {
+        capabilities.push(format!("object-format={object_format}").into());
+    }
+
+    let mut out = Vec::new();
+    let capabilities = capabilities.join(&b' ');
+    match refs.split_first() {
+        Some(((id, name), rest)) => {
+            encode::data_to_write(
+                &[id.as_bytes(), b" ", name, b"\0", &capabilities, b"\n"].concat(),
+                &mut out,
+            )?;
+            for (id, name) in rest {
+                encode::data_to_write(&[id.as_bytes(), b" ", name, b"\n"].concat(), &mut out)?;
+            }
+        }
+        None => {
+            let null_id = "0".repeat(hex_len(list.object_format.as_ref().map(AsRef::as_ref)));
+            encode::data_to_write(
+                &[null_id.as_bytes(), b" capabilities^{}\0", &capabilities, b"\n"].concat(),
+                &mut out,
+            )?;
+        }
+    }
+    encode::flush_to_write(&mut out)?;
+
+    let mut names_by_id = HashMap::new();
+    for (id, name) in refs {
+        names_by_id.entry(id).or_insert(name);
+    }
+    Ok((out, names_by_id))
+}
+
+fn is_sha256(object_format: Option<&BStr>) -> bool {
+    object_format.is_some_and(|format| format == "sha256")
+}
+
+fn hex_len(object_format: Option<&BStr>) -> usize {
+    if is_sha256(object_format) { 64 } else { 40 }
+}
+
+/// The response to a request, which is available only once the request was finished.
+#[derive(Default, Clone)]
+struct Response(Arc<Mutex<ResponseState>>);
+
+#[derive(Default)]
+enum ResponseState {
+    #[default]
+    Pending,
+    Ready(std::io::Cursor<Vec<u8>>),
+    Failed(Option<std::io::Error>),
+}
+
+impl Response {
+    fn ready(data: Vec<u8>) -> Self {
+        Response(Arc::new(Mutex::new(ResponseState::Ready(std::io::Cursor::new(data)))))
+    }
+
+    fn set(&self, result: Result<Vec<u8>, Error>) {
+        *self.0.lock().expect("not poisoned") = match result {
+            Ok(data) => ResponseState::Ready(std::io::Cursor::new(data)),
+            Err(err) => ResponseState::Failed(Some(std::io::Error::other(err))),
+        };
+    }
+}
+
+impl Read for Response {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match &mut *self.0.lock().expect("not poisoned") {
+            ResponseState::Pending => Err(std::io::Error::other(
+                "The request must be finished before its response can be read",
+            )),
+            ResponseState::Ready(data) => data.read(buf),
+            ResponseState::Failed(err) => Err(err
+                .take()
+                .unwrap_or_else(|| std::io::Error::other("The remote helper failed to handle the request"))),
+        }
+    }
+}
+
+/// A request to an emulated service, which is handled by the helper once all of it was written.
+struct EmulatedRequest<'a, H: Helper> {
+    service: Service,
+    helper: &'a mut H,
+    names_by_id: &'a HashMap<BString, BString>,
+    object_format: Option<&'a BStr>,
+    lock_files: &'a mut Vec<PathBuf>,
+    request: Vec<u8>,
+    response: Response,
+}
+
+impl<H: Helper> Write for EmulatedRequest<'_, H> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.request.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<H: Helper> EmulatedRequest<'_, H> {
+    fn upload_pack(&mut self) -> Result<Vec<u8>, Error> {
+        let mut wants = Vec::new();
+        let mut done = false;
+        let mut sideband = false;
+        for line in data_lines(&self.request, false) {
+            let line = line.trim_end();
+            if let Some(rest) = line.strip_prefix(b"want ") {
+                let mut tokens = rest.split_str(b" ");
+                let id = tokens.next().unwrap_or_default().as_bstr();
+                if wants.is_empty() {
+                    sideband = tokens.any(|cap| cap == b"side-band-64k" || cap == b"side-band");
+                }
+                let name = self.names_by_id.get(id).cloned().unwrap_or_else(|| id.to_owned());
+                wants.push(Want {
+                    id: id.to_owned(),
+                    name,
+                });
+            } else if line == b"done" {
+                done = true;
+            } else if line.starts_with(b"deepen") || line.starts_with(b"shallow ") {
+                return Err(Error::ShallowFetch);
+            }
+        }
+
+        let mut out = Vec::new();
+        encode::data_to_write(b"NAK\n", &mut out)?;
+        if done {
+            let outcome = self.helper.fetch(&wants)?;
+            self.lock_files.extend(outcome.lock_files);
+            let trailer = if is_sha256(self.object_format) {
+                EMPTY_PACK_SHA256_TRAILER.as_slice()
+            } else {
+                EMPTY_PACK_SHA1_TRAILER.as_slice()
+            };
+            let pack = [EMPTY_PACK_HEADER, trailer].concat();
+            if sideband {
+                encode::band_to_write(Channel::Data, &pack, &mut out)?;
+                encode::flush_to_write(&mut out)?;
+            } else {
+                out.extend_from_slice(&pack);
+            }
+        }
+        Ok(out)
+    }
+
+    fn receive_pack(&mut self) -> Result<Vec<u8>, Error> {
+        let mut specs = Vec::new();
+        let mut report_status = false;
+        for (line_no, line) in data_lines(&self.request, true).enumerate() {
+            let line = line.trim_end_with(|c| c == '\n');
+            let line = match line.find_byte(0) {
+                Some(pos) => {
+                    if line_no == 0 {
+                        report_status = line[pos + 1..].split_str(b" ").any(|cap| cap == b"report-status");
+                    }
+                    &line[..pos]
+                }
+                None => line,
+            };
+            let mut tokens = line.split_str(b" ");
+            let (Some(_old), Some(new), Some(name)) = (tokens.next(), tokens.next(), tokens.next()) else {
+                continue;
+            };
+            specs.push(PushSpec {
+                src: new.iter().any(|b| *b != b'0').then(|| new.into()),
+                dst: name.into(),
+                force: true,
+            });
+        }
+
+        let statuses = self.helper.push(&specs)?;
+        let mut out = Vec::new();
+        if report_status {
+            encode::data_to_write(b"unpack ok\n", &mut out)?;
+            for status in statuses {
+                let line = match status {
+                    PushStatus::Ok { dst } => [b"ok ".as_slice(), &dst, b"\n"].concat(),
+                    PushStatus::Error { dst, reason } => [
+                        b"ng ".as_slice(),
+                        &dst,
+                        b" ",
+                        reason.as_ref().map_or(b"failed".as_slice(), |reason| reason.as_bytes()),
+                        b"\n",
+                    ]
+                    .concat(),
+                };
+                encode::data_to_write(&line, &mut out)?;
+            }
+            encode::flush_to_write(&mut out)?;
+        }
+        Ok(out)
+    }
+}
+
+impl<H: Helper> Drop for EmulatedRequest<'_, H> {
+    fn drop(&mut self) {
+        // Requests are written entirely before their response is read, which happens only after the writer is dropped.
+        let response = match self.service {
+            Service::UploadPack => self.upload_pack(),
+            Service::ReceivePack => self.receive_pack(),
+        };
+        self.response.set(response);
+    }
+}
+
+/// Return the content of all data lines in `request`, skipping all other packets, or stopping at the first flush packet
+/// if `stop_at_flush` is `true` as it is followed by a pack when pushing.
+fn data_lines(mut request: &[u8], stop_at_flush: bool) -> impl Iterator<Item = &BStr> {
+    std::iter::from_fn(move || {
+        loop {
+            let decode::Stream::Complete { line, bytes_consumed } = decode::streaming(request).ok()? else {
+                return None;
+            };
+            request = &request[bytes_consumed..];
+            match line {
+                PacketLineRef::Data(data) => return Some(data.as_bstr()),
+                PacketLineRef::Flush if stop_at_flush => return None,
+                PacketLineRef::Flush | PacketLineRef::Delimiter | PacketLineRef::ResponseEnd => continue,
+            }
+        }
+    })
+}
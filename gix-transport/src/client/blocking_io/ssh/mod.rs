@@ -38,6 +38,10 @@ pub enum ProgramKind {
 
 mod program_kind;
 
+///
+#[cfg(feature = "ssh-client-native")]
+pub mod native;
+
 ///
 pub mod invocation {
     use std::ffi::OsString;
@@ -80,9 +84,21 @@ pub mod connect {
         /// when invoking the program.
         /// If unset, the `program` basename determines the variant, or an invocation of the `command` itself.
         pub kind: Option<ProgramKind>,
+        /// If `true`, use the [native ssh client](crate::client::blocking_io::ssh::native) instead of invoking a
+        /// program, which requires the `ssh-client-native` feature. It's configured like `ssh` would be.
+        ///
+        /// As the native client can't apply what's passed to other programs, it's only used if neither `command`
+        /// nor a `kind` other than [`ProgramKind::Ssh`] are set, see [`use_native_client()`](Self::use_native_client()).
+        pub native: bool,
     }
 
     impl Options {
+        /// Return `true` if the [native ssh client](crate::client::blocking_io::ssh::native) should be used instead of
+        /// invoking a program, which is the case if it's enabled and no program other than `ssh` is configured.
+        pub fn use_native_client(&self) -> bool {
+            self.native && self.command.is_none() && matches!(self.kind, None | Some(ProgramKind::Ssh))
+        }
+
         /// Return the configured ssh command, defaulting to `ssh` if neither the `command` nor the `kind` fields are set.
         pub fn ssh_command(&self) -> &OsStr {
             self.command
@@ -0,0 +1,553 @@
+//! Parsing of `ssh_config` files, limited to the directives needed to connect to a host.
+//!
+//! Supported are `Host` blocks with `*` and `?` wildcards and `!` negation, `Include` as well as the directives
+//! `HostName`, `User`, `Port`, `IdentityFile`, `IdentitiesOnly`, `ProxyJump`, `UserKnownHostsFile` and
+//! `StrictHostKeyChecking`. All other directives are ignored, and so are `Match` blocks as their criteria can't
+//! be evaluated.
+use std::path::{Path, PathBuf};
+
+/// The maximum depth of nested `Include` directives.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// The error returned by [`Config::from_bytes()`] and [`Config::from_paths()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read ssh configuration at '{}'", path.display())]
+    Io { path: PathBuf, source: std::io::Error },
+    #[error("Line {line}: the value {value:?} of '{keyword}' is invalid")]
+    InvalidValue {
+        line: usize,
+        keyword: &'static str,
+        value: String,
+    },
+    #[error("Line {line}: '{keyword}' needs a value")]
+    MissingValue { line: usize, keyword: &'static str },
+    #[error("Line {line}: an opening quote wasn't closed")]
+    UnclosedQuote { line: usize },
+    #[error("Includes are nested too deeply at '{}'", path.display())]
+    IncludeDepth { path: PathBuf },
+}
+
+/// How to handle host keys that aren't known yet, as configured by `StrictHostKeyChecking`.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum StrictHostKeyChecking {
+    /// Never connect to hosts with unknown keys.
+    Yes,
+    /// Add keys of unknown hosts to the user's `known_hosts` file, but never connect if a known key changed.
+    AcceptNew,
+    /// Connect to hosts with unknown keys and add them to the user's `known_hosts` file.
+    /// Changed keys still prevent the connection.
+    No,
+    /// Ask the user whether to trust an unknown host key, and if so, add it to the user's `known_hosts` file.
+    #[default]
+    Ask,
+}
+
+/// A host to connect through as part of `ProxyJump`, in the form `[user@]host[:port]` or `ssh://[user@]host[:port]`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Jump {
+    /// The user to log in as, if set.
+    pub user: Option<String>,
+    /// The host name or alias, which is subject to configuration as well.
+    pub host: String,
+    /// The port to connect to, if set.
+    pub port: Option<u16>,
+}
+
+/// The configuration applying to a single host, obtained with [`Config::resolve()`].
+///
+/// Values may contain tokens like `%h`, which aren't expanded.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Host {
+    /// `HostName`, the actual name of the host to connect to.
+    pub host_name: Option<String>,
+    /// `User`, the name of the user to log in as.
+    pub user: Option<String>,
+    /// `Port`, the port to connect to.
+    pub port: Option<u16>,
+    /// All `IdentityFile` values, in order.
+    pub identity_files: Vec<String>,
+    /// `IdentitiesOnly`, if `true` only identity files should be used, and not keys offered by an agent.
+    pub identities_only: Option<bool>,
+    /// `ProxyJump`, the hosts to connect through in order, which is empty if it was set to `none`.
+    pub proxy_jump: Option<Vec<Jump>>,
+    /// `UserKnownHostsFile`, which is empty if it was set to `none`.
+    pub user_known_hosts_files: Option<Vec<String>>,
+    /// `StrictHostKeyChecking`.
+    pub strict_host_key_checking: Option<StrictHostKeyChecking>,
+}
+
+/// The parsed content of one or more `ssh_config` files.
+#[derive(Debug, Default, Clone)]
+pub struct Config {
+    sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone)]
+struct Section {
+    /// The patterns of the `Host` line, or `None` for `Match` blocks, which never match.
+    patterns: Option<Vec<Pattern>>,
+    directives: Vec<Directive>,
+}
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    negated: bool,
+    glob: String,
+}
+
+#[derive(Debug, Clone)]
+enum Directive {
+    HostName(String),
+    User(String),
+    Port(u16),
+    IdentityFile(String),
+    IdentitiesOnly(bool),
+    ProxyJump(Vec<Jump>),
+    UserKnownHostsFile(Vec<String>),
+    StrictHostKeyChecking(StrictHostKeyChecking),
+}
+
+impl Config {
+    /// Parse `input` as the content of an `ssh_config` file.
+    ///
+    /// `Include` directives with relative paths are resolved against the current working directory.
+    pub fn from_bytes(input: &[u8]) -> Result<Self, Error> {
+        let mut config = Config::default();
+        config.parse(input, None, 0)?;
+        Ok(config)
+    }
+
+    /// Parse all files at `paths` in order, as if they were a single file, skipping those that don't exist.
+    ///
+    /// As values seen first take precedence, `paths` should start with the user's configuration file.
+    /// `Include` directives with relative paths are resolved against the directory containing the respective
+    /// top-level file, and `~` is expanded with `home_dir`.
+    pub fn from_paths(
+        paths: impl IntoIterator<Item = impl AsRef<Path>>,
+        home_dir: Option<&Path>,
+    ) -> Result<Self, Error> {
+        let mut config = Config::default();
+        for path in paths {
+            let path = path.as_ref();
+            let input = match std::fs::read(path) {
+                Ok(input) => input,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => {
+                    return Err(Error::Io {
+                        path: path.to_owned(),
+                        source: err,
+                    });
+                }
+            };
+            // Each file starts out unconditionally, like any directive before the first `Host` line.
+            config.sections.push(Section {
+                patterns: Some(vec![Pattern {
+                    negated: false,
+                    glob: "*".into(),
+                }]),
+                directives: Vec::new(),
+            });
+            let include = IncludeContext {
+                base: path.parent().unwrap_or(Path::new(".")).to_owned(),
+                home_dir: home_dir.map(ToOwned::to_owned),
+            };
+            config.parse(&input, Some(&include), 0)?;
+        }
+        Ok(config)
+    }
+
+    /// Return the configuration applying to the host `alias` as it was provided by the user, where the first
+    /// value obtained for each directive takes precedence.
+    pub fn resolve(&self, alias: &str) -> Host {
+        let alias = alias.to_ascii_lowercase();
+        let mut host = Host::default();
+        for section in self.sections.iter().filter(|section| section.matches(&alias)) {
+            for directive in &section.directives {
+                match directive {
+                    Directive::HostName(value) => {
+                        host.host_name.get_or_insert_with(|| value.clone());
+                    }
+                    Directive::User(value) => {
+                        host.user.get_or_insert_with(|| value.clone());
+                    }
+                    Directive::Port(value) => {
+                        host.port.get_or_insert(*value);
+                    }
+                    Directive::IdentityFile(value) => host.identity_files.push(value.clone()),
+                    Directive::IdentitiesOnly(value) => {
+                        host.identities_only.get_or_insert(*value);
+                    }
+                    Directive::ProxyJump(value) => {
+                        host.proxy_jump.get_or_insert_with(|| value.clone());
+                    }
+                    Directive::UserKnownHostsFile(value) => {
+                        host.user_known_hosts_files.get_or_insert_with(|| value.clone());
+                    }
+                    Directive::StrictHostKeyChecking(value) => {
+                        host.strict_host_key_checking.get_or_insert(*value);
+                    }
+                }
+            }
+        }
+        host
+    }
+}
+
+struct IncludeContext {
+    base: PathBuf,
+    home_dir: Option<PathBuf>,
+}
+
+impl Config {
+    fn parse(&mut self, input: &[u8], include: Option<&IncludeContext>, depth: usize) -> Result<(), Error> {
+        if self.sections.is_empty() {
+            self.sections.push(Section {
+                patterns: Some(vec![Pattern {
+                    negated: false,
+                    glob: "*".into(),
+                }]),
+                directives: Vec::new(),
+            });
+        }
+        for (line_number, line) in input.split(|b| *b == b'\n').enumerate() {
+            let line_number = line_number + 1;
+            let line = String::from_utf8_lossy(line);
+            let Some((keyword, args)) = split_keyword(&line) else {
+                continue;
+            };
+            let args = split_arguments(args).ok_or(Error::UnclosedQuote { line: line_number })?;
+            let first = |keyword: &'static str| -> Result<String, Error> {
+                args.first().cloned().ok_or(Error::MissingValue {
+                    line: line_number,
+                    keyword,
+                })
+            };
+            let invalid = |keyword: &'static str, value: &str| Error::InvalidValue {
+                line: line_number,
+                keyword,
+                value: value.to_owned(),
+            };
+            let directive = match keyword.to_ascii_lowercase().as_str() {
+                "host" => {
+                    if args.is_empty() {
+                        return Err(Error::MissingValue {
+                            line: line_number,
+                            keyword: "Host",
+                        });
+                    }
+                    self.sections.push(Section {
+                        patterns: Some(
+                            args.iter()
+                                .map(|arg| match arg.strip_prefix('!') {
+                                    Some(glob) => Pattern {
+                                        negated: true,
+                                        glob: glob.to_ascii_lowercase(),
+                                    },
+                                    None => Pattern {
+                                        negated: false,
+                                        glob: arg.to_ascii_lowercase(),
+                                    },
+                                })
+                                .collect(),
+                        ),
+                        directives: Vec::new(),
+                    });
+                    continue;
+                }
+                "match" => {
+                    self.sections.push(Section {
+                        patterns: None,
+                        directives: Vec::new(),
+                    });
+                    continue;
+                }
+                "include" => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        return Err(Error::IncludeDepth {
+                            path: first("Include")?.into(),
+                        });
+                    }
+                    for arg in &args {
+                        for path in include_paths(arg, include) {
+                            let input = match std::fs::read(&path) {
+                                Ok(input) => input,
+                                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                                Err(err) => return Err(Error::Io { path, source: err }),
+                            };
+                            self.parse(&input, include, depth + 1)?;
+                        }
+                    }
+                    continue;
+                }
+                "hostname" => Directive::HostName(first("HostName")?),
+                "user" => Directive::User(first("User")?),
+                "port" => {
+                    let value = first("Port")?;
+                    Directive::Port(value.parse().map_err(|_| invalid("Port", &value))?)
+                }
+                "identityfile" => Directive::IdentityFile(first("IdentityFile")?),
+                "identitiesonly" => {
+                    let value = first("IdentitiesOnly")?;
+                    Directive::IdentitiesOnly(match value.to_ascii_lowercase().as_str() {
+                        "yes" => true,
+                        "no" => false,
+                        _ => return Err(invalid("IdentitiesOnly", &value)),
+                    })
+                }
+                "proxyjump" => {
+                    let value = first("ProxyJump")?;
+                    Directive::ProxyJump(if value.eq_ignore_ascii_case("none") {
+                        Vec::new()
+                    } else {
+                        value
+                            .split(',')
+                            .map(|jump| parse_jump(jump.trim()).ok_or_else(|| invalid("ProxyJump", &value)))
+                            .collect::<Result<_, _>>()?
+                    })
+                }
+                "userknownhostsfile" => {
+                    first("UserKnownHostsFile")?;
+                    Directive::UserKnownHostsFile(if args.len() == 1 && args[0].eq_ignore_ascii_case("none") {
+                        Vec::new()
+                    } else {
+                        args.clone()
+                    })
+                }
+                "stricthostkeychecking" => {
+                    let value = first("StrictHostKeyChecking")?;
+                    Directive::StrictHostKeyChecking(match value.to_ascii_lowercase().as_str() {
+                        "yes" => StrictHostKeyChecking::Yes,
+                        "accept-new" => StrictHostKeyChecking::AcceptNew,
+                        "no" | "off" => StrictHostKeyChecking::No,
+                        "ask" => StrictHostKeyChecking::Ask,
+                        _ => return Err(invalid("StrictHostKeyChecking", &value)),
+                    })
+                }
+                _ => continue,
+            };
+            self.sections
+                .last_mut()
+                .expect("there is always a section")
+                .directives
+                .push(directive);
+        }
+        Ok(())
+    }
+}
+
+impl Section {
+    fn matches(&self, alias: &str) -> bool {
+        let Some(patterns) = &self.patterns else {
+            return false;
+        };
+        let mut matched = false;
+        for pattern in patterns {
+            if wildcard_match(pattern.glob.as_bytes(), alias.as_bytes()) {
+                if pattern.negated {
+                    return false;
+                }
+                matched = true;
+            }
+        }
+        matched
+    }
+}
+
+/// Split `line` into its keyword and the remaining arguments, or return `None` if it's empty or a comment.
+fn split_keyword(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line
+        .find(|c: char| c.is_ascii_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let (keyword, rest) = line.split_at(end);
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim_start();
+    Some((keyword, rest))
+}
+
+/// Split `args` at whitespace, keeping whitespace within double quotes, or return `None` if a quote isn't closed.
+fn split_arguments(args: &str) -> Option<Vec<String>> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    for c in args.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            '#' if !in_quotes && !has_arg => break,
+            c if c.is_ascii_whitespace() && !in_quotes => {
+                if has_arg {
+                    out.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    if has_arg {
+        out.push(current);
+    }
+    Some(out)
+}
+
+fn parse_jump(jump: &str) -> Option<Jump> {
+    let jump = jump.strip_prefix("ssh://").unwrap_or(jump);
+    let (user, host_and_port) = match jump.rsplit_once('@') {
+        Some((user, rest)) => (Some(user.to_owned()), rest),
+        None => (None, jump),
+    };
+    let (host, port) = if let Some(rest) = host_and_port.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        let port = match rest.strip_prefix(':') {
+            Some(port) => Some(port.parse().ok()?),
+            None if rest.is_empty() => None,
+            None => return None,
+        };
+        (host, port)
+    } else {
+        match host_and_port.split_once(':') {
+            Some((host, port)) => (host, Some(port.parse().ok()?)),
+            None => (host_and_port, None),
+        }
+    };
+    if host.is_empty() {
+        return None;
+    }
+    Some(Jump {
+        user,
+        host: host.to_owned(),
+        port,
+    })
+}
+
+/// Return all paths matching the `Include` argument `arg`, which may contain wildcards in its last component.
+fn include_paths(arg: &str, include: Option<&IncludeContext>) -> Vec<PathBuf> {
+    let path = match (arg.strip_prefix("~/"), include.and_then(|ctx| ctx.home_dir.as_deref())) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => {
+            let path = PathBuf::from(arg);
+            match include {
+                Some(ctx) if path.is_relative() => ctx.base.join(path),
+                _ => path,
+            }
+        }
+    };
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return vec![path];
+    };
+    if !file_name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| wildcard_match(file_name.as_bytes(), name.as_bytes()))
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Match `value` against `pattern`, where `*` matches any amount of characters and `?` matches exactly one.
+pub(crate) fn wildcard_match(pattern: &[u8], value: &[u8]) -> bool {
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, v));
+                p += 1;
+            }
+            Some(b'?') => {
+                p += 1;
+                v += 1;
+            }
+            Some(c) if *c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    v = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// The values to substitute for `%` tokens in configuration values.
+pub(crate) struct Tokens<'a> {
+    /// `%d`, also used to expand a leading `~`.
+    pub home_dir: Option<&'a Path>,
+    /// `%h`, the host name to connect to.
+    pub host_name: &'a str,
+    /// `%n`, the host name as provided by the user.
+    pub alias: &'a str,
+    /// `%p`, the port to connect to.
+    pub port: u16,
+    /// `%r`, the remote user name.
+    pub remote_user: &'a str,
+    /// `%u`, the local user name.
+    pub local_user: &'a str,
+}
+
+impl Tokens<'_> {
+    /// Expand all tokens in `value` as well as a leading `~`, leaving unknown tokens as is.
+    pub(crate) fn expand(&self, value: &str) -> String {
+        let mut out = String::with_capacity(value.len());
+        let value = match (value.strip_prefix("~/"), self.home_dir) {
+            (Some(rest), Some(home)) => {
+                out.push_str(&home.to_string_lossy());
+                out.push('/');
+                rest
+            }
+            _ => value,
+        };
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('%') => out.push('%'),
+                Some('h') => out.push_str(self.host_name),
+                Some('n') => out.push_str(self.alias),
+                Some('p') => out.push_str(&self.port.to_string()),
+                Some('r') => out.push_str(self.remote_user),
+                Some('u') => out.push_str(self.local_user),
+                Some('d') => out.push_str(&self.home_dir.map(|dir| dir.to_string_lossy()).unwrap_or_default()),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        }
+        out
+    }
+}
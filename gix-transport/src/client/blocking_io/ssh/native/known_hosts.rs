@@ -0,0 +1,197 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use base64::Engine;
+use russh::keys::PublicKey;
+
+use crate::client::blocking_io::ssh::native::Error;
+
+/// The result of looking up a host key in `known_hosts` files.
+#[derive(Debug)]
+pub(crate) enum Verdict {
+    /// The key is known for the host.
+    Known,
+    /// The key is unknown and no other key of the same type is known for the host.
+    Unknown,
+    /// Another key of the same type is known for the host, so the key changed.
+    Changed { path: PathBuf, line: usize },
+    /// The key was marked as revoked.
+    Revoked { path: PathBuf, line: usize },
+}
+
+/// Find out how `key` relates to what `files` know about `host` at `port`.
+pub(crate) fn check(files: &[PathBuf], host: &str, port: u16, key: &PublicKey) -> Result<Verdict, Error> {
+    let host = host_with_port(host, port);
+    let mut verdict = Verdict::Unknown;
+    for path in files {
+        let content = match std::fs::read(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(Error::KnownHosts {
+                    path: path.clone(),
+                    source: err,
+                });
+            }
+        };
+        for (line_number, line) in content.split(|b| *b == b'\n').enumerate() {
+            let line = String::from_utf8_lossy(line);
+            let mut fields = line.split_ascii_whitespace();
+            let Some(mut patterns) = fields.next() else {
+                continue;
+            };
+            if patterns.starts_with('#') {
+                continue;
+            }
+            let mut revoked = false;
+            if let Some(marker) = patterns.strip_prefix('@') {
+                match marker {
+                    "revoked" => revoked = true,
+                    // Certificate authorities aren't supported.
+                    _ => continue,
+                }
+                let Some(next) = fields.next() else { continue };
+                patterns = next;
+            }
+            let (Some(_algorithm), Some(encoded)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            if !host_matches(patterns, &host) {
+                continue;
+            }
+            let Ok(known) = russh::keys::parse_public_key_base64(encoded) else {
+                continue;
+            };
+            let line = line_number + 1;
+            if revoked {
+                if known == *key {
+                    return Ok(Verdict::Revoked {
+                        path: path.clone(),
+                        line,
+                    });
+                }
+            } else if known == *key {
+                verdict = Verdict::Known;
+            } else if known.algorithm() == key.algorithm() && matches!(verdict, Verdict::Unknown) {
+                verdict = Verdict::Changed {
+                    path: path.clone(),
+                    line,
+                };
+            }
+        }
+    }
+    Ok(verdict)
+}
+
+/// Append `key` for `host` at `port` to the `known_hosts` file at `path`, creating it if needed.
+pub(crate) fn learn(path: &Path, host: &str, port: u16, key: &PublicKey) -> Result<(), Error> {
+    let to_error = |source| Error::KnownHosts {
+        path: path.to_owned(),
+        source,
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(to_error)?;
+    }
+    let key = key.to_openssh().map_err(|err| to_error(std::io::Error::other(err)))?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(to_error)?;
+    writeln!(file, "{} {}", host_with_port(host, port), key.trim_end()).map_err(to_error)
+}
+
+fn host_with_port(host: &str, port: u16) -> String {
+    let host = host.to_ascii_lowercase();
+    if port == 22 { host } else { format!("[{host}]:{port}") }
+}
+
+/// Return `true` if the comma-separated `patterns` match `host`, which may be hashed or contain wildcards and negations.
+fn host_matches(patterns: &str, host: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns.split(',') {
+        if let Some(hashed) = pattern.strip_prefix("|1|") {
+            matched |= hashed_host_matches(hashed, host);
+        } else if let Some(negated) = pattern.strip_prefix('!') {
+            if super::config::wildcard_match(negated.to_ascii_lowercase().as_bytes(), host.as_bytes()) {
+                return false;
+            }
+        } else {
+            matched |= super::config::wildcard_match(pattern.to_ascii_lowercase().as_bytes(), host.as_bytes());
+        }
+    }
+    matched
+}
+
+/// Check `host` against `salt|hash` as written by `ssh-keygen -H`, where `hash` is the HMAC-SHA1 of `host` keyed with `salt`.
+fn hashed_host_matches(salt_and_hash: &str, host: &str) -> bool {
+    let Some((salt, hash)) = salt_and_hash.split_once('|') else {
+        return false;
+    };
+    let engine = base64::engine::general_purpose::STANDARD;
+    let (Ok(salt), Ok(hash)) = (engine.decode(salt), engine.decode(hash)) else {
+        return false;
+    };
+    hmac_sha1(&salt, host.as_bytes()).is_some_and(|mac| mac.as_slice() == hash.as_slice())
+}
+
+/// Compute the HMAC of `message` keyed with `key` as defined in RFC 2104, using the SHA1 implementation of `gix-hash`,
+/// or `None` if a SHA1 collision attack was detected.
+fn hmac_sha1(key: &[u8], message: &[u8]) -> Option<gix_hash::ObjectId> {
+    const BLOCK_SIZE: usize = 64;
+    let sha1 = |parts: &[&[u8]]| {
+        let mut hasher = gix_hash::hasher(gix_hash::Kind::Sha1);
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.try_finalize().ok()
+    };
+    let mut padded_key = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        let key = sha1(&[key])?;
+        padded_key[..key.as_slice().len()].copy_from_slice(key.as_slice());
+    } else {
+        padded_key[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| padded_key.map(|b| b ^ byte);
+    let inner = sha1(&[&pad(0x36), message])?;
+    sha1(&[&pad(0x5c), inner.as_slice()])
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn hmac_sha1_matches_rfc_2202() {
+        for (key, message, expected) in [
+            (
+                &[0x0b; 20][..],
+                &b"Hi There"[..],
+                "b617318655057264e28bc0b6fb378c8ef146be00",
+            ),
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+            ),
+            (
+                &[0xaa; 80],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                "aa4ae5e15272d00e95705637ce8a3b55ed402112",
+            ),
+        ] {
+            assert_eq!(
+                super::hmac_sha1(key, message).expect("no collision").to_string(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn hashed_hosts_as_written_by_ssh_keygen() {
+        let salt_and_hash = "cHPP9kz9ztfzc07At/TA3H+xYA4=|w+U73tLcIHBR4xUrfQsCXeBN6U8=";
+        assert!(super::host_matches(&format!("|1|{salt_and_hash}"), "example.com"));
+        assert!(!super::host_matches(&format!("|1|{salt_and_hash}"), "example.org"));
+    }
+}
@@ -0,0 +1,262 @@
+//! A native SSH client which doesn't need an `ssh` program.
+//!
+//! Connections are established and driven by `russh` in a dedicated thread with its own `tokio` runtime,
+//! while the [`Transport`] exposes them as blocking transport.
+//!
+//! The client is configured by `ssh_config` files, see [`config`] for the supported directives.
+//! Host keys are verified against `known_hosts` files, and authentication is attempted with keys provided by
+//! `ssh-agent`, followed by identity files and finally passwords.
+use std::{
+    any::Any,
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+use bstr::{BStr, BString, ByteSlice};
+use gix_features::io::pipe;
+
+use crate::{
+    Protocol, Service,
+    client::{
+        self, MessageKind, WriteMode,
+        blocking_io::{RequestWriter, SetServiceResponse},
+        git::blocking_io::Connection,
+    },
+};
+
+pub mod config;
+mod known_hosts;
+mod session;
+
+/// The amount of chunks of received data to buffer before the connection stops reading from the server.
+const IN_FLIGHT_READS: usize = 32;
+
+/// The error used in [`connect()`] and when performing the handshake with a [`Transport`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The scheme in \"{}\" is not usable for an ssh connection", .0.to_bstring())]
+    UnsupportedScheme(gix_url::Url),
+    #[error(transparent)]
+    Config(#[from] config::Error),
+    #[error("Could not determine the user to log in as, and no local user name is known")]
+    MissingUser,
+    #[error("Could not connect to {host}:{port}")]
+    Connect {
+        host: String,
+        port: u16,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Ssh(#[from] russh::Error),
+    #[error("Could not read or write the known hosts file at '{}'", path.display())]
+    KnownHosts { path: PathBuf, source: std::io::Error },
+    #[error(
+        "The host key of '{host}' isn't known, and it wasn't accepted. Its {fingerprint} fingerprint may be added to a known_hosts file, or StrictHostKeyChecking may be configured differently"
+    )]
+    UnknownHostKey { host: String, fingerprint: String },
+    #[error("The host key of '{host}' changed compared to the one in '{}' on line {line}, and the connection could be intercepted", path.display())]
+    HostKeyChanged { host: String, path: PathBuf, line: usize },
+    #[error("The host key of '{host}' was revoked in '{}' on line {line}", path.display())]
+    HostKeyRevoked { host: String, path: PathBuf, line: usize },
+    #[error("Could not load the identity file at '{}'", path.display())]
+    IdentityFile { path: PathBuf, source: russh::keys::Error },
+    #[error("Could not authenticate using an ssh-agent key")]
+    Agent(#[from] russh::AgentAuthError),
+    #[error("Authentication as '{user}' at '{host}' failed with all available methods")]
+    Authentication { user: String, host: String },
+    #[error("Could not start the tokio runtime to drive the connection")]
+    Runtime(#[source] std::io::Error),
+    #[error("The connection thread stopped unexpectedly")]
+    ConnectionThread,
+}
+
+impl crate::IsSpuriousError for Error {
+    fn is_spurious(&self) -> bool {
+        match self {
+            Error::Connect { source, .. } => source.is_spurious(),
+            _ => false,
+        }
+    }
+}
+
+/// Options to configure the native SSH client.
+#[derive(Clone)]
+pub struct Options {
+    /// The `ssh_config` files to read, in order of precedence.
+    pub config_files: Vec<PathBuf>,
+    /// The user's `known_hosts` files, used if `UserKnownHostsFile` isn't configured. Newly accepted host keys are
+    /// added to the first one of them.
+    pub user_known_hosts_files: Vec<PathBuf>,
+    /// The system-wide `known_hosts` files, which are only read.
+    pub global_known_hosts_files: Vec<PathBuf>,
+    /// The home directory to expand `~` and `%d` with, and to find the default identity files in its `.ssh` directory.
+    pub home_dir: Option<PathBuf>,
+    /// The name of the local user, used to log in as if no other user is configured, and to expand `%u`.
+    pub local_user: Option<String>,
+    /// The path to the socket of the `ssh-agent` to obtain keys from, or `None` to not use an agent.
+    pub agent_socket: Option<PathBuf>,
+    /// Determines how to prompt for passphrases, passwords and unknown host keys.
+    pub prompt: gix_prompt::Options<'static>,
+}
+
+impl Options {
+    /// Create options like `ssh` would use them, with configuration and `known_hosts` files in the home directory
+    /// and in `/etc/ssh`, the agent at `SSH_AUTH_SOCK` and prompts that can be controlled with `GIT_ASKPASS`,
+    /// `SSH_ASKPASS` and `GIT_TERMINAL_PROMPT`.
+    pub fn from_env() -> Self {
+        let home_dir = gix_path::env::home_dir();
+        let ssh_dir = home_dir.as_deref().map(|home| home.join(".ssh"));
+        let etc_ssh = Path::new("/etc/ssh");
+        Options {
+            config_files: ssh_dir
+                .iter()
+                .map(|dir| dir.join("config"))
+                .chain(Some(etc_ssh.join("ssh_config")))
+                .collect(),
+            user_known_hosts_files: ssh_dir
+                .iter()
+                .flat_map(|dir| [dir.join("known_hosts"), dir.join("known_hosts2")])
+                .collect(),
+            global_known_hosts_files: vec![etc_ssh.join("ssh_known_hosts"), etc_ssh.join("ssh_known_hosts2")],
+            local_user: std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok(),
+            agent_socket: std::env::var_os("SSH_AUTH_SOCK").map(PathBuf::from),
+            prompt: gix_prompt::Options::default().apply_environment(true, true, true),
+            home_dir,
+        }
+    }
+}
+
+/// A blocking transport which connects to the `ssh` server on handshake and executes the service there.
+pub struct Transport {
+    url: gix_url::Url,
+    path: BString,
+    desired_version: Protocol,
+    options: Options,
+    password: Option<String>,
+    trace: bool,
+    connection: Option<Connection<pipe::Reader, session::Writer>>,
+    session: Option<session::Handle>,
+}
+
+/// Connect to the host in `url` with the native SSH client, to obtain data from the repository at the path in `url`.
+///
+/// The actual connection is established during the handshake, configured by `options`.
+///
+/// The `desired_version` is the preferred protocol version when establishing the connection, but note that it can be
+/// downgraded by servers not supporting it.
+/// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+#[allow(clippy::result_large_err)]
+pub fn connect(
+    url: gix_url::Url,
+    desired_version: Protocol,
+    options: Options,
+    trace: bool,
+) -> Result<Transport, Error> {
+    if url.scheme != gix_url::Scheme::Ssh || url.host().is_none() {
+        return Err(Error::UnsupportedScheme(url));
+    }
+    let path = gix_url::expand_path::for_shell(url.path.clone());
+    Ok(Transport {
+        url,
+        path,
+        desired_version,
+        options,
+        password: None,
+        trace,
+        connection: None,
+        session: None,
+    })
+}
+
+impl client::TransportWithoutIO for Transport {
+    fn set_identity(&mut self, identity: gix_sec::identity::Account) -> Result<(), client::Error> {
+        self.url
+            .set_user((!identity.username.is_empty()).then_some(identity.username));
+        self.password = (!identity.password.is_empty()).then_some(identity.password);
+        Ok(())
+    }
+
+    fn to_url(&self) -> Cow<'_, BStr> {
+        Cow::Owned(self.url.to_bstring())
+    }
+
+    fn connection_persists_across_multiple_requests(&self) -> bool {
+        true
+    }
+
+    fn configure(&mut self, _config: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        Ok(())
+    }
+}
+
+impl client::blocking_io::Transport for Transport {
+    fn handshake<'a>(
+        &mut self,
+        service: Service,
+        extra_parameters: &'a [(&'a str, Option<&'a str>)],
+    ) -> Result<SetServiceResponse<'_>, client::Error> {
+        if self.path.trim().first() == Some(&b'-') {
+            return Err(client::Error::AmbiguousPath {
+                path: self.path.clone(),
+            });
+        }
+        self.close();
+        let command = format!(
+            "{} {}",
+            service.as_str(),
+            gix_quote::single(self.path.as_ref()).to_str_lossy()
+        );
+        let target = session::Target {
+            url: self.url.clone(),
+            options: self.options.clone(),
+            password: self.password.clone(),
+            command,
+            git_protocol: (self.desired_version != Protocol::V1)
+                .then(|| format!("version={}", self.desired_version as usize)),
+        };
+
+        let (write, read) = pipe::unidirectional(IN_FLIGHT_READS);
+        let (session, writer) = session::start(target, write).map_err(|err| client::Error::SshNative(Box::new(err)))?;
+        self.session = Some(session);
+        self.connection = Some(Connection::new_for_spawned_process(
+            read,
+            writer,
+            self.desired_version,
+            self.path.clone(),
+            self.trace,
+        ));
+        self.connection
+            .as_mut()
+            .expect("connection to be there right after setting it")
+            .handshake(service, extra_parameters)
+    }
+
+    fn request(
+        &mut self,
+        write_mode: WriteMode,
+        on_into_read: MessageKind,
+        trace: bool,
+    ) -> Result<RequestWriter<'_>, client::Error> {
+        self.connection
+            .as_mut()
+            .ok_or(client::Error::MissingHandshake)?
+            .request(write_mode, on_into_read, trace)
+    }
+}
+
+impl Transport {
+    fn close(&mut self) {
+        self.connection.take();
+        if let Some((thread, stop)) = self.session.take() {
+            stop.send(()).ok();
+            thread.join().ok();
+        }
+    }
+}
+
+impl Drop for Transport {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
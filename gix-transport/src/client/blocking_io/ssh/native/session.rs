@@ -0,0 +1,466 @@
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread::JoinHandle,
+};
+
+use gix_features::io::pipe;
+use russh::{
+    ChannelMsg, MethodKind,
+    client::{self, AuthResult},
+    keys::{HashAlg, PrivateKey, PrivateKeyWithHashAlg, PublicKey},
+};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::client::blocking_io::ssh::native::{
+    Error, Options,
+    config::{self, StrictHostKeyChecking, Tokens},
+    known_hosts,
+};
+
+/// The identity files to try if none are configured, relative to the `.ssh` directory in the home directory.
+const DEFAULT_IDENTITY_FILES: &[&str] = &["id_rsa", "id_ecdsa", "id_ed25519"];
+
+/// Everything needed to connect to the host in `url` and run `command` there.
+pub(crate) struct Target {
+    pub url: gix_url::Url,
+    pub options: Options,
+    /// The password to use instead of prompting for one.
+    pub password: Option<String>,
+    pub command: String,
+    /// The value of the `GIT_PROTOCOL` environment variable to pass to the server, if set.
+    pub git_protocol: Option<String>,
+}
+
+/// The thread driving a connection, along with the sender to signal it to stop.
+pub(crate) type Handle = (JoinHandle<()>, oneshot::Sender<()>);
+
+/// The writing end of a connection, passing all data to the connection thread.
+pub(crate) struct Writer(mpsc::UnboundedSender<Vec<u8>>);
+
+impl std::io::Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .send(buf.to_vec())
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "the ssh connection was closed"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Connect to `target` in a new thread and execute its command, writing all output of the command to `out`.
+///
+/// Return the thread along with a sender to stop it once the connection is established, and the writer to provide
+/// input to the command.
+pub(crate) fn start(target: Target, out: pipe::Writer) -> Result<(Handle, Writer), Error> {
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    let (input_tx, input_rx) = mpsc::unbounded_channel();
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let thread = std::thread::Builder::new()
+        .name("gix ssh connection".into())
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(err) => {
+                    ready_tx.send(Err(Error::Runtime(err))).ok();
+                    return;
+                }
+            };
+            runtime.block_on(async move {
+                let connected = match connect(&target).await {
+                    Ok(connected) => connected,
+                    Err(err) => {
+                        ready_tx.send(Err(err)).ok();
+                        return;
+                    }
+                };
+                ready_tx.send(Ok(())).ok();
+                relay(connected, &target.command, input_rx, out, stop_rx).await;
+            });
+        })
+        .map_err(Error::Runtime)?;
+    let res = ready_rx.recv().unwrap_or(Err(Error::ConnectionThread));
+    match res {
+        Ok(()) => Ok(((thread, stop_tx), Writer(input_tx))),
+        Err(err) => {
+            thread.join().ok();
+            Err(err)
+        }
+    }
+}
+
+/// A host to connect to, with all configuration applied.
+struct Hop {
+    alias: String,
+    host_name: String,
+    port: u16,
+    user: String,
+    identity_files: Vec<PathBuf>,
+    use_agent: bool,
+    user_known_hosts_files: Vec<PathBuf>,
+    strict_host_key_checking: StrictHostKeyChecking,
+}
+
+impl Hop {
+    fn new(
+        config: &config::Config,
+        options: &Options,
+        alias: &str,
+        user: Option<&str>,
+        port: Option<u16>,
+    ) -> Result<Self, Error> {
+        let host = config.resolve(alias);
+        let port = port.or(host.port).unwrap_or(22);
+        let user = user
+            .map(ToOwned::to_owned)
+            .or(host.user)
+            .or_else(|| options.local_user.clone())
+            .ok_or(Error::MissingUser)?;
+        let local_user = options.local_user.as_deref().unwrap_or_default();
+        let mut tokens = Tokens {
+            home_dir: options.home_dir.as_deref(),
+            host_name: alias,
+            alias,
+            port,
+            remote_user: &user,
+            local_user,
+        };
+        let host_name = host
+            .host_name
+            .map_or_else(|| alias.to_owned(), |name| tokens.expand(&name));
+        tokens.host_name = &host_name;
+
+        let identity_files = if host.identity_files.is_empty() {
+            options
+                .home_dir
+                .iter()
+                .flat_map(|home| DEFAULT_IDENTITY_FILES.iter().map(|name| home.join(".ssh").join(name)))
+                .collect()
+        } else {
+            host.identity_files
+                .iter()
+                .map(|path| PathBuf::from(tokens.expand(path)))
+                .collect()
+        };
+        let user_known_hosts_files = match host.user_known_hosts_files {
+            Some(files) => files.iter().map(|path| PathBuf::from(tokens.expand(path))).collect(),
+            None => options.user_known_hosts_files.clone(),
+        };
+        Ok(Hop {
+            alias: alias.to_owned(),
+            port,
+            user,
+            identity_files,
+            use_agent: !host.identities_only.unwrap_or(false),
+            user_known_hosts_files,
+            strict_host_key_checking: host.strict_host_key_checking.unwrap_or_default(),
+            host_name,
+        })
+    }
+}
+
+/// The established connections to all hops, along with the channel running the command on the last one.
+struct Connected {
+    sessions: Vec<client::Handle<Verifier>>,
+    channel: russh::Channel<client::Msg>,
+}
+
+async fn connect(target: &Target) -> Result<Connected, Error> {
+    let options = &target.options;
+    let config = config::Config::from_paths(&options.config_files, options.home_dir.as_deref())?;
+    let alias = target.url.host().expect("validated when creating the transport");
+    let jumps = config.resolve(alias).proxy_jump.unwrap_or_default();
+    let mut hops = jumps
+        .iter()
+        .map(|jump| Hop::new(&config, options, &jump.host, jump.user.as_deref(), jump.port))
+        .collect::<Result<Vec<_>, _>>()?;
+    hops.push(Hop::new(&config, options, alias, target.url.user(), target.url.port)?);
+
+    let client_config = Arc::new(client::Config::default());
+    let mut sessions: Vec<client::Handle<Verifier>> = Vec::with_capacity(hops.len());
+    let num_hops = hops.len();
+    for (index, hop) in hops.iter().enumerate() {
+        let verifier = Verifier {
+            host_name: hop.host_name.clone(),
+            port: hop.port,
+            user_known_hosts_files: hop.user_known_hosts_files.clone(),
+            global_known_hosts_files: options.global_known_hosts_files.clone(),
+            strict_host_key_checking: hop.strict_host_key_checking,
+            prompt: options.prompt.clone(),
+        };
+        let mut session = match sessions.last() {
+            None => {
+                let stream = tokio::net::TcpStream::connect((hop.host_name.as_str(), hop.port))
+                    .await
+                    .map_err(|source| Error::Connect {
+                        host: hop.host_name.clone(),
+                        port: hop.port,
+                        source,
+                    })?;
+                client::connect_stream(client_config.clone(), stream, verifier).await?
+            }
+            Some(previous) => {
+                let channel = previous
+                    .channel_open_direct_tcpip(hop.host_name.clone(), hop.port.into(), "127.0.0.1", 0)
+                    .await?;
+                client::connect_stream(client_config.clone(), channel.into_stream(), verifier).await?
+            }
+        };
+        let password = (index + 1 == num_hops).then_some(target.password.as_deref()).flatten();
+        authenticate(&mut session, hop, options, password).await?;
+        sessions.push(session);
+    }
+
+    let channel = sessions
+        .last()
+        .expect("there is at least one hop")
+        .channel_open_session()
+        .await?;
+    if let Some(version) = &target.git_protocol {
+        channel.set_env(false, "GIT_PROTOCOL", version.as_str()).await?;
+    }
+    channel.exec(true, target.command.as_bytes()).await?;
+    Ok(Connected { sessions, channel })
+}
+
+/// Try all authentication methods supported by the server as `hop.user`, using `password` instead of prompting if set.
+async fn authenticate(
+    session: &mut client::Handle<Verifier>,
+    hop: &Hop,
+    options: &Options,
+    password: Option<&str>,
+) -> Result<(), Error> {
+    let user = hop.user.as_str();
+    let methods = match session.authenticate_none(user).await? {
+        AuthResult::Success => return Ok(()),
+        AuthResult::Failure { remaining_methods, .. } => remaining_methods,
+    };
+
+    if methods.contains(&MethodKind::PublicKey) {
+        let rsa_hash = session.best_supported_rsa_hash().await?.flatten();
+        #[cfg(unix)]
+        if let Some(socket) = options.agent_socket.as_deref().filter(|_| hop.use_agent) {
+            if let Ok(mut agent) = russh::keys::agent::client::AgentClient::connect_uds(socket).await {
+                for identity in agent.request_identities().await.unwrap_or_default() {
+                    // Certificates aren't supported.
+                    let russh::keys::agent::AgentIdentity::PublicKey { key, .. } = identity else {
+                        continue;
+                    };
+                    if session
+                        .authenticate_publickey_with(user, key, rsa_hash, &mut agent)
+                        .await?
+                        .success()
+                    {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        for path in &hop.identity_files {
+            let Some(key) = load_identity(path, &options.prompt)? else {
+                continue;
+            };
+            if session
+                .authenticate_publickey(user, PrivateKeyWithHashAlg::new(Arc::new(key), rsa_hash))
+                .await?
+                .success()
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    if methods.contains(&MethodKind::Password) {
+        match password {
+            Some(password) => {
+                if session.authenticate_password(user, password).await?.success() {
+                    return Ok(());
+                }
+            }
+            None => {
+                let prompt = format!("{user}@{}'s password: ", hop.host_name);
+                for _attempt in 0..3 {
+                    let Ok(password) = gix_prompt::ask(&prompt, &options.prompt) else {
+                        break;
+                    };
+                    if session.authenticate_password(user, password).await?.success() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+    Err(Error::Authentication {
+        user: user.to_owned(),
+        host: hop.alias.clone(),
+    })
+}
+
+/// Load the private key at `path`, prompting for a passphrase if it's encrypted, or return `None` if it doesn't exist
+/// or if no passphrase could be obtained.
+fn load_identity(path: &Path, prompt: &gix_prompt::Options<'_>) -> Result<Option<PrivateKey>, Error> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let to_error = |source| Error::IdentityFile {
+        path: path.to_owned(),
+        source,
+    };
+    match russh::keys::load_secret_key(path, None) {
+        Ok(key) => Ok(Some(key)),
+        Err(russh::keys::Error::KeyIsEncrypted) => {
+            let Ok(passphrase) = gix_prompt::ask(&format!("Enter passphrase for key '{}': ", path.display()), prompt)
+            else {
+                return Ok(None);
+            };
+            russh::keys::load_secret_key(path, Some(&passphrase))
+                .map(Some)
+                .map_err(to_error)
+        }
+        Err(err) => Err(to_error(err)),
+    }
+}
+
+/// Verifies the keys of servers against `known_hosts` files.
+struct Verifier {
+    host_name: String,
+    port: u16,
+    user_known_hosts_files: Vec<PathBuf>,
+    global_known_hosts_files: Vec<PathBuf>,
+    strict_host_key_checking: StrictHostKeyChecking,
+    prompt: gix_prompt::Options<'static>,
+}
+
+impl client::Handler for Verifier {
+    type Error = Error;
+
+    async fn check_server_key(&mut self, key: &PublicKey) -> Result<bool, Self::Error> {
+        let files: Vec<_> = self
+            .user_known_hosts_files
+            .iter()
+            .chain(&self.global_known_hosts_files)
+            .cloned()
+            .collect();
+        let host = self.host_name.clone();
+        match known_hosts::check(&files, &self.host_name, self.port, key)? {
+            known_hosts::Verdict::Known => return Ok(true),
+            known_hosts::Verdict::Changed { path, line } => return Err(Error::HostKeyChanged { host, path, line }),
+            known_hosts::Verdict::Revoked { path, line } => return Err(Error::HostKeyRevoked { host, path, line }),
+            known_hosts::Verdict::Unknown => {}
+        }
+
+        let fingerprint = key.fingerprint(HashAlg::Sha256).to_string();
+        let accept = match self.strict_host_key_checking {
+            StrictHostKeyChecking::Yes => false,
+            StrictHostKeyChecking::AcceptNew | StrictHostKeyChecking::No => true,
+            StrictHostKeyChecking::Ask => {
+                let mut prompt = self.prompt.clone();
+                if prompt.mode == gix_prompt::Mode::Hidden {
+                    prompt.mode = gix_prompt::Mode::Visible;
+                }
+                gix_prompt::ask(
+                    &format!(
+                        "The authenticity of host '{host}' can't be established.\n\
+                         {algorithm} key fingerprint is {fingerprint}.\n\
+                         Are you sure you want to continue connecting (yes/no)? ",
+                        algorithm = key.algorithm()
+                    ),
+                    &prompt,
+                )
+                .is_ok_and(|answer| answer.trim().eq_ignore_ascii_case("yes"))
+            }
+        };
+        if !accept {
+            return Err(Error::UnknownHostKey { host, fingerprint });
+        }
+        if let Some(path) = self.user_known_hosts_files.first() {
+            known_hosts::learn(path, &self.host_name, self.port, key)?;
+        }
+        Ok(true)
+    }
+}
+
+/// Pass data between the remote command running in `connected` and the local `input` and `out` until the command
+/// finishes or `stop` is signalled.
+async fn relay(
+    mut connected: Connected,
+    command: &str,
+    mut input: mpsc::UnboundedReceiver<Vec<u8>>,
+    mut out: pipe::Writer,
+    mut stop: oneshot::Receiver<()>,
+) {
+    let channel = &mut connected.channel;
+    let mut stderr = Vec::new();
+    let mut input_closed = false;
+    let mut exit_status = None;
+    loop {
+        tokio::select! {
+            _ = &mut stop => {
+                // Send what was written before, as the remote command may still act on it.
+                while let Ok(data) = input.try_recv() {
+                    if channel.data(data.as_slice()).await.is_err() {
+                        break;
+                    }
+                }
+                break;
+            }
+            data = input.recv(), if !input_closed => match data {
+                Some(data) => {
+                    if channel.data(data.as_slice()).await.is_err() {
+                        break;
+                    }
+                }
+                None => {
+                    input_closed = true;
+                    channel.eof().await.ok();
+                }
+            },
+            msg = channel.wait() => match msg {
+                Some(ChannelMsg::Data { data }) => {
+                    if out.write_all(&data).is_err() {
+                        break;
+                    }
+                }
+                Some(ChannelMsg::ExtendedData { data, ext: 1 }) => stderr.extend_from_slice(&data),
+                Some(ChannelMsg::ExitStatus { exit_status: status }) => exit_status = Some(status),
+                Some(ChannelMsg::Failure) => {
+                    out.channel
+                        .send(Err(std::io::Error::other(format!(
+                            "The server refused to execute '{command}'"
+                        ))))
+                        .ok();
+                    break;
+                }
+                Some(ChannelMsg::Close) | None => break,
+                Some(_) => {}
+            },
+        }
+    }
+
+    let stderr = String::from_utf8_lossy(&stderr);
+    match exit_status {
+        Some(status) if status != 0 => {
+            out.channel
+                .send(Err(std::io::Error::other(format!(
+                    "'{command}' failed with exit status {status}: {}",
+                    stderr.trim_end()
+                ))))
+                .ok();
+        }
+        _ if !stderr.is_empty() => {
+            std::io::stderr().write_all(stderr.as_bytes()).ok();
+        }
+        _ => {}
+    }
+    drop(out);
+    for session in connected.sessions.iter().rev() {
+        session
+            .disconnect(russh::Disconnect::ByApplication, "", "en")
+            .await
+            .ok();
+    }
+}
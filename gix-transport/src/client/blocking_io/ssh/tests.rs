@@ -28,6 +28,39 @@ mod options {
             );
         }
 
+        #[test]
+        fn native_client_is_only_used_without_other_programs() {
+            assert!(!Options::default().use_native_client(), "it's opt-in");
+            let native = Options {
+                native: true,
+                ..Default::default()
+            };
+            assert!(native.use_native_client());
+            assert!(
+                Options {
+                    kind: Some(ProgramKind::Ssh),
+                    ..native.clone()
+                }
+                .use_native_client()
+            );
+            assert!(
+                !Options {
+                    kind: Some(ProgramKind::Plink),
+                    ..native.clone()
+                }
+                .use_native_client(),
+                "the configuration of other programs can't be applied"
+            );
+            assert!(
+                !Options {
+                    command: Some("ssh -i identity -p 2222".into()),
+                    ..native
+                }
+                .use_native_client(),
+                "neither can the arguments of a configured command"
+            );
+        }
+
         #[test]
         fn kind_serves_as_fallback() {
             assert_eq!(
@@ -41,6 +41,9 @@ pub(crate) mod connect {
         #[cfg(feature = "blocking-client")]
         /// Options to use if the scheme of the URL is `ssh`.
         pub ssh: crate::client::blocking_io::ssh::connect::Options,
+        #[cfg(feature = "blocking-client")]
+        /// Options to use if the scheme of the URL isn't known, and a remote helper is used for it.
+        pub remote_helper: crate::client::blocking_io::remote_helper::connect::Options,
         /// If `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
         pub trace: bool,
     }
@@ -67,6 +70,9 @@ pub(crate) mod connect {
         #[cfg(not(any(feature = "http-client-curl", feature = "http-client-reqwest")))]
         #[error("'{0}' is not compiled in. Compile with the 'http-client-curl' or 'http-client-reqwest' cargo feature")]
         CompiledWithoutHttp(gix_url::Scheme),
+        #[cfg(not(feature = "ssh-client-native"))]
+        #[error("The native ssh client is not compiled in. Compile with the 'ssh-client-native' cargo feature")]
+        CompiledWithoutNativeSsh,
     }
 
     // TODO: maybe fix this workaround: want `IsSpuriousError`  in `Connection(…)`
@@ -108,6 +114,14 @@ mod error {
     type HttpError = std::convert::Infallible;
     #[cfg(not(feature = "blocking-client"))]
     type SshInvocationError = std::convert::Infallible;
+    #[cfg(feature = "ssh-client-native")]
+    type SshNativeError = ssh::native::Error;
+    #[cfg(not(feature = "ssh-client-native"))]
+    type SshNativeError = std::convert::Infallible;
+    #[cfg(feature = "blocking-client")]
+    type RemoteHelperError = crate::client::blocking_io::remote_helper::Error;
+    #[cfg(not(feature = "blocking-client"))]
+    type RemoteHelperError = std::convert::Infallible;
 
     /// The error used in most methods of the [`client`][crate::client] module
     #[derive(thiserror::Error, Debug)]
@@ -143,6 +157,10 @@ mod error {
         Http(#[from] HttpError),
         #[error(transparent)]
         SshInvocation(SshInvocationError),
+        #[error(transparent)]
+        SshNative(Box<SshNativeError>),
+        #[error(transparent)]
+        RemoteHelper(Box<RemoteHelperError>),
         #[error("The repository path '{path}' could be mistaken for a command-line argument")]
         AmbiguousPath { path: BString },
     }
@@ -0,0 +1,631 @@
+use std::{
+    io::BufRead,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use bstr::ByteSlice;
+use gix_transport::{
+    Protocol, Service,
+    client::{
+        self, TransportWithoutIO,
+        blocking_io::{Transport, TransportV2Ext, ssh::native},
+    },
+};
+use russh::{
+    keys::{PrivateKey, PublicKey, ssh_key},
+    server::{self, Auth, Msg, Session},
+};
+
+pub type Error = Box<dyn std::error::Error>;
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+const USER: &str = "git";
+const PASSWORD: &str = "secret";
+
+fn key(seed: u8) -> PrivateKey {
+    ssh_key::private::Ed25519Keypair::from_seed(&[seed; 32]).into()
+}
+
+fn fixture_bytes(path: &str) -> Vec<u8> {
+    std::fs::read(PathBuf::from("tests").join("fixtures").join(path)).expect("fixture to be present and readable")
+}
+
+/// What the server observed from its clients.
+#[derive(Default, Debug)]
+struct Log {
+    commands: Vec<String>,
+    env: Vec<(String, String)>,
+    direct_tcpip: Vec<(String, u32)>,
+    received: Vec<u8>,
+}
+
+/// An SSH server in its own thread which accepts `USER` with `PASSWORD` or with `client_key`, and answers every
+/// command with the same canned response.
+struct Server {
+    port: u16,
+    host_key: PublicKey,
+    log: Arc<Mutex<Log>>,
+}
+
+impl Server {
+    fn start(client_key: PublicKey) -> Self {
+        let host_key = key(1);
+        let public_host_key = host_key.public_key().clone();
+        let log = Arc::new(Mutex::new(Log::default()));
+        let config = Arc::new(server::Config {
+            keys: vec![host_key],
+            auth_rejection_time: std::time::Duration::ZERO,
+            auth_rejection_time_initial: Some(std::time::Duration::ZERO),
+            ..Default::default()
+        });
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("can bind to a local port");
+        listener.set_nonblocking(true).expect("can configure the listener");
+        let port = listener.local_addr().expect("bound").port();
+        let handler = Handler {
+            client_key,
+            response: Arc::new(fixture_bytes("v2/clone.response")),
+            log: log.clone(),
+        };
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("runtime can be created");
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).expect("listener is usable with tokio");
+                while let Ok((stream, _)) = listener.accept().await {
+                    let (config, handler) = (config.clone(), handler.clone());
+                    tokio::spawn(async move {
+                        if let Ok(session) = server::run_stream(config, stream, handler).await {
+                            session.await.ok();
+                        }
+                    });
+                }
+            });
+        });
+        Server {
+            port,
+            host_key: public_host_key,
+            log,
+        }
+    }
+
+    fn known_hosts_line(&self) -> String {
+        format!(
+            "[127.0.0.1]:{} {}\n",
+            self.port,
+            self.host_key.to_openssh().expect("key can be serialized")
+        )
+    }
+
+    fn log(&self) -> std::sync::MutexGuard<'_, Log> {
+        self.log.lock().expect("not poisoned")
+    }
+
+    /// Return the log once `ready` is `true`, giving the server some time to receive everything the client sent.
+    fn wait_for_log(&self, ready: impl Fn(&Log) -> bool) -> std::sync::MutexGuard<'_, Log> {
+        for _attempt in 0..100 {
+            let log = self.log();
+            if ready(&log) {
+                return log;
+            }
+            drop(log);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        self.log()
+    }
+}
+
+#[derive(Clone)]
+struct Handler {
+    client_key: PublicKey,
+    response: Arc<Vec<u8>>,
+    log: Arc<Mutex<Log>>,
+}
+
+impl server::Handler for Handler {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, user: &str, password: &str) -> std::result::Result<Auth, Self::Error> {
+        Ok(if user == USER && password == PASSWORD {
+            Auth::Accept
+        } else {
+            Auth::reject()
+        })
+    }
+
+    async fn auth_publickey(&mut self, user: &str, public_key: &PublicKey) -> std::result::Result<Auth, Self::Error> {
+        Ok(if user == USER && *public_key == self.client_key {
+            Auth::Accept
+        } else {
+            Auth::reject()
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: russh::Channel<Msg>,
+        reply: server::ChannelOpenHandle,
+        _session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        reply.accept().await;
+        Ok(())
+    }
+
+    async fn channel_open_direct_tcpip(
+        &mut self,
+        channel: russh::Channel<Msg>,
+        host_to_connect: &str,
+        port_to_connect: u32,
+        _originator_address: &str,
+        _originator_port: u32,
+        reply: server::ChannelOpenHandle,
+        _session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        self.log
+            .lock()
+            .expect("not poisoned")
+            .direct_tcpip
+            .push((host_to_connect.into(), port_to_connect));
+        let mut stream = tokio::net::TcpStream::connect((host_to_connect, port_to_connect as u16)).await?;
+        reply.accept().await;
+        tokio::spawn(async move {
+            let mut channel = channel.into_stream();
+            tokio::io::copy_bidirectional(&mut channel, &mut stream).await.ok();
+        });
+        Ok(())
+    }
+
+    async fn env_request(
+        &mut self,
+        _channel: russh::ChannelId,
+        variable_name: &str,
+        variable_value: &str,
+        _session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        self.log
+            .lock()
+            .expect("not poisoned")
+            .env
+            .push((variable_name.into(), variable_value.into()));
+        Ok(())
+    }
+
+    async fn exec_request(
+        &mut self,
+        channel: russh::ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        self.log
+            .lock()
+            .expect("not poisoned")
+            .commands
+            .push(data.to_str_lossy().into_owned());
+        session.channel_success(channel)?;
+        session.data(channel, self.response.to_vec())?;
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        _channel: russh::ChannelId,
+        data: &[u8],
+        _session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        self.log.lock().expect("not poisoned").received.extend_from_slice(data);
+        Ok(())
+    }
+
+    async fn channel_eof(
+        &mut self,
+        channel: russh::ChannelId,
+        session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        session.exit_status_request(channel, 0)?;
+        session.eof(channel)?;
+        session.close(channel)
+    }
+}
+
+/// Options which only use files in `dir` and never prompt.
+fn options(dir: &Path) -> native::Options {
+    native::Options {
+        config_files: vec![dir.join("config")],
+        user_known_hosts_files: vec![dir.join("known_hosts")],
+        global_known_hosts_files: Vec::new(),
+        home_dir: Some(dir.to_owned()),
+        local_user: Some("local".into()),
+        agent_socket: None,
+        prompt: gix_prompt::Options {
+            askpass: None,
+            mode: gix_prompt::Mode::Disable,
+        },
+    }
+}
+
+fn connect(url: &str, options: native::Options) -> Result<native::Transport> {
+    Ok(native::connect(
+        gix_url::parse(url.into())?,
+        Protocol::V2,
+        options,
+        false,
+    )?)
+}
+
+/// Perform a V2 handshake followed by `ls-refs` and assert on the outcome.
+fn handshake_and_list_refs(transport: &mut native::Transport) -> Result {
+    let res = transport.handshake(Service::UploadPack, &[])?;
+    assert_eq!(res.actual_protocol, Protocol::V2);
+    assert!(
+        res.capabilities.capability("ls-refs").is_some(),
+        "the capabilities are read from the remote command"
+    );
+    drop(res);
+
+    let reader = transport.invoke(
+        "ls-refs",
+        [("agent", Some("git/2.28.0"))].iter().copied(),
+        Some(
+            ["symrefs", "ref-prefix HEAD"]
+                .iter()
+                .map(|s| s.as_bytes().as_bstr().to_owned()),
+        ),
+        false,
+    )?;
+    let refs = reader.lines().collect::<std::result::Result<Vec<_>, _>>()?;
+    assert_eq!(
+        refs,
+        [
+            "808e50d724f604f69ab93c6da2919c014667bedb HEAD symref-target:refs/heads/master",
+            "808e50d724f604f69ab93c6da2919c014667bedb refs/heads/master"
+        ]
+    );
+    Ok(())
+}
+
+fn native_error(err: client::Error) -> native::Error {
+    match err {
+        client::Error::SshNative(err) => *err,
+        err => panic!("expected an error of the native ssh client, got {err:?}"),
+    }
+}
+
+#[test]
+fn password_authentication_with_known_host() -> Result {
+    let server = Server::start(key(2).public_key().clone());
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    std::fs::write(dir.path().join("known_hosts"), server.known_hosts_line())?;
+
+    let mut transport = connect(
+        &format!("ssh://127.0.0.1:{}/repo.git", server.port),
+        options(dir.path()),
+    )?;
+    transport.set_identity(gix_sec::identity::Account {
+        username: USER.into(),
+        password: PASSWORD.into(),
+        oauth_refresh_token: None,
+    })?;
+    assert!(transport.connection_persists_across_multiple_requests());
+    handshake_and_list_refs(&mut transport)?;
+    drop(transport);
+
+    let log = server.wait_for_log(|log| !log.received.is_empty());
+    assert_eq!(log.commands, ["git-upload-pack '/repo.git'"]);
+    assert_eq!(log.env, [("GIT_PROTOCOL".to_string(), "version=2".to_string())]);
+    assert!(
+        log.received.starts_with(b"0014command=ls-refs\n"),
+        "the request is sent through the channel"
+    );
+    Ok(())
+}
+
+#[test]
+fn publickey_authentication_configured_in_ssh_config_and_learning_host_keys() -> Result {
+    let client_key = key(2);
+    let server = Server::start(client_key.public_key().clone());
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    std::fs::write(
+        dir.path().join("id_test"),
+        client_key.to_openssh(ssh_key::LineEnding::LF)?.as_bytes(),
+    )?;
+    std::fs::write(
+        dir.path().join("config"),
+        format!(
+            "Host server\n  HostName 127.0.0.1\n  Port {}\n  User {USER}\n  IdentityFile ~/id_test\n  StrictHostKeyChecking accept-new\n",
+            server.port
+        ),
+    )?;
+
+    let mut transport = connect("ssh://server/~/repo.git", options(dir.path()))?;
+    handshake_and_list_refs(&mut transport)?;
+    drop(transport);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("known_hosts"))?,
+        server.known_hosts_line(),
+        "unknown host keys are learned with `accept-new`"
+    );
+
+    std::fs::write(
+        dir.path().join("config"),
+        format!(
+            "Host server\n  HostName 127.0.0.1\n  Port {}\n  User {USER}\n  IdentityFile ~/id_test\n  StrictHostKeyChecking yes\n",
+            server.port
+        ),
+    )?;
+    let mut transport = connect("ssh://server/~/repo.git", options(dir.path()))?;
+    handshake_and_list_refs(&mut transport)?;
+    drop(transport);
+
+    assert_eq!(
+        server.log().commands,
+        ["git-upload-pack '~/repo.git'", "git-upload-pack '~/repo.git'"],
+        "the second connection verifies against the learned key"
+    );
+    Ok(())
+}
+
+#[test]
+fn unknown_host_keys_are_rejected_with_strict_checking() -> Result {
+    let server = Server::start(key(2).public_key().clone());
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    std::fs::write(dir.path().join("config"), "StrictHostKeyChecking yes\n")?;
+
+    let mut transport = connect(
+        &format!("ssh://{USER}@127.0.0.1:{}/repo.git", server.port),
+        options(dir.path()),
+    )?;
+    let err = transport
+        .handshake(Service::UploadPack, &[])
+        .map(|_| ())
+        .expect_err("the host key isn't known");
+    assert!(
+        matches!(native_error(err), native::Error::UnknownHostKey { host, .. } if host == "127.0.0.1"),
+        "the connection is refused"
+    );
+    assert!(server.log().commands.is_empty());
+    assert!(!dir.path().join("known_hosts").exists(), "nothing is learned");
+    Ok(())
+}
+
+#[test]
+fn changed_host_keys_are_rejected() -> Result {
+    let server = Server::start(key(2).public_key().clone());
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    std::fs::write(
+        dir.path().join("known_hosts"),
+        format!(
+            "# a comment\n[127.0.0.1]:{} {}\n",
+            server.port,
+            key(3).public_key().to_openssh()?
+        ),
+    )?;
+    std::fs::write(dir.path().join("config"), "StrictHostKeyChecking no\n")?;
+
+    let mut transport = connect(
+        &format!("ssh://{USER}@127.0.0.1:{}/repo.git", server.port),
+        options(dir.path()),
+    )?;
+    let err = transport
+        .handshake(Service::UploadPack, &[])
+        .map(|_| ())
+        .expect_err("the host key changed");
+    assert!(
+        matches!(native_error(err), native::Error::HostKeyChanged { line: 2, .. }),
+        "a changed key is never accepted"
+    );
+    Ok(())
+}
+
+#[test]
+fn revoked_host_keys_are_rejected() -> Result {
+    let server = Server::start(key(2).public_key().clone());
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    std::fs::write(
+        dir.path().join("known_hosts"),
+        format!(
+            "{}@revoked * {}\n",
+            server.known_hosts_line(),
+            server.host_key.to_openssh()?
+        ),
+    )?;
+
+    let mut transport = connect(
+        &format!("ssh://{USER}@127.0.0.1:{}/repo.git", server.port),
+        options(dir.path()),
+    )?;
+    let err = transport
+        .handshake(Service::UploadPack, &[])
+        .map(|_| ())
+        .expect_err("the host key was revoked");
+    assert!(matches!(
+        native_error(err),
+        native::Error::HostKeyRevoked { line: 2, .. }
+    ));
+    Ok(())
+}
+
+#[test]
+fn failing_authentication() -> Result {
+    let server = Server::start(key(2).public_key().clone());
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    std::fs::write(dir.path().join("known_hosts"), server.known_hosts_line())?;
+
+    let mut transport = connect(
+        &format!("ssh://{USER}@127.0.0.1:{}/repo.git", server.port),
+        options(dir.path()),
+    )?;
+    let err = transport
+        .handshake(Service::UploadPack, &[])
+        .map(|_| ())
+        .expect_err("there is no key and prompting is disabled");
+    assert!(matches!(
+        native_error(err),
+        native::Error::Authentication { user, .. } if user == USER
+    ));
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn password_from_askpass_program() -> Result {
+    use std::os::unix::fs::PermissionsExt;
+
+    let server = Server::start(key(2).public_key().clone());
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    std::fs::write(dir.path().join("known_hosts"), server.known_hosts_line())?;
+    let askpass = dir.path().join("askpass");
+    std::fs::write(&askpass, format!("#!/bin/sh\necho {PASSWORD}\n"))?;
+    std::fs::set_permissions(&askpass, std::fs::Permissions::from_mode(0o755))?;
+
+    let mut options = options(dir.path());
+    options.prompt.askpass = Some(askpass.into());
+    let mut transport = connect(&format!("ssh://{USER}@127.0.0.1:{}/repo.git", server.port), options)?;
+    handshake_and_list_refs(&mut transport)?;
+    Ok(())
+}
+
+#[test]
+fn proxy_jump_through_another_host() -> Result {
+    let client_key = key(2);
+    let server = Server::start(client_key.public_key().clone());
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    std::fs::create_dir(dir.path().join(".ssh"))?;
+    std::fs::write(
+        dir.path().join(".ssh").join("id_ed25519"),
+        client_key.to_openssh(ssh_key::LineEnding::LF)?.as_bytes(),
+    )?;
+    std::fs::write(dir.path().join("known_hosts"), server.known_hosts_line())?;
+    std::fs::write(
+        dir.path().join("config"),
+        format!(
+            "Host target\n  HostName 127.0.0.1\n  Port {port}\n  ProxyJump {USER}@jump\nHost jump\n  HostName 127.0.0.1\n  Port {port}\nHost *\n  User {USER}\n",
+            port = server.port
+        ),
+    )?;
+
+    let mut transport = connect("ssh://target/repo.git", options(dir.path()))?;
+    handshake_and_list_refs(&mut transport)?;
+    drop(transport);
+
+    let log = server.log();
+    assert_eq!(
+        log.direct_tcpip,
+        [("127.0.0.1".to_string(), server.port.into())],
+        "the connection to the target is tunneled through the jump host, using the default identity file for both"
+    );
+    assert_eq!(log.commands, ["git-upload-pack '/repo.git'"]);
+    Ok(())
+}
+
+mod config {
+    use gix_transport::client::blocking_io::ssh::native::config::{Config, Host, Jump, StrictHostKeyChecking};
+
+    #[test]
+    fn first_obtained_value_wins_and_identity_files_accumulate() -> crate::Result {
+        let config = Config::from_bytes(
+            br#"
+# global values come first
+IdentityFile ~/.ssh/global
+
+Host *.example.com !private.example.com
+    HostName "%h.internal"
+    user alice
+    Port=2222
+    IdentityFile ~/.ssh/example
+    ProxyJump bob@bastion:22,ssh://carol@gateway
+
+Host git.example.com
+    User mallory
+    IdentitiesOnly yes
+    UserKnownHostsFile ~/.ssh/one ~/.ssh/two
+
+Match host *
+    User ignored
+
+Host *
+    Port 22
+    StrictHostKeyChecking accept-new
+"#,
+        )?;
+        assert_eq!(
+            config.resolve("git.example.com"),
+            Host {
+                host_name: Some("%h.internal".into()),
+                user: Some("alice".into()),
+                port: Some(2222),
+                identity_files: vec!["~/.ssh/global".into(), "~/.ssh/example".into()],
+                identities_only: Some(true),
+                proxy_jump: Some(vec![
+                    Jump {
+                        user: Some("bob".into()),
+                        host: "bastion".into(),
+                        port: Some(22),
+                    },
+                    Jump {
+                        user: Some("carol".into()),
+                        host: "gateway".into(),
+                        port: None,
+                    }
+                ]),
+                user_known_hosts_files: Some(vec!["~/.ssh/one".into(), "~/.ssh/two".into()]),
+                strict_host_key_checking: Some(StrictHostKeyChecking::AcceptNew),
+            }
+        );
+        assert_eq!(
+            config.resolve("private.example.com"),
+            Host {
+                port: Some(22),
+                identity_files: vec!["~/.ssh/global".into()],
+                strict_host_key_checking: Some(StrictHostKeyChecking::AcceptNew),
+                ..Default::default()
+            },
+            "negated patterns exclude hosts"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn proxy_jump_none_disables_jumping() -> crate::Result {
+        let config = Config::from_bytes(b"Host direct\n  ProxyJump none\nHost *\n  ProxyJump jump\n")?;
+        assert_eq!(config.resolve("direct").proxy_jump, Some(Vec::new()));
+        assert_eq!(
+            config.resolve("other").proxy_jump.expect("set").len(),
+            1,
+            "otherwise the catch-all applies"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn includes_are_resolved_relative_to_the_including_file() -> crate::Result {
+        let dir = gix_testtools::tempfile::TempDir::new()?;
+        std::fs::create_dir(dir.path().join("conf.d"))?;
+        std::fs::write(
+            dir.path().join("conf.d").join("a.conf"),
+            "Host a\n  User from-include\n",
+        )?;
+        std::fs::write(
+            dir.path().join("config"),
+            "Include conf.d/*.conf\nHost *\n  User fallback\n",
+        )?;
+
+        let config = Config::from_paths([dir.path().join("config"), dir.path().join("missing")], None)?;
+        assert_eq!(config.resolve("a").user.as_deref(), Some("from-include"));
+        assert_eq!(config.resolve("b").user.as_deref(), Some("fallback"));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_values_are_errors() {
+        for input in [
+            &b"Port not-a-number"[..],
+            b"StrictHostKeyChecking maybe",
+            b"User",
+            b"User \"alice",
+        ] {
+            assert!(Config::from_bytes(input).is_err(), "{:?}", std::str::from_utf8(input));
+        }
+    }
+}
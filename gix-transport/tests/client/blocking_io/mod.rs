@@ -1,2 +1,3 @@
 #[cfg(any(feature = "http-client-curl", feature = "http-client-reqwest"))]
 mod http;
+mod remote_helper;
@@ -0,0 +1,617 @@
+use std::{
+    io::{BufRead, Write},
+    sync::{Arc, Mutex},
+};
+
+use bstr::{BString, ByteSlice};
+use gix_transport::{
+    Protocol, Service,
+    client::{
+        self, TransportWithoutIO,
+        blocking_io::{
+            Transport, TransportV2Ext,
+            remote_helper::{
+                self, Capabilities, ConnectKind, Connection, FetchOutcome, Helper, List, OptionResponse, PushSpec,
+                PushStatus, Ref, RefTarget, Want,
+            },
+        },
+    },
+};
+
+const ID: &str = "808e50d724f604f69ab93c6da2919c014667bedb";
+
+fn url() -> gix_url::Url {
+    gix_url::parse("mock://example.com/repo.git".into()).expect("valid url")
+}
+
+/// A buffer that can be shared with a [`Connection`] to see what was written to it.
+#[derive(Default, Clone)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().expect("not poisoned").write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Shared {
+    fn data(&self) -> BString {
+        self.0.lock().expect("not poisoned").clone().into()
+    }
+}
+
+/// An in-process helper which records what it's asked to do.
+#[derive(Default)]
+struct Mock {
+    capabilities: Capabilities,
+    list: List,
+    server_response: Vec<u8>,
+    requests: Shared,
+    options: Vec<(String, String)>,
+    wants: Vec<Want>,
+    pushes: Vec<PushSpec>,
+    connects: Vec<(Service, ConnectKind)>,
+}
+
+impl Helper for Mock {
+    fn capabilities(&mut self) -> Result<Capabilities, remote_helper::Error> {
+        Ok(self.capabilities.clone())
+    }
+
+    fn option(&mut self, name: &str, value: &str) -> Result<OptionResponse, remote_helper::Error> {
+        self.options.push((name.into(), value.into()));
+        Ok(OptionResponse::Ok)
+    }
+
+    fn list(&mut self, _for_push: bool) -> Result<List, remote_helper::Error> {
+        Ok(self.list.clone())
+    }
+
+    fn fetch(&mut self, wants: &[Want]) -> Result<FetchOutcome, remote_helper::Error> {
+        self.wants.extend_from_slice(wants);
+        Ok(FetchOutcome::default())
+    }
+
+    fn push(&mut self, specs: &[PushSpec]) -> Result<Vec<PushStatus>, remote_helper::Error> {
+        self.pushes.extend_from_slice(specs);
+        Ok(specs
+            .iter()
+            .map(|spec| PushStatus::Error {
+                dst: spec.dst.clone(),
+                reason: Some("rejected".into()),
+            })
+            .collect())
+    }
+
+    fn connect(&mut self, service: Service, kind: ConnectKind) -> Result<Option<Connection>, remote_helper::Error> {
+        self.connects.push((service, kind));
+        Ok(Some(Connection {
+            reader: Box::new(std::io::Cursor::new(self.server_response.clone())),
+            writer: Box::new(self.requests.clone()),
+        }))
+    }
+}
+
+fn list() -> List {
+    List::from_lines(
+        [
+            "@refs/heads/main HEAD".into(),
+            format!("{ID} refs/heads/main"),
+            "? refs/heads/unknown".into(),
+            ":object-format sha1".into(),
+        ]
+        .iter()
+        .map(|line| line.as_bytes().as_bstr()),
+    )
+    .expect("valid")
+}
+
+fn packet_lines(lines: &[&str]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for line in lines {
+        match *line {
+            "0000" => gix_packetline::blocking_io::encode::flush_to_write(&mut out),
+            "0002" => gix_packetline::blocking_io::encode::response_end_to_write(&mut out),
+            line => gix_packetline::blocking_io::encode::data_to_write(format!("{line}\n").as_bytes(), &mut out),
+        }
+        .expect("writing to memory works");
+    }
+    out
+}
+
+#[test]
+fn capabilities_from_lines() -> crate::Result {
+    let caps = Capabilities::from_lines(
+        ["fetch", "*push", "option", "refspec refs/heads/*:refs/mock/*", "import"]
+            .map(|line| line.as_bytes().as_bstr()),
+    )?;
+    assert_eq!(
+        caps,
+        Capabilities {
+            fetch: true,
+            push: true,
+            option: true,
+            refspecs: vec!["refs/heads/*:refs/mock/*".into()],
+            other: vec!["import".into()],
+            ..Default::default()
+        }
+    );
+
+    let err = Capabilities::from_lines(["*export"].map(|line| line.as_bytes().as_bstr())).unwrap_err();
+    assert!(
+        matches!(&err, remote_helper::Error::MandatoryCapability { name } if name == "export"),
+        "unknown mandatory capabilities are an error"
+    );
+    Ok(())
+}
+
+#[test]
+fn list_from_lines() {
+    assert_eq!(
+        list(),
+        List {
+            refs: vec![
+                Ref {
+                    name: "HEAD".into(),
+                    target: RefTarget::Symbolic("refs/heads/main".into()),
+                    attributes: vec![],
+                },
+                Ref {
+                    name: "refs/heads/main".into(),
+                    target: RefTarget::Id(ID.into()),
+                    attributes: vec![],
+                },
+                Ref {
+                    name: "refs/heads/unknown".into(),
+                    target: RefTarget::Unknown,
+                    attributes: vec![],
+                },
+            ],
+            object_format: Some("sha1".into()),
+        }
+    );
+}
+
+#[test]
+fn fetch_is_emulated_with_list_and_fetch() -> crate::Result {
+    let mut transport = remote_helper::Transport::new(
+        Mock {
+            capabilities: Capabilities {
+                fetch: true,
+                option: true,
+                object_format: true,
+                ..Default::default()
+            },
+            list: list(),
+            ..Default::default()
+        },
+        url(),
+        Protocol::V2,
+        false,
+    );
+    let mut res = transport.handshake(Service::UploadPack, &[])?;
+    assert_eq!(res.actual_protocol, Protocol::V1, "emulation is only possible with V1");
+    assert_eq!(
+        res.capabilities
+            .iter()
+            .map(|c| (c.name().to_owned(), c.value().map(ToOwned::to_owned)))
+            .collect::<Vec<_>>(),
+        [
+            ("symref".into(), Some("HEAD:refs/heads/main".into())),
+            ("multi_ack_detailed".into(), None),
+            ("side-band-64k".into(), None),
+            ("object-format".into(), Some("sha1".into()))
+        ]
+    );
+    let refs = res
+        .refs
+        .as_mut()
+        .expect("v1 provides refs")
+        .lines()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        refs,
+        [format!("{ID} HEAD"), format!("{ID} refs/heads/main")],
+        "refs with unknown values are skipped"
+    );
+    drop(res);
+    assert!(!transport.connection_persists_across_multiple_requests());
+
+    let mut writer = transport.request(
+        client::WriteMode::OneLfTerminatedLinePerWriteCall,
+        client::MessageKind::Flush,
+        false,
+    )?;
+    writer.write_all(format!("want {ID} ofs-delta").as_bytes())?;
+    writer.write_message(client::MessageKind::Flush)?;
+    let mut reader = writer.into_read()?;
+    let mut line = String::new();
+    reader.readline_str(&mut line)?;
+    assert_eq!(line, "NAK\n", "negotiation isn't needed, but is answered");
+    drop(reader);
+    assert!(
+        transport.helper_mut().wants.is_empty(),
+        "nothing is fetched before 'done'"
+    );
+
+    let mut writer = transport.request(
+        client::WriteMode::OneLfTerminatedLinePerWriteCall,
+        client::MessageKind::Text(b"done"),
+        false,
+    )?;
+    writer.write_all(format!("want {ID} side-band-64k").as_bytes())?;
+    writer.write_message(client::MessageKind::Flush)?;
+    let mut reader = writer.into_read()?;
+    let mut line = String::new();
+    reader.readline_str(&mut line)?;
+    assert_eq!(line, "NAK\n");
+    reader.set_progress_handler(Some(Box::new(|_is_err, _data| std::ops::ControlFlow::Continue(()))));
+    let entries = gix_pack::data::input::BytesToEntriesIter::new_from_header(
+        reader,
+        gix_pack::data::input::Mode::Verify,
+        gix_pack::data::input::EntryDataMode::Crc32,
+        gix_hash::Kind::Sha1,
+    )?;
+    assert_eq!(
+        entries.count(),
+        0,
+        "the helper fetched the objects, so the pack is empty"
+    );
+
+    let helper = transport.helper_mut();
+    assert_eq!(
+        helper.wants,
+        [Want {
+            id: ID.into(),
+            name: "HEAD".into()
+        }]
+    );
+    assert_eq!(helper.options, [("object-format".to_string(), "true".to_string())]);
+    assert!(helper.connects.is_empty());
+    Ok(())
+}
+
+#[test]
+fn push_is_emulated_with_list_and_push() -> crate::Result {
+    let mut transport = remote_helper::Transport::new(
+        Mock {
+            capabilities: Capabilities {
+                push: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        url(),
+        Protocol::V1,
+        false,
+    );
+    let mut res = transport.handshake(Service::ReceivePack, &[])?;
+    assert_eq!(
+        res.capabilities.iter().map(|c| c.name().to_owned()).collect::<Vec<_>>(),
+        ["report-status", "delete-refs"]
+    );
+    let refs = res
+        .refs
+        .as_mut()
+        .expect("v1 provides refs")
+        .lines()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        refs,
+        [format!("{} capabilities^{{}}", "0".repeat(40))],
+        "the remote is empty, which is indicated like `git` does"
+    );
+    drop(res);
+
+    let mut writer = transport.request(client::WriteMode::Binary, client::MessageKind::Flush, false)?;
+    writer.write_all(format!("{} {ID} refs/heads/main\0report-status", "0".repeat(40)).as_bytes())?;
+    writer.write_all(format!("{ID} {} refs/heads/old", "0".repeat(40)).as_bytes())?;
+    writer.write_message(client::MessageKind::Flush)?;
+    let (write, reader) = writer.into_parts();
+    drop(write);
+    let lines = reader.lines().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        lines,
+        ["unpack ok", "ng refs/heads/main rejected", "ng refs/heads/old rejected"]
+    );
+    assert_eq!(
+        transport.helper_mut().pushes,
+        [
+            PushSpec {
+                src: Some(ID.into()),
+                dst: "refs/heads/main".into(),
+                force: true,
+            },
+            PushSpec {
+                src: None,
+                dst: "refs/heads/old".into(),
+                force: true,
+            }
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn connect_is_preferred_to_emulation() -> crate::Result {
+    let mut transport = remote_helper::Transport::new(
+        Mock {
+            capabilities: Capabilities {
+                connect: true,
+                fetch: true,
+                ..Default::default()
+            },
+            server_response: crate::fixture_bytes("v1/clone.response"),
+            ..Default::default()
+        },
+        url(),
+        Protocol::V1,
+        false,
+    );
+    let res = transport.handshake(Service::UploadPack, &[])?;
+    assert_eq!(res.actual_protocol, Protocol::V1);
+    assert_eq!(
+        res.capabilities
+            .capability("symref")
+            .and_then(|c| c.value().map(ToOwned::to_owned)),
+        Some("HEAD:refs/heads/master".into())
+    );
+    drop(res);
+    assert!(transport.connection_persists_across_multiple_requests());
+    assert_eq!(
+        transport.helper_mut().connects,
+        [(Service::UploadPack, ConnectKind::Connect)]
+    );
+    Ok(())
+}
+
+#[test]
+fn stateless_connect_for_v2_skips_response_ends() -> crate::Result {
+    let requests = Shared::default();
+    let mut transport = remote_helper::Transport::new(
+        Mock {
+            capabilities: Capabilities {
+                connect: true,
+                stateless_connect: true,
+                ..Default::default()
+            },
+            server_response: packet_lines(&[
+                "version 2",
+                "ls-refs",
+                "fetch=shallow",
+                "0000",
+                &format!("{ID} HEAD"),
+                &format!("{ID} refs/heads/main"),
+                "0000",
+                "0002",
+                &format!("{ID} refs/heads/other"),
+                "0000",
+                "0002",
+            ]),
+            requests: requests.clone(),
+            ..Default::default()
+        },
+        url(),
+        Protocol::V2,
+        false,
+    );
+    let res = transport.handshake(Service::UploadPack, &[])?;
+    assert_eq!(res.actual_protocol, Protocol::V2);
+    assert!(res.capabilities.capability("ls-refs").is_some());
+    drop(res);
+    assert!(!transport.connection_persists_across_multiple_requests());
+
+    let mut reader = transport.invoke(
+        "ls-refs",
+        None::<(&str, Option<&str>)>.into_iter(),
+        None::<std::iter::Empty<BString>>,
+        false,
+    )?;
+    let mut line = String::new();
+    reader.readline_str(&mut line)?;
+    assert_eq!(line, format!("{ID} HEAD\n"), "the response isn't consumed entirely");
+    drop(reader);
+
+    let reader = transport.invoke(
+        "ls-refs",
+        None::<(&str, Option<&str>)>.into_iter(),
+        None::<std::iter::Empty<BString>>,
+        false,
+    )?;
+    let lines = reader.lines().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        lines,
+        [format!("{ID} refs/heads/other")],
+        "the remainder of the previous response, and its end, are skipped"
+    );
+    assert_eq!(
+        transport.helper_mut().connects,
+        [(Service::UploadPack, ConnectKind::StatelessConnect)]
+    );
+    assert_eq!(requests.data(), "0014command=ls-refs\n00000014command=ls-refs\n0000");
+    Ok(())
+}
+
+#[test]
+fn unsupported_service() {
+    let mut transport = remote_helper::Transport::new(
+        Mock {
+            capabilities: Capabilities {
+                fetch: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        url(),
+        Protocol::V1,
+        false,
+    );
+    let err = transport
+        .handshake(Service::ReceivePack, &[])
+        .err()
+        .expect("push isn't possible");
+    assert!(matches!(
+        err,
+        client::Error::RemoteHelper(err) if matches!(*err, remote_helper::Error::UnsupportedService { service: Service::ReceivePack })
+    ));
+}
+
+#[cfg(unix)]
+mod process {
+    use std::io::Write;
+
+    use gix_transport::{
+        Protocol, Service,
+        client::{
+            self,
+            blocking_io::{Transport, remote_helper},
+        },
+    };
+
+    use super::ID;
+
+    fn helper_script(dir: &std::path::Path, script: &str) -> std::io::Result<std::path::PathBuf> {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("git-remote-mock");
+        std::fs::write(&path, script)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        Ok(path)
+    }
+
+    #[test]
+    fn list_and_fetch() -> crate::Result {
+        let dir = gix_testtools::tempfile::TempDir::new()?;
+        let program = helper_script(
+            dir.path(),
+            &format!(
+                r#"#!/bin/sh
+log="$GIT_DIR/helper.log"
+echo "$1 $2" > "$log"
+fetching=
+while read -r line; do
+  echo "$line" >> "$log"
+  case "$line" in
+    capabilities) printf 'fetch\noption\nrefspec refs/heads/*:refs/mock/*\n\n' ;;
+    "option "*) echo unsupported ;;
+    list) printf '@refs/heads/main HEAD\n{ID} refs/heads/main\n\n' ;;
+    "fetch "*) fetching=1 ;;
+    "")
+      if [ -n "$fetching" ]; then
+        touch "$GIT_DIR/fetch.keep"
+        printf 'lock %s\nconnectivity-ok\n\n' "$GIT_DIR/fetch.keep"
+        fetching=
+      else
+        exit 0
+      fi
+      ;;
+  esac
+done
+"#
+            ),
+        )?;
+        let git_dir = dir.path().join("repo.git");
+        std::fs::create_dir(&git_dir)?;
+
+        let mut transport = remote_helper::Transport::new(
+            remote_helper::Process::new(
+                program,
+                &super::url(),
+                Protocol::V2,
+                remote_helper::connect::Options {
+                    git_dir: Some(git_dir.clone()),
+                    remote_name: Some("origin".into()),
+                },
+            ),
+            super::url(),
+            Protocol::V2,
+            false,
+        );
+        assert_eq!(
+            transport.option("verbosity", "1")?,
+            remote_helper::OptionResponse::Unsupported
+        );
+        let res = transport.handshake(Service::UploadPack, &[])?;
+        assert_eq!(res.actual_protocol, Protocol::V1);
+        drop(res);
+
+        let mut writer = transport.request(
+            client::WriteMode::OneLfTerminatedLinePerWriteCall,
+            client::MessageKind::Text(b"done"),
+            false,
+        )?;
+        writer.write_all(format!("want {ID}").as_bytes())?;
+        writer.write_message(client::MessageKind::Flush)?;
+        let mut reader = writer.into_read()?;
+        let mut line = String::new();
+        reader.readline_str(&mut line)?;
+        assert_eq!(line, "NAK\n");
+        drop(reader);
+        assert!(git_dir.join("fetch.keep").is_file(), "the lock file is kept for now");
+
+        drop(transport);
+        assert!(
+            !git_dir.join("fetch.keep").exists(),
+            "lock files are removed when the transport is done"
+        );
+        let log = std::fs::read_to_string(git_dir.join("helper.log"))?;
+        assert_eq!(
+            log.lines().collect::<Vec<_>>(),
+            [
+                "origin mock://example.com/repo.git",
+                "capabilities",
+                "option verbosity 1",
+                "list",
+                &format!("fetch {ID} HEAD"),
+                "",
+                "",
+            ],
+            "the helper is told to exit with a blank line or by closing its input"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn connect_to_upload_pack() -> crate::Result {
+        let dir = gix_testtools::tempfile::TempDir::new()?;
+        let repo = dir.path().join("remote.git");
+        let status = std::process::Command::new("git")
+            .args(["init", "--bare", "--quiet"])
+            .arg(&repo)
+            .status()?;
+        assert!(status.success());
+        let program = helper_script(
+            dir.path(),
+            &format!(
+                r#"#!/bin/sh
+while read -r line; do
+  case "$line" in
+    capabilities) printf 'connect\n\n' ;;
+    "connect git-upload-pack") echo; exec git upload-pack '{}' ;;
+    *) exit 1 ;;
+  esac
+done
+"#,
+                repo.display()
+            ),
+        )?;
+
+        let mut transport = remote_helper::Transport::new(
+            remote_helper::Process::new(program, &super::url(), Protocol::V2, Default::default()),
+            super::url(),
+            Protocol::V2,
+            false,
+        );
+        let res = transport.handshake(Service::UploadPack, &[])?;
+        assert_eq!(
+            res.actual_protocol,
+            Protocol::V2,
+            "the desired protocol is passed to the helper, and thus to the service"
+        );
+        assert!(res.capabilities.capability("ls-refs").is_some());
+        Ok(())
+    }
+}
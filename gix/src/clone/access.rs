@@ -35,6 +35,16 @@ impl PrepareFetch {
         self
     }
 
+    /// Make this clone a partial one by asking the remote to omit objects matching the filter `spec`, like `blob:none`
+    /// or `blob:limit=1m`.
+    ///
+    /// The remote is configured as promisor remote with `remote.<name>.promisor` and `remote.<name>.partialCloneFilter`
+    /// along with `extensions.partialClone`, so that missing objects are fetched from it on demand.
+    pub fn with_filter(mut self, spec: impl Into<String>) -> Self {
+        self.filter = Some(spec.into());
+        self
+    }
+
     /// Apply the given configuration `values` right before readying the actual fetch from the remote.
     /// The configuration is marked with [source API](gix_config::Source::Api), and will not be written back, it's
     /// retained only in memory.
@@ -35,6 +35,11 @@ pub mod main_worktree {
         FindHead(#[from] crate::reference::find::existing::Error),
         #[error("The HEAD reference could not be located")]
         PeelHeadToId(#[from] crate::head::peel::Error),
+        #[error("Could not obtain the objects to check out from the promisor remote")]
+        FetchMissing(#[source] gix_odb::store::find::Error),
+        #[cfg(feature = "lfs-http-client-reqwest")]
+        #[error(transparent)]
+        DownloadLfsObjects(#[from] crate::repository::filter::download_missing_lfs_objects::Error),
     }
 
     /// The progress ids used in [`PrepareCheckout::main_worktree()`].
@@ -117,6 +122,18 @@ pub mod main_worktree {
                     source: err,
                 })?;
             let mut index = gix_index::File::from_state(index, repo.index_path());
+            // In partial clones, obtain all missing blobs at once instead of one at a time during checkout.
+            repo.fetch_missing_objects(
+                index
+                    .entries()
+                    .iter()
+                    .filter(|entry| !entry.mode.is_submodule())
+                    .map(|entry| entry.id),
+            )
+            .map_err(Error::FetchMissing)?;
+            // Likewise, obtain the content of files tracked with LFS in batches instead of letting the driver fetch them one by one.
+            #[cfg(feature = "lfs-http-client-reqwest")]
+            repo.download_missing_lfs_objects(&index)?;
 
             let mut opts = repo.checkout_options(gix_worktree::stack::state::attributes::Source::IdMapping)?;
             opts.destination_is_initially_empty = true;
@@ -0,0 +1,212 @@
+use std::{borrow::Cow, path::Path, sync::atomic::AtomicBool};
+
+use super::{Error, util};
+use crate::{
+    Repository,
+    bstr::{BStr, BString, ByteSlice, ByteVec},
+    clone::PrepareFetch,
+    remote,
+    remote::fetch::RefLogMessage,
+};
+
+/// Return the path to the bundle file `url` points to, if it's a local path to a bundle.
+pub(super) fn path(url: &gix_url::Url) -> Option<std::path::PathBuf> {
+    if url.scheme != gix_url::Scheme::File {
+        return None;
+    }
+    let path = gix_path::from_bstr(url.path.as_bstr()).into_owned();
+    gix_bundle::File::is_bundle(&path).then_some(path)
+}
+
+impl PrepareFetch {
+    /// Like [`fetch_only()`](Self::fetch_only()), but obtain references and objects from the bundle at `bundle_path`
+    /// instead of connecting to a remote, similar to `git clone <bundle>`.
+    ///
+    /// Shallow clones aren't supported, so the configured shallow settings are ignored.
+    pub(super) fn fetch_from_bundle(
+        &mut self,
+        mut repo: Repository,
+        remote_name: BString,
+        bundle_path: &Path,
+        progress: &mut dyn crate::DynNestedProgress,
+        should_interrupt: &AtomicBool,
+    ) -> Result<(Repository, remote::fetch::Outcome), Error> {
+        let bundle = gix_bundle::File::at(bundle_path)?;
+
+        let mut remote = repo.remote_at(self.url.clone())?;
+        if remote.fetch_specs.is_empty() {
+            remote = remote
+                .with_refspecs(
+                    Some(format!("+refs/heads/*:refs/remotes/{remote_name}/*").as_str()),
+                    remote::Direction::Fetch,
+                )
+                .expect("valid static spec");
+        }
+        let mut clone_fetch_tags = None;
+        if let Some(f) = self.configure_remote.as_mut() {
+            remote = f(remote).map_err(Error::RemoteConfiguration)?;
+        } else {
+            clone_fetch_tags = remote::fetch::Tags::All.into();
+        }
+        #[cfg_attr(not(feature = "sha256"), allow(unused_mut))]
+        let mut config = Some(util::append_remote_to_local_config_file(
+            &mut remote,
+            remote_name.clone(),
+            None,
+        )?);
+        if let Some(fetch_tags) = clone_fetch_tags {
+            remote = remote.with_fetch_tags(fetch_tags);
+        }
+        let fetch_refspecs = remote.fetch_specs.clone();
+        let fetch_tags = remote.fetch_tags;
+        drop(remote);
+
+        let object_hash = bundle.header.object_hash;
+        // Only reachable with sha256, otherwise bundles of other formats can't be opened.
+        #[cfg(feature = "sha256")]
+        {
+            if object_hash != repo.object_hash() {
+                util::adopt_object_hash(&mut repo, object_hash)?;
+                config = None;
+            }
+        }
+        bundle.header.verify_prerequisites(&repo.objects)?;
+
+        let mut extra_refspecs = self.fetch_options.extra_refspecs.clone();
+        let head_refspec = gix_refspec::parse(
+            format!("HEAD:refs/remotes/{remote_name}/HEAD").as_str().into(),
+            gix_refspec::parse::Operation::Fetch,
+        )
+        .expect("valid")
+        .to_owned();
+        if !extra_refspecs.contains(&head_refspec) {
+            extra_refspecs.push(head_refspec);
+        }
+        if let Some(tag_spec) = fetch_tags.to_refspec().map(|spec| spec.to_owned()) {
+            if !extra_refspecs.contains(&tag_spec) {
+                extra_refspecs.push(tag_spec);
+            }
+        }
+        let default_branch = repo
+            .config
+            .resolved
+            .string(crate::config::tree::Init::DEFAULT_BRANCH)
+            .unwrap_or_else(|| Cow::Borrowed(crate::init::DEFAULT_BRANCH_NAME.into()));
+        let remote_refs = remote_refs(&bundle.header, default_branch.as_ref());
+        let capabilities = gix_protocol::transport::client::Capabilities::from_bytes(
+            format!("\0object-format={object_hash}").as_bytes(),
+        )
+        .expect("valid capabilities")
+        .0;
+        let ref_map = gix_protocol::fetch::RefMap::from_refs(
+            remote_refs.clone(),
+            &capabilities,
+            gix_protocol::fetch::refmap::init::Context {
+                fetch_refspecs: fetch_refspecs.clone(),
+                extra_refspecs,
+            },
+        )
+        .map_err(remote::ref_map::Error::InitRefMap)?;
+        if let Some(ref_name) = &self.ref_name {
+            util::find_custom_refname(&ref_map, ref_name)?;
+        }
+
+        let reflog_message = {
+            let mut b = self.url.to_bstring();
+            b.insert_str(0, "clone: from ");
+            b
+        };
+        let fetch_err = |err: remote::fetch::Error| Error::Fetch(err);
+        let mut write_pack_bundle = bundle.write_pack_to_directory(
+            &repo.objects.store_ref().path().join("pack"),
+            progress,
+            should_interrupt,
+            repo.objects.clone(),
+            gix_bundle::unpack::Options {
+                thread_limit: remote::fetch::config::index_threads(&repo).map_err(|err| fetch_err(err.into()))?,
+                index_version: remote::fetch::config::pack_index_version(&repo).map_err(fetch_err)?,
+            },
+        )?;
+        let update_refs = remote::fetch::refs::update(
+            &repo,
+            RefLogMessage::Override {
+                message: reflog_message.clone(),
+            },
+            &ref_map.mappings,
+            &fetch_refspecs,
+            &ref_map.extra_refspecs,
+            fetch_tags,
+            remote::fetch::DryRun::No,
+            remote::fetch::WritePackedRefs::Only,
+        )
+        .map_err(|err| fetch_err(err.into()))?;
+        if let Some(path) = write_pack_bundle.keep_path.take() {
+            std::fs::remove_file(&path)
+                .map_err(|source| fetch_err(remote::fetch::Error::RemovePackKeepFile { path, source }))?;
+        }
+
+        if let Some(config) = config {
+            util::append_config_to_repo_config(&mut repo, config);
+        }
+        util::update_head(
+            &mut repo,
+            &ref_map,
+            reflog_message.as_ref(),
+            remote_name.as_ref(),
+            self.ref_name.as_ref(),
+        )?;
+
+        let outcome = remote::fetch::Outcome {
+            ref_map,
+            handshake: gix_protocol::Handshake {
+                server_protocol_version: gix_protocol::transport::Protocol::V1,
+                refs: Some(remote_refs),
+                v1_shallow_updates: None,
+                capabilities,
+            },
+            status: remote::fetch::Status::Change {
+                write_pack_bundle,
+                update_refs,
+                negotiate: Default::default(),
+            },
+        };
+        drop(self.repo.take().expect("still present"));
+        Ok((repo, outcome))
+    }
+}
+
+/// Turn the references of a bundle into the ones a remote would advertise.
+///
+/// Bundles only store the object `HEAD` points to, so like `git`, we assume it's a symbolic reference to
+/// the `default_branch` if it points to the same object, or to the first other branch that does.
+fn remote_refs(header: &gix_bundle::Header, default_branch: &BStr) -> Vec<gix_protocol::handshake::Ref> {
+    use gix_protocol::handshake::Ref;
+    let branch_for = |id: &gix_hash::oid| {
+        let branches = header
+            .refs
+            .iter()
+            .filter(|r| r.id == id && r.name.as_bstr().starts_with(b"refs/heads/"));
+        branches
+            .clone()
+            .find(|r| r.name.as_bstr().strip_prefix(b"refs/heads/") == Some(default_branch.as_bytes()))
+            .or_else(|| branches.clone().next())
+    };
+    header
+        .refs
+        .iter()
+        .map(
+            |r| match (r.name.as_bstr() == "HEAD").then(|| branch_for(&r.id)).flatten() {
+                Some(branch) => Ref::Symbolic {
+                    full_ref_name: r.name.as_bstr().into(),
+                    target: branch.name.as_bstr().into(),
+                    tag: None,
+                    object: r.id,
+                },
+                None => Ref::Direct {
+                    full_ref_name: r.name.as_bstr().into(),
+                    object: r.id,
+                },
+            },
+        )
+        .collect()
+}
@@ -0,0 +1,396 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use gix_protocol::bundle_uri::{Heuristic, List, Mode};
+
+use crate::{
+    Repository,
+    bstr::{BStr, BString, ByteSlice, ByteVec},
+    config::{
+        cache::util::ApplyLeniency,
+        tree::{Bundle, Transfer},
+    },
+};
+
+/// The maximum amount of bundle lists that may refer to each other, matching `git`.
+const MAX_LIST_DEPTH: usize = 4;
+
+/// The error returned when downloading or applying a single bundle, which isn't fatal to the clone.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Url(#[from] gix_url::parse::Error),
+    #[error("Cannot download bundles from {url} as the URL scheme isn't supported")]
+    UnsupportedScheme { url: BString },
+    #[cfg(any(
+        feature = "blocking-http-transport-curl",
+        feature = "blocking-http-transport-reqwest"
+    ))]
+    #[error(transparent)]
+    Http(#[from] gix_transport::client::blocking_io::http::Error),
+    #[cfg(any(
+        feature = "blocking-http-transport-curl",
+        feature = "blocking-http-transport-reqwest"
+    ))]
+    #[error(transparent)]
+    TransportOptions(#[from] crate::config::transport::Error),
+    #[cfg(any(
+        feature = "blocking-http-transport-curl",
+        feature = "blocking-http-transport-reqwest"
+    ))]
+    #[error("Could not configure the HTTP client")]
+    ConfigureHttp(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("Could not read the bundle list")]
+    ListConfig(#[from] gix_config::file::init::from_paths::Error),
+    #[error("The bundle list version {version:?} is unsupported")]
+    ListVersion { version: Option<u64> },
+    #[error(transparent)]
+    ConfigValue(#[from] crate::config::key::GenericErrorWithValue),
+    #[error(transparent)]
+    ConfigInteger(#[from] crate::config::unsigned_integer::Error),
+    #[error("The bundle {id:?} doesn't have a URI")]
+    ListMissingUri { id: BString },
+    #[error("Bundle lists may only refer to other bundle lists {MAX_LIST_DEPTH} levels deep")]
+    ListTooDeep,
+    #[error(transparent)]
+    Open(#[from] gix_bundle::open::Error),
+    #[error("The bundle uses {actual} objects, but the repository uses {expected}")]
+    ObjectHash {
+        actual: gix_hash::Kind,
+        expected: gix_hash::Kind,
+    },
+    #[error(transparent)]
+    Prerequisites(#[from] gix_bundle::verify::Error),
+    #[error(transparent)]
+    Unpack(#[from] gix_bundle::unpack::Error),
+    #[error(transparent)]
+    Fetch(#[from] crate::remote::fetch::Error),
+    #[error(transparent)]
+    Reference(#[from] crate::reference::edit::Error),
+}
+
+/// Return `true` if `transfer.bundleURI` allows to download the bundles advertised by the remote.
+pub(super) fn is_enabled(repo: &Repository) -> Result<bool, crate::config::boolean::Error> {
+    Ok(repo
+        .config
+        .resolved
+        .boolean_filter(Transfer::BUNDLE_URI, &mut repo.filter_config_section())
+        .map(|value| Transfer::BUNDLE_URI.enrich_error(value))
+        .transpose()
+        .with_leniency(repo.config.lenient_config)?
+        .unwrap_or(false))
+}
+
+/// A bundle that is available locally.
+struct Downloaded {
+    /// The location of the bundle on disk.
+    path: PathBuf,
+    /// The URL the bundle was obtained from.
+    url: BString,
+    /// The file the bundle was downloaded into, which is deleted when dropped, or `None` if the bundle is a local file.
+    _tempfile: Option<gix_tempfile::Handle<gix_tempfile::handle::Closed>>,
+}
+
+/// Download all bundles in `list`, whose relative URIs are resolved against `remote_url`, and write their packs into `repo`
+/// along with `refs/bundles/*` references pointing to their branches, so a subsequent fetch only needs to obtain what's missing.
+///
+/// Bundles that can't be downloaded or applied are reported through `progress`, but otherwise ignored.
+pub(super) fn fetch(
+    repo: &Repository,
+    list: List,
+    remote_url: &gix_url::Url,
+    progress: &mut dyn crate::DynNestedProgress,
+    should_interrupt: &AtomicBool,
+) {
+    let mut bundles = Vec::new();
+    download_list(
+        repo,
+        list,
+        remote_url,
+        false,
+        0,
+        &mut bundles,
+        progress,
+        should_interrupt,
+    );
+
+    // Later bundles may depend on objects from earlier ones, so apply all bundles whose prerequisites are met
+    // until there is no more progress.
+    loop {
+        let num_bundles = bundles.len();
+        let mut failed = Vec::new();
+        for bundle in bundles {
+            if should_interrupt.load(Ordering::Relaxed) {
+                return;
+            }
+            match unbundle(repo, &bundle, progress, should_interrupt) {
+                Ok(()) => {}
+                Err(err) => failed.push((bundle, err)),
+            }
+        }
+        if failed.len() == num_bundles {
+            for (bundle, err) in failed {
+                progress.fail(format!("Could not apply bundle from {}: {err}", bundle.url));
+            }
+            break;
+        }
+        bundles = failed.into_iter().map(|(bundle, _err)| bundle).collect();
+    }
+}
+
+/// Download the bundles of `list` into `out`, descending into bundle lists up to the maximum depth.
+#[allow(clippy::too_many_arguments)]
+fn download_list(
+    repo: &Repository,
+    mut list: List,
+    base_url: &gix_url::Url,
+    base_is_list: bool,
+    depth: usize,
+    out: &mut Vec<Downloaded>,
+    progress: &mut dyn crate::DynNestedProgress,
+    should_interrupt: &AtomicBool,
+) -> bool {
+    if list.heuristic == Some(Heuristic::CreationToken) {
+        list.bundles
+            .sort_by_key(|bundle| (bundle.creation_token.is_none(), bundle.creation_token));
+    }
+    let mut downloaded_any = false;
+    for bundle in list.bundles {
+        if should_interrupt.load(Ordering::Relaxed) {
+            break;
+        }
+        let res = resolve(base_url, base_is_list, bundle.uri.as_ref())
+            .map_err(Error::from)
+            .and_then(|url| download(repo, &url).map(|(path, tempfile)| (url, path, tempfile)))
+            .and_then(|(url, path, tempfile)| {
+                if gix_bundle::File::is_bundle(&path) {
+                    out.push(Downloaded {
+                        path,
+                        url: url.to_bstring(),
+                        _tempfile: tempfile,
+                    });
+                    return Ok(true);
+                }
+                if depth + 1 >= MAX_LIST_DEPTH {
+                    return Err(Error::ListTooDeep);
+                }
+                let list = list_from_config(&path)?;
+                Ok(download_list(
+                    repo,
+                    list,
+                    &url,
+                    true,
+                    depth + 1,
+                    out,
+                    progress,
+                    should_interrupt,
+                ))
+            });
+        match res {
+            Ok(downloaded) => {
+                downloaded_any |= downloaded;
+                if downloaded && list.mode == Mode::Any {
+                    break;
+                }
+            }
+            Err(err) => progress.fail(format!("Could not download bundle {:?}: {err}", bundle.uri)),
+        }
+    }
+    downloaded_any
+}
+
+/// Resolve `uri` against `base`, which is the location of a bundle list if `base_is_list` is `true`, or the location
+/// of the remote repository otherwise.
+///
+/// Relative URIs are resolved against the directory containing a bundle list, but against the remote as if it was
+/// a directory.
+fn resolve(base: &gix_url::Url, base_is_list: bool, uri: &BStr) -> Result<gix_url::Url, gix_url::parse::Error> {
+    if uri.contains_str("://") {
+        return gix_url::parse(uri);
+    }
+    let mut components: Vec<&[u8]> = if uri.starts_with(b"/") {
+        Vec::new()
+    } else {
+        base.path.split_str("/").filter(|c| !c.is_empty()).collect()
+    };
+    if base_is_list {
+        components.pop();
+    }
+    for component in uri.split_str("/") {
+        match component {
+            b"" | b"." => {}
+            b".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+    let mut path = BString::default();
+    if base.path.starts_with(b"/") || uri.starts_with(b"/") {
+        path.push_byte(b'/');
+    }
+    path.push_str(components.join(b"/".as_slice()));
+    let mut url = base.clone();
+    url.path = path;
+    Ok(url)
+}
+
+/// Make the file `url` points to available locally, returning its path along with the tempfile it was downloaded into.
+#[cfg_attr(
+    not(any(
+        feature = "blocking-http-transport-curl",
+        feature = "blocking-http-transport-reqwest"
+    )),
+    allow(unused_variables)
+)]
+fn download(
+    repo: &Repository,
+    url: &gix_url::Url,
+) -> Result<(PathBuf, Option<gix_tempfile::Handle<gix_tempfile::handle::Closed>>), Error> {
+    match url.scheme {
+        gix_url::Scheme::File => Ok((gix_path::from_bstr(url.path.as_bstr()).into_owned(), None)),
+        #[cfg(any(
+            feature = "blocking-http-transport-curl",
+            feature = "blocking-http-transport-reqwest"
+        ))]
+        gix_url::Scheme::Http | gix_url::Scheme::Https => {
+            let mut file = gix_tempfile::new(
+                repo.git_dir(),
+                gix_tempfile::ContainingDirectory::Exists,
+                gix_tempfile::AutoRemove::Tempfile,
+            )?;
+            download_http(repo, url, &mut file)?;
+            let path = file.with_mut(|file| file.path().to_owned())?;
+            Ok((path, Some(file.close()?)))
+        }
+        _ => Err(Error::UnsupportedScheme { url: url.to_bstring() }),
+    }
+}
+
+#[cfg(any(
+    feature = "blocking-http-transport-curl",
+    feature = "blocking-http-transport-reqwest"
+))]
+fn download_http(repo: &Repository, url: &gix_url::Url, out: &mut dyn std::io::Write) -> Result<(), Error> {
+    use gix_transport::client::blocking_io::http::{self, Http};
+
+    #[cfg(feature = "blocking-http-transport-curl")]
+    let mut client = http::curl::Curl::default();
+    #[cfg(all(
+        feature = "blocking-http-transport-reqwest",
+        not(feature = "blocking-http-transport-curl")
+    ))]
+    let mut client = http::reqwest::Remote::default();
+
+    let url = url.to_bstring().to_string();
+    if let Some(options) = repo.transport_options(url.as_str(), None)? {
+        client.configure(options.as_ref()).map_err(Error::ConfigureHttp)?;
+    }
+    let http::GetResponse { mut headers, mut body } = client.get(&url, &url, std::iter::empty::<&str>())?;
+    std::io::copy(&mut headers, &mut std::io::sink())?;
+    std::io::copy(&mut body, out)?;
+    Ok(())
+}
+
+/// Parse the bundle list stored in git-config format at `path`.
+fn list_from_config(path: &Path) -> Result<List, Error> {
+    let config = gix_config::File::from_path_no_includes(path.to_owned(), gix_config::Source::Api)?;
+    let version = config
+        .integer(Bundle::VERSION)
+        .map(|value| Bundle::VERSION.try_into_u64(value))
+        .transpose()?;
+    if version != Some(1) {
+        return Err(Error::ListVersion { version });
+    }
+    let mode = config
+        .string(Bundle::MODE)
+        .map(|value| Bundle::MODE.try_into_mode(value))
+        .transpose()?
+        .unwrap_or_default();
+    // Like `git`, ignore heuristics we don't know.
+    let heuristic = config
+        .string(Bundle::HEURISTIC)
+        .and_then(|value| Bundle::HEURISTIC.try_into_heuristic(value).ok());
+
+    let mut bundles = Vec::<gix_protocol::bundle_uri::Bundle>::new();
+    for section in config.sections_by_name("bundle").into_iter().flatten() {
+        let Some(id) = section.header().subsection_name() else {
+            continue;
+        };
+        if bundles.iter().any(|bundle| bundle.id == id) {
+            continue;
+        }
+        let uri = config
+            .string_by("bundle", Some(id), Bundle::URI.name)
+            .ok_or_else(|| Error::ListMissingUri { id: id.to_owned() })?;
+        let creation_token = config
+            .integer_by("bundle", Some(id), Bundle::CREATION_TOKEN.name)
+            .map(|value| Bundle::CREATION_TOKEN.try_into_u64(value))
+            .transpose()?;
+        bundles.push(gix_protocol::bundle_uri::Bundle {
+            id: id.to_owned(),
+            uri: uri.into_owned(),
+            creation_token,
+        });
+    }
+    Ok(List {
+        mode,
+        heuristic,
+        bundles,
+    })
+}
+
+/// Write the pack of `bundle` into `repo` and point `refs/bundles/*` to the tips of its branches.
+fn unbundle(
+    repo: &Repository,
+    bundle: &Downloaded,
+    progress: &mut dyn crate::DynNestedProgress,
+    should_interrupt: &AtomicBool,
+) -> Result<(), Error> {
+    let file = gix_bundle::File::at(&bundle.path)?;
+    if file.header.object_hash != repo.object_hash() {
+        return Err(Error::ObjectHash {
+            actual: file.header.object_hash,
+            expected: repo.object_hash(),
+        });
+    }
+    file.header.verify_prerequisites(&repo.objects)?;
+
+    let mut outcome = file.write_pack_to_directory(
+        &repo.objects.store_ref().path().join("pack"),
+        progress,
+        should_interrupt,
+        repo.objects.clone(),
+        gix_bundle::unpack::Options {
+            thread_limit: crate::remote::fetch::config::index_threads(repo)?,
+            index_version: crate::remote::fetch::config::pack_index_version(repo)?,
+        },
+    )?;
+    for r in &file.header.refs {
+        let Some(branch) = r.name.as_bstr().strip_prefix(b"refs/heads/") else {
+            continue;
+        };
+        let mut name = BString::from("refs/bundles/");
+        name.push_str(branch);
+        repo.reference(
+            name.as_bstr(),
+            r.id,
+            gix_ref::transaction::PreviousValue::Any,
+            "fetched bundle",
+        )?;
+    }
+    if let Some(path) = outcome.keep_path.take() {
+        std::fs::remove_file(&path)
+            .map_err(|source| crate::remote::fetch::Error::RemovePackKeepFile { path, source })?;
+    }
+    // Assure the object database sees the new pack even if it's used without refreshing it on miss.
+    for r in &file.header.refs {
+        repo.has_object(r.id);
+    }
+    Ok(())
+}
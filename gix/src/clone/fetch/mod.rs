@@ -66,6 +66,16 @@ pub enum Error {
         local: gix_hash::Kind,
         remote: gix_hash::Kind,
     },
+    #[error(transparent)]
+    BundleOpen(#[from] gix_bundle::open::Error),
+    #[error("Could not obtain the list of bundles advertised by the remote")]
+    BundleUri(#[from] gix_protocol::bundle_uri::Error),
+    #[error(transparent)]
+    BundleUriConfig(#[from] crate::config::boolean::Error),
+    #[error(transparent)]
+    BundlePrerequisites(#[from] gix_bundle::verify::Error),
+    #[error("Could not write the pack of the bundle into the object database")]
+    BundleUnpack(#[from] gix_bundle::unpack::Error),
     #[cfg(feature = "sha256")]
     #[error("Failed to reopen the local repository after adopting the remote's object format")]
     ReopenWithObjectHash(#[from] crate::open::Error),
@@ -86,6 +96,10 @@ impl PrepareFetch {
     ///
     /// Note that all data we created will be removed once this instance drops if the operation wasn't successful.
     ///
+    /// If `transfer.bundleURI` is `true` and the remote advertises bundles via the `bundle-uri` command, these are downloaded
+    /// and applied first so only what's missing has to be fetched from the remote. Bundles that can't be used are reported
+    /// through `progress`, without failing the clone.
+    ///
     /// ### Note for users of `async`
     ///
     /// Even though `async` is technically supported, it will still be blocking in nature as it uses a lot of non-async writes
@@ -126,6 +140,10 @@ impl PrepareFetch {
                 .unwrap_or_else(|| "origin".into()),
         };
 
+        if let Some(bundle_path) = bundle::path(&self.url) {
+            return self.fetch_from_bundle(repo, remote_name, &bundle_path, &mut progress, should_interrupt);
+        }
+
         let mut remote = repo.remote_at(self.url.clone())?;
 
         // For shallow clones without custom configuration, we'll use a single-branch refspec
@@ -253,6 +271,7 @@ impl PrepareFetch {
         let mut config = Some(util::append_remote_to_local_config_file(
             &mut remote,
             remote_name.clone(),
+            self.filter.as_deref(),
         )?);
 
         // Now we are free to apply remote configuration we don't want to be written to disk.
@@ -269,7 +288,7 @@ impl PrepareFetch {
         )
         .expect("valid")
         .to_owned();
-        let pending_pack = {
+        let mut pending_pack = {
             // For shallow clones, we already connected once, so we need to connect again
             let mut connection = remote.connect(remote::Direction::Fetch).await?;
             if let Some(f) = self.configure_connection.as_mut() {
@@ -334,40 +353,32 @@ impl PrepareFetch {
         {
             let remote_object_hash = pending_pack.ref_map().object_hash;
             if remote_object_hash != repo.object_hash() {
-                let mut in_memory_config = Vec::new();
-                repo.config.resolved.write_to_filter(&mut in_memory_config, |section| {
-                    section.meta().source == gix_config::Source::Api
-                })?;
-                // Reopen the still-empty repo with the remote's format; on error the original is kept for a retry.
-                repo = util::reinitialize_with_object_hash(&repo, remote_object_hash)?;
-                let mut resolved_config = repo.config.resolved.as_ref().clone();
-                // The reopened repo has the rewritten local config. Reapply the
-                // old API-only layer and then the remote config written during
-                // clone setup, matching the normal in-memory config order.
-                // TODO: make this much easier - we go from parsed-to-buffer-to-parsed.
-                //       Maybe make API changes available as overlay, just as utility over
-                //       Api sections.
-                resolved_config.append(gix_config::File::from_bytes_owned(
-                    &mut in_memory_config,
-                    gix_config::file::Metadata::api(),
-                    Default::default(),
-                )?);
-                repo.config
-                    .reread_values_and_clear_caches_replacing_config(resolved_config.into())?;
+                util::adopt_object_hash(&mut repo, remote_object_hash)?;
                 config = None;
             }
         }
+        if self.filter.is_some() {
+            util::upgrade_repository_format_version(&mut repo)?;
+        }
         let reflog_message = {
             let mut b = self.url.to_bstring();
             b.insert_str(0, "clone: from ");
             b
         };
+        // Bootstrap from the bundles the remote advertises so the fetch only has to obtain what they don't contain.
+        // Shallow and partial clones are excluded as bundles always contain the complete history with all objects.
+        if self.shallow == remote::fetch::Shallow::NoChange && self.filter.is_none() && bundle_uri::is_enabled(&repo)? {
+            if let Some(list) = pending_pack.bundle_list(&repo, &mut progress).await? {
+                bundle_uri::fetch(&repo, list, &self.url, &mut progress, should_interrupt);
+            }
+        }
         let outcome = pending_pack
             .with_write_packed_refs_only(true)
             .with_reflog_message(RefLogMessage::Override {
                 message: reflog_message.clone(),
             })
             .with_shallow(self.shallow.clone())
+            .with_filter(self.filter.clone())
             .receive(&repo, &mut progress, should_interrupt)
             .await?;
 
@@ -376,6 +387,11 @@ impl PrepareFetch {
         if let Some(config) = config {
             util::append_config_to_repo_config(&mut repo, config);
         }
+        // The repository was opened before it became a partial clone, so it doesn't know how to obtain missing objects yet.
+        #[cfg(feature = "blocking-network-client")]
+        if self.filter.is_some() {
+            remote::promisor::install_lazy_fetch(&repo);
+        }
         util::update_head(
             &mut repo,
             &outcome.ref_map,
@@ -411,4 +427,6 @@ impl PrepareFetch {
     }
 }
 
+mod bundle;
+mod bundle_uri;
 mod util;
@@ -20,9 +20,26 @@ enum WriteMode {
 pub fn append_remote_to_local_config_file(
     remote: &mut crate::Remote<'_>,
     remote_name: BString,
+    partial_clone_filter: Option<&str>,
 ) -> Result<gix_config::File<'static>, Error> {
+    use crate::config::tree::{Extensions, Key, Remote};
+
     let mut config = gix_config::File::new(local_config_meta(remote.repo));
-    remote.save_as_to(remote_name, &mut config)?;
+    remote.save_as_to(remote_name.clone(), &mut config)?;
+    if let Some(filter) = partial_clone_filter {
+        let mut section = config
+            .section_mut("remote", Some(remote_name.as_bstr()))
+            .expect("remote section was just written");
+        section.push(Remote::PROMISOR.name().try_into().expect("valid"), Some("true".into()));
+        section.push(
+            Remote::PARTIAL_CLONE_FILTER.name().try_into().expect("valid"),
+            Some(filter.into()),
+        );
+        config.new_section("extensions", None).expect("valid").push(
+            Extensions::PARTIAL_CLONE.name().try_into().expect("valid"),
+            Some(remote_name.as_bstr()),
+        );
+    }
 
     write_to_local_config(&config, WriteMode::Append)?;
     Ok(config)
@@ -85,6 +102,63 @@ pub(super) fn reinitialize_with_object_hash(
     Ok(crate::ThreadSafeRepository::open_opts(git_dir, repo.options.clone())?.to_thread_local())
 }
 
+/// Reopen the still-empty `repo` with `object_hash` as its object format, and keep all configuration that was
+/// set through the API so far. The remote configuration written during clone setup is picked up from disk.
+///
+/// On error, `repo` is left unchanged to allow a retry.
+#[cfg(feature = "sha256")]
+pub(super) fn adopt_object_hash(repo: &mut Repository, object_hash: gix_hash::Kind) -> Result<(), Error> {
+    let mut in_memory_config = Vec::new();
+    repo.config.resolved.write_to_filter(&mut in_memory_config, |section| {
+        section.meta().source == gix_config::Source::Api
+    })?;
+    let mut reopened = reinitialize_with_object_hash(repo, object_hash)?;
+    let mut resolved_config = reopened.config.resolved.as_ref().clone();
+    // The reopened repo has the rewritten local config. Reapply the
+    // old API-only layer and then the remote config written during
+    // clone setup, matching the normal in-memory config order.
+    // TODO: make this much easier - we go from parsed-to-buffer-to-parsed.
+    //       Maybe make API changes available as overlay, just as utility over
+    //       Api sections.
+    resolved_config.append(gix_config::File::from_bytes_owned(
+        &mut in_memory_config,
+        gix_config::file::Metadata::api(),
+        Default::default(),
+    )?);
+    reopened
+        .config
+        .reread_values_and_clear_caches_replacing_config(resolved_config.into())?;
+    *repo = reopened;
+    Ok(())
+}
+
+/// Set `core.repositoryformatversion` to `1` in the local configuration of `repo`, on disk and in memory, as required by
+/// the `extensions.partialClone` setting of partial clones.
+pub fn upgrade_repository_format_version(repo: &mut Repository) -> Result<(), Error> {
+    use gix_config::parse::section::ValueName;
+
+    let key = || ValueName::try_from("repositoryformatversion").expect("valid");
+    let config_path = repo.git_dir().join("config");
+    let mut config = gix_config::File::from_path_no_includes(config_path.clone(), gix_config::Source::Local)?;
+    config
+        .section_mut("core", None)
+        .expect("freshly initialized repository has a core section")
+        .set(key(), "1".into());
+    let mut lock =
+        gix_lock::File::acquire_to_update_resource(&config_path, gix_lock::acquire::Fail::Immediately, None)?;
+    config.write_to_filter(&mut lock, |section| section.meta().source == gix_config::Source::Local)?;
+    lock.commit()?;
+
+    // The local configuration may be written back from memory later, so both have to agree.
+    let repo_config = gix_features::threading::OwnShared::make_mut(&mut repo.config.resolved);
+    if let Ok(Some(mut core)) =
+        repo_config.section_mut_filter("core", None, |meta| meta.source == gix_config::Source::Local)
+    {
+        core.set(key(), "1".into());
+    }
+    Ok(())
+}
+
 fn local_config_meta(repo: &Repository) -> gix_config::file::Metadata {
     let meta = repo.config.resolved.meta().clone();
     assert_eq!(
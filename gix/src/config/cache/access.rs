@@ -202,6 +202,15 @@ impl Cache {
             .unwrap_or_default()
     }
 
+    #[cfg(feature = "in-process-file-transport")]
+    pub(crate) fn in_process_file_transport(&self) -> bool {
+        use config::tree::Gitoxide;
+        self.resolved
+            .boolean(Gitoxide::IN_PROCESS_FILE_TRANSPORT)
+            .and_then(Result::ok)
+            .unwrap_or_default()
+    }
+
     pub(crate) fn personas(&self) -> &identity::Personas {
         self.personas
             .get_or_init(|| identity::Personas::from_config_and_env(&self.resolved))
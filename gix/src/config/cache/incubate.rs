@@ -15,6 +15,7 @@ pub(crate) struct StageOne {
     pub is_bare: Option<bool>,
     pub lossy: bool,
     pub object_hash: gix_hash::Kind,
+    pub ref_storage: gix_ref::store::Backend,
     pub reflog: Option<gix_ref::store::WriteReflog>,
     pub precompose_unicode: bool,
     pub protect_windows: bool,
@@ -52,6 +53,12 @@ impl StageOne {
             (0 | 1, None) => legacy_object_hash()?,
             (version, _) => return Err(Error::UnsupportedRepositoryFormatVersion { version }),
         };
+        let ref_storage = match (repo_format_version, config.string(Extensions::REF_STORAGE)) {
+            (1, Some(storage)) => Extensions::REF_STORAGE.try_into_ref_storage(storage)?,
+            (0, Some(_)) => return Err(Error::RefStorageRequiresV1),
+            (_, None) => gix_ref::store::Backend::Files,
+            (version, _) => return Err(Error::UnsupportedRepositoryFormatVersion { version }),
+        };
 
         let extension_worktree = util::config_bool(
             &config,
@@ -95,6 +102,7 @@ impl StageOne {
             is_bare,
             lossy,
             object_hash,
+            ref_storage,
             reflog,
             precompose_unicode,
             protect_windows,
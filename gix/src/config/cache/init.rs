@@ -26,6 +26,7 @@ impl Cache {
             lossy,
             is_bare,
             object_hash,
+            ref_storage: _,
             reflog: _,
             precompose_unicode: _,
             protect_windows: _,
@@ -305,8 +306,9 @@ impl crate::Repository {
     }
 
     fn apply_changed_values(&mut self) {
-        self.refs.write_reflog = util::reflog_or_default(self.config.reflog, self.workdir().is_some());
-        self.refs.namespace.clone_from(&self.config.refs_namespace);
+        self.refs
+            .set_write_reflog(util::reflog_or_default(self.config.reflog, self.workdir().is_some()));
+        self.refs.set_namespace(self.config.refs_namespace.clone());
     }
 }
 
@@ -364,6 +366,10 @@ fn apply_environment_overrides(
                     let key = &Gitoxide::TRACE_PACKET;
                     (env(key), key.name)
                 },
+                {
+                    let key = &Gitoxide::IN_PROCESS_FILE_TRANSPORT;
+                    (env(key), key.name)
+                },
                 {
                     let key = &Gitoxide::PARSE_PRECIOUS;
                     (env(key), key.name)
@@ -528,6 +534,10 @@ fn apply_environment_overrides(
                     let key = &gitoxide::Objects::ALLOC_LIMIT;
                     (env(key), key.name)
                 },
+                {
+                    let key = &gitoxide::Objects::NO_LAZY_FETCH;
+                    (env(key), key.name)
+                },
             ],
         ),
         (
@@ -614,6 +624,10 @@ fn apply_environment_overrides(
                 let key = &Core::SSH_COMMAND;
                 (env(key), key.name, git_prefix)
             },
+            {
+                let key = &Core::NOTES_REF;
+                (env(key), key.name, git_prefix)
+            },
             {
                 let key = &Core::USE_REPLACE_REFS;
                 (env(key), key.name, objects)
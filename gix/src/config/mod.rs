@@ -91,6 +91,11 @@ pub enum Error {
          set core.repositoryFormatVersion=1 to use it, or remove extensions.objectFormat to fall back to the default Sha1 format (if supported by this build)"
     )]
     ObjectFormatRequiresV1,
+    #[error(
+        "extensions.refStorage is a v1-only extension, but the repository format version is 0; \
+         set core.repositoryFormatVersion=1 to use it, or remove extensions.refStorage to store references in files"
+    )]
+    RefStorageRequiresV1,
     #[error("Unsupported repository format version {version}; only versions 0 and 1 are supported")]
     UnsupportedRepositoryFormatVersion { version: usize },
     #[error(transparent)]
@@ -20,6 +20,8 @@ pub(crate) mod root {
         pub const AUTHOR: sections::Author = sections::Author;
         /// The `branch` section.
         pub const BRANCH: sections::Branch = sections::Branch;
+        /// The `bundle` section.
+        pub const BUNDLE: sections::Bundle = sections::Bundle;
         /// The `checkout` section.
         pub const CHECKOUT: sections::Checkout = sections::Checkout;
         /// The `clone` section.
@@ -57,6 +59,8 @@ pub(crate) mod root {
         pub const PUSH: sections::Push = sections::Push;
         /// The `remote` section.
         pub const REMOTE: sections::Remote = sections::Remote;
+        /// The `rerere` section.
+        pub const RERERE: sections::Rerere = sections::Rerere;
         /// The `safe` section.
         pub const SAFE: sections::Safe = sections::Safe;
         /// The `ssh` section.
@@ -64,6 +68,8 @@ pub(crate) mod root {
         /// The `status` section.
         #[cfg(feature = "status")]
         pub const STATUS: sections::Status = sections::Status;
+        /// The `transfer` section.
+        pub const TRANSFER: sections::Transfer = sections::Transfer;
         /// The `user` section.
         pub const USER: sections::User = sections::User;
         /// The `url` section.
@@ -74,6 +80,7 @@ pub(crate) mod root {
             &[
                 &Self::AUTHOR,
                 &Self::BRANCH,
+                &Self::BUNDLE,
                 &Self::CHECKOUT,
                 &Self::CLONE,
                 &Self::COMMITTER,
@@ -93,10 +100,12 @@ pub(crate) mod root {
                 &Self::PROTOCOL,
                 &Self::PUSH,
                 &Self::REMOTE,
+                &Self::RERERE,
                 &Self::SAFE,
                 &Self::SSH,
                 #[cfg(feature = "status")]
                 &Self::STATUS,
+                &Self::TRANSFER,
                 &Self::USER,
                 &Self::URL,
             ]
@@ -106,9 +115,9 @@ pub(crate) mod root {
 
 mod sections;
 pub use sections::{
-    Author, Branch, Checkout, Clone, Committer, Core, Credential, Extensions, Fetch, Gitoxide, Http, Index, Init,
-    Mailmap, Merge, Pack, Protocol, Push, Remote, Safe, Ssh, Url, User, branch, checkout, core, credential, extensions,
-    fetch, gitoxide, http, index, protocol, push, remote, ssh,
+    Author, Branch, Bundle, Checkout, Clone, Committer, Core, Credential, Extensions, Fetch, Gitoxide, Http, Index,
+    Init, Mailmap, Merge, Pack, Protocol, Push, Remote, Rerere, Safe, Ssh, Transfer, Url, User, branch, bundle,
+    checkout, core, credential, extensions, fetch, gitoxide, http, index, protocol, push, remote, ssh,
 };
 #[cfg(feature = "blob-diff")]
 pub use sections::{Diff, diff};
@@ -55,6 +55,7 @@ pub mod validate {
         config::tree::{branch::Merge, keys},
     };
 
+    #[derive(Clone, Copy)]
     pub struct FullNameRef;
     impl keys::Validate for FullNameRef {
         fn validate(&self, value: &BStr) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
@@ -0,0 +1,103 @@
+use crate::{
+    config,
+    config::tree::{Bundle, Key, Section, keys, traits::SubSectionRequirement},
+};
+
+const ID_PARAMETER: Option<SubSectionRequirement> = Some(SubSectionRequirement::Parameter("id"));
+
+impl Bundle {
+    /// The `bundle.heuristic` key.
+    pub const HEURISTIC: Heuristic =
+        Heuristic::new_with_validate("heuristic", &config::Tree::BUNDLE, validate::Heuristic);
+    /// The `bundle.mode` key.
+    pub const MODE: Mode = Mode::new_with_validate("mode", &config::Tree::BUNDLE, validate::Mode);
+    /// The `bundle.version` key.
+    pub const VERSION: keys::UnsignedInteger =
+        keys::UnsignedInteger::new_unsigned_integer("version", &config::Tree::BUNDLE);
+    /// The `bundle.<id>.creationToken` key.
+    pub const CREATION_TOKEN: keys::UnsignedInteger =
+        keys::UnsignedInteger::new_unsigned_integer("creationToken", &config::Tree::BUNDLE)
+            .with_subsection_requirement(ID_PARAMETER);
+    /// The `bundle.<id>.uri` key.
+    pub const URI: keys::String =
+        keys::String::new_string("uri", &config::Tree::BUNDLE).with_subsection_requirement(ID_PARAMETER);
+}
+
+impl Section for Bundle {
+    fn name(&self) -> &str {
+        "bundle"
+    }
+
+    fn keys(&self) -> &[&dyn Key] {
+        &[
+            &Self::HEURISTIC,
+            &Self::MODE,
+            &Self::VERSION,
+            &Self::CREATION_TOKEN,
+            &Self::URI,
+        ]
+    }
+}
+
+/// The `bundle.heuristic` key.
+pub type Heuristic = keys::Any<validate::Heuristic>;
+
+/// The `bundle.mode` key.
+pub type Mode = keys::Any<validate::Mode>;
+
+mod heuristic {
+    use std::borrow::Cow;
+
+    use crate::{bstr::BStr, config, config::tree::bundle::Heuristic};
+
+    impl Heuristic {
+        /// Return the heuristic identified by `value`, case-sensitively.
+        pub fn try_into_heuristic(
+            &'static self,
+            value: Cow<'_, BStr>,
+        ) -> Result<gix_protocol::bundle_uri::Heuristic, config::key::GenericErrorWithValue> {
+            gix_protocol::bundle_uri::Heuristic::from_bytes(value.as_ref())
+                .ok_or_else(|| config::key::GenericErrorWithValue::from_value(self, value.into_owned()))
+        }
+    }
+}
+
+mod mode {
+    use std::borrow::Cow;
+
+    use crate::{bstr::BStr, config, config::tree::bundle::Mode};
+
+    impl Mode {
+        /// Return the mode identified by `value`, case-sensitively.
+        pub fn try_into_mode(
+            &'static self,
+            value: Cow<'_, BStr>,
+        ) -> Result<gix_protocol::bundle_uri::Mode, config::key::GenericErrorWithValue> {
+            gix_protocol::bundle_uri::Mode::from_bytes(value.as_ref())
+                .ok_or_else(|| config::key::GenericErrorWithValue::from_value(self, value.into_owned()))
+        }
+    }
+}
+
+///
+pub mod validate {
+    use crate::{bstr::BStr, config::tree::keys};
+
+    #[derive(Clone, Copy)]
+    pub struct Heuristic;
+    impl keys::Validate for Heuristic {
+        fn validate(&self, value: &BStr) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+            crate::config::tree::Bundle::HEURISTIC.try_into_heuristic(value.into())?;
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    pub struct Mode;
+    impl keys::Validate for Mode {
+        fn validate(&self, value: &BStr) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+            crate::config::tree::Bundle::MODE.try_into_mode(value.into())?;
+            Ok(())
+        }
+    }
+}
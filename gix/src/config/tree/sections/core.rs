@@ -65,6 +65,8 @@ impl Core {
         .with_note("fallback is 'SSH_ASKPASS'");
     /// The `core.excludesFile` key.
     pub const EXCLUDES_FILE: keys::Path = keys::Path::new_path("excludesFile", &config::Tree::CORE);
+    /// The `core.hooksPath` key.
+    pub const HOOKS_PATH: keys::Path = keys::Path::new_path("hooksPath", &config::Tree::CORE);
     /// The `core.attributesFile` key.
     pub const ATTRIBUTES_FILE: keys::Path =
         keys::Path::new_path("attributesFile", &config::Tree::CORE)
@@ -72,6 +74,10 @@ impl Core {
     /// The `core.sshCommand` key.
     pub const SSH_COMMAND: keys::Executable = keys::Executable::new_executable("sshCommand", &config::Tree::CORE)
         .with_environment_override("GIT_SSH_COMMAND");
+    /// The `core.notesRef` key.
+    pub const NOTES_REF: NotesRef =
+        NotesRef::new_with_validate("notesRef", &config::Tree::CORE, super::branch::validate::FullNameRef)
+            .with_environment_override("GIT_NOTES_REF");
     /// The `core.useReplaceRefs` key.
     pub const USE_REPLACE_REFS: keys::Boolean = keys::Boolean::new_boolean("useReplaceRefs", &config::Tree::CORE)
         .with_environment_override("GIT_NO_REPLACE_OBJECTS");
@@ -124,8 +130,10 @@ impl Section for Core {
             &Self::PROTECT_NTFS,
             &Self::ASKPASS,
             &Self::EXCLUDES_FILE,
+            &Self::HOOKS_PATH,
             &Self::ATTRIBUTES_FILE,
             &Self::SSH_COMMAND,
+            &Self::NOTES_REF,
             &Self::USE_REPLACE_REFS,
             &Self::COMMIT_GRAPH,
             #[cfg(feature = "attributes")]
@@ -152,6 +160,9 @@ pub type LogAllRefUpdates = keys::Any<validate::LogAllRefUpdates>;
 /// The `core.disambiguate` key.
 pub type Disambiguate = keys::Any<validate::Disambiguate>;
 
+/// The `core.notesRef` key.
+pub type NotesRef = keys::Any<super::branch::validate::FullNameRef>;
+
 #[cfg(feature = "attributes")]
 mod filter {
     use super::validate;
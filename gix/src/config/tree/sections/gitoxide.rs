@@ -36,6 +36,13 @@ impl Gitoxide {
     /// The `gitoxide.tracePacket` Key.
     pub const TRACE_PACKET: keys::Boolean = keys::Boolean::new_boolean("tracePacket", &config::Tree::GITOXIDE)
         .with_environment_override("GIT_TRACE_PACKET");
+    /// The `gitoxide.inProcessFileTransport` Key.
+    pub const IN_PROCESS_FILE_TRANSPORT: keys::Boolean =
+        keys::Boolean::new_boolean("inProcessFileTransport", &config::Tree::GITOXIDE)
+            .with_environment_override("GIX_IN_PROCESS_FILE_TRANSPORT")
+            .with_note(
+                "Serve fetches from `file://` URLs within the process instead of spawning `git upload-pack` if the `in-process-file-transport` feature is enabled. Defaults to false as shallow and partial clones aren't fully supported yet",
+            );
     /// The `gitoxide.parsePrecious` Key.
     pub const PARSE_PRECIOUS: keys::Boolean = keys::Boolean::new_boolean("parsePrecious", &config::Tree::GITOXIDE)
         .with_environment_override("GIX_PARSE_PRECIOUS");
@@ -47,7 +54,12 @@ impl Section for Gitoxide {
     }
 
     fn keys(&self) -> &[&dyn Key] {
-        &[&Self::USER_AGENT, &Self::TRACE_PACKET, &Self::PARSE_PRECIOUS]
+        &[
+            &Self::USER_AGENT,
+            &Self::TRACE_PACKET,
+            &Self::IN_PROCESS_FILE_TRANSPORT,
+            &Self::PARSE_PRECIOUS,
+        ]
     }
 
     fn sub_sections(&self) -> &[&dyn Section] {
@@ -353,6 +365,10 @@ mod subsections {
             keys::Executable::new_executable("commandWithoutShellFallback", &Gitoxide::SSH)
                 .with_environment_override("GIT_SSH")
                 .with_note("is always executed without shell and treated as fallback");
+        /// The `gitoxide.ssh.native` key.
+        pub const NATIVE: keys::Boolean = keys::Boolean::new_boolean("native", &Gitoxide::SSH).with_note(
+            "use the built-in ssh client instead of an ssh program, if compiled with the `blocking-ssh-transport-native` feature and if neither `core.sshCommand` nor an `ssh.variant` other than `ssh` are set",
+        );
     }
 
     impl Section for Ssh {
@@ -361,7 +377,7 @@ mod subsections {
         }
 
         fn keys(&self) -> &[&dyn Key] {
-            &[&Self::COMMAND_WITHOUT_SHELL_FALLBACK]
+            &[&Self::COMMAND_WITHOUT_SHELL_FALLBACK, &Self::NATIVE]
         }
 
         fn parent(&self) -> Option<&dyn Section> {
@@ -455,6 +471,12 @@ mod subsections {
         /// The `gitoxide.objects.replaceRefBase` key.
         pub const REPLACE_REF_BASE: keys::Any =
             keys::Any::new("replaceRefBase", &Gitoxide::OBJECTS).with_environment_override("GIT_REPLACE_REF_BASE");
+        /// The `gitoxide.objects.noLazyFetch` key.
+        pub const NO_LAZY_FETCH: keys::Boolean = keys::Boolean::new_boolean("noLazyFetch", &Gitoxide::OBJECTS)
+            .with_environment_override("GIT_NO_LAZY_FETCH")
+            .with_note(
+                "If true, objects missing in a partial clone won't be fetched from the promisor remote on demand",
+            );
     }
 
     impl Section for Objects {
@@ -468,6 +490,7 @@ mod subsections {
                 &Self::ALLOC_LIMIT,
                 &Self::ALLOC_LIMIT_IF_REDUCED_TRUST,
                 &Self::REPLACE_REF_BASE,
+                &Self::NO_LAZY_FETCH,
             ]
         }
 
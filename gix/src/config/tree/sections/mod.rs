@@ -11,6 +11,11 @@ mod author;
 pub struct Branch;
 pub mod branch;
 
+/// The `bundle` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct Bundle;
+pub mod bundle;
+
 /// The `checkout` top-level section.
 #[derive(Copy, Clone, Default)]
 pub struct Checkout;
@@ -101,6 +106,11 @@ pub mod push;
 pub struct Remote;
 pub mod remote;
 
+/// The `rerere` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct Rerere;
+mod rerere;
+
 /// The `safe` top-level section.
 #[derive(Copy, Clone, Default)]
 pub struct Safe;
@@ -118,6 +128,11 @@ pub struct Status;
 #[cfg(feature = "status")]
 pub mod status;
 
+/// The `transfer` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct Transfer;
+mod transfer;
+
 /// The `user` top-level section.
 #[derive(Copy, Clone, Default)]
 pub struct User;
@@ -0,0 +1,22 @@
+use crate::config::{
+    Tree,
+    tree::{Key, Rerere, Section, keys},
+};
+
+impl Rerere {
+    /// The `rerere.enabled` key.
+    pub const ENABLED: keys::Boolean = keys::Boolean::new_boolean("enabled", &Tree::RERERE)
+        .with_note("If unset, rerere is enabled if the `rr-cache` directory exists in the git directory");
+    /// The `rerere.autoUpdate` key.
+    pub const AUTO_UPDATE: keys::Boolean = keys::Boolean::new_boolean("autoUpdate", &Tree::RERERE);
+}
+
+impl Section for Rerere {
+    fn name(&self) -> &str {
+        "rerere"
+    }
+
+    fn keys(&self) -> &[&dyn Key] {
+        &[&Self::ENABLED, &Self::AUTO_UPDATE]
+    }
+}
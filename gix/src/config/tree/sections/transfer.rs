@@ -0,0 +1,19 @@
+use crate::{
+    config,
+    config::tree::{Key, Section, Transfer, keys},
+};
+
+impl Transfer {
+    /// The `transfer.bundleURI` key.
+    pub const BUNDLE_URI: keys::Boolean = keys::Boolean::new_boolean("bundleURI", &config::Tree::TRANSFER);
+}
+
+impl Section for Transfer {
+    fn name(&self) -> &str {
+        "transfer"
+    }
+
+    fn keys(&self) -> &[&dyn Key] {
+        &[&Self::BUNDLE_URI]
+    }
+}
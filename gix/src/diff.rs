@@ -251,6 +251,52 @@ pub(crate) mod utils {
         );
         Ok(diff_cache)
     }
+
+    /// Return `true` if `rewrites` are tracked by similarity, which needs the content of all blobs that are candidates
+    /// for a rename or copy.
+    pub(crate) fn rewrites_need_blob_content(rewrites: Option<&Rewrites>) -> bool {
+        rewrites.is_some_and(|rewrites| {
+            rewrites.percentage.is_some() || rewrites.copies.is_some_and(|copies| copies.percentage.is_some())
+        })
+    }
+
+    /// Obtain the ids of all blobs and symlinks that changed between the trees `lhs` and `rhs`, using `repo` to
+    /// read their subtrees, for use in obtaining them all at once in partial clones.
+    pub(crate) fn changed_blob_ids(
+        repo: &Repository,
+        lhs: gix_object::TreeRefIter<'_>,
+        rhs: gix_object::TreeRefIter<'_>,
+    ) -> Result<Vec<gix_hash::ObjectId>, gix_diff::tree::Error> {
+        use gix_diff::tree::recorder::Change;
+
+        let mut recorder = gix_diff::tree::Recorder::default().track_location(None);
+        gix_diff::tree(lhs, rhs, &mut Default::default(), &repo.objects, &mut recorder)?;
+        let mut out = Vec::new();
+        for change in recorder.records {
+            match change {
+                Change::Addition { entry_mode, oid, .. } | Change::Deletion { entry_mode, oid, .. } => {
+                    if entry_mode.is_blob_or_symlink() {
+                        out.push(oid);
+                    }
+                }
+                Change::Modification {
+                    previous_entry_mode,
+                    previous_oid,
+                    entry_mode,
+                    oid,
+                    ..
+                } => {
+                    if previous_entry_mode.is_blob_or_symlink() {
+                        out.push(previous_oid);
+                    }
+                    if entry_mode.is_blob_or_symlink() {
+                        out.push(oid);
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
 }
 #[cfg(feature = "blob-diff")]
 pub use utils::{new_rewrites, resource_cache};
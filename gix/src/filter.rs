@@ -122,12 +122,22 @@ impl<'repo> Pipeline<'repo> {
             .map(|value| Core::EOL.try_into_eol(value))
             .transpose()?;
         let drivers = extract_drivers(repo)?;
+        #[cfg(feature = "lfs")]
+        let lfs = drivers.iter().any(|driver| driver.name == "lfs").then(|| {
+            let storage = config
+                .path("lfs.storage")
+                .and_then(|path| gix_path::try_from_bstr(path.value).ok().map(Cow::into_owned))
+                .unwrap_or_else(|| "lfs".into());
+            gix_filter::lfs::Store::at(repo.common_dir().join(storage))
+        });
         Ok(gix_filter::pipeline::Options {
             drivers,
             eol_config: gix_filter::eol::Configuration { auto_crlf, eol },
             encodings_with_roundtrip_check: encodings,
             crlf_roundtrip_check: safe_crlf,
             object_hash: repo.object_hash(),
+            #[cfg(feature = "lfs")]
+            lfs,
         })
     }
 
@@ -7,8 +7,8 @@ use crate::{
 
 impl<'repo> Head<'repo> {
     /// Return a platform for obtaining iterators on the reference log associated with the `HEAD` reference.
-    pub fn log_iter(&self) -> gix_ref::file::log::iter::Platform<'static, 'repo> {
-        gix_ref::file::log::iter::Platform {
+    pub fn log_iter(&self) -> gix_ref::store::reflog::Platform<'static, 'repo> {
+        gix_ref::store::reflog::Platform {
             store: &self.repo.refs,
             name: "HEAD".try_into().expect("HEAD is always valid"),
             buf: Vec::new(),
@@ -18,7 +18,9 @@ impl<'repo> Head<'repo> {
     /// Return a list of all branch names that were previously checked out with the first-ever checked out branch
     /// being the first entry of the list, and the most recent is the last, along with the commit they were pointing to
     /// at the time.
-    pub fn prior_checked_out_branches(&self) -> std::io::Result<Option<Vec<(BString, ObjectId)>>> {
+    pub fn prior_checked_out_branches(
+        &self,
+    ) -> Result<Option<Vec<(BString, ObjectId)>>, gix_ref::store::reflog::Error> {
         Ok(self.log_iter().all()?.map(|log| {
             log.filter_map(Result::ok)
                 .filter_map(|line| {
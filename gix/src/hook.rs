@@ -0,0 +1,27 @@
+//! Find and run hooks, like `pre-commit` or `pre-push`, just like `git` does.
+//!
+//! Hooks are found in `$GIT_DIR/hooks`, or in the directory configured with `core.hooksPath`, and are only run if the
+//! repository is fully trusted, which typically means that it's owned by the current user.
+//! Otherwise, hooks are ignored as if they didn't exist, as running them would execute code controlled by someone else.
+//!
+//! Hooks run in the root of the worktree, or in the `.git` directory of bare repositories, with `GIT_DIR` set
+//! so `git` commands they invoke operate on the same repository.
+//! Their standard output is redirected to standard error.
+//!
+//! Note that hooks aren't run automatically, but have to be run by the caller at the right time, for instance
+//! with [`Repository::run_pre_commit_hook()`](crate::Repository::run_pre_commit_hook()) before creating a commit.
+pub use gix_hook as plumbing;
+pub use gix_hook::{
+    Invocation,
+    invocation::{MessageSource, PushUpdate, RefUpdate, TransactionState},
+};
+
+/// The error returned by [`Repository::run_hook()`](crate::Repository::run_hook()) and the methods to run specific hooks.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not interpolate the path in core.hooksPath")]
+    HooksPath(#[from] gix_config::path::interpolate::Error),
+    #[error(transparent)]
+    Run(#[from] gix_hook::run::Error),
+}
@@ -113,6 +113,8 @@ pub use gix_actor as actor;
 pub use gix_attributes as attrs;
 #[cfg(feature = "blame")]
 pub use gix_blame as blame;
+#[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+pub use gix_bundle as bundle;
 #[cfg(feature = "command")]
 pub use gix_command as command;
 pub use gix_commitgraph as commitgraph;
@@ -129,6 +131,8 @@ pub use gix_features::{
     progress::{Count, DynNestedProgress, NestedProgress, Progress},
     threading,
 };
+#[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+pub use gix_fetchhead as fetchhead;
 pub use gix_fs as fs;
 pub use gix_glob as glob;
 pub use gix_hash as hash;
@@ -176,7 +180,7 @@ mod attribute_stack;
 pub mod path;
 
 /// The standard type for a store to handle git references.
-pub type RefStore = gix_ref::file::Store;
+pub type RefStore = gix_ref::store::Handle;
 /// A handle for finding objects in an object database, abstracting away caches for thread-local use.
 pub type OdbHandle = gix_odb::memory::Proxy<gix_odb::Handle>;
 /// A handle for finding objects in an object database, abstracting away caches for moving across threads.
@@ -202,6 +206,8 @@ pub mod commit;
 #[cfg(feature = "dirwalk")]
 pub mod dirwalk;
 pub mod head;
+#[cfg(feature = "hook")]
+pub mod hook;
 pub mod id;
 pub mod object;
 #[cfg(feature = "attributes")]
@@ -226,6 +232,22 @@ pub mod diff;
 #[cfg(feature = "merge")]
 pub mod merge;
 
+///
+#[cfg(feature = "note")]
+pub mod note;
+
+///
+#[cfg(feature = "rerere")]
+pub mod rerere;
+
+///
+#[cfg(feature = "sequencer")]
+pub mod sequence;
+
+///
+#[cfg(feature = "stash")]
+pub mod stash;
+
 /// Try to open a git repository in `directory` and search upwards through its parents until one is found,
 /// using default trust options which matters in case the found repository isn't owned by the current user.
 ///
@@ -0,0 +1,116 @@
+//! Read, add, remove and merge notes, which attach metadata to objects without changing them, just like `git notes` does.
+//!
+//! Notes are kept in notes commits whose trees map annotated objects to blobs with their notes, with each change to the
+//! notes being a new commit. The reference to these commits defaults to `refs/notes/commits`, and can be changed
+//! with `core.notesRef` or the `GIT_NOTES_REF` environment variable, see
+//! [`Repository::notes_ref()`](crate::Repository::notes_ref()).
+//!
+//! All methods take the name of the notes reference to operate on, so notes under any reference can be used.
+pub use gix_note as plumbing;
+pub use gix_note::{Notes, merge::Strategy};
+
+/// The name of the reference with notes if `core.notesRef` isn't set.
+pub const DEFAULT_REF: &str = "refs/notes/commits";
+
+///
+pub mod notes_ref {
+    /// The error returned by [`Repository::notes_ref()`](crate::Repository::notes_ref()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The notes reference in core.notesRef is not a valid full reference name")]
+        InvalidName(#[from] gix_validate::reference::name::Error),
+    }
+}
+
+///
+pub mod find {
+    /// The error returned by [`Repository::find_note()`](crate::Repository::find_note())
+    /// and [`Repository::notes()`](crate::Repository::notes()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        FindReference(#[from] crate::reference::find::Error),
+        #[error(transparent)]
+        PeelReference(#[from] crate::reference::peel::to_kind::Error),
+        #[error(transparent)]
+        DecodeCommit(#[from] gix_object::decode::Error),
+        #[error(transparent)]
+        FindObject(#[from] gix_object::find::existing_object::Error),
+        #[error(transparent)]
+        FindBlob(#[from] crate::object::find::existing::with_conversion::Error),
+    }
+}
+
+///
+pub mod edit {
+    use crate::bstr::BString;
+
+    /// The error returned by [`Repository::add_note()`](crate::Repository::add_note()) and the other methods
+    /// to change notes.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Find(#[from] super::find::Error),
+        #[error("Object {object} already has a note in '{notes_ref}'")]
+        NoteExists {
+            object: gix_hash::ObjectId,
+            notes_ref: BString,
+        },
+        #[error("Object {object} has no note in '{notes_ref}'")]
+        NoteMissing {
+            object: gix_hash::ObjectId,
+            notes_ref: BString,
+        },
+        #[error(transparent)]
+        WriteObject(#[from] crate::object::write::Error),
+        #[error(transparent)]
+        WriteTree(#[from] gix_note::write::Error),
+        #[error("Author or committer identity is not configured")]
+        IdentityMissing,
+        #[error(transparent)]
+        Identity(#[from] crate::config::time::Error),
+        #[error(transparent)]
+        EditReference(#[from] crate::reference::edit::Error),
+    }
+}
+
+///
+pub mod merge {
+    use crate::bstr::BString;
+
+    /// The error returned by [`Repository::merge_notes()`](crate::Repository::merge_notes()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("There are no notes to merge at '{notes_ref}'")]
+        MissingReference { notes_ref: BString },
+        #[error(transparent)]
+        Find(#[from] super::find::Error),
+        #[error(transparent)]
+        MergeBase(#[from] crate::repository::merge_base::Error),
+        #[error(transparent)]
+        Merge(#[from] gix_note::merge::Error),
+        #[error(transparent)]
+        Commit(#[from] super::edit::Error),
+    }
+
+    /// The outcome of [`Repository::merge_notes()`](crate::Repository::merge_notes()).
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum Outcome {
+        /// All notes to merge were already contained in the notes reference, which was left unchanged.
+        UpToDate,
+        /// The notes reference was updated to point to `commit`, which contains all of its notes.
+        FastForward {
+            /// The notes commit the notes reference now points to.
+            commit: gix_hash::ObjectId,
+        },
+        /// A merge commit was created and the notes reference was updated to point to it.
+        Merged {
+            /// The newly created notes commit.
+            commit: gix_hash::ObjectId,
+        },
+    }
+}
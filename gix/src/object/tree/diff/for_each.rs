@@ -15,6 +15,8 @@ pub enum Error {
     ResourceCache(#[from] crate::repository::diff_resource_cache::Error),
     #[error("Failure during rename tracking")]
     RenameTracking(#[from] tracker::emit::Error),
+    #[error("Could not obtain the blobs to check for renames from the promisor remote")]
+    FetchMissing(#[source] gix_odb::store::find::Error),
 }
 
 /// Add the item to compare to.
@@ -23,6 +25,9 @@ impl<'old> Platform<'_, 'old> {
     ///
     /// `other` could also be created with the [`empty_tree()`][crate::Repository::empty_tree()] method to handle the first commit
     /// in a repository - it doesn't have a parent, equivalent to compare 'nothing' to something.
+    ///
+    /// In partial clones, if renames are tracked by similarity, all changed blobs that are missing are obtained from the
+    /// promisor remote at once. Blobs accessed by `for_each` are otherwise obtained one at a time.
     pub fn for_each_to_obtain_tree<'new, E>(
         &mut self,
         other: &Tree<'new>,
@@ -74,7 +79,16 @@ impl<'old> Platform<'_, 'old> {
             }
             Some(cache) => cache,
         };
-        let opts = self.options.into();
+        let opts: gix_diff::tree_with_rewrites::Options = self.options.into();
+        if repo.fetches_missing_objects() && crate::diff::utils::rewrites_need_blob_content(opts.rewrites.as_ref()) {
+            let ids = crate::diff::utils::changed_blob_ids(
+                repo,
+                TreeRefIter::from_bytes(&self.lhs.data, self.lhs.id.kind()),
+                TreeRefIter::from_bytes(&other.data, other.id.kind()),
+            )
+            .map_err(gix_diff::tree_with_rewrites::Error::Diff)?;
+            repo.fetch_missing_objects(ids).map_err(Error::FetchMissing)?;
+        }
         Ok(gix_diff::tree_with_rewrites(
             TreeRefIter::from_bytes(&self.lhs.data, self.lhs.id.kind()),
             TreeRefIter::from_bytes(&other.data, other.id.kind()),
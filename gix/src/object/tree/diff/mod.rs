@@ -175,6 +175,10 @@ pub mod stats {
         CreateResourceCache(#[from] crate::repository::diff_resource_cache::Error),
         #[error(transparent)]
         ForEachChange(#[from] crate::object::tree::diff::for_each::Error),
+        #[error(transparent)]
+        ChangedBlobs(#[from] gix_diff::tree::Error),
+        #[error("Could not obtain the blobs to diff from the promisor remote")]
+        FetchMissing(#[source] gix_odb::store::find::Error),
     }
 }
 
@@ -188,9 +192,19 @@ impl Platform<'_, '_> {
     /// rename tracking, an operation that doesn't affect the statistics currently.
     /// As diffed resources aren't cached, if highly repetitive blobs are expected, performance
     /// may be diminished. In real-world scenarios where blobs are mostly unique, that's not an issue though.
+    /// In partial clones, all changed blobs that are missing are obtained from the promisor remote at once.
     pub fn stats(&mut self, other: &Tree<'_>) -> Result<Stats, stats::Error> {
         // let (mut number_of_files, mut lines_added, mut lines_removed) = (0, 0, 0);
-        let mut resource_cache = self.lhs.repo.diff_resource_cache_for_tree_diff()?;
+        let repo = self.lhs.repo;
+        let mut resource_cache = repo.diff_resource_cache_for_tree_diff()?;
+        if repo.fetches_missing_objects() {
+            let ids = crate::diff::utils::changed_blob_ids(
+                repo,
+                gix_object::TreeRefIter::from_bytes(&self.lhs.data, self.lhs.id.kind()),
+                gix_object::TreeRefIter::from_bytes(&other.data, other.id.kind()),
+            )?;
+            repo.fetch_missing_objects(ids).map_err(stats::Error::FetchMissing)?;
+        }
 
         let (mut files_changed, mut lines_added, mut lines_removed) = (0, 0, 0);
         self.for_each_to_obtain_tree(other, |change| {
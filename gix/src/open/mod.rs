@@ -17,9 +17,9 @@ pub struct Permissions {
 ///
 /// ### Replacement Objects for the object database
 ///
-/// The environment variables `GIT_REPLACE_REF_BASE`, `GIT_NO_REPLACE_OBJECTS`, and `GIT_ALLOC_LIMIT` are mapped to
-/// `gitoxide.objects.replaceRefBase`, `gitoxide.objects.noReplace`, and `gitoxide.objects.allocLimit` respectively and then
-/// interpreted exactly as their environment variable counterparts.
+/// The environment variables `GIT_REPLACE_REF_BASE`, `GIT_NO_REPLACE_OBJECTS`, `GIT_ALLOC_LIMIT` and `GIT_NO_LAZY_FETCH` are mapped to
+/// `gitoxide.objects.replaceRefBase`, `gitoxide.objects.noReplace`, `gitoxide.objects.allocLimit` and `gitoxide.objects.noLazyFetch`
+/// respectively and then interpreted exactly as their environment variable counterparts.
 ///
 /// Use [Permissions] to control which environment variables can be read, and config-overrides to control these values programmatically.
 #[derive(Clone)]
@@ -59,6 +59,8 @@ pub enum Error {
     EnvironmentAccessDenied(#[from] gix_sec::permission::Error<std::path::PathBuf>),
     #[error(transparent)]
     PrefixNotRelative(#[from] gix_path::relative_path::Error),
+    #[error("Could not open the reference store")]
+    RefStore(#[from] gix_ref::store::general::init::Error),
 }
 
 mod options;
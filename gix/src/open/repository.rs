@@ -228,13 +228,15 @@ impl ThreadSafeRepository {
                 object_hash,
                 precompose_unicode: repo_config.precompose_unicode,
                 prohibit_windows_device_names: repo_config.protect_windows,
+                backend: repo_config.ref_storage,
             };
             match &common_dir {
                 Some(common_dir) => {
-                    crate::RefStore::for_linked_worktree(git_dir.to_owned(), common_dir.into(), ref_store_init_opts)
+                    gix_ref::Store::for_linked_worktree(git_dir.to_owned(), common_dir.into(), ref_store_init_opts)?
                 }
-                None => crate::RefStore::at(git_dir.to_owned(), ref_store_init_opts),
+                None => gix_ref::Store::at(git_dir.to_owned(), ref_store_init_opts)?,
             }
+            .to_handle()
         };
         let head = refs.find("HEAD").ok();
         let git_install_dir = crate::path::install_dir().ok();
@@ -429,8 +431,11 @@ impl ThreadSafeRepository {
             config.resolved = resolved.into();
         }
 
-        refs.write_reflog = config::cache::util::reflog_or_default(config.reflog, worktree_dir.is_some());
-        refs.namespace.clone_from(&config.refs_namespace);
+        refs.set_write_reflog(config::cache::util::reflog_or_default(
+            config.reflog,
+            worktree_dir.is_some(),
+        ));
+        refs.set_namespace(config.refs_namespace.clone());
         let prefix = replacement_objects_refs_prefix(&config.resolved, lenient_config, filter_config_section)?;
 
         if *git_dir_trust == gix_sec::Trust::Reduced && config.alloc_limit_bytes.is_none() {
@@ -486,18 +491,40 @@ impl ThreadSafeRepository {
         };
         let replacements = replacements.unwrap_or_default();
 
+        let objects = gix_odb::Store::at_opts(
+            common_dir_ref.join("objects"),
+            &mut replacements.into_iter(),
+            gix_odb::store::init::Options {
+                slots: object_store_slots,
+                object_hash: config.object_hash,
+                use_multi_pack_index: config.use_multi_pack_index,
+                alloc_limit_bytes: config.alloc_limit_bytes,
+                current_dir: current_dir.to_owned().into(),
+            },
+        )?;
+        #[cfg(feature = "blocking-network-client")]
+        {
+            use crate::config::cache::util::ApplyLeniency;
+            let no_lazy_fetch = config
+                .resolved
+                .boolean_filter(gitoxide::Objects::NO_LAZY_FETCH, &mut filter_config_section)
+                .map(|res| gitoxide::Objects::NO_LAZY_FETCH.enrich_error(res))
+                .transpose()
+                .with_leniency(lenient_config)
+                .map_err(config::Error::from)?
+                .unwrap_or(false);
+            if !no_lazy_fetch
+                && !crate::remote::promisor::remote_names(&config.resolved, filter_config_section).is_empty()
+            {
+                objects.set_promisor(Some(std::sync::Arc::new(crate::remote::promisor::LazyFetch {
+                    git_dir: git_dir.clone(),
+                    options: options.clone(),
+                })));
+            }
+        }
+
         Ok(ThreadSafeRepository {
-            objects: OwnShared::new(gix_odb::Store::at_opts(
-                common_dir_ref.join("objects"),
-                &mut replacements.into_iter(),
-                gix_odb::store::init::Options {
-                    slots: object_store_slots,
-                    object_hash: config.object_hash,
-                    use_multi_pack_index: config.use_multi_pack_index,
-                    alloc_limit_bytes: config.alloc_limit_bytes,
-                    current_dir: current_dir.to_owned().into(),
-                },
-            )?),
+            objects: OwnShared::new(objects),
             common_dir,
             refs,
             work_tree: worktree_dir,
@@ -76,7 +76,17 @@ impl<'repo> Remote<'repo> {
     /// Connect to the url suitable for `direction` and return a handle through which operations can be performed.
     ///
     /// Note that the `protocol.version` configuration key affects the transport protocol used to connect,
-    /// with `2` being the default.
+    /// with `2` being the default. Connections for [pushing](crate::remote::Direction::Push) use version `1` at most.
+    ///
+    /// `file://` URLs are served by spawning `git`, unless the `in-process-file-transport` feature is enabled and
+    /// [`gitoxide.inProcessFileTransport`](crate::config::tree::Gitoxide::IN_PROCESS_FILE_TRANSPORT) is `true` to opt into
+    /// the in-process transport for fetches, which has known limitations. Pushes always spawn `git receive-pack`.
+    ///
+    /// With blocking I/O, URLs with other schemes than the ones known to `git` are reached through a `git-remote-<scheme>`
+    /// remote helper, which is invoked with the name of this remote and operates on this repository.
+    /// Just like in `git`, these schemes are allowed according to `protocol.allow` and `protocol.<scheme>.allow`, which default
+    /// to `user`, so they are denied if `GIT_PROTOCOL_FROM_USER` is `0`. The `ext` scheme is never allowed by default.
+    /// Remote helpers implemented in-process can be used by creating their transport by hand.
     ///
     /// The transport used for connection can be configured via `transport_mut().configure()` assuming the actually
     /// used transport is well known. If that's not the case, the transport can be created by hand and passed to
@@ -88,6 +98,11 @@ impl<'repo> Remote<'repo> {
         direction: crate::remote::Direction,
     ) -> Result<Connection<'_, 'static, 'repo, Box<dyn Transport + Send>>, Error> {
         let (url, version) = self.sanitized_url_and_version(direction)?;
+        #[cfg(feature = "in-process-file-transport")]
+        if url.scheme == gix_url::Scheme::File && self.repo.config.in_process_file_transport() {
+            let transport = crate::remote::in_process::connect(url, version, self.repo.config.trace_packet());
+            return Ok(self.to_connection_with_transport(Box::new(transport)));
+        }
         #[cfg(feature = "blocking-network-client")]
         let scheme_is_ssh = url.scheme == gix_url::Scheme::Ssh;
         let transport = connect::connect(
@@ -99,6 +114,11 @@ impl<'repo> Remote<'repo> {
                     .then(|| self.repo.ssh_connect_options())
                     .transpose()?
                     .unwrap_or_default(),
+                #[cfg(feature = "blocking-network-client")]
+                remote_helper: gix_transport::client::blocking_io::remote_helper::connect::Options {
+                    git_dir: Some(self.repo.git_dir().to_owned()),
+                    remote_name: self.name().map(|name| name.as_bstr().to_owned()),
+                },
                 trace: self.repo.config.trace_packet(),
             },
         )
@@ -141,9 +161,13 @@ impl<'repo> Remote<'repo> {
             Ok(url)
         }
 
-        let version = crate::config::tree::Protocol::VERSION
+        let mut version = crate::config::tree::Protocol::VERSION
             .try_into_protocol_version(self.repo.config.resolved.integer(Protocol::VERSION))
             .map_err(|err| Error::UnknownProtocol { source: err })?;
+        if direction == crate::remote::Direction::Push {
+            // There is no push in protocol V2, which `git` handles the same way.
+            version = version.min(gix_protocol::transport::Protocol::V1);
+        }
 
         let url = self.url(direction).ok_or(Error::MissingUrl { direction })?.to_owned();
         if !self.repo.config.url_scheme()?.allow(&url.scheme) {
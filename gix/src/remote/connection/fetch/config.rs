@@ -4,14 +4,13 @@ use crate::{
     config::{cache::util::ApplyLeniency, tree::Pack},
 };
 
-pub fn index_threads(repo: &Repository) -> Result<Option<usize>, Error> {
-    Ok(repo
-        .config
+pub fn index_threads(repo: &Repository) -> Result<Option<usize>, crate::config::unsigned_integer::Error> {
+    repo.config
         .resolved
         .integer_filter(Pack::THREADS, &mut repo.filter_config_section())
         .map(|threads| Pack::THREADS.try_into_usize(threads))
         .transpose()
-        .with_leniency(repo.options.lenient_config)?)
+        .with_leniency(repo.options.lenient_config)
 }
 
 pub fn pack_index_version(repo: &Repository) -> Result<gix_pack::index::Version, Error> {
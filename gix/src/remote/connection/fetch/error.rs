@@ -36,6 +36,22 @@ pub enum Error {
     RejectShallowRemoteConfig(#[from] config::boolean::Error),
     #[error(transparent)]
     NegotiationAlgorithmConfig(#[from] config::key::GenericErrorWithValue),
+    #[error("Could not obtain configuration to learn if FETCH_HEAD should be written")]
+    WriteFetchHeadConfig(#[source] config::boolean::Error),
+    #[error("Could not find HEAD to determine which fetched references to merge")]
+    FindHead(#[from] crate::reference::find::existing::Error),
+    #[error("Could not obtain the upstream branch to determine which fetched references to merge")]
+    UpstreamBranch(#[from] crate::repository::branch_remote_ref_name::Error),
+    #[error("Failed to mark the pack at \"{}\" as received from a promisor remote", path.display())]
+    WritePromisorFile {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to write FETCH_HEAD at \"{}\"", path.display())]
+    WriteFetchHead {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
 }
 
 impl gix_protocol::transport::IsSpuriousError for Error {
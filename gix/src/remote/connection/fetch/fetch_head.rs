@@ -0,0 +1,155 @@
+use std::io::Write;
+
+use gix_fetchhead::Entry;
+
+use super::Error;
+use crate::{
+    Repository,
+    config::tree::Fetch,
+    remote::{
+        self,
+        fetch::{
+            FetchHeadMerge, RefMap, Tags, WriteFetchHead,
+            refmap::{Mapping, SpecIndex},
+            refs::update::{self, Mode},
+        },
+    },
+    types::RemoteDetached,
+};
+
+/// Determine how to write `FETCH_HEAD`, using `fetch.writeFetchHead` if `mode` isn't set.
+pub(super) fn mode(repo: &Repository, mode: Option<WriteFetchHead>) -> Result<WriteFetchHead, Error> {
+    if let Some(mode) = mode {
+        return Ok(mode);
+    }
+    let enabled = repo
+        .config
+        .resolved
+        .boolean_filter(Fetch::WRITE_FETCH_HEAD, &mut repo.filter_config_section())
+        .map(|value| Fetch::WRITE_FETCH_HEAD.enrich_error(value))
+        .transpose()
+        .map_err(Error::WriteFetchHeadConfig)?
+        .unwrap_or(true);
+    Ok(if enabled {
+        WriteFetchHead::Overwrite
+    } else {
+        WriteFetchHead::Never
+    })
+}
+
+/// Write all fetched objects of `ref_map` along with their `updates` to `FETCH_HEAD` according to `mode`, and mark the ones to merge
+/// according to `merge`, just like `git fetch` does.
+pub(super) fn write(
+    repo: &Repository,
+    mode: WriteFetchHead,
+    merge: FetchHeadMerge,
+    remote: &RemoteDetached,
+    ref_map: &RefMap,
+    updates: &update::Outcome,
+) -> Result<(), Error> {
+    let append = match mode {
+        WriteFetchHead::Never => return Ok(()),
+        WriteFetchHead::Overwrite => false,
+        WriteFetchHead::Append => true,
+    };
+    let url = remote
+        .fetch_url()
+        .map(|url| {
+            let mut url = url.clone();
+            url.set_user(None);
+            url.set_password(None);
+            gix_fetchhead::display_url(url.to_bstring().as_ref()).to_owned()
+        })
+        .unwrap_or_default();
+    let tag_refspec = remote.fetch_tags.to_refspec();
+    let merge_index = match merge {
+        FetchHeadMerge::Configured => configured_merge_index(repo, remote, &ref_map.mappings)?,
+        FetchHeadMerge::AllMatches => None,
+    };
+
+    let mut entries: Vec<_> = updates
+        .iter_mapping_updates(&ref_map.mappings, remote.fetch_refspecs(), &ref_map.extra_refspecs)
+        .enumerate()
+        .filter_map(|(index, (update, mapping, spec, _edit))| {
+            let id = mapping.remote.as_id()?.to_owned();
+            let is_tag = matches!(mapping.spec_index, SpecIndex::Implicit(_))
+                && tag_refspec.is_some_and(|tag_spec| spec.is_some_and(|spec| spec.to_ref() == tag_spec));
+            let keep = match update.mode {
+                Mode::RejectedSourceObjectNotFound { .. } | Mode::ImplicitTagNotSentByRemote => false,
+                // Tags that are only fetched as they point into the fetched history are only mentioned if they are new.
+                _ if is_tag && matches!(remote.fetch_tags, Tags::Included) => matches!(update.mode, Mode::New),
+                _ => true,
+            };
+            keep.then(|| Entry {
+                id,
+                for_merge: !is_tag
+                    && match merge {
+                        FetchHeadMerge::Configured => merge_index == Some(index),
+                        FetchHeadMerge::AllMatches => true,
+                    },
+                name: mapping
+                    .remote
+                    .as_name()
+                    .map_or_else(|| id.to_string().into(), ToOwned::to_owned),
+                url: url.clone(),
+            })
+        })
+        .collect();
+    entries.sort_by_key(|entry| !entry.for_merge);
+
+    let path = repo.git_dir().join("FETCH_HEAD");
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&path)
+        .map_err(|err| Error::WriteFetchHead {
+            path: path.clone(),
+            source: err,
+        })?;
+    let mut buf = Vec::new();
+    gix_fetchhead::FetchHead { entries }
+        .write_to(&mut buf)
+        .and_then(|_| file.write_all(&buf))
+        .map_err(|err| Error::WriteFetchHead { path, source: err })
+}
+
+/// Return the index of the mapping to merge, which is the one for the upstream branch of the current branch if it's
+/// fetched from `remote`. Without upstream branch, it's the first mapping of the first refspec unless it's a pattern.
+fn configured_merge_index(
+    repo: &Repository,
+    remote: &RemoteDetached,
+    mappings: &[Mapping],
+) -> Result<Option<usize>, Error> {
+    let head_name = repo.head_name()?;
+    let upstream = head_name
+        .as_ref()
+        .and_then(|name| repo.branch_remote_ref_name(name.as_ref(), remote::Direction::Fetch))
+        .transpose()?;
+    Ok(match upstream {
+        Some(upstream) => {
+            let head_name = head_name.expect("upstream branches are only known for named branches");
+            let upstream_remote = repo.branch_remote_name(head_name.shorten(), remote::Direction::Fetch);
+            if upstream_remote.as_ref().map(remote::Name::as_bstr) != remote.name().map(remote::Name::as_bstr) {
+                return Ok(None);
+            }
+            mappings
+                .iter()
+                .position(|mapping| mapping.remote.as_name() == Some(upstream.as_bstr()))
+        }
+        None => {
+            let first_spec_is_pattern = remote
+                .fetch_refspecs()
+                .first()
+                .and_then(|spec| spec.to_ref().remote().map(|remote| remote.contains(&b'*')))
+                .unwrap_or(true);
+            if first_spec_is_pattern {
+                return Ok(None);
+            }
+            mappings
+                .iter()
+                .position(|mapping| mapping.spec_index == SpecIndex::ExplicitInRemote(0))
+        }
+    })
+}
@@ -18,7 +18,7 @@ use crate::{
 mod error;
 pub use error::Error;
 
-use crate::remote::fetch::WritePackedRefs;
+use crate::remote::fetch::{FetchHeadMerge, WriteFetchHead, WritePackedRefs};
 
 /// The way reflog messages should be composed whenever a ref is written with recent objects from a remote.
 pub enum RefLogMessage {
@@ -145,7 +145,8 @@ where
         options: ref_map::Options,
     ) -> Result<Prepare<'auth, 'repo, T>, prepare::Error> {
         let repo = self.remote.repo;
-        let inner = self.into_detached().prepare_fetch(repo, progress, options).await?;
+        let mut inner = self.into_detached().prepare_fetch(repo, progress, options).await?;
+        inner.write_fetch_head = None;
         Ok(Prepare { inner, repo })
     }
 }
@@ -173,6 +174,9 @@ where
             reflog_message: None,
             write_packed_refs: WritePackedRefs::Never,
             shallow: Default::default(),
+            write_fetch_head: Some(WriteFetchHead::Never),
+            fetch_head_merge: Default::default(),
+            filter: None,
         })
     }
 }
@@ -196,9 +200,42 @@ where
     pub(crate) fn ref_map(&self) -> &RefMap {
         &self.ref_map
     }
+
+    /// Obtain the list of bundles the remote advertises via the `bundle-uri` command, or `None` if the remote
+    /// doesn't support it.
+    ///
+    /// Must be called before [`receive()`](Self::receive()).
+    #[gix_protocol::maybe_async::maybe_async]
+    pub(crate) async fn bundle_list(
+        &mut self,
+        repo: &crate::Repository,
+        progress: &mut impl Progress,
+    ) -> Result<Option<gix_protocol::bundle_uri::List>, gix_protocol::bundle_uri::Error> {
+        let con = self.con.as_mut().expect("receive() wasn't called yet");
+        let Some(handshake) = con.handshake.as_ref() else {
+            return Ok(None);
+        };
+        if handshake.server_protocol_version != gix_protocol::transport::Protocol::V2
+            || !gix_protocol::bundle_uri::is_advertised(&handshake.capabilities)
+        {
+            return Ok(None);
+        }
+        let command = gix_protocol::BundleUriCommand::new(&handshake.capabilities, repo.config.user_agent_tuple());
+
+        #[cfg(feature = "async-network-client")]
+        let list = command
+            .invoke_async(&mut con.transport.inner, progress, con.trace)
+            .await?;
+
+        #[cfg(feature = "blocking-network-client")]
+        let list = command.invoke_blocking(&mut con.transport.inner, progress, con.trace)?;
+
+        Ok(Some(list))
+    }
 }
 
-mod config;
+pub(crate) mod config;
+mod fetch_head;
 mod receive_pack;
 ///
 #[path = "update_refs/mod.rs"]
@@ -224,6 +261,11 @@ where
     reflog_message: Option<RefLogMessage>,
     write_packed_refs: WritePackedRefs,
     shallow: remote::fetch::Shallow,
+    /// If `None`, `fetch.writeFetchHead` determines if `FETCH_HEAD` is written.
+    write_fetch_head: Option<WriteFetchHead>,
+    fetch_head_merge: FetchHeadMerge,
+    /// If `None`, `remote.<name>.partialCloneFilter` is used if the remote is a promisor remote.
+    filter: Option<String>,
 }
 
 /// Builder
@@ -262,6 +304,32 @@ where
         self.inner.shallow = shallow;
         self
     }
+
+    /// Define if and how `FETCH_HEAD` is written, overriding `fetch.writeFetchHead`.
+    ///
+    /// *Has no effect in dry-run mode, which never writes `FETCH_HEAD`.*
+    pub fn with_write_fetch_head(mut self, mode: WriteFetchHead) -> Self {
+        self.inner.write_fetch_head = Some(mode);
+        self
+    }
+
+    /// Define which of the fetched references are marked for merge in `FETCH_HEAD`.
+    pub fn with_fetch_head_merge(mut self, merge: FetchHeadMerge) -> Self {
+        self.inner.fetch_head_merge = merge;
+        self
+    }
+
+    /// Ask the remote to omit objects matching the filter `spec`, like `blob:none` or `blob:limit=1m`, to perform a partial fetch.
+    ///
+    /// The received pack is marked as obtained from a promisor remote, and objects that were omitted will be fetched on demand
+    /// if the remote is configured as promisor remote, see `remote.<name>.promisor`.
+    /// If unset, `remote.<name>.partialCloneFilter` is used if the remote is a promisor remote.
+    ///
+    /// *Note that the filter is ignored if the remote doesn't support filtering.*
+    pub fn with_filter(mut self, spec: impl Into<String>) -> Self {
+        self.inner.filter = Some(spec.into());
+        self
+    }
 }
 
 /// Builder
@@ -288,4 +356,9 @@ where
         self.shallow = shallow;
         self
     }
+
+    pub(crate) fn with_filter(mut self, spec: Option<String>) -> Self {
+        self.filter = spec;
+        self
+    }
 }
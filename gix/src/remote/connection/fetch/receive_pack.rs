@@ -12,8 +12,9 @@ use crate::{
         cache::util::ApplyLeniency,
         tree::{Clone, Fetch},
     },
+    remote,
     remote::{
-        connection::fetch::{PrepareDetached, config},
+        connection::fetch::{PrepareDetached, config, fetch_head},
         fetch,
         fetch::{Error, Outcome, Prepare, RefLogMessage, Status, negotiate::Algorithm, outcome, refs},
     },
@@ -68,6 +69,8 @@ where
     /// ### Configuration
     ///
     /// - `gitoxide.userAgent` is read to obtain the application user agent for git servers and for HTTP servers as well.
+    /// - `fetch.writeFetchHead` is read to learn if `FETCH_HEAD` should be written, unless overridden with
+    ///   [`Prepare::with_write_fetch_head()`].
     ///
     #[gix_protocol::maybe_async::maybe_async]
     pub async fn receive<P>(self, progress: P, should_interrupt: &AtomicBool) -> Result<Outcome, Error>
@@ -116,6 +119,18 @@ where
             });
         }
 
+        let is_promisor_remote = con.remote.name().is_some_and(|name| {
+            remote::promisor::is_promisor_remote(&repo.config.resolved, repo.filter_config_section(), name.as_bstr())
+        });
+        let filter = self.filter.take().or_else(|| {
+            con.remote.name().filter(|_| is_promisor_remote).and_then(|name| {
+                remote::promisor::partial_clone_filter(
+                    &repo.config.resolved,
+                    repo.filter_config_section(),
+                    name.as_bstr(),
+                )
+            })
+        });
         let fetch_options = gix_protocol::fetch::Options {
             shallow_file: repo.shallow_file(),
             shallow: &self.shallow,
@@ -127,6 +142,7 @@ where
                 .map(|val| Clone::REJECT_SHALLOW.enrich_error(val))
                 .transpose()?
                 .unwrap_or(false),
+            filter: filter.as_deref(),
         };
         let context = gix_protocol::fetch::Context {
             handshake: &mut handshake,
@@ -228,6 +244,35 @@ where
             self.write_packed_refs,
         )?;
 
+        if matches!(self.dry_run, fetch::DryRun::No) {
+            let mode = fetch_head::mode(repo, self.write_fetch_head)?;
+            fetch_head::write(
+                repo,
+                mode,
+                self.fetch_head_merge,
+                &con.remote,
+                &self.ref_map,
+                &update_refs,
+            )?;
+        }
+
+        if let Some(bundle) = write_pack_bundle.as_ref() {
+            if filter.is_some() || is_promisor_remote {
+                if let Some(path) = bundle.index_path.as_deref() {
+                    gix_odb::promisor::mark_pack(
+                        path,
+                        self.ref_map.mappings.iter().filter_map(|mapping| {
+                            Some((mapping.remote.as_id()?.to_owned(), mapping.remote.as_name()?))
+                        }),
+                    )
+                    .map_err(|source| Error::WritePromisorFile {
+                        path: path.with_extension(gix_odb::promisor::EXTENSION),
+                        source,
+                    })?;
+                }
+            }
+        }
+
         if let Some(bundle) = write_pack_bundle.as_mut() {
             if !update_refs.edits.is_empty() || bundle.index.num_objects == 0 {
                 if let Some(path) = bundle.keep_path.take() {
@@ -258,7 +303,7 @@ where
 
 struct Negotiate<'a, 'b, 'c> {
     objects: &'a crate::OdbHandle,
-    refs: &'a gix_ref::file::Store,
+    refs: &'a gix_ref::store::Handle,
     graph: &'a mut gix_negotiate::Graph<'b, 'c>,
     alternates: Vec<PathBuf>,
     ref_map: &'a gix_protocol::fetch::RefMap,
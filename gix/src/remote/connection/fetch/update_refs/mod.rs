@@ -204,7 +204,7 @@ pub(crate) fn update(
                                 )
                             }
                             Err(crate::reference::peel::Error::ToId(gix_ref::peel::to_id::Error::FollowToObject(
-                                gix_ref::peel::to_object::Error::Follow(_),
+                                gix_ref::peel::to_object::Error::FollowInStore(_),
                             ))) => {
                                 // An unborn reference, always allow it to be changed to whatever the remote wants.
                                 (
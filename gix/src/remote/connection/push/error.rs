@@ -0,0 +1,24 @@
+use crate::config;
+
+/// The error returned by [`send()`](super::Prepare::send()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Push(#[from] gix_protocol::push::Error),
+    #[error("The value to configure pack threads should be 0 to auto-configure or the amount of threads to use")]
+    PackThreads(#[from] config::unsigned_integer::Error),
+    #[error(transparent)]
+    FindTrackingRef(#[from] crate::reference::find::Error),
+    #[error("Could not update the remote-tracking references")]
+    UpdateTrackingRefs(#[from] crate::reference::edit::Error),
+}
+
+impl gix_protocol::transport::IsSpuriousError for Error {
+    fn is_spurious(&self) -> bool {
+        match self {
+            Error::Push(err) => err.is_spurious(),
+            _ => false,
+        }
+    }
+}
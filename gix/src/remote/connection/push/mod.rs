@@ -0,0 +1,312 @@
+use gix_hash::ObjectId;
+use gix_ref::FullName;
+use gix_transport::client::blocking_io::Transport;
+
+use crate::{
+    Progress,
+    bstr::BString,
+    remote::{Connection, Direction},
+};
+
+mod error;
+pub use error::Error;
+
+mod resolve;
+mod send_pack;
+
+/// An expectation about the value of a remote reference, which has to hold for it to be overwritten, just like
+/// `git push --force-with-lease=<refname>[:<expect>]` does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lease {
+    /// The name of the reference on the remote, like `refs/heads/main` or `main`.
+    pub name: BString,
+    /// The value the remote reference is expected to have, or `None` to expect the value of the remote-tracking reference
+    /// that corresponds to `name`.
+    ///
+    /// If the expected value or the remote-tracking reference doesn't exist, the remote reference is expected to not exist either.
+    pub expected: Option<ObjectId>,
+}
+
+/// Options for use in [`Connection::prepare_push()`].
+#[derive(Default, Debug, Clone)]
+pub struct Options {
+    /// The refspecs to push, overriding the push refspecs of the remote, or `push.default` if these aren't set either.
+    pub refspecs: Vec<gix_refspec::RefSpec>,
+    /// If `true`, allow all updates, even if they aren't fast-forwards, like `git push --force` does.
+    pub force: bool,
+    /// Allow updates to the given references only if they have the expected value, even if they aren't fast-forwards.
+    pub force_with_lease: Vec<Lease>,
+    /// If `true`, the remote is asked to perform all updates or none of them, and nothing is sent if any of them
+    /// is rejected locally.
+    pub atomic: bool,
+    /// Strings to transmit to the remote for use by its hooks, like `git push --push-option` does.
+    pub push_options: Vec<BString>,
+    /// Parameters in the form of `(name, optional value)` to add to the handshake.
+    ///
+    /// This is useful in case of custom servers.
+    pub handshake_parameters: Vec<(String, Option<String>)>,
+}
+
+/// The status of a single [update](Update).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// The update is going to be sent to the remote.
+    Pending,
+    /// The remote performed the update.
+    Ok,
+    /// The remote reference already has the value to push, so there is nothing to do.
+    UpToDate,
+    /// The reference to delete doesn't exist on the remote.
+    RejectedNoSuchRef,
+    /// The update isn't a fast-forward, and it isn't forced.
+    RejectedNonFastForward,
+    /// The remote reference is a tag that exists already, and it isn't forced.
+    RejectedAlreadyExists,
+    /// The current value of the remote reference isn't available locally, so it's unknown if the update is a fast-forward.
+    ///
+    /// Fetching from the remote first typically resolves this.
+    RejectedFetchFirst,
+    /// The remote reference or the value to push isn't a commit, so it can't be a fast-forward and needs to be forced.
+    RejectedNeedsForce,
+    /// The remote reference doesn't have the value that was expected by its [lease](Lease).
+    RejectedStale,
+    /// Updates weren't sent as other updates of an atomic push were rejected.
+    AtomicPushFailed,
+    /// The remote didn't perform the update, for the given `reason`.
+    RemoteRejected {
+        /// The reason for the rejection as provided by the remote.
+        reason: BString,
+    },
+}
+
+impl Status {
+    /// Return `true` if the update was rejected, either locally or by the remote.
+    pub fn is_rejected(&self) -> bool {
+        !matches!(self, Status::Pending | Status::Ok | Status::UpToDate)
+    }
+}
+
+/// A reference update on the remote, as derived from a refspec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Update {
+    /// The full name of the local reference whose value is pushed, or `None` if an object was specified directly or if
+    /// the remote reference is deleted.
+    pub local: Option<FullName>,
+    /// The full name of the reference on the remote.
+    pub remote: FullName,
+    /// The value of the remote reference as advertised by the remote, or the null id if it doesn't exist.
+    pub old: ObjectId,
+    /// The value to set the remote reference to, or the null id if it is deleted.
+    pub new: ObjectId,
+    /// The status of this update.
+    pub status: Status,
+}
+
+impl Update {
+    /// Return `true` if this update deletes the remote reference.
+    pub fn is_delete(&self) -> bool {
+        self.new.is_null()
+    }
+}
+
+/// The outcome of [`Prepare::send()`].
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// All updates along with their final status.
+    pub updates: Vec<Update>,
+    /// The status report of the remote, or `None` if nothing was sent or if the remote doesn't support sending one.
+    pub report: Option<gix_protocol::push::Report>,
+    /// The edits made to remote-tracking references to reflect the updates that were performed on the remote.
+    pub tracking_ref_edits: Vec<gix_ref::transaction::RefEdit>,
+}
+
+impl Outcome {
+    /// Return `true` if none of the updates were rejected.
+    pub fn is_ok(&self) -> bool {
+        !self.updates.iter().any(|update| update.status.is_rejected())
+    }
+}
+
+///
+pub mod prepare {
+    use crate::bstr::BString;
+
+    /// The error returned by [`prepare_push()`](super::Connection::prepare_push()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Failed to configure the transport before connecting to {url:?}")]
+        GatherTransportConfig {
+            url: BString,
+            source: crate::config::transport::Error,
+        },
+        #[error("Failed to configure the transport layer")]
+        ConfigureTransport(#[from] Box<dyn std::error::Error + Send + Sync + 'static>),
+        #[error(transparent)]
+        ConfigureCredentials(#[from] crate::config::credential_helpers::Error),
+        #[error(transparent)]
+        Handshake(#[from] gix_protocol::handshake::Error),
+        #[error(
+            "The remote didn't advertise its references, which happens if it doesn't support pushing with protocol version 0 or 1"
+        )]
+        MissingRemoteRefs,
+        #[error("Nothing to push as there are no refspecs and `push.default` is set to 'nothing'")]
+        NothingToPush,
+        #[error("Could not determine the refspecs to push with")]
+        PushDefault(#[from] crate::config::key::GenericErrorWithValue),
+        #[error("Cannot push the current branch as HEAD is detached")]
+        DetachedHead,
+        #[error("The current branch {branch:?} has no upstream branch, which is required by `push.default=upstream`")]
+        MissingUpstream { branch: BString },
+        #[error(transparent)]
+        FindHead(#[from] crate::reference::find::existing::Error),
+        #[error(transparent)]
+        UpstreamBranch(#[from] crate::repository::branch_remote_ref_name::Error),
+        #[error(
+            "The upstream branch {upstream:?} of the current branch doesn't match its name, which is required by `push.default=simple`"
+        )]
+        UpstreamNameMismatch { upstream: BString },
+        #[error(
+            "The source {source_spec:?} of a refspec doesn't match any reference and can't be resolved to an object"
+        )]
+        UnresolvedSource {
+            source_spec: BString,
+            source: Option<Box<dyn std::error::Error + Send + Sync + 'static>>,
+        },
+        #[error(
+            "The destination {destination:?} isn't a full reference name, and it can't be inferred from the source"
+        )]
+        UnqualifiedDestination { destination: BString },
+        #[error("Cannot push {source_spec:?} without a destination as it isn't a reference")]
+        MissingDestination { source_spec: BString },
+        #[error("Deleting references by pattern isn't supported, got {pattern:?}")]
+        DeletePattern { pattern: BString },
+        #[error(transparent)]
+        ValidateName(#[from] gix_validate::reference::name::Error),
+        #[error(transparent)]
+        FindReference(#[from] crate::reference::find::Error),
+        #[error(transparent)]
+        FollowReference(#[from] gix_ref::store::find::existing::Error),
+        #[error(transparent)]
+        IterReferences(#[from] crate::reference::iter::Error),
+        #[error(transparent)]
+        IterReferencesInit(#[from] crate::reference::iter::init::Error),
+        #[error("Could not read a local reference")]
+        IterReference(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+        #[error(transparent)]
+        FindObject(#[from] crate::object::find::existing::Error),
+        #[error(transparent)]
+        FindHeader(#[from] crate::object::find::existing::with_conversion::Error),
+        #[error(transparent)]
+        OpenCommitGraph(#[from] crate::repository::commit_graph_if_enabled::Error),
+        #[error("Could not determine if the remote reference can be fast-forwarded")]
+        MergeBase(#[from] gix_revision::merge_base::Error),
+    }
+
+    impl gix_protocol::transport::IsSpuriousError for Error {
+        fn is_spurious(&self) -> bool {
+            match self {
+                Error::Handshake(err) => err.is_spurious(),
+                _ => false,
+            }
+        }
+    }
+}
+
+/// A structure to hold the result of the handshake with the remote along with the reference updates to send,
+/// ready to [send a pack](Prepare::send()).
+pub struct Prepare<'remote, 'auth, 'repo, T>
+where
+    T: Transport,
+{
+    con: Connection<'remote, 'auth, 'repo, T>,
+    handshake: gix_protocol::Handshake,
+    updates: Vec<Update>,
+    atomic: bool,
+    push_options: Vec<BString>,
+}
+
+impl<'remote, 'auth, 'repo, T> Connection<'remote, 'auth, 'repo, T>
+where
+    T: Transport,
+{
+    /// Perform a handshake with the remote and determine the reference updates to send with `options`, by matching local references
+    /// against the push refspecs, or by using `push.default` if there are none.
+    /// Note that at this point, the `transport` should already be configured using the [`transport_mut()`][Self::transport_mut()]
+    /// method, as it will be consumed here.
+    ///
+    /// Updates that would be rejected by the remote are rejected here already, so they aren't sent.
+    ///
+    /// ### Configuration
+    ///
+    /// - `push.default` determines what to push if neither `options` nor the remote provide refspecs.
+    /// - `gitoxide.userAgent` is read to obtain the application user agent for git servers and for HTTP servers as well.
+    #[allow(clippy::result_large_err)]
+    pub fn prepare_push(
+        mut self,
+        mut progress: impl Progress,
+        options: Options,
+    ) -> Result<Prepare<'remote, 'auth, 'repo, T>, prepare::Error> {
+        let _span = gix_trace::coarse!("remote::Connection::prepare_push()");
+        let repo = self.remote.repo;
+        let mut credentials_storage;
+        let url = self.transport.inner.to_url();
+        let authenticate = match self.authenticate.as_mut() {
+            Some(f) => f,
+            None => {
+                let url = self.remote.url(Direction::Push).map_or_else(
+                    || gix_url::parse(url.as_ref()).expect("valid URL to be provided by transport"),
+                    ToOwned::to_owned,
+                );
+                credentials_storage = self.configured_credentials(url)?;
+                &mut credentials_storage
+            }
+        };
+
+        if self.transport_options.is_none() {
+            self.transport_options = repo
+                .transport_options(url.as_ref(), self.remote.name().map(crate::remote::Name::as_bstr))
+                .map_err(|err| prepare::Error::GatherTransportConfig {
+                    source: err,
+                    url: url.into_owned(),
+                })?;
+        }
+        if let Some(config) = self.transport_options.as_ref() {
+            self.transport.inner.configure(&**config)?;
+        }
+        let handshake = gix_protocol::handshake(
+            &mut self.transport.inner,
+            gix_transport::Service::ReceivePack,
+            authenticate,
+            options.handshake_parameters.clone(),
+            &mut progress,
+        )?;
+        let remote_refs = handshake.refs.as_deref().ok_or(prepare::Error::MissingRemoteRefs)?;
+        let updates = resolve::updates(self.remote, remote_refs, &options)?;
+        Ok(Prepare {
+            con: self,
+            handshake,
+            updates,
+            atomic: options.atomic,
+            push_options: options.push_options,
+        })
+    }
+}
+
+/// Access
+impl<T> Prepare<'_, '_, '_, T>
+where
+    T: Transport,
+{
+    /// Return the reference updates that were determined, along with their status.
+    ///
+    /// Only those with [`Status::Pending`] will be sent.
+    pub fn updates(&self) -> &[Update] {
+        &self.updates
+    }
+
+    /// Return the outcome of the handshake with the remote.
+    pub fn handshake(&self) -> &gix_protocol::Handshake {
+        &self.handshake
+    }
+}
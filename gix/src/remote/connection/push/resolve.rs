@@ -0,0 +1,382 @@
+use std::borrow::Cow;
+
+use gix_hash::ObjectId;
+use gix_ref::{Category, FullName, FullNameRef, PartialNameRef};
+use gix_refspec::{Instruction, RefSpec, instruction::Push};
+
+use super::{Options, Status, Update, prepare::Error};
+use crate::{
+    Remote, Repository,
+    bstr::{BStr, BString, ByteSlice, ByteVec},
+    config::{cache::util::ApplyLeniencyDefault, tree},
+    push,
+    remote::Direction,
+};
+
+/// An update as requested by a refspec, before it was validated.
+struct Candidate {
+    local: Option<FullName>,
+    remote: FullName,
+    new: ObjectId,
+    force: bool,
+}
+
+/// Turn the push refspecs into updates, based on the references of the remote as `remote_refs`, and reject all of those
+/// that the remote is expected to reject.
+pub(super) fn updates(
+    remote: &Remote<'_>,
+    remote_refs: &[gix_protocol::handshake::Ref],
+    options: &Options,
+) -> Result<Vec<Update>, Error> {
+    let repo = remote.repo;
+    let remote_refs: Vec<(&BStr, ObjectId)> = remote_refs
+        .iter()
+        .filter_map(|r| {
+            let (name, target, _peeled) = r.unpack();
+            Some((name, target?.to_owned()))
+        })
+        .collect();
+    let specs: Cow<'_, [RefSpec]> = if !options.refspecs.is_empty() {
+        options.refspecs.as_slice().into()
+    } else if !remote.push_specs.is_empty() {
+        remote.push_specs.as_slice().into()
+    } else {
+        push_default_specs(repo)?.into()
+    };
+
+    let excludes: Vec<_> = specs
+        .iter()
+        .filter_map(|spec| match spec.to_ref().instruction() {
+            Instruction::Push(Push::Exclude { src }) => Some(src),
+            _ => None,
+        })
+        .collect();
+    let mut candidates = Vec::<Candidate>::new();
+    let mut add = |candidate: Candidate| {
+        let is_excluded = candidate.local.as_ref().is_some_and(|name| {
+            excludes
+                .iter()
+                .any(|pattern| matches(pattern, name.as_bstr()).is_some())
+        });
+        if !is_excluded && !candidates.iter().any(|c| c.remote == candidate.remote) {
+            candidates.push(candidate);
+        }
+    };
+    for spec in specs.iter() {
+        let Instruction::Push(instruction) = spec.to_ref().instruction() else {
+            continue;
+        };
+        match instruction {
+            Push::Exclude { .. } => {}
+            Push::AllMatchingBranches { allow_non_fast_forward } => {
+                for reference in repo.references()?.local_branches()? {
+                    let reference = reference.map_err(Error::IterReference)?;
+                    let Some(id) = reference.try_id() else { continue };
+                    if remote_refs.iter().any(|(name, _)| *name == reference.name().as_bstr()) {
+                        add(Candidate {
+                            local: Some(reference.name().to_owned()),
+                            remote: reference.name().to_owned(),
+                            new: id.detach(),
+                            force: allow_non_fast_forward || options.force,
+                        });
+                    }
+                }
+            }
+            Push::Delete { ref_or_pattern } => {
+                if ref_or_pattern.contains(&b'*') {
+                    return Err(Error::DeletePattern {
+                        pattern: ref_or_pattern.into(),
+                    });
+                }
+                let remote_name = match find_on_remote(&remote_refs, ref_or_pattern) {
+                    Some(name) => name.try_into()?,
+                    None if ref_or_pattern.starts_with(b"refs/") => ref_or_pattern.try_into()?,
+                    None => Category::LocalBranch.to_full_name(ref_or_pattern)?,
+                };
+                add(Candidate {
+                    local: None,
+                    remote: remote_name,
+                    new: repo.object_hash().null(),
+                    force: true,
+                });
+            }
+            Push::Matching {
+                src,
+                dst,
+                allow_non_fast_forward,
+            } => {
+                let force = allow_non_fast_forward || options.force;
+                if src.contains(&b'*') {
+                    for reference in repo.references()?.all()? {
+                        let reference = reference.map_err(Error::IterReference)?;
+                        let Some(matched) = matches(src, reference.name().as_bstr()) else {
+                            continue;
+                        };
+                        let Some(id) = reference.try_id() else { continue };
+                        let mut remote_name = BString::from(dst.replace("*", matched));
+                        if !remote_name.starts_with(b"refs/") {
+                            remote_name.insert_str(0, "refs/heads/");
+                        }
+                        add(Candidate {
+                            local: Some(reference.name().to_owned()),
+                            remote: remote_name.try_into()?,
+                            new: id.detach(),
+                            force,
+                        });
+                    }
+                } else {
+                    let (local, new) = resolve_source(repo, src)?;
+                    let remote_name = match local.as_ref() {
+                        Some(local) if src == dst => local.clone(),
+                        None if src == dst => {
+                            return Err(Error::MissingDestination {
+                                source_spec: src.into(),
+                            });
+                        }
+                        local => qualify_destination(&remote_refs, dst, local)?,
+                    };
+                    add(Candidate {
+                        local,
+                        remote: remote_name,
+                        new,
+                        force,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut updates = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        let old = remote_refs
+            .iter()
+            .find_map(|(name, id)| (*name == candidate.remote.as_bstr()).then_some(*id))
+            .unwrap_or_else(|| repo.object_hash().null());
+        let lease = match options
+            .force_with_lease
+            .iter()
+            .find(|lease| lease_applies(&remote_refs, lease.name.as_ref(), candidate.remote.as_ref()))
+        {
+            Some(lease) => Some(match lease.expected {
+                Some(id) => id,
+                None => tracking_ref_name(remote, candidate.remote.as_ref())
+                    .map(|name| repo.try_find_reference(name.as_ref()))
+                    .transpose()?
+                    .flatten()
+                    .and_then(|r| r.try_id().map(crate::Id::detach))
+                    .unwrap_or_else(|| repo.object_hash().null()),
+            }),
+            None => None,
+        };
+        let status = status(repo, &candidate, old, lease)?;
+        updates.push(Update {
+            local: candidate.local,
+            remote: candidate.remote,
+            old,
+            new: candidate.new,
+            status,
+        });
+    }
+
+    if options.atomic && updates.iter().any(|u| u.status.is_rejected()) {
+        for update in updates.iter_mut().filter(|u| u.status == Status::Pending) {
+            update.status = Status::AtomicPushFailed;
+        }
+    }
+    Ok(updates)
+}
+
+/// Return the name of the remote-tracking reference that corresponds to the reference `name` on `remote`, according to its fetch refspecs.
+pub(super) fn tracking_ref_name(remote: &Remote<'_>, name: &FullNameRef) -> Option<FullName> {
+    let null = remote.repo.object_hash().null();
+    let outcome = gix_refspec::MatchGroup::from_fetch_specs(remote.fetch_specs.iter().map(RefSpec::to_ref)).match_lhs(
+        std::iter::once(gix_refspec::match_group::Item {
+            full_ref_name: name.as_bstr(),
+            target: &null,
+            object: None,
+        }),
+    );
+    outcome
+        .mappings
+        .into_iter()
+        .find_map(|mapping| mapping.rhs.and_then(|name| FullName::try_from(name.into_owned()).ok()))
+}
+
+fn status(repo: &Repository, candidate: &Candidate, old: ObjectId, lease: Option<ObjectId>) -> Result<Status, Error> {
+    let new = candidate.new;
+    if new.is_null() && old.is_null() {
+        return Ok(Status::RejectedNoSuchRef);
+    }
+    if old == new {
+        return Ok(Status::UpToDate);
+    }
+    if let Some(expected) = lease {
+        return Ok(if expected == old {
+            Status::Pending
+        } else {
+            Status::RejectedStale
+        });
+    }
+    if new.is_null() || old.is_null() || candidate.force {
+        return Ok(Status::Pending);
+    }
+    if candidate.remote.as_bstr().starts_with(b"refs/tags/") {
+        return Ok(Status::RejectedAlreadyExists);
+    }
+    if !repo.has_object(old) {
+        return Ok(Status::RejectedFetchFirst);
+    }
+    if repo.find_header(old)?.kind() != gix_object::Kind::Commit
+        || repo.find_header(new)?.kind() != gix_object::Kind::Commit
+    {
+        return Ok(Status::RejectedNeedsForce);
+    }
+    // Commit times can't be trusted to increase along the history, so like `git`, only an exact ancestry check will do.
+    let cache = repo.commit_graph_if_enabled()?;
+    let mut graph = repo.revision_graph(cache.as_ref());
+    let is_fast_forward = gix_revision::merge_base(old, &[new], &mut graph)?.is_some_and(|bases| bases.contains(&old));
+    Ok(if is_fast_forward {
+        Status::Pending
+    } else {
+        Status::RejectedNonFastForward
+    })
+}
+
+/// Return the portion of `name` matched by the `*` in `pattern`, or all of `name` if it equals `pattern` without a `*`.
+fn matches<'a>(pattern: &BStr, name: &'a BStr) -> Option<&'a BStr> {
+    match pattern.split_once_str("*") {
+        Some((prefix, suffix)) => name
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+            .map(ByteSlice::as_bstr),
+        None => (pattern == name).then_some(name),
+    }
+}
+
+/// Find the reference on the remote that the partial `name` refers to, in the order git would try them.
+fn find_on_remote<'a>(remote_refs: &[(&'a BStr, ObjectId)], name: &BStr) -> Option<&'a BStr> {
+    ["", "refs/", "refs/tags/", "refs/heads/", "refs/remotes/"]
+        .into_iter()
+        .filter(|prefix| !prefix.is_empty() || name.starts_with(b"refs/"))
+        .find_map(|prefix| {
+            let mut candidate = BString::from(prefix);
+            candidate.push_str(name);
+            remote_refs
+                .iter()
+                .find_map(|(remote_name, _)| (*remote_name == candidate).then_some(*remote_name))
+        })
+}
+
+/// Turn the destination of a refspec into a full reference name, using the category of the `local` reference if it
+/// doesn't exist on the remote yet.
+fn qualify_destination(
+    remote_refs: &[(&BStr, ObjectId)],
+    dst: &BStr,
+    local: Option<&FullName>,
+) -> Result<FullName, Error> {
+    if dst.starts_with(b"refs/") {
+        return Ok(dst.try_into()?);
+    }
+    if let Some(name) = find_on_remote(remote_refs, dst) {
+        return Ok(name.try_into()?);
+    }
+    match local.and_then(FullName::category) {
+        Some(category @ (Category::LocalBranch | Category::Tag)) => Ok(category.to_full_name(dst)?),
+        _ => Err(Error::UnqualifiedDestination {
+            destination: dst.into(),
+        }),
+    }
+}
+
+/// Resolve `src` to the local reference it names along with its value, or to an object if it isn't a reference.
+fn resolve_source(repo: &Repository, src: &BStr) -> Result<(Option<FullName>, ObjectId), Error> {
+    let reference = match <&PartialNameRef>::try_from(src) {
+        Ok(name) => repo.try_find_reference(name)?,
+        Err(_) => None,
+    };
+    if let Some(mut reference) = reference {
+        while let Some(next) = reference.follow() {
+            reference = next?;
+        }
+        if let Some(id) = reference.try_id() {
+            return Ok((Some(reference.name().to_owned()), id.detach()));
+        }
+    }
+    #[cfg(feature = "revision")]
+    let id = repo
+        .rev_parse_single(src)
+        .map(crate::Id::detach)
+        .map_err(|err| Error::UnresolvedSource {
+            source_spec: src.into(),
+            source: Some(err.into()),
+        })?;
+    #[cfg(not(feature = "revision"))]
+    let id = ObjectId::from_hex(src)
+        .ok()
+        .filter(|id| repo.has_object(id))
+        .ok_or_else(|| Error::UnresolvedSource {
+            source_spec: src.into(),
+            source: None,
+        })?;
+    Ok((None, id))
+}
+
+/// Return `true` if the lease for the reference `name` applies to the reference `remote_name` on the remote.
+fn lease_applies(remote_refs: &[(&BStr, ObjectId)], name: &BStr, remote_name: &FullNameRef) -> bool {
+    if name == remote_name.as_bstr() {
+        return true;
+    }
+    match find_on_remote(remote_refs, name) {
+        Some(found) => found == remote_name.as_bstr(),
+        None => Category::LocalBranch
+            .to_full_name(name)
+            .is_ok_and(|full_name| full_name.as_ref() == remote_name),
+    }
+}
+
+/// Translate `push.default` into refspecs for the current branch.
+fn push_default_specs(repo: &Repository) -> Result<Vec<RefSpec>, Error> {
+    let push_default = repo
+        .config
+        .resolved
+        .string(tree::Push::DEFAULT)
+        .map_or(Ok(Default::default()), |value| {
+            tree::Push::DEFAULT
+                .try_into_default(value)
+                .with_lenient_default(repo.config.lenient_config)
+        })?;
+    let spec = |spec: &BStr| {
+        gix_refspec::parse(spec, gix_refspec::parse::Operation::Push)
+            .expect("valid refspecs are built from valid reference names")
+            .to_owned()
+    };
+    let head = match push_default {
+        push::Default::Nothing => return Err(Error::NothingToPush),
+        push::Default::Matching => return Ok(vec![spec(":".into())]),
+        push::Default::Current | push::Default::Upstream | push::Default::Simple => {
+            repo.head_name()?.ok_or(Error::DetachedHead)?
+        }
+    };
+    let upstream = repo
+        .branch_remote_ref_name(head.as_ref(), Direction::Fetch)
+        .transpose()?
+        .map(Cow::into_owned);
+    let destination = match push_default {
+        push::Default::Upstream => upstream.ok_or_else(|| Error::MissingUpstream {
+            branch: head.as_bstr().into(),
+        })?,
+        push::Default::Simple => match upstream {
+            Some(upstream) if upstream != head => {
+                return Err(Error::UpstreamNameMismatch {
+                    upstream: upstream.into_inner(),
+                });
+            }
+            _ => head.clone(),
+        },
+        _ => head.clone(),
+    };
+    let mut refspec = head.into_inner();
+    refspec.push_byte(b':');
+    refspec.push_str(destination.as_bstr());
+    Ok(vec![spec(refspec.as_ref())])
+}
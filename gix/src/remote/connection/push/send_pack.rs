@@ -0,0 +1,263 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_features::progress::DynNestedProgress;
+use gix_hash::ObjectId;
+use gix_protocol::push::{Command, Report};
+use gix_ref::{
+    Target,
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+};
+use gix_transport::client::blocking_io::Transport;
+
+use super::{Error, Outcome, Prepare, Status, Update};
+use crate::{Repository, bstr::ByteSlice, remote::connection::fetch::config};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+impl<T> Prepare<'_, '_, '_, T>
+where
+    T: Transport,
+{
+    /// Send all [pending updates](Status::Pending) to the remote along with a pack containing the objects it needs
+    /// to perform them, and update the corresponding remote-tracking references for all updates the remote performed.
+    ///
+    /// `progress` is used to display pack generation as well as the progress reported by the remote, and the operation
+    /// can be interrupted by setting `should_interrupt`.
+    ///
+    /// Note that it's *not* an error if the remote rejects updates, and callers have to check the [status](Update::status)
+    /// of each update, or use [`Outcome::is_ok()`].
+    ///
+    /// ### Configuration
+    ///
+    /// - `pack.threads` controls the amount of threads used to create the pack.
+    pub fn send<P>(mut self, progress: P, should_interrupt: &AtomicBool) -> Result<Outcome, Error>
+    where
+        P: gix_features::progress::NestedProgress,
+        P::SubProgress: 'static,
+    {
+        let _span = gix_trace::coarse!("remote::push::Prepare::send()");
+        let repo = self.con.remote.repo;
+        let commands: Vec<_> = self
+            .updates
+            .iter()
+            .filter(|update| update.status == Status::Pending)
+            .map(|update| Command {
+                old: update.old,
+                new: update.new,
+                name: update.remote.as_bstr().into(),
+            })
+            .collect();
+        if commands.is_empty() {
+            return Ok(Outcome {
+                updates: self.updates,
+                report: None,
+                tracking_ref_edits: Vec::new(),
+            });
+        }
+
+        let thread_limit = config::index_threads(repo)?;
+        let remote_ids: Vec<ObjectId> = self
+            .handshake
+            .refs
+            .iter()
+            .flatten()
+            .filter_map(|r| r.unpack().1.map(ToOwned::to_owned))
+            .collect();
+        let report = gix_protocol::push(
+            &commands,
+            |out, progress, should_interrupt| {
+                write_pack(
+                    repo,
+                    &commands,
+                    &remote_ids,
+                    thread_limit,
+                    out,
+                    progress,
+                    should_interrupt,
+                )
+            },
+            progress,
+            should_interrupt,
+            gix_protocol::push::Context {
+                handshake: &self.handshake,
+                transport: &mut self.con.transport.inner,
+                user_agent: repo.config.user_agent_tuple(),
+                trace_packetlines: self.con.trace,
+            },
+            gix_protocol::push::Options {
+                atomic: self.atomic,
+                push_options: std::mem::take(&mut self.push_options),
+            },
+        )?;
+
+        let has_report = ["report-status-v2", "report-status"]
+            .iter()
+            .any(|name| self.handshake.capabilities.contains(name));
+        for update in self.updates.iter_mut().filter(|u| u.status == Status::Pending) {
+            update.status = status_from_report(update, report.as_ref(), has_report);
+        }
+
+        let mut tracking_ref_edits = Vec::new();
+        for update in self.updates.iter().filter(|u| u.status == Status::Ok) {
+            let Some(name) = super::resolve::tracking_ref_name(self.con.remote, update.remote.as_ref()) else {
+                continue;
+            };
+            let log = LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: false,
+                message: "update by push".into(),
+            };
+            let change = if update.is_delete() {
+                if repo.try_find_reference(name.as_ref())?.is_none() {
+                    continue;
+                }
+                Change::Delete {
+                    expected: PreviousValue::Any,
+                    log: RefLog::AndReference,
+                }
+            } else {
+                Change::Update {
+                    log,
+                    expected: PreviousValue::Any,
+                    new: Target::Object(update.new),
+                }
+            };
+            tracking_ref_edits.push(RefEdit {
+                change,
+                name,
+                deref: false,
+            });
+        }
+        let tracking_ref_edits = if tracking_ref_edits.is_empty() {
+            tracking_ref_edits
+        } else {
+            repo.edit_references(tracking_ref_edits)?
+        };
+
+        Ok(Outcome {
+            updates: self.updates,
+            report,
+            tracking_ref_edits,
+        })
+    }
+}
+
+fn status_from_report(update: &Update, report: Option<&Report>, has_report: bool) -> Status {
+    let Some(report) = report else {
+        return if has_report {
+            Status::RemoteRejected {
+                reason: "remote failed to report status".into(),
+            }
+        } else {
+            Status::Ok
+        };
+    };
+    match report
+        .refs
+        .iter()
+        .find(|status| status.name.as_bstr() == update.remote.as_bstr())
+    {
+        Some(status) => match &status.error {
+            None => Status::Ok,
+            Some(reason) => Status::RemoteRejected { reason: reason.clone() },
+        },
+        None => Status::RemoteRejected {
+            reason: report
+                .unpack_error
+                .clone()
+                .unwrap_or_else(|| "remote failed to report status".into()),
+        },
+    }
+}
+
+/// Write a pack with all objects reachable from the new values of `commands`, excluding those reachable from the objects
+/// the remote has advertised in `remote_ids` if we have them as well.
+fn write_pack(
+    repo: &Repository,
+    commands: &[Command],
+    remote_ids: &[ObjectId],
+    thread_limit: Option<usize>,
+    out: &mut dyn std::io::Write,
+    progress: &mut dyn DynNestedProgress,
+    should_interrupt: &AtomicBool,
+) -> Result<(), BoxError> {
+    use gix_features::progress::{Count, Progress};
+    use gix_pack::data::output;
+
+    let peel_to_commit = |id: ObjectId| -> Result<Option<ObjectId>, BoxError> {
+        let object = repo.find_object(id)?;
+        Ok(match object.kind {
+            gix_object::Kind::Commit => Some(id),
+            gix_object::Kind::Tag => object.peel_to_kind(gix_object::Kind::Commit).ok().map(|c| c.id),
+            _ => None,
+        })
+    };
+    let mut hidden = Vec::new();
+    for id in remote_ids.iter().filter(|id| repo.has_object(id)) {
+        hidden.extend(peel_to_commit(*id)?);
+    }
+    let mut tips = Vec::new();
+    let mut ids = Vec::new();
+    for new in commands.iter().map(|c| c.new).filter(|id| !id.is_null()) {
+        match peel_to_commit(new)? {
+            Some(commit) => {
+                if commit != new {
+                    ids.push(new);
+                }
+                tips.push(commit);
+            }
+            None => ids.push(new),
+        }
+    }
+    if !tips.is_empty() {
+        for info in repo.rev_walk(tips).with_hidden(hidden).all()? {
+            ids.push(info?.id);
+        }
+    }
+
+    let mut db = repo.objects.clone().into_arc()?.into_inner();
+    db.prevent_pack_unload();
+    db.ignore_replacements = true;
+    let chunk_size = 1000;
+    let (counts, _stats) = {
+        let mut counting = progress.add_child("counting".into());
+        counting.init(None, gix_features::progress::count("objects"));
+        output::count::objects(
+            db.clone(),
+            Box::new(ids.into_iter().map(Ok)),
+            &counting,
+            should_interrupt,
+            output::count::objects::Options {
+                // Expanding trees by comparison with their ancestors could yield duplicate objects if done in parallel.
+                thread_limit: Some(1),
+                chunk_size,
+                input_object_expansion: output::count::objects::ObjectExpansion::TreeAdditionsComparedToAncestor,
+            },
+        )?
+    };
+    let num_entries = counts.len();
+    let entries = gix_features::parallel::InOrderIter::from(output::entry::iter_from_counts(
+        counts,
+        db,
+        Box::new(progress.add_child("creating entries".into())),
+        output::entry::iter_from_counts::Options {
+            thread_limit,
+            mode: output::entry::iter_from_counts::Mode::PackCopyAndBaseObjects,
+            allow_thin_pack: false,
+            chunk_size,
+            version: Default::default(),
+        },
+    ));
+    let mut write_progress = progress.add_child("writing".into());
+    write_progress.init(None, gix_features::progress::bytes());
+    for written in output::bytes::FromEntriesIter::new(
+        entries,
+        out,
+        num_entries as u32,
+        gix_pack::data::Version::V2,
+        repo.object_hash(),
+    ) {
+        write_progress.inc_by(written? as usize);
+    }
+    Ok(())
+}
@@ -6,6 +6,8 @@ pub mod negotiate {
     pub use gix_protocol::fetch::negotiate::Error;
 }
 
+#[cfg(any(feature = "async-network-client-async-std", feature = "blocking-network-client"))]
+pub(crate) use super::connection::fetch::config;
 #[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
 pub use super::connection::fetch::{
     Error, Outcome, Prepare, ProgressId, RefLogMessage, Status, outcome, prepare, refs,
@@ -31,6 +33,38 @@ pub(crate) enum WritePackedRefs {
     Only,
 }
 
+/// Control if and how `FETCH_HEAD` is written after fetching.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+pub enum WriteFetchHead {
+    /// Don't write `FETCH_HEAD`, like `git fetch --no-write-fetch-head` does.
+    Never,
+    /// Replace the contents of `FETCH_HEAD` with what was fetched.
+    #[default]
+    Overwrite,
+    /// Append what was fetched to `FETCH_HEAD`, like `git fetch --append` does.
+    Append,
+}
+
+/// Determine which of the fetched references are marked for merge in `FETCH_HEAD`.
+///
+/// Tags that were fetched due to [`Tags`] are never marked for merge.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+pub enum FetchHeadMerge {
+    /// Mark references like `git fetch [<remote>]` does with the refspecs configured for the remote, which is the
+    /// upstream branch of the current branch if it's configured to be fetched from this remote.
+    ///
+    /// Without upstream branch, the first reference of the first refspec is marked, unless that refspec is a pattern.
+    ///
+    /// Unlike `git`, the upstream branch isn't fetched additionally if none of the refspecs match it.
+    #[default]
+    Configured,
+    /// Mark all references matched by the refspecs of the remote, like `git fetch <remote> <refspec>...` does with
+    /// refspecs passed on the command-line.
+    AllMatches,
+}
+
 #[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
 pub use gix_protocol::fetch::{RefMap, refmap};
 pub use gix_protocol::fetch::{Shallow, Tags};
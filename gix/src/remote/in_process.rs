@@ -0,0 +1,273 @@
+//! A transport for `file://` URLs and plain paths which serves fetches from the local repository within this process.
+//!
+//! Instead of spawning `git upload-pack`, the repository on the other side is opened with `gix` and served by
+//! [`gix_protocol::upload_pack()`] in a thread, with both sides being connected by in-memory pipes.
+//! This makes local clones and fetches possible without a `git` installation.
+//!
+//! It's opt-in and only used by [`Remote::connect()`](crate::Remote::connect()) for fetches if
+//! [`gitoxide.inProcessFileTransport`](crate::config::tree::Gitoxide::IN_PROCESS_FILE_TRANSPORT) is `true`.
+//! By default, `git upload-pack` is spawned as it handles a few cases this transport rejects with an error:
+//!
+//! * fetching from shallow repositories,
+//! * `deepen-relative`, as used to deepen shallow repositories by a number of commits,
+//! * `want`s for objects that aren't advertised, as sent when fetching missing objects of partial clones,
+//! * any protocol version other than V2.
+//!
+//! Pushes are always served by spawning `git receive-pack`.
+use std::{
+    any::Any,
+    borrow::Cow,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+};
+
+use gix_features::io::pipe;
+use gix_protocol::{handshake::Ref, upload_pack};
+use gix_transport::{
+    Protocol, Service,
+    client::{
+        self, MessageKind, WriteMode,
+        blocking_io::{RequestWriter, SetServiceResponse},
+        git::{ConnectMode, blocking_io::Connection},
+    },
+};
+
+use crate::{
+    bstr::{BStr, BString, ByteSlice},
+    ext::ReferenceExt,
+};
+
+/// The amount of chunks to buffer in each pipe before the writer blocks.
+const IN_FLIGHT_WRITES: usize = 32;
+
+/// A transport which serves the repository at a local path in-process, answering `upload-pack` requests with
+/// [`gix_protocol::upload_pack()`] in a separate thread.
+///
+/// The server only speaks protocol version 2, so other desired versions are rejected when fetching.
+/// `receive-pack` is still served by spawning `git receive-pack`, as done by
+/// [`gix_transport::client::blocking_io::file::connect()`].
+pub struct Transport {
+    url: gix_url::Url,
+    desired_version: Protocol,
+    trace: bool,
+    connection: Option<Connection<pipe::Reader, pipe::Writer>>,
+    server: Option<JoinHandle<Result<(), upload_pack::Error>>>,
+    should_interrupt: Arc<AtomicBool>,
+    fallback: Option<gix_transport::client::blocking_io::file::SpawnProcessOnDemand>,
+}
+
+/// Create a transport to serve the repository at `url`, which must use the `file` scheme and point to a git directory.
+///
+/// Fetches fail unless `desired_version` is V2, while it's passed on if `git` has to be spawned to handle `receive-pack`.
+/// If `trace` is `true`, all packetlines received or sent will be passed to the facilities of the `gix-trace` crate.
+pub fn connect(url: gix_url::Url, desired_version: Protocol, trace: bool) -> Transport {
+    Transport {
+        url,
+        desired_version,
+        trace,
+        connection: None,
+        server: None,
+        should_interrupt: Default::default(),
+        fallback: None,
+    }
+}
+
+impl Transport {
+    fn start_upload_pack(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let repo = crate::open_opts(
+            gix_path::from_bstr(self.url.path.as_bstr()).into_owned(),
+            crate::open::Options::isolated(),
+        )?;
+        if repo.is_shallow() {
+            return Err(format!(
+                "Cannot serve the shallow repository at '{}' in-process, as its history is incomplete",
+                self.url.path
+            )
+            .into());
+        }
+        let refs = advertised_refs(&repo)?;
+        let mut objects = repo.objects.clone().into_inner().into_arc()?;
+        objects.prevent_pack_unload();
+        let object_hash = repo.object_hash();
+        let options = upload_pack::Options {
+            trace_packetlines: self.trace,
+            ..Default::default()
+        };
+
+        let (client_write, server_read) = pipe::unidirectional(IN_FLIGHT_WRITES);
+        let (server_write, client_read) = pipe::unidirectional(IN_FLIGHT_WRITES);
+        let should_interrupt = self.should_interrupt.clone();
+        self.server = Some(
+            std::thread::Builder::new()
+                .name("gix upload-pack".into())
+                .spawn(move || {
+                    gix_protocol::upload_pack(
+                        server_read,
+                        server_write,
+                        upload_pack::Context {
+                            refs: &refs,
+                            objects,
+                            object_hash,
+                        },
+                        gix_features::progress::Discard,
+                        &should_interrupt,
+                        options,
+                    )
+                })?,
+        );
+        self.connection = Some(
+            Connection::new(
+                client_read,
+                client_write,
+                Protocol::V2,
+                self.url.path.clone(),
+                None::<(&str, _)>,
+                ConnectMode::Process,
+                self.trace,
+            )
+            .custom_url(Some(self.url.to_bstring())),
+        );
+        Ok(())
+    }
+}
+
+/// Obtain all references of `repo` to advertise, similar to what `git upload-pack` would do.
+fn advertised_refs(repo: &crate::Repository) -> Result<Vec<Ref>, Box<dyn std::error::Error + Send + Sync>> {
+    let peel_tag =
+        |id: gix_hash::ObjectId| -> Result<Option<gix_hash::ObjectId>, Box<dyn std::error::Error + Send + Sync>> {
+            if repo.find_header(id)?.kind() != gix_object::Kind::Tag {
+                return Ok(None);
+            }
+            Ok(Some(repo.find_object(id)?.peel_tags_to_end()?.id))
+        };
+    let mut out = Vec::new();
+    match repo.head()?.kind {
+        crate::head::Kind::Unborn(target) => out.push(Ref::Unborn {
+            full_ref_name: "HEAD".into(),
+            target: target.into_inner(),
+        }),
+        crate::head::Kind::Detached { target, .. } => out.push(match peel_tag(target)? {
+            Some(object) => Ref::Peeled {
+                full_ref_name: "HEAD".into(),
+                tag: target,
+                object,
+            },
+            None => Ref::Direct {
+                full_ref_name: "HEAD".into(),
+                object: target,
+            },
+        }),
+        crate::head::Kind::Symbolic(referent) => {
+            let target = referent.name.as_bstr().to_owned();
+            let id = referent.attach(repo).follow_to_object()?.detach();
+            let peeled = peel_tag(id)?;
+            out.push(Ref::Symbolic {
+                full_ref_name: "HEAD".into(),
+                target,
+                tag: peeled.map(|_| id),
+                object: peeled.unwrap_or(id),
+            });
+        }
+    }
+
+    for reference in repo.references()?.all()? {
+        let mut reference = reference?;
+        let full_ref_name = reference.name().as_bstr().to_owned();
+        let symbolic_target = reference.target().try_name().map(|name| name.as_bstr().to_owned());
+        let Ok(id) = reference.follow_to_object().map(crate::Id::detach) else {
+            // Dangling symbolic references aren't advertised.
+            continue;
+        };
+        let peeled = peel_tag(id)?;
+        out.push(match (symbolic_target, peeled) {
+            (Some(target), peeled) => Ref::Symbolic {
+                full_ref_name,
+                target,
+                tag: peeled.map(|_| id),
+                object: peeled.unwrap_or(id),
+            },
+            (None, Some(object)) => Ref::Peeled {
+                full_ref_name,
+                tag: id,
+                object,
+            },
+            (None, None) => Ref::Direct {
+                full_ref_name,
+                object: id,
+            },
+        });
+    }
+    Ok(out)
+}
+
+impl client::TransportWithoutIO for Transport {
+    fn to_url(&self) -> Cow<'_, BStr> {
+        Cow::Owned(self.url.to_bstring())
+    }
+
+    fn connection_persists_across_multiple_requests(&self) -> bool {
+        true
+    }
+
+    fn configure(&mut self, _config: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        Ok(())
+    }
+}
+
+impl client::blocking_io::Transport for Transport {
+    fn handshake<'a>(
+        &mut self,
+        service: Service,
+        extra_parameters: &'a [(&'a str, Option<&'a str>)],
+    ) -> Result<SetServiceResponse<'_>, client::Error> {
+        if service != Service::UploadPack {
+            let path: BString = self.url.path.clone();
+            return self
+                .fallback
+                .insert(
+                    gix_transport::client::blocking_io::file::connect(path, self.desired_version, self.trace)
+                        .expect("infallible"),
+                )
+                .handshake(service, extra_parameters);
+        }
+        if self.desired_version != Protocol::V2 {
+            return Err(client::Error::UnsupportedProtocolVersion(
+                format!("protocol.version={}", self.desired_version as usize).into(),
+            ));
+        }
+        self.start_upload_pack().map_err(std::io::Error::other)?;
+        self.connection
+            .as_mut()
+            .expect("set after starting the server")
+            .handshake(service, extra_parameters)
+    }
+
+    fn request(
+        &mut self,
+        write_mode: WriteMode,
+        on_into_read: MessageKind,
+        trace: bool,
+    ) -> Result<RequestWriter<'_>, client::Error> {
+        if let Some(fallback) = self.fallback.as_mut() {
+            return fallback.request(write_mode, on_into_read, trace);
+        }
+        self.connection
+            .as_mut()
+            .ok_or(client::Error::MissingHandshake)?
+            .request(write_mode, on_into_read, trace)
+    }
+}
+
+impl Drop for Transport {
+    fn drop(&mut self) {
+        self.should_interrupt.store(true, Ordering::Relaxed);
+        // Closing our ends of the pipes makes the server stop, even if it's in the middle of sending a pack.
+        self.connection.take();
+        if let Some(server) = self.server.take() {
+            server.join().ok();
+        }
+    }
+}
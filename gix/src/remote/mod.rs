@@ -49,12 +49,22 @@ pub mod init;
 ///
 pub mod fetch;
 
+///
+#[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+pub mod promisor;
+
 ///
 #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
 pub mod connect;
 
+///
+#[cfg(feature = "in-process-file-transport")]
+pub mod in_process;
+
 #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
 mod connection;
+#[cfg(feature = "blocking-network-client")]
+pub use connection::push;
 #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
 pub use connection::{AuthenticateFn, Connection, ref_map};
 
@@ -0,0 +1,189 @@
+//! Support for partial clones, whose missing objects are obtained from *promisor remotes* when needed.
+//!
+//! A promisor remote is the remote named by `extensions.partialClone`, or any remote with `remote.<name>.promisor` set to `true`.
+use crate::{
+    bstr::BStr,
+    config::tree::{Extensions, Key, Remote},
+};
+
+type Filter = fn(&gix_config::file::Metadata) -> bool;
+
+/// Return the names of all promisor remotes in `config`, in the order in which they should be tried when obtaining missing objects.
+///
+/// The remote named by `extensions.partialClone` comes first, followed by all remotes with `remote.<name>.promisor` set to `true`.
+#[cfg(feature = "blocking-network-client")]
+pub(crate) fn remote_names(config: &gix_config::File<'static>, mut filter: Filter) -> Vec<crate::bstr::BString> {
+    use crate::config::tree::Section;
+
+    let mut out: Vec<crate::bstr::BString> = config
+        .string_filter(Extensions::PARTIAL_CLONE, &mut filter)
+        .map(std::borrow::Cow::into_owned)
+        .into_iter()
+        .collect();
+    for name in config
+        .sections_by_name_and_filter(Remote.name(), filter)
+        .into_iter()
+        .flatten()
+        .filter_map(|section| section.header().subsection_name())
+    {
+        if !out.iter().any(|existing| existing == name) && is_promisor_flag_set(config, filter, name) {
+            out.push(name.to_owned());
+        }
+    }
+    out
+}
+
+/// Return `true` if the remote with `name` is a promisor remote according to `config`.
+pub(crate) fn is_promisor_remote(config: &gix_config::File<'static>, mut filter: Filter, name: &BStr) -> bool {
+    config
+        .string_filter(Extensions::PARTIAL_CLONE, &mut filter)
+        .is_some_and(|partial_clone| partial_clone.as_ref() == name)
+        || is_promisor_flag_set(config, filter, name)
+}
+
+/// Return the filter specification of the promisor remote with `name` to use when fetching from it, as configured
+/// with `remote.<name>.partialCloneFilter`.
+pub(crate) fn partial_clone_filter(
+    config: &gix_config::File<'static>,
+    mut filter: Filter,
+    name: &BStr,
+) -> Option<String> {
+    config
+        .string_filter(
+            format!("remote.{name}.{}", Remote::PARTIAL_CLONE_FILTER.name()).as_str(),
+            &mut filter,
+        )
+        .map(|spec| spec.to_string())
+}
+
+fn is_promisor_flag_set(config: &gix_config::File<'static>, mut filter: Filter, name: &BStr) -> bool {
+    config
+        .boolean_filter(
+            format!("remote.{name}.{}", Remote::PROMISOR.name()).as_str(),
+            &mut filter,
+        )
+        .and_then(Result::ok)
+        .unwrap_or(false)
+}
+
+#[cfg(feature = "blocking-network-client")]
+pub(crate) use lazy_fetch::LazyFetch;
+
+/// Let the object database of `repo` obtain missing objects from its promisor remotes on demand.
+#[cfg(feature = "blocking-network-client")]
+pub(crate) fn install_lazy_fetch(repo: &crate::Repository) {
+    repo.objects
+        .store_ref()
+        .set_promisor(Some(std::sync::Arc::new(LazyFetch {
+            git_dir: repo.git_dir().to_owned(),
+            options: repo.options.clone(),
+        })));
+}
+
+///
+#[cfg(feature = "blocking-network-client")]
+pub mod lazy_fetch {
+    use std::{path::PathBuf, sync::atomic::AtomicBool};
+
+    use gix_hash::ObjectId;
+
+    use crate::{
+        bstr::{BString, ByteSlice},
+        config::tree::{Fetch, Key, gitoxide},
+        remote,
+    };
+
+    /// The error returned when objects couldn't be obtained from a promisor remote on demand.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not open the repository to fetch missing objects into")]
+        Open(#[from] crate::open::Error),
+        #[error("There is no promisor remote to obtain {count} missing object(s) from")]
+        NoPromisorRemote { count: usize },
+        #[error("Could not obtain {count} missing object(s), like {first}, from any promisor remote")]
+        StillMissing {
+            count: usize,
+            first: ObjectId,
+            source: Option<Box<dyn std::error::Error + Send + Sync + 'static>>,
+        },
+    }
+
+    /// Fetch objects that are missing in a partial clone from its promisor remotes, as installed into the object database
+    /// of repositories with promisor remotes.
+    pub(crate) struct LazyFetch {
+        /// The repository to open for fetching.
+        pub git_dir: PathBuf,
+        /// The options the repository was originally opened with.
+        pub options: crate::open::Options,
+    }
+
+    impl gix_odb::promisor::Fetch for LazyFetch {
+        fn fetch(&self, ids: &[ObjectId]) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+            self.fetch_inner(ids).map_err(Into::into)
+        }
+    }
+
+    impl LazyFetch {
+        fn fetch_inner(&self, ids: &[ObjectId]) -> Result<(), Error> {
+            let _span = gix_trace::coarse!("gix::remote::promisor::LazyFetch::fetch()", num_ids = ids.len());
+            let mut options = self.options.clone();
+            // Missing objects in the repository we fetch with must not trigger another fetch, and negotiation is pointless
+            // as we only want specific objects.
+            options.api_config_overrides.extend([
+                BString::from(format!("{}=true", gitoxide::Objects::NO_LAZY_FETCH.logical_name())),
+                format!("{}=noop", Fetch::NEGOTIATION_ALGORITHM.logical_name()).into(),
+            ]);
+            let repo = crate::open_opts(&self.git_dir, options)?;
+            let names = super::remote_names(&repo.config.resolved, repo.filter_config_section());
+            if names.is_empty() {
+                return Err(Error::NoPromisorRemote { count: ids.len() });
+            }
+
+            let mut missing: Vec<_> = ids.iter().filter(|id| !repo.has_object(id)).copied().collect();
+            let mut last_err = None;
+            for name in names {
+                if missing.is_empty() {
+                    break;
+                }
+                if let Err(err) = fetch_from(&repo, name.as_bstr(), &missing) {
+                    gix_trace::warn!("Could not fetch missing objects from promisor remote '{name}': {err}");
+                    last_err = Some(err);
+                }
+                missing.retain(|id| !repo.has_object(id));
+            }
+            match missing.first() {
+                None => Ok(()),
+                Some(first) => Err(Error::StillMissing {
+                    count: missing.len(),
+                    first: *first,
+                    source: last_err,
+                }),
+            }
+        }
+    }
+
+    fn fetch_from(
+        repo: &crate::Repository,
+        name: &crate::bstr::BStr,
+        ids: &[ObjectId],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let mut remote = repo.find_remote(name)?.with_fetch_tags(remote::fetch::Tags::None);
+        remote.replace_refspecs(
+            ids.iter().map(|id| BString::from(id.to_string())),
+            remote::Direction::Fetch,
+        )?;
+        let outcome = remote
+            .connect(remote::Direction::Fetch)?
+            .prepare_fetch(gix_features::progress::Discard, Default::default())?
+            .with_write_fetch_head(remote::fetch::WriteFetchHead::Never)
+            .receive(gix_features::progress::Discard, &AtomicBool::default())?;
+        if let remote::fetch::Status::Change { write_pack_bundle, .. } = outcome.status {
+            // There are no refs to protect the objects, but they are reachable through the promisor pack.
+            if let Some(path) = write_pack_bundle.keep_path {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
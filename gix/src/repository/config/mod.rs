@@ -89,6 +89,12 @@ impl crate::Repository {
                 .and_then(|variant| Ssh::VARIANT.try_into_variant(variant).transpose())
                 .transpose()
                 .with_leniency(self.options.lenient_config)?,
+            native: config
+                .boolean_filter(gitoxide::Ssh::NATIVE, &mut trusted)
+                .transpose()
+                .map_err(|err| config::key::GenericErrorWithValue::from(&gitoxide::Ssh::NATIVE).with_source(err))
+                .with_leniency(self.options.lenient_config)?
+                .unwrap_or_default(),
         };
         Ok(opts)
     }
@@ -127,7 +133,7 @@ impl crate::Repository {
                 self.filter_config_section(),
             )?
             .map(|enabled| !enabled),
-            ref_namespace: self.refs.namespace.as_ref().map(|ns| ns.as_bstr().to_owned()),
+            ref_namespace: self.refs.namespace().map(|ns| ns.as_bstr().to_owned()),
             literal_pathspecs: pathspec_boolean(&gitoxide::Pathspec::LITERAL)?,
             glob_pathspecs: pathspec_boolean(&gitoxide::Pathspec::GLOB)?
                 .or(pathspec_boolean(&gitoxide::Pathspec::NOGLOB)?),
@@ -54,7 +54,7 @@ impl Repository {
         options: impl Into<Option<crate::diff::Options>>,
     ) -> Result<Vec<crate::object::tree::diff::ChangeDetached>, diff_tree_to_tree::Error> {
         let mut cache = self.diff_resource_cache(gix_diff::blob::pipeline::Mode::ToGit, Default::default())?;
-        let opts = options
+        let opts: gix_diff::tree_with_rewrites::Options = options
             .into()
             .map_or_else(|| crate::diff::Options::from_configuration(&self.config), Ok)?
             .into();
@@ -62,6 +62,16 @@ impl Repository {
         let empty_tree = self.empty_tree();
         let old_tree = old_tree.into().unwrap_or(&empty_tree);
         let new_tree = new_tree.into().unwrap_or(&empty_tree);
+        if self.fetches_missing_objects() && crate::diff::utils::rewrites_need_blob_content(opts.rewrites.as_ref()) {
+            let ids = crate::diff::utils::changed_blob_ids(
+                self,
+                TreeRefIter::from_bytes(&old_tree.data, old_tree.id.kind()),
+                TreeRefIter::from_bytes(&new_tree.data, new_tree.id.kind()),
+            )
+            .map_err(gix_diff::tree_with_rewrites::Error::Diff)?;
+            self.fetch_missing_objects(ids)
+                .map_err(diff_tree_to_tree::Error::FetchMissing)?;
+        }
         let mut out = Vec::new();
         gix_diff::tree_with_rewrites(
             TreeRefIter::from_bytes(&old_tree.data, old_tree.id.kind()),
@@ -21,6 +21,30 @@ pub mod pipeline {
     }
 }
 
+///
+#[cfg(feature = "lfs-http-client-reqwest")]
+pub mod download_missing_lfs_objects {
+    use crate::bstr::BString;
+
+    /// The error returned by [Repository::download_missing_lfs_objects()](super::Repository::download_missing_lfs_objects()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Options(#[from] crate::filter::pipeline::options::Error),
+        #[error(transparent)]
+        AttributeStack(#[from] crate::config::attribute_stack::Error),
+        #[error("Could not obtain the attributes of '{path}'")]
+        Attributes { path: BString, source: std::io::Error },
+        #[error(transparent)]
+        FindObject(#[from] gix_object::find::Error),
+        #[error("Could not create the HTTP client to talk to the LFS server")]
+        HttpClient(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+        #[error("Could not download the missing LFS objects")]
+        Download(#[from] gix_lfs::transfer::Error),
+    }
+}
+
 impl Repository {
     /// Configure a pipeline for converting byte buffers to the worktree representation, and byte streams to the git-internal
     /// representation. Also return the index that was used when initializing the pipeline as it may be useful when calling
@@ -62,3 +86,118 @@ impl Repository {
         Ok((filter::Pipeline::new(self, cache.detach())?, index))
     }
 }
+
+#[cfg(feature = "lfs-http-client-reqwest")]
+impl Repository {
+    /// Download the objects of all files in `index` that are tracked with the `lfs` filter, but are missing in the LFS store,
+    /// in batches from the LFS server of the repository, which is much faster than having the `lfs` driver obtain them one
+    /// at a time while checking out.
+    ///
+    /// The LFS server is configured with `lfs.url` or `remote.<name>.lfsurl`, or derived from the URL of the default remote,
+    /// and it's sent all `http.extraHeader` values.
+    /// Return `None` if there is no `lfs` filter driver or if no LFS server is known, so missing objects will be obtained by
+    /// the configured driver during checkout. Entries that are marked to be skipped in the worktree are ignored.
+    pub fn download_missing_lfs_objects(
+        &self,
+        index: &gix_index::State,
+    ) -> Result<Option<gix_lfs::transfer::Outcome>, download_missing_lfs_objects::Error> {
+        use download_missing_lfs_objects::Error;
+        use gix_object::Find;
+
+        use crate::bstr::ByteSlice;
+
+        let Some(store) = filter::Pipeline::options(self)?.lfs else {
+            return Ok(None);
+        };
+        let Some(endpoint) = self.lfs_endpoint() else {
+            return Ok(None);
+        };
+
+        let mut attributes = self.attributes_only(index, gix_worktree::stack::state::attributes::Source::IdMapping)?;
+        let mut out = attributes.selected_attribute_matches(["filter"]);
+        let mut buf = Vec::new();
+        let mut pointers = Vec::new();
+        for entry in index.entries().iter().filter(|entry| {
+            !entry.flags.contains(gix_index::entry::Flags::SKIP_WORKTREE)
+                && matches!(
+                    entry.mode,
+                    gix_index::entry::Mode::FILE | gix_index::entry::Mode::FILE_EXECUTABLE
+                )
+        }) {
+            let path = entry.path(index);
+            attributes
+                .at_entry(path, Some(entry.mode))
+                .map_err(|err| Error::Attributes {
+                    path: path.to_owned(),
+                    source: err,
+                })?
+                .matching_attributes(&mut out);
+            let is_lfs = out
+                .iter_selected()
+                .next()
+                .is_some_and(|filter| filter.assignment.state.as_bstr() == Some(b"lfs".as_bstr()));
+            if !is_lfs {
+                continue;
+            }
+            let Some(data) = self.objects.try_find(&entry.id, &mut buf)?.map(|obj| obj.data) else {
+                continue;
+            };
+            if let Ok(pointer) = gix_lfs::Pointer::from_bytes(data) {
+                if !store.contains(&pointer) {
+                    pointers.push(pointer);
+                }
+            }
+        }
+        if pointers.is_empty() {
+            return Ok(Some(Default::default()));
+        }
+
+        let http = gix_lfs::transfer::Client::new().map_err(|err| Error::HttpClient(err.into()))?;
+        let mut options = gix_lfs::transfer::Options::default();
+        if let Some(concurrency) = self
+            .config
+            .resolved
+            .integer("lfs.concurrentTransfers")
+            .and_then(Result::ok)
+            .filter(|value| *value > 0)
+        {
+            options.concurrency = concurrency as usize;
+        }
+        Ok(Some(gix_lfs::transfer::download(
+            &http, &endpoint, pointers, &store, &options,
+        )?))
+    }
+
+    /// Return the LFS server to talk to, like `git-lfs` would determine it.
+    fn lfs_endpoint(&self) -> Option<gix_lfs::transfer::Endpoint> {
+        use crate::bstr::ByteSlice;
+
+        let config = &self.config.resolved;
+        let remote_name = self.remote_default_name(crate::remote::Direction::Fetch);
+        let url = config.string("lfs.url").or_else(|| {
+            remote_name
+                .as_ref()
+                .and_then(|name| config.string_by("remote", Some(name.as_ref()), "lfsurl"))
+        });
+        let mut endpoint = match url {
+            Some(url) => gix_lfs::transfer::Endpoint {
+                url: url.to_str_lossy().trim_end_matches('/').to_owned(),
+                headers: Vec::new(),
+            },
+            None => {
+                let remote = self.find_remote(remote_name?.as_ref()).ok()?;
+                let url = remote.url(crate::remote::Direction::Fetch)?.to_bstring();
+                gix_lfs::transfer::Endpoint::from_remote_url(url.to_str().ok()?)?
+            }
+        };
+        // An empty value resets the list of headers, just like `git` does.
+        for header in config.strings("http.extraHeader").unwrap_or_default() {
+            match header.to_str().ok().and_then(|header| header.split_once(':')) {
+                Some((name, value)) => endpoint.headers.push((name.trim().into(), value.trim().into())),
+                None if header.is_empty() => endpoint.headers.clear(),
+                None => {}
+            }
+        }
+        Some(endpoint)
+    }
+}
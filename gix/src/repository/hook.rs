@@ -0,0 +1,128 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitStatus,
+};
+
+use crate::{
+    Repository,
+    bstr::BStr,
+    config::tree::Core,
+    hook::{Error, Invocation, MessageSource, PushUpdate, RefUpdate, TransactionState},
+};
+
+/// Hooks
+impl Repository {
+    /// Return `true` if hooks may run in this repository, which is only the case if it's fully trusted.
+    pub fn hooks_enabled(&self) -> bool {
+        self.git_dir_trust() == gix_sec::Trust::Full
+    }
+
+    /// Return the directory in which hooks are found, which is `core.hooksPath` if set, or `$GIT_DIR/hooks` otherwise.
+    ///
+    /// Just like in `git`, a relative `core.hooksPath` is relative to the directory hooks run in, which is
+    /// the root of the worktree, or the `.git` directory in bare repositories.
+    pub fn hooks_dir(&self) -> Result<PathBuf, Error> {
+        Ok(match self.config.trusted_file_path(Core::HOOKS_PATH).transpose()? {
+            Some(path) => self.hook_cwd().join(path),
+            None => self.current_dir().join(self.common_dir()).join("hooks"),
+        })
+    }
+
+    /// Return the path to the executable hook called `name`, like `pre-commit`, or `None` if there is no such hook
+    /// or if [hooks are disabled](Self::hooks_enabled()).
+    pub fn find_hook(&self, name: &str) -> Result<Option<PathBuf>, Error> {
+        if !self.hooks_enabled() {
+            return Ok(None);
+        }
+        Ok(gix_hook::find(&self.hooks_dir()?, name))
+    }
+
+    /// Run the hook called [`invocation.name`](Invocation::name) with the arguments, environment and standard input
+    /// provided by `invocation`, and return its exit status once it finished, or `None` if the hook
+    /// [doesn't exist](Self::find_hook()).
+    ///
+    /// Hooks should be considered failed if their exit status [isn't successful](ExitStatus::success()), which makes
+    /// `git` abort the operation the hook is run for, if it can be aborted.
+    pub fn run_hook(&self, invocation: Invocation) -> Result<Option<ExitStatus>, Error> {
+        let Some(hook) = self.find_hook(invocation.name)? else {
+            return Ok(None);
+        };
+        let options = gix_hook::run::Options {
+            cwd: self.hook_cwd(),
+            context: gix_command::Context {
+                git_dir: Some(self.current_dir().join(self.git_dir())),
+                ..Default::default()
+            },
+        };
+        Ok(Some(gix_hook::run(&hook, &invocation, options)?))
+    }
+
+    /// Run the `pre-commit` hook, which may prevent a commit from being created by failing.
+    pub fn run_pre_commit_hook(&self) -> Result<Option<ExitStatus>, Error> {
+        self.run_hook(self.with_index_file(Invocation::pre_commit()))
+    }
+
+    /// Run the `prepare-commit-msg` hook to let it edit the commit message in `message_file`, which originates from `source`.
+    pub fn run_prepare_commit_msg_hook(
+        &self,
+        message_file: &Path,
+        source: Option<MessageSource>,
+    ) -> Result<Option<ExitStatus>, Error> {
+        self.run_hook(self.with_index_file(Invocation::prepare_commit_msg(
+            &self.current_dir().join(message_file),
+            source,
+        )))
+    }
+
+    /// Run the `commit-msg` hook to let it edit or reject the final commit message in `message_file`.
+    pub fn run_commit_msg_hook(&self, message_file: &Path) -> Result<Option<ExitStatus>, Error> {
+        self.run_hook(self.with_index_file(Invocation::commit_msg(&self.current_dir().join(message_file))))
+    }
+
+    /// Run the `post-checkout` hook after the worktree was updated from `previous_head` to `new_head`, with
+    /// `is_branch_checkout` being `true` if `HEAD` changed as well.
+    ///
+    /// Its exit status doesn't affect the checkout, which already happened.
+    pub fn run_post_checkout_hook(
+        &self,
+        previous_head: &gix_hash::oid,
+        new_head: &gix_hash::oid,
+        is_branch_checkout: bool,
+    ) -> Result<Option<ExitStatus>, Error> {
+        self.run_hook(Invocation::post_checkout(previous_head, new_head, is_branch_checkout))
+    }
+
+    /// Run the `pre-push` hook before `updates` are pushed to the remote called `remote_name` at `remote_url`,
+    /// which may prevent the push by failing.
+    pub fn run_pre_push_hook(
+        &self,
+        remote_name: &BStr,
+        remote_url: &BStr,
+        updates: &[PushUpdate],
+    ) -> Result<Option<ExitStatus>, Error> {
+        self.run_hook(Invocation::pre_push(remote_name, remote_url, updates))
+    }
+
+    /// Run the `reference-transaction` hook once a transaction with `updates` reaches `state`.
+    /// The hook may abort the transaction by failing if `state` is [`TransactionState::Prepared`].
+    pub fn run_reference_transaction_hook(
+        &self,
+        state: TransactionState,
+        updates: &[RefUpdate],
+    ) -> Result<Option<ExitStatus>, Error> {
+        self.run_hook(Invocation::reference_transaction(state, updates))
+    }
+}
+
+impl Repository {
+    /// The directory hooks run in, which is the root of the worktree or the `.git` directory of bare repositories.
+    fn hook_cwd(&self) -> PathBuf {
+        self.current_dir()
+            .join(self.workdir().unwrap_or_else(|| self.git_dir()))
+    }
+
+    /// Let hooks that run while committing know the index that is about to be committed, just like `git` does.
+    fn with_index_file(&self, invocation: Invocation) -> Invocation {
+        invocation.env("GIT_INDEX_FILE", self.current_dir().join(self.index_path()))
+    }
+}
@@ -142,9 +142,13 @@ impl Repository {
     ) -> Result<crate::merge::tree::Outcome<'_>, merge_trees::Error> {
         let mut diff_cache = self.diff_resource_cache_for_tree_diff()?;
         let mut blob_merge = self.merge_resource_cache(Default::default())?;
+        let options: gix_merge::tree::Options = options.into();
+        #[cfg(feature = "rerere")]
+        let marker_size = options.blob_merge.text.conflict.marker_size();
+        #[cfg_attr(not(feature = "rerere"), allow(unused_mut))]
         let gix_merge::tree::Outcome {
-            tree,
-            conflicts,
+            mut tree,
+            mut conflicts,
             failed_on_first_unresolved_conflict,
         } = gix_merge::tree(
             ancestor_tree.as_ref(),
@@ -156,8 +160,10 @@ impl Repository {
             &mut Default::default(),
             &mut diff_cache,
             &mut blob_merge,
-            options.into(),
+            options,
         )?;
+        #[cfg(feature = "rerere")]
+        self.rerere_replay(&mut tree, &mut conflicts, marker_size)?;
 
         let validate = self.config.protect_options()?;
         Ok(crate::merge::tree::Outcome {
@@ -198,11 +204,15 @@ impl Repository {
         let mut blob_merge = self.merge_resource_cache(Default::default())?;
         let commit_graph = self.commit_graph_if_enabled()?;
         let mut graph = self.revision_graph(commit_graph.as_ref());
+        let options: gix_merge::commit::Options = options.into();
+        #[cfg(feature = "rerere")]
+        let marker_size = options.tree_merge.blob_merge.text.conflict.marker_size();
+        #[cfg_attr(not(feature = "rerere"), allow(unused_mut))]
         let gix_merge::commit::Outcome {
             tree_merge:
                 gix_merge::tree::Outcome {
-                    tree,
-                    conflicts,
+                    mut tree,
+                    mut conflicts,
                     failed_on_first_unresolved_conflict,
                 },
             merge_base_tree_id,
@@ -217,8 +227,10 @@ impl Repository {
             &mut blob_merge,
             self,
             &mut |id| id.to_owned().attach(self).shorten_or_id().to_string(),
-            options.into(),
+            options,
         )?;
+        #[cfg(feature = "rerere")]
+        self.rerere_replay(&mut tree, &mut conflicts, marker_size)?;
 
         let validate = self.config.protect_options()?;
         let tree_merge = crate::merge::tree::Outcome {
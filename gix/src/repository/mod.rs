@@ -36,6 +36,8 @@ pub mod filter;
 ///
 pub mod freelist;
 mod graph;
+#[cfg(feature = "hook")]
+mod hook;
 pub(crate) mod identity;
 mod impls;
 #[cfg(feature = "index")]
@@ -47,13 +49,21 @@ mod mailmap;
 ///
 #[cfg(feature = "merge")]
 mod merge;
+#[cfg(feature = "note")]
+mod note;
 mod object;
 #[cfg(feature = "attributes")]
 mod pathspec;
 mod reference;
 mod remote;
+#[cfg(feature = "rerere")]
+mod rerere;
 mod revision;
+#[cfg(feature = "sequencer")]
+mod sequence;
 mod shallow;
+#[cfg(feature = "stash")]
+mod stash;
 mod state;
 #[cfg(feature = "attributes")]
 mod submodule;
@@ -134,6 +144,8 @@ pub mod diff_tree_to_tree {
         CreateResourceCache(#[from] super::diff_resource_cache::Error),
         #[error(transparent)]
         TreeDiff(#[from] gix_diff::tree_with_rewrites::Error),
+        #[error("Could not obtain the blobs to check for renames from the promisor remote")]
+        FetchMissing(#[source] gix_odb::store::find::Error),
     }
 }
 
@@ -190,6 +202,9 @@ pub mod merge_trees {
         TreeMerge(#[from] gix_merge::tree::Error),
         #[error(transparent)]
         ValidationOptions(#[from] crate::config::boolean::Error),
+        #[cfg(feature = "rerere")]
+        #[error(transparent)]
+        Rerere(#[from] crate::rerere::Error),
     }
 }
 
@@ -210,6 +225,9 @@ pub mod merge_commits {
         CommitMerge(#[from] gix_merge::commit::Error),
         #[error(transparent)]
         ValidationOptions(#[from] crate::config::boolean::Error),
+        #[cfg(feature = "rerere")]
+        #[error(transparent)]
+        Rerere(#[from] crate::rerere::Error),
     }
 }
 
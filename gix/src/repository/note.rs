@@ -0,0 +1,293 @@
+use gix_hash::ObjectId;
+use gix_ref::{
+    FullName, FullNameRef,
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+};
+
+use crate::{
+    Blob, Repository,
+    bstr::{BStr, BString},
+    config::tree::{Core, core::NotesRef},
+    note::{DEFAULT_REF, Notes, Strategy, edit, find, merge, notes_ref},
+};
+
+/// Notes
+impl Repository {
+    /// Return the name of the reference with the notes to use by default, which is `core.notesRef` or
+    /// `GIT_NOTES_REF` if set, or [`refs/notes/commits`](DEFAULT_REF) otherwise.
+    pub fn notes_ref(&self) -> Result<FullName, notes_ref::Error> {
+        Ok(match self.config.resolved.string(Core::NOTES_REF) {
+            Some(name) => NotesRef::try_into_fullrefname(name)?.into_owned(),
+            None => DEFAULT_REF.try_into().expect("valid"),
+        })
+    }
+
+    /// Return the note of `object` in the notes at `notes_ref`, or `None` if it has no note or if `notes_ref` doesn't exist.
+    pub fn find_note(&self, notes_ref: &FullNameRef, object: &gix_hash::oid) -> Result<Option<Blob<'_>>, find::Error> {
+        let Some((_, tree)) = self.notes_commit(notes_ref)? else {
+            return Ok(None);
+        };
+        match gix_note::find(&tree, object, self)? {
+            Some(note) => Ok(Some(self.find_blob(note)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Read all notes at `notes_ref`, which are empty if `notes_ref` doesn't exist.
+    pub fn notes(&self, notes_ref: &FullNameRef) -> Result<Notes, find::Error> {
+        Ok(match self.notes_commit(notes_ref)? {
+            Some((_, tree)) => Notes::from_tree(&tree, self)?,
+            None => Notes::new(self.object_hash()),
+        })
+    }
+
+    /// Set the note of `object` at `notes_ref` to `message` in a new notes commit, similar to `git notes add`,
+    /// and return the id of that commit.
+    ///
+    /// If `object` already has a note, it's only replaced if `overwrite` is `true`.
+    /// If `message` is empty, the note of `object` is removed instead.
+    pub fn add_note(
+        &self,
+        notes_ref: &FullNameRef,
+        object: impl Into<ObjectId>,
+        message: &BStr,
+        overwrite: bool,
+    ) -> Result<ObjectId, edit::Error> {
+        let object = object.into();
+        let (previous, mut notes) = self.notes_to_edit(notes_ref)?;
+        if notes.get(&object).is_some() && !overwrite {
+            return Err(edit::Error::NoteExists {
+                object,
+                notes_ref: notes_ref.as_bstr().into(),
+            });
+        }
+        if message.is_empty() {
+            notes.remove(&object);
+        } else {
+            notes.insert(object, self.write_note(message.to_owned())?);
+        }
+        self.commit_notes(notes_ref, previous, &notes, "Notes added by 'git notes add'", previous)
+    }
+
+    /// Append `message` to the note of `object` at `notes_ref`, separated by an empty line, or add it if there
+    /// is no note yet, in a new notes commit similar to `git notes append`, and return the id of that commit.
+    pub fn append_note(
+        &self,
+        notes_ref: &FullNameRef,
+        object: impl Into<ObjectId>,
+        message: &BStr,
+    ) -> Result<ObjectId, edit::Error> {
+        let object = object.into();
+        let (previous, mut notes) = self.notes_to_edit(notes_ref)?;
+        let mut note = match notes.get(&object) {
+            Some(note) => self.find_blob(note).map_err(find::Error::from)?.detach().data,
+            None => Vec::new(),
+        };
+        if !note.is_empty() && !message.is_empty() {
+            note.push(b'\n');
+        }
+        note.extend_from_slice(message);
+        if !note.is_empty() {
+            notes.insert(object, self.write_note(note.into())?);
+        }
+        self.commit_notes(
+            notes_ref,
+            previous,
+            &notes,
+            "Notes added by 'git notes append'",
+            previous,
+        )
+    }
+
+    /// Give the object `to` the same note that `from` has at `notes_ref` in a new notes commit, similar to
+    /// `git notes copy`, and return the id of that commit.
+    ///
+    /// If `to` already has a note, it's only replaced if `overwrite` is `true`.
+    pub fn copy_note(
+        &self,
+        notes_ref: &FullNameRef,
+        from: &gix_hash::oid,
+        to: impl Into<ObjectId>,
+        overwrite: bool,
+    ) -> Result<ObjectId, edit::Error> {
+        let to = to.into();
+        let (previous, mut notes) = self.notes_to_edit(notes_ref)?;
+        let note = notes.get(from).ok_or_else(|| edit::Error::NoteMissing {
+            object: from.to_owned(),
+            notes_ref: notes_ref.as_bstr().into(),
+        })?;
+        let note = note.to_owned();
+        if notes.get(&to).is_some() && !overwrite {
+            return Err(edit::Error::NoteExists {
+                object: to,
+                notes_ref: notes_ref.as_bstr().into(),
+            });
+        }
+        notes.insert(to, note);
+        self.commit_notes(notes_ref, previous, &notes, "Notes added by 'git notes copy'", previous)
+    }
+
+    /// Remove the note of `object` at `notes_ref` in a new notes commit, similar to `git notes remove`,
+    /// and return the id of that commit.
+    pub fn remove_note(&self, notes_ref: &FullNameRef, object: &gix_hash::oid) -> Result<ObjectId, edit::Error> {
+        let (previous, mut notes) = self.notes_to_edit(notes_ref)?;
+        if notes.remove(object).is_none() {
+            return Err(edit::Error::NoteMissing {
+                object: object.to_owned(),
+                notes_ref: notes_ref.as_bstr().into(),
+            });
+        }
+        self.commit_notes(
+            notes_ref,
+            previous,
+            &notes,
+            "Notes removed by 'git notes remove'",
+            previous,
+        )
+    }
+
+    /// Merge the notes at `other` into the notes at `notes_ref`, similar to `git notes merge`, and resolve notes
+    /// changed on both sides with `strategy`.
+    ///
+    /// If possible, `notes_ref` is fast-forwarded to `other`. Otherwise, the changes made to the notes at `other` since
+    /// their merge-base are merged into those at `notes_ref` in a new notes commit with both notes commits as parents.
+    pub fn merge_notes(
+        &self,
+        notes_ref: &FullNameRef,
+        other: &FullNameRef,
+        strategy: Strategy,
+    ) -> Result<merge::Outcome, merge::Error> {
+        let Some((theirs, their_tree)) = self.notes_commit(other)? else {
+            return Err(merge::Error::MissingReference {
+                notes_ref: other.as_bstr().into(),
+            });
+        };
+        let Some((ours, our_tree)) = self.notes_commit(notes_ref)? else {
+            self.update_notes_ref(notes_ref, None, theirs, other)?;
+            return Ok(merge::Outcome::FastForward { commit: theirs });
+        };
+        let base = match self.merge_base(ours, theirs) {
+            Ok(base) => Some(base.detach()),
+            Err(crate::repository::merge_base::Error::NotFound { .. }) => None,
+            Err(err) => return Err(err.into()),
+        };
+        if base == Some(theirs) {
+            return Ok(merge::Outcome::UpToDate);
+        }
+        if base == Some(ours) {
+            self.update_notes_ref(notes_ref, Some(ours), theirs, other)?;
+            return Ok(merge::Outcome::FastForward { commit: theirs });
+        }
+
+        let base_notes = match base {
+            Some(base) => self.notes_of_commit(base)?,
+            None => Notes::new(self.object_hash()),
+        };
+        let our_notes = Notes::from_tree(&our_tree, self).map_err(find::Error::from)?;
+        let their_notes = Notes::from_tree(&their_tree, self).map_err(find::Error::from)?;
+        let merged = gix_note::merge(&base_notes, &our_notes, &their_notes, strategy, self)?;
+        let message = format!("Merged notes from {} into {}", other.as_bstr(), notes_ref.as_bstr());
+        let commit = self.commit_notes(notes_ref, Some(ours), &merged, &message, [ours, theirs])?;
+        Ok(merge::Outcome::Merged { commit })
+    }
+
+    /// Return the notes commit at `notes_ref` and its tree, if `notes_ref` exists.
+    fn notes_commit(&self, notes_ref: &FullNameRef) -> Result<Option<(ObjectId, ObjectId)>, find::Error> {
+        let Some(mut reference) = self.try_find_reference(notes_ref)? else {
+            return Ok(None);
+        };
+        let commit = reference.peel_to_commit()?;
+        Ok(Some((commit.id, commit.tree_id()?.detach())))
+    }
+
+    /// Read the notes of the notes commit `commit`.
+    fn notes_of_commit(&self, commit: ObjectId) -> Result<Notes, find::Error> {
+        let tree = self.find_commit(commit).map_err(find::Error::from)?.tree_id()?;
+        Ok(Notes::from_tree(&tree, self)?)
+    }
+
+    /// Return the notes commit at `notes_ref`, if it exists, along with its notes for editing.
+    fn notes_to_edit(&self, notes_ref: &FullNameRef) -> Result<(Option<ObjectId>, Notes), find::Error> {
+        Ok(match self.notes_commit(notes_ref)? {
+            Some((commit, tree)) => (Some(commit), Notes::from_tree(&tree, self)?),
+            None => (None, Notes::new(self.object_hash())),
+        })
+    }
+
+    /// Write `message` as note, making sure it ends with a newline.
+    fn write_note(&self, mut message: BString) -> Result<ObjectId, edit::Error> {
+        if message.last() != Some(&b'\n') {
+            message.push(b'\n');
+        }
+        Ok(self.write_blob(&message)?.detach())
+    }
+
+    /// Write `notes` into a notes commit with `message` and `parents`, and point `notes_ref` to it if it's still at `previous`.
+    fn commit_notes(
+        &self,
+        notes_ref: &FullNameRef,
+        previous: Option<ObjectId>,
+        notes: &Notes,
+        message: &str,
+        parents: impl IntoIterator<Item = ObjectId>,
+    ) -> Result<ObjectId, edit::Error> {
+        let tree = notes.write_tree(self)?;
+        let committer = self.committer().ok_or(edit::Error::IdentityMissing)??;
+        let author = self.author().ok_or(edit::Error::IdentityMissing)??;
+        let commit = gix_object::Commit {
+            message: format!("{message}\n").into(),
+            tree,
+            author: author.into(),
+            committer: committer.into(),
+            encoding: None,
+            parents: parents.into_iter().collect(),
+            extra_headers: Default::default(),
+        };
+        let commit = self.write_object(commit)?.detach();
+        self.edit_notes_ref(notes_ref, previous, commit, format!("notes: {message}").into())?;
+        Ok(commit)
+    }
+
+    /// Fast-forward `notes_ref` from `previous` to `commit`, the notes commit at `other`.
+    fn update_notes_ref(
+        &self,
+        notes_ref: &FullNameRef,
+        previous: Option<ObjectId>,
+        commit: ObjectId,
+        other: &FullNameRef,
+    ) -> Result<(), edit::Error> {
+        let message = format!(
+            "notes: Merged notes from {} into {}",
+            other.as_bstr(),
+            notes_ref.as_bstr()
+        );
+        self.edit_notes_ref(notes_ref, previous, commit, message.into())
+    }
+
+    /// Point `notes_ref` to `commit` if it's still at `previous`, and log the change with `message`.
+    fn edit_notes_ref(
+        &self,
+        notes_ref: &FullNameRef,
+        previous: Option<ObjectId>,
+        commit: ObjectId,
+        message: BString,
+    ) -> Result<(), edit::Error> {
+        self.edit_reference(RefEdit {
+            change: Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: false,
+                    message,
+                },
+                expected: match previous {
+                    Some(previous) => PreviousValue::MustExistAndMatch(gix_ref::Target::Object(previous)),
+                    None => PreviousValue::MustNotExist,
+                },
+                new: gix_ref::Target::Object(commit),
+            },
+            name: notes_ref.to_owned(),
+            deref: false,
+        })?;
+        Ok(())
+    }
+}
@@ -240,6 +240,25 @@ impl crate::Repository {
             None => Ok(None),
         }
     }
+
+    /// Return `true` if objects that are missing in this partial clone are obtained from a promisor remote on demand,
+    /// which makes it worth to [obtain them in batches](Self::fetch_missing_objects()) before they are accessed.
+    #[cfg(feature = "blob-diff")]
+    pub(crate) fn fetches_missing_objects(&self) -> bool {
+        self.objects.store_ref().promisor().is_some()
+    }
+
+    /// Obtain all objects in `ids` that are missing in this partial clone from the promisor remote at once,
+    /// which is much faster than obtaining them one at a time while they are accessed.
+    ///
+    /// This does nothing if missing objects aren't obtained from a promisor remote.
+    #[cfg(any(feature = "blob-diff", feature = "worktree-mutation"))]
+    pub(crate) fn fetch_missing_objects(
+        &self,
+        ids: impl IntoIterator<Item = ObjectId>,
+    ) -> Result<(), gix_odb::store::find::Error> {
+        self.objects.fetch_missing(ids)
+    }
 }
 
 /// Write objects of any type.
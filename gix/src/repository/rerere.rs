@@ -0,0 +1,185 @@
+use gix_index::entry::{Flags, Stage};
+use gix_merge::blob::builtin_driver::text;
+
+use crate::{
+    Repository,
+    bstr::{BStr, ByteSlice},
+    config::{cache::util::ApplyLeniencyDefault, tree},
+    rerere::{Error, Outcome},
+};
+
+/// Rerere
+impl Repository {
+    /// Return `true` if conflicts and their resolutions should be recorded and reused, which is the case if `rerere.enabled`
+    /// is set, or if it's unset and the `rr-cache` directory exists.
+    pub fn rerere_enabled(&self) -> Result<bool, Error> {
+        let enabled = self
+            .config
+            .resolved
+            .boolean(tree::Rerere::ENABLED)
+            .map(|res| tree::Rerere::ENABLED.enrich_error(res))
+            .transpose()
+            .with_lenient_default(self.config.lenient_config)?;
+        Ok(enabled.unwrap_or_else(|| self.rerere_cache().dir().is_dir()))
+    }
+
+    /// Return the `rr-cache` directory in which conflicts and their resolutions are recorded.
+    pub fn rerere_cache(&self) -> gix_rerere::Cache {
+        gix_rerere::Cache::at(self.common_dir().join("rr-cache"))
+    }
+
+    /// Record the conflicts of all unmerged paths in the index as well as the resolutions of previously recorded conflicts,
+    /// and resolve conflicts in the worktree for which a resolution is known, akin to `git rerere`.
+    ///
+    /// Paths with conflicts are tracked in the `MERGE_RR` file until their resolution is recorded.
+    /// If `rerere.autoUpdate` is set, files resolved with a recorded resolution are added to the index.
+    ///
+    /// Return `None` if [rerere isn't enabled](Self::rerere_enabled()).
+    pub fn rerere(&self) -> Result<Option<Outcome>, Error> {
+        if !self.rerere_enabled()? {
+            return Ok(None);
+        }
+        let workdir = self.workdir().ok_or(Error::MissingWorktree)?;
+        let mut index = if self.index_path().is_file() {
+            Some(self.open_index()?)
+        } else {
+            None
+        };
+        let mut conflicted_paths: Vec<&BStr> = index
+            .iter()
+            .flat_map(|index| {
+                index
+                    .entries()
+                    .iter()
+                    .filter(|entry| entry.stage() != Stage::Unconflicted)
+                    .map(|entry| entry.path(index))
+            })
+            .collect();
+        conflicted_paths.dedup();
+
+        let merge_rr_path = self.git_dir().join("MERGE_RR");
+        let mut state = gix_rerere::MergeRr::at(&merge_rr_path, self.object_hash())?;
+        let outcome = gix_rerere::record(
+            conflicted_paths,
+            workdir,
+            &self.rerere_cache(),
+            &mut state,
+            gix_rerere::record::Options {
+                marker_size: self.conflict_marker_size()?,
+                object_hash: self.object_hash(),
+            },
+        )?;
+        if state.entries.is_empty() {
+            match std::fs::remove_file(&merge_rr_path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+                _ => {}
+            }
+        } else {
+            let mut lock =
+                gix_lock::File::acquire_to_update_resource(&merge_rr_path, gix_lock::acquire::Fail::Immediately, None)?;
+            state.write_to(&mut lock)?;
+            lock.commit().map_err(|err| err.error)?;
+        }
+
+        if let Some(index) = index.as_mut().filter(|_| !outcome.resolved.is_empty()) {
+            if self.rerere_auto_update()? {
+                self.stage_resolved(index, &outcome.resolved)?;
+            }
+        }
+        Ok(Some(outcome))
+    }
+
+    /// Replay recorded resolutions on all content merges among `conflicts` that still have conflict markers, writing the
+    /// resolved blobs and placing them into `tree`.
+    /// With `rerere.autoUpdate`, the resolved content merges are also marked as complete.
+    pub(crate) fn rerere_replay(
+        &self,
+        tree: &mut gix_object::tree::Editor<'_>,
+        conflicts: &mut [gix_merge::tree::Conflict],
+        marker_size: Option<u8>,
+    ) -> Result<(), Error> {
+        let Some(marker_size) = marker_size else {
+            return Ok(());
+        };
+        if !conflicts.iter().any(has_conflict_markers) || !self.rerere_enabled()? {
+            return Ok(());
+        }
+        let cache = self.rerere_cache();
+        let auto_update = self.rerere_auto_update()?;
+        for conflict in conflicts.iter_mut().filter(|conflict| has_conflict_markers(conflict)) {
+            let location = conflict.ours.location().to_owned();
+            let Ok(gix_merge::tree::Resolution::OursModifiedTheirsModifiedThenBlobContentMerge { merged_blob }) =
+                &mut conflict.resolution
+            else {
+                unreachable!("BUG: only conflicting content merges are considered")
+            };
+            let blob = self.find_blob(merged_blob.merged_blob_id)?;
+            let Some(normalized) = gix_rerere::normalize(&blob.data, marker_size.into(), self.object_hash())? else {
+                continue;
+            };
+            let Some(resolved) = cache.resolve(&normalized.id, &normalized.preimage)? else {
+                continue;
+            };
+            let components = || location.split(|b| *b == b'/');
+            let Some(mode) = tree.get(components()).map(|entry| entry.mode) else {
+                continue;
+            };
+            let id = self.write_blob(&resolved.content)?.detach();
+            tree.upsert(components(), mode.kind(), id)?;
+            merged_blob.merged_blob_id = id;
+            if auto_update {
+                merged_blob.resolution = gix_merge::blob::Resolution::Complete;
+            }
+        }
+        Ok(())
+    }
+
+    fn rerere_auto_update(&self) -> Result<bool, Error> {
+        Ok(self
+            .config
+            .resolved
+            .boolean(tree::Rerere::AUTO_UPDATE)
+            .map(|res| tree::Rerere::AUTO_UPDATE.enrich_error(res))
+            .transpose()
+            .with_lenient_default(self.config.lenient_config)?
+            .unwrap_or_default())
+    }
+
+    fn conflict_marker_size(&self) -> Result<usize, Error> {
+        Ok(self
+            .blob_merge_options()?
+            .text
+            .conflict
+            .marker_size()
+            .unwrap_or(text::Conflict::DEFAULT_MARKER_SIZE)
+            .into())
+    }
+
+    /// Replace the conflicting entries of all `paths` in `index` with the current content of their worktree files.
+    fn stage_resolved(&self, index: &mut gix_index::File, paths: &[crate::bstr::BString]) -> Result<(), Error> {
+        let (mut pipeline, _) = self.filter_pipeline(None)?;
+        let mut staged = Vec::new();
+        for path in paths {
+            if let Some((id, kind, _)) = pipeline.worktree_file_to_object(path.as_bstr(), index)? {
+                staged.push((path, id, gix_object::tree::EntryMode::from(kind).into()));
+            }
+        }
+        index.remove_entries(|_, path, entry| {
+            entry.stage() != Stage::Unconflicted && staged.iter().any(|(staged, _, _)| *staged == path)
+        });
+        for (path, id, mode) in staged {
+            index.dangerously_push_entry(Default::default(), id, Flags::empty(), mode, path.as_bstr());
+        }
+        index.sort_entries();
+        index.write(Default::default())?;
+        Ok(())
+    }
+}
+
+fn has_conflict_markers(conflict: &gix_merge::tree::Conflict) -> bool {
+    matches!(
+        &conflict.resolution,
+        Ok(gix_merge::tree::Resolution::OursModifiedTheirsModifiedThenBlobContentMerge { merged_blob })
+            if merged_blob.resolution == gix_merge::blob::Resolution::Conflict
+    )
+}
@@ -0,0 +1,253 @@
+use gix_hash::ObjectId;
+use gix_revision::Spec;
+use gix_sequencer::{
+    State,
+    state::{Action, Step},
+};
+
+use crate::{
+    Repository,
+    bstr::{BStr, ByteSlice},
+    prelude::ObjectIdExt,
+    sequence::{Error, Options, Outcome},
+    worktree::checkout::Checkout,
+};
+
+/// Cherry-pick and revert
+impl Repository {
+    /// Apply the changes introduced by the commits selected by `specs` on top of `HEAD`, one after another, akin to
+    /// `git cherry-pick <commit>...`.
+    ///
+    /// Each spec is [parsed](Self::rev_parse()) like `git` would. If all of them name single commits, these are applied
+    /// in the given order. Otherwise, ranges like `A..B`, `^A` or `B^!` are walked along with all other commits in
+    /// topological order, and the commits found are applied oldest first, skipping merge commits unless
+    /// [`options.mainline`](Options::mainline) is set. Symmetric differences like `A...B` aren't supported.
+    ///
+    /// Each commit is merged with [`gix_merge::tree()`] using its parent as merge-base, and committed with the original
+    /// author and message. For merge commits, [`options.mainline`](Options::mainline) selects the parent to use.
+    /// It's an error to set it if a commit that is named on its own isn't a merge, but commits of ranges may be anything.
+    ///
+    /// If a commit can't be applied without conflicts, the sequence stops and the conflicts are written to the
+    /// index and worktree, with [`CHERRY_PICK_HEAD`](gix_sequencer::CHERRY_PICK_HEAD) pointing to the commit.
+    /// Use [`sequence_continue()`](Self::sequence_continue()) once they are resolved.
+    ///
+    /// This fails if a sequence is already in progress, or if the index or worktree have changes unless
+    /// [`options.no_commit`](Options::no_commit) is set.
+    pub fn cherry_pick(
+        &self,
+        specs: impl IntoIterator<Item = impl AsRef<BStr>>,
+        options: Options,
+    ) -> Result<Outcome<'_>, Error> {
+        self.start_sequence(Action::Pick, specs, options)
+    }
+
+    /// Revert the changes introduced by the commits selected by `specs`, one after another, by creating new commits
+    /// on top of `HEAD`, akin to `git revert <commit>...`.
+    ///
+    /// Unlike with [`cherry_pick()`](Self::cherry_pick()), the commits of ranges are reverted newest first.
+    /// Each commit is merged with [`gix_merge::tree()`] using the commit itself as merge-base and its parent as the
+    /// changes to apply. For merge commits, [`options.mainline`](Options::mainline) selects the parent to revert to.
+    /// Everything else works like [`cherry_pick()`](Self::cherry_pick()), with [`REVERT_HEAD`](gix_sequencer::REVERT_HEAD)
+    /// pointing to the commit if the sequence stops.
+    pub fn revert(
+        &self,
+        specs: impl IntoIterator<Item = impl AsRef<BStr>>,
+        options: Options,
+    ) -> Result<Outcome<'_>, Error> {
+        self.start_sequence(Action::Revert, specs, options)
+    }
+
+    /// Commit the resolved conflicts of a stopped cherry-pick or revert, using the message in `.git/MERGE_MSG`,
+    /// and apply all remaining commits, akin to `git cherry-pick --continue`.
+    ///
+    /// If the sequence isn't stopped, it just applies the remaining commits.
+    #[doc(alias = "continue")]
+    pub fn sequence_continue(&self) -> Result<Outcome<'_>, Error> {
+        let mut state = self.sequence_state()?;
+        let (options, mut checkout) = self.sequence_options()?;
+        if gix_sequencer::sequence::stopped_at(&self.refs)?.is_some() {
+            gix_sequencer::sequence::resume(&mut state, &self.refs, self, &mut checkout, &options)?;
+        }
+        self.run_sequence(&mut state, &options, &mut checkout)
+    }
+
+    /// Drop the commit the sequence stopped at along with the changes in the index and worktree,
+    /// and apply all remaining commits, akin to `git cherry-pick --skip`.
+    pub fn sequence_skip(&self) -> Result<Outcome<'_>, Error> {
+        let mut state = self.sequence_state()?;
+        let (options, mut checkout) = self.sequence_options()?;
+        gix_sequencer::sequence::skip(&mut state, &self.refs, self, &mut checkout)?;
+        self.run_sequence(&mut state, &options, &mut checkout)
+    }
+
+    /// Restore `HEAD`, the index and the worktree to what they were before the cherry-pick or revert started,
+    /// akin to `git cherry-pick --abort`.
+    pub fn sequence_abort(&self) -> Result<(), Error> {
+        let state = self.sequence_state()?;
+        let (options, mut checkout) = self.sequence_options()?;
+        gix_sequencer::sequence::abort(
+            state,
+            &self.refs,
+            self,
+            &mut checkout,
+            options.committer.to_ref(&mut Default::default()),
+        )?;
+        Ok(())
+    }
+
+    /// Forget about the cherry-pick or revert in progress, leaving `HEAD`, the index and the worktree as they are,
+    /// akin to `git cherry-pick --quit`.
+    pub fn sequence_quit(&self) -> Result<(), Error> {
+        gix_sequencer::sequence::quit(&self.refs)?;
+        Ok(())
+    }
+}
+
+/// Utilities
+impl Repository {
+    fn start_sequence(
+        &self,
+        action: Action,
+        specs: impl IntoIterator<Item = impl AsRef<BStr>>,
+        options: Options,
+    ) -> Result<Outcome<'_>, Error> {
+        let (sequence_options, mut checkout) = self.sequence_options()?;
+        if !options.no_commit && self.is_dirty()? {
+            return Err(Error::Dirty);
+        }
+        let commits = self.commits_to_apply(specs, action, options.mainline)?;
+        let steps = commits
+            .into_iter()
+            .map(|id| -> Result<_, Error> {
+                let commit = self.find_commit(id)?;
+                Ok(Step {
+                    action,
+                    summary: commit.message()?.summary().into_owned(),
+                    id: commit.id,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut state = gix_sequencer::sequence::start(steps, options, &self.refs, self)?;
+        self.run_sequence(&mut state, &sequence_options, &mut checkout)
+    }
+
+    /// Resolve `specs` into the commits to apply for `action`, in the order they are given, or in the order `git` would
+    /// apply them if a range is involved.
+    ///
+    /// If `mainline` is set, commits that are named on their own must be merges, while ranges may contain any commit.
+    fn commits_to_apply(
+        &self,
+        specs: impl IntoIterator<Item = impl AsRef<BStr>>,
+        action: Action,
+        mainline: Option<usize>,
+    ) -> Result<Vec<ObjectId>, Error> {
+        let mut tips = Vec::new();
+        let mut hidden = Vec::new();
+        let mut needs_walk = false;
+        for spec in specs {
+            let spec = spec.as_ref();
+            match self.rev_parse(spec)?.detach() {
+                Spec::Include(id) => tips.push(id),
+                Spec::Exclude(id) => {
+                    hidden.push(id);
+                    needs_walk = true;
+                }
+                Spec::Range { from, to } => {
+                    hidden.push(from);
+                    tips.push(to);
+                    needs_walk = true;
+                }
+                Spec::IncludeOnlyParents(id) => tips.extend(self.find_commit(id)?.parent_ids().map(crate::Id::detach)),
+                Spec::ExcludeParents(id) => {
+                    hidden.extend(self.find_commit(id)?.parent_ids().map(crate::Id::detach));
+                    tips.push(id);
+                    needs_walk = true;
+                }
+                Spec::Merge { .. } => return Err(Error::UnsupportedSpec { spec: spec.to_owned() }),
+            }
+        }
+        if !needs_walk {
+            if mainline.is_some() {
+                for id in &tips {
+                    if self.find_commit(*id)?.parent_ids().count() < 2 {
+                        return Err(Error::MainlineWithoutMerge { id: *id });
+                    }
+                }
+            }
+            return Ok(tips);
+        }
+        let mut commits = Vec::new();
+        for info in gix_traverse::commit::topo::Builder::from_iters(&self.objects, tips, Some(hidden))
+            .sorting(gix_traverse::commit::topo::Sorting::TopoOrder)
+            .build()?
+        {
+            let info = info?;
+            if mainline.is_some() || info.parent_ids.len() < 2 {
+                commits.push(info.id);
+            }
+        }
+        if action == Action::Pick {
+            commits.reverse();
+        }
+        Ok(commits)
+    }
+
+    fn run_sequence(
+        &self,
+        state: &mut State,
+        options: &gix_sequencer::sequence::Options,
+        checkout: &mut Checkout<'_>,
+    ) -> Result<Outcome<'_>, Error> {
+        let mut diff_cache = self.diff_resource_cache_for_tree_diff()?;
+        let mut blob_merge = self.merge_resource_cache(Default::default())?;
+        Ok(
+            match gix_sequencer::sequence::run(
+                state,
+                &self.refs,
+                self,
+                checkout,
+                &mut diff_cache,
+                &mut blob_merge,
+                &mut |id| id.to_owned().attach(self).shorten_or_id().to_string(),
+                options,
+            )? {
+                gix_sequencer::sequence::Outcome::Finished { head } => Outcome::Finished {
+                    head: head.attach(self),
+                },
+                gix_sequencer::sequence::Outcome::Stopped { id, action, tree_merge } => Outcome::Stopped {
+                    id: id.attach(self),
+                    action,
+                    tree_merge: crate::merge::tree::Outcome {
+                        tree: crate::object::tree::Editor {
+                            inner: tree_merge.tree,
+                            validate: self.config.protect_options()?,
+                            repo: self,
+                        },
+                        conflicts: tree_merge.conflicts,
+                        failed_on_first_unresolved_conflict: tree_merge.failed_on_first_unresolved_conflict,
+                    },
+                },
+            },
+        )
+    }
+
+    fn sequence_state(&self) -> Result<State, Error> {
+        State::read_resolving(self.git_dir(), |name| {
+            let prefix = gix_hash::Prefix::from_hex(name.to_str().ok()?).ok()?;
+            self.objects.lookup_prefix(prefix, None).ok()??.ok()
+        })?
+        .ok_or(Error::NotInProgress)
+    }
+
+    fn sequence_options(&self) -> Result<(gix_sequencer::sequence::Options, Checkout<'_>), Error> {
+        let treat_as_unresolved = gix_merge::tree::TreatAsUnresolved::git();
+        let checkout = Checkout::new(self, treat_as_unresolved).ok_or(Error::MissingWorktree)?;
+        let committer = self.committer().ok_or(Error::CommitterMissing)??.into();
+        let options = gix_sequencer::sequence::Options {
+            tree_merge: self.tree_merge_options()?.into(),
+            treat_as_unresolved,
+            committer,
+        };
+        Ok((options, checkout))
+    }
+}
@@ -0,0 +1,415 @@
+use std::collections::BTreeSet;
+
+use gix_hash::ObjectId;
+use gix_merge::tree::TreatAsUnresolved;
+use gix_ref::transaction::{self, LogChange, PreviousValue, RefEdit, RefLog};
+
+use crate::{
+    Id, Repository,
+    bstr::ByteSlice,
+    prelude::ObjectIdExt,
+    stash::{ApplyOptions, Entry, Error, Options, Outcome, REFERENCE},
+    status::{UntrackedFiles, index_worktree::Item, plumbing::index_as_worktree::Change},
+    worktree::checkout::Checkout,
+};
+
+/// Stash
+impl Repository {
+    /// Record the changes in the index and worktree in a new stash entry and reset both to `HEAD`, akin to `git stash push`.
+    ///
+    /// The stash commit holds the state of the worktree with `HEAD` as first parent, and a commit with the state of the index
+    /// as second parent. With [`options.include_untracked`](Options::include_untracked), untracked files are recorded in a
+    /// third parent and removed from the worktree.
+    ///
+    /// Return the id of the stash commit, or `None` if there was nothing to stash.
+    #[doc(alias = "stash")]
+    pub fn stash_push(&self, options: Options) -> Result<Option<Id<'_>>, Error> {
+        let checkout = self.stash_checkout()?;
+        let head = self.head()?;
+        if head.is_unborn() {
+            return Err(Error::UnbornHead);
+        }
+        let branch = head
+            .referent_name()
+            .map_or_else(|| "(no branch)".into(), |name| name.shorten().to_owned());
+        let head_commit = self.head_commit()?;
+        let head_tree = head_commit.tree_id()?.detach();
+        let description = format!(
+            "{branch}: {short} {subject}",
+            short = head_commit.id().shorten_or_id(),
+            subject = head_commit.message()?.summary()
+        );
+
+        let index = checkout.current_index().map_err(Error::Checkout)?;
+        if index.entries().iter().any(|entry| entry.stage_raw() != 0) {
+            return Err(Error::UnmergedIndex);
+        }
+        let index_tree = checkout.write_tree().map_err(Error::Checkout)?;
+
+        let (mut pipeline, _) = self.filter_pipeline(None)?;
+        let mut worktree = self.edit_tree(index_tree)?;
+        let mut untracked = self.edit_tree(ObjectId::empty_tree(self.object_hash()))?;
+        let mut dirty = BTreeSet::new();
+        let mut untracked_paths = Vec::new();
+        for item in self.worktree_status(&index, options.include_untracked)? {
+            match item? {
+                Item::Modification {
+                    rela_path,
+                    status: gix_status::index_as_worktree::EntryStatus::Change(change),
+                    ..
+                } => {
+                    match change {
+                        Change::Removed => {
+                            worktree.remove(rela_path.as_bstr())?;
+                        }
+                        Change::Type { .. } | Change::Modification { .. } => {
+                            match pipeline.worktree_file_to_object(rela_path.as_ref(), &index)? {
+                                Some((id, kind, _)) => worktree.upsert(rela_path.as_bstr(), kind, id)?,
+                                None => worktree.remove(rela_path.as_bstr())?,
+                            };
+                        }
+                        Change::SubmoduleModification(_) => continue,
+                    }
+                    dirty.insert(rela_path);
+                }
+                Item::DirectoryContents { entry, .. }
+                    if entry.status == gix_dir::entry::Status::Untracked
+                        && matches!(
+                            entry.disk_kind,
+                            Some(gix_dir::entry::Kind::File | gix_dir::entry::Kind::Symlink)
+                        ) =>
+                {
+                    if let Some((id, kind, _)) = pipeline.worktree_file_to_object(entry.rela_path.as_ref(), &index)? {
+                        untracked.upsert(entry.rela_path.as_bstr(), kind, id)?;
+                        untracked_paths.push(entry.rela_path);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if index_tree == head_tree && dirty.is_empty() && untracked_paths.is_empty() {
+            return Ok(None);
+        }
+
+        let index_commit = self.write_stash_commit(
+            format!("index on {description}\n"),
+            index_tree,
+            vec![head_commit.id],
+        )?;
+        let mut parents = vec![head_commit.id, index_commit];
+        if !untracked_paths.is_empty() {
+            let untracked_tree = untracked.write()?.detach();
+            parents.push(self.write_stash_commit(
+                format!("untracked files on {description}\n"),
+                untracked_tree,
+                Vec::new(),
+            )?);
+        }
+        let message = match &options.message {
+            Some(message) => format!("On {branch}: {}\n", message.to_str_lossy().trim_end()),
+            None => format!("WIP on {description}\n"),
+        };
+        let worktree_tree = worktree.write()?.detach();
+        let stash = self.write_stash_commit(message.clone(), worktree_tree, parents)?;
+        self.edit_reference(RefEdit {
+            change: transaction::Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: true,
+                    message: message.lines().next().unwrap_or_default().into(),
+                },
+                expected: PreviousValue::Any,
+                new: gix_ref::Target::Object(stash),
+            },
+            name: REFERENCE.try_into().expect("valid"),
+            deref: false,
+        })?;
+
+        checkout
+            .reset_with_dirty(&head_tree, &dirty)
+            .map_err(Error::Checkout)?;
+        let workdir = self.workdir().ok_or(Error::MissingWorktree)?;
+        for rela_path in untracked_paths {
+            let path = workdir.join(gix_path::from_bstr(rela_path.as_bstr()));
+            match std::fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(Error::Checkout(err.into()));
+                }
+                _ => crate::worktree::checkout::remove_empty_parents(&path, workdir),
+            }
+        }
+        Ok(Some(stash.attach(self)))
+    }
+
+    /// Return all stash entries, with the most recent one first, akin to `git stash list`.
+    ///
+    /// The position of an entry in the list is its index, as used in `stash@{<index>}`.
+    pub fn stash_list(&self) -> Result<Vec<Entry<'_>>, Error> {
+        let mut buf = Vec::new();
+        let Some(lines) = self.refs.reflog_iter(REFERENCE, &mut buf)? else {
+            return Ok(Vec::new());
+        };
+        let mut entries = lines
+            .map(|line| {
+                line.map(|line| Entry {
+                    id: line.new_oid().attach(self),
+                    message: line.message.into(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Return the changes recorded in the stash entry at `index`, compared to the commit it was created on,
+    /// akin to `git stash show`.
+    pub fn stash_show(&self, index: usize) -> Result<Vec<crate::object::tree::diff::ChangeDetached>, Error> {
+        let stash = self.stash_entry(index)?;
+        let commit = self.find_commit(stash)?;
+        let base = commit.parent_ids().next().ok_or(Error::NotAStash { id: stash })?;
+        let base_tree = self.find_commit(base)?.tree()?;
+        Ok(self.diff_tree_to_tree(&base_tree, &commit.tree()?, None)?)
+    }
+
+    /// Remove the stash entry at `index`, akin to `git stash drop`, and return the id of the stash commit it referred to.
+    ///
+    /// As reflog entries can't be removed individually, the stash reference is deleted along with its reflog,
+    /// and recreated with all remaining entries, one transaction per entry, with their original committer and message.
+    /// If the last entry is dropped, the stash reference stays deleted.
+    pub fn stash_drop(&self, index: usize) -> Result<Id<'_>, Error> {
+        let mut buf = Vec::new();
+        let mut lines: Vec<gix_ref::log::Line> = match self.refs.reflog_iter(REFERENCE, &mut buf)? {
+            Some(lines) => lines.map(|line| line.map(Into::into)).collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+        let top = lines.last().ok_or(Error::NoSuchEntry { index })?.new_oid;
+        let pos = lines
+            .len()
+            .checked_sub(index + 1)
+            .ok_or(Error::NoSuchEntry { index })?;
+        let dropped = lines.remove(pos);
+
+        let name: gix_ref::FullName = REFERENCE.try_into().expect("valid");
+        self.edit_reference(RefEdit {
+            change: transaction::Change::Delete {
+                expected: PreviousValue::MustExistAndMatch(gix_ref::Target::Object(top)),
+                log: RefLog::AndReference,
+            },
+            name: name.clone(),
+            deref: false,
+        })?;
+        let mut previous = None;
+        for line in lines {
+            self.edit_references_as(
+                Some(RefEdit {
+                    change: transaction::Change::Update {
+                        log: LogChange {
+                            mode: RefLog::AndReference,
+                            force_create_reflog: true,
+                            message: line.message,
+                        },
+                        expected: previous.map_or(PreviousValue::MustNotExist, |previous| {
+                            PreviousValue::MustExistAndMatch(gix_ref::Target::Object(previous))
+                        }),
+                        new: gix_ref::Target::Object(line.new_oid),
+                    },
+                    name: name.clone(),
+                    deref: false,
+                }),
+                Some(line.signature.to_ref(&mut gix_date::parse::TimeBuf::default())),
+            )?;
+            previous = Some(line.new_oid);
+        }
+        Ok(dropped.new_oid.attach(self))
+    }
+
+    /// Merge the changes of the stash entry at `index` into the index and worktree, akin to `git stash apply`.
+    ///
+    /// The worktree may have changes, as long as they aren't touched by the stash. Only newly added files are staged,
+    /// unless [`options.restore_index`](ApplyOptions::restore_index) is set.
+    /// If there are conflicts, they are written to the index and worktree, just like `git` would.
+    pub fn stash_apply(&self, index: usize, options: ApplyOptions) -> Result<Outcome<'_>, Error> {
+        let stash = self.stash_entry(index)?;
+        self.apply_stash(stash, options)
+    }
+
+    /// Like [`stash_apply()`](Self::stash_apply()), but [drop](Self::stash_drop()) the stash entry at `index` if it
+    /// could be applied without conflicts, akin to `git stash pop`.
+    pub fn stash_pop(&self, index: usize, options: ApplyOptions) -> Result<Outcome<'_>, Error> {
+        let outcome = self.stash_apply(index, options)?;
+        if matches!(outcome, Outcome::Applied) {
+            self.stash_drop(index)?;
+        }
+        Ok(outcome)
+    }
+}
+
+/// Utilities
+impl Repository {
+    fn stash_checkout(&self) -> Result<Checkout<'_>, Error> {
+        Checkout::new(self, TreatAsUnresolved::git()).ok_or(Error::MissingWorktree)
+    }
+
+    fn stash_entry(&self, index: usize) -> Result<ObjectId, Error> {
+        self.stash_list()?
+            .into_iter()
+            .nth(index)
+            .map(|entry| entry.id.detach())
+            .ok_or(Error::NoSuchEntry { index })
+    }
+
+    fn worktree_status(
+        &self,
+        index: &gix_index::File,
+        include_untracked: bool,
+    ) -> Result<crate::status::index_worktree::Iter, Error> {
+        Ok(self
+            .status(gix_features::progress::Discard)?
+            .index(crate::worktree::IndexPersistedOrInMemory::InMemory(index.clone()))
+            .index_worktree_submodules(None)
+            .untracked_files(if include_untracked {
+                UntrackedFiles::Files
+            } else {
+                UntrackedFiles::None
+            })
+            .into_index_worktree_iter(Vec::new())?)
+    }
+
+    fn write_stash_commit(&self, message: String, tree: ObjectId, parents: Vec<ObjectId>) -> Result<ObjectId, Error> {
+        let committer = self.committer().ok_or(Error::IdentityMissing)??;
+        let author = self.author().ok_or(Error::IdentityMissing)??;
+        let commit = gix_object::Commit {
+            message: message.into(),
+            tree,
+            author: author.into(),
+            committer: committer.into(),
+            encoding: None,
+            parents: parents.into(),
+            extra_headers: Default::default(),
+        };
+        Ok(self.write_object(commit)?.detach())
+    }
+
+    fn apply_stash(&self, stash: ObjectId, options: ApplyOptions) -> Result<Outcome<'_>, Error> {
+        let checkout = self.stash_checkout()?;
+        let treat_as_unresolved = TreatAsUnresolved::git();
+        let commit = self.find_commit(stash)?;
+        let parents: Vec<_> = commit.parent_ids().map(Id::detach).collect();
+        let (base, index_commit, untracked_commit) = match parents.as_slice() {
+            [base, index] => (*base, *index, None),
+            [base, index, untracked] => (*base, *index, Some(*untracked)),
+            _ => return Err(Error::NotAStash { id: stash }),
+        };
+        let stash_tree = commit.tree_id()?.detach();
+        let base_tree = self.find_commit(base)?.tree_id()?.detach();
+
+        let index = checkout.current_index().map_err(Error::Checkout)?;
+        if index.entries().iter().any(|entry| entry.stage_raw() != 0) {
+            return Err(Error::UnmergedIndex);
+        }
+        let current_tree = checkout.write_tree().map_err(Error::Checkout)?;
+        let mut dirty = BTreeSet::new();
+        for item in self.worktree_status(&index, false)? {
+            if let Item::Modification {
+                rela_path,
+                status: gix_status::index_as_worktree::EntryStatus::Change(change),
+                ..
+            } = item?
+            {
+                if !matches!(change, Change::SubmoduleModification(_)) {
+                    dirty.insert(rela_path);
+                }
+            }
+        }
+
+        let labels = gix_merge::blob::builtin_driver::text::Labels {
+            ancestor: Some("Version stash was based on".into()),
+            current: Some("Updated upstream".into()),
+            other: Some("Stashed changes".into()),
+        };
+        let merge_options = self.tree_merge_options()?;
+        let restored_index_tree = if options.restore_index {
+            let index_tree = self.find_commit(index_commit)?.tree_id()?.detach();
+            if index_tree == base_tree {
+                Some(current_tree)
+            } else {
+                let mut index_merge =
+                    self.merge_trees(base_tree, current_tree, index_tree, labels, merge_options.clone())?;
+                if index_merge.has_unresolved_conflicts(treat_as_unresolved) {
+                    return Err(Error::IndexConflicts);
+                }
+                Some(index_merge.tree.write()?.detach())
+            }
+        } else {
+            None
+        };
+        let mut tree_merge = self.merge_trees(base_tree, current_tree, stash_tree, labels, merge_options)?;
+        let merged_tree = tree_merge.tree.write()?.detach();
+        let mut merged = self.index_from_tree(&merged_tree)?;
+
+        let would_overwrite: Vec<_> = dirty
+            .iter()
+            .filter(|path| {
+                let before = index.entry_by_path(path.as_bstr()).map(|e| (e.id, e.mode));
+                let after = merged.entry_by_path(path.as_bstr()).map(|e| (e.id, e.mode));
+                before != after
+            })
+            .cloned()
+            .collect();
+        if !would_overwrite.is_empty() {
+            return Err(Error::WouldOverwrite { paths: would_overwrite });
+        }
+        if let Some(untracked_commit) = untracked_commit {
+            let untracked_tree = self.find_commit(untracked_commit)?.tree_id()?.detach();
+            let workdir = self.workdir().ok_or(Error::MissingWorktree)?;
+            let untracked = self.index_from_tree(&untracked_tree)?;
+            let existing: Vec<_> = untracked
+                .entries()
+                .iter()
+                .map(|entry| entry.path(&untracked))
+                .filter(|path| workdir.join(gix_path::from_bstr(*path)).symlink_metadata().is_ok())
+                .map(ToOwned::to_owned)
+                .collect();
+            if !existing.is_empty() {
+                return Err(Error::UntrackedExists { paths: existing });
+            }
+            checkout.write_untracked(&untracked_tree).map_err(Error::Checkout)?;
+        }
+
+        if tree_merge.has_unresolved_conflicts(treat_as_unresolved) {
+            checkout
+                .write_conflicts(&merged_tree, &tree_merge.conflicts)
+                .map_err(Error::Checkout)?;
+            return Ok(Outcome::Conflicts { tree_merge });
+        }
+        checkout
+            .update_worktree(&mut merged, &BTreeSet::new())
+            .map_err(Error::Checkout)?;
+
+        let mut new_index = self.index_from_tree(&restored_index_tree.unwrap_or(current_tree))?;
+        if !options.restore_index {
+            // Stage files that were added in the stash, just like `git` does.
+            let base = self.index_from_tree(&base_tree)?;
+            for entry in merged.entries() {
+                let path = entry.path(&merged);
+                if new_index.entry_by_path(path).is_none() && base.entry_by_path(path).is_none() {
+                    new_index.dangerously_push_entry(Default::default(), entry.id, entry.flags, entry.mode, path);
+                }
+            }
+            new_index.sort_entries();
+        }
+        // Keep the stat information of files that weren't changed since they were written, to avoid hashing them again.
+        for (entry, path) in new_index.entries_mut_with_paths() {
+            if let Some(written) = merged
+                .entry_by_path(path)
+                .filter(|written| written.id == entry.id && written.mode == entry.mode)
+            {
+                entry.stat = written.stat;
+            }
+        }
+        new_index
+            .write(Default::default())
+            .map_err(|err| Error::Checkout(err.into()))?;
+        Ok(Outcome::Applied)
+    }
+}
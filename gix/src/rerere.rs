@@ -0,0 +1,54 @@
+//! Record resolutions of merge conflicts and reuse them when the same conflicts occur again, akin to `git rerere`.
+//!
+//! If `rerere.enabled` is set, or if it's unset and the `rr-cache` directory exists, conflicts are recorded whenever they
+//! are written to the worktree by cherry-picks, reverts or when applying stashes.
+//! The resolution of a conflict is recorded once [`Repository::rerere()`](crate::Repository::rerere()) runs after it was
+//! resolved by the user, which happens automatically when continuing a sequence.
+//!
+//! Known resolutions are replayed by [`Repository::merge_trees()`](crate::Repository::merge_trees()) and
+//! [`Repository::merge_commits()`](crate::Repository::merge_commits()), so the merged blob won't have conflict markers anymore.
+//! Such files are only considered resolved if `rerere.autoUpdate` is set, just like `git` only adds them to the index then.
+//!
+//! The `rr-cache` directory and `MERGE_RR` file are compatible with `git`, so both can be used interchangeably.
+//!
+//! ### Deviation
+//!
+//! * The `conflict-marker-size` attribute isn't respected, all conflict markers are assumed to have the default size.
+pub use gix_rerere as plumbing;
+pub use gix_rerere::record::Outcome;
+
+/// The error returned by [`Repository::rerere()`](crate::Repository::rerere()) and when replaying resolutions during merges.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Recording conflict resolutions needs a worktree")]
+    MissingWorktree,
+    #[error(transparent)]
+    ConfigBoolean(#[from] crate::config::boolean::Error),
+    #[error(transparent)]
+    BlobMergeOptions(#[from] crate::repository::blob_merge_options::Error),
+    #[error(transparent)]
+    OpenIndex(#[from] crate::worktree::open_index::Error),
+    #[error(transparent)]
+    ReadMergeRr(#[from] gix_rerere::merge_rr::Error),
+    #[error(transparent)]
+    Record(#[from] gix_rerere::record::Error),
+    #[error(transparent)]
+    Normalize(#[from] gix_rerere::conflict::Error),
+    #[error("Could not access the rr-cache or MERGE_RR file")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    LockMergeRr(#[from] gix_lock::acquire::Error),
+    #[error(transparent)]
+    FilterPipeline(#[from] crate::repository::filter::pipeline::Error),
+    #[error(transparent)]
+    WorktreeFile(#[from] crate::filter::pipeline::worktree_file_to_object::Error),
+    #[error(transparent)]
+    WriteIndex(#[from] gix_index::file::write::Error),
+    #[error(transparent)]
+    FindBlob(#[from] crate::object::find::existing::with_conversion::Error),
+    #[error(transparent)]
+    WriteBlob(#[from] crate::object::write::Error),
+    #[error(transparent)]
+    EditTree(#[from] gix_object::tree::editor::Error),
+}
@@ -0,0 +1,80 @@
+//! Cherry-pick or revert commits, akin to `git cherry-pick` and `git revert`.
+//!
+//! Use [`Repository::cherry_pick()`](crate::Repository::cherry_pick()) or [`Repository::revert()`](crate::Repository::revert())
+//! to apply single commits or ranges of commits, one after another.
+//! If one of them can't be applied cleanly, the sequence stops with the conflicts written to the index and worktree, just like
+//! `git` would. Once they are resolved and the resolution was added to the index,
+//! [continue](crate::Repository::sequence_continue()) or [skip](crate::Repository::sequence_skip()) the commit, or
+//! [abort](crate::Repository::sequence_abort()) the whole sequence.
+//!
+//! As the state is stored in a format compatible with `git`, a sequence started here can be finished with
+//! `git cherry-pick --continue` as well.
+use gix_hash::ObjectId;
+
+use crate::{Id, bstr::BString};
+pub use gix_sequencer as plumbing;
+pub use gix_sequencer::state::{Action, Options};
+
+/// The error returned by [`Repository::cherry_pick()`](crate::Repository::cherry_pick()),
+/// [`Repository::revert()`](crate::Repository::revert()) and the methods that continue or stop a sequence.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Cherry-picks and reverts need a worktree")]
+    MissingWorktree,
+    #[error("Refusing to start a sequence as the index or worktree have uncommitted changes")]
+    Dirty,
+    #[error("There is no cherry-pick or revert in progress")]
+    NotInProgress,
+    #[error("Cannot create commits without a committer identity")]
+    CommitterMissing,
+    #[error("The symmetric difference '{spec}' can't be used to select commits to apply")]
+    UnsupportedSpec { spec: BString },
+    #[error("A mainline was specified but commit {id} is not a merge")]
+    MainlineWithoutMerge { id: ObjectId },
+    #[error(transparent)]
+    RevParse(#[from] gix_error::Error),
+    #[error(transparent)]
+    Topo(#[from] gix_traverse::commit::topo::Error),
+    #[error(transparent)]
+    Committer(#[from] crate::config::time::Error),
+    #[error(transparent)]
+    FindCommit(#[from] crate::object::find::existing::with_conversion::Error),
+    #[error(transparent)]
+    DecodeCommit(#[from] gix_object::decode::Error),
+    #[error(transparent)]
+    IsDirty(#[from] crate::status::is_dirty::Error),
+    #[error(transparent)]
+    TreeMergeOptions(#[from] crate::repository::tree_merge_options::Error),
+    #[error(transparent)]
+    DiffResourceCache(#[from] crate::repository::diff_resource_cache::Error),
+    #[error(transparent)]
+    MergeResourceCache(#[from] crate::repository::merge_resource_cache::Error),
+    #[error(transparent)]
+    ProtectOptions(#[from] crate::config::boolean::Error),
+    #[error(transparent)]
+    ReadState(#[from] gix_sequencer::state::read::Error),
+    #[error(transparent)]
+    Sequence(#[from] gix_sequencer::sequence::Error),
+}
+
+/// The outcome of [`Repository::cherry_pick()`](crate::Repository::cherry_pick()) and its sibling methods.
+pub enum Outcome<'repo> {
+    /// All commits were applied and the sequence is done.
+    Finished {
+        /// The commit `HEAD` points to now, which is unchanged if no commit was made.
+        head: Id<'repo>,
+    },
+    /// Applying a commit led to conflicts that were written to the index and worktree.
+    ///
+    /// Resolve them and [continue](crate::Repository::sequence_continue()) the sequence, or
+    /// [skip](crate::Repository::sequence_skip()) the commit instead.
+    Stopped {
+        /// The commit whose changes couldn't be applied without conflicts.
+        id: Id<'repo>,
+        /// What was done with the commit.
+        action: Action,
+        /// The outcome of the tree-merge, with the conflicts that stopped the sequence.
+        tree_merge: crate::merge::tree::Outcome<'repo>,
+    },
+}
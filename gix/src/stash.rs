@@ -0,0 +1,134 @@
+//! Save changes to the index and worktree and restore them later, akin to `git stash`.
+//!
+//! Use [`Repository::stash_push()`](crate::Repository::stash_push()) to record the changes in stash commits, which also
+//! resets the index and worktree to `HEAD`. The commits are made reachable through the reflog of
+//! [`refs/stash`](REFERENCE), which is where [`Repository::stash_list()`](crate::Repository::stash_list()) finds them.
+//!
+//! [`Repository::stash_apply()`](crate::Repository::stash_apply()) merges a stash entry back into the index and worktree,
+//! writing conflicts just like `git` would, and [`Repository::stash_pop()`](crate::Repository::stash_pop()) also
+//! [drops](crate::Repository::stash_drop()) the entry if there were none.
+//!
+//! The commits are laid out like the ones created by `git`, so stash entries can be exchanged freely between both.
+//!
+//! ### Deviation
+//!
+//! * Pathspecs to limit what's stashed aren't supported, neither is `--keep-index`, `--patch` or stashing ignored files.
+//! * Changes in submodules aren't stashed.
+use crate::{Id, bstr::BString};
+
+/// The name of the reference whose reflog holds all stash entries.
+pub const REFERENCE: &str = "refs/stash";
+
+/// The error returned by [`Repository::stash_push()`](crate::Repository::stash_push()) and its sibling methods.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Stashing needs a worktree")]
+    MissingWorktree,
+    #[error("Cannot stash changes before the initial commit")]
+    UnbornHead,
+    #[error("Cannot stash or apply changes while the index has unresolved conflicts")]
+    UnmergedIndex,
+    #[error("There is no stash entry at index {index}")]
+    NoSuchEntry { index: usize },
+    #[error("The stash entry {id} isn't a stash commit")]
+    NotAStash { id: gix_hash::ObjectId },
+    #[error("Local changes to {} would be overwritten by applying the stash", paths.iter().map(|p| format!("'{p}'")).collect::<Vec<_>>().join(", "))]
+    WouldOverwrite { paths: Vec<BString> },
+    #[error("Untracked files {} already exist and would be overwritten by applying the stash", paths.iter().map(|p| format!("'{p}'")).collect::<Vec<_>>().join(", "))]
+    UntrackedExists { paths: Vec<BString> },
+    #[error("The index of the stash entry can't be restored without conflicts")]
+    IndexConflicts,
+    #[error("Could not change the index or worktree")]
+    Checkout(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("Cannot create commits without a committer and author identity")]
+    IdentityMissing,
+    #[error(transparent)]
+    Identity(#[from] crate::config::time::Error),
+    #[error(transparent)]
+    HeadCommit(#[from] crate::reference::head_commit::Error),
+    #[error(transparent)]
+    FindReference(#[from] crate::reference::find::existing::Error),
+    #[error(transparent)]
+    FindCommit(#[from] crate::object::find::existing::with_conversion::Error),
+    #[error(transparent)]
+    FindObject(#[from] crate::object::find::existing::Error),
+    #[error(transparent)]
+    DecodeCommit(#[from] gix_object::decode::Error),
+    #[error(transparent)]
+    CommitTree(#[from] crate::object::commit::Error),
+    #[error(transparent)]
+    Status(#[from] crate::status::Error),
+    #[error(transparent)]
+    StatusIter(#[from] crate::status::into_iter::Error),
+    #[error(transparent)]
+    StatusItem(#[from] crate::status::index_worktree::Error),
+    #[error(transparent)]
+    FilterPipeline(#[from] crate::repository::filter::pipeline::Error),
+    #[error(transparent)]
+    WorktreeFile(#[from] crate::filter::pipeline::worktree_file_to_object::Error),
+    #[error(transparent)]
+    EditTree(#[from] crate::repository::edit_tree::Error),
+    #[error(transparent)]
+    WriteTree(#[from] crate::object::tree::editor::write::Error),
+    #[error(transparent)]
+    EditTreeEntry(#[from] gix_object::tree::editor::Error),
+    #[error(transparent)]
+    WriteObject(#[from] crate::object::write::Error),
+    #[error(transparent)]
+    EditReference(#[from] crate::reference::edit::Error),
+    #[error(transparent)]
+    OpenReflog(#[from] gix_ref::store::reflog::Error),
+    #[error(transparent)]
+    DecodeReflog(#[from] gix_ref::file::log::iter::decode::Error),
+    #[error(transparent)]
+    IndexFromTree(#[from] crate::repository::index_from_tree::Error),
+    #[error(transparent)]
+    TreeMergeOptions(#[from] crate::repository::tree_merge_options::Error),
+    #[error(transparent)]
+    MergeTrees(#[from] crate::repository::merge_trees::Error),
+    #[error(transparent)]
+    DiffTrees(#[from] crate::repository::diff_tree_to_tree::Error),
+}
+
+/// A way to configure [`Repository::stash_push()`](crate::Repository::stash_push()).
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// The message describing the stash entry, or `None` to describe it by the commit `HEAD` points to.
+    pub message: Option<BString>,
+    /// If `true`, untracked files are stashed as well and removed from the worktree, like `git stash --include-untracked`.
+    /// Ignored files are never stashed.
+    pub include_untracked: bool,
+}
+
+/// A way to configure [`Repository::stash_apply()`](crate::Repository::stash_apply()).
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ApplyOptions {
+    /// If `true`, restore the changes that were staged when stashing to the index as well, like `git stash apply --index`.
+    /// Otherwise, only newly added files are staged.
+    pub restore_index: bool,
+}
+
+/// A stash entry as listed by [`Repository::stash_list()`](crate::Repository::stash_list()).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry<'repo> {
+    /// The stash commit, which holds the state of the worktree, and has the commit `HEAD` pointed to as first parent,
+    /// the state of the index as second parent and the untracked files, if any, as third parent.
+    pub id: Id<'repo>,
+    /// The message of the entry as stored in the reflog, like `WIP on main: 1234567 subject`.
+    pub message: BString,
+}
+
+/// The outcome of [`Repository::stash_apply()`](crate::Repository::stash_apply()) and
+/// [`Repository::stash_pop()`](crate::Repository::stash_pop()).
+pub enum Outcome<'repo> {
+    /// The changes were applied without conflicts.
+    Applied,
+    /// The changes were applied, but led to conflicts that were written to the index and worktree.
+    ///
+    /// The stash entry is kept even when popping, to be dropped once the conflicts are resolved.
+    Conflicts {
+        /// The outcome of the tree-merge, with the conflicts that were written.
+        tree_merge: crate::merge::tree::Outcome<'repo>,
+    },
+}
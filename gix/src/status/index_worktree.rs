@@ -28,6 +28,8 @@ pub enum Error {
     StatOptions(#[from] config::stat_options::Error),
     #[error(transparent)]
     ResourceCache(#[from] crate::diff::resource_cache::Error),
+    #[error("Could not obtain the blobs of removed files to check for renames from the promisor remote")]
+    FetchMissing(#[source] gix_odb::store::find::Error),
 }
 
 /// Options for use with [Repository::index_worktree_status()].
@@ -83,6 +85,9 @@ impl Repository {
     /// * `options`
     ///     - Additional configuration for all parts of the operation.
     ///
+    /// In partial clones, if renames are tracked by similarity, the blobs of all removed files that are missing are obtained
+    /// from the promisor remote at once.
+    ///
     /// ### Note
     ///
     /// This is a lower-level method, prefer the [`status`](Repository::status()) method for greater ease of use.
@@ -132,6 +137,22 @@ impl Repository {
             },
         )?;
 
+        if self.fetches_missing_objects() && crate::diff::utils::rewrites_need_blob_content(options.rewrites.as_ref()) {
+            // Removed files are the candidates for the source of a rename, and their blobs are compared to untracked files.
+            let removed = index.entries().iter().filter(|entry| {
+                entry
+                    .mode
+                    .to_tree_entry_mode()
+                    .is_some_and(|mode| mode.is_blob_or_symlink())
+                    && workdir
+                        .join(gix_path::from_bstr(entry.path(index)))
+                        .symlink_metadata()
+                        .is_err_and(|err| err.kind() == std::io::ErrorKind::NotFound)
+            });
+            self.fetch_missing_objects(removed.map(|entry| entry.id))
+                .map_err(Error::FetchMissing)?;
+        }
+
         let out = gix_status::index_as_worktree_with_renames(
             index,
             workdir,
@@ -12,6 +12,8 @@ pub enum Error {
     DiffResourceCache(#[from] crate::repository::diff_resource_cache::Error),
     #[error(transparent)]
     TreeIndexDiff(#[from] gix_diff::index::Error),
+    #[error("Could not obtain the blobs to check for renames from the promisor remote")]
+    FetchMissing(#[source] gix_odb::store::find::Error),
 }
 
 /// Specify how to perform rewrite tracking [Repository::tree_index_status()].
@@ -48,6 +50,9 @@ impl Repository {
     /// *(It's notable that internally, the `tree_id` is converted into an index before diffing these)*.
     /// Set `pathspec` to `Some(_)` to further reduce the set of files to check.
     ///
+    /// In partial clones, if renames are tracked by similarity, all changed blobs that are missing are obtained from the
+    /// promisor remote at once.
+    ///
     /// ### Notes
     ///
     /// * This is a low-level method - prefer the [`Repository::status()`] platform instead for access to various iterators
@@ -109,6 +114,45 @@ impl Repository {
 
         let pathspec =
             pathspec.unwrap_or_else(|| pathspec_storage.as_mut().expect("set if pathspec isn't set by user"));
+        if self.fetches_missing_objects() && crate::diff::utils::rewrites_need_blob_content(rewrites.as_ref()) {
+            let is_blob =
+                |mode: gix_index::entry::Mode| mode.to_tree_entry_mode().is_some_and(|mode| mode.is_blob_or_symlink());
+            let mut ids = Vec::new();
+            gix_diff::index(
+                &tree_index,
+                worktree_index,
+                |change| {
+                    if let gix_diff::index::ChangeRef::Modification {
+                        previous_entry_mode,
+                        previous_id,
+                        ..
+                    } = &change
+                    {
+                        if is_blob(*previous_entry_mode) {
+                            ids.push(previous_id.clone().into_owned());
+                        }
+                    }
+                    if is_blob(change.entry_mode()) {
+                        ids.push(change.id().to_owned());
+                    }
+                    Ok::<_, std::convert::Infallible>(std::ops::ControlFlow::Continue(()))
+                },
+                None::<gix_diff::index::RewriteOptions<'_, Repository>>,
+                &mut pathspec.search,
+                &mut |relative_path, case, is_dir, out| {
+                    let stack = pathspec.stack.as_mut().expect("initialized in advance");
+                    stack
+                        .set_case(case)
+                        .at_entry(
+                            relative_path,
+                            Some(crate::pathspec::is_dir_to_mode(is_dir)),
+                            &pathspec.repo.objects,
+                        )
+                        .is_ok_and(|platform| platform.matching_attributes(out))
+                },
+            )?;
+            self.fetch_missing_objects(ids).map_err(Error::FetchMissing)?;
+        }
         let rewrite = gix_diff::index(
             &tree_index,
             worktree_index,
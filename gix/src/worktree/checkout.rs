@@ -0,0 +1,202 @@
+use std::{collections::BTreeSet, sync::atomic::AtomicBool};
+
+use gix_hash::{ObjectId, oid};
+use gix_index::entry::{Flags, Stage};
+
+use crate::{
+    Repository,
+    bstr::{BString, ByteSlice},
+};
+
+/// The error produced when changing the index or worktree.
+pub(crate) type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Keep the index and worktree of a repository in sync with the trees produced by the sequencer or when applying stashes.
+pub(crate) struct Checkout<'repo> {
+    repo: &'repo Repository,
+    workdir: std::path::PathBuf,
+    treat_as_unresolved: gix_merge::tree::TreatAsUnresolved,
+}
+
+impl<'repo> Checkout<'repo> {
+    pub(crate) fn new(
+        repo: &'repo Repository,
+        treat_as_unresolved: gix_merge::tree::TreatAsUnresolved,
+    ) -> Option<Self> {
+        Some(Checkout {
+            repo,
+            workdir: repo.workdir()?.to_owned(),
+            treat_as_unresolved,
+        })
+    }
+
+    /// Read the index from disk without caching, as it's changed by us and possibly by the user while resolving conflicts.
+    pub(crate) fn current_index(&self) -> Result<gix_index::File, Error> {
+        let path = self.repo.index_path();
+        Ok(if path.is_file() {
+            self.repo.open_index()?
+        } else {
+            gix_index::File::from_state(gix_index::State::new(self.repo.object_hash()), path)
+        })
+    }
+
+    /// Make index and worktree match `tree`, overwriting local changes.
+    #[cfg(feature = "sequencer")]
+    pub(crate) fn reset(&self, tree: &oid) -> Result<(), Error> {
+        self.reset_with_dirty(tree, &BTreeSet::new())
+    }
+
+    /// Like [`reset()`](Self::reset()), but also restore the files at the `dirty` paths even if their index entry
+    /// doesn't change, as their worktree version was modified.
+    pub(crate) fn reset_with_dirty(&self, tree: &oid, dirty: &BTreeSet<BString>) -> Result<(), Error> {
+        let mut index = self.repo.index_from_tree(tree)?;
+        self.update_worktree(&mut index, dirty)?;
+        index.write(Default::default())?;
+        Ok(())
+    }
+
+    /// Write `tree` to index and worktree, and add the entries of unresolved `conflicts` to the index.
+    /// Conflicts are recorded and resolved with known resolutions if rerere is enabled.
+    pub(crate) fn write_conflicts(&self, tree: &oid, conflicts: &[gix_merge::tree::Conflict]) -> Result<(), Error> {
+        let mut index = self.repo.index_from_tree(tree)?;
+        self.update_worktree(&mut index, &BTreeSet::new())?;
+        gix_merge::tree::apply_index_entries(
+            conflicts,
+            self.treat_as_unresolved,
+            &mut index,
+            gix_merge::tree::apply_index_entries::RemovalMode::Prune,
+        );
+        index.write(Default::default())?;
+        self.repo.rerere()?;
+        Ok(())
+    }
+
+    /// Write the current index as tree, or fail if it still has conflicts.
+    pub(crate) fn write_tree(&self) -> Result<ObjectId, Error> {
+        let index = self.current_index()?;
+        let mut editor = self.repo.edit_tree(ObjectId::empty_tree(self.repo.object_hash()))?;
+        for entry in index.entries() {
+            let path = entry.path(&index);
+            if entry.stage() != Stage::Unconflicted {
+                return Err(format!("'{}' still has unresolved conflicts", path.as_bstr()).into());
+            }
+            let Some(mode) = entry.mode.to_tree_entry_mode() else {
+                continue;
+            };
+            editor.upsert(path, mode.kind(), entry.id)?;
+        }
+        Ok(editor.write()?.detach())
+    }
+
+    /// Write all files of `tree` to the worktree without touching the index, and fail if one of them already exists.
+    #[cfg(feature = "stash")]
+    pub(crate) fn write_untracked(&self, tree: &oid) -> Result<(), Error> {
+        let mut index = self.repo.index_from_tree(tree)?;
+        self.checkout(&mut index, false)
+    }
+
+    /// Write all entries of `index` that differ from the current index or whose path is `dirty` to the worktree,
+    /// and remove those that are not present in `index` anymore.
+    pub(crate) fn update_worktree(&self, index: &mut gix_index::File, dirty: &BTreeSet<BString>) -> Result<(), Error> {
+        let previous = self.current_index()?;
+        for (entry, path) in index.entries_mut_with_paths() {
+            let unchanged = previous
+                .entry_by_path_and_stage(path, Stage::Unconflicted)
+                .filter(|prev| {
+                    prev.id == entry.id
+                        && prev.mode == entry.mode
+                        && !is_conflicted(&previous, path)
+                        && !dirty.contains(path)
+                });
+            if let Some(prev) = unchanged {
+                entry.stat = prev.stat;
+                entry.flags.insert(Flags::SKIP_WORKTREE);
+            }
+        }
+
+        for prev in previous.entries() {
+            let path = prev.path(&previous);
+            if index.entry_by_path(path).is_none() {
+                let path = self.workdir.join(gix_path::from_bstr(path));
+                match std::fs::remove_file(&path) {
+                    Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+                    _ => remove_empty_parents(&path, &self.workdir),
+                }
+            }
+        }
+
+        let res = self.checkout(index, true);
+        for entry in index.entries_mut() {
+            entry.flags.remove(Flags::SKIP_WORKTREE);
+        }
+        res
+    }
+
+    fn checkout(&self, index: &mut gix_index::File, overwrite_existing: bool) -> Result<(), Error> {
+        let mut options = self
+            .repo
+            .checkout_options(gix_worktree::stack::state::attributes::Source::IdMapping)?;
+        options.overwrite_existing = overwrite_existing;
+        // In partial clones, obtain all missing blobs at once instead of one at a time during checkout.
+        self.repo.fetch_missing_objects(
+            index
+                .entries()
+                .iter()
+                .filter(|entry| !entry.flags.contains(Flags::SKIP_WORKTREE) && !entry.mode.is_submodule())
+                .map(|entry| entry.id),
+        )?;
+        #[cfg(feature = "lfs-http-client-reqwest")]
+        self.repo.download_missing_lfs_objects(index)?;
+        let outcome = gix_worktree_state::checkout(
+            index,
+            &self.workdir,
+            self.repo.objects.clone().into_arc()?,
+            &gix_features::progress::Discard,
+            &gix_features::progress::Discard,
+            &AtomicBool::default(),
+            options,
+        )?;
+        if let Some(record) = outcome.errors.first() {
+            return Err(format!("Could not check out '{}': {}", record.path, record.error).into());
+        }
+        if let Some(collision) = outcome.collisions.first() {
+            return Err(format!(
+                "Could not check out '{}' as it collides with another path",
+                collision.path
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+fn is_conflicted(index: &gix_index::State, path: &crate::bstr::BStr) -> bool {
+    [Stage::Base, Stage::Ours, Stage::Theirs]
+        .into_iter()
+        .any(|stage| index.entry_by_path_and_stage(path, stage).is_some())
+}
+
+pub(crate) fn remove_empty_parents(path: &std::path::Path, workdir: &std::path::Path) {
+    for dir in path.ancestors().skip(1).take_while(|dir| *dir != workdir) {
+        if std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(feature = "sequencer")]
+impl gix_sequencer::Worktree for Checkout<'_> {
+    fn reset(&mut self, tree: &oid) -> Result<(), Error> {
+        Checkout::reset(self, tree)
+    }
+
+    fn write_conflicts(&mut self, tree: &oid, merge: &gix_merge::tree::Outcome<'_>) -> Result<(), Error> {
+        Checkout::write_conflicts(self, tree, &merge.conflicts)
+    }
+
+    fn write_tree(&mut self) -> Result<ObjectId, Error> {
+        // Record the resolutions of conflicts the user resolved before continuing.
+        self.repo.rerere()?;
+        Checkout::write_tree(self)
+    }
+}
@@ -109,6 +109,9 @@ pub(crate) fn id(git_dir: &std::path::Path, has_common_dir: bool) -> Option<&BSt
     (maybe_worktrees.file_name()?.to_str()? == "worktrees").then_some(candidate)
 }
 
+#[cfg(any(feature = "sequencer", feature = "stash"))]
+pub(crate) mod checkout;
+
 ///
 pub mod proxy;
 
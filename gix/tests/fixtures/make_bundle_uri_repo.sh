@@ -0,0 +1,33 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q origin
+(cd origin
+  echo 1 > file
+  git add file
+  git commit -q -m c1
+  echo 2 > file
+  git commit -q -am c2
+  git bundle create ../base.bundle main
+
+  echo 3 > file
+  git commit -q -am c3
+  git bundle create ../incremental.bundle main ^main~1
+
+  echo 4 > file
+  git commit -q -am c4
+)
+
+# The incremental bundle comes first to assure the creation tokens determine the order in which bundles are applied.
+cat <<EOT >bundle-list
+[bundle]
+  version = 1
+  mode = all
+  heuristic = creationToken
+[bundle "incremental"]
+  uri = incremental.bundle
+  creationToken = 2
+[bundle "base"]
+  uri = ./base.bundle
+  creationToken = 1
+EOT
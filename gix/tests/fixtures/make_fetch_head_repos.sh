@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q remote
+(cd remote
+  git checkout -q -b main
+  git commit -q --allow-empty -m first
+  git tag -a -m "annotated" v1
+  git branch feature
+)
+
+for clone in clone-git clone-gix; do
+  git clone -q remote $clone
+done
+
+(cd remote
+  git commit -q --allow-empty -m second
+  git tag -a -m "annotated" v2
+  git checkout -q feature
+  git commit -q --allow-empty -m feature
+  git tag lightweight HEAD~1
+  git checkout -q main
+)
@@ -0,0 +1,50 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+# Record everything a hook receives in `$GIT_DIR/<hook>.out`, and exit with `$HOOK_EXIT`.
+function install_recording_hook() {
+  local hook=${1:?first argument is the path to the hook}
+  cat >"$hook" <<'HOOK'
+#!/bin/sh
+{
+  echo "cwd: $(pwd)"
+  echo "index: ${GIT_INDEX_FILE:-}"
+  for arg in "$@"; do echo "arg: $arg"; done
+  sed 's/^/stdin: /'
+} >"$GIT_DIR/$(basename "$0").out"
+exit "${HOOK_EXIT:-0}"
+HOOK
+  chmod +x "$hook"
+}
+
+git init -q worktree
+(cd worktree
+  rm -f .git/hooks/*
+  git commit -q --allow-empty -m init
+
+  for hook in pre-commit post-checkout; do
+    install_recording_hook .git/hooks/$hook
+  done
+
+  cat >.git/hooks/prepare-commit-msg <<'HOOK'
+#!/bin/sh
+echo "prepared with $2" >>"$1"
+HOOK
+  cat >.git/hooks/commit-msg <<'HOOK'
+#!/bin/sh
+grep -q '^Signed-off-by: ' "$1"
+HOOK
+  chmod +x .git/hooks/prepare-commit-msg .git/hooks/commit-msg
+
+  mkdir custom-hooks
+  install_recording_hook custom-hooks/pre-commit
+  mv custom-hooks/pre-commit custom-hooks/pre-push
+)
+
+git init -q --bare bare.git
+(cd bare.git
+  rm -f hooks/*
+  for hook in pre-push reference-transaction; do
+    install_recording_hook hooks/$hook
+  done
+)
@@ -0,0 +1,12 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git config filter.lfs.clean "git-lfs clean -- %f"
+git config filter.lfs.smudge "git-lfs smudge -- %f"
+git config filter.lfs.process "git-lfs filter-process"
+git config filter.lfs.required true
+
+echo "*.bin filter=lfs diff=lfs merge=lfs -text" > .gitattributes
+git add .gitattributes
+git commit -q -m "track binaries with LFS"
@@ -0,0 +1,11 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+echo "*.bin filter=lfs diff=lfs merge=lfs -text" > .gitattributes
+git add .gitattributes
+
+# The pointer to the content 'hello world', without the object being in the LFS store.
+pointer=$(printf 'version https://git-lfs.github.com/spec/v1\noid sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9\nsize 11\n' | git hash-object -w --stdin --no-filters)
+git update-index --add --cacheinfo "100644,$pointer,file.bin"
+git commit -q -m "add a file tracked with LFS"
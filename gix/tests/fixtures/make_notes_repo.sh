@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git config user.name "A U Thor"
+git config user.email author@example.com
+git commit -q --allow-empty -m first
+git commit -q --allow-empty -m second
+
+git notes add -m "note for first" HEAD~1
+
+git notes --ref edited add -m one HEAD~1
+git notes --ref edited append -m two HEAD~1
+git notes --ref edited copy HEAD~1 HEAD
+git notes --ref edited remove HEAD~1
+
+git notes --ref ours add -m ours HEAD
+git update-ref refs/notes/theirs refs/notes/ours
+git notes --ref ours append -m "ours again" HEAD
+git notes --ref theirs add -f -m theirs HEAD
+git notes --ref theirs add -m "theirs first" HEAD~1
+git update-ref refs/notes/merged refs/notes/ours
+git notes --ref merged merge -q -s union refs/notes/theirs
@@ -0,0 +1,19 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q remote
+(cd remote
+  git config uploadpack.allowFilter true
+
+  seq 1 20 >renamed
+  seq 21 40 >modified
+  seq 41 60 >removed
+  git add . && git commit -qm "first"
+
+  git mv renamed renamed-and-changed
+  echo 21 >>renamed-and-changed
+  echo 41 >>modified
+  git rm -q removed
+  seq 61 80 >added
+  git add . && git commit -qm "second"
+)
@@ -0,0 +1,53 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q --bare remote.git
+(cd remote.git
+  git config receive.advertisePushOptions true
+  cat <<'HOOK' > hooks/pre-receive
+#!/bin/sh
+if test "${GIT_PUSH_OPTION_0:-}" = reject; then
+  echo "rejected by push option" >&2
+  exit 1
+fi
+HOOK
+  chmod +x hooks/pre-receive
+)
+
+git init -q clone
+(cd clone
+  git checkout -q -b main
+  git commit -q --allow-empty -m first
+  git tag -a -m "annotated" v1
+  git branch feature
+  git branch old
+  git remote add origin ../remote.git
+  git push -q -u origin main feature old v1
+)
+
+git clone -q remote.git other
+(cd other
+  git checkout -q feature
+  echo remote >file && git add file
+  git commit -q -m "remote feature"
+  git push -q origin feature
+)
+
+(cd clone
+  git fetch -q origin
+  mkdir dir && echo main >dir/file && git add dir
+  git commit -q -m second
+  git tag -a -m "annotated" v2
+  git checkout -q feature
+  git commit -q --allow-empty -m "local feature"
+  git checkout -q main
+)
+
+(cd clone
+  git checkout -q -b skewed
+  GIT_COMMITTER_DATE="2010-01-01 00:00:00 +0000" git commit -q --allow-empty -m "from the future"
+  git push -q origin skewed
+  GIT_COMMITTER_DATE="2001-01-01 00:00:00 +0000" git commit -q --allow-empty -m "back in time"
+  GIT_COMMITTER_DATE="2002-01-01 00:00:00 +0000" git commit -q --allow-empty -m "still in the past"
+  git checkout -q main
+)
@@ -0,0 +1,30 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git checkout -q -b main
+git config user.name committer
+git config user.email committer@example.com
+git config rerere.enabled true
+
+seq 1 10 >file
+git add file && git commit -q -m base
+git branch base
+
+git checkout -q -b theirs base
+sed -e 's/^3$/three-theirs/' -e 's/^8$/eight-theirs/' file >file.tmp && mv file.tmp file
+git commit -q -am theirs
+
+git checkout -q -b unknown base
+sed -e 's/^5$/five-unknown/' file >file.tmp && mv file.tmp file
+git commit -q -am unknown
+
+git checkout -q main
+sed -e 's/^3$/three-ours/' -e 's/^5$/five-ours/' -e 's/^8$/eight-ours/' file >file.tmp && mv file.tmp file
+git commit -q -am ours
+
+# Let `git` record the resolution of the conflicts with `theirs`, and undo the merge.
+git merge theirs >/dev/null 2>&1 || :
+sed -e 's/^3$/three-resolved/' -e 's/^8$/eight-resolved/' -e 's/^5$/five-ours/' <(seq 1 10) >file
+git rerere 2>/dev/null
+git reset -q --hard main
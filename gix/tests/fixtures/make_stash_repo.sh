@@ -0,0 +1,26 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git checkout -q -b main
+git config user.name committer
+git config user.email committer@example.com
+
+function commit() {
+  local file=${1:?first argument is the file}
+  local content=${2:?second argument is the content}
+  echo "$content" > "$file"
+  git add "$file"
+  git commit -q -m "$file: $content"
+}
+
+commit a 1
+commit b 1
+
+# A stash entry written by git, with a staged and an unstaged change as well as an untracked file.
+echo 2 > a
+echo 2 > b
+git add b
+echo 3 > b
+echo untracked > u
+git stash push -q --include-untracked -m "from git"
@@ -45,6 +45,7 @@ mod with_overrides {
             .set("GIT_REPLACE_REF_BASE", "refs/replace-mine")
             .set("GIT_NO_REPLACE_OBJECTS", "no-replace")
             .set("GIT_ALLOC_LIMIT", "7m")
+            .set("GIT_NO_LAZY_FETCH", "no-lazy-fetch")
             .set("GIT_COMMITTER_NAME", "committer name")
             .set("GIT_COMMITTER_EMAIL", "committer email")
             .set("GIT_COMMITTER_DATE", default_date)
@@ -54,6 +55,7 @@ mod with_overrides {
             .set("EMAIL", "user email")
             .set("GIX_PACK_CACHE_MEMORY", "0")
             .set("GIX_PARSE_PRECIOUS", "1")
+            .set("GIX_IN_PROCESS_FILE_TRANSPORT", "1")
             .set("GIX_OBJECT_CACHE_MEMORY", "5m")
             .set("GIX_CREDENTIALS_HELPER_STDERR", "creds-stderr")
             .set("GIX_EXTERNAL_COMMAND_STDERR", "filter-stderr")
@@ -69,6 +71,7 @@ mod with_overrides {
             .set("GIT_TERMINAL_PROMPT", "42")
             .set("GIT_SHALLOW_FILE", "shallow-file-env")
             .set("GIT_NAMESPACE", "namespace-env")
+            .set("GIT_NOTES_REF", "refs/notes/env")
             .set("GIT_EXTERNAL_DIFF", "external-diff-env");
         let mut opts = gix::open::Options::isolated()
             .cli_overrides([
@@ -236,6 +239,7 @@ mod with_overrides {
             ("gitoxide.http.verbose", "true"),
             ("gitoxide.allow.protocolFromUser", "file-allowed"),
             ("core.useReplaceRefs", "no-replace"),
+            ("core.notesRef", "refs/notes/env"),
             #[cfg(feature = "blob-diff")]
             ("diff.external", "external-diff-env"),
             ("gitoxide.objects.replaceRefBase", "refs/replace-mine"),
@@ -247,9 +251,11 @@ mod with_overrides {
             ("gitoxide.commit.committerDate", default_date),
             ("gitoxide.user.emailFallback", "user email"),
             ("gitoxide.parsePrecious", "1"),
+            ("gitoxide.inProcessFileTransport", "1"),
             ("core.deltaBaseCacheLimit", "0"),
             ("gitoxide.objects.cacheLimit", "5m"),
             ("gitoxide.objects.allocLimit", "7m"),
+            ("gitoxide.objects.noLazyFetch", "no-lazy-fetch"),
             ("gitoxide.pathspec.icase", "pathspecs-icase"),
             ("gitoxide.pathspec.glob", "pathspecs-glob"),
             ("gitoxide.pathspec.noglob", "pathspecs-noglob"),
@@ -426,7 +426,7 @@ mod blocking_io {
             .cached_packed_buffer()?
             .expect("packed refs should be present");
         assert_eq!(
-            repo.refs.loose_iter()?.count(),
+            repo.refs.as_file_store().expect("loose refs").loose_iter()?.count(),
             1,
             "HEAD is the only remaining loose symbolic ref as born remote symrefs are stored peeled"
         );
@@ -577,7 +577,7 @@ mod blocking_io {
         Ok(())
     }
 
-    fn assert_reflog(log: std::io::Result<Option<gix_ref::file::log::iter::Forward<'_>>>) {
+    fn assert_reflog(log: Result<Option<gix_ref::file::log::iter::Forward<'_>>, gix_ref::store::reflog::Error>) {
         let lines = log
             .unwrap()
             .expect("log present")
@@ -614,6 +614,186 @@ mod blocking_io {
         Ok(())
     }
 
+    #[test]
+    #[cfg(feature = "lfs")]
+    fn fetch_and_checkout_smudges_missing_lfs_objects_with_the_configured_driver() -> crate::Result {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let mut prepare = gix::clone::PrepareFetch::new(
+            gix_testtools::scripted_fixture_read_only("make_lfs_repo_with_missing_object.sh")?,
+            tmp.path(),
+            gix::create::Kind::WithWorktree,
+            Default::default(),
+            restricted(),
+        )?
+        .with_in_memory_config_overrides([
+            // A stand-in for `git-lfs smudge`, which would download the object the pointer points to.
+            "filter.lfs.smudge=cat >/dev/null && printf 'hello world'",
+            "filter.lfs.required=true",
+        ]);
+        let (mut checkout, _out) = prepare.fetch_then_checkout(gix::progress::Discard, &AtomicBool::default())?;
+        let (repo, _) = checkout.main_worktree(gix::progress::Discard, &AtomicBool::default())?;
+
+        let workdir = repo.workdir().expect("non-bare");
+        assert_eq!(
+            std::fs::read(workdir.join("file.bin"))?.as_bstr(),
+            "hello world",
+            "the object isn't in the LFS store, so the driver is used instead of checking out the pointer"
+        );
+        assert!(
+            !repo.common_dir().join("lfs").exists(),
+            "nothing was stored as the object was obtained by the driver"
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "lfs-http-client-reqwest")]
+    fn fetch_and_checkout_downloads_missing_lfs_objects_in_batches() -> crate::Result {
+        let server = lfs_server::Server::start([(
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9".into(),
+            b"hello world".to_vec(),
+        )])?;
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let mut prepare = gix::clone::PrepareFetch::new(
+            gix_testtools::scripted_fixture_read_only("make_lfs_repo_with_missing_object.sh")?,
+            tmp.path(),
+            gix::create::Kind::WithWorktree,
+            Default::default(),
+            restricted(),
+        )?
+        .with_in_memory_config_overrides([
+            // The driver fails, just like it would if `git-lfs` isn't installed.
+            "filter.lfs.smudge=false".to_owned(),
+            "filter.lfs.required=true".into(),
+            format!("lfs.url={}", server.url),
+            format!("http.extraHeader=Authorization: {}", lfs_server::TOKEN),
+        ]);
+        let (mut checkout, _out) = prepare.fetch_then_checkout(gix::progress::Discard, &AtomicBool::default())?;
+        let (repo, outcome) = checkout.main_worktree(gix::progress::Discard, &AtomicBool::default())?;
+
+        assert!(outcome.errors.is_empty(), "the driver wasn't used");
+        let workdir = repo.workdir().expect("non-bare");
+        assert_eq!(std::fs::read(workdir.join("file.bin"))?.as_bstr(), "hello world");
+        assert_eq!(
+            server.batches.load(std::sync::atomic::Ordering::SeqCst),
+            1,
+            "all missing objects are requested at once"
+        );
+        let index = repo.index()?;
+        let entry = index.entry_by_path("file.bin".into()).expect("present");
+        let pointer = gix::filter::plumbing::lfs::Pointer::from_bytes(&repo.find_blob(entry.id)?.data)?;
+        assert!(
+            gix::filter::plumbing::lfs::Store::at(repo.common_dir().join("lfs")).contains(&pointer),
+            "the downloaded object is stored for later use"
+        );
+        Ok(())
+    }
+
+    /// A minimal LFS server that implements downloads with the batch API and the `basic` transfer adapter over HTTP/1.1.
+    #[cfg(feature = "lfs-http-client-reqwest")]
+    mod lfs_server {
+        use std::{
+            collections::BTreeMap,
+            io::{BufRead, BufReader, Read, Write},
+            net::{TcpListener, TcpStream},
+            sync::{
+                Arc,
+                atomic::{AtomicUsize, Ordering},
+            },
+        };
+
+        /// The token clients have to authorize batch requests with.
+        pub const TOKEN: &str = "Bearer secret";
+
+        pub struct Server {
+            /// The URL of the LFS API.
+            pub url: String,
+            /// The amount of batch requests received.
+            pub batches: Arc<AtomicUsize>,
+        }
+
+        impl Server {
+            /// Serve `objects` by their hex id in the background until the end of the process.
+            pub fn start(objects: impl IntoIterator<Item = (String, Vec<u8>)>) -> std::io::Result<Self> {
+                let listener = TcpListener::bind("127.0.0.1:0")?;
+                let base = format!("http://{}", listener.local_addr()?);
+                let objects = Arc::new(objects.into_iter().collect::<BTreeMap<_, _>>());
+                let batches = Arc::new(AtomicUsize::default());
+                std::thread::spawn({
+                    let (base, objects, batches) = (base.clone(), objects.clone(), batches.clone());
+                    move || {
+                        for stream in listener.incoming().flatten() {
+                            handle(stream, &base, &objects, &batches);
+                        }
+                    }
+                });
+                Ok(Server {
+                    url: format!("{base}/info/lfs"),
+                    batches,
+                })
+            }
+        }
+
+        fn handle(mut stream: TcpStream, base: &str, objects: &BTreeMap<String, Vec<u8>>, batches: &AtomicUsize) {
+            let mut reader = BufReader::new(stream.try_clone().expect("can clone sockets"));
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).expect("valid request");
+            let mut headers = BTreeMap::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("valid header");
+                match line.trim_end().split_once(':') {
+                    Some((name, value)) => headers.insert(name.to_ascii_lowercase(), value.trim().to_owned()),
+                    None => break,
+                };
+            }
+            let mut body = vec![
+                0;
+                headers
+                    .get("content-length")
+                    .map_or(0, |len| len.parse().expect("number"))
+            ];
+            reader.read_exact(&mut body).expect("complete body");
+
+            let path = request_line.split_whitespace().nth(1).expect("path");
+            let (status, content) = match path.strip_prefix("/objects/") {
+                Some(oid) => objects.get(oid).map_or((404, Vec::new()), |data| (200, data.clone())),
+                None if path == "/info/lfs/objects/batch"
+                    && headers.get("authorization").map(String::as_str) == Some(TOKEN) =>
+                {
+                    batches.fetch_add(1, Ordering::SeqCst);
+                    let objects = String::from_utf8(body)
+                        .expect("JSON is UTF-8")
+                        .split(r#""oid":""#)
+                        .skip(1)
+                        .map(|rest| {
+                            let (oid, rest) = rest.split_once('"').expect("quoted oid");
+                            let size = rest
+                                .split(r#""size":"#)
+                                .nth(1)
+                                .and_then(|size| size.split(|c: char| !c.is_ascii_digit()).next())
+                                .expect("size");
+                            format!(r#"{{"oid":"{oid}","size":{size},"actions":{{"download":{{"href":"{base}/objects/{oid}"}}}}}}"#)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    (
+                        200,
+                        format!(r#"{{"transfer":"basic","objects":[{objects}]}}"#).into_bytes(),
+                    )
+                }
+                None => (401, Vec::new()),
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {status} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                content.len()
+            )
+            .and_then(|()| stream.write_all(&content))
+            .expect("can write response");
+        }
+    }
+
     #[test]
     #[cfg(unix)]
     fn fetch_and_checkout_does_not_follow_delayed_symlink_prefixes() -> crate::Result {
@@ -1020,6 +1200,640 @@ mod blocking_io {
         Ok(())
     }
 
+    #[test]
+    #[cfg(feature = "in-process-file-transport")]
+    fn fetch_and_checkout_with_in_process_file_transport() -> crate::Result {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let remote_repo = remote::repo("base");
+        let mut prepare = gix::clone::PrepareFetch::new(
+            remote_repo.path(),
+            tmp.path(),
+            gix::create::Kind::WithWorktree,
+            Default::default(),
+            restricted().config_overrides(["gitoxide.inProcessFileTransport=true"]),
+        )?;
+        let (mut checkout, out) = prepare.fetch_then_checkout(gix::progress::Discard, &AtomicBool::default())?;
+        assert_eq!(
+            out.handshake.server_protocol_version,
+            gix::protocol::transport::Protocol::V2,
+            "the in-process server always speaks V2"
+        );
+        let (repo, _) = checkout.main_worktree(gix::progress::Discard, &AtomicBool::default())?;
+
+        assert_eq!(repo.head_id()?, remote_repo.head_id()?);
+        for reference in remote_repo.references()?.local_branches()? {
+            let reference = reference?;
+            let tracking = format!("refs/remotes/origin/{}", reference.name().shorten());
+            assert_eq!(
+                repo.find_reference(tracking.as_str())?.id(),
+                reference.id(),
+                "all branches are received"
+            );
+        }
+        assert_eq!(
+            repo.references()?.tags()?.count(),
+            remote_repo.references()?.tags()?.count(),
+            "tags are received as well"
+        );
+        assure_index_entries_on_disk(&repo.index()?, repo.workdir().expect("non-bare"));
+
+        let mut remote = repo.find_remote("origin")?;
+        remote.replace_refspecs(Some("+refs/heads/*:refs/remotes/origin/*"), Direction::Fetch)?;
+        let out = remote
+            .connect(Direction::Fetch)?
+            .prepare_fetch(gix::progress::Discard, Default::default())?
+            .receive(gix::progress::Discard, &AtomicBool::default())?;
+        assert!(
+            matches!(out.status, gix::remote::fetch::Status::NoPackReceived { .. }),
+            "everything is up to date"
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "in-process-file-transport")]
+    fn in_process_file_transport_rejects_what_it_does_not_support() -> crate::Result {
+        fn messages(err: &dyn std::error::Error) -> String {
+            std::iter::successors(Some(err), |err| err.source())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(": ")
+        }
+        let in_process = || restricted().config_overrides(["gitoxide.inProcessFileTransport=true"]);
+
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let err = gix::clone::PrepareFetch::new(
+            remote::repo("base").path(),
+            tmp.path().join("v1"),
+            gix::create::Kind::Bare,
+            Default::default(),
+            in_process(),
+        )?
+        .with_in_memory_config_overrides(Some("protocol.version=1"))
+        .fetch_only(gix::progress::Discard, &AtomicBool::default())
+        .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                gix::clone::fetch::Error::PrepareFetch(gix::remote::fetch::prepare::Error::RefMap(
+                    gix::remote::ref_map::Error::Handshake(gix::protocol::handshake::Error::Transport(
+                        gix::protocol::transport::client::Error::UnsupportedProtocolVersion(_)
+                    ))
+                ))
+            ),
+            "{err:?}"
+        );
+
+        let err = gix::prepare_clone_bare(remote::repo("base.shallow").path(), tmp.path().join("from-shallow"))?
+            .with_in_memory_config_overrides(Some("gitoxide.inProcessFileTransport=true"))
+            .fetch_only(gix::progress::Discard, &AtomicBool::default())
+            .unwrap_err();
+        assert!(
+            messages(&err).contains("Cannot serve the shallow repository"),
+            "{err:?}"
+        );
+
+        let (repo, _out) = gix::clone::PrepareFetch::new(
+            remote::repo("base").path(),
+            tmp.path().join("shallow"),
+            gix::create::Kind::Bare,
+            Default::default(),
+            in_process(),
+        )?
+        .with_shallow(Shallow::DepthAtRemote(1.try_into()?))
+        .fetch_only(gix::progress::Discard, &AtomicBool::default())?;
+        assert!(repo.is_shallow(), "'deepen' is supported");
+        let err = repo
+            .find_remote("origin")?
+            .connect(Direction::Fetch)?
+            .prepare_fetch(gix::progress::Discard, Default::default())?
+            .with_shallow(Shallow::Deepen(1))
+            .receive(gix::progress::Discard, &AtomicBool::default())
+            .unwrap_err();
+        assert!(
+            messages(&err).contains("The 'deepen-relative' argument isn't supported"),
+            "{err:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_and_checkout_from_bundle() -> crate::Result {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let bundle_path = tmp.path().join("base.bundle");
+        gix_testtools::git(
+            remote::repo("base").path(),
+            &format!("bundle create {} --all", bundle_path.display()),
+        )?;
+
+        let mut prepare = gix::clone::PrepareFetch::new(
+            bundle_path.as_path(),
+            tmp.path().join("clone"),
+            gix::create::Kind::WithWorktree,
+            Default::default(),
+            restricted(),
+        )?;
+        let (mut checkout, out) = prepare.fetch_then_checkout(gix::progress::Discard, &AtomicBool::default())?;
+        let (repo, _) = checkout.main_worktree(gix::progress::Discard, &AtomicBool::default())?;
+
+        let gix::remote::fetch::Status::Change {
+            write_pack_bundle,
+            update_refs,
+            ..
+        } = out.status
+        else {
+            unreachable!("a clone always carries a change");
+        };
+        assert!(write_pack_bundle.keep_path.is_none(), "keep files are removed");
+        assert_eq!(
+            update_refs.edits.len(),
+            out.ref_map.mappings.len(),
+            "all refs of the bundle were written"
+        );
+
+        let head = repo.head()?;
+        assert_eq!(
+            head.referent_name().expect("symbolic").as_bstr(),
+            "refs/heads/main",
+            "HEAD is assumed to point to the default branch as it has the same id"
+        );
+        assert_eq!(
+            repo.find_reference("refs/remotes/origin/b")?.id(),
+            remote::repo("base").find_reference("refs/heads/b")?.id(),
+        );
+        assert!(repo.try_find_reference("refs/tags/annotated-detached-tag")?.is_some());
+        assert_eq!(
+            repo.find_remote("origin")?.url(Direction::Fetch).expect("present").path,
+            gix_path::into_bstr(bundle_path.as_path()).as_ref(),
+            "the bundle is configured as the remote"
+        );
+
+        let index = repo.index()?;
+        assert_eq!(index.entries().len(), 1, "All entries are known as per HEAD tree");
+        assure_index_entries_on_disk(&index, repo.workdir().expect("non-bare"));
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_only_from_bundle_requires_prerequisites() -> crate::Result {
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let bundle_path = tmp.path().join("incremental.bundle");
+        gix_testtools::git(
+            remote::repo("base").path(),
+            &format!("bundle create {} main ^main~1", bundle_path.display()),
+        )?;
+
+        let err = gix::clone::PrepareFetch::new(
+            bundle_path.as_path(),
+            tmp.path().join("clone"),
+            gix::create::Kind::Bare,
+            Default::default(),
+            restricted(),
+        )?
+        .fetch_only(gix::progress::Discard, &AtomicBool::default())
+        .expect_err("the new repository lacks the prerequisites");
+        assert!(
+            matches!(err, gix::clone::fetch::Error::BundlePrerequisites(_)),
+            "{err:?}"
+        );
+        Ok(())
+    }
+
+    mod bundle_uri {
+        use std::{
+            io::Read,
+            path::{Path, PathBuf},
+            sync::atomic::AtomicBool,
+            thread::JoinHandle,
+        };
+
+        use gix::remote::fetch::Status;
+        use gix_protocol::{
+            bundle_uri::{Bundle, List},
+            handshake::Ref,
+            upload_pack,
+        };
+
+        use crate::util::restricted;
+
+        type ServerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+        /// Serve a single fetch from `origin` like `git daemon` would, advertising `bundle_list` if set.
+        fn serve_once(origin: &Path, bundle_list: Option<List>) -> crate::Result<(String, JoinHandle<ServerResult>)> {
+            let repo = gix::open_opts(origin, gix::open::Options::isolated())?;
+            let main = repo.find_reference("refs/heads/main")?.id().detach();
+            let refs = vec![
+                Ref::Symbolic {
+                    full_ref_name: "HEAD".into(),
+                    target: "refs/heads/main".into(),
+                    tag: None,
+                    object: main,
+                },
+                Ref::Direct {
+                    full_ref_name: "refs/heads/main".into(),
+                    object: main,
+                },
+            ];
+            let mut objects = gix::odb::at_opts(
+                repo.objects.store_ref().path(),
+                Vec::new(),
+                gix::odb::store::init::Options {
+                    object_hash: repo.object_hash(),
+                    ..Default::default()
+                },
+            )?
+            .into_arc()?;
+            objects.prevent_pack_unload();
+
+            let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+            let url = format!("git://127.0.0.1:{}/repo", listener.local_addr()?.port());
+            let server = std::thread::spawn(move || -> ServerResult {
+                let (mut stream, _) = listener.accept()?;
+                let mut len = [0; 4];
+                stream.read_exact(&mut len)?;
+                let len = usize::from_str_radix(std::str::from_utf8(&len)?, 16)?;
+                stream.read_exact(&mut vec![0; len - 4])?;
+
+                let object_hash = objects.store_ref().object_hash();
+                gix_protocol::upload_pack(
+                    stream.try_clone()?,
+                    stream,
+                    upload_pack::Context {
+                        refs: &refs,
+                        objects,
+                        object_hash,
+                    },
+                    gix::progress::Discard,
+                    &AtomicBool::default(),
+                    upload_pack::Options {
+                        bundle_list,
+                        ..Default::default()
+                    },
+                )?;
+                Ok(())
+            });
+            Ok((url, server))
+        }
+
+        /// Serve `content` to a single HTTP `GET` request.
+        #[cfg(any(
+            feature = "blocking-http-transport-curl",
+            feature = "blocking-http-transport-reqwest"
+        ))]
+        fn serve_http_once(content: Vec<u8>) -> crate::Result<(String, JoinHandle<ServerResult>)> {
+            use std::io::Write;
+
+            let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+            let url = format!("http://127.0.0.1:{}", listener.local_addr()?.port());
+            let server = std::thread::spawn(move || -> ServerResult {
+                let (mut stream, _) = listener.accept()?;
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let num_read = stream.read(&mut buf)?;
+                    if num_read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..num_read]);
+                }
+                assert!(request.starts_with(b"GET /base.bundle "), "the bundle is requested");
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    content.len()
+                )?;
+                stream.write_all(&content)?;
+                Ok(())
+            });
+            Ok((url, server))
+        }
+
+        fn fixture() -> crate::Result<PathBuf> {
+            // Absolute paths are needed for `file://` URLs.
+            Ok(std::fs::canonicalize(gix_testtools::scripted_fixture_read_only(
+                "make_bundle_uri_repo.sh",
+            )?)?)
+        }
+
+        fn list_with(uri: String) -> List {
+            List {
+                bundles: vec![Bundle {
+                    id: "only".into(),
+                    uri: uri.into(),
+                    creation_token: None,
+                }],
+                ..Default::default()
+            }
+        }
+
+        fn clone(url: &str, dir: &Path, overrides: &[&str]) -> crate::Result<(gix::Repository, u32)> {
+            let (repo, out) =
+                gix::clone::PrepareFetch::new(url, dir, gix::create::Kind::Bare, Default::default(), restricted())?
+                    .with_in_memory_config_overrides(overrides.iter().copied())
+                    .fetch_only(gix::progress::Discard, &AtomicBool::default())?;
+            let Status::Change { write_pack_bundle, .. } = out.status else {
+                unreachable!("the bundles never contain everything");
+            };
+            Ok((repo, write_pack_bundle.index.num_objects))
+        }
+
+        fn rev_parse(repo_dir: &Path, spec: &str) -> crate::Result<gix::ObjectId> {
+            Ok(gix::open_opts(repo_dir, gix::open::Options::isolated())?
+                .rev_parse_single(spec)?
+                .detach())
+        }
+
+        #[test]
+        fn list_of_bundles_is_applied_before_fetching_the_rest() -> crate::Result {
+            let fixture = fixture()?;
+            let origin = fixture.join("origin");
+            let list = list_with(format!("file://{}", fixture.join("bundle-list").display()));
+            let (url, server) = serve_once(&origin, Some(list))?;
+
+            let tmp = gix_testtools::tempfile::TempDir::new()?;
+            let (repo, num_fetched_objects) = clone(&url, tmp.path(), &["transfer.bundleURI=true"])?;
+            server.join().expect("no panic")?;
+
+            assert_eq!(
+                repo.find_reference("refs/bundles/main")?.id(),
+                rev_parse(&origin, "main~1")?,
+                "the bundles referred to by the advertised bundle list were applied in order of their creation token"
+            );
+            assert_eq!(
+                repo.find_reference("refs/remotes/origin/main")?.id(),
+                rev_parse(&origin, "main")?
+            );
+            assert_eq!(
+                num_fetched_objects, 3,
+                "only the commit, tree and blob missing from the bundles are fetched"
+            );
+            assert!(
+                std::fs::read_dir(repo.path().join("objects/pack"))?
+                    .all(|entry| entry.is_ok_and(|e| e.path().extension() != Some("keep".as_ref()))),
+                "keep files of unbundled packs are removed"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn bundles_are_ignored_unless_enabled() -> crate::Result {
+            let fixture = fixture()?;
+            let origin = fixture.join("origin");
+            let list = list_with(format!("file://{}", fixture.join("bundle-list").display()));
+            let (url, server) = serve_once(&origin, Some(list))?;
+
+            let tmp = gix_testtools::tempfile::TempDir::new()?;
+            let (repo, num_fetched_objects) = clone(&url, tmp.path(), &[])?;
+            server.join().expect("no panic")?;
+
+            assert!(repo.try_find_reference("refs/bundles/main")?.is_none());
+            assert_eq!(num_fetched_objects, 12, "everything is fetched from the remote");
+            Ok(())
+        }
+
+        #[test]
+        #[cfg(any(
+            feature = "blocking-http-transport-curl",
+            feature = "blocking-http-transport-reqwest"
+        ))]
+        fn bundle_is_downloaded_via_http() -> crate::Result {
+            let fixture = fixture()?;
+            let origin = fixture.join("origin");
+            let (http_url, http_server) = serve_http_once(std::fs::read(fixture.join("base.bundle"))?)?;
+            let (url, server) = serve_once(&origin, Some(list_with(format!("{http_url}/base.bundle"))))?;
+
+            let tmp = gix_testtools::tempfile::TempDir::new()?;
+            let (repo, num_fetched_objects) = clone(&url, tmp.path(), &["transfer.bundleURI=true"])?;
+            server.join().expect("no panic")?;
+            http_server.join().expect("no panic")?;
+
+            assert_eq!(
+                repo.find_reference("refs/bundles/main")?.id(),
+                rev_parse(&origin, "main~2")?
+            );
+            assert_eq!(
+                num_fetched_objects, 6,
+                "the two commits missing from the bundle are fetched"
+            );
+            Ok(())
+        }
+    }
+
+    /// Return a writable copy of the `base` remote which allows to filter objects.
+    fn remote_with_filter_support() -> crate::Result<(gix_testtools::tempfile::TempDir, std::path::PathBuf)> {
+        let fixture = gix_testtools::scripted_fixture_writable("make_remote_repos.sh")?;
+        let remote = fixture.path().join("base");
+        let mut config = std::fs::OpenOptions::new()
+            .append(true)
+            .open(remote.join(".git").join("config"))?;
+        std::io::Write::write_all(&mut config, b"[uploadpack]\n\tallowFilter = true\n")?;
+        Ok((fixture, remote))
+    }
+
+    #[test]
+    fn partial_clone_fetches_missing_blobs_on_demand() -> crate::Result {
+        let (_fixture, remote) = remote_with_filter_support()?;
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let (repo, out) = gix::clone::PrepareFetch::new(
+            remote.as_path(),
+            tmp.path(),
+            gix::create::Kind::Bare,
+            Default::default(),
+            restricted(),
+        )?
+        .with_filter("blob:none")
+        .fetch_only(gix::progress::Discard, &AtomicBool::default())?;
+
+        let gix::remote::fetch::Status::Change { write_pack_bundle, .. } = out.status else {
+            unreachable!("a clone always carries a change")
+        };
+        assert!(
+            gix::odb::promisor::is_promisor_pack(write_pack_bundle.index_path.as_deref().expect("pack was written")),
+            "packs received with a filter are marked as promisor packs"
+        );
+
+        let persisted = gix::open_opts(repo.git_dir(), restricted())?;
+        let config = persisted.config_snapshot();
+        assert_eq!(config.boolean("remote.origin.promisor"), Some(true));
+        assert_eq!(
+            config.string("remote.origin.partialCloneFilter").as_deref(),
+            Some("blob:none".into())
+        );
+        assert_eq!(
+            config.string("extensions.partialClone").as_deref(),
+            Some("origin".into())
+        );
+        assert_eq!(
+            config.integer("core.repositoryFormatVersion"),
+            Some(1),
+            "extensions are only understood by git with this version"
+        );
+
+        let blob_id = persisted.head_tree()?.find_entry("file").expect("present").object_id();
+        assert!(
+            !persisted.has_object(blob_id),
+            "blobs were filtered, and checking for existence doesn't fetch them"
+        );
+
+        let no_lazy_fetch = gix::open_opts(
+            repo.git_dir(),
+            restricted().config_overrides(["gitoxide.objects.noLazyFetch=true"]),
+        )?;
+        assert!(
+            no_lazy_fetch.try_find_object(blob_id)?.is_none(),
+            "fetching on demand can be turned off"
+        );
+
+        assert_eq!(
+            persisted.find_object(blob_id)?.detach().data,
+            gix::open_opts(&remote, gix::open::Options::isolated())?
+                .find_object(blob_id)?
+                .detach()
+                .data,
+            "missing objects are fetched from the promisor remote on demand"
+        );
+        assert!(persisted.has_object(blob_id), "the blob is now available locally");
+        assert!(
+            no_lazy_fetch.has_object(blob_id),
+            "it's written into the object database for all to see"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn partial_clone_and_checkout() -> crate::Result {
+        let (_fixture, remote) = remote_with_filter_support()?;
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let mut prepare = gix::clone::PrepareFetch::new(
+            remote.as_path(),
+            tmp.path(),
+            gix::create::Kind::WithWorktree,
+            Default::default(),
+            restricted(),
+        )?
+        .with_filter("blob:none");
+        let (mut checkout, _out) = prepare.fetch_then_checkout(gix::progress::Discard, &AtomicBool::default())?;
+        let (repo, _) = checkout.main_worktree(gix::progress::Discard, &AtomicBool::default())?;
+
+        let index = repo.index()?;
+        assert_eq!(index.entries().len(), 1);
+        assure_index_entries_on_disk(&index, repo.workdir().expect("non-bare"));
+        Ok(())
+    }
+
+    fn blobless_clone_with_renames() -> crate::Result<(gix_testtools::tempfile::TempDir, gix::Repository)> {
+        let remote = gix_testtools::scripted_fixture_read_only("make_partial_clone_remote.sh")?.join("remote");
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let (repo, _out) = gix::clone::PrepareFetch::new(
+            remote.as_path(),
+            tmp.path(),
+            gix::create::Kind::Bare,
+            Default::default(),
+            restricted(),
+        )?
+        .with_filter("blob:none")
+        .fetch_only(gix::progress::Discard, &AtomicBool::default())?;
+        Ok((tmp, repo))
+    }
+
+    fn num_packs(repo: &gix::Repository) -> crate::Result<usize> {
+        Ok(std::fs::read_dir(repo.objects.store_ref().path().join("pack"))?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "pack"))
+            .count())
+    }
+
+    #[test]
+    fn partial_clone_obtains_blobs_for_rename_tracking_at_once() -> crate::Result {
+        let (_tmp, repo) = blobless_clone_with_renames()?;
+        let num_packs_after_clone = num_packs(&repo)?;
+
+        let head = repo.head_commit()?;
+        let parent = repo.find_commit(head.parent_ids().next().expect("one parent"))?;
+        let changes = repo.diff_tree_to_tree(&parent.tree()?, &head.tree()?, None)?;
+        assert!(
+            changes.iter().any(|change| matches!(
+                change,
+                gix::object::tree::diff::ChangeDetached::Rewrite { location, .. } if location == "renamed-and-changed"
+            )),
+            "the renamed file is found by similarity, which needs the content of the blobs"
+        );
+        assert_eq!(
+            num_packs(&repo)?,
+            num_packs_after_clone + 1,
+            "all blobs that are candidates for renames are obtained from the promisor remote with a single fetch"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn partial_clone_obtains_blobs_for_status_rename_tracking_at_once() -> crate::Result {
+        let remote = gix_testtools::scripted_fixture_read_only("make_partial_clone_remote.sh")?.join("remote");
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let (mut checkout, _out) = gix::clone::PrepareFetch::new(
+            remote.as_path(),
+            tmp.path(),
+            gix::create::Kind::WithWorktree,
+            Default::default(),
+            restricted(),
+        )?
+        .with_filter("blob:none")
+        .fetch_then_checkout(gix::progress::Discard, &AtomicBool::default())?;
+        let (repo, _) = checkout.main_worktree(gix::progress::Discard, &AtomicBool::default())?;
+        let num_packs_after_checkout = num_packs(&repo)?;
+
+        let parent_tree_id = repo
+            .find_commit(repo.head_commit()?.parent_ids().next().expect("one parent"))?
+            .tree_id()?;
+        let index = repo.index()?;
+        let mut rewrites = Vec::new();
+        repo.tree_index_status(
+            &parent_tree_id,
+            &index,
+            None,
+            gix::status::tree_index::TrackRenames::AsConfigured,
+            |change, _, _| {
+                if let gix::diff::index::ChangeRef::Rewrite { location, .. } = change {
+                    rewrites.push(location.into_owned());
+                }
+                Ok::<_, std::convert::Infallible>(std::ops::ControlFlow::Continue(()))
+            },
+        )?;
+        assert_eq!(rewrites, ["renamed-and-changed"], "renames are found by similarity");
+        assert_eq!(
+            num_packs(&repo)?,
+            num_packs_after_checkout + 1,
+            "the blobs of the parent tree are obtained from the promisor remote with a single fetch"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn partial_clone_obtains_blobs_for_diff_stats_at_once() -> crate::Result {
+        let (_tmp, repo) = blobless_clone_with_renames()?;
+        let num_packs_after_clone = num_packs(&repo)?;
+
+        let head = repo.head_commit()?;
+        let parent = repo.find_commit(head.parent_ids().next().expect("one parent"))?;
+        let stats = parent
+            .tree()?
+            .changes()?
+            .options(|opts| {
+                opts.track_rewrites(None);
+            })
+            .stats(&head.tree()?)?;
+        assert_eq!(
+            stats.files_changed, 5,
+            "the rename isn't tracked, so it's an addition and a deletion"
+        );
+        assert_eq!(
+            num_packs(&repo)?,
+            num_packs_after_clone + 1,
+            "all changed blobs are obtained from the promisor remote with a single fetch"
+        );
+        Ok(())
+    }
+
     #[test]
     #[cfg(feature = "sha256")]
     fn fetch_only_adopts_remote_sha256_object_format() -> crate::Result {
@@ -122,6 +122,58 @@ mod branch {
     }
 }
 
+mod bundle {
+    use gix::config::tree::{Bundle, Key};
+    use gix::protocol::bundle_uri::{Heuristic, Mode};
+
+    use crate::config::tree::bcow;
+
+    #[test]
+    fn mode() -> crate::Result {
+        for (actual, expected) in [("all", Mode::All), ("any", Mode::Any)] {
+            assert_eq!(Bundle::MODE.try_into_mode(bcow(actual))?, expected);
+            assert!(Bundle::MODE.validate(actual.into()).is_ok());
+        }
+        assert_eq!(
+            Bundle::MODE.try_into_mode(bcow("All")).unwrap_err().to_string(),
+            "The key \"bundle.mode=All\" was invalid"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn heuristic() -> crate::Result {
+        assert_eq!(
+            Bundle::HEURISTIC.try_into_heuristic(bcow("creationToken"))?,
+            Heuristic::CreationToken
+        );
+        assert!(Bundle::HEURISTIC.validate("creationToken".into()).is_ok());
+        assert_eq!(
+            Bundle::HEURISTIC
+                .try_into_heuristic(bcow("creationtoken"))
+                .unwrap_err()
+                .to_string(),
+            "The key \"bundle.heuristic=creationtoken\" was invalid"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn uri_and_creation_token() {
+        assert!(Bundle::URI.full_name(None).is_err());
+        assert_eq!(
+            Bundle::URI.full_name(Some("base".into())).expect("valid"),
+            "bundle.base.uri"
+        );
+        assert_eq!(
+            Bundle::CREATION_TOKEN.full_name(Some("base".into())).expect("valid"),
+            "bundle.base.creationToken"
+        );
+        assert!(Bundle::CREATION_TOKEN.validate("42".into()).is_ok());
+        assert!(Bundle::CREATION_TOKEN.validate("-1".into()).is_err());
+    }
+}
+
 mod ssh {
 
     #[test]
@@ -41,5 +41,93 @@ mod blocking_io {
             }
             Ok(())
         }
+
+        #[test]
+        #[serial]
+        #[cfg(unix)]
+        fn remote_helpers_on_path_are_used_for_users() -> crate::Result {
+            use std::os::unix::fs::PermissionsExt;
+
+            let helper_dir = gix_testtools::tempfile::TempDir::new()?;
+            let helper = helper_dir.path().join("git-remote-mock");
+            std::fs::write(
+                &helper,
+                format!(
+                    r#"#!/bin/sh
+while read -r line; do
+  case "$line" in
+    capabilities) printf 'connect\n\n' ;;
+    "connect git-upload-pack") echo; exec git upload-pack '{}' ;;
+    *) exit 1 ;;
+  esac
+done
+"#,
+                    remote::repo_path("base").display()
+                ),
+            )?;
+            std::fs::set_permissions(&helper, std::fs::Permissions::from_mode(0o755))?;
+            let path = std::env::join_paths(
+                std::iter::once(helper_dir.path().to_owned())
+                    .chain(std::env::split_paths(&std::env::var_os("PATH").unwrap_or_default())),
+            )?;
+            let _path = gix_testtools::Env::new().set("PATH", path.into_string().expect("valid UTF-8"));
+
+            for (env_value, should_allow) in [(None, true), (Some("0"), false), (Some("1"), true)] {
+                let _env = env_value.map(|value| gix_testtools::Env::new().set("GIT_PROTOCOL_FROM_USER", value));
+                let repo = gix::open_opts(
+                    remote::repo_path("clone"),
+                    gix::open::Options::isolated().permissions(gix::open::Permissions {
+                        env: gix::open::permissions::Environment {
+                            git_prefix: gix_sec::Permission::Allow,
+                            ..gix::open::permissions::Environment::all()
+                        },
+                        ..gix::open::Permissions::isolated()
+                    }),
+                )?;
+                let remote = repo
+                    .remote_at("mock://example.com/repo.git")?
+                    .with_refspecs(Some("refs/heads/*:refs/remotes/origin/*"), Fetch)?;
+                match remote.connect(Fetch) {
+                    Ok(connection) => {
+                        assert!(should_allow, "Value = {env_value:?}");
+                        let (map, _handshake) = connection.ref_map(gix::progress::Discard, Default::default())?;
+                        assert!(
+                            map.mappings
+                                .iter()
+                                .any(|mapping| mapping.remote.as_name().is_some_and(|name| name == "refs/heads/main")),
+                            "the refs are obtained through the helper"
+                        );
+                    }
+                    Err(err) => {
+                        assert!(!should_allow, "Value = {env_value:?}: {err}");
+                        assert!(
+                            matches!(
+                                err,
+                                gix::remote::connect::Error::ProtocolDenied {
+                                    scheme: gix::url::Scheme::Ext(_),
+                                    ..
+                                }
+                            ),
+                            "{err:?}"
+                        );
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn ext_is_denied_by_default() -> crate::Result {
+            let repo = remote::repo("clone");
+            let remote = repo.remote_at("ext://example.com/repo.git")?;
+            assert!(matches!(
+                remote.connect(Fetch).err(),
+                Some(gix::remote::connect::Error::ProtocolDenied {
+                    url: _,
+                    scheme: gix::url::Scheme::Ext(_)
+                })
+            ));
+            Ok(())
+        }
     }
 }
@@ -914,3 +914,143 @@ mod blocking_and_async_io {
         Ok(())
     }
 }
+
+#[cfg(feature = "blocking-network-client")]
+mod fetch_head {
+    use std::sync::atomic::AtomicBool;
+
+    use gix::{
+        config::tree::Fetch,
+        remote::{
+            Direction,
+            fetch::{FetchHeadMerge, Tags, WriteFetchHead},
+        },
+    };
+    use gix_testtools::tempfile::TempDir;
+
+    /// Return a writable copy of the fixture with `clone-git` and `clone-gix` being clones of `remote`, which received
+    /// more commits and tags since, and the `clone-gix` repository.
+    fn clones() -> crate::Result<(gix::Repository, TempDir)> {
+        let tmp = gix_testtools::scripted_fixture_writable("make_fetch_head_repos.sh")?;
+        let remote = gix::path::realpath(tmp.path().join("remote"))?;
+        for clone in ["clone-git", "clone-gix"] {
+            gix_testtools::git(
+                tmp.path().join(clone),
+                &format!("remote set-url origin {}", remote.display()),
+            )?;
+        }
+        let repo = gix::open_opts(tmp.path().join("clone-gix"), crate::restricted())?;
+        Ok((repo, tmp))
+    }
+
+    fn fetch_head(tmp: &TempDir, clone: &str) -> crate::Result<String> {
+        Ok(std::fs::read_to_string(tmp.path().join(clone).join(".git/FETCH_HEAD"))?)
+    }
+
+    fn fetch(
+        remote: gix::Remote<'_>,
+        dry_run: bool,
+        write_fetch_head: Option<WriteFetchHead>,
+        merge: FetchHeadMerge,
+    ) -> crate::Result {
+        let mut prepare = remote
+            .connect(Direction::Fetch)?
+            .prepare_fetch(gix::progress::Discard, Default::default())?
+            .with_dry_run(dry_run)
+            .with_fetch_head_merge(merge);
+        if let Some(mode) = write_fetch_head {
+            prepare = prepare.with_write_fetch_head(mode);
+        }
+        prepare.receive(gix::progress::Discard, &AtomicBool::default())?;
+        Ok(())
+    }
+
+    #[test]
+    fn configured_refspecs_mark_the_upstream_branch_and_list_new_tags() -> crate::Result {
+        let (repo, tmp) = clones()?;
+        gix_testtools::git(tmp.path().join("clone-git"), "fetch -q")?;
+        fetch(repo.find_remote("origin")?, false, None, FetchHeadMerge::Configured)?;
+
+        let actual = fetch_head(&tmp, "clone-gix")?;
+        assert_eq!(actual, fetch_head(&tmp, "clone-git")?);
+        let fetch_head = gix::fetchhead::FetchHead::at(&repo.git_dir().join("FETCH_HEAD"), repo.object_hash())?;
+        assert_eq!(
+            fetch_head
+                .entries
+                .iter()
+                .map(|entry| (entry.name.to_string(), entry.for_merge))
+                .collect::<Vec<_>>(),
+            [
+                ("refs/heads/main".into(), true),
+                ("refs/heads/feature".into(), false),
+                ("refs/tags/lightweight".into(), false),
+                ("refs/tags/v2".into(), false)
+            ],
+            "only the new tags are listed"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn all_tags() -> crate::Result {
+        let (repo, tmp) = clones()?;
+        gix_testtools::git(tmp.path().join("clone-git"), "fetch -q --tags")?;
+        fetch(
+            repo.find_remote("origin")?.with_fetch_tags(Tags::All),
+            false,
+            None,
+            FetchHeadMerge::Configured,
+        )?;
+        assert_eq!(fetch_head(&tmp, "clone-gix")?, fetch_head(&tmp, "clone-git")?);
+        Ok(())
+    }
+
+    #[test]
+    fn refspecs_like_on_the_command_line_are_all_marked_for_merge() -> crate::Result {
+        let (repo, tmp) = clones()?;
+        gix_testtools::git(tmp.path().join("clone-git"), "fetch -q origin main feature")?;
+        let mut remote = repo.find_remote("origin")?.with_fetch_tags(Tags::None);
+        remote.replace_refspecs(["main", "feature"], Direction::Fetch)?;
+        fetch(remote, false, None, FetchHeadMerge::AllMatches)?;
+        assert_eq!(fetch_head(&tmp, "clone-gix")?, fetch_head(&tmp, "clone-git")?);
+        Ok(())
+    }
+
+    #[test]
+    fn append() -> crate::Result {
+        let (repo, tmp) = clones()?;
+        gix_testtools::git(tmp.path().join("clone-git"), "fetch -q")?;
+        gix_testtools::git(tmp.path().join("clone-git"), "fetch -q --append")?;
+        fetch(repo.find_remote("origin")?, false, None, FetchHeadMerge::Configured)?;
+        fetch(
+            repo.find_remote("origin")?,
+            false,
+            Some(WriteFetchHead::Append),
+            FetchHeadMerge::Configured,
+        )?;
+        assert_eq!(fetch_head(&tmp, "clone-gix")?, fetch_head(&tmp, "clone-git")?);
+        Ok(())
+    }
+
+    #[test]
+    fn disabled_by_configuration_or_dry_run() -> crate::Result {
+        let (mut repo, _tmp) = clones()?;
+        let path = repo.git_dir().join("FETCH_HEAD");
+        fetch(repo.find_remote("origin")?, true, None, FetchHeadMerge::Configured)?;
+        assert!(!path.exists(), "dry-runs don't write FETCH_HEAD");
+
+        repo.config_snapshot_mut()
+            .set_value(&Fetch::WRITE_FETCH_HEAD, "false")?;
+        fetch(repo.find_remote("origin")?, false, None, FetchHeadMerge::Configured)?;
+        assert!(!path.exists(), "fetch.writeFetchHead is respected");
+
+        fetch(
+            repo.find_remote("origin")?,
+            false,
+            Some(WriteFetchHead::Overwrite),
+            FetchHeadMerge::Configured,
+        )?;
+        assert!(path.exists(), "the configuration can be overridden");
+        Ok(())
+    }
+}
@@ -0,0 +1,351 @@
+#[cfg(feature = "blocking-network-client")]
+mod blocking_io {
+    use std::sync::atomic::AtomicBool;
+
+    use gix::remote::{
+        Direction::Push,
+        push::{Lease, Options, Outcome, Status},
+    };
+    use gix_features::progress;
+    use gix_testtools::tempfile::TempDir;
+
+    use crate::util::restricted;
+
+    fn repos() -> crate::Result<(gix::Repository, TempDir)> {
+        let dir = gix_testtools::scripted_fixture_writable("make_push_repos.sh")?;
+        let remote_url = dir.path().join("remote.git");
+        let repo = gix::open_opts(
+            dir.path().join("clone"),
+            restricted().config_overrides([
+                "user.name=gitoxide".to_string(),
+                "user.email=gitoxide@localhost".to_string(),
+                format!("remote.origin.url={}", remote_url.display()),
+            ]),
+        )?;
+        Ok((repo, dir))
+    }
+
+    fn remote_repo(dir: &TempDir) -> crate::Result<gix::Repository> {
+        Ok(gix::open_opts(dir.path().join("remote.git"), restricted())?)
+    }
+
+    fn options(refspecs: &[&str]) -> Options {
+        Options {
+            refspecs: refspecs
+                .iter()
+                .map(|spec| {
+                    gix::refspec::parse((*spec).into(), gix::refspec::parse::Operation::Push)
+                        .expect("valid refspec")
+                        .to_owned()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn push(repo: &gix::Repository, options: Options) -> crate::Result<Outcome> {
+        let remote = repo.find_remote("origin")?;
+        Ok(remote
+            .connect(Push)?
+            .prepare_push(progress::Discard, options)?
+            .send(progress::Discard, &AtomicBool::default())?)
+    }
+
+    fn statuses(outcome: &Outcome) -> Vec<(String, Status)> {
+        outcome
+            .updates
+            .iter()
+            .map(|update| (update.remote.as_bstr().to_string(), update.status.clone()))
+            .collect()
+    }
+
+    fn id(repo: &gix::Repository, name: &str) -> crate::Result<Option<gix::ObjectId>> {
+        Ok(repo.try_find_reference(name)?.map(|r| r.id().detach()))
+    }
+
+    /// Assert that all objects reachable from `tip` are present in `repo`.
+    fn assert_connected(repo: &gix::Repository, tip: gix::ObjectId) -> crate::Result {
+        let tip = repo.find_object(tip)?.peel_to_commit()?.id;
+        for info in repo.rev_walk(Some(tip)).all()? {
+            let commit = info?.object()?;
+            let mut recorder = gix::traverse::tree::Recorder::default();
+            commit.tree()?.traverse().breadthfirst(&mut recorder)?;
+            for entry in recorder.records {
+                assert!(repo.has_object(entry.oid), "{} is missing", entry.filepath);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn push_default_updates_current_branch_and_its_tracking_ref() -> crate::Result {
+        let (repo, dir) = repos()?;
+        let outcome = push(&repo, Options::default())?;
+        assert_eq!(statuses(&outcome), [("refs/heads/main".into(), Status::Ok)]);
+        assert!(outcome.is_ok());
+        let report = outcome.report.expect("the remote supports reporting");
+        assert!(report.is_ok());
+
+        let main = id(&repo, "refs/heads/main")?.expect("present");
+        let remote = remote_repo(&dir)?;
+        assert_eq!(id(&remote, "refs/heads/main")?, Some(main));
+        assert_connected(&remote, main)?;
+
+        assert_eq!(outcome.tracking_ref_edits.len(), 1);
+        assert_eq!(outcome.tracking_ref_edits[0].name.as_bstr(), "refs/remotes/origin/main");
+        assert_eq!(id(&repo, "refs/remotes/origin/main")?, Some(main));
+        let tracking_ref = repo.find_reference("refs/remotes/origin/main")?;
+        let mut log = tracking_ref.log_iter();
+        let entry = log.all()?.expect("reflog").last().expect("entry")?;
+        assert_eq!(entry.message, "update by push");
+
+        let outcome = push(&repo, Options::default())?;
+        assert_eq!(
+            statuses(&outcome),
+            [("refs/heads/main".into(), Status::UpToDate)],
+            "nothing is sent the second time"
+        );
+        assert!(outcome.report.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn creation_of_branches_and_tags() -> crate::Result {
+        let (repo, dir) = repos()?;
+        let outcome = push(&repo, options(&["main:new", "refs/tags/*:refs/tags/*"]))?;
+        assert_eq!(
+            statuses(&outcome),
+            [
+                ("refs/heads/new".into(), Status::Ok),
+                ("refs/tags/v1".into(), Status::UpToDate),
+                ("refs/tags/v2".into(), Status::Ok),
+            ],
+            "the destination is qualified using the source, and patterns are expanded"
+        );
+
+        let remote = remote_repo(&dir)?;
+        let v2 = id(&repo, "refs/tags/v2")?.expect("present");
+        assert_eq!(id(&remote, "refs/heads/new")?, id(&repo, "refs/heads/main")?);
+        assert_eq!(id(&remote, "refs/tags/v2")?, Some(v2));
+        assert_eq!(
+            remote.find_object(v2)?.kind,
+            gix::object::Kind::Tag,
+            "tag objects are sent"
+        );
+        assert_connected(&remote, v2)?;
+        assert_eq!(
+            id(&repo, "refs/remotes/origin/new")?,
+            id(&repo, "refs/heads/main")?,
+            "tracking refs are created as well"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn non_fast_forwards_are_rejected_unless_forced() -> crate::Result {
+        let (repo, dir) = repos()?;
+        let remote_feature = id(&remote_repo(&dir)?, "refs/heads/feature")?;
+
+        let outcome = push(&repo, options(&["feature"]))?;
+        assert_eq!(
+            statuses(&outcome),
+            [("refs/heads/feature".into(), Status::RejectedNonFastForward)]
+        );
+        assert!(!outcome.is_ok());
+        assert!(outcome.report.is_none(), "nothing was sent");
+        assert_eq!(id(&remote_repo(&dir)?, "refs/heads/feature")?, remote_feature);
+
+        let outcome = push(&repo, options(&["+feature"]))?;
+        assert_eq!(statuses(&outcome), [("refs/heads/feature".into(), Status::Ok)]);
+        assert_eq!(
+            id(&remote_repo(&dir)?, "refs/heads/feature")?,
+            id(&repo, "refs/heads/feature")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tags_are_not_overwritten_unless_forced() -> crate::Result {
+        let (repo, _dir) = repos()?;
+        let outcome = push(&repo, options(&["v2:refs/tags/v1"]))?;
+        assert_eq!(
+            statuses(&outcome),
+            [("refs/tags/v1".into(), Status::RejectedAlreadyExists)]
+        );
+
+        let outcome = push(
+            &repo,
+            Options {
+                force: true,
+                ..options(&["v2:refs/tags/v1"])
+            },
+        )?;
+        assert_eq!(statuses(&outcome), [("refs/tags/v1".into(), Status::Ok)]);
+        Ok(())
+    }
+
+    #[test]
+    fn deletion() -> crate::Result {
+        let (repo, dir) = repos()?;
+        assert!(id(&repo, "refs/remotes/origin/old")?.is_some());
+
+        let outcome = push(&repo, options(&[":old", ":refs/heads/does-not-exist"]))?;
+        assert_eq!(
+            statuses(&outcome),
+            [
+                ("refs/heads/old".into(), Status::Ok),
+                ("refs/heads/does-not-exist".into(), Status::RejectedNoSuchRef),
+            ]
+        );
+        assert_eq!(id(&remote_repo(&dir)?, "refs/heads/old")?, None);
+        assert_eq!(
+            id(&repo, "refs/remotes/origin/old")?,
+            None,
+            "the tracking ref is deleted"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn force_with_lease() -> crate::Result {
+        let (repo, dir) = repos()?;
+        let first = id(&repo, "refs/heads/old")?.expect("present");
+        let outcome = push(
+            &repo,
+            Options {
+                force_with_lease: vec![Lease {
+                    name: "feature".into(),
+                    expected: Some(first),
+                }],
+                ..options(&["feature"])
+            },
+        )?;
+        assert_eq!(
+            statuses(&outcome),
+            [("refs/heads/feature".into(), Status::RejectedStale)]
+        );
+
+        let outcome = push(
+            &repo,
+            Options {
+                force_with_lease: vec![Lease {
+                    name: "refs/heads/feature".into(),
+                    expected: None,
+                }],
+                ..options(&["feature"])
+            },
+        )?;
+        assert_eq!(
+            statuses(&outcome),
+            [("refs/heads/feature".into(), Status::Ok)],
+            "the remote-tracking reference has the current value of the remote"
+        );
+        assert_eq!(
+            id(&remote_repo(&dir)?, "refs/heads/feature")?,
+            id(&repo, "refs/heads/feature")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn fast_forwards_are_detected_even_if_commit_times_go_backwards() -> crate::Result {
+        let (repo, dir) = repos()?;
+        let outcome = push(&repo, options(&["skewed"]))?;
+        assert_eq!(
+            statuses(&outcome),
+            [("refs/heads/skewed".into(), Status::Ok)],
+            "the new commits are older than the one on the remote, but descend from it nonetheless"
+        );
+        assert_eq!(
+            id(&remote_repo(&dir)?, "refs/heads/skewed")?,
+            id(&repo, "refs/heads/skewed")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn atomic_pushes_send_nothing_if_one_update_is_rejected() -> crate::Result {
+        let (repo, dir) = repos()?;
+        let outcome = push(
+            &repo,
+            Options {
+                atomic: true,
+                ..options(&["main", "feature"])
+            },
+        )?;
+        assert_eq!(
+            statuses(&outcome),
+            [
+                ("refs/heads/main".into(), Status::AtomicPushFailed),
+                ("refs/heads/feature".into(), Status::RejectedNonFastForward),
+            ]
+        );
+        assert_eq!(
+            id(&remote_repo(&dir)?, "refs/heads/main")?,
+            id(&repo, "refs/heads/old")?,
+            "main wasn't updated"
+        );
+
+        let outcome = push(
+            &repo,
+            Options {
+                atomic: true,
+                ..options(&["main", "+feature"])
+            },
+        )?;
+        assert!(outcome.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn push_options_are_passed_to_hooks_which_may_reject_updates() -> crate::Result {
+        let (repo, dir) = repos()?;
+        let outcome = push(
+            &repo,
+            Options {
+                push_options: vec!["reject".into()],
+                ..options(&["main"])
+            },
+        )?;
+        assert_eq!(
+            statuses(&outcome),
+            [(
+                "refs/heads/main".into(),
+                Status::RemoteRejected {
+                    reason: "pre-receive hook declined".into()
+                }
+            )]
+        );
+        assert!(outcome.tracking_ref_edits.is_empty());
+        assert_eq!(
+            id(&remote_repo(&dir)?, "refs/heads/main")?,
+            id(&repo, "refs/heads/old")?
+        );
+
+        let outcome = push(
+            &repo,
+            Options {
+                push_options: vec!["accept".into()],
+                ..options(&["main"])
+            },
+        )?;
+        assert_eq!(statuses(&outcome), [("refs/heads/main".into(), Status::Ok)]);
+        Ok(())
+    }
+
+    #[test]
+    fn unresolvable_sources_are_an_error() -> crate::Result {
+        let (repo, _dir) = repos()?;
+        let remote = repo.find_remote("origin")?;
+        let err = remote
+            .connect(Push)?
+            .prepare_push(progress::Discard, options(&["does-not-exist"]))
+            .err()
+            .expect("the source can't be resolved");
+        assert!(matches!(
+            err,
+            gix::remote::push::prepare::Error::UnresolvedSource { .. }
+        ));
+        Ok(())
+    }
+}
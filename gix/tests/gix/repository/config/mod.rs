@@ -20,7 +20,7 @@ fn big_file_threshold() -> crate::Result {
 mod ssh_options {
     use std::ffi::OsStr;
 
-    use crate::repository::config::repo;
+    use crate::repository::config::{repo, repo_opts};
 
     #[test]
     fn with_command_and_variant() -> crate::Result {
@@ -50,6 +50,21 @@ mod ssh_options {
         );
         Ok(())
     }
+
+    #[test]
+    fn native_client_is_opt_in() -> crate::Result {
+        let repo = repo("ssh-all-options");
+        assert!(
+            !repo.ssh_connect_options()?.native,
+            "the ssh program is used by default"
+        );
+
+        let repo = repo_opts("ssh-all-options", |opts| {
+            opts.strict_config(true).config_overrides(["gitoxide.ssh.native=true"])
+        });
+        assert!(repo.ssh_connect_options()?.native);
+        Ok(())
+    }
 }
 
 #[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
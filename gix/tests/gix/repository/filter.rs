@@ -122,3 +122,37 @@ fn pipeline_with_autocrlf() -> crate::Result {
     }
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "lfs")]
+fn pipeline_handles_lfs_natively() -> crate::Result {
+    use std::io::Read;
+
+    use gix::filter::plumbing::lfs::Pointer;
+
+    let tmp = gix_testtools::scripted_fixture_writable("make_lfs_repo.sh")?;
+    let repo = gix::open_opts(tmp.path(), crate::util::restricted())?;
+    let (mut pipe, index) = repo.filter_pipeline(None)?;
+
+    let mut pointer = Vec::new();
+    pipe.convert_to_git(&b"hello world"[..], Path::new("file.bin"), &index)?
+        .read_to_end(&mut pointer)?;
+    let expected = Pointer::from_bytes(&pointer)?;
+    assert_eq!(
+        expected.oid.to_string(),
+        "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9",
+        "the same pointer `git-lfs` would produce"
+    );
+    assert_eq!(
+        std::fs::read(
+            repo.common_dir()
+                .join("lfs/objects/b9/4d/b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
+        )?,
+        b"hello world",
+        "the content is in the LFS store of the repository"
+    );
+
+    let out = pipe.convert_to_worktree(&pointer, "file.bin".into(), Delay::Forbid)?;
+    assert_eq!(out.as_bytes().expect("in memory").as_bstr(), "hello world");
+    Ok(())
+}
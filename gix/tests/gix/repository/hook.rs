@@ -0,0 +1,188 @@
+use gix::{
+    config::tree::Core,
+    hook::{Invocation, MessageSource, PushUpdate, RefUpdate, TransactionState},
+};
+use gix_sec::Trust;
+
+use crate::util::restricted;
+
+fn writable() -> crate::Result<(gix::Repository, gix::Repository, gix_testtools::tempfile::TempDir)> {
+    let tmp = gix_testtools::scripted_fixture_writable("make_hooks_repo.sh")?;
+    let worktree = gix::open_opts(tmp.path().join("worktree"), restricted())?;
+    let bare = gix::open_opts(tmp.path().join("bare.git"), restricted())?;
+    Ok((worktree, bare, tmp))
+}
+
+/// Return the lines of what the recording hook called `name` received.
+fn output(repo: &gix::Repository, name: &str) -> crate::Result<Vec<String>> {
+    Ok(std::fs::read_to_string(repo.git_dir().join(format!("{name}.out")))?
+        .lines()
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
+fn id(byte: u8) -> gix_hash::ObjectId {
+    gix_hash::ObjectId::from_bytes_or_panic(&vec![byte; gix_testtools::object_hash().len_in_bytes()])
+}
+
+#[test]
+fn hooks_dir_defaults_to_git_dir_and_respects_core_hooks_path() -> crate::Result {
+    let (mut repo, _bare, _tmp) = writable()?;
+    let workdir = repo.workdir().expect("non-bare").to_owned();
+    assert_eq!(repo.hooks_dir()?, repo.git_dir().join("hooks"));
+    assert_eq!(
+        repo.find_hook("pre-commit")?,
+        Some(repo.git_dir().join("hooks/pre-commit"))
+    );
+    assert_eq!(repo.find_hook("pre-push")?, None);
+
+    repo.config_snapshot_mut()
+        .set_value(&Core::HOOKS_PATH, "custom-hooks")?;
+    assert_eq!(
+        repo.hooks_dir()?,
+        workdir.join("custom-hooks"),
+        "relative paths are relative to the worktree"
+    );
+    assert_eq!(
+        repo.find_hook("pre-commit")?,
+        None,
+        "the default directory isn't used anymore"
+    );
+    assert_eq!(repo.run_pre_commit_hook()?, None, "missing hooks aren't run");
+    let status = repo
+        .run_pre_push_hook("origin".into(), "url".into(), &[])?
+        .expect("hook exists");
+    assert!(status.success());
+    assert_eq!(output(&repo, "pre-push")?[0], format!("cwd: {}", workdir.display()));
+    Ok(())
+}
+
+#[test]
+fn untrusted_repositories_do_not_run_hooks() -> crate::Result {
+    let tmp = gix_testtools::scripted_fixture_read_only("make_hooks_repo.sh")?;
+    let repo = gix::open_opts(tmp.join("worktree"), restricted().with(Trust::Reduced))?;
+    assert_eq!(repo.git_dir_trust(), Trust::Reduced);
+    assert!(!repo.hooks_enabled());
+    assert_eq!(
+        repo.find_hook("pre-commit")?,
+        None,
+        "hooks are ignored as if they didn't exist"
+    );
+    assert_eq!(repo.run_pre_commit_hook()?, None);
+    assert!(!repo.git_dir().join("pre-commit.out").exists());
+    Ok(())
+}
+
+#[test]
+fn commit_hooks_run_in_worktree_with_index() -> crate::Result {
+    let (repo, _bare, _tmp) = writable()?;
+    assert!(repo.hooks_enabled());
+    let status = repo.run_pre_commit_hook()?.expect("hook exists");
+    assert!(status.success());
+    let out = output(&repo, "pre-commit")?;
+    assert!(out[0].ends_with("worktree"), "hooks run in the root of the worktree");
+    assert_eq!(out[1], format!("index: {}", repo.index_path().display()));
+    assert_eq!(out.len(), 2, "no arguments and no input");
+
+    let message_file = repo.git_dir().join("COMMIT_EDITMSG");
+    std::fs::write(&message_file, "subject\n")?;
+    let status = repo
+        .run_prepare_commit_msg_hook(&message_file, Some(MessageSource::Message))?
+        .expect("hook exists");
+    assert!(status.success());
+    assert_eq!(
+        std::fs::read_to_string(&message_file)?,
+        "subject\nprepared with message\n",
+        "hooks can edit the message"
+    );
+
+    let status = repo.run_commit_msg_hook(&message_file)?.expect("hook exists");
+    assert!(
+        !status.success(),
+        "the message isn't signed off, so the commit is rejected"
+    );
+    std::fs::write(&message_file, "subject\n\nSigned-off-by: me\n")?;
+    let status = repo.run_commit_msg_hook(&message_file)?.expect("hook exists");
+    assert!(status.success());
+    Ok(())
+}
+
+#[test]
+fn post_checkout() -> crate::Result {
+    let (repo, _bare, _tmp) = writable()?;
+    let status = repo.run_post_checkout_hook(&id(1), &id(2), true)?.expect("hook exists");
+    assert!(status.success());
+    let out = output(&repo, "post-checkout")?;
+    assert_eq!(
+        &out[1..],
+        [
+            "index: ".to_string(),
+            format!("arg: {}", id(1)),
+            format!("arg: {}", id(2)),
+            "arg: 1".into()
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn bare_repositories_run_hooks_in_git_dir_and_feed_input() -> crate::Result {
+    let (_repo, bare, _tmp) = writable()?;
+    let null = gix_hash::ObjectId::null(gix_testtools::object_hash());
+    let status = bare
+        .run_pre_push_hook(
+            "origin".into(),
+            "https://example.com/repo".into(),
+            &[PushUpdate {
+                local_ref: Some("refs/heads/main".into()),
+                local_id: id(1),
+                remote_ref: "refs/heads/main".into(),
+                remote_id: null,
+            }],
+        )?
+        .expect("hook exists");
+    assert!(status.success());
+    let out = output(&bare, "pre-push")?;
+    assert!(
+        out[0].ends_with("bare.git"),
+        "bare repositories run hooks in the git dir"
+    );
+    assert_eq!(
+        &out[1..],
+        [
+            "index: ".to_string(),
+            "arg: origin".into(),
+            "arg: https://example.com/repo".into(),
+            format!("stdin: refs/heads/main {} refs/heads/main {null}", id(1)),
+        ]
+    );
+
+    let status = bare
+        .run_reference_transaction_hook(
+            TransactionState::Prepared,
+            &[RefUpdate {
+                previous_id: null,
+                new_id: id(2),
+                name: "refs/heads/new".into(),
+            }],
+        )?
+        .expect("hook exists");
+    assert!(status.success());
+    assert_eq!(
+        output(&bare, "reference-transaction")?[2..],
+        [
+            "arg: prepared".to_string(),
+            format!("stdin: {null} {} refs/heads/new", id(2))
+        ]
+    );
+
+    let status = bare
+        .run_hook(Invocation::reference_transaction(TransactionState::Prepared, &[]).env("HOOK_EXIT", "1"))?
+        .expect("hook exists");
+    assert_eq!(
+        status.code(),
+        Some(1),
+        "failures are reported so the transaction can be aborted"
+    );
+    Ok(())
+}
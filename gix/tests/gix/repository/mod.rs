@@ -11,17 +11,27 @@ mod config;
 mod excludes;
 #[cfg(feature = "attributes")]
 mod filter;
+#[cfg(feature = "hook")]
+mod hook;
 #[cfg(feature = "mailmap")]
 mod mailmap;
 #[cfg(feature = "merge")]
 mod merge;
+#[cfg(feature = "note")]
+mod note;
 mod object;
 mod open;
 #[cfg(feature = "attributes")]
 mod pathspec;
 mod reference;
 mod remote;
+#[cfg(feature = "rerere")]
+mod rerere;
+#[cfg(feature = "sequencer")]
+mod sequence;
 mod shallow;
+#[cfg(feature = "stash")]
+mod stash;
 mod state;
 #[cfg(feature = "attributes")]
 mod submodule;
@@ -0,0 +1,159 @@
+use gix::{
+    bstr::ByteSlice,
+    config::tree::Core,
+    note::{Strategy, merge::Outcome},
+};
+use gix_ref::FullName;
+
+use crate::util::restricted;
+
+fn writable() -> crate::Result<(gix::Repository, gix_testtools::tempfile::TempDir)> {
+    let tmp = gix_testtools::scripted_fixture_writable("make_notes_repo.sh")?;
+    let repo = gix::open_opts(tmp.path(), restricted())?;
+    Ok((repo, tmp))
+}
+
+fn name(name: &str) -> FullName {
+    name.try_into().expect("valid")
+}
+
+fn rev(repo: &gix::Repository, spec: &str) -> crate::Result<gix_hash::ObjectId> {
+    Ok(repo.rev_parse_single(spec)?.detach())
+}
+
+fn note(repo: &gix::Repository, notes_ref: &FullName, object: &gix_hash::oid) -> crate::Result<Option<String>> {
+    Ok(repo
+        .find_note(notes_ref.as_ref(), object)?
+        .map(|note| note.data.to_str_lossy().into_owned()))
+}
+
+#[test]
+fn notes_ref_defaults_to_notes_commits_and_respects_core_notes_ref() -> crate::Result {
+    let (mut repo, _tmp) = writable()?;
+    assert_eq!(repo.notes_ref()?.as_bstr(), "refs/notes/commits");
+
+    repo.config_snapshot_mut()
+        .set_value(&Core::NOTES_REF, "refs/notes/ci")?;
+    assert_eq!(repo.notes_ref()?.as_bstr(), "refs/notes/ci");
+    Ok(())
+}
+
+#[test]
+fn find_note() -> crate::Result {
+    let (repo, _tmp) = writable()?;
+    let notes_ref = repo.notes_ref()?;
+    assert_eq!(
+        note(&repo, &notes_ref, &rev(&repo, "HEAD~1")?)?.as_deref(),
+        Some("note for first\n")
+    );
+    assert_eq!(note(&repo, &notes_ref, &rev(&repo, "HEAD")?)?, None);
+    assert_eq!(
+        note(&repo, &name("refs/notes/missing"), &rev(&repo, "HEAD~1")?)?,
+        None,
+        "a missing notes reference has no notes"
+    );
+
+    let notes = repo.notes(notes_ref.as_ref())?;
+    assert_eq!(notes.len(), 1);
+    assert!(repo.notes(name("refs/notes/missing").as_ref())?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn add_append_copy_and_remove_like_git() -> crate::Result {
+    let (repo, _tmp) = writable()?;
+    let (first, second) = (rev(&repo, "HEAD~1")?, rev(&repo, "HEAD")?);
+    let notes_ref = name("refs/notes/new");
+
+    let commit = repo.add_note(notes_ref.as_ref(), first, "one".into(), false)?;
+    assert_eq!(repo.find_commit(commit)?.parent_ids().count(), 0);
+    let err = repo
+        .add_note(notes_ref.as_ref(), first, "again".into(), false)
+        .unwrap_err();
+    assert!(matches!(err, gix::note::edit::Error::NoteExists { .. }));
+
+    let commit = repo.append_note(notes_ref.as_ref(), first, "two".into())?;
+    assert_eq!(note(&repo, &notes_ref, &first)?.as_deref(), Some("one\n\ntwo\n"));
+    let commit_message = repo.find_commit(commit)?.message_raw()?.to_owned();
+    assert_eq!(commit_message, "Notes added by 'git notes append'\n");
+
+    repo.copy_note(notes_ref.as_ref(), &first, second, false)?;
+    assert_eq!(note(&repo, &notes_ref, &second)?.as_deref(), Some("one\n\ntwo\n"));
+    let err = repo.copy_note(notes_ref.as_ref(), &first, second, false).unwrap_err();
+    assert!(matches!(err, gix::note::edit::Error::NoteExists { .. }));
+
+    let commit = repo.remove_note(notes_ref.as_ref(), &first)?;
+    assert_eq!(note(&repo, &notes_ref, &first)?, None);
+    let err = repo.remove_note(notes_ref.as_ref(), &first).unwrap_err();
+    assert!(matches!(err, gix::note::edit::Error::NoteMissing { .. }));
+
+    assert_eq!(
+        repo.find_commit(commit)?.tree_id()?,
+        rev(&repo, "refs/notes/edited^{tree}")?,
+        "the notes tree is the same as the one `git` created"
+    );
+    assert_eq!(
+        repo.find_commit(commit)?.parent_ids().count(),
+        1,
+        "each change is a new commit on top of the previous one"
+    );
+
+    let mut reference = repo.find_reference(notes_ref.as_ref())?;
+    let mut log = reference.log_iter();
+    let mut entries = log.rev()?.expect("reflog exists");
+    assert_eq!(
+        entries.next().expect("at least one entry")?.message,
+        "notes: Notes removed by 'git notes remove'"
+    );
+    assert_eq!(reference.peel_to_id()?, commit);
+
+    repo.add_note(notes_ref.as_ref(), second, "".into(), true)?;
+    assert!(
+        repo.notes(notes_ref.as_ref())?.is_empty(),
+        "adding an empty note removes it"
+    );
+    Ok(())
+}
+
+#[test]
+fn merge_notes_like_git() -> crate::Result {
+    let (repo, _tmp) = writable()?;
+    let (ours, theirs) = (name("refs/notes/ours"), name("refs/notes/theirs"));
+    let (our_commit, their_commit) = (rev(&repo, "refs/notes/ours")?, rev(&repo, "refs/notes/theirs")?);
+
+    let Outcome::Merged { commit } = repo.merge_notes(ours.as_ref(), theirs.as_ref(), Strategy::Union)? else {
+        panic!("diverged notes need a merge commit")
+    };
+    let merge = repo.find_commit(commit)?;
+    assert_eq!(merge.tree_id()?, rev(&repo, "refs/notes/merged^{tree}")?);
+    assert_eq!(
+        merge.parent_ids().map(gix::Id::detach).collect::<Vec<_>>(),
+        [our_commit, their_commit]
+    );
+    assert_eq!(
+        merge.message_raw()?,
+        "Merged notes from refs/notes/theirs into refs/notes/ours\n"
+    );
+    assert_eq!(rev(&repo, "refs/notes/ours")?, commit);
+
+    assert_eq!(
+        repo.merge_notes(ours.as_ref(), theirs.as_ref(), Strategy::Union)?,
+        Outcome::UpToDate
+    );
+    assert_eq!(
+        repo.merge_notes(theirs.as_ref(), ours.as_ref(), Strategy::Union)?,
+        Outcome::FastForward { commit }
+    );
+    assert_eq!(rev(&repo, "refs/notes/theirs")?, commit);
+    assert_eq!(
+        repo.merge_notes(name("refs/notes/new").as_ref(), ours.as_ref(), Strategy::Ours)?,
+        Outcome::FastForward { commit },
+        "missing notes references are created"
+    );
+
+    let err = repo
+        .merge_notes(ours.as_ref(), name("refs/notes/missing").as_ref(), Strategy::Ours)
+        .unwrap_err();
+    assert!(matches!(err, gix::note::merge::Error::MissingReference { .. }));
+    Ok(())
+}
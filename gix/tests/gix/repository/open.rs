@@ -99,16 +99,73 @@ fn non_bare_reftable() -> crate::Result {
         }
         Err(err) => panic!("{err}"),
     };
-    assert!(
-        repo.head_id().is_err(),
-        "Trying to do anything with head will fail as we don't support reftables yet"
+    assert_eq!(repo.refs.backend(), gix::refs::store::Backend::Reftable);
+    let head = repo.head()?;
+    assert_eq!(
+        head.referent_name().expect("symbolic").as_bstr(),
+        "refs/heads/main",
+        "HEAD is read from the reftable, not from the stub file"
+    );
+    let head_id = repo.head_id()?;
+    assert_eq!(head_id.object()?.into_commit().message()?.summary().as_ref(), "c2");
+    assert_eq!(
+        repo.head_ref()?
+            .expect("born")
+            .log_iter()
+            .all()?
+            .expect("present")
+            .count(),
+        1,
+        "reflogs are read from the reftable as well"
+    );
+    let names: Vec<_> = repo
+        .references()?
+        .all()?
+        .map(|r| r.map(|r| r.name().as_bstr().to_owned()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        names,
+        [
+            "refs/heads/main",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main"
+        ]
     );
     assert!(!repo.is_bare());
     assert_eq!(repo.kind(), gix::repository::Kind::Common);
-    assert_ne!(
-        repo.workdir(),
-        None,
-        "Otherwise it can be used, but it's hard to do without refs"
+    assert_ne!(repo.workdir(), None);
+    Ok(())
+}
+
+#[test]
+fn non_bare_reftable_references_can_be_written() -> crate::Result {
+    let tmp = match gix_testtools::scripted_fixture_writable("make_reftable_repo.sh") {
+        Ok(tmp) => tmp,
+        Err(_) if *gix_testtools::GIT_VERSION < (2, 44, 0) => {
+            eprintln!("Fixture script failure ignored as it looks like Git isn't recent enough.");
+            return Ok(());
+        }
+        Err(err) => panic!("{err}"),
+    };
+    let repo = gix::open_opts(tmp.path().join("reftable-clone"), crate::util::restricted())?;
+    let head_id = repo.head_id()?;
+    repo.reference(
+        "refs/heads/new",
+        head_id,
+        gix::refs::transaction::PreviousValue::MustNotExist,
+        "create new",
+    )?;
+
+    let mut new = repo.find_reference("new")?;
+    assert_eq!(new.peel_to_id()?, head_id);
+    assert_eq!(
+        new.log_iter().all()?.expect("present").count(),
+        1,
+        "the reflog is written to the reftable along with the reference"
+    );
+    assert!(
+        !repo.git_dir().join("refs/heads/new").exists(),
+        "no loose reference is written"
     );
     Ok(())
 }
@@ -0,0 +1,167 @@
+use gix::{config::tree::Rerere, merge::tree::TreatAsUnresolved, rerere::plumbing::cache::Image};
+
+use crate::util::repo_rw;
+
+const RESOLVED: &str = "1\n2\nthree-resolved\n4\nfive-ours\n6\n7\neight-resolved\n9\n10\n";
+
+fn read(repo: &gix::Repository, path: &str) -> std::io::Result<String> {
+    std::fs::read_to_string(repo.workdir().expect("non-bare").join(path))
+}
+
+fn id(repo: &gix::Repository, name: &str) -> crate::Result<gix_hash::ObjectId> {
+    Ok(repo.find_reference(name)?.peel_to_id()?.detach())
+}
+
+fn stages(repo: &gix::Repository, path: &str) -> crate::Result<Vec<u32>> {
+    let index = repo.open_index()?;
+    Ok(index
+        .entries()
+        .iter()
+        .filter(|e| e.path(&index) == path)
+        .map(gix::index::Entry::stage_raw)
+        .collect())
+}
+
+#[test]
+fn enabled_by_configuration_or_presence_of_rr_cache() -> crate::Result {
+    let (mut repo, _tmp) = repo_rw("make_rerere_repo.sh")?;
+    assert!(repo.rerere_enabled()?);
+
+    repo.config_snapshot_mut().set_value(&Rerere::ENABLED, "false")?;
+    assert!(!repo.rerere_enabled()?);
+    assert_eq!(repo.rerere()?, None, "nothing happens if disabled");
+
+    git(&repo, &["config", "--unset", "rerere.enabled"])?;
+    let repo = gix::open(repo.workdir().expect("non-bare"))?;
+    assert!(repo.rerere_enabled()?, "the rr-cache directory exists");
+    std::fs::remove_dir_all(repo.rerere_cache().dir())?;
+    assert!(!repo.rerere_enabled()?);
+    Ok(())
+}
+
+#[test]
+fn merges_replay_recorded_resolutions() -> crate::Result {
+    let (mut repo, _tmp) = repo_rw("make_rerere_repo.sh")?;
+    for auto_update in [false, true] {
+        repo.config_snapshot_mut()
+            .set_value(&Rerere::AUTO_UPDATE, auto_update.to_string().as_str())?;
+        let mut outcome = repo
+            .merge_commits(
+                id(&repo, "main")?,
+                id(&repo, "theirs")?,
+                Default::default(),
+                repo.tree_merge_options()?.into(),
+            )?
+            .tree_merge;
+        assert_eq!(outcome.conflicts.len(), 1);
+        let merged_blob = outcome.conflicts[0].content_merge().expect("content merge");
+        assert_eq!(
+            repo.find_blob(merged_blob.merged_blob_id)?.data,
+            RESOLVED.as_bytes(),
+            "the resolution recorded by `git` was applied"
+        );
+        assert_eq!(
+            outcome.has_unresolved_conflicts(TreatAsUnresolved::git()),
+            !auto_update,
+            "it's only considered resolved with `rerere.autoUpdate`"
+        );
+
+        let tree = repo.find_tree(outcome.tree.write()?)?;
+        assert_eq!(
+            tree.find_entry("file").expect("present").object_id(),
+            merged_blob.merged_blob_id,
+            "the tree has the resolution"
+        );
+    }
+
+    let outcome = repo.merge_commits(
+        id(&repo, "main")?,
+        id(&repo, "unknown")?,
+        Default::default(),
+        repo.tree_merge_options()?.into(),
+    )?;
+    assert!(
+        outcome.tree_merge.has_unresolved_conflicts(TreatAsUnresolved::git()),
+        "unknown conflicts aren't affected"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "sequencer")]
+fn cherry_picks_record_conflicts_and_their_resolution() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_rerere_repo.sh")?;
+    let cache = repo.rerere_cache();
+    let before: Vec<_> = std::fs::read_dir(cache.dir())?.collect::<Result<_, _>>()?;
+    let outcome = repo.cherry_pick(["unknown"], Default::default())?;
+    assert!(matches!(outcome, gix::sequence::Outcome::Stopped { .. }));
+
+    let merge_rr = gix::rerere::plumbing::MergeRr::at(&repo.git_dir().join("MERGE_RR"), repo.object_hash())?;
+    assert_eq!(merge_rr.entries.len(), 1, "the conflicted path is tracked");
+    let entry = &merge_rr.entries[0];
+    assert_eq!(entry.path, "file");
+    assert!(cache.contains(&entry.id, 0, Image::Preimage));
+    assert_eq!(std::fs::read_dir(cache.dir())?.count(), before.len() + 1);
+
+    let resolution = "1\n2\nthree-ours\n4\nfive-resolved\n6\n7\neight-ours\n9\n10\n";
+    std::fs::write(repo.workdir().expect("non-bare").join("file"), resolution)?;
+    git(&repo, &["add", "file"])?;
+    repo.sequence_continue()?;
+    assert_eq!(
+        std::fs::read(cache.path(&entry.id, 0, Image::Postimage))?,
+        resolution.as_bytes(),
+        "the resolution is recorded when continuing"
+    );
+    assert!(!repo.git_dir().join("MERGE_RR").exists(), "nothing is tracked anymore");
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "sequencer")]
+fn cherry_picks_replay_recorded_resolutions() -> crate::Result {
+    for auto_update in [false, true] {
+        let (mut repo, _tmp) = repo_rw("make_rerere_repo.sh")?;
+        repo.config_snapshot_mut()
+            .set_value(&Rerere::AUTO_UPDATE, auto_update.to_string().as_str())?;
+        let outcome = repo.cherry_pick(["theirs"], Default::default())?;
+        assert!(matches!(outcome, gix::sequence::Outcome::Stopped { .. }));
+        assert_eq!(read(&repo, "file")?, RESOLVED, "the worktree file was resolved");
+        assert_eq!(
+            stages(&repo, "file")?,
+            if auto_update { vec![0] } else { vec![1, 2, 3] },
+            "the resolution is only staged with `rerere.autoUpdate`"
+        );
+        assert!(!repo.git_dir().join("MERGE_RR").exists());
+
+        if !auto_update {
+            git(&repo, &["add", "file"])?;
+        }
+        let gix::sequence::Outcome::Finished { head } = repo.sequence_continue()? else {
+            panic!("the sequence finishes")
+        };
+        assert_eq!(
+            head.object()?
+                .into_commit()
+                .tree()?
+                .find_entry("file")
+                .expect("present")
+                .object()?
+                .data,
+            RESOLVED.as_bytes()
+        );
+    }
+    Ok(())
+}
+
+fn git(repo: &gix::Repository, args: &[&str]) -> crate::Result<String> {
+    let out = std::process::Command::new(gix::path::env::exe_invocation())
+        .args(args)
+        .current_dir(repo.workdir().expect("non-bare"))
+        .output()?;
+    assert!(
+        out.status.success(),
+        "{args:?} failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    Ok(String::from_utf8(out.stdout)?)
+}
@@ -0,0 +1,258 @@
+use gix::sequence::{Action, Error, Options, Outcome};
+use gix_hash::ObjectId;
+
+use crate::util::repo_rw;
+
+fn id(repo: &gix::Repository, name: &str) -> crate::Result<ObjectId> {
+    Ok(repo.find_reference(name)?.peel_to_id()?.detach())
+}
+
+/// Return the commits in `tip` that aren't in `hidden`, oldest first.
+fn range(repo: &gix::Repository, tip: &str, hidden: &str) -> crate::Result<Vec<ObjectId>> {
+    let mut ids = repo
+        .rev_walk([id(repo, tip)?])
+        .with_hidden([id(repo, hidden)?])
+        .all()?
+        .map(|info| info.map(|info| info.id))
+        .collect::<Result<Vec<_>, _>>()?;
+    ids.reverse();
+    Ok(ids)
+}
+
+fn summaries(repo: &gix::Repository, count: usize) -> crate::Result<Vec<String>> {
+    let head = repo.head_id()?;
+    repo.rev_walk([head])
+        .first_parent_only()
+        .all()?
+        .take(count)
+        .map(|info| Ok(info?.object()?.message()?.summary().to_string()))
+        .collect()
+}
+
+fn read(repo: &gix::Repository, path: &str) -> std::io::Result<String> {
+    std::fs::read_to_string(repo.workdir().expect("non-bare").join(path))
+}
+
+#[test]
+fn cherry_pick_stops_on_conflicts_and_continues_once_resolved() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_sequence_repo.sh")?;
+    let commits = range(&repo, "topic", "base")?;
+    let Outcome::Stopped { id, action, tree_merge } = repo.cherry_pick(
+        ["base..topic"],
+        Options {
+            record_origin: true,
+            ..Default::default()
+        },
+    )?
+    else {
+        panic!("b conflicts")
+    };
+    assert_eq!(id, commits[1]);
+    assert_eq!(action, Action::Pick);
+    assert_eq!(tree_merge.conflicts.len(), 1);
+    assert_eq!(summaries(&repo, 2)?, ["d: 1", "c: 1"], "the first commit was picked");
+    assert_eq!(read(&repo, "d")?, "1\n", "the worktree is updated");
+    assert!(read(&repo, "b")?.contains("<<<<<<<"), "conflicts are written");
+    let index = repo.open_index()?;
+    assert_eq!(
+        index
+            .entries()
+            .iter()
+            .filter(|e| e.path(&index) == "b")
+            .map(gix::index::Entry::stage_raw)
+            .collect::<Vec<_>>(),
+        [1, 2, 3],
+        "conflicting stages are in the index"
+    );
+    assert_eq!(
+        repo.find_reference("CHERRY_PICK_HEAD")?.id(),
+        id,
+        "it's stopped just like git would be"
+    );
+    assert!(matches!(
+        repo.cherry_pick(["base..topic"], Default::default()),
+        Err(Error::Dirty)
+    ));
+    assert!(
+        matches!(repo.sequence_continue(), Err(Error::Sequence(_))),
+        "conflicts must be resolved first"
+    );
+
+    std::fs::write(repo.workdir().expect("non-bare").join("b"), "resolved\n")?;
+    assert!(gix_testtools::run_git(repo.workdir().expect("non-bare"), &["add", "b"])?.success());
+    let Outcome::Finished { head } = repo.sequence_continue()? else {
+        panic!("e applies cleanly")
+    };
+    assert_eq!(head, repo.head_id()?);
+    assert_eq!(summaries(&repo, 4)?, ["e: 1", "b: conflict", "d: 1", "c: 1"]);
+    assert_eq!(read(&repo, "b")?, "resolved\n");
+    assert_eq!(read(&repo, "e")?, "1\n");
+    assert!(!repo.is_dirty()?, "the index and worktree match HEAD");
+    assert!(
+        head.object()?
+            .into_commit()
+            .message_raw()?
+            .ends_with(format!("(cherry picked from commit {})\n", commits[2]).as_bytes()),
+        "the origin is recorded"
+    );
+    assert!(repo.try_find_reference("CHERRY_PICK_HEAD")?.is_none());
+    assert!(matches!(repo.sequence_continue(), Err(Error::NotInProgress)));
+
+    let messages: Vec<_> = repo
+        .find_reference("refs/heads/main")?
+        .log_iter()
+        .all()?
+        .expect("reflog present")
+        .map(|line| line.map(|line| line.message.to_string()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        messages[messages.len() - 3..],
+        [
+            "cherry-pick: d: 1",
+            "commit (cherry-pick): b: conflict",
+            "cherry-pick: e: 1"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn abort_restores_head_index_and_worktree() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_sequence_repo.sh")?;
+    let main = repo.head_id()?.detach();
+    let outcome = repo.cherry_pick(["base..topic"], Default::default())?;
+    assert!(matches!(outcome, Outcome::Stopped { .. }));
+    drop(outcome);
+
+    repo.sequence_abort()?;
+    assert_eq!(repo.head_id()?, main);
+    assert!(!repo.is_dirty()?);
+    assert_eq!(read(&repo, "b")?, "2\n");
+    assert!(
+        !repo.workdir().expect("non-bare").join("d").exists(),
+        "files added by the sequence are removed"
+    );
+    assert!(matches!(repo.sequence_abort(), Err(Error::NotInProgress)));
+    Ok(())
+}
+
+#[test]
+fn skip_drops_the_conflicting_commit() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_sequence_repo.sh")?;
+    let outcome = repo.cherry_pick(["base..topic"], Default::default())?;
+    assert!(matches!(outcome, Outcome::Stopped { .. }));
+    drop(outcome);
+
+    let Outcome::Finished { .. } = repo.sequence_skip()? else {
+        panic!("e applies cleanly")
+    };
+    assert_eq!(summaries(&repo, 3)?, ["e: 1", "d: 1", "c: 1"]);
+    assert_eq!(read(&repo, "b")?, "2\n");
+    assert!(!repo.is_dirty()?);
+    Ok(())
+}
+
+#[test]
+fn revert_merge_with_mainline() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_sequence_repo.sh")?;
+    assert!(matches!(
+        repo.revert(["merged"], Default::default()),
+        Err(Error::Sequence(
+            gix::sequence::plumbing::sequence::Error::MergeWithoutMainline { .. }
+        ))
+    ));
+    repo.sequence_quit()?;
+
+    repo.cherry_pick(
+        ["merged"],
+        Options {
+            mainline: Some(1),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(read(&repo, "s")?, "1\n");
+
+    let Outcome::Finished { head } = repo.revert(
+        ["HEAD"],
+        Options {
+            no_commit: true,
+            ..Default::default()
+        },
+    )?
+    else {
+        panic!("reverting the last commit can't conflict")
+    };
+    assert_eq!(summaries(&repo, 1)?, ["merge side"], "nothing was committed");
+    assert_eq!(head, repo.head_id()?);
+    assert!(
+        !repo.workdir().expect("non-bare").join("s").exists(),
+        "the revert is in the worktree"
+    );
+    assert!(repo.is_dirty()?, "…and in the index");
+    Ok(())
+}
+
+#[test]
+fn ranges_are_applied_oldest_first_without_merges() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_sequence_repo.sh")?;
+    let Outcome::Finished { .. } = repo.cherry_pick(["base..merged", "topic~2"], Default::default())? else {
+        panic!("none of these commits conflict")
+    };
+    assert_eq!(
+        summaries(&repo, 5)?,
+        ["d: 1", "s: 1", "m: 1", "c: 1", "b: 2"],
+        "the merge commit is skipped and single commits are walked along with the range in topological order"
+    );
+    assert_eq!(read(&repo, "s")?, "1\n");
+
+    let Outcome::Finished { .. } = repo.revert(["HEAD~2..HEAD"], Default::default())? else {
+        panic!("reverting the last commits can't conflict")
+    };
+    assert_eq!(
+        summaries(&repo, 2)?,
+        ["Revert \"s: 1\"", "Revert \"d: 1\""],
+        "reverts of ranges are applied newest first"
+    );
+
+    assert!(matches!(
+        repo.cherry_pick(["base...topic"], Default::default()),
+        Err(Error::UnsupportedSpec { .. })
+    ));
+    assert!(matches!(
+        repo.cherry_pick(["does-not-exist"], Default::default()),
+        Err(Error::RevParse(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn ranges_with_merges_and_mainline() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_sequence_repo.sh")?;
+    let options = Options {
+        mainline: Some(1),
+        ..Default::default()
+    };
+    assert!(
+        matches!(
+            repo.cherry_pick(["topic"], options),
+            Err(Error::MainlineWithoutMerge { id }) if id == repo.rev_parse_single("topic")?.detach()
+        ),
+        "a commit named on its own must be a merge if a mainline is set"
+    );
+
+    let Outcome::Finished { .. } = repo.cherry_pick(["side..merged"], options)? else {
+        panic!("none of these commits conflict")
+    };
+    assert_eq!(
+        summaries(&repo, 3)?,
+        ["merge side", "m: 1", "c: 1"],
+        "the mainline is ignored for the commit that isn't a merge, and parents are applied before their children"
+    );
+    assert_eq!(read(&repo, "m")?, "1\n");
+    assert_eq!(
+        read(&repo, "s")?,
+        "1\n",
+        "the merge brought in the changes of its second parent"
+    );
+    Ok(())
+}
@@ -0,0 +1,282 @@
+use gix::{
+    bstr::ByteSlice,
+    stash::{ApplyOptions, Error, Options, Outcome},
+};
+
+use crate::util::repo_rw;
+
+fn read(repo: &gix::Repository, path: &str) -> std::io::Result<String> {
+    std::fs::read_to_string(repo.workdir().expect("non-bare").join(path))
+}
+
+fn write(repo: &gix::Repository, path: &str, content: &str) -> std::io::Result<()> {
+    std::fs::write(repo.workdir().expect("non-bare").join(path), content)
+}
+
+fn exists(repo: &gix::Repository, path: &str) -> bool {
+    repo.workdir().expect("non-bare").join(path).exists()
+}
+
+fn git(repo: &gix::Repository, args: &[&str]) -> crate::Result<String> {
+    let out = std::process::Command::new(gix::path::env::exe_invocation())
+        .args(args)
+        .current_dir(repo.workdir().expect("non-bare"))
+        .output()?;
+    assert!(out.status.success(), "{args:?} failed: {}", String::from_utf8_lossy(&out.stderr));
+    Ok(String::from_utf8(out.stdout)?)
+}
+
+fn messages(repo: &gix::Repository) -> crate::Result<Vec<String>> {
+    Ok(repo
+        .stash_list()?
+        .into_iter()
+        .map(|entry| entry.message.to_string())
+        .collect())
+}
+
+fn staged(repo: &gix::Repository, path: &str) -> crate::Result<Option<String>> {
+    let index = repo.open_index()?;
+    let Some(entry) = index.entry_by_path(path.into()) else {
+        return Ok(None);
+    };
+    Ok(Some(repo.find_blob(entry.id)?.data.to_str_lossy().into_owned()))
+}
+
+#[test]
+fn list_and_apply_entries_created_by_git() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    assert_eq!(messages(&repo)?, ["On main: from git"]);
+    assert!(!repo.is_dirty()?);
+    assert!(!exists(&repo, "u"), "untracked files were stashed as well");
+
+    let changes = repo.stash_show(0)?;
+    assert_eq!(changes.len(), 2, "a and b are changed in the worktree");
+    assert!(matches!(repo.stash_show(1), Err(Error::NoSuchEntry { index: 1 })));
+
+    let Outcome::Applied = repo.stash_apply(0, ApplyOptions::default())? else {
+        panic!("there is nothing to conflict with")
+    };
+    assert_eq!(read(&repo, "a")?, "2\n");
+    assert_eq!(read(&repo, "b")?, "3\n");
+    assert_eq!(read(&repo, "u")?, "untracked\n");
+    assert_eq!(staged(&repo, "b")?.as_deref(), Some("1\n"), "the index isn't restored");
+    assert_eq!(staged(&repo, "u")?, None, "untracked files stay untracked");
+    assert_eq!(messages(&repo)?.len(), 1, "applying keeps the entry");
+    Ok(())
+}
+
+#[test]
+fn apply_with_restore_index() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    let Outcome::Applied = repo.stash_apply(0, ApplyOptions { restore_index: true })? else {
+        panic!("there is nothing to conflict with")
+    };
+    assert_eq!(read(&repo, "b")?, "3\n");
+    assert_eq!(staged(&repo, "b")?.as_deref(), Some("2\n"), "the staged change is restored");
+    assert_eq!(
+        git(&repo, &["status", "--porcelain"])?,
+        " M a\nMM b\n?? u\n",
+        "git sees the same state as before stashing"
+    );
+    Ok(())
+}
+
+#[test]
+fn push_and_pop_round_trip() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    assert!(
+        repo.stash_push(Default::default())?.is_none(),
+        "there is nothing to stash in a clean worktree"
+    );
+
+    write(&repo, "a", "changed\n")?;
+    write(&repo, "c", "added\n")?;
+    write(&repo, "untracked", "untracked\n")?;
+    std::fs::remove_file(repo.workdir().expect("non-bare").join("b"))?;
+    git(&repo, &["add", "c"])?;
+
+    let id = repo
+        .stash_push(Options {
+            include_untracked: true,
+            ..Default::default()
+        })?
+        .expect("there are changes");
+    assert!(!repo.is_dirty()?, "index and worktree are reset to HEAD");
+    assert_eq!(read(&repo, "a")?, "1\n");
+    assert_eq!(read(&repo, "b")?, "1\n");
+    assert!(!exists(&repo, "c"), "files added to the index are stashed");
+    assert!(!exists(&repo, "untracked"), "untracked files are removed");
+
+    let list = repo.stash_list()?;
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0].id, id);
+    assert!(list[0].message.starts_with(b"WIP on main: "));
+    assert_eq!(
+        git(&repo, &["stash", "list", "--format=%gs"])?,
+        format!("{}\nOn main: from git\n", list[0].message),
+        "git sees the same entries"
+    );
+    assert_eq!(
+        git(&repo, &["stash", "show", "--name-status", "--include-untracked", "stash@{0}"])?,
+        "M\ta\nD\tb\nA\tc\nA\tuntracked\n",
+        "git understands the stash commits"
+    );
+
+    let Outcome::Applied = repo.stash_pop(0, ApplyOptions::default())? else {
+        panic!("HEAD didn't change, so there is nothing to conflict with")
+    };
+    assert_eq!(read(&repo, "a")?, "changed\n");
+    assert!(!exists(&repo, "b"));
+    assert_eq!(read(&repo, "c")?, "added\n");
+    assert_eq!(read(&repo, "untracked")?, "untracked\n");
+    assert_eq!(staged(&repo, "c")?.as_deref(), Some("added\n"), "new files are staged");
+    assert_eq!(
+        git(&repo, &["status", "--porcelain"])?,
+        " M a\n D b\nA  c\n?? untracked\n"
+    );
+    assert_eq!(messages(&repo)?, ["On main: from git"], "popping drops the entry");
+    Ok(())
+}
+
+#[test]
+fn push_with_message() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    write(&repo, "a", "changed\n")?;
+    repo.stash_push(Options {
+        message: Some("the message".into()),
+        ..Default::default()
+    })?
+    .expect("a is changed");
+    assert_eq!(messages(&repo)?, ["On main: the message", "On main: from git"]);
+    assert_eq!(
+        git(&repo, &["stash", "show", "--name-only"])?,
+        "a\n",
+        "untracked files aren't included by default"
+    );
+    Ok(())
+}
+
+fn signatures(repo: &gix::Repository) -> crate::Result<Vec<gix::actor::Signature>> {
+    let stash = repo.find_reference("refs/stash")?;
+    let mut log = stash.log_iter();
+    log.all()?
+        .expect("present")
+        .map(|line| Ok(line?.signature.to_owned()?))
+        .collect()
+}
+
+#[test]
+fn drop_rewrites_the_reflog() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    let from_git = repo.stash_list()?[0].id;
+    let signature_from_git = signatures(&repo)?.remove(0);
+    write(&repo, "a", "one\n")?;
+    let one = repo.stash_push(Default::default())?.expect("changed");
+    write(&repo, "a", "two\n")?;
+    let two = repo.stash_push(Default::default())?.expect("changed");
+
+    assert_eq!(repo.stash_drop(1)?, one);
+    let ids: Vec<_> = repo.stash_list()?.into_iter().map(|entry| entry.id).collect();
+    assert_eq!(ids, [two, from_git]);
+    assert_eq!(repo.find_reference("refs/stash")?.id(), two);
+    git(&repo, &["stash", "show", "stash@{1}"])?;
+    assert_eq!(
+        signatures(&repo)?[0],
+        signature_from_git,
+        "remaining entries keep their original committer and time"
+    );
+
+    assert_eq!(repo.stash_drop(0)?, two);
+    assert_eq!(repo.find_reference("refs/stash")?.id(), from_git, "the ref follows");
+    assert_eq!(git(&repo, &["stash", "list", "--format=%gs"])?, "On main: from git\n");
+
+    assert!(matches!(repo.stash_drop(1), Err(Error::NoSuchEntry { index: 1 })));
+    assert_eq!(repo.stash_drop(0)?, from_git);
+    assert!(repo.stash_list()?.is_empty());
+    assert!(
+        repo.try_find_reference("refs/stash")?.is_none(),
+        "the reference is removed with the last entry"
+    );
+    Ok(())
+}
+
+#[test]
+fn apply_refuses_to_overwrite_local_changes() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    write(&repo, "a", "local\n")?;
+    assert!(matches!(
+        repo.stash_apply(0, ApplyOptions::default()),
+        Err(Error::WouldOverwrite { paths }) if paths == ["a"]
+    ));
+    write(&repo, "a", "1\n")?;
+
+    write(&repo, "u", "in the way\n")?;
+    assert!(matches!(
+        repo.stash_apply(0, ApplyOptions::default()),
+        Err(Error::UntrackedExists { paths }) if paths == ["u"]
+    ));
+    assert_eq!(read(&repo, "a")?, "1\n", "nothing was changed");
+    Ok(())
+}
+
+#[test]
+fn pop_with_conflicts_keeps_the_entry() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    write(&repo, "a", "upstream\n")?;
+    git(&repo, &["commit", "-q", "-am", "a: upstream"])?;
+
+    let Outcome::Conflicts { tree_merge } = repo.stash_pop(0, ApplyOptions::default())? else {
+        panic!("a was changed on both sides")
+    };
+    assert_eq!(tree_merge.conflicts.len(), 1);
+    let a = read(&repo, "a")?;
+    assert!(
+        a.contains("<<<<<<< Updated upstream") && a.contains(">>>>>>> Stashed changes"),
+        "conflicts are written like git does: {a}"
+    );
+    assert_eq!(read(&repo, "b")?, "3\n", "non-conflicting changes are applied");
+    let index = repo.open_index()?;
+    assert_eq!(
+        index
+            .entries()
+            .iter()
+            .filter(|e| e.path(&index) == "a")
+            .map(gix::index::Entry::stage_raw)
+            .collect::<Vec<_>>(),
+        [1, 2, 3],
+        "conflicting stages are in the index"
+    );
+    assert_eq!(messages(&repo)?.len(), 1, "the entry is kept");
+    assert!(matches!(repo.stash_push(Default::default()), Err(Error::UnmergedIndex)));
+    Ok(())
+}
+
+#[test]
+fn drop_with_reftables() -> crate::Result {
+    let tmp = match gix_testtools::scripted_fixture_writable("make_reftable_repo.sh") {
+        Ok(tmp) => tmp,
+        Err(_) if *gix_testtools::GIT_VERSION < (2, 44, 0) => {
+            eprintln!("Fixture script failure ignored as it looks like Git isn't recent enough.");
+            return Ok(());
+        }
+        Err(err) => panic!("{err}"),
+    };
+    let repo = gix::open_opts(tmp.path().join("reftable-clone"), crate::util::restricted())?;
+    write(&repo, "this", "one\n")?;
+    let one = repo.stash_push(Default::default())?.expect("changed");
+    write(&repo, "this", "two\n")?;
+    let two = repo.stash_push(Default::default())?.expect("changed");
+
+    assert_eq!(repo.stash_drop(1)?, one);
+    let ids: Vec<_> = repo.stash_list()?.into_iter().map(|entry| entry.id).collect();
+    assert_eq!(ids, [two], "the reflog in the reftable was rewritten");
+    assert_eq!(repo.find_reference("refs/stash")?.id(), two);
+
+    assert_eq!(repo.stash_drop(0)?, two);
+    assert!(repo.stash_list()?.is_empty());
+    assert!(
+        repo.try_find_reference("refs/stash")?.is_none(),
+        "the reference is removed with the last entry"
+    );
+    Ok(())
+}
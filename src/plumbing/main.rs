@@ -668,6 +668,7 @@ pub fn main() -> Result<()> {
             handshake_info,
             negotiation_info,
             open_negotiation_graph,
+            append,
             remote,
             shallow,
             ref_spec,
@@ -679,6 +680,7 @@ pub fn main() -> Result<()> {
                 handshake_info,
                 negotiation_info,
                 open_negotiation_graph,
+                append,
                 shallow: shallow.into(),
                 ref_specs: ref_spec,
             };
@@ -646,6 +646,10 @@ pub mod fetch {
         #[clap(long, value_name = "PATH", short = 'g')]
         pub open_negotiation_graph: Option<std::path::PathBuf>,
 
+        /// Append what was fetched to FETCH_HEAD instead of overwriting it.
+        #[clap(long, short = 'a')]
+        pub append: bool,
+
         #[clap(flatten)]
         pub shallow: ShallowOptions,
 
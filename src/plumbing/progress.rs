@@ -436,14 +436,6 @@ static GIT_CONFIG: &[Record] = &[
         config: "sparse.expectFilesOutsideOfPatterns",
         usage: NotPlanned("TODO"),
     },
-    Record {
-        config: "remote.<name>.promisor",
-        usage: Planned("Required for big monorepos, and typically used in conjunction with sparse indices"),
-    },
-    Record {
-        config: "remote.<name>.partialCloneFilter",
-        usage: Planned("Required for big monorepos, and typically used in conjunction with sparse indices"),
-    },
     Record {
         config: "merge.directoryRenames",
         usage: NotPlanned("On demand"),